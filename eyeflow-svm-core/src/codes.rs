@@ -0,0 +1,146 @@
+/*! eyeflow-svm-core::codes — error codes of the MCU profile
+ *
+ * The numeric values are reported by the firmware (SvmResult, defmt logs)
+ * and must stay stable:
+ *
+ *   0x01..0x0F  IR validation (header / layout)
+ *   0x10..0x2F  runtime faults
+ *   0x30..0x3F  edge-link framing
+ *   0xFF        unknown service / action id (dispatch tables)
+ */
+
+use core::fmt;
+
+/// Returned by a dispatch table for an id it does not know.
+pub const UNKNOWN_DISPATCH_ID: u8 = 0xFF;
+
+// ── IR validation ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ValidationError {
+    /// Shorter than the header
+    TooShort            = 0x01,
+    /// Not 0xEF 0xF1
+    BadMagic            = 0x02,
+    /// Unsupported IR version
+    BadVersion          = 0x03,
+    /// Fewer bytes than the header's instruction count requires
+    Truncated           = 0x04,
+    /// More than ir::MAX_INSTRUCTIONS instructions
+    TooManyInstructions = 0x05,
+}
+
+impl ValidationError {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::TooShort            => "IR shorter than its header",
+            Self::BadMagic            => "IR magic mismatch",
+            Self::BadVersion          => "unsupported IR version",
+            Self::Truncated           => "IR truncated",
+            Self::TooManyInstructions => "too many instructions",
+        }
+    }
+}
+
+// ── Runtime faults ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RuntimeError {
+    /// BRANCH target past the last instruction
+    BranchOutOfRange = 0x10,
+    /// Opcode outside ir::Opcode
+    UnknownOpcode    = 0x20,
+}
+
+impl RuntimeError {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::BranchOutOfRange => "branch target out of range",
+            Self::UnknownOpcode    => "unknown opcode",
+        }
+    }
+}
+
+// ── Framing ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FrameError {
+    /// Frame larger than the receive buffer
+    Overflow   = 0x30,
+    /// CRC-16 mismatch — the frame is dropped
+    BadCrc     = 0x31,
+    /// Payload longer than the u16 length field
+    TooLong    = 0x32,
+    /// Unknown or malformed link message (link.rs)
+    BadMessage = 0x33,
+}
+
+impl FrameError {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Overflow   => "frame exceeds the receive buffer",
+            Self::BadCrc     => "frame CRC mismatch",
+            Self::TooLong    => "payload exceeds 65535 bytes",
+            Self::BadMessage => "malformed link message",
+        }
+    }
+}
+
+/// An encoder's output buffer cannot hold the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferTooSmall {
+    pub needed: usize,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (0x{:02x})", self.as_str(), *self as u8)
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (0x{:02x})", self.as_str(), *self as u8)
+    }
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (0x{:02x})", self.as_str(), *self as u8)
+    }
+}
+
+impl fmt::Display for BufferTooSmall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "output buffer too small ({} bytes needed)", self.needed)
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_stable() {
+        // Reported by deployed firmware: never renumber
+        assert_eq!(
+            [ValidationError::TooShort, ValidationError::BadMagic, ValidationError::BadVersion,
+             ValidationError::Truncated, ValidationError::TooManyInstructions].map(|e| e as u8),
+            [0x01, 0x02, 0x03, 0x04, 0x05],
+        );
+        assert_eq!([RuntimeError::BranchOutOfRange, RuntimeError::UnknownOpcode].map(|e| e as u8), [0x10, 0x20]);
+        assert_eq!(
+            [FrameError::Overflow, FrameError::BadCrc, FrameError::TooLong, FrameError::BadMessage].map(|e| e as u8),
+            [0x30, 0x31, 0x32, 0x33],
+        );
+        assert_eq!(UNKNOWN_DISPATCH_ID, 0xFF);
+        assert_eq!(RuntimeError::UnknownOpcode.to_string(), "unknown opcode (0x20)");
+        assert_eq!(ValidationError::Truncated.to_string(), "IR truncated (0x04)");
+        assert_eq!(BufferTooSmall { needed: 7 }.to_string(), "output buffer too small (7 bytes needed)");
+    }
+}
@@ -0,0 +1,197 @@
+/*! eyeflow-svm-core::frame — edge-link framing (USART / serial)
+ *
+ * ┌──────────┬──────────────┬───────────────┬────────────────┐
+ * │ 0xAA 0x55│ len: u16 BE  │ payload (len) │ CRC-16 BE      │
+ * └──────────┴──────────────┴───────────────┴────────────────┘
+ *
+ * The CRC is CRC-16/CCITT-FALSE (poly 0x1021, init 0xFFFF) over the length
+ * field and the payload.  Bytes before a 0xAA 0x55 sync are line noise and
+ * skipped; a frame failing its CRC is reported and dropped.
+ */
+
+use crate::codes::FrameError;
+
+pub const SYNC: [u8; 2] = [0xAA, 0x55];
+/// Sync + length
+pub const HEADER_LEN: usize = 4;
+pub const CRC_LEN: usize = 2;
+pub const MAX_PAYLOAD: usize = u16::MAX as usize;
+
+/// CRC-16/CCITT-FALSE.
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for &b in data {
+        crc ^= (b as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Bytes on the wire for a `payload_len`-byte payload.
+pub const fn encoded_len(payload_len: usize) -> usize {
+    HEADER_LEN + payload_len + CRC_LEN
+}
+
+/// Frame `payload` into `out`, returning the frame length.
+pub fn encode_into(payload: &[u8], out: &mut [u8]) -> Result<usize, FrameError> {
+    if payload.len() > MAX_PAYLOAD {
+        return Err(FrameError::TooLong);
+    }
+    let total = encoded_len(payload.len());
+    if out.len() < total {
+        return Err(FrameError::Overflow);
+    }
+    out[..2].copy_from_slice(&SYNC);
+    out[2..4].copy_from_slice(&(payload.len() as u16).to_be_bytes());
+    out[HEADER_LEN..HEADER_LEN + payload.len()].copy_from_slice(payload);
+    let crc = crc16(&out[2..HEADER_LEN + payload.len()]);
+    out[HEADER_LEN + payload.len()..total].copy_from_slice(&crc.to_be_bytes());
+    Ok(total)
+}
+
+/// Frame `payload`.
+#[cfg(feature = "alloc")]
+pub fn encode(payload: &[u8]) -> Result<alloc::vec::Vec<u8>, FrameError> {
+    let mut out = alloc::vec![0u8; encoded_len(payload.len())];
+    encode_into(payload, &mut out)?;
+    Ok(out)
+}
+
+// ── Decoder ───────────────────────────────────────────────────────────────────
+
+/// Byte-at-a-time deframer with an `N`-byte buffer (whole frame, framing included).
+pub struct FrameDecoder<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    /// The buffer holds a complete frame, handed out by the last `push`
+    complete: bool,
+}
+
+impl<const N: usize> Default for FrameDecoder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FrameDecoder<N> {
+    pub const fn new() -> Self {
+        Self { buf: [0u8; N], len: 0, complete: false }
+    }
+
+    /// Feed one received byte; a complete frame yields its payload (or why it was dropped).
+    pub fn push(&mut self, byte: u8) -> Option<Result<&[u8], FrameError>> {
+        if self.complete {
+            self.len = 0;
+            self.complete = false;
+        }
+        // Hunt for the sync word
+        if self.len < SYNC.len() && byte != SYNC[self.len] {
+            self.len = usize::from(byte == SYNC[0]);
+            return None;
+        }
+        if self.len == N {
+            self.len = 0;
+            return Some(Err(FrameError::Overflow));
+        }
+        self.buf[self.len] = byte;
+        self.len += 1;
+        if self.len < HEADER_LEN {
+            return None;
+        }
+        let payload_len = u16::from_be_bytes([self.buf[2], self.buf[3]]) as usize;
+        let total = encoded_len(payload_len);
+        if total > N {
+            self.len = 0;
+            return Some(Err(FrameError::Overflow));
+        }
+        if self.len < total {
+            return None;
+        }
+        self.complete = true;
+        let end = HEADER_LEN + payload_len;
+        let crc = u16::from_be_bytes([self.buf[end], self.buf[end + 1]]);
+        if crc16(&self.buf[2..end]) != crc {
+            return Some(Err(FrameError::BadCrc));
+        }
+        Some(Ok(&self.buf[HEADER_LEN..end]))
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_roundtrip_and_crc() {
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+
+        let mut wire = [0u8; 32];
+        let n = encode_into(b"hello", &mut wire).unwrap();
+        assert_eq!(n, encoded_len(5));
+        assert_eq!(&wire[..4], &[0xAA, 0x55, 0x00, 0x05]);
+
+        let mut decoder: FrameDecoder<16> = FrameDecoder::new();
+        // Line noise (including a lone 0xAA) before the frame is skipped
+        let mut frames = 0;
+        for &b in [0x13, 0xAA, 0x00].iter().chain(&wire[..n]) {
+            if let Some(frame) = decoder.push(b) {
+                assert_eq!(frame.unwrap(), b"hello");
+                frames += 1;
+            }
+        }
+        assert_eq!(frames, 1);
+
+        // A corrupted payload byte fails the CRC, then decoding resumes
+        wire[6] ^= 0x01;
+        let results: Vec<_> = wire[..n].iter().filter_map(|&b| decoder.push(b).map(|r| r.map(<[u8]>::to_vec))).collect();
+        assert_eq!(results, vec![Err(FrameError::BadCrc)]);
+
+        // Larger than the buffer
+        let mut big = [0u8; 64];
+        let n = encode_into(&[7u8; 20], &mut big).unwrap();
+        let results: Vec<_> = big[..n].iter().filter_map(|&b| decoder.push(b).map(|r| r.map(<[u8]>::to_vec))).collect();
+        assert_eq!(results, vec![Err(FrameError::Overflow)]);
+    }
+
+    #[test]
+    fn test_frame_vectors_and_resync() {
+        let mut wire = [0u8; 16];
+        let n = encode_into(b"", &mut wire).unwrap();
+        assert_eq!(&wire[..n], &[0xAA, 0x55, 0x00, 0x00, 0x1D, 0x0F]);
+        let n = encode_into(&[0x01, 0x02], &mut wire).unwrap();
+        assert_eq!(&wire[..n], &[0xAA, 0x55, 0x00, 0x02, 0x01, 0x02, 0xF9, 0xD3]);
+        assert_eq!(encode_into(&[0x01, 0x02], &mut [0u8; 7]), Err(FrameError::Overflow));
+        assert_eq!(encode_into(&vec![0u8; MAX_PAYLOAD + 1], &mut []), Err(FrameError::TooLong));
+
+        // Sync bytes inside a payload, a repeated 0xAA before the sync and
+        // back-to-back frames
+        let mut stream = vec![0xAA];
+        for payload in [&[0xAA, 0x55, 0xAA][..], &[][..], &[0x55][..]] {
+            let mut buf = [0u8; 16];
+            let n = encode_into(payload, &mut buf).unwrap();
+            stream.extend_from_slice(&buf[..n]);
+        }
+        let mut decoder: FrameDecoder<16> = FrameDecoder::new();
+        let frames: Vec<_> = stream.iter().filter_map(|&b| decoder.push(b).map(|r| r.unwrap().to_vec())).collect();
+        assert_eq!(frames, vec![vec![0xAA, 0x55, 0xAA], vec![], vec![0x55]]);
+
+        // A corrupted CRC byte drops the frame, the next one still decodes
+        let mut corrupt = stream[1..10].to_vec();
+        corrupt[8] ^= 0x80;
+        corrupt.extend_from_slice(&stream[10..]);
+        let results: Vec<_> = corrupt.iter().filter_map(|&b| decoder.push(b).map(|r| r.map(<[u8]>::to_vec))).collect();
+        assert_eq!(results, vec![Err(FrameError::BadCrc), Ok(vec![]), Ok(vec![0x55])]);
+
+        // A buffer exactly one frame long
+        let mut exact: FrameDecoder<{ encoded_len(3) }> = FrameDecoder::new();
+        let n = encode_into(&[1, 2, 3], &mut wire).unwrap();
+        assert_eq!(wire[..n].iter().filter_map(|&b| exact.push(b).map(|r| r.map(<[u8]>::to_vec))).last(), Some(Ok(vec![1, 2, 3])));
+        let n = encode_into(&[1, 2, 3, 4], &mut wire).unwrap();
+        assert_eq!(wire[..n].iter().filter_map(|&b| exact.push(b).map(|r| r.map(<[u8]>::to_vec))).next(), Some(Err(FrameError::Overflow)));
+    }
+}
+
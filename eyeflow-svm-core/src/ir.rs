@@ -0,0 +1,447 @@
+/*! eyeflow-svm-core::ir — MCU binary IR
+ *
+ * Layout (spec §5.3 MCU profile):
+ * ┌─────────────────────────────────────────────────────┐
+ * │ Header (8 bytes)                                    │
+ * │   [0..2]  magic    : 0xEF_F1                        │
+ * │   [2]     version  : u8 (must be 1)                 │
+ * │   [3]     flags    : u8 (0x01 = no_std profile)     │
+ * │   [4..6]  num_instr: u16 BE                         │
+ * │   [6..8]  reserved : u16                            │
+ * ├─────────────────────────────────────────────────────┤
+ * │ Instructions (8 bytes each)                         │
+ * │   [0]   opcode  : u8                                │
+ * │   [1]   operands: 7 bytes (opcode-specific)         │
+ * └─────────────────────────────────────────────────────┘
+ *
+ * Opcodes:
+ *   0x01  CALL_SERVICE  service_id, input_reg, output_reg
+ *   0x02  CALL_ACTION   action_id, value_reg, 5 argument bytes
+ *   0x03  BRANCH        condition, target_pc (u16 BE, absolute)
+ *   0x04  RETURN        output_reg
+ *   0x05  LOAD_IMM      output_reg, value (u16 BE) — lets the node pass
+ *                       an instruction's input to a translated program
+ *
+ * Register operands address R0..R7 (only the low 3 bits are used).
+ */
+
+use crate::codes::{BufferTooSmall, RuntimeError, ValidationError};
+
+// ── Constants ─────────────────────────────────────────────────────────────────
+
+pub const MAGIC: [u8; 2] = [0xEF, 0xF1];
+pub const IR_VERSION: u8 = 1;
+pub const FLAG_NO_STD: u8 = 0x01;
+
+pub const HEADER_LEN: usize = 8;
+pub const INSTR_LEN: usize = 8; // opcode(1) + operands(7)
+
+pub const MAX_INSTRUCTIONS: usize = 256;
+/// General-purpose registers R0..R7
+pub const REGISTERS: usize = 8;
+/// Service ids are 0..MAX_SERVICE_ID
+pub const MAX_SERVICE_ID: u8 = 64;
+
+// ── Opcodes ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Opcode {
+    CallService = 0x01,
+    CallAction  = 0x02,
+    Branch      = 0x03,
+    Return      = 0x04,
+    LoadImm     = 0x05,
+}
+
+impl Opcode {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0x01 => Some(Self::CallService),
+            0x02 => Some(Self::CallAction),
+            0x03 => Some(Self::Branch),
+            0x04 => Some(Self::Return),
+            0x05 => Some(Self::LoadImm),
+            _ => None,
+        }
+    }
+}
+
+/// BRANCH conditions, tested against the register flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Condition {
+    /// BEQ — zero flag set
+    Zero    = 0,
+    /// BNE — zero flag clear
+    NonZero = 1,
+    /// BERR — error flag set
+    Error   = 2,
+    /// BNOERR — error flag clear
+    NoError = 3,
+}
+
+impl Condition {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(Self::Zero),
+            1 => Some(Self::NonZero),
+            2 => Some(Self::Error),
+            3 => Some(Self::NoError),
+            _ => None,
+        }
+    }
+}
+
+// ── Instructions ──────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    CallService { service: u8, input: u8, output: u8 },
+    CallAction { action: u8, value: u8, args: [u8; 5] },
+    /// `condition` is kept raw: unknown conditions are never taken
+    Branch { condition: u8, target: u16 },
+    Return { output: u8 },
+    LoadImm { output: u8, value: u16 },
+}
+
+impl Instruction {
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            Self::CallService { .. } => Opcode::CallService,
+            Self::CallAction { .. } => Opcode::CallAction,
+            Self::Branch { .. } => Opcode::Branch,
+            Self::Return { .. } => Opcode::Return,
+            Self::LoadImm { .. } => Opcode::LoadImm,
+        }
+    }
+
+    /// Decode one 8-byte instruction (register operands masked to R0..R7).
+    pub fn decode(bytes: &[u8; INSTR_LEN]) -> Result<Self, RuntimeError> {
+        let ops = &bytes[1..];
+        let reg = |b: u8| b & 0x07;
+        match Opcode::from_u8(bytes[0]).ok_or(RuntimeError::UnknownOpcode)? {
+            Opcode::CallService => Ok(Self::CallService { service: ops[0], input: reg(ops[1]), output: reg(ops[2]) }),
+            Opcode::CallAction => Ok(Self::CallAction {
+                action: ops[0],
+                value: reg(ops[1]),
+                args: [ops[2], ops[3], ops[4], ops[5], ops[6]],
+            }),
+            Opcode::Branch => Ok(Self::Branch { condition: ops[0], target: u16::from_be_bytes([ops[1], ops[2]]) }),
+            Opcode::Return => Ok(Self::Return { output: reg(ops[0]) }),
+            Opcode::LoadImm => Ok(Self::LoadImm { output: reg(ops[0]), value: u16::from_be_bytes([ops[1], ops[2]]) }),
+        }
+    }
+
+    pub fn encode(&self) -> [u8; INSTR_LEN] {
+        let mut out = [0u8; INSTR_LEN];
+        out[0] = self.opcode() as u8;
+        match *self {
+            Self::CallService { service, input, output } => {
+                out[1..4].copy_from_slice(&[service, input & 0x07, output & 0x07]);
+            }
+            Self::CallAction { action, value, args } => {
+                out[1] = action;
+                out[2] = value & 0x07;
+                out[3..8].copy_from_slice(&args);
+            }
+            Self::Branch { condition, target } => {
+                out[1] = condition;
+                out[2..4].copy_from_slice(&target.to_be_bytes());
+            }
+            Self::Return { output } => out[1] = output & 0x07,
+            Self::LoadImm { output, value } => {
+                out[1] = output & 0x07;
+                out[2..4].copy_from_slice(&value.to_be_bytes());
+            }
+        }
+        out
+    }
+}
+
+// ── Program (decoder) ─────────────────────────────────────────────────────────
+
+/// A validated artifact, borrowed from the received payload.
+#[derive(Debug, Clone, Copy)]
+pub struct Program<'a> {
+    flags: u8,
+    body: &'a [u8],
+}
+
+impl<'a> Program<'a> {
+    /// Check the header and that every instruction is present.
+    pub fn parse(payload: &'a [u8]) -> Result<Self, ValidationError> {
+        if payload.len() < HEADER_LEN {
+            return Err(ValidationError::TooShort);
+        }
+        if payload[0..2] != MAGIC {
+            return Err(ValidationError::BadMagic);
+        }
+        if payload[2] != IR_VERSION {
+            return Err(ValidationError::BadVersion);
+        }
+        let num_instr = u16::from_be_bytes([payload[4], payload[5]]) as usize;
+        if num_instr > MAX_INSTRUCTIONS {
+            return Err(ValidationError::TooManyInstructions);
+        }
+        let end = HEADER_LEN + num_instr * INSTR_LEN;
+        if payload.len() < end {
+            return Err(ValidationError::Truncated);
+        }
+        Ok(Self { flags: payload[3], body: &payload[HEADER_LEN..end] })
+    }
+
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    /// Whether the compiler marked the artifact as MCU (no_std) profile.
+    pub fn no_std_profile(&self) -> bool {
+        self.flags & FLAG_NO_STD != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.body.len() / INSTR_LEN
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.body.is_empty()
+    }
+
+    /// Decode the instruction at `pc` (`pc < len()`).
+    pub fn instruction(&self, pc: usize) -> Result<Instruction, RuntimeError> {
+        let bytes = self.body.get(pc * INSTR_LEN..(pc + 1) * INSTR_LEN).ok_or(RuntimeError::BranchOutOfRange)?;
+        let mut raw = [0u8; INSTR_LEN];
+        raw.copy_from_slice(bytes);
+        Instruction::decode(&raw)
+    }
+}
+
+// ── Encoder ───────────────────────────────────────────────────────────────────
+
+/// Bytes needed to encode `num_instr` instructions.
+pub const fn encoded_len(num_instr: usize) -> usize {
+    HEADER_LEN + num_instr * INSTR_LEN
+}
+
+/// Encode an MCU-profile artifact into `out`, returning its length.
+pub fn encode_into(instrs: &[Instruction], out: &mut [u8]) -> Result<usize, EncodeError> {
+    if instrs.len() > MAX_INSTRUCTIONS {
+        return Err(EncodeError::Invalid(ValidationError::TooManyInstructions));
+    }
+    let needed = encoded_len(instrs.len());
+    if out.len() < needed {
+        return Err(EncodeError::Buffer(BufferTooSmall { needed }));
+    }
+    let n = (instrs.len() as u16).to_be_bytes();
+    out[..HEADER_LEN].copy_from_slice(&[MAGIC[0], MAGIC[1], IR_VERSION, FLAG_NO_STD, n[0], n[1], 0, 0]);
+    for (i, instr) in instrs.iter().enumerate() {
+        let at = HEADER_LEN + i * INSTR_LEN;
+        out[at..at + INSTR_LEN].copy_from_slice(&instr.encode());
+    }
+    Ok(needed)
+}
+
+/// Encode an MCU-profile artifact.
+#[cfg(feature = "alloc")]
+pub fn encode(instrs: &[Instruction]) -> Result<alloc::vec::Vec<u8>, ValidationError> {
+    let mut out = alloc::vec![0u8; encoded_len(instrs.len())];
+    match encode_into(instrs, &mut out) {
+        Ok(_) => Ok(out),
+        Err(EncodeError::Invalid(e)) => Err(e),
+        Err(EncodeError::Buffer(_)) => unreachable!("buffer sized by encoded_len"),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeError {
+    Invalid(ValidationError),
+    Buffer(BufferTooSmall),
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_parse_roundtrip() {
+        let instrs = [
+            Instruction::CallService { service: 0x01, input: 0, output: 1 },
+            Instruction::Branch { condition: Condition::Zero as u8, target: 3 },
+            Instruction::CallAction { action: 0x00, value: 1, args: [5, 0, 0, 0, 0] },
+            Instruction::Return { output: 1 },
+            Instruction::LoadImm { output: 2, value: 0xBEEF },
+        ];
+        let mut buf = [0u8; 64];
+        let len = encode_into(&instrs, &mut buf).unwrap();
+        assert_eq!(len, encoded_len(5));
+        assert_eq!(&buf[..8], &[0xEF, 0xF1, 1, FLAG_NO_STD, 0, 5, 0, 0]);
+
+        let program = Program::parse(&buf[..len]).unwrap();
+        assert!(program.no_std_profile());
+        assert_eq!(program.len(), 5);
+        for (pc, instr) in instrs.iter().enumerate() {
+            assert_eq!(program.instruction(pc).unwrap(), *instr);
+        }
+        assert_eq!(program.instruction(5), Err(RuntimeError::BranchOutOfRange));
+        assert_eq!(
+            encode_into(&instrs, &mut [0u8; 16]),
+            Err(EncodeError::Buffer(BufferTooSmall { needed: 48 })),
+        );
+    }
+
+    #[test]
+    fn test_header_validation() {
+        assert_eq!(Program::parse(&[0xEF, 0xF1]).unwrap_err(), ValidationError::TooShort);
+        assert_eq!(Program::parse(&[0xEF, 0xF0, 1, 1, 0, 0, 0, 0]).unwrap_err(), ValidationError::BadMagic);
+        assert_eq!(Program::parse(&[0xEF, 0xF1, 2, 1, 0, 0, 0, 0]).unwrap_err(), ValidationError::BadVersion);
+        assert_eq!(Program::parse(&[0xEF, 0xF1, 1, 1, 0, 1, 0, 0]).unwrap_err(), ValidationError::Truncated);
+        assert_eq!(Program::parse(&[0xEF, 0xF1, 1, 1, 0x01, 0x01, 0, 0]).unwrap_err(), ValidationError::TooManyInstructions);
+        assert_eq!(Instruction::decode(&[0x09, 0, 0, 0, 0, 0, 0, 0]), Err(RuntimeError::UnknownOpcode));
+    }
+
+    #[test]
+    fn test_decode_operands() {
+        // Register operands keep their low 3 bits; other operands are raw
+        let cases: [([u8; INSTR_LEN], Instruction); 5] = [
+            ([0x01, 0x3F, 0xFA, 0x0B, 9, 9, 9, 9], Instruction::CallService { service: 0x3F, input: 2, output: 3 }),
+            ([0x02, 0x03, 0x09, 1, 2, 3, 4, 5], Instruction::CallAction { action: 3, value: 1, args: [1, 2, 3, 4, 5] }),
+            ([0x03, 0x07, 0x01, 0x02, 9, 9, 9, 9], Instruction::Branch { condition: 7, target: 0x0102 }),
+            ([0x04, 0xFF, 9, 9, 9, 9, 9, 9], Instruction::Return { output: 7 }),
+            ([0x05, 0x0A, 0xBE, 0xEF, 9, 9, 9, 9], Instruction::LoadImm { output: 2, value: 0xBEEF }),
+        ];
+        for (raw, instr) in cases {
+            assert_eq!(Instruction::decode(&raw).unwrap(), instr);
+            let encoded = instr.encode();
+            assert_eq!(Instruction::decode(&encoded).unwrap(), instr);
+            assert_eq!(encoded[0], instr.opcode() as u8);
+        }
+        assert_eq!(Instruction::Return { output: 9 }.encode(), [0x04, 1, 0, 0, 0, 0, 0, 0]);
+
+        for op in 0..=u8::MAX {
+            let decoded = Instruction::decode(&[op, 0, 0, 0, 0, 0, 0, 0]);
+            assert_eq!(decoded.is_ok(), (0x01..=0x05).contains(&op), "opcode 0x{op:02x}");
+            assert_eq!(decoded.ok().map(|i| i.opcode() as u8), Opcode::from_u8(op).map(|o| o as u8));
+            assert_eq!(Condition::from_u8(op).map(|c| c as u8), (op < 4).then_some(op));
+        }
+    }
+
+    #[test]
+    fn test_program_bounds() {
+        // Exactly MAX_INSTRUCTIONS fit; one more does not
+        let full = [Instruction::Return { output: 0 }; MAX_INSTRUCTIONS + 1];
+        let mut buf = [0u8; encoded_len(MAX_INSTRUCTIONS + 1)];
+        let len = encode_into(&full[..MAX_INSTRUCTIONS], &mut buf).unwrap();
+        assert_eq!(Program::parse(&buf[..len]).unwrap().len(), MAX_INSTRUCTIONS);
+        assert_eq!(encode_into(&full, &mut buf), Err(EncodeError::Invalid(ValidationError::TooManyInstructions)));
+
+        // Bytes past the header's count are ignored; every shorter prefix is refused
+        let instrs = [Instruction::LoadImm { output: 0, value: 7 }, Instruction::Return { output: 0 }];
+        let len = encode_into(&instrs, &mut buf).unwrap();
+        assert_eq!(Program::parse(&buf[..len + 3]).unwrap().len(), 2);
+        for cut in 0..len {
+            let expected = if cut < HEADER_LEN { ValidationError::TooShort } else { ValidationError::Truncated };
+            assert_eq!(Program::parse(&buf[..cut]).unwrap_err(), expected, "prefix of {cut} bytes");
+        }
+
+        // Layout is checked up front, opcodes when executed
+        let empty = Program::parse(&[0xEF, 0xF1, 1, 0, 0, 0, 0, 0]).unwrap();
+        assert!(empty.is_empty() && !empty.no_std_profile());
+        assert_eq!(empty.instruction(0), Err(RuntimeError::BranchOutOfRange));
+        let bad = [0xEF, 0xF1, 1, 1, 0, 1, 0, 0, 0x7F, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(Program::parse(&bad).unwrap().instruction(0), Err(RuntimeError::UnknownOpcode));
+    }
+
+    /// The firmware executor's control flow (eyeflow-svm-mcu svm.rs) over
+    /// `Program`, with `service` standing in for the dispatch table.
+    fn run(payload: &[u8], service: impl Fn(u8, u16) -> Option<u16>) -> Result<Option<u16>, RuntimeError> {
+        let program = Program::parse(payload).expect("valid artifact");
+        let (mut r, mut zero, mut error) = ([0u16; REGISTERS], false, false);
+        let mut pc = 0;
+        while pc < program.len() {
+            match program.instruction(pc)? {
+                Instruction::CallService { service: id, input, output } => {
+                    match service(id, r[input as usize]).filter(|_| id < MAX_SERVICE_ID) {
+                        Some(v) => (r[output as usize], zero, error) = (v, v == 0, false),
+                        None => error = true,
+                    }
+                }
+                Instruction::CallAction { .. } => error = false,
+                Instruction::Branch { condition, target } => {
+                    let take = match Condition::from_u8(condition) {
+                        Some(Condition::Zero) => zero,
+                        Some(Condition::NonZero) => !zero,
+                        Some(Condition::Error) => error,
+                        Some(Condition::NoError) => !error,
+                        None => false,
+                    };
+                    if take {
+                        if target as usize >= program.len() {
+                            return Err(RuntimeError::BranchOutOfRange);
+                        }
+                        pc = target as usize;
+                        continue;
+                    }
+                }
+                Instruction::LoadImm { output, value } => (r[output as usize], zero) = (value, value == 0),
+                Instruction::Return { output } => return Ok(Some(r[output as usize])),
+            }
+            pc += 1;
+        }
+        Ok(None)
+    }
+
+    #[test]
+    fn test_node_translations_execute_as_on_the_mcu() {
+        // eyeflow-svm-node mcu.rs translates CALL_SERVICE { service: 2 } with
+        // input 7 to exactly these bytes (asserted there too)
+        let call_service = [
+            0xEF, 0xF1, 0x01, 0x01, 0x00, 0x03, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+            0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut buf = [0u8; 64];
+        let len = encode_into(&[
+            Instruction::LoadImm { output: 0, value: 7 },
+            Instruction::CallService { service: 2, input: 0, output: 1 },
+            Instruction::Return { output: 1 },
+        ], &mut buf).unwrap();
+        assert_eq!(&buf[..len], &call_service);
+        assert_eq!(run(&call_service, |id, input| (id == 2).then_some(input * 100)), Ok(Some(700)));
+
+        // Fall back to R2 = 1 when the service fails, skip the action when it reads 0
+        let program = [
+            Instruction::CallService { service: 1, input: 0, output: 2 },
+            Instruction::Branch { condition: Condition::NoError as u8, target: 3 },
+            Instruction::LoadImm { output: 2, value: 1 },
+            Instruction::Branch { condition: Condition::Zero as u8, target: 5 },
+            Instruction::CallAction { action: 0, value: 2, args: [5, 0, 0, 0, 0] },
+            Instruction::Return { output: 2 },
+        ];
+        let len = encode_into(&program, &mut buf).unwrap();
+        assert_eq!(run(&buf[..len], |_, _| Some(2048)), Ok(Some(2048)));
+        assert_eq!(run(&buf[..len], |_, _| Some(0)), Ok(Some(0)));
+        assert_eq!(run(&buf[..len], |_, _| None), Ok(Some(1)));
+
+        // A taken branch past the end faults; an untaken one or an unknown
+        // condition falls through to the implicit RETURN
+        let len = encode_into(&[Instruction::Branch { condition: Condition::Zero as u8, target: 1 }], &mut buf).unwrap();
+        assert_eq!(run(&buf[..len], |_, _| None), Ok(None));
+        let len = encode_into(&[Instruction::Branch { condition: Condition::NoError as u8, target: 1 }], &mut buf).unwrap();
+        assert_eq!(run(&buf[..len], |_, _| None), Err(RuntimeError::BranchOutOfRange));
+        let len = encode_into(&[Instruction::Branch { condition: 9, target: 0 }], &mut buf).unwrap();
+        assert_eq!(run(&buf[..len], |_, _| None), Ok(None));
+        // Service ids at or above MAX_SERVICE_ID set the error flag
+        let len = encode_into(&[
+            Instruction::CallService { service: MAX_SERVICE_ID, input: 0, output: 1 },
+            Instruction::Branch { condition: Condition::Error as u8, target: 3 },
+            Instruction::Return { output: 0 },
+            Instruction::Return { output: 1 },
+        ], &mut buf).unwrap();
+        assert_eq!(run(&buf[..len], |_, _| Some(5)), Ok(Some(0)));
+    }
+}
+
@@ -0,0 +1,29 @@
+/*! eyeflow-svm-core — definitions shared by the MCU firmware and the Linux node
+ *
+ * Spec §5.3 / §8.4 — MCU profile
+ *
+ * The edge node generates MCU-profile artifacts and frames them onto the
+ * serial link; the firmware (eyeflow-svm-mcu) deframes and executes them.
+ * Both sides build on this crate so the wire formats cannot drift:
+ *
+ *   ir     — MCU binary IR: header, opcodes, branch conditions,
+ *            instruction encoder / decoder
+ *   frame  — edge-link framing 0xAA 0x55 <len u16 BE> <payload> <CRC-16 BE>
+ *   link   — MCU → node messages (execution result, offline flush, status)
+ *   codes  — validation / runtime / framing error codes
+ *
+ * `no_std`, no dependencies, no allocator; the `alloc` feature adds
+ * Vec-returning encoders for the node.
+ */
+
+#![cfg_attr(not(test), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod codes;
+pub mod frame;
+pub mod ir;
+pub mod link;
+
+pub use codes::{BufferTooSmall, FrameError, RuntimeError, ValidationError};
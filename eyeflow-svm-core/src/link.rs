@@ -0,0 +1,184 @@
+/*! eyeflow-svm-core::link — MCU → node messages
+ *
+ * Frames from the node carry an MCU-profile artifact (first byte 0xEF, the
+ * IR magic).  Frames from the MCU carry one message, tagged by its first
+ * byte:
+ *
+ *   0x01  RESULT  [status, code, output...]   answer to the last artifact
+ *                 status 0 = ok (code 0), 1 = validation error, 2 = runtime
+ *                 error (code = codes.rs value), 3 = offline queued (code =
+ *                 failed service / action count); output = RETURN bytes
+ *   0x02  FLUSH   n × [type, flags, value u16 BE]   offline buffer entries
+ *                 (type 0x01 = REPORT, 0x02 = ACTION_RESULT; flags 0x01 = urgent)
+ *   0x03  STATUS  [pending u16 BE, dropped u32 BE]  offline buffer state
+ */
+
+use crate::codes::{BufferTooSmall, FrameError};
+
+pub const TAG_RESULT: u8 = 0x01;
+pub const TAG_FLUSH: u8 = 0x02;
+pub const TAG_STATUS: u8 = 0x03;
+
+/// Bytes per FLUSH entry.
+pub const FLUSH_ENTRY_LEN: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Status {
+    Ok              = 0,
+    ValidationError = 1,
+    RuntimeError    = 2,
+    OfflineQueued   = 3,
+}
+
+impl Status {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(Self::Ok),
+            1 => Some(Self::ValidationError),
+            2 => Some(Self::RuntimeError),
+            3 => Some(Self::OfflineQueued),
+            _ => None,
+        }
+    }
+}
+
+/// One offline buffer entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlushEntry {
+    pub kind: u8,
+    pub flags: u8,
+    pub value: u16,
+}
+
+impl FlushEntry {
+    pub fn to_bytes(self) -> [u8; FLUSH_ENTRY_LEN] {
+        let v = self.value.to_be_bytes();
+        [self.kind, self.flags, v[0], v[1]]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message<'a> {
+    Result { status: Status, code: u8, output: &'a [u8] },
+    /// Raw entries, FLUSH_ENTRY_LEN bytes each — see `entries()`
+    Flush(&'a [u8]),
+    Status { pending: u16, dropped: u32 },
+}
+
+impl<'a> Message<'a> {
+    /// Parse a deframed MCU payload.
+    pub fn parse(payload: &'a [u8]) -> Result<Self, FrameError> {
+        let (&tag, body) = payload.split_first().ok_or(FrameError::BadMessage)?;
+        match tag {
+            TAG_RESULT if body.len() >= 2 => Ok(Self::Result {
+                status: Status::from_u8(body[0]).ok_or(FrameError::BadMessage)?,
+                code: body[1],
+                output: &body[2..],
+            }),
+            TAG_FLUSH if body.len() % FLUSH_ENTRY_LEN == 0 => Ok(Self::Flush(body)),
+            TAG_STATUS if body.len() == 6 => Ok(Self::Status {
+                pending: u16::from_be_bytes([body[0], body[1]]),
+                dropped: u32::from_be_bytes([body[2], body[3], body[4], body[5]]),
+            }),
+            _ => Err(FrameError::BadMessage),
+        }
+    }
+
+    /// FLUSH entries (empty for other messages).
+    pub fn entries(&self) -> impl Iterator<Item = FlushEntry> + 'a {
+        let raw: &'a [u8] = match self {
+            Self::Flush(raw) => raw,
+            _ => &[],
+        };
+        raw.chunks_exact(FLUSH_ENTRY_LEN)
+            .map(|c| FlushEntry { kind: c[0], flags: c[1], value: u16::from_be_bytes([c[2], c[3]]) })
+    }
+
+    /// Encode into `out` (a frame payload), returning its length.
+    pub fn encode_into(&self, out: &mut [u8]) -> Result<usize, BufferTooSmall> {
+        let needed = match self {
+            Self::Result { output, .. } => 3 + output.len(),
+            Self::Flush(raw) => 1 + raw.len(),
+            Self::Status { .. } => 7,
+        };
+        if out.len() < needed {
+            return Err(BufferTooSmall { needed });
+        }
+        match *self {
+            Self::Result { status, code, output } => {
+                out[..3].copy_from_slice(&[TAG_RESULT, status as u8, code]);
+                out[3..needed].copy_from_slice(output);
+            }
+            Self::Flush(raw) => {
+                out[0] = TAG_FLUSH;
+                out[1..needed].copy_from_slice(raw);
+            }
+            Self::Status { pending, dropped } => {
+                out[0] = TAG_STATUS;
+                out[1..3].copy_from_slice(&pending.to_be_bytes());
+                out[3..7].copy_from_slice(&dropped.to_be_bytes());
+            }
+        }
+        Ok(needed)
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_roundtrip() {
+        let mut buf = [0u8; 32];
+        let n = Message::Result { status: Status::Ok, code: 0, output: &[0x08, 0x00] }.encode_into(&mut buf).unwrap();
+        assert_eq!(
+            Message::parse(&buf[..n]).unwrap(),
+            Message::Result { status: Status::Ok, code: 0, output: &[0x08, 0x00] },
+        );
+
+        let mut raw = [0u8; 8];
+        raw[..4].copy_from_slice(&FlushEntry { kind: 0x01, flags: 0, value: 1500 }.to_bytes());
+        raw[4..].copy_from_slice(&FlushEntry { kind: 0x02, flags: 1, value: 7 }.to_bytes());
+        let n = Message::Flush(&raw).encode_into(&mut buf).unwrap();
+        let msg = Message::parse(&buf[..n]).unwrap();
+        let entries: Vec<_> = msg.entries().collect();
+        assert_eq!(entries[1], FlushEntry { kind: 0x02, flags: 1, value: 7 });
+
+        let n = Message::Status { pending: 3, dropped: 70_000 }.encode_into(&mut buf).unwrap();
+        assert_eq!(Message::parse(&buf[..n]).unwrap(), Message::Status { pending: 3, dropped: 70_000 });
+
+        assert_eq!(Message::parse(&[TAG_FLUSH, 1, 2]), Err(FrameError::BadMessage));
+        assert_eq!(Message::parse(&[0x7F]), Err(FrameError::BadMessage));
+        assert_eq!(Message::Status { pending: 0, dropped: 0 }.encode_into(&mut [0u8; 4]), Err(BufferTooSmall { needed: 7 }));
+    }
+
+    #[test]
+    fn test_malformed_messages() {
+        assert_eq!(Message::parse(&[]), Err(FrameError::BadMessage));
+        // RESULT needs status and code; unknown statuses are refused
+        assert_eq!(Message::parse(&[TAG_RESULT, 0]), Err(FrameError::BadMessage));
+        assert_eq!(Message::parse(&[TAG_RESULT, 4, 0]), Err(FrameError::BadMessage));
+        assert_eq!(
+            Message::parse(&[TAG_RESULT, 2, 0x10]).unwrap(),
+            Message::Result { status: Status::RuntimeError, code: 0x10, output: &[] },
+        );
+        // FLUSH carries whole entries only; an empty one is fine
+        assert_eq!(Message::parse(&[TAG_FLUSH]).unwrap().entries().count(), 0);
+        for extra in 1..FLUSH_ENTRY_LEN {
+            assert_eq!(Message::parse(&[TAG_FLUSH, 1, 0, 0, 5, 1, 0, 0][..1 + FLUSH_ENTRY_LEN + extra]), Err(FrameError::BadMessage));
+        }
+        // STATUS is exactly 6 bytes
+        for len in 0..=8 {
+            let body = [TAG_STATUS, 0, 3, 0, 0, 0, 9, 0, 0];
+            assert_eq!(Message::parse(&body[..1 + len]).is_ok(), len == 6, "STATUS body of {len} bytes");
+        }
+        assert_eq!(Message::Status { pending: 0, dropped: 0 }.entries().count(), 0);
+        for v in 0..=u8::MAX {
+            assert_eq!(Status::from_u8(v).map(|s| s as u8), (v < 4).then_some(v));
+        }
+    }
+}
+
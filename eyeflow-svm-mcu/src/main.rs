@@ -37,6 +37,8 @@ use embassy_stm32::{
     Config,
 };
 use embassy_time::{Duration, Timer};
+use eyeflow_svm_core::frame::{self, FrameDecoder};
+use eyeflow_svm_core::link::{Message, Status};
 
 mod svm;
 mod offline;
@@ -58,7 +60,7 @@ async fn led_task(mut led: Output<'static>) {
 
 // ── Edge-link receive task ────────────────────────────────────────────────────
 
-/// Maximum size of a received IR artifact frame (bytes).
+/// Maximum size of a received edge-link frame, framing included (bytes).
 const MAX_FRAME_LEN: usize = 4096;
 
 /// Shared channel capacity between edge_link_task and svm_task.
@@ -73,35 +75,25 @@ async fn edge_link_task(
 ) {
     defmt::info!("EyeFlow edge-link ready (USART2 115200 8N1)");
 
-    let mut rx_buf: heapless::Vec<u8, MAX_FRAME_LEN> = heapless::Vec::new();
+    // Framing: 0xAA 0x55 <len_hi> <len_lo> <payload...> <crc_hi> <crc_lo> —
+    // see spec §8.4 and eyeflow_svm_core::frame (shared with the edge node)
+    let mut decoder: FrameDecoder<MAX_FRAME_LEN> = FrameDecoder::new();
 
     loop {
         let mut byte = [0u8; 1];
         match uart.read(&mut byte).await {
-            Ok(_) => {
-                // Simple framing: 0xAA 0x55 <len_hi> <len_lo> <payload...> — see spec §8.4
-                if !rx_buf.is_empty() || byte[0] == 0xAA {
-                    if rx_buf.push(byte[0]).is_err() {
-                        defmt::warn!("RX buffer overflow — discarding frame");
-                        rx_buf.clear();
-                        continue;
-                    }
-                }
+            Ok(_) => match decoder.push(byte[0]) {
+                Some(Ok(payload)) => {
+                    defmt::debug!("Frame received: {} bytes", payload.len());
 
-                // Detect complete frame
-                if rx_buf.len() >= 4 && rx_buf[0] == 0xAA && rx_buf[1] == 0x55 {
-                    let payload_len =
-                        ((rx_buf[2] as usize) << 8) | (rx_buf[3] as usize);
-                    if rx_buf.len() == 4 + payload_len {
-                        let payload = &rx_buf[4..];
-                        defmt::debug!("Frame received: {} bytes", payload.len());
-
-                        // Execute in the SVM synchronously (this task drives the SVM)
-                        execute_svm_frame(payload).await;
-                        rx_buf.clear();
-                    }
+                    // Execute in the SVM synchronously (this task drives the SVM)
+                    execute_svm_frame(payload, &mut uart).await;
                 }
-            }
+                Some(Err(e)) => {
+                    defmt::warn!("Frame dropped: {=str}", e.as_str());
+                }
+                None => {}
+            },
             Err(e) => {
                 defmt::error!("USART read error: {:?}", e);
                 Timer::after(Duration::from_millis(10)).await;
@@ -110,26 +102,61 @@ async fn edge_link_task(
     }
 }
 
-/// Drive the MicroSVM for one received frame.
-async fn execute_svm_frame(payload: &[u8]) {
+/// Largest message the MCU sends (a RESULT with a full output buffer, or a
+/// FLUSH of the whole offline buffer).
+const LINK_TX_LEN: usize = 1 + offline::FLUSH_BYTES;
+
+/// Frame `msg` and write it to the edge node.
+async fn send(uart: &mut Uart<'static>, msg: Message<'_>) {
+    let mut payload = [0u8; LINK_TX_LEN];
+    let mut wire = [0u8; frame::encoded_len(LINK_TX_LEN)];
+    let Ok(n) = msg.encode_into(&mut payload) else {
+        defmt::error!("Link message exceeds {} bytes — not sent", LINK_TX_LEN);
+        return;
+    };
+    let Ok(n) = frame::encode_into(&payload[..n], &mut wire) else {
+        return;
+    };
+    if let Err(e) = uart.write(&wire[..n]).await {
+        defmt::error!("USART write error: {:?}", e);
+    }
+}
+
+/// Drive the MicroSVM for one received frame, then answer the edge node:
+/// RESULT, FLUSH (queued offline entries, if any) and STATUS.
+async fn execute_svm_frame(payload: &[u8], uart: &mut Uart<'static>) {
     let mut svm = MicroSvm::new();
     let mut offline = OfflineBuffer::new();
 
-    match svm.execute(payload, &mut offline).await {
+    let (status, code) = match svm.execute(payload, &mut offline).await {
         SvmResult::Ok(output_len) => {
             defmt::info!("SVM ok: {} output bytes", output_len);
-            offline.flush_pending().await;
+            (Status::Ok, 0)
         }
         SvmResult::ValidationError(code) => {
             defmt::error!("SVM IR validation error: {}", code);
+            (Status::ValidationError, code)
         }
         SvmResult::RuntimeError(code) => {
             defmt::error!("SVM runtime error: {}", code);
+            (Status::RuntimeError, code)
         }
         SvmResult::OfflineQueued(n) => {
             defmt::warn!("SVM offline: {} actions queued", n);
+            (Status::OfflineQueued, n.min(u8::MAX as usize) as u8)
         }
+    };
+    send(uart, Message::Result { status, code, output: &svm.output }).await;
+
+    // The edge node just reached us: the link is up
+    offline.set_link_up(true);
+    let dropped = offline.dropped();
+    let mut entries = [0u8; offline::FLUSH_BYTES];
+    let n = offline.flush_pending(&mut entries);
+    if n > 0 {
+        send(uart, Message::Flush(&entries[..n])).await;
     }
+    send(uart, Message::Status { pending: offline.pending() as u16, dropped }).await;
 }
 
 // ── Firmware entry point ──────────────────────────────────────────────────────
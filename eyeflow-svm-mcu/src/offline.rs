@@ -30,8 +30,11 @@ use defmt;
 /// Number of offline entries that fit in SRAM.
 const OFFLINE_CAPACITY: usize = 128;
 
-/// Bytes per entry.
-const ENTRY_LEN: usize = 4;
+/// Bytes per entry — the link FLUSH entry layout (eyeflow_svm_core::link).
+const ENTRY_LEN: usize = eyeflow_svm_core::link::FLUSH_ENTRY_LEN;
+
+/// Bytes needed to flush a full buffer.
+pub const FLUSH_BYTES: usize = OFFLINE_CAPACITY * ENTRY_LEN;
 
 // ── Entry types ───────────────────────────────────────────────────────────────
 
@@ -115,39 +118,38 @@ impl OfflineBuffer {
         });
     }
 
-    /// Flush all pending entries over the USART link (if link is up).
-    ///
-    /// In a real implementation this would write to the USART channel.
-    /// Stubbed here because the concrete USART handle is not passed in.
-    /// Production code should inject a `&mut Uart` or a heapless channel.
-    pub async fn flush_pending(&mut self) {
+    /// Drain pending entries into `out` as link FLUSH entries (if link is
+    /// up), returning the bytes written.  The caller frames and sends them
+    /// to the edge node (edge_link_task).
+    pub fn flush_pending(&mut self, out: &mut [u8]) -> usize {
         if !self.link_up {
             defmt::trace!("[Offline] flush_pending called but link is down");
-            return;
+            return 0;
         }
 
         let count = self.queue.len();
         if count == 0 {
-            return;
+            return 0;
         }
 
         defmt::info!("[Offline] Flushing {} queued entries", count);
 
-        while let Some(entry) = self.queue.pop_front() {
-            let bytes = entry.to_bytes();
-            // TODO: inject USART handle or signal via channel
+        let mut written = 0;
+        while written + ENTRY_LEN <= out.len() {
+            let Some(entry) = self.queue.pop_front() else { break };
             defmt::debug!(
                 "[Offline] Flush entry: type={} flags={} value={}",
                 entry.entry_type, entry.flags, entry.value
             );
-            // In production: uart.write(&bytes).await;
-            let _ = bytes; // prevent unused warning
+            out[written..written + ENTRY_LEN].copy_from_slice(&entry.to_bytes());
+            written += ENTRY_LEN;
         }
 
         if self.dropped > 0 {
             defmt::warn!("[Offline] {} entries were dropped due to buffer overflow", self.dropped);
             self.dropped = 0;
         }
+        written
     }
 
     /// Returns the number of queued entries.
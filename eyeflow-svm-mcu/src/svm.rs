@@ -7,20 +7,10 @@
  *   0x02  CALL_ACTION   — invoke an output action (relay, GPIO, etc.)
  *   0x03  BRANCH        — conditional jump based on register flag
  *   0x04  RETURN        — halt execution and return output slot
+ *   0x05  LOAD_IMM      — load a 16-bit immediate (the edge node's input)
  *
- * IR binary layout (per spec §5.3 MCU profile):
- * ┌─────────────────────────────────────────────────────┐
- * │ Header (8 bytes)                                    │
- * │   [0..2]  magic    : 0xEF_F1                        │
- * │   [2]     version  : u8 (must be 1)                 │
- * │   [3]     flags    : u8 (0x01 = no_std profile)     │
- * │   [4..6]  num_instr: u16 BE                         │
- * │   [6..8]  reserved : u16                            │
- * ├─────────────────────────────────────────────────────┤
- * │ Instructions (variable)                             │
- * │   [0]   opcode  : u8                                │
- * │   [1]   operands: 7 bytes (opcode-specific)         │
- * └─────────────────────────────────────────────────────┘
+ * IR binary layout: spec §5.3 MCU profile — decoded by eyeflow_svm_core::ir,
+ * the same definitions the edge node encodes with.
  *
  * Registers:
  *   R[0..7]  — 8 × u16 general-purpose registers
@@ -29,31 +19,23 @@
  * No heap, no alloc — all buffers are heapless.
  */
 
-use heapless::{Vec, String};
+use heapless::Vec;
 use defmt;
 
+use eyeflow_svm_core::codes::{RuntimeError, UNKNOWN_DISPATCH_ID};
+use eyeflow_svm_core::ir::{Condition, Instruction, Program, MAX_SERVICE_ID, REGISTERS};
+
 use crate::offline::OfflineBuffer;
 
 // ── Constants ─────────────────────────────────────────────────────────────────
 
-const MAGIC_HI: u8 = 0xEF;
-const MAGIC_LO: u8 = 0xF1;
-
-const IR_VERSION:  u8 = 1;
-const FLAG_NO_STD: u8 = 0x01;
-
-const HEADER_LEN: usize = 8;
-const INSTR_LEN:  usize = 8; // opcode(1) + operands(7)
-
-const MAX_INSTRUCTIONS: usize = 256;
-const MAX_OUTPUT_LEN:   usize = 512;
-const MAX_SERVICE_ID:   u8    = 64;
+const MAX_OUTPUT_LEN: usize = 512;
 
 // ── Register file ─────────────────────────────────────────────────────────────
 
 #[derive(Default)]
 pub struct Registers {
-    pub r:     [u16; 8],
+    pub r:     [u16; REGISTERS],
     pub flags: u8,
 }
 
@@ -115,62 +97,44 @@ impl MicroSvm {
         offline: &mut OfflineBuffer,
     ) -> SvmResult {
         // ── Header validation ──────────────────────────────────────────────
-        if payload.len() < HEADER_LEN {
-            defmt::error!("IR too short: {} bytes", payload.len());
-            return SvmResult::ValidationError(0x01);
-        }
-
-        if payload[0] != MAGIC_HI || payload[1] != MAGIC_LO {
-            defmt::error!("IR magic mismatch: {:02x} {:02x}", payload[0], payload[1]);
-            return SvmResult::ValidationError(0x02);
-        }
-
-        if payload[2] != IR_VERSION {
-            defmt::error!(
-                "IR version mismatch: expected {} got {}",
-                IR_VERSION, payload[2]
-            );
-            return SvmResult::ValidationError(0x03);
-        }
+        let program = match Program::parse(payload) {
+            Ok(p) => p,
+            Err(e) => {
+                defmt::error!("IR rejected: {=str} ({} bytes)", e.as_str(), payload.len());
+                return SvmResult::ValidationError(e as u8);
+            }
+        };
 
-        if payload[3] & FLAG_NO_STD == 0 {
-            defmt::warn!("IR flags: no_std profile bit not set ({})", payload[3]);
+        if !program.no_std_profile() {
+            defmt::warn!("IR flags: no_std profile bit not set ({})", program.flags());
             // Warn only — continue execution
         }
 
-        let num_instr = u16::from_be_bytes([payload[4], payload[5]]) as usize;
+        let num_instr = program.len();
         defmt::debug!("IR header ok: {} instructions", num_instr);
 
-        let expected_len = HEADER_LEN + num_instr * INSTR_LEN;
-        if payload.len() < expected_len {
-            defmt::error!(
-                "IR truncated: expected {} bytes, got {}",
-                expected_len, payload.len()
-            );
-            return SvmResult::ValidationError(0x04);
-        }
-
         // ── Execute instructions ──────────────────────────────────────────
         let mut pc: usize = 0;
         let mut offline_count: usize = 0;
 
         while pc < num_instr {
-            let offset = HEADER_LEN + pc * INSTR_LEN;
-            let instr = &payload[offset..offset + INSTR_LEN];
-            let opcode = instr[0];
-            let ops    = &instr[1..]; // 7 operand bytes
-
-            defmt::trace!("PC={} opcode=0x{:02x}", pc, opcode);
-
-            match opcode {
-                // ── CALL_SERVICE (0x01) ────────────────────────────────────
-                // ops[0]: service_id (0..MAX_SERVICE_ID)
-                // ops[1]: input_reg  (0..7) — register with input value
-                // ops[2]: output_reg (0..7) — register to store result
-                0x01 => {
-                    let svc_id   = ops[0];
-                    let in_reg   = (ops[1] & 0x07) as usize;
-                    let out_reg  = (ops[2] & 0x07) as usize;
+            let instr = match program.instruction(pc) {
+                Ok(i) => i,
+                Err(e) => {
+                    defmt::error!("{=str} at PC={}", e.as_str(), pc);
+                    return SvmResult::RuntimeError(e as u8);
+                }
+            };
+
+            defmt::trace!("PC={} opcode=0x{:02x}", pc, instr.opcode() as u8);
+
+            match instr {
+                // ── CALL_SERVICE ──────────────────────────────────────────
+                // Invoke a local sensor service on the input register; the
+                // result lands in the output register.
+                Instruction::CallService { service: svc_id, input, output } => {
+                    let in_reg   = input as usize;
+                    let out_reg  = output as usize;
 
                     if svc_id >= MAX_SERVICE_ID {
                         defmt::error!("CALL_SERVICE: invalid service_id {}", svc_id);
@@ -195,17 +159,13 @@ impl MicroSvm {
                     }
                 }
 
-                // ── CALL_ACTION (0x02) ────────────────────────────────────
-                // ops[0]: action_id (any u8, interpreted by dispatch_action)
-                // ops[1]: value_reg (0..7) — register with action payload
-                // ops[2..6]: extra args (e.g., GPIO port/pin)
-                0x02 => {
-                    let action_id = ops[0];
-                    let val_reg   = (ops[1] & 0x07) as usize;
-                    let value     = self.regs.r[val_reg];
-                    let args      = &ops[2..]; // 5 extra bytes
-
-                    match dispatch_action(action_id, value, args, offline).await {
+                // ── CALL_ACTION ───────────────────────────────────────────
+                // Drive an output with the value register; the 5 argument
+                // bytes are interpreted by dispatch_action (e.g. GPIO pin).
+                Instruction::CallAction { action: action_id, value, args } => {
+                    let value = self.regs.r[value as usize];
+
+                    match dispatch_action(action_id, value, &args, offline).await {
                         Ok(_) => {
                             self.regs.set_error(false);
                             defmt::debug!("CALL_ACTION action={} value={}", action_id, value);
@@ -218,19 +178,17 @@ impl MicroSvm {
                     }
                 }
 
-                // ── BRANCH (0x03) ─────────────────────────────────────────
-                // ops[0]: condition (0=Zero, 1=NonZero, 2=Error, 3=NoError)
-                // ops[1..2]: target_pc (u16 BE — absolute instruction index)
-                0x03 => {
-                    let condition = ops[0];
-                    let target_pc = u16::from_be_bytes([ops[1], ops[2]]) as usize;
-
-                    let take = match condition {
-                        0 => self.regs.zero_flag(),         // BEQ
-                        1 => !self.regs.zero_flag(),        // BNE
-                        2 => self.regs.error_flag(),        // BERR
-                        3 => !self.regs.error_flag(),       // BNOERR
-                        _ => {
+                // ── BRANCH ────────────────────────────────────────────────
+                // Jump to the absolute target_pc when the condition holds.
+                Instruction::Branch { condition, target } => {
+                    let target_pc = target as usize;
+
+                    let take = match Condition::from_u8(condition) {
+                        Some(Condition::Zero)    => self.regs.zero_flag(),   // BEQ
+                        Some(Condition::NonZero) => !self.regs.zero_flag(),  // BNE
+                        Some(Condition::Error)   => self.regs.error_flag(),  // BERR
+                        Some(Condition::NoError) => !self.regs.error_flag(), // BNOERR
+                        None => {
                             defmt::warn!("BRANCH: unknown condition {}", condition);
                             false
                         }
@@ -239,7 +197,7 @@ impl MicroSvm {
                     if take {
                         if target_pc >= num_instr {
                             defmt::error!("BRANCH: target_pc {} out of range", target_pc);
-                            return SvmResult::RuntimeError(0x10);
+                            return SvmResult::RuntimeError(RuntimeError::BranchOutOfRange as u8);
                         }
                         defmt::debug!("BRANCH taken: PC={} → {}", pc, target_pc);
                         pc = target_pc;
@@ -247,11 +205,20 @@ impl MicroSvm {
                     }
                 }
 
-                // ── RETURN (0x04) ─────────────────────────────────────────
-                // ops[0]: output_reg (0..7) — register value to emit
-                // Halts execution and writes output register to output buffer.
-                0x04 => {
-                    let out_reg = (ops[0] & 0x07) as usize;
+                // ── LOAD_IMM ──────────────────────────────────────────────
+                // Load an immediate into a register — how the edge node
+                // passes an instruction's input to a translated program.
+                Instruction::LoadImm { output, value } => {
+                    self.regs.r[output as usize] = value;
+                    self.regs.set_zero(value == 0);
+                    defmt::debug!("LOAD_IMM r[{}]={}", output, value);
+                }
+
+                // ── RETURN ────────────────────────────────────────────────
+                // Halts execution and writes the output register to the
+                // output buffer.
+                Instruction::Return { output } => {
+                    let out_reg = output as usize;
                     let result  = self.regs.r[out_reg];
 
                     // Write u16 output to output buffer (big-endian)
@@ -268,12 +235,6 @@ impl MicroSvm {
                     }
                     return SvmResult::Ok(self.output.len());
                 }
-
-                // ── Unknown opcode ────────────────────────────────────────
-                op => {
-                    defmt::error!("Unknown opcode 0x{:02x} at PC={}", op, pc);
-                    return SvmResult::RuntimeError(0x20);
-                }
             }
 
             pc += 1;
@@ -329,7 +290,7 @@ async fn dispatch_service(
         }
         id => {
             defmt::error!("dispatch_service: unknown id {}", id);
-            Err(0xFF)
+            Err(UNKNOWN_DISPATCH_ID)
         }
     }
 }
@@ -375,7 +336,7 @@ async fn dispatch_action(
         }
         id => {
             defmt::error!("dispatch_action: unknown id {}", id);
-            Err(0xFF)
+            Err(UNKNOWN_DISPATCH_ID)
         }
     }
 }
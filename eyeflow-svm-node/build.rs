@@ -1,9 +1,30 @@
 fn main() {
     // Compile llm_ir.proto so the SVM can decode LLM-IR binary artifacts
     // sent by the NestJS central node via WebSocket TLS (spec §8.2).
-    prost_build::compile_protos(
-        &["proto/llm_ir.proto"],
-        &["proto/"],
-    )
-    .expect("prost_build failed — ensure proto/llm_ir.proto exists");
+    //
+    // Every message also (de)serialises with serde, camelCase like the rest
+    // of the JSON wire, so text frames carry the proto types directly.
+    // Missing fields take their proto default; the field attributes below
+    // keep the RESULT payload central already parses.
+    let mut config = prost_build::Config::new();
+    config
+        .message_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
+        .message_attribute(".", "#[serde(default, rename_all = \"camelCase\")]")
+        .enum_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
+        .enum_attribute(".", "#[serde(rename_all = \"SCREAMING_SNAKE_CASE\")]")
+        .field_attribute(".llmir.SignedIRArtifact.payload", "#[serde(with = \"crate::proto::base64_bytes\")]")
+        .field_attribute(".llmir.SignedIRArtifact.signature", "#[serde(with = \"crate::proto::base64_bytes\")]")
+        .field_attribute(".llmir.SliceExecutionResult.error", "#[serde(skip_serializing_if = \"String::is_empty\")]")
+        .field_attribute(".llmir.SliceExecutionResult.error_code", "#[serde(skip_serializing_if = \"String::is_empty\")]")
+        .field_attribute(
+            ".llmir.SliceExecutionResult.error_instruction",
+            "#[serde(default = \"crate::proto::slice_level\", skip_serializing_if = \"crate::proto::is_slice_level\")]",
+        )
+        .field_attribute(".llmir.SliceExecutionResult.audit_events", "#[serde(skip_serializing_if = \"Vec::is_empty\")]")
+        .field_attribute(".llmir.SliceExecutionResult.lineage", "#[serde(skip_serializing_if = \"Vec::is_empty\")]")
+        .field_attribute(".llmir.SliceExecutionResult.blobs", "#[serde(skip_serializing_if = \"Vec::is_empty\")]")
+        .field_attribute(".llmir.RegisterLineage.register_index", "#[serde(rename = \"register\")]")
+        .field_attribute(".llmir.BlobRef.register_index", "#[serde(rename = \"register\")]")
+        .compile_protos(&["proto/llm_ir.proto"], &["proto/"])
+        .expect("prost_build failed — ensure proto/llm_ir.proto exists");
 }
@@ -0,0 +1,98 @@
+//! Admission — the gates an artifact passes before it runs on this node
+//!
+//! Work reaches the SVM from central (IR_DISTRIBUTION, node.rs) and from
+//! pinned peers (DELEGATE, peer.rs).  Both are admitted by `Admission::admit`,
+//! which refuses, in order:
+//!
+//!   QUARANTINED      the node key is not approved yet (provision.rs)
+//!   NOT_ALLOWLISTED  the artifact checksum is not on the enforced IR
+//!                    allow-list (ir_allowlist.rs)
+//!   STANDBY          the node is the standby of its pair (standby.rs)
+//!   THROTTLED        the tenant is over its quotas (tenant.rs)
+//!
+//! An admitted slice holds the tenant permit until it finishes.  An unlisted
+//! artifact admitted in audit mode is flagged so the caller can alert.
+
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::errors::ErrorCode;
+use crate::ir_allowlist::{IrAllowList, Verdict};
+use crate::proto::llmir::LlmIntermediateRepresentation;
+use crate::provision::Enrollment;
+use crate::standby::Standby;
+use crate::tenant::{TenantGovernor, TenantPermit};
+
+/// Why an artifact was not admitted.
+#[derive(Debug)]
+pub struct Refusal {
+    pub status: &'static str,
+    pub code: ErrorCode,
+    pub reason: String,
+}
+
+impl Refusal {
+    fn new(status: &'static str, code: ErrorCode, reason: String) -> Self {
+        Self { status, code, reason }
+    }
+}
+
+/// An admitted artifact.
+#[derive(Debug)]
+pub struct Admitted {
+    pub permit: TenantPermit,
+    /// Not on the allow-list, admitted because the list is in audit mode
+    pub unlisted: bool,
+}
+
+#[derive(Clone)]
+pub struct Admission {
+    allowlist: Arc<IrAllowList>,
+    tenants: Arc<TenantGovernor>,
+    enrollment: Option<Arc<Enrollment>>,
+    standby: Option<Arc<Standby>>,
+}
+
+impl Admission {
+    pub fn new(allowlist: Arc<IrAllowList>, tenants: Arc<TenantGovernor>) -> Self {
+        Self { allowlist, tenants, enrollment: None, standby: None }
+    }
+
+    /// Quarantine the node until `enrollment` is approved.
+    pub fn with_enrollment(mut self, enrollment: Option<Arc<Enrollment>>) -> Self {
+        self.enrollment = enrollment;
+        self
+    }
+
+    /// Run nothing while `standby` is not the leader of its pair.
+    pub fn with_standby(mut self, standby: Option<Arc<Standby>>) -> Self {
+        self.standby = standby;
+        self
+    }
+
+    /// Admit `ir` (allow-list `checksum`) or say why not.
+    pub fn admit(&self, ir: &LlmIntermediateRepresentation, checksum: &str) -> Result<Admitted, Refusal> {
+        if let Some(enrollment) = self.enrollment.as_ref().filter(|e| e.quarantined()) {
+            return Err(Refusal::new(
+                "QUARANTINED", ErrorCode::Auth,
+                format!("node key enrollment is {}", enrollment.status().as_str()),
+            ));
+        }
+        let unlisted = match self.allowlist.check(checksum) {
+            Verdict::Allowed => false,
+            Verdict::Unlisted { enforced: false } => true,
+            Verdict::Unlisted { enforced: true } => {
+                return Err(Refusal::new(
+                    "NOT_ALLOWLISTED", ErrorCode::Auth,
+                    format!("artifact {checksum} is not on the IR allow-list"),
+                ));
+            }
+        };
+        if self.standby.as_ref().is_some_and(|s| !s.is_leader()) {
+            return Err(Refusal::new("STANDBY", ErrorCode::Unsupported, "node is the standby of its pair".into()));
+        }
+        let permit = self.tenants.admit(crate::tenant::tenant_of(ir))
+            .map_err(|throttled| Refusal::new("THROTTLED", ErrorCode::Quota, json!(throttled).to_string()))?;
+        Ok(Admitted { permit, unlisted })
+    }
+}
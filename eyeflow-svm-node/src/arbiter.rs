@@ -0,0 +1,552 @@
+//! Resource Arbiter — spec §6.5
+//!
+//! Serialises access to shared physical / logical resources (Modbus gateway,
+//! DB pool, serial port…) across concurrent workflows according to the
+//! instruction's PriorityPolicy:
+//!
+//!   priority_level  0 = critical … 255 = lowest; waiters are served in
+//!                   priority order (FIFO within a level), not arrival order
+//!   preemptible     the holder may be asked to yield to a higher-priority
+//!                   request — its in-flight dispatch is cancelled, the slot
+//!                   is handed over, and the holder re-queues for the opcode
+//!   max_wait_ms     bound on the wait before the caller triggers fallback
+//!
+//! Each resource has a number of slots (1 for a device, the pool size for a
+//! connection pool), chosen by the caller on first use unless central pushed
+//! an explicit capacity (CONFIG_UPDATE `resourceCapacities`).
+//!
+//! Wait time, timeouts, preemptions and the age of the oldest waiter are
+//! tracked per resource and exported on /metrics so operators can tune
+//! priority policies and capacities.
+//!
+//! When a distributed lock backend is configured (see dlock.rs), single-slot
+//! resources are also leased cluster-wide after the local slot is won; the
+//! lease's fencing token is visible to the dispatch via `FENCING_TOKEN`.
+
+use anyhow::{anyhow, Result};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::future::Future;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::{oneshot, watch};
+use tracing::{debug, info};
+
+use crate::dlock::{DistributedLock, Lease};
+use crate::errors::{ErrorCode, ExecError};
+use crate::health::MetricsCollector;
+use crate::proto::llmir::PriorityPolicy;
+
+tokio::task_local! {
+    /// Fencing token of the distributed lease held by the running dispatch.
+    pub static FENCING_TOKEN: u64;
+    /// Set once the running dispatch's request has left the process.
+    pub static DISPATCHED: Arc<AtomicBool>;
+}
+
+/// Record that the running dispatch has sent its request: a preempted side
+/// effect is re-queued only before this point (Svm::with_resource).
+pub fn mark_dispatched() {
+    let _ = DISPATCHED.try_with(|d| d.store(true, std::sync::atomic::Ordering::Relaxed));
+}
+
+/// Lowest priority — used for accesses that carry no PriorityPolicy.
+pub const LOWEST_PRIORITY: u32 = 255;
+
+// ── Claim ─────────────────────────────────────────────────────────────────────
+
+/// What a caller asks of the arbiter (mirrors PriorityPolicy).
+#[derive(Debug, Clone, Copy)]
+pub struct Claim {
+    pub priority: u32,
+    pub preemptible: bool,
+    pub max_wait_ms: u32,
+}
+
+impl Claim {
+    /// Claim for accesses without a PriorityPolicy: lowest priority, not
+    /// preemptible, bounded by `max_wait_ms`.
+    pub fn background(max_wait_ms: u32) -> Self {
+        Self { priority: LOWEST_PRIORITY, preemptible: false, max_wait_ms }
+    }
+}
+
+impl From<&PriorityPolicy> for Claim {
+    fn from(p: &PriorityPolicy) -> Self {
+        Self {
+            priority: p.priority_level.min(LOWEST_PRIORITY),
+            preemptible: p.preemptible,
+            max_wait_ms: p.max_wait_ms,
+        }
+    }
+}
+
+// ── Per-resource state ────────────────────────────────────────────────────────
+
+struct Holder {
+    priority: u32,
+    preemptible: bool,
+    yield_tx: watch::Sender<bool>,
+}
+
+/// Slot handed from a releasing holder directly to a waiter.
+struct Grant {
+    id: u64,
+    yield_rx: watch::Receiver<bool>,
+}
+
+struct Waiter {
+    priority: u32,
+    preemptible: bool,
+    seq: u64,
+    since: Instant,
+    tx: oneshot::Sender<Grant>,
+}
+
+// BinaryHeap is a max-heap: the "greatest" waiter is the one served next,
+// i.e. the lowest priority_level, then the earliest arrival.
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.cmp(&self.priority).then(other.seq.cmp(&self.seq))
+    }
+}
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Waiter {}
+
+#[derive(Debug, Clone, Default)]
+struct ResourceStats {
+    acquired_total: u64,
+    timeouts_total: u64,
+    preemptions_total: u64,
+    wait_ms_sum: u64,
+    wait_ms_max: u64,
+}
+
+struct ResourceState {
+    capacity: usize,
+    holders: HashMap<u64, Holder>,
+    waiters: BinaryHeap<Waiter>,
+    next_id: u64,
+    stats: ResourceStats,
+}
+
+impl ResourceState {
+    fn admit(&mut self, priority: u32, preemptible: bool) -> Grant {
+        let id = self.next_id;
+        self.next_id += 1;
+        let (yield_tx, yield_rx) = watch::channel(false);
+        self.holders.insert(id, Holder { priority, preemptible, yield_tx });
+        Grant { id, yield_rx }
+    }
+
+    /// Ask the weakest preemptible holder ranked below `priority` to yield.
+    fn preempt_for(&mut self, key: &str, priority: u32) {
+        let victim = self.holders.values()
+            .filter(|h| h.preemptible && h.priority > priority && !*h.yield_tx.borrow())
+            .max_by_key(|h| h.priority);
+        if let Some(h) = victim {
+            info!(
+                "[Arbiter] '{key}': asking priority {} holder to yield to priority {priority}",
+                h.priority
+            );
+            let _ = h.yield_tx.send(true);
+            self.stats.preemptions_total += 1;
+        }
+    }
+
+    /// Release holder `id` and hand the slot to the best live waiter.
+    fn release(&mut self, id: u64) {
+        self.holders.remove(&id);
+        self.fill();
+    }
+
+    /// Hand free slots to the best live waiters.
+    fn fill(&mut self) {
+        while self.holders.len() < self.capacity {
+            let Some(w) = self.waiters.pop() else { return };
+            let grant = self.admit(w.priority, w.preemptible);
+            let gid = grant.id;
+            if w.tx.send(grant).is_err() {
+                // Waiter gave up (timed out) — undo and try the next one
+                self.holders.remove(&gid);
+            }
+        }
+    }
+
+    fn record_wait(&mut self, waited: Duration) {
+        let ms = waited.as_millis() as u64;
+        self.stats.acquired_total += 1;
+        self.stats.wait_ms_sum += ms;
+        self.stats.wait_ms_max = self.stats.wait_ms_max.max(ms);
+    }
+}
+
+/// Point-in-time view of one resource, for metrics.
+#[derive(Debug, Clone)]
+pub struct ResourceSnapshot {
+    pub key: String,
+    pub capacity: usize,
+    pub in_use: usize,
+    pub waiting: usize,
+    pub oldest_wait_ms: u64,
+    pub acquired_total: u64,
+    pub timeouts_total: u64,
+    pub preemptions_total: u64,
+    pub wait_ms_sum: u64,
+    pub wait_ms_max: u64,
+}
+
+struct Resource {
+    key: String,
+    state: Mutex<ResourceState>,
+}
+
+// ── Permit ────────────────────────────────────────────────────────────────────
+
+/// A held slot; released (and handed to the next waiter) on drop.
+pub struct ResourcePermit {
+    resource: Arc<Resource>,
+    id: u64,
+    yield_rx: watch::Receiver<bool>,
+    lease: Option<Lease>,
+}
+
+impl ResourcePermit {
+    fn new(resource: Arc<Resource>, grant: Grant) -> Self {
+        Self { resource, id: grant.id, yield_rx: grant.yield_rx, lease: None }
+    }
+
+    pub fn fencing_token(&self) -> Option<u64> {
+        self.lease.as_ref().map(|l| l.fencing_token)
+    }
+
+    /// Drive `fut` while holding the slot.
+    ///
+    /// Returns `None` if the holder was asked to yield (or lost its
+    /// distributed lease) before `fut` finished — the dispatch future is
+    /// dropped (cancelled) at its current await point.
+    pub async fn run<F: Future>(&self, fut: F) -> Option<F::Output> {
+        let mut rx = self.yield_rx.clone();
+        let mut lost = self.lease.as_ref().map(|l| l.lost.clone());
+        let lease_lost = async {
+            let lost_ok = match lost.as_mut() {
+                Some(l) => l.wait_for(|v| *v).await.is_ok(),
+                None => false,
+            };
+            if !lost_ok {
+                std::future::pending::<()>().await;
+            }
+        };
+        let fence = self.fencing_token();
+        let fut = async move {
+            match fence {
+                Some(t) => FENCING_TOKEN.scope(t, fut).await,
+                None => fut.await,
+            }
+        };
+        tokio::select! {
+            out = fut => Some(out),
+            Ok(_) = rx.wait_for(|y| *y) => None,
+            _ = lease_lost => None,
+        }
+    }
+}
+
+impl Drop for ResourcePermit {
+    fn drop(&mut self) {
+        if let Ok(mut st) = self.resource.state.lock() {
+            st.release(self.id);
+        }
+    }
+}
+
+// ── Arbiter ───────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct ResourceArbiter {
+    resources: Mutex<HashMap<String, Arc<Resource>>>,
+    /// Capacities pushed by central; override the caller's default
+    capacities: Mutex<HashMap<String, usize>>,
+    seq: std::sync::atomic::AtomicU64,
+    /// Cluster-wide lock backend (None = local arbitration only)
+    distributed: Option<Arc<DistributedLock>>,
+}
+
+impl ResourceArbiter {
+    #[cfg(test)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_distributed(distributed: Option<Arc<DistributedLock>>) -> Self {
+        Self { distributed, ..Self::default() }
+    }
+
+    fn resource(&self, key: &str, capacity: usize) -> Arc<Resource> {
+        let capacity = self.capacities.lock().unwrap_or_else(|e| e.into_inner())
+            .get(key).copied().unwrap_or(capacity);
+        let mut map = self.resources.lock().unwrap_or_else(|e| e.into_inner());
+        map.entry(key.to_owned())
+            .or_insert_with(|| Arc::new(Resource {
+                key: key.to_owned(),
+                state: Mutex::new(ResourceState {
+                    capacity: capacity.max(1),
+                    holders: HashMap::new(),
+                    waiters: BinaryHeap::new(),
+                    next_id: 0,
+                    stats: ResourceStats::default(),
+                }),
+            }))
+            .clone()
+    }
+
+    /// Apply capacities pushed by central (`{ "resourceKey": slots, ... }`).
+    ///
+    /// Existing resources are resized in place — growing a resource admits
+    /// queued waiters immediately; shrinking takes effect as holders release.
+    pub fn set_capacities(&self, update: &serde_json::Value) -> Result<usize> {
+        let parsed: HashMap<String, usize> = serde_json::from_value(update.clone())
+            .map_err(|e| anyhow!("invalid resourceCapacities: {e}"))?;
+        let resources = self.resources.lock().unwrap_or_else(|e| e.into_inner());
+        for (key, cap) in &parsed {
+            if let Some(res) = resources.get(key) {
+                let mut st = res.state.lock().unwrap_or_else(|e| e.into_inner());
+                st.capacity = (*cap).max(1);
+                st.fill();
+            }
+        }
+        let n = parsed.len();
+        self.capacities.lock().unwrap_or_else(|e| e.into_inner()).extend(parsed);
+        info!("[Arbiter] applied {n} resource capacity override(s)");
+        Ok(n)
+    }
+
+    pub fn snapshot(&self) -> Vec<ResourceSnapshot> {
+        let resources = self.resources.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out: Vec<ResourceSnapshot> = resources.values().map(|res| {
+            let st = res.state.lock().unwrap_or_else(|e| e.into_inner());
+            ResourceSnapshot {
+                key: res.key.clone(),
+                capacity: st.capacity,
+                in_use: st.holders.len(),
+                waiting: st.waiters.len(),
+                oldest_wait_ms: st.waiters.iter()
+                    .map(|w| w.since.elapsed().as_millis() as u64)
+                    .max()
+                    .unwrap_or(0),
+                acquired_total: st.stats.acquired_total,
+                timeouts_total: st.stats.timeouts_total,
+                preemptions_total: st.stats.preemptions_total,
+                wait_ms_sum: st.stats.wait_ms_sum,
+                wait_ms_max: st.stats.wait_ms_max,
+            }
+        }).collect();
+        out.sort_by(|a, b| a.key.cmp(&b.key));
+        out
+    }
+
+    /// Acquire a slot on `key` (created with `capacity` slots on first use).
+    ///
+    /// `max_wait_ms = 0` means non-blocking (short grace period only).
+    /// Returns `Err` when the wait bound elapses — the caller triggers fallback.
+    pub async fn acquire(&self, key: &str, capacity: usize, claim: Claim) -> Result<ResourcePermit> {
+        let wait = if claim.max_wait_ms == 0 {
+            Duration::from_millis(50) // non-blocking: short grace period
+        } else {
+            Duration::from_millis(claim.max_wait_ms as u64)
+        };
+        let deadline = Instant::now() + wait;
+
+        let (mut permit, capacity) = self.acquire_local(key, capacity, claim, wait).await?;
+
+        if let Some(dl) = self.distributed.as_ref().filter(|_| capacity <= 1) {
+            match dl.acquire(key, deadline).await? {
+                Some(lease) => permit.lease = Some(lease),
+                None => return Err(ExecError::new(ErrorCode::Timeout, format!(
+                    "resource '{key}' held by another node — max_wait_ms={} exceeded (spec §6.5 PriorityPolicy)",
+                    claim.max_wait_ms
+                )).into()),
+            }
+        }
+        Ok(permit)
+    }
+
+    async fn acquire_local(
+        &self,
+        key: &str,
+        capacity: usize,
+        claim: Claim,
+        wait: Duration,
+    ) -> Result<(ResourcePermit, usize)> {
+        let resource = self.resource(key, capacity);
+        let since = Instant::now();
+
+        let (rx, capacity) = {
+            let mut st = resource.state.lock().unwrap_or_else(|e| e.into_inner());
+            if st.holders.len() < st.capacity && st.waiters.is_empty() {
+                let g = st.admit(claim.priority, claim.preemptible);
+                st.record_wait(Duration::ZERO);
+                let cap = st.capacity;
+                return Ok((ResourcePermit::new(resource.clone(), g), cap));
+            }
+            st.preempt_for(key, claim.priority);
+            let (tx, rx) = oneshot::channel();
+            let seq = self.seq.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            st.waiters.push(Waiter { priority: claim.priority, preemptible: claim.preemptible, seq, since, tx });
+            (rx, st.capacity)
+        };
+
+        let mut rx = rx;
+        match tokio::time::timeout(wait, &mut rx).await {
+            Ok(Ok(g)) => {
+                debug!("[Arbiter] '{key}' granted to priority {}", claim.priority);
+                resource.state.lock().unwrap_or_else(|e| e.into_inner()).record_wait(since.elapsed());
+                Ok((ResourcePermit::new(resource, g), capacity))
+            }
+            _ => {
+                // A grant may have raced the timeout — give it back.
+                rx.close();
+                if let Ok(g) = rx.try_recv() {
+                    drop(ResourcePermit::new(resource.clone(), g));
+                }
+                {
+                    let mut st = resource.state.lock().unwrap_or_else(|e| e.into_inner());
+                    st.stats.timeouts_total += 1;
+                    // Drop the abandoned entry so it doesn't skew oldest_wait_ms
+                    st.waiters.retain(|w| !w.tx.is_closed());
+                }
+                Err(ExecError::new(ErrorCode::Timeout, format!(
+                    "resource '{}' busy — max_wait_ms={} exceeded (spec §6.5 PriorityPolicy)",
+                    resource.key, claim.max_wait_ms
+                )).into())
+            }
+        }
+    }
+}
+
+// ── Metrics ───────────────────────────────────────────────────────────────────
+
+/// (metric name, Prometheus type, help text, accessor)
+type Series = (&'static str, &'static str, &'static str, fn(&ResourceSnapshot) -> u64);
+
+impl MetricsCollector for ResourceArbiter {
+    fn prometheus(&self, node_id: &str) -> String {
+        let snap = self.snapshot();
+        if snap.is_empty() {
+            return String::new();
+        }
+        let series: [Series; 9] = [
+            ("eyeflow_resource_capacity", "gauge", "Slots available on the resource", |s| s.capacity as u64),
+            ("eyeflow_resource_in_use", "gauge", "Slots currently held", |s| s.in_use as u64),
+            ("eyeflow_resource_waiting", "gauge", "Requests queued for the resource", |s| s.waiting as u64),
+            ("eyeflow_resource_oldest_wait_ms", "gauge", "Age of the oldest queued request (starvation)", |s| s.oldest_wait_ms),
+            ("eyeflow_resource_acquired_total", "counter", "Slots granted", |s| s.acquired_total),
+            ("eyeflow_resource_timeouts_total", "counter", "Requests that exceeded max_wait_ms", |s| s.timeouts_total),
+            ("eyeflow_resource_preemptions_total", "counter", "Holders asked to yield", |s| s.preemptions_total),
+            ("eyeflow_resource_wait_ms_sum", "counter", "Total time spent waiting for a slot (ms)", |s| s.wait_ms_sum),
+            ("eyeflow_resource_wait_ms_max", "gauge", "Longest wait for a slot (ms)", |s| s.wait_ms_max),
+        ];
+
+        let mut out = String::new();
+        for (name, kind, help, get) in series {
+            out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+            for s in &snap {
+                out.push_str(&format!(
+                    "{name}{{node_id=\"{node_id}\",resource=\"{}\"}} {}\n", s.key, get(s)
+                ));
+            }
+        }
+        out
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(priority: u32, preemptible: bool) -> Claim {
+        Claim { priority, preemptible, max_wait_ms: 1_000 }
+    }
+
+    #[tokio::test]
+    async fn test_waiters_served_by_priority() {
+        let arbiter = Arc::new(ResourceArbiter::new());
+        let held = arbiter.acquire("gw", 1, claim(10, false)).await.unwrap();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for p in [200, 5, 50] {
+            let (a, o) = (arbiter.clone(), order.clone());
+            tasks.push(tokio::spawn(async move {
+                let _permit = a.acquire("gw", 1, claim(p, false)).await.unwrap();
+                o.lock().unwrap().push(p);
+            }));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        drop(held);
+        for t in tasks {
+            t.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec![5, 50, 200]);
+    }
+
+    #[tokio::test]
+    async fn test_preemptible_holder_yields() {
+        let arbiter = Arc::new(ResourceArbiter::new());
+        let low = arbiter.acquire("gw", 1, claim(200, true)).await.unwrap();
+
+        let a = arbiter.clone();
+        let critical = tokio::spawn(async move { a.acquire("gw", 1, claim(0, false)).await.is_ok() });
+
+        let out = low.run(tokio::time::sleep(Duration::from_secs(5))).await;
+        assert!(out.is_none(), "low-priority dispatch should have been cancelled");
+        drop(low);
+        assert!(critical.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_capacity_override_and_metrics() {
+        let arbiter = Arc::new(ResourceArbiter::new());
+        let _a = arbiter.acquire("db", 1, claim(10, false)).await.unwrap();
+
+        let a = arbiter.clone();
+        let waiter = tokio::spawn(async move { a.acquire("db", 1, claim(10, false)).await.map(|_| ()) });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(arbiter.snapshot()[0].waiting, 1);
+
+        // Growing the resource admits the queued waiter without a release
+        arbiter.set_capacities(&serde_json::json!({ "db": 2 })).unwrap();
+        waiter.await.unwrap().unwrap();
+
+        let snap = &arbiter.snapshot()[0];
+        assert_eq!(snap.capacity, 2);
+        assert_eq!(snap.acquired_total, 2);
+        assert!(snap.wait_ms_max >= 15);
+        let text = arbiter.prometheus("n1");
+        assert!(text.contains("eyeflow_resource_capacity{node_id=\"n1\",resource=\"db\"} 2"));
+    }
+
+    #[tokio::test]
+    async fn test_non_preemptible_holder_times_out_waiter() {
+        let arbiter = ResourceArbiter::new();
+        let _held = arbiter.acquire("gw", 1, claim(200, false)).await.unwrap();
+        let err = arbiter.acquire("gw", 1, Claim { max_wait_ms: 20, ..claim(0, false) }).await;
+        assert!(err.is_err());
+        // Timed-out waiter must not leak the slot
+        drop(_held);
+        assert!(arbiter.acquire("gw", 1, claim(0, false)).await.is_ok());
+    }
+}
@@ -0,0 +1,330 @@
+//! Signed assets — ONNX models, WASM modules, JS bundles, lookup tables
+//!
+//! Central pushes auxiliary files instructions need, ahead of the IR that
+//! uses them:
+//!
+//!   → { "type": "ASSET_DISTRIBUTION", "payload": {
+//!         "name": "weld-defects", "kind": "onnx", "sha256": "<hex>",
+//!         "signature": "<base64 Ed25519, see below>",
+//!         "base64": "…" } }            (or "url": "https://…" for large files)
+//!   ← { "type": "ASSET_ACK", "payload": { "sha256", "name", "status": "CACHED" } }
+//!     (or "status": "REJECTED", "error": "…")
+//!
+//! Like an IR artifact, an asset is checked against its SHA-256 checksum and
+//! an Ed25519 signature over its name, kind and checksum:
+//!
+//!   eyeflow-asset:v1\n<name>\n<kind>\nsha256:<hex>
+//!
+//! The key is pinned on the node: SVM_ASSET_PUBLIC_KEY,
+//! or else SVM_CONFIG_OPS_PUBLIC_KEY; without either, unsigned assets are
+//! accepted with a warning.  A rejected signature raises
+//! ASSET_SIGNATURE_INVALID.
+//!
+//! Assets are cached on disk by checksum in SVM_ASSET_DIR (`<sha256>` plus a
+//! `<sha256>.json` sidecar holding name, kind and signature) and survive
+//! restarts; a file is re-hashed before every load and dropped when it no
+//! longer matches.  At most SVM_ASSET_MAX_BYTES per asset (QUOTA beyond).
+//! REGISTER capabilities list the cached ones under `assets`.
+//!
+//! Instructions reference them from `dispatch_metadata.assets`
+//! ({ name, sha256, kind }); an instruction whose assets are not cached
+//! fails with NOT_FOUND before it dispatches.  The ONNX format takes its
+//! model from there (inference.rs).
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use bytes::Bytes;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+use crate::proto::llmir::AssetRef;
+
+/// An asset as announced by central (or by an instruction's operands).
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssetSpec {
+    pub name: String,
+    pub kind: String,
+    pub sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl AssetSpec {
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let mut spec: Self = serde_json::from_value(value.clone())
+            .map_err(|e| ExecError::validation(format!("asset: {e}")))?;
+        spec.sha256 = normalize_sha256(&spec.sha256)
+            .ok_or_else(|| ExecError::validation("asset: sha256 must be 64 hex characters"))?;
+        if spec.name.contains('\n') || spec.kind.contains('\n') {
+            return Err(ExecError::validation("asset: name and kind must be single-line"));
+        }
+        spec.url = spec.url.filter(|u| !u.is_empty());
+        spec.signature = spec.signature.filter(|s| !s.is_empty());
+        Ok(spec)
+    }
+
+    /// Text the publisher signs: name, kind and checksum, one per line, so
+    /// a signature cannot be replayed for an asset under another name or kind.
+    fn signed_text(&self) -> String {
+        format!("eyeflow-asset:v1\n{}\n{}\nsha256:{}", self.name, self.kind, self.sha256)
+    }
+
+    /// "name (sha256:…)" for messages.
+    fn label(&self) -> String {
+        format!("{} (sha256:{})", self.name, self.sha256)
+    }
+}
+
+/// Lower-case hex of a "sha256:<hex>" or "<hex>" checksum.
+fn normalize_sha256(text: &str) -> Option<String> {
+    let hex = text.trim_start_matches("sha256:").to_ascii_lowercase();
+    (hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit())).then_some(hex)
+}
+
+pub struct AssetStore {
+    dir: PathBuf,
+    key: Option<VerifyingKey>,
+    max_bytes: u64,
+    http: reqwest::Client,
+    /// Cached assets by checksum (sidecars)
+    index: Mutex<BTreeMap<String, AssetSpec>>,
+}
+
+impl AssetStore {
+    /// Open the cache in `dir`, indexing the assets already there.
+    pub fn open(dir: PathBuf, key: Option<VerifyingKey>, max_bytes: u64, http: reqwest::Client) -> Arc<Self> {
+        let mut index = BTreeMap::new();
+        for entry in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                match std::fs::read(&path).ok().and_then(|b| serde_json::from_slice::<AssetSpec>(&b).ok()) {
+                    Some(spec) => {
+                        index.insert(spec.sha256.clone(), spec);
+                    }
+                    None => warn!("[Asset] ignoring unreadable sidecar {}", path.display()),
+                }
+            }
+        }
+        if !index.is_empty() {
+            info!("[Asset] {} cached asset(s) in {}", index.len(), dir.display());
+        }
+        Arc::new(Self { dir, key, max_bytes, http, index: Mutex::new(index) })
+    }
+
+    pub fn from_config(config: &Config, http: reqwest::Client) -> Result<Arc<Self>> {
+        let key = config.asset_public_key.as_deref()
+            .or(config.config_ops_public_key.as_deref())
+            .map(crate::config_sig::verifying_key_from_str)
+            .transpose()?;
+        Ok(Self::open(PathBuf::from(&config.asset_dir), key, config.asset_max_bytes, http))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, AssetSpec>> {
+        self.index.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Refuse an asset without a valid signature when a key is pinned.
+    pub fn check_signature(&self, spec: &AssetSpec) -> Result<()> {
+        let Some(key) = &self.key else {
+            if spec.signature.is_none() {
+                warn!("[Asset] {} is unsigned — no asset key pinned", spec.label());
+            }
+            return Ok(());
+        };
+        let invalid = |msg: &str| ExecError::new(ErrorCode::Auth, format!("asset {}: {msg}", spec.label()));
+        let signature = spec.signature.as_deref().ok_or_else(|| invalid("not signed"))?;
+        let signature = B64.decode(signature).ok()
+            .and_then(|b| Signature::from_slice(&b).ok())
+            .ok_or_else(|| invalid("malformed signature"))?;
+        key.verify(spec.signed_text().as_bytes(), &signature)
+            .map_err(|_| invalid("signature does not match the asset key").into())
+    }
+
+    /// Handle an ASSET_DISTRIBUTION payload: verify and cache the asset.
+    pub async fn receive(&self, payload: &Value) -> Result<AssetSpec> {
+        let spec = AssetSpec::from_json(payload)?;
+        self.check_signature(&spec)?;
+        let bytes = match (payload.get("base64").and_then(Value::as_str), &spec.url) {
+            (Some(b64), _) => Bytes::from(B64.decode(b64).map_err(|e| ExecError::validation(format!("asset base64: {e}")))?),
+            (None, Some(url)) => self.download(url).await?,
+            (None, None) => return Err(ExecError::validation("asset has neither base64 nor url")),
+        };
+        self.put(&spec, bytes).await?;
+        Ok(spec)
+    }
+
+    /// Cache `bytes` as `spec` once they match its checksum.
+    async fn put(&self, spec: &AssetSpec, bytes: Bytes) -> Result<()> {
+        if bytes.len() as u64 > self.max_bytes {
+            return Err(ExecError::new(ErrorCode::Quota, format!(
+                "asset {}: {} bytes exceed SVM_ASSET_MAX_BYTES={}", spec.label(), bytes.len(), self.max_bytes,
+            )).into());
+        }
+        let got = hex::encode(Sha256::digest(&bytes));
+        if got != spec.sha256 {
+            return Err(ExecError::validation(format!("asset {} has sha256 {got}", spec.label())));
+        }
+        let spec = AssetSpec { url: None, ..spec.clone() };
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(&spec.sha256);
+        let tmp = path.with_extension("part");
+        tokio::fs::write(&tmp, &bytes).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        tokio::fs::write(path.with_extension("json"), serde_json::to_vec(&spec)?).await?;
+        info!("[Asset] cached {} {} ({} bytes)", spec.kind, spec.name, bytes.len());
+        self.lock().insert(spec.sha256.clone(), spec);
+        Ok(())
+    }
+
+    /// The bytes of cached asset `sha256`, re-hashed and (with a pinned
+    /// key) re-verified.
+    #[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+    pub async fn get(&self, sha256: &str) -> Result<Bytes> {
+        let sha256 = normalize_sha256(sha256).unwrap_or_default();
+        let spec = self.lock().get(&sha256).cloned().ok_or_else(|| ExecError::new(
+            ErrorCode::NotFound,
+            format!("asset sha256:{sha256} is not cached — central distributes it with ASSET_DISTRIBUTION"),
+        ))?;
+        self.check_signature(&spec)?;
+        let path = self.dir.join(&sha256);
+        let bytes = tokio::fs::read(&path).await.unwrap_or_default();
+        if hex::encode(Sha256::digest(&bytes)) != sha256 {
+            warn!("[Asset] {} no longer matches its checksum — dropped", path.display());
+            self.remove(&sha256).await;
+            return Err(ExecError::new(ErrorCode::NotFound, format!("cached asset sha256:{sha256} was corrupt and is dropped")).into());
+        }
+        Ok(bytes.into())
+    }
+
+    /// `spec` from the cache, or downloaded from its url into it.
+    #[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+    pub async fn fetch(&self, spec: &AssetSpec) -> Result<Bytes> {
+        if self.contains(&spec.sha256) {
+            match self.get(&spec.sha256).await {
+                Err(e) if spec.url.is_none() || crate::errors::classify(&e) != ErrorCode::NotFound => return Err(e),
+                Err(_) => {}
+                ok => return ok,
+            }
+        }
+        let url = spec.url.as_deref().ok_or_else(|| ExecError::new(
+            ErrorCode::NotFound,
+            format!("asset {} is not cached and has no url", spec.label()),
+        ))?;
+        self.check_signature(spec)?;
+        let bytes = self.download(url).await?;
+        self.put(spec, bytes.clone()).await?;
+        Ok(bytes)
+    }
+
+    pub fn contains(&self, sha256: &str) -> bool {
+        normalize_sha256(sha256).is_some_and(|s| self.lock().contains_key(&s))
+    }
+
+    /// Fail with NOT_FOUND unless every asset in `refs` is cached.
+    pub fn precheck(&self, refs: &[AssetRef]) -> Result<()> {
+        match refs.iter().find(|r| !self.contains(&r.sha256)) {
+            Some(r) => Err(ExecError::new(ErrorCode::NotFound, format!(
+                "asset {} (sha256:{}) is not cached — central distributes it with ASSET_DISTRIBUTION", r.name, r.sha256,
+            )).into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Cached assets, for REGISTER capabilities.
+    pub fn cached(&self) -> Vec<Value> {
+        self.lock().values()
+            .map(|s| json!({ "name": s.name, "kind": s.kind, "sha256": s.sha256 }))
+            .collect()
+    }
+
+    #[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+    async fn remove(&self, sha256: &str) {
+        self.lock().remove(sha256);
+        let path = self.dir.join(sha256);
+        tokio::fs::remove_file(&path).await.ok();
+        tokio::fs::remove_file(path.with_extension("json")).await.ok();
+    }
+
+    async fn download(&self, url: &str) -> Result<Bytes> {
+        let what = format!("asset {url}");
+        let too_large = || ExecError::new(ErrorCode::Quota, format!("{what}: larger than SVM_ASSET_MAX_BYTES={}", self.max_bytes));
+        let mut resp = self.http.get(url).send().await?;
+        if !resp.status().is_success() {
+            return Err(ExecError::http(what, resp.status()));
+        }
+        if resp.content_length().is_some_and(|len| len > self.max_bytes) {
+            return Err(too_large().into());
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = resp.chunk().await? {
+            if (body.len() + chunk.len()) as u64 > self.max_bytes {
+                return Err(too_large().into());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body.into())
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::classify;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    #[tokio::test]
+    async fn test_distribution_and_cache() {
+        let table = b"code,label\n1,ok\n".to_vec();
+        let sha256 = hex::encode(Sha256::digest(&table));
+        let publisher = SigningKey::from_bytes(&[5u8; 32]);
+        let signed = |name: &str, kind: &str| format!("eyeflow-asset:v1\n{name}\n{kind}\nsha256:{sha256}");
+        let sign = |key: &SigningKey| B64.encode(key.sign(signed("codes", "table").as_bytes()).to_bytes());
+        let dir = std::env::temp_dir().join(format!("eyeflow-assets-{}", std::process::id()));
+        let store = AssetStore::open(dir.clone(), Some(publisher.verifying_key()), 1024, reqwest::Client::new());
+        let frame = |signature: Option<String>| json!({
+            "name": "codes", "kind": "table", "sha256": format!("sha256:{}", sha256.to_uppercase()),
+            "signature": signature, "base64": B64.encode(&table),
+        });
+
+        for bad in [frame(None), frame(Some(sign(&SigningKey::from_bytes(&[6u8; 32]))))] {
+            assert_eq!(classify(&store.receive(&bad).await.unwrap_err()), ErrorCode::Auth);
+        }
+        // The signature binds the name and kind: it does not carry over to another
+        let mut renamed = frame(Some(sign(&publisher)));
+        renamed["name"] = json!("other-codes");
+        assert_eq!(classify(&store.receive(&renamed).await.unwrap_err()), ErrorCode::Auth);
+        let mut rekinded = frame(Some(sign(&publisher)));
+        rekinded["kind"] = json!("wasm");
+        assert_eq!(classify(&store.receive(&rekinded).await.unwrap_err()), ErrorCode::Auth);
+        let mut wrong_bytes = frame(Some(sign(&publisher)));
+        wrong_bytes["base64"] = json!(B64.encode(b"other"));
+        assert_eq!(classify(&store.receive(&wrong_bytes).await.unwrap_err()), ErrorCode::Validation);
+
+        let refs = [AssetRef { name: "codes".into(), sha256: sha256.clone(), kind: "table".into() }];
+        assert_eq!(classify(&store.precheck(&refs).unwrap_err()), ErrorCode::NotFound);
+        store.receive(&frame(Some(sign(&publisher)))).await.unwrap();
+        store.precheck(&refs).unwrap();
+        assert_eq!(store.get(&sha256).await.unwrap(), Bytes::from(table.clone()));
+
+        // Survives a restart; a tampered file is dropped
+        let reopened = AssetStore::open(dir.clone(), Some(publisher.verifying_key()), 1024, reqwest::Client::new());
+        assert_eq!(reopened.cached(), vec![json!({ "name": "codes", "kind": "table", "sha256": sha256 })]);
+        std::fs::write(dir.join(&sha256), b"tampered").unwrap();
+        assert_eq!(classify(&reopened.get(&sha256).await.unwrap_err()), ErrorCode::NotFound);
+        assert!(!reopened.contains(&sha256));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
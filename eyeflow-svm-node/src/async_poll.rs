@@ -0,0 +1,289 @@
+//! Long-running operations — 202 Accepted + status polling
+//!
+//! Many actuator and ERP APIs accept a request with 202 and a status URL
+//! instead of the result.  When the instruction's dispatch_metadata carries
+//! `async_polling`, CALL_SERVICE / CALL_ACTION follow such an answer up and
+//! resolve the register only when the operation completes:
+//!
+//!   status_url_template   where to poll: {{location}} is the 202's Location
+//!                         header (relative URLs resolve against the
+//!                         endpoint), {{body.<path>}} a field of its body;
+//!                         default "{{location}}"
+//!   interval_ms           between polls (default 2000; Retry-After wins)
+//!   max_duration_ms       TIMEOUT once exceeded (default 300000)
+//!   done_path             status field holding the state; the operation is
+//!                         done when it is in done_values, failed (UPSTREAM)
+//!                         when in failed_values.  Without done_path, any
+//!                         2xx other than 202 completes it.
+//!   result_path           field of the final body loaded into dest
+//!   progress_path         field recorded with each poll
+//!
+//! Transient poll failures (network, 5xx, 429) are retried until the
+//! deadline.  Every poll — attempt, elapsed ms, HTTP status, state and
+//! progress — is attached to the instruction's audit event as
+//! `details.polling`.
+
+use anyhow::Result;
+use reqwest::header::{HeaderMap, LOCATION, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::errors::{ErrorCode, ExecError};
+use crate::fastjson;
+use crate::proto::llmir::AsyncPolling;
+use crate::svm::extract_dot_path;
+
+const DEFAULT_INTERVAL_MS: u64 = 2_000;
+const DEFAULT_MAX_DURATION_MS: u64 = 300_000;
+
+tokio::task_local! {
+    /// Polls made by the dispatch being audited.
+    static POLLS: Arc<Mutex<Vec<Value>>>;
+}
+
+/// Run `dispatch`, returning its output and the polls it made (None when it
+/// did not poll).
+pub async fn tracked<F: Future>(dispatch: F) -> (F::Output, Option<Value>) {
+    let polls = Arc::new(Mutex::new(Vec::new()));
+    let out = POLLS.scope(polls.clone(), dispatch).await;
+    let polls = std::mem::take(&mut *polls.lock().unwrap_or_else(|e| e.into_inner()));
+    (out, (!polls.is_empty()).then_some(Value::Array(polls)))
+}
+
+fn record(poll: Value) {
+    let _ = POLLS.try_with(|p| p.lock().unwrap_or_else(|e| e.into_inner()).push(poll));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Pending,
+    Done,
+    Failed,
+}
+
+/// State reported by a 2xx status answer.
+fn state(cfg: &AsyncPolling, status: StatusCode, body: &Value) -> (State, Value) {
+    if status == StatusCode::ACCEPTED {
+        let reported = if cfg.done_path.is_empty() { Value::Null } else { extract_dot_path(body, &cfg.done_path) };
+        return (State::Pending, reported);
+    }
+    if cfg.done_path.is_empty() {
+        return (State::Done, Value::Null);
+    }
+    let reported = extract_dot_path(body, &cfg.done_path);
+    let text = match &reported {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let state = if cfg.done_values.iter().any(|v| v.eq_ignore_ascii_case(&text)) {
+        State::Done
+    } else if cfg.failed_values.iter().any(|v| v.eq_ignore_ascii_case(&text)) {
+        State::Failed
+    } else {
+        State::Pending
+    };
+    (state, reported)
+}
+
+/// Expand the status URL template against the 202 answer.
+fn status_url(template: &str, location: Option<&str>, body: &Value, endpoint: &str) -> Result<String> {
+    let template = if template.is_empty() { "{{location}}" } else { template };
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..].find("}}")
+            .ok_or_else(|| ExecError::validation(format!("unterminated placeholder in status_url_template {template}")))?;
+        out.push_str(&rest[..start]);
+        let name = rest[start + 2..start + end].trim();
+        let value = match name {
+            "location" => location
+                .ok_or_else(|| ExecError::new(ErrorCode::Upstream, format!("{endpoint} answered 202 without a Location header")))?
+                .to_owned(),
+            _ => match name.strip_prefix("body.").map(|path| extract_dot_path(body, path)) {
+                Some(Value::String(s)) => s,
+                Some(Value::Null) | None => {
+                    return Err(ExecError::new(ErrorCode::Upstream, format!("202 answer from {endpoint} has no {name}")).into());
+                }
+                Some(other) => other.to_string(),
+            },
+        };
+        out.push_str(&value);
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    // Relative Location headers / templates resolve against the endpoint
+    let base = reqwest::Url::parse(endpoint).map_err(|e| ExecError::validation(format!("{endpoint}: {e}")))?;
+    Ok(base.join(&out).map_err(|e| ExecError::validation(format!("status URL {out}: {e}")))?.to_string())
+}
+
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers.get(RETRY_AFTER)?.to_str().ok()?.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Poll the operation `accepted` (a 202 answer from `endpoint`) until it
+/// completes, and return its result.  `headers` are sent with every poll.
+pub async fn follow(
+    http: &reqwest::Client,
+    cfg: &AsyncPolling,
+    endpoint: &str,
+    headers: &HashMap<String, String>,
+    accepted: reqwest::Response,
+) -> Result<Value> {
+    let interval = Duration::from_millis(if cfg.interval_ms > 0 { cfg.interval_ms as u64 } else { DEFAULT_INTERVAL_MS });
+    let max = Duration::from_millis(if cfg.max_duration_ms > 0 { cfg.max_duration_ms as u64 } else { DEFAULT_MAX_DURATION_MS });
+    let location = accepted.headers().get(LOCATION).and_then(|v| v.to_str().ok()).map(str::to_owned);
+    let mut wait = retry_after(accepted.headers()).unwrap_or(interval);
+    let body: Value = accepted.json().await.unwrap_or(Value::Null);
+    let url = status_url(&cfg.status_url_template, location.as_deref(), &body, endpoint)?;
+    debug!("[AsyncPoll] {endpoint} accepted — polling {url}");
+
+    let started = Instant::now();
+    for attempt in 1u32.. {
+        if started.elapsed() + wait > max {
+            return Err(ExecError::new(
+                ErrorCode::Timeout,
+                format!("{url} still running after {} ms", started.elapsed().as_millis()),
+            ).into());
+        }
+        tokio::time::sleep(wait).await;
+        wait = interval;
+
+        let mut req = http.get(&url);
+        for (k, v) in headers {
+            req = req.header(k, v);
+        }
+        let resp = match req.send().await {
+            Ok(r) if r.status().is_success() => r,
+            Ok(r) => {
+                let err = ExecError::http(format!("polling {url}"), r.status());
+                if !crate::errors::classify(&err).is_transient() {
+                    return Err(err);
+                }
+                wait = retry_after(r.headers()).unwrap_or(interval);
+                warn!("[AsyncPoll] {err:#} — retrying");
+                record(json!({ "attempt": attempt, "elapsedMs": started.elapsed().as_millis() as u64, "status": r.status().as_u16(), "state": "error" }));
+                continue;
+            }
+            Err(e) => {
+                warn!("[AsyncPoll] polling {url}: {e} — retrying");
+                record(json!({ "attempt": attempt, "elapsedMs": started.elapsed().as_millis() as u64, "state": "error" }));
+                continue;
+            }
+        };
+        let status = resp.status();
+        if let Some(d) = retry_after(resp.headers()) {
+            wait = d;
+        }
+        let body = fastjson::response_body(resp).await;
+        let (state, reported) = state(cfg, status, &body);
+        let mut poll = json!({
+            "attempt": attempt,
+            "elapsedMs": started.elapsed().as_millis() as u64,
+            "status": status.as_u16(),
+            "state": match state { State::Pending => "pending", State::Done => "done", State::Failed => "failed" },
+        });
+        if !reported.is_null() {
+            poll["reported"] = reported.clone();
+        }
+        if !cfg.progress_path.is_empty() {
+            poll["progress"] = extract_dot_path(&body, &cfg.progress_path);
+        }
+        record(poll);
+
+        match state {
+            State::Pending => {}
+            State::Done if cfg.result_path.is_empty() => return Ok(body),
+            State::Done => return Ok(extract_dot_path(&body, &cfg.result_path)),
+            State::Failed => {
+                return Err(ExecError::new(ErrorCode::Upstream, format!("{url} reported {reported}")).into());
+            }
+        }
+    }
+    unreachable!("the attempt counter is unbounded")
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_status_url_and_state() {
+        let body = json!({ "job": { "id": 42 } });
+        assert_eq!(
+            status_url("", Some("/jobs/7"), &body, "http://erp:8080/api/orders").unwrap(),
+            "http://erp:8080/jobs/7",
+        );
+        assert_eq!(
+            status_url("https://erp/jobs/{{body.job.id}}/status", None, &body, "http://erp/api").unwrap(),
+            "https://erp/jobs/42/status",
+        );
+        assert!(status_url("{{location}}", None, &body, "http://erp/api").is_err());
+
+        let cfg = AsyncPolling {
+            done_path: "status".into(),
+            done_values: vec!["succeeded".into()],
+            failed_values: vec!["failed".into()],
+            ..Default::default()
+        };
+        assert_eq!(state(&cfg, StatusCode::OK, &json!({ "status": "Running" })).0, State::Pending);
+        assert_eq!(state(&cfg, StatusCode::OK, &json!({ "status": "SUCCEEDED" })).0, State::Done);
+        assert_eq!(state(&cfg, StatusCode::OK, &json!({ "status": "failed" })).0, State::Failed);
+        assert_eq!(state(&AsyncPolling::default(), StatusCode::ACCEPTED, &json!({})).0, State::Pending);
+        assert_eq!(state(&AsyncPolling::default(), StatusCode::OK, &json!({})).0, State::Done);
+    }
+
+    #[tokio::test]
+    async fn test_polls_until_done_and_records_progress() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let answers = [
+                ("202 Accepted\r\nLocation: /ops/1", "{}"),
+                ("200 OK", r#"{"state":"running","percent":50}"#),
+                ("200 OK", r#"{"state":"done","percent":100,"output":{"moved":true}}"#),
+            ];
+            for (head, body) in answers {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 2048];
+                let _ = sock.read(&mut buf).await.unwrap();
+                let answer = format!(
+                    "HTTP/1.1 {head}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len(),
+                );
+                sock.write_all(answer.as_bytes()).await.unwrap();
+            }
+        });
+
+        let http = reqwest::Client::new();
+        let endpoint = format!("http://{addr}/ops");
+        let cfg = AsyncPolling {
+            interval_ms: 10,
+            done_path: "state".into(),
+            done_values: vec!["done".into()],
+            result_path: "output".into(),
+            progress_path: "percent".into(),
+            ..Default::default()
+        };
+        let (out, polls) = tracked(async {
+            let accepted = http.post(&endpoint).send().await?;
+            assert_eq!(accepted.status(), StatusCode::ACCEPTED);
+            follow(&http, &cfg, &endpoint, &HashMap::new(), accepted).await
+        }).await;
+        assert_eq!(out.unwrap(), json!({ "moved": true }));
+        let polls = polls.unwrap();
+        assert_eq!(polls.as_array().unwrap().len(), 2);
+        assert_eq!(polls[0]["state"], "pending");
+        assert_eq!(polls[0]["progress"], 50);
+        assert_eq!(polls[1]["state"], "done");
+        assert_eq!(polls[1]["reported"], "done");
+    }
+}
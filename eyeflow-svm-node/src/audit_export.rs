@@ -0,0 +1,405 @@
+//! Audit export — the audit chain in formats auditors can take away
+//!
+//! Audit events leave the node with their slice result (or through the
+//! offline buffer), so the node keeps its own append-only copy:
+//!
+//!   SVM_AUDIT_LOG_PATH   NDJSON of every audit event shipped (unset = off;
+//!                        rotate with copytruncate — the file is reopened
+//!                        for every append)
+//!
+//! which renders as
+//!
+//!   ndjson   one event per line, as logged
+//!   csv      a header row, then one row per event (details as JSON)
+//!   bundle   { format, nodeId, createdAt, publicKeyHex, keyFingerprint,
+//!              filter, eventCount, merkleRoot, signature, events }
+//!            merkleRoot: RFC 6962 Merkle tree hash over the events'
+//!            selfHash bytes; signature: Ed25519 by the node key over
+//!            `eyeflow-audit-bundle:v1:{nodeId}:{eventCount}:{merkleRoot}`.
+//!            `eyeflow-svm-node verify` checks the events of a bundle.
+//!
+//! from the command line (reads SVM_AUDIT_LOG_PATH unless --input is given;
+//! any layout `verify` reads is accepted):
+//!
+//!   eyeflow-svm-node audit export [--format ndjson|csv|bundle] [--input <file>]
+//!       [--workflow <id>] [--since <RFC 3339>] [--until <RFC 3339>] [--output <file>]
+//!
+//! or from the health server:
+//!
+//!   GET /audit/export?format=…&workflow_id=…&since=…&until=…
+//!   Authorization: Bearer <SVM_HEALTH_API_TOKEN>
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, FixedOffset};
+use ed25519_dalek::{Signer, SigningKey};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+
+use crate::audit::AuditEvent;
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+
+const USAGE: &str = "usage: eyeflow-svm-node audit export [--format ndjson|csv|bundle] [--input <file>] \
+    [--workflow <id>] [--since <RFC 3339>] [--until <RFC 3339>] [--output <file>]";
+
+pub const BUNDLE_FORMAT: &str = "eyeflow-audit-bundle/v1";
+
+const CSV_COLUMNS: [&str; 18] = [
+    "eventId", "timestamp", "nodeId", "tenantId", "workflowId", "workflowVersion",
+    "instructionId", "eventType", "durationMs", "inputHash", "outputHash",
+    "previousEventHash", "selfHash", "signature", "publicKeyHex", "parentSlice",
+    "clockOffsetMs", "details",
+];
+
+// ── Selection ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Ndjson,
+    Csv,
+    Bundle,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            "csv" => Ok(Self::Csv),
+            "bundle" => Ok(Self::Bundle),
+            other => Err(ExecError::validation(format!("unknown export format '{other}' (ndjson, csv, bundle)"))),
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Ndjson => "application/x-ndjson",
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Bundle => "application/json",
+        }
+    }
+}
+
+/// Events to export (all by default).
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub workflow_id: Option<String>,
+    pub since: Option<DateTime<FixedOffset>>,
+    pub until: Option<DateTime<FixedOffset>>,
+}
+
+impl Filter {
+    /// Parse a `since` / `until` bound.
+    pub fn time(name: &str, value: &str) -> Result<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(value)
+            .map_err(|e| ExecError::validation(format!("{name} must be an RFC 3339 timestamp: {e}")))
+    }
+
+    fn matches(&self, ev: &AuditEvent) -> bool {
+        if self.workflow_id.as_deref().is_some_and(|w| w != ev.workflow_id) {
+            return false;
+        }
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+        let Ok(at) = DateTime::parse_from_rfc3339(&ev.timestamp) else {
+            return false;
+        };
+        self.since.is_none_or(|s| at >= s) && self.until.is_none_or(|u| at < u)
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "workflowId": self.workflow_id,
+            "since": self.since.map(|t| t.to_rfc3339()),
+            "until": self.until.map(|t| t.to_rfc3339()),
+        })
+    }
+}
+
+// ── Formats ───────────────────────────────────────────────────────────────────
+
+pub fn ndjson(events: &[AuditEvent]) -> Result<String> {
+    let mut out = String::new();
+    for ev in events {
+        out.push_str(&serde_json::to_string(ev)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+pub fn csv(events: &[AuditEvent]) -> String {
+    let mut out = CSV_COLUMNS.join(",");
+    out.push_str("\r\n");
+    for ev in events {
+        let opt = |v: Option<String>| v.unwrap_or_default();
+        let row = [
+            ev.event_id.clone(),
+            ev.timestamp.clone(),
+            ev.node_id.clone(),
+            opt(ev.tenant_id.clone()),
+            ev.workflow_id.clone(),
+            opt(ev.workflow_version.map(|v| v.to_string())),
+            opt(ev.instruction_id.clone()),
+            ev.event_type.clone(),
+            ev.duration_ms.to_string(),
+            ev.input_hash.clone(),
+            ev.output_hash.clone(),
+            ev.previous_event_hash.clone(),
+            ev.self_hash.clone(),
+            ev.signature.clone(),
+            ev.public_key_hex.clone(),
+            opt(ev.parent_slice.clone()),
+            opt(ev.clock_offset_ms.map(|v| v.to_string())),
+            opt(ev.details.as_ref().map(Value::to_string)),
+        ];
+        out.push_str(&row.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// RFC 6962 Merkle tree hash of `leaves`.
+fn merkle_hash(leaves: &[Vec<u8>]) -> [u8; 32] {
+    match leaves {
+        [] => Sha256::digest([]).into(),
+        [leaf] => Sha256::new().chain_update([0x00]).chain_update(leaf).finalize().into(),
+        _ => {
+            // Largest power of two below the leaf count
+            let split = 1 << (leaves.len() - 1).ilog2();
+            Sha256::new()
+                .chain_update([0x01])
+                .chain_update(merkle_hash(&leaves[..split]))
+                .chain_update(merkle_hash(&leaves[split..]))
+                .finalize()
+                .into()
+        }
+    }
+}
+
+/// Merkle root (hex) over the events' selfHash.
+pub fn merkle_root(events: &[AuditEvent]) -> String {
+    let leaves: Vec<Vec<u8>> = events.iter()
+        .map(|ev| hex::decode(&ev.self_hash).unwrap_or_else(|_| ev.self_hash.as_bytes().to_vec()))
+        .collect();
+    hex::encode(merkle_hash(&leaves))
+}
+
+/// Message the node signs for a bundle.
+pub fn bundle_message(node_id: &str, event_count: usize, merkle_root: &str) -> String {
+    format!("eyeflow-audit-bundle:v1:{node_id}:{event_count}:{merkle_root}")
+}
+
+pub fn bundle(events: Vec<AuditEvent>, node_id: &str, key: &SigningKey, filter: &Filter) -> Value {
+    let root = merkle_root(&events);
+    let signature = key.sign(bundle_message(node_id, events.len(), &root).as_bytes());
+    json!({
+        "format": BUNDLE_FORMAT,
+        "nodeId": node_id,
+        "createdAt": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        "publicKeyHex": hex::encode(key.verifying_key().as_bytes()),
+        "keyFingerprint": hex::encode(Sha256::digest(key.verifying_key().as_bytes())),
+        "filter": filter.to_json(),
+        "eventCount": events.len(),
+        "merkleRoot": root,
+        "signature": hex::encode(signature.to_bytes()),
+        "events": events,
+    })
+}
+
+// ── Audit log ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    node_id: String,
+    /// Bundle signer (None = bundles unavailable)
+    key: Option<SigningKey>,
+    /// Serialises appends
+    write: tokio::sync::Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn new(path: impl Into<PathBuf>, node_id: impl Into<String>, key: Option<SigningKey>) -> Self {
+        Self { path: path.into(), node_id: node_id.into(), key, write: tokio::sync::Mutex::new(()) }
+    }
+
+    /// The node's log (SVM_AUDIT_LOG_PATH), bundles signed with `key`.
+    pub fn from_config(config: &Config, key: &SigningKey) -> Option<Arc<Self>> {
+        let path = config.audit_log_path.as_ref()?;
+        Some(Arc::new(Self::new(path, config.node_id.clone(), Some(key.clone()))))
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    pub async fn append(&self, events: &[AuditEvent]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let lines = ndjson(events)?;
+        let _write = self.write.lock().await;
+        let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(&self.path).await?;
+        file.write_all(lines.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Check the log with the `verify` checks (integrity.rs); signatures
+    /// against each event's own key, as delegated events carry a peer's.
+    pub async fn check(&self) -> Result<crate::integrity::Report> {
+        let _write = self.write.lock().await;
+        let text = match tokio::fs::read_to_string(&self.path).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(anyhow!("reading {}: {e}", self.path.display())),
+        };
+        Ok(crate::integrity::check(&text, &[]))
+    }
+
+    /// Cut the log before `line` (1-based): that line and the rest move to
+    /// `quarantine`, the log keeps what comes before.  Returns the number
+    /// of lines moved.
+    pub async fn quarantine_from(&self, line: usize, quarantine: &std::path::Path) -> Result<usize> {
+        let _write = self.write.lock().await;
+        let text = tokio::fs::read_to_string(&self.path).await
+            .with_context(|| format!("reading {}", self.path.display()))?;
+        let lines: Vec<&str> = text.lines().collect();
+        let cut = line.saturating_sub(1).min(lines.len());
+        let moved = &lines[cut..];
+        let mut rest = moved.join("\n");
+        rest.push('\n');
+        tokio::fs::write(quarantine, rest).await
+            .with_context(|| format!("writing {}", quarantine.display()))?;
+        let mut kept = lines[..cut].join("\n");
+        if cut > 0 {
+            kept.push('\n');
+        }
+        let tmp = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp, kept).await?;
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(moved.len())
+    }
+
+    /// Render the logged events selected by `filter`.
+    pub async fn export(&self, format: Format, filter: &Filter) -> Result<String> {
+        let text = match tokio::fs::read_to_string(&self.path).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(anyhow!("reading {}: {e}", self.path.display())),
+        };
+        let events: Vec<AuditEvent> = crate::integrity::audit_events(&text)
+            .with_context(|| self.path.display().to_string())?
+            .into_iter()
+            .filter(|ev| filter.matches(ev))
+            .collect();
+        match format {
+            Format::Ndjson => ndjson(&events),
+            Format::Csv => Ok(csv(&events)),
+            Format::Bundle => {
+                let key = self.key.as_ref().ok_or_else(|| ExecError::new(
+                    ErrorCode::Unsupported,
+                    "signed bundles need the node key (SVM_SIGNING_PRIVATE_KEY_PEM)",
+                ))?;
+                Ok(serde_json::to_string_pretty(&bundle(events, &self.node_id, key, filter))?)
+            }
+        }
+    }
+}
+
+// ── CLI ───────────────────────────────────────────────────────────────────────
+
+pub async fn run(args: &[String]) -> Result<()> {
+    if args.first().map(String::as_str) != Some("export") {
+        return Err(anyhow!(USAGE));
+    }
+    let _ = dotenvy::dotenv();
+    let config = Config::from_env();
+
+    let mut format = Format::Ndjson;
+    let mut input = config.audit_log_path.clone();
+    let mut output = None;
+    let mut filter = Filter::default();
+    let mut it = args[1..].iter();
+    while let Some(arg) = it.next() {
+        let mut value = || it.next().cloned().ok_or_else(|| anyhow!("{arg} needs a value\n{USAGE}"));
+        match arg.as_str() {
+            "--format" => format = Format::parse(&value()?)?,
+            "--input" => input = Some(value()?),
+            "--output" => output = Some(value()?),
+            "--workflow" => filter.workflow_id = Some(value()?),
+            "--since" => filter.since = Some(Filter::time("--since", &value()?)?),
+            "--until" => filter.until = Some(Filter::time("--until", &value()?)?),
+            other => return Err(anyhow!("unknown argument {other}\n{USAGE}")),
+        }
+    }
+    let input = input.ok_or_else(|| anyhow!("no audit log: set SVM_AUDIT_LOG_PATH or pass --input\n{USAGE}"))?;
+    let key = config.signing_private_key_pem.as_deref()
+        .map(crate::audit::signing_key_from_pkcs8_pem)
+        .transpose()?;
+
+    let rendered = AuditLog::new(input, config.node_id, key).export(format, &filter).await?;
+    match output {
+        Some(path) => std::fs::write(&path, rendered).with_context(|| format!("writing {path}"))?,
+        None => print!("{rendered}"),
+    }
+    Ok(())
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditChain;
+
+    #[tokio::test]
+    async fn test_export_formats() {
+        let mut chain = AuditChain::new("node-1".into(), None).unwrap();
+        for (wf, details) in [("wf-a", None), ("wf-b", Some(json!({ "note": "a, \"quoted\" value" }))), ("wf-a", None)] {
+            chain.append(wf, Some(1), Some("i0"), "INSTRUCTION_EXECUTED", None, None, 3, details);
+        }
+        let events = chain.drain();
+        let path = std::env::temp_dir().join(format!("eyeflow_audit_{}.ndjson", uuid::Uuid::new_v4()));
+        let log = AuditLog::new(&path, "node-1", Some(chain.signing_key().clone()));
+        log.append(&events[..1]).await.unwrap();
+        log.append(&events[1..]).await.unwrap();
+
+        let all = log.export(Format::Ndjson, &Filter::default()).await.unwrap();
+        assert_eq!(crate::integrity::check(&all, &[]).findings.len(), 0);
+        let only_a = Filter { workflow_id: Some("wf-a".into()), ..Default::default() };
+        assert_eq!(log.export(Format::Ndjson, &only_a).await.unwrap().lines().count(), 2);
+        let future = Filter { since: Some(Filter::time("since", "2999-01-01T00:00:00Z").unwrap()), ..Default::default() };
+        assert_eq!(log.export(Format::Ndjson, &future).await.unwrap(), "");
+
+        let csv = log.export(Format::Csv, &Filter::default()).await.unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.contains(r#""{""note"":""a, \""quoted\"" value""}""#));
+
+        let bundle: Value = serde_json::from_str(&log.export(Format::Bundle, &Filter::default()).await.unwrap()).unwrap();
+        assert_eq!(bundle["eventCount"], 3);
+        assert_eq!(bundle["merkleRoot"], merkle_root(&events));
+        let message = bundle_message("node-1", 3, bundle["merkleRoot"].as_str().unwrap());
+        let signature = ed25519_dalek::Signature::from_slice(&hex::decode(bundle["signature"].as_str().unwrap()).unwrap()).unwrap();
+        assert!(chain.signing_key().verifying_key().verify_strict(message.as_bytes(), &signature).is_ok());
+        assert_eq!(crate::integrity::check(&bundle.to_string(), &[chain.public_key_hex().to_owned()]).findings.len(), 0);
+        let _ = std::fs::remove_file(&path);
+
+        // RFC 6962: three leaves split 2 + 1
+        let leaf = |b: &[u8]| -> [u8; 32] { Sha256::new().chain_update([0x00]).chain_update(b).finalize().into() };
+        let node = |l: [u8; 32], r: [u8; 32]| -> [u8; 32] { Sha256::new().chain_update([0x01]).chain_update(l).chain_update(r).finalize().into() };
+        let leaves = [vec![1u8], vec![2], vec![3]];
+        assert_eq!(merkle_hash(&leaves), node(node(leaf(&[1]), leaf(&[2])), leaf(&[3])));
+    }
+}
@@ -0,0 +1,217 @@
+//! Audit verbosity — per event-type sampling and roll-up
+//!
+//! High-frequency workflows can append thousands of events a minute.  A
+//! policy maps event types to a rule:
+//!
+//!   all        every event is chained (the default)
+//!   sample:N   the first event and every Nth after it, per workflow
+//!   rollup     none individually — only counted
+//!
+//!   { "LOAD_RESOURCE": "sample:100", "CALL_SERVICE": "rollup", "*": "all" }
+//!
+//! set by CONFIG_UPDATE (`payload.auditPolicy`, null clears it) or at start
+//! with SVM_AUDIT_POLICY (same JSON).  `*` is the rule of unlisted types.
+//!
+//! Events left out never enter the chain, so linkage is untouched; what they
+//! were is accounted for by an AUDIT_SUMMARY event per (workflow, event
+//! type) appended when the slice's events are drained:
+//!
+//!   details { eventType, rule, omitted, durationMsTotal, durationMsMax,
+//!             firstAt, lastAt, omittedDigest }
+//!
+//! omittedDigest is SHA-256 over the omitted events' inputHash ‖ outputHash,
+//! in order, so the summary still commits to what it stands for.
+//!
+//! Failure and security events (SLICE_FAILED, TRY_CAUGHT,
+//! GUARDRAIL_VIOLATION, CONFIG_UPDATE_REJECTED, SECRET_ACCESS) are always
+//! chained.
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::config::Config;
+
+/// Event type of the roll-up events.
+pub const SUMMARY_EVENT: &str = "AUDIT_SUMMARY";
+
+/// Never sampled nor rolled up.
+const ALWAYS_KEPT: [&str; 6] = [
+    "SLICE_FAILED", "TRY_CAUGHT", "GUARDRAIL_VIOLATION", "CONFIG_UPDATE_REJECTED", "SECRET_ACCESS", SUMMARY_EVENT,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    All,
+    Sample(u64),
+    Rollup,
+}
+
+impl Rule {
+    fn parse(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "all" => Ok(Self::All),
+            "rollup" => Ok(Self::Rollup),
+            other => match other.strip_prefix("sample:").map(str::parse::<u64>) {
+                Some(Ok(n)) if n >= 1 => Ok(if n == 1 { Self::All } else { Self::Sample(n) }),
+                _ => Err(anyhow!("audit policy: unknown rule '{s}' (all, sample:N, rollup)")),
+            },
+        }
+    }
+
+    /// Whether the `seen`-th event (1-based) of a workflow is chained.
+    pub fn keeps(self, seen: u64) -> bool {
+        match self {
+            Self::All => true,
+            Self::Sample(n) => (seen - 1).is_multiple_of(n),
+            Self::Rollup => false,
+        }
+    }
+
+    fn label(self) -> String {
+        match self {
+            Self::All => "all".into(),
+            Self::Sample(n) => format!("sample:{n}"),
+            Self::Rollup => "rollup".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditPolicy {
+    rules: HashMap<String, Rule>,
+    default: Rule,
+}
+
+impl AuditPolicy {
+    /// Parse a policy object (None for null / an empty object).
+    pub fn parse(value: &Value) -> Result<Option<Self>> {
+        let map = match value {
+            Value::Null => return Ok(None),
+            Value::Object(map) => map,
+            other => return Err(anyhow!("audit policy must be an object, got {other}")),
+        };
+        let mut policy = Self { rules: HashMap::new(), default: Rule::All };
+        for (event_type, rule) in map {
+            let rule = Rule::parse(rule.as_str().ok_or_else(|| anyhow!("audit policy: rule of {event_type} must be a string"))?)?;
+            if event_type == "*" {
+                policy.default = rule;
+            } else if !ALWAYS_KEPT.contains(&event_type.as_str()) {
+                policy.rules.insert(event_type.clone(), rule);
+            }
+        }
+        Ok((policy.default != Rule::All || policy.rules.values().any(|r| *r != Rule::All)).then_some(policy))
+    }
+
+    /// SVM_AUDIT_POLICY (None when unset).
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        match &config.audit_policy {
+            Some(text) => Self::parse(&serde_json::from_str(text).map_err(|e| anyhow!("SVM_AUDIT_POLICY: {e}"))?),
+            None => Ok(None),
+        }
+    }
+
+    pub fn rule(&self, event_type: &str) -> Rule {
+        if ALWAYS_KEPT.contains(&event_type) {
+            return Rule::All;
+        }
+        self.rules.get(event_type).copied().unwrap_or(self.default)
+    }
+}
+
+// ── Omitted events ────────────────────────────────────────────────────────────
+
+/// What an AUDIT_SUMMARY stands for.
+#[derive(Debug, Clone)]
+pub struct Omitted {
+    rule: Rule,
+    count: u64,
+    duration_ms_total: u64,
+    duration_ms_max: u64,
+    first_at: String,
+    last_at: String,
+    digest: Sha256,
+}
+
+impl Omitted {
+    pub fn new(rule: Rule) -> Self {
+        Self {
+            rule,
+            count: 0,
+            duration_ms_total: 0,
+            duration_ms_max: 0,
+            first_at: String::new(),
+            last_at: String::new(),
+            digest: Sha256::new(),
+        }
+    }
+
+    pub fn add(&mut self, at: &str, duration_ms: u64, input_hash: &str, output_hash: &str) {
+        if self.count == 0 {
+            self.first_at = at.to_owned();
+        }
+        self.count += 1;
+        self.last_at = at.to_owned();
+        self.duration_ms_total += duration_ms;
+        self.duration_ms_max = self.duration_ms_max.max(duration_ms);
+        self.digest.update(input_hash.as_bytes());
+        self.digest.update(output_hash.as_bytes());
+    }
+
+    pub fn duration_ms_total(&self) -> u64 {
+        self.duration_ms_total
+    }
+
+    /// AUDIT_SUMMARY details.
+    pub fn details(self, event_type: &str) -> Value {
+        json!({
+            "eventType": event_type,
+            "rule": self.rule.label(),
+            "omitted": self.count,
+            "durationMsTotal": self.duration_ms_total,
+            "durationMsMax": self.duration_ms_max,
+            "firstAt": self.first_at,
+            "lastAt": self.last_at,
+            "omittedDigest": hex::encode(self.digest.finalize()),
+        })
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditChain;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_sampling_and_rollup_keep_the_chain_linked() {
+        let policy = AuditPolicy::parse(&json!({
+            "LOAD_RESOURCE": "sample:3", "CALL_SERVICE": "rollup", "SLICE_FAILED": "rollup",
+        })).unwrap();
+        assert!(AuditPolicy::parse(&json!({ "*": "all" })).unwrap().is_none());
+        assert!(AuditPolicy::parse(&json!({ "X": "sample:0" })).is_err());
+
+        let mut chain = AuditChain::new("node-a".into(), None).unwrap();
+        chain.set_policy(policy.map(Arc::new));
+        let kept: Vec<bool> = (0..7)
+            .map(|i| chain.append("wf", Some(1), Some("r"), "LOAD_RESOURCE", None, Some(&json!(i)), 2, None).is_some())
+            .collect();
+        assert_eq!(kept, [true, false, false, true, false, false, true]);
+        assert!(chain.append("wf", Some(1), Some("s"), "CALL_SERVICE", None, None, 5, None).is_none());
+        assert!(chain.append("wf", Some(1), None::<String>, "SLICE_FAILED", None, None, 0, None).is_some());
+
+        let events = chain.drain();
+        let types: Vec<&str> = events.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(types, ["LOAD_RESOURCE", "LOAD_RESOURCE", "LOAD_RESOURCE", "SLICE_FAILED", SUMMARY_EVENT, SUMMARY_EVENT]);
+        let summary = events[4].details.as_ref().unwrap();
+        assert_eq!((summary["eventType"].as_str(), summary["rule"].as_str(), summary["omitted"].as_u64()), (Some("LOAD_RESOURCE"), Some("sample:3"), Some(4)));
+        assert_eq!(events[5].details.as_ref().unwrap()["durationMsTotal"], 5);
+
+        let ndjson = crate::audit_export::ndjson(&events).unwrap();
+        assert!(crate::integrity::check(&ndjson, &[]).findings.is_empty());
+        assert!(chain.drain().is_empty());
+    }
+}
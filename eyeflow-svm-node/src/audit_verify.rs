@@ -0,0 +1,276 @@
+//! Audit self-test — the node re-verifies its own audit trail
+//!
+//! Central only checks audit events once they arrive; a chain tampered with
+//! in memory, or bit rot in the audit log, went unnoticed on the node.  The
+//! self-test runs `AuditChain::verify` (hashes, links, signatures):
+//!
+//!   – before every drain, i.e. before the events leave with a result
+//!   – every SVM_AUDIT_VERIFY_SECS (default 300, 0 = before drains only),
+//!     together with the `verify` checks (integrity.rs) over the audit log
+//!     (SVM_AUDIT_LOG_PATH)
+//!
+//! A corrupted segment is quarantined, kept as evidence but never shipped:
+//!
+//!   chain   the undrained events go to audit-quarantine-<unix ms>.ndjson
+//!           next to the audit log (or the offline buffer)
+//!   log     the log is cut at the first finding; that line and the rest
+//!           move to <log>.quarantine-<unix ms>
+//!
+//! and an AUDIT_CHAIN_CORRUPTED security alert goes out with the next ALERT
+//! frame.  /health carries `audit_chain` { status: UNCHECKED | OK |
+//! CORRUPTED, checkedAt, events, quarantined, detail }; CORRUPTED is also an
+//! auditChain reason (health_score.rs) and stays until restart.
+//!
+//!   eyeflow_audit_verify_runs_total            self-test runs
+//!   eyeflow_audit_chain_corrupted              1 once a corruption was found
+//!   eyeflow_audit_quarantined_events_total     events / log lines quarantined
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+
+use crate::audit::{AuditChain, AuditEvent};
+use crate::audit_export::AuditLog;
+use crate::config::Config;
+use crate::health::{HealthState, MetricsCollector};
+use crate::offline::OfflineBuffer;
+
+/// What /health reports as `audit_chain`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Status {
+    /// "UNCHECKED" | "OK" | "CORRUPTED"
+    pub status: &'static str,
+    pub checked_at: Option<String>,
+    /// Events checked by the last run
+    pub events: usize,
+    /// Events and log lines quarantined since startup
+    pub quarantined: u64,
+    /// First finding (CORRUPTED only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct AuditVerifier {
+    node_id: String,
+    /// Where a quarantined chain segment is written
+    dir: PathBuf,
+    interval: Option<Duration>,
+    status: Mutex<Status>,
+    runs: AtomicU64,
+}
+
+impl AuditVerifier {
+    pub fn new(node_id: impl Into<String>, dir: PathBuf, interval: Option<Duration>) -> Arc<Self> {
+        Arc::new(Self {
+            node_id: node_id.into(),
+            dir,
+            interval,
+            status: Mutex::new(Status {
+                status: "UNCHECKED",
+                checked_at: None,
+                events: 0,
+                quarantined: 0,
+                detail: None,
+            }),
+            runs: AtomicU64::new(0),
+        })
+    }
+
+    pub fn from_config(config: &Config) -> Arc<Self> {
+        let file = config.audit_log_path.as_deref().unwrap_or(&config.offline_buffer_path);
+        let dir = Path::new(file).parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf();
+        let interval = (config.audit_verify_secs > 0).then(|| Duration::from_secs(config.audit_verify_secs));
+        Self::new(config.node_id.clone(), dir, interval)
+    }
+
+    pub fn status(&self) -> Status {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Status> {
+        self.status.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record a clean run over `events` (a CORRUPTED status is kept).
+    fn passed(&self, events: usize) {
+        self.runs.fetch_add(1, Ordering::Relaxed);
+        let mut status = self.lock();
+        if status.status != "CORRUPTED" {
+            status.status = "OK";
+        }
+        status.checked_at = Some(now());
+        status.events = events;
+    }
+
+    /// Record a finding and `quarantined` events set aside; returns the
+    /// AUDIT_CHAIN_CORRUPTED alert.
+    fn failed(&self, source: &str, detail: String, quarantined: usize, path: &Path) -> Value {
+        self.runs.fetch_add(1, Ordering::Relaxed);
+        error!("[AuditVerify] ⛔ {source}: {detail} — {quarantined} entr(ies) quarantined to {}", path.display());
+        let mut status = self.lock();
+        status.status = "CORRUPTED";
+        status.checked_at = Some(now());
+        status.quarantined += quarantined as u64;
+        status.detail = Some(format!("{source}: {detail}"));
+        json!({
+            "type": "AUDIT_CHAIN_CORRUPTED",
+            "nodeId": self.node_id,
+            "source": source,
+            "detail": detail,
+            "quarantined": quarantined,
+            "quarantinePath": path,
+        })
+    }
+
+    /// Verify `chain`; when it fails, drain it into a quarantine file.
+    /// Returns the alert to raise.
+    pub async fn check_chain(&self, chain: &mut AuditChain) -> Option<Value> {
+        match chain.verify() {
+            Ok(n) => {
+                self.passed(n);
+                None
+            }
+            Err(e) => {
+                let events = chain.drain();
+                let path = self.dir.join(format!("audit-quarantine-{}.ndjson", chrono::Utc::now().timestamp_millis()));
+                if let Err(w) = write_events(&path, &events).await {
+                    warn!("[AuditVerify] failed to write {}: {w}", path.display());
+                }
+                Some(self.failed("chain", e.to_string(), events.len(), &path))
+            }
+        }
+    }
+
+    /// Check the audit log; from its first finding on it is quarantined.
+    /// Returns the alert to raise.
+    pub async fn check_log(&self, log: &AuditLog) -> Result<Option<Value>> {
+        let report = log.check().await?;
+        let Some(first) = report.findings.first() else {
+            self.passed(report.audit_events);
+            return Ok(None);
+        };
+        let mut path = log.path().as_os_str().to_owned();
+        path.push(format!(".quarantine-{}", chrono::Utc::now().timestamp_millis()));
+        let path = PathBuf::from(path);
+        let moved = log.quarantine_from(first.line, &path).await?;
+        let detail = format!("line {}: {} ({} finding(s))", first.line, first.message, report.findings.len());
+        Ok(Some(self.failed("log", detail, moved, &path)))
+    }
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+async fn write_events(path: &Path, events: &[AuditEvent]) -> Result<()> {
+    tokio::fs::write(path, crate::audit_export::ndjson(events)?).await?;
+    Ok(())
+}
+
+impl MetricsCollector for AuditVerifier {
+    fn prometheus(&self, node_id: &str) -> String {
+        let status = self.status();
+        format!(
+            "# HELP eyeflow_audit_verify_runs_total Audit chain / log self-test runs\n\
+             # TYPE eyeflow_audit_verify_runs_total counter\n\
+             eyeflow_audit_verify_runs_total{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_audit_chain_corrupted 1 once the self-test found a corrupted audit segment\n\
+             # TYPE eyeflow_audit_chain_corrupted gauge\n\
+             eyeflow_audit_chain_corrupted{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_audit_quarantined_events_total Audit events / log lines quarantined\n\
+             # TYPE eyeflow_audit_quarantined_events_total counter\n\
+             eyeflow_audit_quarantined_events_total{{node_id=\"{node_id}\"}} {}\n",
+            self.runs.load(Ordering::Relaxed),
+            u8::from(status.status == "CORRUPTED"),
+            status.quarantined,
+        )
+    }
+}
+
+// ── Scheduled self-test ───────────────────────────────────────────────────────
+
+/// Verify the in-memory chain and the audit log every interval, publish the
+/// status to /health and queue an alert for every corruption found.
+pub async fn run(
+    verifier: Arc<AuditVerifier>,
+    audit: Arc<tokio::sync::Mutex<AuditChain>>,
+    log: Option<Arc<AuditLog>>,
+    offline: Arc<tokio::sync::Mutex<OfflineBuffer>>,
+    health: Arc<HealthState>,
+    alerts_ready: Arc<Notify>,
+) {
+    let Some(interval) = verifier.interval else { return };
+    info!("[AuditVerify] self-test every {}s", interval.as_secs());
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let mut alerts = Vec::new();
+        alerts.extend(verifier.check_chain(&mut *audit.lock().await).await);
+        if let Some(log) = &log {
+            match verifier.check_log(log).await {
+                Ok(alert) => alerts.extend(alert),
+                Err(e) => warn!("[AuditVerify] audit log {}: {e}", log.path().display()),
+            }
+        }
+        health.set_audit_chain(verifier.status());
+        if !alerts.is_empty() {
+            let mut buf = offline.lock().await;
+            for alert in alerts {
+                buf.enqueue_alert(alert);
+            }
+            drop(buf);
+            alerts_ready.notify_one();
+        }
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("eyeflow-audit-verify-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_log_is_quarantined() {
+        let dir = dir("q");
+        let verifier = AuditVerifier::new("n", dir.clone(), None);
+        assert_eq!(verifier.status().status, "UNCHECKED");
+
+        let mut chain = AuditChain::new("n".into(), None).unwrap();
+        for i in 0..3 {
+            chain.append("wf", None, Some(i.to_string()), "STEP", None, None, 1, None);
+        }
+        assert!(verifier.check_chain(&mut chain).await.is_none());
+        assert_eq!(verifier.status().status, "OK");
+
+        // Log: two clean events, then one whose details rotted on disk
+        let log = AuditLog::new(dir.join("audit.ndjson"), "n", None);
+        log.append(&chain.drain()).await.unwrap();
+        assert!(verifier.check_log(&log).await.unwrap().is_none());
+        let text = std::fs::read_to_string(log.path()).unwrap();
+        std::fs::write(log.path(), text.replacen("\"2\"", "\"7\"", 1)).unwrap();
+        let alert = verifier.check_log(&log).await.unwrap().unwrap();
+        assert_eq!((alert["type"].as_str(), alert["quarantined"].as_u64()), (Some("AUDIT_CHAIN_CORRUPTED"), Some(1)));
+        assert_eq!(std::fs::read_to_string(log.path()).unwrap().lines().count(), 2);
+        assert!(verifier.check_log(&log).await.unwrap().is_none());
+
+        let status = verifier.status();
+        assert_eq!((status.status, status.quarantined), ("CORRUPTED", 1));
+        assert!(verifier.prometheus("n").contains("eyeflow_audit_chain_corrupted{node_id=\"n\"} 1"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,78 @@
+//! Reconnect backoff — exponential with decorrelated jitter
+//!
+//! A fixed reconnect interval makes every node of a fleet retry in lockstep,
+//! so central is stampeded the moment it comes back from an outage.  Network
+//! failures (handshake refused, DNS, TLS, reset before REGISTER) back off as
+//!
+//!   delay = min(max, random(base, previous delay × 3))
+//!
+//! ("decorrelated jitter"), starting from RECONNECT_INTERVAL_SECS and capped
+//! at SVM_RECONNECT_MAX_SECS (default 300).  A session that got as far as
+//! REGISTER proves central is reachable: the backoff resets and the node
+//! retries after a short random delay in [0, base) instead — a clean
+//! rejection or a central restart is recovered from quickly, while the
+//! jitter still spreads the fleet's reconnects.
+
+use rand::Rng;
+use tokio::time::Duration;
+
+#[derive(Debug)]
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    /// Last network-failure delay (`base` after a reset)
+    prev: Duration,
+}
+
+impl Backoff {
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self { base, max: max.max(base), prev: base }
+    }
+
+    /// Delay after a failure to reach central; grows with each call.
+    pub fn after_network_error(&mut self, rng: &mut impl Rng) -> Duration {
+        let lo = self.base.as_millis() as u64;
+        let hi = (self.prev.as_millis() as u64).saturating_mul(3).max(lo);
+        let delay = Duration::from_millis(rng.gen_range(lo..=hi)).min(self.max);
+        self.prev = delay;
+        delay
+    }
+
+    /// Delay after a session that reached REGISTER; resets the backoff.
+    pub fn after_session(&mut self, rng: &mut impl Rng) -> Duration {
+        self.prev = self.base;
+        Duration::from_millis(rng.gen_range(0..self.base.as_millis().max(1) as u64))
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_backoff_grows_caps_and_resets() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let (base, max) = (Duration::from_secs(1), Duration::from_secs(30));
+        let mut b = Backoff::new(base, max);
+
+        let mut prev = base;
+        let mut delays = Vec::new();
+        for _ in 0..40 {
+            let d = b.after_network_error(&mut rng);
+            assert!(d >= base && d <= max, "{d:?} out of range");
+            assert!(d <= prev * 3);
+            prev = d;
+            delays.push(d);
+        }
+        assert!(delays.iter().any(|d| *d >= Duration::from_secs(10)), "never grew: {delays:?}");
+        assert!(delays.windows(2).any(|w| w[0] != w[1]), "no jitter: {delays:?}");
+
+        for _ in 0..20 {
+            assert!(b.after_session(&mut rng) < base);
+        }
+        assert!(b.after_network_error(&mut rng) <= base * 3, "backoff was not reset");
+    }
+}
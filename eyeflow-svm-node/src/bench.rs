@@ -0,0 +1,431 @@
+//! `bench` subcommand — load generation / soak runs for hardware sizing
+//!
+//!   eyeflow-svm-node bench [--ir <artifact>] [--rate <runs/s>] [--duration <secs>]
+//!                          [--concurrency <n>] [--mock-latency-ms <ms>] [--json]
+//!
+//!   --ir               LLM-IR proto or SignedIrArtifact, binary or base64;
+//!                      default: a synthetic CALL_SERVICE → LLM_CALL →
+//!                      CALL_ACTION slice
+//!   --rate             slice executions started per second (default 50)
+//!   --duration         length of the run in seconds (default 30)
+//!   --concurrency      executions in flight at most (default 64); a start
+//!                      finding them all busy is shed and counted
+//!   --mock-latency-ms  answer time of the mock endpoints (default 5)
+//!
+//! Every HTTP(S) endpoint of the IR, and the LLM service, is pointed at a
+//! local mock server answering `{"ok":true}` — path and query are kept,
+//! Vault credentials are dropped.  Other transports (SQL, Kafka, devices)
+//! are left as configured and fail unless reachable.  Executions run on a
+//! scratch catalogue / WAL with full auditing, as on a live node; the node
+//! config (SVM_*) still applies, SVM_CHAOS included.
+//!
+//! Reports throughput, latency percentiles, failures by error code and the
+//! resident set size (Linux) at start, peak and end — a growing RSS over a
+//! long soak points at a leak.
+
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+
+use crate::audit::AuditChain;
+use crate::proto::llmir::{
+    DispatchMetadata, IrInstruction, IrOpcode, LlmIntermediateRepresentation, WorkflowMetadata,
+};
+
+const USAGE: &str = "usage: eyeflow-svm-node bench [--ir <artifact>] [--rate <runs/s>] [--duration <secs>] \
+                     [--concurrency <n>] [--mock-latency-ms <ms>] [--json]";
+
+// ── Inputs ────────────────────────────────────────────────────────────────────
+
+struct Args {
+    ir: Option<PathBuf>,
+    rate: f64,
+    duration: Duration,
+    concurrency: usize,
+    mock_latency: Duration,
+    json: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Args> {
+    let mut parsed = Args {
+        ir: None,
+        rate: 50.0,
+        duration: Duration::from_secs(30),
+        concurrency: 64,
+        mock_latency: Duration::from_millis(5),
+        json: false,
+    };
+    let mut it = args.iter();
+    while let Some(a) = it.next() {
+        let mut number = |name: &str| -> Result<f64> {
+            it.next()
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| *v > 0.0 || (name == "--mock-latency-ms" && *v == 0.0))
+                .ok_or_else(|| anyhow!("{name} needs a positive number\n{USAGE}"))
+        };
+        match a.as_str() {
+            "--ir" => parsed.ir = it.next().map(PathBuf::from),
+            "--rate" => parsed.rate = number("--rate")?,
+            "--duration" => parsed.duration = Duration::from_secs_f64(number("--duration")?),
+            "--concurrency" => parsed.concurrency = number("--concurrency")? as usize,
+            "--mock-latency-ms" => parsed.mock_latency = Duration::from_secs_f64(number("--mock-latency-ms")? / 1000.0),
+            "--json" => parsed.json = true,
+            other => return Err(anyhow!("unknown argument '{other}'\n{USAGE}")),
+        }
+    }
+    parsed.concurrency = parsed.concurrency.max(1);
+    Ok(parsed)
+}
+
+/// CALL_SERVICE (GET) → LLM_CALL → CALL_ACTION, all against `base`.
+fn synthetic_ir(base: &str) -> LlmIntermediateRepresentation {
+    let op = |index, opcode: IrOpcode, dest, src: Vec<i32>, dm: DispatchMetadata| IrInstruction {
+        index, opcode: opcode as i32, dest, src, dispatch_metadata: Some(dm),
+        ..Default::default()
+    };
+    let instrs = vec![
+        op(1, IrOpcode::CallService, 2, vec![], DispatchMetadata {
+            service_id: "bench-sensor".into(), endpoint_url: format!("{base}/sensor"), method: "GET".into(),
+            ..Default::default()
+        }),
+        op(2, IrOpcode::LlmCall, 3, vec![2], DispatchMetadata {
+            service_id: "bench-llm".into(), model: "bench".into(), max_tokens: 64,
+            ..Default::default()
+        }),
+        op(3, IrOpcode::CallAction, 4, vec![3], DispatchMetadata {
+            service_id: "bench-actuator".into(), endpoint_url: format!("{base}/actuator"), method: "POST".into(),
+            ..Default::default()
+        }),
+    ];
+    LlmIntermediateRepresentation {
+        instruction_order: instrs.iter().map(|i| i.index).collect(),
+        instructions: instrs.into_iter().map(|i| (i.index, i)).collect(),
+        output_register: 4,
+        metadata: Some(WorkflowMetadata { id: "bench-synthetic".into(), ..Default::default() }),
+        ..Default::default()
+    }
+}
+
+/// Point every HTTP(S) endpoint of `ir` at `base`, keeping path and query.
+fn redirect(ir: &mut LlmIntermediateRepresentation, base: &str) {
+    for instr in ir.instructions.values_mut() {
+        let Some(dm) = instr.dispatch_metadata.as_mut() else { continue };
+        let Some(rest) = dm.endpoint_url.strip_prefix("http://").or_else(|| dm.endpoint_url.strip_prefix("https://")) else {
+            continue;
+        };
+        let path = rest.find('/').map_or("", |i| &rest[i..]);
+        dm.endpoint_url = format!("{base}{path}");
+        dm.credentials_vault_path.clear();
+    }
+}
+
+// ── Mock endpoints ────────────────────────────────────────────────────────────
+
+/// Answer every request on `listener` with `{"ok":true}` after `latency`.
+async fn serve_mock(listener: TcpListener, latency: Duration) {
+    while let Ok((socket, _)) = listener.accept().await {
+        tokio::spawn(mock_connection(socket, latency));
+    }
+}
+
+/// One keep-alive connection: read a request (headers + Content-Length body), answer, repeat.
+async fn mock_connection(mut socket: TcpStream, latency: Duration) -> std::io::Result<()> {
+    const BODY: &str = r#"{"ok":true}"#;
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let head_end = loop {
+            if let Some(p) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break p + 4;
+            }
+            let n = socket.read(&mut chunk).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        };
+        let head = String::from_utf8_lossy(&buf[..head_end]);
+        let body_len: usize = head.lines()
+            .filter_map(|l| l.split_once(':'))
+            .find(|(k, _)| k.trim().eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, v)| v.trim().parse().ok())
+            .unwrap_or(0);
+        while buf.len() < head_end + body_len {
+            let n = socket.read(&mut chunk).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        }
+        buf.drain(..head_end + body_len);
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{BODY}",
+            BODY.len(),
+        );
+        socket.write_all(response.as_bytes()).await?;
+    }
+}
+
+// ── Measurements ──────────────────────────────────────────────────────────────
+
+/// Latency (ms) of one execution and its error code, if it failed
+type Sample = (f64, Option<String>);
+
+/// Resident set size in KiB (Linux; None elsewhere).
+fn rss_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status.lines()
+        .find_map(|l| l.strip_prefix("VmRSS:"))
+        .and_then(|v| v.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+/// `p`-th percentile (0..=100) of sorted `values`, nearest rank.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Latency {
+    mean: f64,
+    p50: f64,
+    p90: f64,
+    p99: f64,
+    max: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Memory {
+    start_kb: u64,
+    peak_kb: u64,
+    end_kb: u64,
+    growth_kb: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    workflow: String,
+    target_rate: f64,
+    elapsed_secs: f64,
+    runs: u64,
+    ok: u64,
+    failed: u64,
+    /// Starts dropped because `concurrency` executions were already in flight
+    shed: u64,
+    throughput_per_sec: f64,
+    latency_ms: Latency,
+    failures: BTreeMap<String, u64>,
+    memory: Option<Memory>,
+}
+
+impl Report {
+    fn render(&self) -> String {
+        let l = &self.latency_ms;
+        let mut out = format!(
+            "bench {}: {} runs in {:.1}s ({:.1}/s, target {:.1}/s) — {} ok, {} failed, {} shed\n\
+             latency ms: mean {:.1}  p50 {:.1}  p90 {:.1}  p99 {:.1}  max {:.1}\n",
+            self.workflow, self.runs, self.elapsed_secs, self.throughput_per_sec, self.target_rate,
+            self.ok, self.failed, self.shed, l.mean, l.p50, l.p90, l.p99, l.max,
+        );
+        if !self.failures.is_empty() {
+            let list: Vec<String> = self.failures.iter().map(|(code, n)| format!("{code} ×{n}")).collect();
+            out.push_str(&format!("failures: {}\n", list.join(", ")));
+        }
+        if let Some(m) = &self.memory {
+            out.push_str(&format!(
+                "rss: start {:.1} MiB, peak {:.1} MiB, end {:.1} MiB (growth {:+.1} MiB)\n",
+                m.start_kb as f64 / 1024.0, m.peak_kb as f64 / 1024.0, m.end_kb as f64 / 1024.0,
+                m.growth_kb as f64 / 1024.0,
+            ));
+        }
+        out
+    }
+}
+
+// ── Run ───────────────────────────────────────────────────────────────────────
+
+/// Drive `ir` (synthetic when None) at `args.rate` against a fresh mock server.
+async fn bench(config: crate::config::Config, ir: Option<LlmIntermediateRepresentation>, args: &Args) -> Result<Report> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let base = format!("http://{}", listener.local_addr()?);
+    let mock = tokio::spawn(serve_mock(listener, args.mock_latency));
+
+    let mut ir = ir.unwrap_or_else(|| synthetic_ir(&base));
+    redirect(&mut ir, &base);
+    let workflow = ir.metadata.as_ref().map_or_else(|| "unknown".to_owned(), |m| m.id.clone());
+    let mut config = config;
+    config.central_http_url = base;
+
+    let scratch = std::env::temp_dir().join(format!("eyeflow_bench_{}", uuid::Uuid::new_v4().simple()));
+    let svm = Arc::new(crate::svm::Svm::scratch(config.clone(), &scratch).await?
+        .with_chaos(crate::chaos::Chaos::from_config(&config)?));
+    let prepared = Arc::new(crate::prepared::PreparedIr::new(ir, String::new()));
+
+    // Audit chains are reused across runs (one per execution in flight)
+    let chains: Arc<Mutex<Vec<AuditChain>>> = Arc::default();
+    let samples: Arc<Mutex<Vec<Sample>>> = Arc::default();
+    let slots = Arc::new(Semaphore::new(args.concurrency));
+
+    let rss_start = rss_kb();
+    let rss_peak = Arc::new(AtomicU64::new(rss_start.unwrap_or(0)));
+    let sampler = {
+        let peak = rss_peak.clone();
+        tokio::spawn(async move {
+            let mut every = tokio::time::interval(Duration::from_millis(250));
+            loop {
+                every.tick().await;
+                if let Some(kb) = rss_kb() {
+                    peak.fetch_max(kb, Ordering::Relaxed);
+                }
+            }
+        })
+    };
+
+    let started = Instant::now();
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / args.rate));
+    let mut shed = 0u64;
+    while started.elapsed() < args.duration {
+        ticker.tick().await;
+        let Ok(permit) = slots.clone().try_acquire_owned() else {
+            shed += 1;
+            continue;
+        };
+        let (svm, prepared, chains, samples) = (svm.clone(), prepared.clone(), chains.clone(), samples.clone());
+        let node_id = config.node_id.clone();
+        tokio::spawn(async move {
+            let pooled = chains.lock().unwrap_or_else(|e| e.into_inner()).pop();
+            let mut audit = match pooled {
+                Some(chain) => chain,
+                None => match AuditChain::new(node_id, None) {
+                    Ok(chain) => chain,
+                    Err(e) => {
+                        samples.lock().unwrap_or_else(|e| e.into_inner()).push((0.0, Some(format!("audit: {e}"))));
+                        return;
+                    }
+                },
+            };
+            let t0 = Instant::now();
+            let outcome = svm.execute(&prepared, &mut audit, None).await;
+            let ms = t0.elapsed().as_secs_f64() * 1000.0;
+            audit.drain();
+            chains.lock().unwrap_or_else(|e| e.into_inner()).push(audit);
+            samples.lock().unwrap_or_else(|e| e.into_inner()).push((ms, outcome.err().map(|e| e.code.to_string())));
+            drop(permit);
+        });
+    }
+    // Let the executions in flight finish
+    let _ = slots.acquire_many(args.concurrency as u32).await?;
+    let elapsed = started.elapsed().as_secs_f64();
+    sampler.abort();
+    mock.abort();
+    let _ = std::fs::remove_dir_all(&scratch);
+
+    let samples = std::mem::take(&mut *samples.lock().unwrap_or_else(|e| e.into_inner()));
+    let mut failures: HashMap<String, u64> = HashMap::new();
+    let mut latencies: Vec<f64> = Vec::with_capacity(samples.len());
+    for (ms, error) in samples {
+        match error {
+            Some(code) => *failures.entry(code).or_default() += 1,
+            None => latencies.push(ms),
+        }
+    }
+    latencies.sort_by(f64::total_cmp);
+    let runs = (latencies.len() as u64) + failures.values().sum::<u64>();
+    let memory = rss_start.zip(rss_kb()).map(|(start_kb, end_kb)| Memory {
+        start_kb,
+        peak_kb: rss_peak.load(Ordering::Relaxed).max(end_kb),
+        end_kb,
+        growth_kb: end_kb as i64 - start_kb as i64,
+    });
+    Ok(Report {
+        workflow,
+        target_rate: args.rate,
+        elapsed_secs: elapsed,
+        runs,
+        ok: latencies.len() as u64,
+        failed: runs - latencies.len() as u64,
+        shed,
+        throughput_per_sec: runs as f64 / elapsed.max(f64::EPSILON),
+        latency_ms: Latency {
+            mean: latencies.iter().sum::<f64>() / (latencies.len().max(1) as f64),
+            p50: percentile(&latencies, 50.0),
+            p90: percentile(&latencies, 90.0),
+            p99: percentile(&latencies, 99.0),
+            max: latencies.last().copied().unwrap_or(0.0),
+        },
+        failures: failures.into_iter().collect(),
+        memory,
+    })
+}
+
+/// `bench` subcommand (args after the subcommand name).
+pub async fn run(args: &[String]) -> Result<()> {
+    let args = parse_args(args)?;
+    let ir = match &args.ir {
+        Some(path) => Some(crate::replay::decode_ir(&std::fs::read(path).with_context(|| format!("reading {path:?}"))?)?),
+        None => None,
+    };
+    let _ = dotenvy::dotenv();
+    let config = crate::config::Config::from_env();
+    eprintln!(
+        "bench: {:.1} runs/s for {:.0}s, {} in flight at most, mock latency {:?}",
+        args.rate, args.duration.as_secs_f64(), args.concurrency, args.mock_latency,
+    );
+    let report = bench(config, ir, &args).await?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&json!(report))?);
+    } else {
+        print!("{}", report.render());
+    }
+    Ok(())
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_and_redirect() {
+        let sorted: Vec<f64> = (1..=100).map(f64::from).collect();
+        assert_eq!(percentile(&sorted, 50.0), 50.0);
+        assert_eq!(percentile(&sorted, 99.0), 99.0);
+        assert_eq!(percentile(&[7.0], 90.0), 7.0);
+        assert_eq!(percentile(&[], 50.0), 0.0);
+
+        let mut ir = synthetic_ir("https://erp.example.com:8443");
+        ir.instructions.get_mut(&1).unwrap().dispatch_metadata.as_mut().unwrap().endpoint_url =
+            "https://erp.example.com:8443/api/stock?site=4".into();
+        redirect(&mut ir, "http://127.0.0.1:9");
+        assert_eq!(ir.instructions[&1].dispatch_metadata.as_ref().unwrap().endpoint_url, "http://127.0.0.1:9/api/stock?site=4");
+        assert_eq!(ir.instructions[&3].dispatch_metadata.as_ref().unwrap().endpoint_url, "http://127.0.0.1:9/actuator");
+    }
+
+    #[tokio::test]
+    async fn test_synthetic_run_against_mock() {
+        let args = parse_args(&["--rate".into(), "40".into(), "--duration".into(), "0.5".into(), "--mock-latency-ms".into(), "0".into()]).unwrap();
+        let report = bench(crate::config::Config::from_env(), None, &args).await.unwrap();
+        assert!(report.runs >= 10, "{report:?}");
+        assert_eq!(report.failed, 0, "{report:?}");
+        assert_eq!(report.shed, 0);
+        assert!(report.latency_ms.p99 >= report.latency_ms.p50);
+    }
+}
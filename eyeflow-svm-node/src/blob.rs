@@ -0,0 +1,259 @@
+//! Binary registers — bytes + MIME type kept beside the JSON register file
+//!
+//! Registers are JSON, so an image or a protobuf blob used to travel as
+//! base64 text.  Binary payloads now live in a content-addressed BlobStore
+//! and the register holds a reference:
+//!
+//!   { "$blob": "sha256:<hex>", "mime": "image/jpeg", "size": 48213 }
+//!
+//! HTTP LOAD_RESOURCE / CALL_SERVICE answers are read by Content-Type:
+//! JSON (or none) as JSON, text/* and other textual types as a string,
+//! anything else (image/*, application/octet-stream, application/x-protobuf,
+//! …) as a blob.  A CALL_SERVICE whose input is a blob reference sends the
+//! bytes as its request body, with the blob's MIME type as Content-Type.
+//!
+//! The output registers of a SliceExecutionResult carry the references;
+//! `blobs` lists them ({ id, mime, size, register }) and central fetches the
+//! bytes on demand:
+//!
+//!   → { "type": "BLOB_REQUEST", "payload": { "id": "sha256:…" } }
+//!   ← { "type": "BLOB", "payload": { "id", "mime", "size", "base64" } }
+//!     (or { "id", "error": "NOT_FOUND" } once the blob was evicted)
+//!
+//!   SVM_BLOB_MAX_BYTES        one blob (default 16 MiB); a larger body fails
+//!                             the instruction with QUOTA before it is read
+//!   SVM_BLOB_STORE_MAX_BYTES  all blobs kept (default 256 MiB); the least
+//!                             recently used are evicted first
+//!
+//! /metrics: eyeflow_blob_store_bytes, eyeflow_blob_store_blobs and
+//! eyeflow_blob_evictions_total.
+
+use anyhow::Result;
+use bytes::Bytes;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+use crate::health::MetricsCollector;
+use crate::proto::llmir::BlobRef;
+
+/// Key of a reference's blob id.
+pub const REF_KEY: &str = "$blob";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blob {
+    pub mime: String,
+    pub bytes: Bytes,
+}
+
+/// How an HTTP body of `content_type` becomes a register value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyKind {
+    Json,
+    Text,
+    Binary,
+}
+
+impl BodyKind {
+    pub fn of(content_type: &str) -> Self {
+        let essence = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        match essence.as_str() {
+            "" | "application/json" => Self::Json,
+            "application/x-ndjson" | "application/ndjson" | "application/xml" | "application/x-www-form-urlencoded" => Self::Text,
+            e if e.ends_with("+json") => Self::Json,
+            e if e.starts_with("text/") || e.ends_with("+xml") => Self::Text,
+            _ => Self::Binary,
+        }
+    }
+}
+
+/// Blob id of a reference value.
+pub fn reference_id(value: &Value) -> Option<&str> {
+    value.get(REF_KEY)?.as_str()
+}
+
+/// Every blob reference inside the registers, by register.
+pub fn references<'a>(registers: impl IntoIterator<Item = (&'a i32, &'a Value)>) -> Vec<BlobRef> {
+    fn walk(register: i32, value: &Value, out: &mut Vec<BlobRef>) {
+        match value {
+            Value::Object(obj) => match obj.get(REF_KEY).and_then(Value::as_str) {
+                Some(id) => out.push(BlobRef {
+                    id: id.to_owned(),
+                    mime: obj.get("mime").and_then(Value::as_str).unwrap_or_default().to_owned(),
+                    size: obj.get("size").and_then(Value::as_u64).unwrap_or_default(),
+                    register_index: register,
+                }),
+                None => obj.values().for_each(|v| walk(register, v, out)),
+            },
+            Value::Array(items) => items.iter().for_each(|v| walk(register, v, out)),
+            _ => {}
+        }
+    }
+    let mut out = Vec::new();
+    for (register, value) in registers {
+        walk(*register, value, &mut out);
+    }
+    out.sort_by(|a, b| (a.register_index, &a.id).cmp(&(b.register_index, &b.id)));
+    out
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+struct Entry {
+    blob: Blob,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<String, Entry>,
+    total: usize,
+}
+
+pub struct BlobStore {
+    max_blob: usize,
+    max_total: usize,
+    inner: Mutex<Inner>,
+    tick: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl BlobStore {
+    pub fn new(max_blob: usize, max_total: usize) -> Arc<Self> {
+        Arc::new(Self {
+            max_blob,
+            max_total,
+            inner: Mutex::new(Inner::default()),
+            tick: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        })
+    }
+
+    pub fn from_config(config: &Config) -> Arc<Self> {
+        Self::new(config.blob_max_bytes, config.blob_store_max_bytes)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Refuse a `len`-byte body for `what` above SVM_BLOB_MAX_BYTES.
+    pub fn check_size(&self, len: usize, what: &str) -> Result<()> {
+        if len > self.max_blob.min(self.max_total) {
+            return Err(ExecError::new(ErrorCode::Quota, format!(
+                "{what}: {len}-byte binary body exceeds SVM_BLOB_MAX_BYTES={}", self.max_blob,
+            )).into());
+        }
+        Ok(())
+    }
+
+    /// Keep `bytes` and return the register reference.
+    pub fn put(&self, mime: &str, bytes: Bytes, what: &str) -> Result<Value> {
+        self.check_size(bytes.len(), what)?;
+        let id = format!("sha256:{}", hex::encode(Sha256::digest(&bytes)));
+        let mime = if mime.is_empty() { "application/octet-stream" } else { mime };
+        let reference = json!({ REF_KEY: id, "mime": mime, "size": bytes.len() });
+        let tick = self.tick.fetch_add(1, Ordering::Relaxed);
+
+        let mut inner = self.lock();
+        if let Some(entry) = inner.entries.get_mut(&id) {
+            entry.last_used = tick;
+            return Ok(reference);
+        }
+        while inner.total + bytes.len() > self.max_total {
+            let Some(oldest) = inner.entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k.clone()) else { break };
+            let evicted = inner.entries.remove(&oldest).expect("present");
+            inner.total -= evicted.blob.bytes.len();
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+            debug!("[Blob] evicted {oldest} ({} bytes)", evicted.blob.bytes.len());
+        }
+        inner.total += bytes.len();
+        inner.entries.insert(id, Entry { blob: Blob { mime: mime.to_owned(), bytes }, last_used: tick });
+        Ok(reference)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Blob> {
+        let tick = self.tick.fetch_add(1, Ordering::Relaxed);
+        let mut inner = self.lock();
+        let entry = inner.entries.get_mut(id)?;
+        entry.last_used = tick;
+        Some(entry.blob.clone())
+    }
+
+    /// The blob `value` refers to: None when `value` is not a reference,
+    /// NOT_FOUND when the blob is gone.
+    pub fn resolve(&self, value: &Value) -> Result<Option<Blob>> {
+        let Some(id) = reference_id(value) else { return Ok(None) };
+        self.get(id)
+            .map(Some)
+            .ok_or_else(|| ExecError::new(ErrorCode::NotFound, format!("blob {id} is no longer in the blob store")).into())
+    }
+
+    /// BLOB frame answering a BLOB_REQUEST for `id`.
+    pub fn frame(&self, id: &str) -> Value {
+        use base64::{engine::general_purpose::STANDARD as B64, Engine};
+        let payload = match self.get(id) {
+            Some(blob) => json!({ "id": id, "mime": blob.mime, "size": blob.bytes.len(), "base64": B64.encode(&blob.bytes) }),
+            None => json!({ "id": id, "error": "NOT_FOUND" }),
+        };
+        json!({ "type": "BLOB", "payload": payload })
+    }
+}
+
+impl MetricsCollector for BlobStore {
+    fn prometheus(&self, node_id: &str) -> String {
+        let (bytes, blobs) = {
+            let inner = self.lock();
+            (inner.total, inner.entries.len())
+        };
+        format!(
+            "# HELP eyeflow_blob_store_bytes Bytes held by binary registers\n\
+             # TYPE eyeflow_blob_store_bytes gauge\n\
+             eyeflow_blob_store_bytes{{node_id=\"{node_id}\"}} {bytes}\n\
+             # HELP eyeflow_blob_store_blobs Binary register values held\n\
+             # TYPE eyeflow_blob_store_blobs gauge\n\
+             eyeflow_blob_store_blobs{{node_id=\"{node_id}\"}} {blobs}\n\
+             # HELP eyeflow_blob_evictions_total Blobs evicted to stay under SVM_BLOB_STORE_MAX_BYTES\n\
+             # TYPE eyeflow_blob_evictions_total counter\n\
+             eyeflow_blob_evictions_total{{node_id=\"{node_id}\"}} {}\n",
+            self.evictions.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_caps_and_references() {
+        assert_eq!(BodyKind::of("application/problem+json"), BodyKind::Json);
+        assert_eq!(BodyKind::of("text/csv; charset=utf-8"), BodyKind::Text);
+        assert_eq!(BodyKind::of("image/jpeg"), BodyKind::Binary);
+
+        let store = BlobStore::new(8, 10);
+        let a = store.put("image/png", Bytes::from_static(b"\x89PNG\0\0"), "t").unwrap();
+        let b = store.put("", Bytes::from_static(b"abcdef"), "t").unwrap();
+        assert_eq!((a["size"].as_u64(), b["mime"].as_str()), (Some(6), Some("application/octet-stream")));
+        // 10-byte store: the second blob evicted the first
+        assert!(store.resolve(&a).is_err());
+        assert_eq!(store.resolve(&b).unwrap().unwrap().bytes, Bytes::from_static(b"abcdef"));
+        assert!(store.resolve(&json!({ "value": 1 })).unwrap().is_none());
+        let err = store.put("image/png", Bytes::from(vec![0; 9]), "t").unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::Quota);
+
+        let regs: HashMap<i32, Value> = [(2, json!({ "frames": [b.clone()] })), (1, json!(3))].into();
+        let refs = references(&regs);
+        assert_eq!((refs.len(), refs[0].register_index, refs[0].id.as_str()), (1, 2, reference_id(&b).unwrap()));
+        assert_eq!(store.frame(reference_id(&b).unwrap())["payload"]["base64"], "YWJjZGVm");
+        assert_eq!(store.frame("sha256:00")["payload"]["error"], "NOT_FOUND");
+        assert!(store.prometheus("n").contains("eyeflow_blob_evictions_total{node_id=\"n\"} 1"));
+    }
+}
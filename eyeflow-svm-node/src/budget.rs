@@ -0,0 +1,162 @@
+//! CPU budgets — heavy opcodes off the async workers, yields in between
+//!
+//! Slices run on the tokio workers that also drive the central WebSocket,
+//! the health server and the peer listener.  Two mechanisms keep those
+//! responsive while a slice crunches data:
+//!
+//!   SVM_CPU_BUDGETS         "TRANSFORM=1000,…" (ms; the default).  The
+//!                           listed opcodes' handlers run on the blocking
+//!                           pool (spawn_blocking); one that outlives its
+//!                           budget fails with TIMEOUT — the slice goes on
+//!                           through its TRY scope / fallback, the runaway
+//!                           thread finishes in the background.  Empty = all
+//!                           handlers inline.
+//!   SVM_YIELD_INTERVAL_MS   the interpreter yields to the scheduler before
+//!                           an instruction once a slice has run this long
+//!                           without doing so (default 10, 0 = never)
+//!
+//! EMBEDDED_JS services go through the LLM_CALL dispatch path rather than
+//! an interpreter on the node, so there is nothing local to budget.
+//!
+//!   eyeflow_cpu_offloaded_total{opcode}
+//!   eyeflow_cpu_budget_exceeded_total{opcode}
+//!   eyeflow_cpu_yields_total
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+use crate::health::MetricsCollector;
+use crate::proto::llmir::IrOpcode;
+
+#[derive(Debug, Default)]
+struct OpcodeBudget {
+    budget: Duration,
+    offloaded: AtomicU64,
+    exceeded: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+pub struct CpuBudgets {
+    budgets: HashMap<IrOpcode, OpcodeBudget>,
+    /// Longest stretch without a yield (None = never yield)
+    yield_interval: Option<Duration>,
+    yields: AtomicU64,
+}
+
+impl CpuBudgets {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let mut budgets = HashMap::new();
+        for entry in &config.cpu_budgets {
+            let (name, ms) = entry.split_once('=')
+                .ok_or_else(|| anyhow!("SVM_CPU_BUDGETS: '{entry}' is not OPCODE=ms"))?;
+            let opcode = IrOpcode::from_str_name(&name.trim().to_uppercase())
+                .ok_or_else(|| anyhow!("SVM_CPU_BUDGETS: unknown opcode '{name}'"))?;
+            let ms: u64 = ms.trim().parse()
+                .map_err(|_| anyhow!("SVM_CPU_BUDGETS: '{ms}' is not a number of milliseconds"))?;
+            budgets.insert(opcode, OpcodeBudget { budget: Duration::from_millis(ms), ..Default::default() });
+        }
+        let yield_interval = (config.yield_interval_ms > 0).then(|| Duration::from_millis(config.yield_interval_ms));
+        Ok(Self { budgets, yield_interval, yields: AtomicU64::new(0) })
+    }
+
+    /// Run `opcode`'s handler `f`: on the blocking pool within its budget,
+    /// or inline when the opcode has none.
+    pub async fn run<T, F>(&self, opcode: IrOpcode, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let Some(b) = self.budgets.get(&opcode) else { return Ok(f()) };
+        b.offloaded.fetch_add(1, Ordering::Relaxed);
+        match tokio::time::timeout(b.budget, tokio::task::spawn_blocking(f)).await {
+            Ok(Ok(out)) => Ok(out),
+            // A panic in the handler resumes here, for the slice supervisor
+            Ok(Err(e)) => match e.try_into_panic() {
+                Ok(payload) => std::panic::resume_unwind(payload),
+                Err(e) => Err(anyhow!("{} handler: {e}", opcode.as_str_name())),
+            },
+            Err(_) => {
+                b.exceeded.fetch_add(1, Ordering::Relaxed);
+                warn!("[Budget] {} exceeded its CPU budget of {:?}", opcode.as_str_name(), b.budget);
+                Err(ExecError::new(
+                    ErrorCode::Timeout,
+                    format!("{} exceeded its CPU budget of {} ms", opcode.as_str_name(), b.budget.as_millis()),
+                ).fatal().into())
+
+            }
+        }
+    }
+
+    /// Yield point: lets other tasks run once the slice has held the worker
+    /// for the yield interval since `since`, which is then reset.
+    pub async fn checkpoint(&self, since: &mut Instant) {
+        let Some(interval) = self.yield_interval else { return };
+        if since.elapsed() >= interval {
+            tokio::task::yield_now().await;
+            self.yields.fetch_add(1, Ordering::Relaxed);
+            *since = Instant::now();
+        }
+    }
+}
+
+impl MetricsCollector for CpuBudgets {
+    fn prometheus(&self, node_id: &str) -> String {
+        let mut offloaded = String::new();
+        let mut exceeded = String::new();
+        for (opcode, b) in &self.budgets {
+            let labels = format!("node_id=\"{node_id}\",opcode=\"{}\"", opcode.as_str_name());
+            offloaded.push_str(&format!("eyeflow_cpu_offloaded_total{{{labels}}} {}\n", b.offloaded.load(Ordering::Relaxed)));
+            exceeded.push_str(&format!("eyeflow_cpu_budget_exceeded_total{{{labels}}} {}\n", b.exceeded.load(Ordering::Relaxed)));
+        }
+        format!(
+            "# HELP eyeflow_cpu_offloaded_total Handlers run on the blocking pool under a CPU budget\n\
+             # TYPE eyeflow_cpu_offloaded_total counter\n\
+             {offloaded}\
+             # HELP eyeflow_cpu_budget_exceeded_total Handlers failed for outliving their CPU budget\n\
+             # TYPE eyeflow_cpu_budget_exceeded_total counter\n\
+             {exceeded}\
+             # HELP eyeflow_cpu_yields_total Yield points taken by the interpreter\n\
+             # TYPE eyeflow_cpu_yields_total counter\n\
+             eyeflow_cpu_yields_total{{node_id=\"{node_id}\"}} {}\n",
+            self.yields.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_budgets_and_yields() {
+        let config = |budgets: &[&str]| Config {
+            cpu_budgets: budgets.iter().map(|b| b.to_string()).collect(),
+            yield_interval_ms: 1,
+            ..Config::from_env()
+        };
+        assert!(CpuBudgets::from_config(&config(&["TRANSFORM"])).is_err());
+        assert!(CpuBudgets::from_config(&config(&["TRANSMOGRIFY=5"])).is_err());
+
+        let budgets = CpuBudgets::from_config(&config(&["transform=50"])).unwrap();
+        assert_eq!(budgets.run(IrOpcode::Transform, || 2 + 2).await.unwrap(), 4);
+        let err = budgets.run(IrOpcode::Transform, || std::thread::sleep(Duration::from_millis(300))).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<ExecError>().unwrap().code, ErrorCode::Timeout);
+        // Not budgeted: inline
+        assert_eq!(budgets.run(IrOpcode::Validate, || "inline").await.unwrap(), "inline");
+
+        let mut since = Instant::now() - Duration::from_millis(5);
+        budgets.checkpoint(&mut since).await;
+        budgets.checkpoint(&mut since).await;
+        let metrics = budgets.prometheus("n");
+        assert!(metrics.contains("eyeflow_cpu_offloaded_total{node_id=\"n\",opcode=\"TRANSFORM\"} 2"), "{metrics}");
+        assert!(metrics.contains("eyeflow_cpu_budget_exceeded_total{node_id=\"n\",opcode=\"TRANSFORM\"} 1"));
+        assert!(metrics.contains("eyeflow_cpu_yields_total{node_id=\"n\"} 1"));
+    }
+}
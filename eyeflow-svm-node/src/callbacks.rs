@@ -0,0 +1,199 @@
+//! Webhook-callback completion for CALL_ACTION
+//!
+//! The alternative to polling (async_poll.rs) for services that call back
+//! when a long-running operation finishes.  When the instruction's
+//! dispatch_metadata carries `async_callback`, the node registers a one-shot
+//! URL on its HTTP listener, hands it to the service and suspends the
+//! instruction until the callback arrives:
+//!
+//!   POST {SVM_CALLBACK_BASE_URL}/callbacks/{id}     body = the result
+//!
+//!   url_field     request body field receiving the URL (default "callbackUrl";
+//!                 only set when the action input is an object)
+//!   url_header    also send it in this header, e.g. "X-Callback-Url"
+//!   timeout_ms    TIMEOUT when nothing arrives (default 300000)
+//!   result_path   field of the callback body loaded into dest
+//!
+//! SVM_CALLBACK_BASE_URL is how services reach the health listener (e.g.
+//! "http://gateway-7:9090"); callbacks are disabled when it is unset.  The id
+//! is a random UUID, so the URL itself is the credential; it accepts one
+//! delivery and is forgotten once used, timed out or abandoned.  Bodies are
+//! capped at SVM_CALLBACK_MAX_BYTES (default 1 MiB).
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{debug, info};
+
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+use crate::health::MetricsCollector;
+
+pub const ROUTE_PREFIX: &str = "/callbacks/";
+
+/// Outcome of an inbound callback request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Delivery {
+    Delivered,
+    /// Unknown, already used or expired id
+    Unknown,
+}
+
+#[derive(Debug)]
+pub struct CallbackRegistry {
+    base_url: String,
+    max_bytes: usize,
+    pending: Mutex<HashMap<String, oneshot::Sender<Value>>>,
+    delivered: AtomicU64,
+    expired: AtomicU64,
+    rejected: AtomicU64,
+}
+
+/// A registered callback; dropping it unregisters the URL.
+pub struct PendingCallback {
+    pub id: String,
+    pub url: String,
+    rx: Option<oneshot::Receiver<Value>>,
+    registry: Arc<CallbackRegistry>,
+}
+
+impl CallbackRegistry {
+    pub fn new(base_url: &str, max_bytes: usize) -> Arc<Self> {
+        Arc::new(Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            max_bytes,
+            pending: Mutex::new(HashMap::new()),
+            delivered: AtomicU64::new(0),
+            expired: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        })
+    }
+
+    /// Registry configured by SVM_CALLBACK_BASE_URL, if any.
+    pub fn from_config(config: &Config) -> Option<Arc<Self>> {
+        let base = config.callback_base_url.as_deref()?;
+        info!("[Callbacks] CALL_ACTION callbacks served at {base}{ROUTE_PREFIX}…");
+        Some(Self::new(base, config.callback_max_bytes))
+    }
+
+    /// Largest callback body accepted.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Register a new one-shot callback URL.
+    pub fn register(self: &Arc<Self>) -> PendingCallback {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).insert(id.clone(), tx);
+        PendingCallback {
+            url: format!("{}{ROUTE_PREFIX}{id}", self.base_url),
+            id,
+            rx: Some(rx),
+            registry: self.clone(),
+        }
+    }
+
+    /// Hand `body` to the instruction waiting on `id`.
+    pub fn deliver(&self, id: &str, body: Value) -> Delivery {
+        let tx = self.pending.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+        match tx.map(|tx| tx.send(body)) {
+            Some(Ok(())) => {
+                self.delivered.fetch_add(1, Ordering::Relaxed);
+                debug!("[Callbacks] {id} delivered");
+                Delivery::Delivered
+            }
+            _ => {
+                self.rejected.fetch_add(1, Ordering::Relaxed);
+                Delivery::Unknown
+            }
+        }
+    }
+
+    /// Answer to `POST /callbacks/{id}` on the health listener.
+    pub fn handle(&self, route: &str, body: &[u8]) -> (&'static str, String) {
+        let id = route.strip_prefix(ROUTE_PREFIX).unwrap_or_default();
+        let value = serde_json::from_slice(body)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into_owned()));
+        match self.deliver(id, value) {
+            Delivery::Delivered => ("200 OK", json!({ "accepted": true }).to_string()),
+            Delivery::Unknown => ("404 Not Found", json!({ "error": "unknown or expired callback" }).to_string()),
+        }
+    }
+}
+
+impl PendingCallback {
+    /// Wait up to `timeout` for the callback body.
+    pub async fn wait(mut self, timeout: Duration) -> Result<Value> {
+        let rx = self.rx.take().expect("waited once");
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(body)) => Ok(body),
+            Ok(Err(_)) => Err(ExecError::new(ErrorCode::Cancelled, format!("callback {} abandoned", self.id)).into()),
+            Err(_) => {
+                self.registry.expired.fetch_add(1, Ordering::Relaxed);
+                Err(ExecError::new(
+                    ErrorCode::Timeout,
+                    format!("no callback on {} within {} ms", self.url, timeout.as_millis()),
+                ).into())
+            }
+        }
+    }
+}
+
+impl Drop for PendingCallback {
+    fn drop(&mut self) {
+        self.registry.pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.id);
+    }
+}
+
+impl MetricsCollector for CallbackRegistry {
+    fn prometheus(&self, node_id: &str) -> String {
+        let pending = self.pending.lock().map(|p| p.len()).unwrap_or(0);
+        format!(
+            "# HELP eyeflow_callbacks_pending CALL_ACTIONs waiting for their webhook callback\n\
+             # TYPE eyeflow_callbacks_pending gauge\n\
+             eyeflow_callbacks_pending{{node_id=\"{node_id}\"}} {pending}\n\
+             # HELP eyeflow_callbacks_total Webhook callbacks by outcome\n\
+             # TYPE eyeflow_callbacks_total counter\n\
+             eyeflow_callbacks_total{{node_id=\"{node_id}\",result=\"delivered\"}} {}\n\
+             eyeflow_callbacks_total{{node_id=\"{node_id}\",result=\"expired\"}} {}\n\
+             eyeflow_callbacks_total{{node_id=\"{node_id}\",result=\"rejected\"}} {}\n",
+            self.delivered.load(Ordering::Relaxed),
+            self.expired.load(Ordering::Relaxed),
+            self.rejected.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_one_shot_delivery_and_timeout() {
+        let registry = CallbackRegistry::new("http://gw:9090/", 1024);
+        let cb = registry.register();
+        assert_eq!(cb.url, format!("http://gw:9090/callbacks/{}", cb.id));
+
+        let route = format!("{ROUTE_PREFIX}{}", cb.id);
+        let (status, _) = registry.handle(&route, br#"{"done":true}"#);
+        assert_eq!(status, "200 OK");
+        // One shot: a replay of the same callback is refused
+        assert_eq!(registry.handle(&route, b"{}").0, "404 Not Found");
+        assert_eq!(cb.wait(Duration::from_secs(1)).await.unwrap(), json!({ "done": true }));
+
+        let late = registry.register();
+        let late_route = format!("{ROUTE_PREFIX}{}", late.id);
+        let err = late.wait(Duration::from_millis(10)).await.unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::Timeout);
+        // Timed out URLs are forgotten
+        assert_eq!(registry.handle(&late_route, b"{}").0, "404 Not Found");
+        assert!(registry.pending.lock().unwrap().is_empty());
+    }
+}
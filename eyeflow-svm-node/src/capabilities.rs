@@ -0,0 +1,317 @@
+//! Capability probing — what this node can do on this machine, right now
+//!
+//! features.rs lists what the build supports; some of it only works when the
+//! machine has it.  Those features are advertised after a probe instead:
+//!
+//!   connector:gpio     a GPIO chip (/dev/gpiochip*) under SVM_DEVICE_ALLOW
+//!   connector:serial   a serial port (/dev/tty*, /dev/serial/by-id/…) under
+//!                      SVM_DEVICE_ALLOW
+//!   connector:ble      the HCI adapter SVM_BLE_ADAPTER exists
+//!   format:ONNX        ONNX Runtime loads (feature `onnx`) and the asset
+//!                      store is enabled
+//!
+//! The probe also reports, under REGISTER capabilities `probes`:
+//!
+//!   { "devices": ["/dev/gpiochip0", "/dev/ttyUSB0"], "bluetooth": "hci0",
+//!     "kafka": { "brokers": 2, "reachable": 1 }, "ffmpeg": true,
+//!     "onnxRuntime": false }
+//!
+//! connector:kafka stays advertised with every broker down — records are
+//! buffered until one is back (connectors/kafka.rs) — and `kafka.reachable`
+//! tells central how far behind they are.  ffmpeg only serves rtsp:// and
+//! MJPEG cameras; camera+http:// snapshots work without it.
+//!
+//! The probe runs at start-up, then every SVM_CAPABILITY_PROBE_SECS (default
+//! 30, 0 = start-up only), and at once when the device watcher sees a
+//! device plugged or unplugged (hotplug.rs).  When its outcome changes — a
+//! USB serial adapter plugged in, the last broker gone — the node
+//! re-announces itself:
+//!
+//!   { "type": "CAPABILITIES_UPDATE", "payload": { nodeId, capabilities,
+//!                                                 added, removed } }
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::{Notify, OnceCell};
+use tracing::info;
+
+use crate::config::Config;
+
+/// Features advertised only when a probe finds them.
+pub const PROBED: &[&str] = &["connector:gpio", "connector:serial", "connector:ble", "format:ONNX"];
+
+/// Deadline of one broker connection attempt.
+const BROKER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Outcome of one probe pass.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Probed {
+    #[serde(skip)]
+    pub features: BTreeSet<String>,
+    pub devices: Vec<String>,
+    pub bluetooth: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kafka: Option<BrokerReach>,
+    pub ffmpeg: bool,
+    pub onnx_runtime: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct BrokerReach {
+    pub brokers: usize,
+    pub reachable: usize,
+}
+
+/// What to probe, from the config.
+struct Probe {
+    device_allow: Vec<String>,
+    ble_adapter: u16,
+    kafka_brokers: Vec<String>,
+    ffmpeg: String,
+    /// ONNX inference is wired (build feature + asset store)
+    onnx: bool,
+    /// Loading ONNX Runtime is tried once: a failed load stays failed
+    onnx_runtime: OnceCell<bool>,
+}
+
+impl Probe {
+    async fn run(&self) -> Probed {
+        let mut out = Probed { devices: devices(&self.device_allow), ..Probed::default() };
+        if cfg!(target_os = "linux") {
+            if out.devices.iter().any(|d| is_gpio(d)) {
+                out.features.insert("connector:gpio".into());
+            }
+            if out.devices.iter().any(|d| !is_gpio(d)) {
+                out.features.insert("connector:serial".into());
+            }
+            let hci = format!("hci{}", self.ble_adapter);
+            if Path::new("/sys/class/bluetooth").join(&hci).exists() {
+                out.features.insert("connector:ble".into());
+                out.bluetooth = Some(hci);
+            }
+        }
+        if !self.kafka_brokers.is_empty() {
+            let attempts = self.kafka_brokers.iter().map(|b| tokio::time::timeout(BROKER_TIMEOUT, tokio::net::TcpStream::connect(b.as_str())));
+            let reachable = futures_util::future::join_all(attempts).await.into_iter()
+                .filter(|r| matches!(r, Ok(Ok(_))))
+                .count();
+            out.kafka = Some(BrokerReach { brokers: self.kafka_brokers.len(), reachable });
+        }
+        out.ffmpeg = tokio::process::Command::new(&self.ffmpeg)
+            .arg("-version")
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .status()
+            .await
+            .is_ok_and(|s| s.success());
+        if self.onnx {
+            out.onnx_runtime = *self.onnx_runtime.get_or_init(crate::inference::runtime_available).await;
+            if out.onnx_runtime {
+                out.features.insert("format:ONNX".into());
+            }
+        }
+        out
+    }
+}
+
+fn is_gpio(device: &str) -> bool {
+    device.rsplit('/').next().is_some_and(|name| name.starts_with("gpiochip"))
+}
+
+/// Devices present under the SVM_DEVICE_ALLOW prefixes: every entry of a
+/// prefix that is a directory, else the entries of its parent it prefixes.
+pub fn devices(allow: &[String]) -> Vec<String> {
+    let mut out = BTreeSet::new();
+    for prefix in allow {
+        let path = Path::new(prefix);
+        let (dir, starts) = if path.is_dir() {
+            (path, prefix.trim_end_matches('/'))
+        } else {
+            (path.parent().unwrap_or(Path::new("/")), prefix.as_str())
+        };
+        let Ok(entries) = std::fs::read_dir(dir) else { continue };
+        for entry in entries.flatten() {
+            let full = entry.path().to_string_lossy().into_owned();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // All of /dev allowed: only its GPIO chips and terminals count
+            let device_like = dir != Path::new("/dev") || name.starts_with("gpiochip") || name.starts_with("tty");
+            if full.starts_with(starts) && device_like {
+                out.insert(full);
+            }
+        }
+    }
+    out.into_iter().collect()
+}
+
+// ── Shared capabilities ───────────────────────────────────────────────────────
+
+pub struct Capabilities {
+    /// Build features + local capabilities + plugins, minus PROBED
+    base: RwLock<BTreeSet<String>>,
+    /// None until the start-up probe
+    probed: RwLock<Option<Probed>>,
+    probe: Probe,
+    interval: Duration,
+    /// Signalled when a probe changed the outcome
+    changed: Notify,
+    /// Features gained / lost by the last change
+    last_change: RwLock<(Vec<String>, Vec<String>)>,
+}
+
+impl Capabilities {
+    /// `onnx`: ONNX inference is wired on this node.  Nothing probed yet.
+    pub fn new(config: &Config, onnx: bool) -> Arc<Self> {
+        let mut base = crate::features::node_features(config);
+        base.retain(|f| !PROBED.contains(&f.as_str()));
+        Arc::new(Self {
+            base: RwLock::new(base),
+            probed: RwLock::new(None),
+            probe: Probe {
+                device_allow: config.device_allow.clone(),
+                ble_adapter: config.ble_adapter,
+                kafka_brokers: config.kafka_brokers.clone(),
+                ffmpeg: config.ffmpeg_path.clone(),
+                onnx: onnx && cfg!(feature = "onnx"),
+                onnx_runtime: OnceCell::new(),
+            },
+            interval: Duration::from_secs(config.capability_probe_secs),
+            changed: Notify::new(),
+            last_change: RwLock::new((Vec::new(), Vec::new())),
+        })
+    }
+
+    /// Advertise `features` as well (connector plugins).
+    pub fn extend(&self, features: impl IntoIterator<Item = String>) {
+        self.base.write().unwrap_or_else(|e| e.into_inner()).extend(features);
+    }
+
+    /// Every feature the node supports as of the last probe.
+    pub fn features(&self) -> BTreeSet<String> {
+        let mut out = self.base.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(probed) = &*self.probed.read().unwrap_or_else(|e| e.into_inner()) {
+            out.extend(probed.features.iter().cloned());
+        }
+        out
+    }
+
+    pub fn contains(&self, feature: &str) -> bool {
+        self.base.read().unwrap_or_else(|e| e.into_inner()).contains(feature)
+            || self.probed.read().unwrap_or_else(|e| e.into_inner()).as_ref().is_some_and(|p| p.features.contains(feature))
+    }
+
+    /// REGISTER capabilities `probes`.
+    pub fn probes(&self) -> Value {
+        serde_json::to_value(&*self.probed.read().unwrap_or_else(|e| e.into_inner())).unwrap_or(Value::Null)
+    }
+
+    /// CAPABILITIES_UPDATE `added` / `removed`.
+    pub fn last_change(&self) -> Value {
+        let (added, removed) = &*self.last_change.read().unwrap_or_else(|e| e.into_inner());
+        json!({ "added": added, "removed": removed })
+    }
+
+    /// Probe now; true when the outcome differs from the last probe's, which
+    /// signals `changed` (not on the start-up probe: REGISTER carries it).
+    pub async fn refresh(&self) -> bool {
+        let probed = self.probe.run().await;
+        self.apply(probed)
+    }
+
+    fn apply(&self, probed: Probed) -> bool {
+        let mut current = self.probed.write().unwrap_or_else(|e| e.into_inner());
+        if current.as_ref() == Some(&probed) {
+            return false;
+        }
+        let before = current.as_ref().map(|p| p.features.clone()).unwrap_or_default();
+        let added: Vec<String> = probed.features.difference(&before).cloned().collect();
+        let removed: Vec<String> = before.difference(&probed.features).cloned().collect();
+        info!(
+            "[Capabilities] probed: +{added:?} -{removed:?}, devices {:?}, kafka {:?}",
+            probed.devices, probed.kafka,
+        );
+        let startup = current.replace(probed).is_none();
+        drop(current);
+        *self.last_change.write().unwrap_or_else(|e| e.into_inner()) = (added, removed);
+        if !startup {
+            self.changed.notify_one();
+        }
+        true
+    }
+
+    /// Resolves after a probe changed the outcome.
+    pub async fn changed(&self) {
+        self.changed.notified().await
+    }
+}
+
+/// Re-probe every SVM_CAPABILITY_PROBE_SECS (the start-up probe is done by
+/// the caller).
+pub async fn run(capabilities: Arc<Capabilities>) {
+    if capabilities.interval.is_zero() {
+        return;
+    }
+    let mut ticker = tokio::time::interval(capabilities.interval);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        capabilities.refresh().await;
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_probed_features_and_changes() {
+        let dir = std::env::temp_dir().join(format!("eyeflow-caps-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("by-id")).unwrap();
+        for name in ["gpiochip0", "ttyUSB0", "ttyUSB1", "null"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        std::fs::write(dir.join("by-id/usb-FTDI_FT232R-if00"), "").unwrap();
+        let d = dir.display();
+        let allow = vec![format!("{d}/ttyUSB"), format!("{d}/by-id/"), format!("{d}/gpiochip")];
+        assert_eq!(devices(&allow), vec![
+            format!("{d}/by-id/usb-FTDI_FT232R-if00"),
+            format!("{d}/gpiochip0"),
+            format!("{d}/ttyUSB0"),
+            format!("{d}/ttyUSB1"),
+        ]);
+
+        let config = Config {
+            device_allow: vec![format!("{d}/ttyUSB")],
+            kafka_brokers: vec!["127.0.0.1:1".into()],
+            ffmpeg_path: "/nonexistent/ffmpeg".into(),
+            ..Config::from_env()
+        };
+        let caps = Capabilities::new(&config, true);
+        assert!(caps.contains("opcode:CALL_SERVICE") && !caps.contains("connector:serial"));
+        assert!(caps.refresh().await);
+        assert_eq!(caps.contains("connector:serial"), cfg!(target_os = "linux"));
+        assert!(!caps.contains("connector:gpio") && !caps.contains("format:ONNX"));
+        let probes = caps.probes();
+        assert_eq!(probes["kafka"], json!({ "brokers": 1, "reachable": 0 }));
+        assert_eq!(probes["ffmpeg"], false);
+        assert!(!caps.refresh().await, "same outcome, no update");
+
+        // The adapter is unplugged: re-announced without connector:serial
+        std::fs::remove_file(dir.join("ttyUSB0")).unwrap();
+        std::fs::remove_file(dir.join("ttyUSB1")).unwrap();
+        assert!(caps.refresh().await);
+        assert!(!caps.contains("connector:serial"));
+        if cfg!(target_os = "linux") {
+            assert_eq!(caps.last_change()["removed"], json!(["connector:serial"]));
+        }
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,227 @@
+//! Service Catalogue — spec §6.1 (registry lookup)
+//!
+//! Local, disk-backed copy of the central service registry, restricted to the
+//! services this node may dispatch.  It lets LOAD_RESOURCE resolve a bare
+//! `service_id` to endpoint / method / credentials / format without a round
+//! trip to central, so registry-based resources keep working while offline.
+//!
+//! Sources of truth (last writer wins, whole-catalogue replacement):
+//!   1. Periodic pull: GET {central}/api/nodes/{nodeId}/service-catalog
+//!   2. Push: CONFIG_UPDATE frame carrying a `serviceCatalog` array
+//!   3. Restart: the JSON snapshot at SVM_CATALOG_PATH
+//!
+//! Each entry mirrors the subset of `DispatchMetadata` needed for dispatch.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::fs;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use crate::proto::llmir::{DispatchMetadata, ServiceFormat};
+
+// ── Entry ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CatalogEntry {
+    pub service_id: String,
+    pub service_version: String,
+    /// ServiceFormat name as in llm_ir.proto (e.g. "HTTP", "CONNECTOR")
+    pub format: String,
+    pub endpoint_url: String,
+    pub method: String,
+    pub auth_type: String,
+    pub credentials_vault_path: String,
+    pub static_headers: HashMap<String, String>,
+    pub output_mapping: HashMap<String, String>,
+}
+
+impl CatalogEntry {
+    /// Build the `DispatchMetadata` the compiler would have frozen into the IR.
+    pub fn to_dispatch_metadata(&self) -> DispatchMetadata {
+        let format = ServiceFormat::from_str_name(&self.format.to_uppercase())
+            .unwrap_or(ServiceFormat::Http);
+        DispatchMetadata {
+            service_id:             self.service_id.clone(),
+            service_version:        self.service_version.clone(),
+            format:                 format as i32,
+            endpoint_url:           self.endpoint_url.clone(),
+            method:                 self.method.clone(),
+            auth_type:              self.auth_type.clone(),
+            credentials_vault_path: self.credentials_vault_path.clone(),
+            static_headers:         self.static_headers.clone(),
+            output_mapping:         self.output_mapping.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+// ── Catalogue ─────────────────────────────────────────────────────────────────
+
+pub struct ServiceCatalog {
+    entries: RwLock<HashMap<String, CatalogEntry>>,
+    path: PathBuf,
+}
+
+impl ServiceCatalog {
+    pub fn new(path: impl Into<PathBuf>) -> Arc<Self> {
+        Arc::new(Self {
+            entries: RwLock::new(HashMap::new()),
+            path: path.into(),
+        })
+    }
+
+    /// Look up a service by id.
+    pub async fn get(&self, service_id: &str) -> Option<CatalogEntry> {
+        self.entries.read().await.get(service_id).cloned()
+    }
+
+    /// Replace the catalogue with the entries contained in `update`.
+    ///
+    /// Accepts either a bare array of entries or `{ "services": [...] }`
+    /// (the shape returned by central).  Returns the new entry count.
+    pub async fn apply_update(&self, update: &Value) -> Result<usize> {
+        let list = update.get("services").unwrap_or(update);
+        let parsed: Vec<CatalogEntry> = serde_json::from_value(list.clone())
+            .map_err(|e| anyhow!("invalid service catalogue: {e}"))?;
+
+        let fresh: HashMap<String, CatalogEntry> = parsed
+            .into_iter()
+            .filter(|e| !e.service_id.is_empty())
+            .map(|e| (e.service_id.clone(), e))
+            .collect();
+        let count = fresh.len();
+
+        *self.entries.write().await = fresh;
+        info!("[Catalog] applied {count} service(s)");
+
+        if let Err(e) = self.persist().await {
+            warn!("[Catalog] failed to persist snapshot: {e}");
+        }
+        Ok(count)
+    }
+
+    // ── Persistence ───────────────────────────────────────────────────────────
+
+    /// Write the catalogue snapshot to disk (temp file + rename).
+    pub async fn persist(&self) -> Result<()> {
+        let list: Vec<CatalogEntry> = self.entries.read().await.values().cloned().collect();
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(&list)?).await?;
+        fs::rename(&tmp, &self.path).await?;
+        debug!("[Catalog] persisted {} entries to {:?}", list.len(), self.path);
+        Ok(())
+    }
+
+    /// Restore the catalogue snapshot written by a previous run.
+    pub async fn load(&self) -> Result<usize> {
+        if !self.path.exists() {
+            return Ok(0);
+        }
+        let content = fs::read(&self.path).await?;
+        let list: Vec<CatalogEntry> = serde_json::from_slice(&content)?;
+        let mut entries = self.entries.write().await;
+        for e in list {
+            entries.insert(e.service_id.clone(), e);
+        }
+        info!("[Catalog] loaded {} entries from {:?}", entries.len(), self.path);
+        Ok(entries.len())
+    }
+}
+
+// ── Background sync ───────────────────────────────────────────────────────────
+
+/// Periodically pull the catalogue from central.
+///
+/// Failures are logged and retried on the next tick — the last good snapshot
+/// stays in effect, which is what makes offline resolution possible.
+/// `interval_secs = 0` disables the pull (CONFIG_UPDATE pushes still apply).
+pub async fn run_sync(
+    catalog: Arc<ServiceCatalog>,
+    http: reqwest::Client,
+    central_http_url: String,
+    node_id: String,
+    interval_secs: u64,
+) {
+    if interval_secs == 0 {
+        info!("[Catalog] periodic sync disabled");
+        return;
+    }
+    let url = format!("{central_http_url}/api/nodes/{node_id}/service-catalog");
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+
+    loop {
+        ticker.tick().await;
+        let body = match http.get(&url).send().await {
+            Ok(resp) if resp.status().is_success() => resp.json::<Value>().await,
+            Ok(resp) => {
+                debug!("[Catalog] sync HTTP {} — keeping cached catalogue", resp.status());
+                continue;
+            }
+            Err(e) => {
+                debug!("[Catalog] sync failed: {e} — keeping cached catalogue");
+                continue;
+            }
+        };
+        match body {
+            Ok(v) => {
+                if let Err(e) = catalog.apply_update(&v).await {
+                    warn!("[Catalog] sync rejected: {e}");
+                }
+            }
+            Err(e) => warn!("[Catalog] sync response decode error: {e}"),
+        }
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_to_dispatch_metadata() {
+        let entry = CatalogEntry {
+            service_id: "plant-sensors".into(),
+            format: "connector".into(),
+            endpoint_url: "http://10.0.0.5/api/readings".into(),
+            method: "GET".into(),
+            ..Default::default()
+        };
+        let dm = entry.to_dispatch_metadata();
+        assert_eq!(dm.format, ServiceFormat::Connector as i32);
+        assert_eq!(dm.endpoint_url, "http://10.0.0.5/api/readings");
+
+        let unknown = CatalogEntry { format: "bogus".into(), ..Default::default() };
+        assert_eq!(unknown.to_dispatch_metadata().format, ServiceFormat::Http as i32);
+    }
+
+    #[tokio::test]
+    async fn test_apply_update_and_reload() {
+        let path = std::env::temp_dir()
+            .join(format!("eyeflow_catalog_{}.json", uuid::Uuid::new_v4()));
+        let catalog = ServiceCatalog::new(&path);
+
+        let update = serde_json::json!({
+            "services": [
+                { "serviceId": "erp-orders", "endpointUrl": "http://erp/orders", "format": "HTTP" },
+                { "endpointUrl": "http://ignored" }
+            ]
+        });
+        assert_eq!(catalog.apply_update(&update).await.unwrap(), 1);
+        assert!(catalog.get("erp-orders").await.is_some());
+
+        let restored = ServiceCatalog::new(&path);
+        assert_eq!(restored.load().await.unwrap(), 1);
+        assert_eq!(restored.get("erp-orders").await.unwrap().endpoint_url, "http://erp/orders");
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
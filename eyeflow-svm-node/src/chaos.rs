@@ -0,0 +1,216 @@
+//! Chaos mode — fault injection to exercise fallbacks before going live
+//!
+//! FAIL_SAFE defaults, DEGRADED_MODE paths and offline buffering only run
+//! when something breaks, which on a healthy test bench is never.  With
+//! SVM_CHAOS set, the node breaks things itself:
+//!
+//!   SVM_CHAOS="call_service.fail=20,call_service.delay=30,call_service.delay_ms=800,
+//!              llm_call.fail=10,llm_call.error=timeout,vault.fail=50,central.drop=5"
+//!
+//!   <target>.fail       % of operations failing before they are attempted
+//!   <target>.error      code of the injected failure (default network —
+//!                       transient, so RETRY_WITH_BACKOFF retries it)
+//!   <target>.delay      % of operations delayed by <target>.delay_ms
+//!                       (default 1000) before they run
+//!   central.drop        % of keepalive ticks closing the central
+//!                       connection, so results are buffered offline until
+//!                       the node reconnects
+//!
+//! Targets: call_service, llm_call, vault (every secret fetch) and central.
+//! Failures are injected inside the fallback loop, so retries re-roll and the
+//! instruction's strategy handles what is left; their messages start with
+//! "chaos:".  SVM_CHAOS_SEED makes a run reproducible.  Injections are
+//! counted on /metrics (eyeflow_chaos_injected_total).  Never enable this on
+//! a production node.
+
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+use crate::health::MetricsCollector;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    CallService,
+    LlmCall,
+    Vault,
+    Central,
+}
+
+impl Target {
+    const ALL: [Target; 4] = [Self::CallService, Self::LlmCall, Self::Vault, Self::Central];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::CallService => "call_service",
+            Self::LlmCall => "llm_call",
+            Self::Vault => "vault",
+            Self::Central => "central",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Fault {
+    /// Failure probability, 0..=1
+    fail: f64,
+    error: ErrorCode,
+    /// Delay probability, 0..=1
+    delay: f64,
+    delay_for: Duration,
+}
+
+impl Default for Fault {
+    fn default() -> Self {
+        Self { fail: 0.0, error: ErrorCode::Network, delay: 0.0, delay_for: Duration::from_secs(1) }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    failed: AtomicU64,
+    delayed: AtomicU64,
+}
+
+pub struct Chaos {
+    faults: [Fault; 4],
+    counters: [Counters; 4],
+    rng: Mutex<StdRng>,
+}
+
+impl Chaos {
+    /// Parse an SVM_CHAOS spec (None when it injects nothing).
+    pub fn parse(spec: &str, seed: Option<u64>) -> Result<Option<Arc<Self>>> {
+        let mut faults = [Fault::default(); 4];
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let bad = || ExecError::validation(format!("SVM_CHAOS: cannot parse \"{item}\""));
+            let (key, value) = item.split_once('=').ok_or_else(bad)?;
+            let (target, knob) = key.trim().split_once('.').ok_or_else(bad)?;
+            let t = Target::ALL.iter().position(|t| t.as_str() == target)
+                .ok_or_else(|| ExecError::validation(format!("SVM_CHAOS: unknown target \"{target}\"")))?;
+            let value = value.trim();
+            let pct = || value.trim_end_matches('%').parse::<f64>().ok()
+                .filter(|p| (0.0..=100.0).contains(p))
+                .map(|p| p / 100.0)
+                .ok_or_else(bad);
+            let fault = &mut faults[t];
+            match (Target::ALL[t], knob) {
+                (Target::Central, "drop") => fault.fail = pct()?,
+                (Target::Central, _) => return Err(bad()),
+                (_, "fail") => fault.fail = pct()?,
+                (_, "delay") => fault.delay = pct()?,
+                (_, "delay_ms") => fault.delay_for = Duration::from_millis(value.parse().map_err(|_| bad())?),
+                (_, "error") => {
+                    fault.error = serde_json::from_value(serde_json::Value::String(value.to_ascii_uppercase()))
+                        .map_err(|_| bad())?;
+                }
+                _ => return Err(bad()),
+            }
+        }
+        if faults.iter().all(|f| f.fail == 0.0 && f.delay == 0.0) {
+            return Ok(None);
+        }
+        let rng = match seed {
+            Some(s) => StdRng::seed_from_u64(s),
+            None => StdRng::from_entropy(),
+        };
+        Ok(Some(Arc::new(Self { faults, counters: Default::default(), rng: Mutex::new(rng) })))
+    }
+
+    /// Chaos configured by SVM_CHAOS, if any.
+    pub fn from_config(config: &Config) -> Result<Option<Arc<Self>>> {
+        let Some(spec) = &config.chaos else {
+            return Ok(None);
+        };
+        let chaos = Self::parse(spec, config.chaos_seed)?;
+        if chaos.is_some() {
+            warn!("[Chaos] fault injection ENABLED ({spec}) — test use only");
+        }
+        Ok(chaos)
+    }
+
+    fn roll(&self, p: f64) -> bool {
+        p > 0.0 && self.rng.lock().unwrap_or_else(|e| e.into_inner()).gen_bool(p)
+    }
+
+    /// Maybe delay, then maybe fail, the `target` operation about to run.
+    pub async fn inject(&self, target: Target, what: &str) -> Result<()> {
+        let t = target as usize;
+        let fault = &self.faults[t];
+        if self.roll(fault.delay) {
+            self.counters[t].delayed.fetch_add(1, Ordering::Relaxed);
+            debug!("[Chaos] delaying {what} by {:?}", fault.delay_for);
+            tokio::time::sleep(fault.delay_for).await;
+        }
+        if self.roll(fault.fail) {
+            self.counters[t].failed.fetch_add(1, Ordering::Relaxed);
+            warn!("[Chaos] failing {what} ({})", fault.error);
+            return Err(ExecError::new(fault.error, format!("chaos: injected {} failure for {what}", fault.error)).into());
+        }
+        Ok(())
+    }
+
+    /// Whether to drop the central connection at this keepalive tick.
+    pub fn drop_central(&self) -> bool {
+        let t = Target::Central as usize;
+        let drop = self.roll(self.faults[t].fail);
+        if drop {
+            self.counters[t].failed.fetch_add(1, Ordering::Relaxed);
+        }
+        drop
+    }
+}
+
+impl MetricsCollector for Chaos {
+    fn prometheus(&self, node_id: &str) -> String {
+        let mut out = String::from(
+            "# HELP eyeflow_chaos_injected_total Faults injected by SVM_CHAOS\n\
+             # TYPE eyeflow_chaos_injected_total counter\n",
+        );
+        for t in Target::ALL {
+            let c = &self.counters[t as usize];
+            for (fault, n) in [("fail", &c.failed), ("delay", &c.delayed)] {
+                out.push_str(&format!(
+                    "eyeflow_chaos_injected_total{{node_id=\"{node_id}\",target=\"{}\",fault=\"{fault}\"}} {}\n",
+                    t.as_str(),
+                    n.load(Ordering::Relaxed),
+                ));
+            }
+        }
+        out
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_spec_and_injection() {
+        assert!(Chaos::parse("", None).unwrap().is_none());
+        assert!(Chaos::parse("call_service.fail=0", None).unwrap().is_none());
+        assert!(Chaos::parse("mqtt.fail=10", None).is_err());
+        assert!(Chaos::parse("vault.fail=150", None).is_err());
+        assert!(Chaos::parse("central.delay=10", None).is_err());
+
+        let chaos = Chaos::parse("call_service.fail=100,call_service.error=timeout, llm_call.delay=100%,llm_call.delay_ms=1", Some(1))
+            .unwrap().unwrap();
+        let err = chaos.inject(Target::CallService, "CALL_SERVICE #3").await.unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::Timeout);
+        assert!(err.to_string().starts_with("chaos:"));
+        chaos.inject(Target::LlmCall, "LLM_CALL #4").await.unwrap();
+        chaos.inject(Target::Vault, "sap/api_key").await.unwrap();
+        assert!(!chaos.drop_central());
+        let metrics = chaos.prometheus("n1");
+        assert!(metrics.contains("target=\"call_service\",fault=\"fail\"} 1"));
+        assert!(metrics.contains("target=\"llm_call\",fault=\"delay\"} 1"));
+    }
+}
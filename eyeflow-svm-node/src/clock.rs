@@ -0,0 +1,73 @@
+//! Clock drift against central — measured from the PING/PONG keepalive
+//!
+//! Audit timestamps and signature validity windows are wall-clock based, and
+//! edge devices without NTP drift badly.  Central's PING carries its clock:
+//!
+//!   { "type": "PING", "payload": { "serverTime": <epoch ms>, "rttMs": <last RTT seen by central> } }
+//!
+//! and the node estimates offset = serverTime + rtt/2 − local time.  As in
+//! NTP, the sample with the lowest RTT among the last few wins (least
+//! queueing noise).  The PONG echoes `serverTime` with the node's own clock,
+//! so central can measure the RTT and the offset from its side too.
+//!
+//! The offset is exposed in /health and /metrics, logged when it crosses
+//! SVM_CLOCK_DRIFT_WARN_MS, and — with SVM_AUDIT_CLOCK_OFFSET=true — stamped
+//! on every audit event (`clockOffsetMs`, outside self_hash).
+
+use std::collections::VecDeque;
+
+/// Samples considered for the min-RTT filter.
+const WINDOW: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    offset_ms: i64,
+    rtt_ms: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct ClockSync {
+    samples: VecDeque<Sample>,
+}
+
+impl ClockSync {
+    /// Add a sample taken at `local_ms`; returns the current offset estimate
+    /// (positive = central is ahead of this node).
+    pub fn record(&mut self, server_ms: i64, rtt_ms: Option<u64>, local_ms: i64) -> i64 {
+        let rtt_ms = rtt_ms.unwrap_or(0);
+        if self.samples.len() == WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample {
+            offset_ms: server_ms + (rtt_ms / 2) as i64 - local_ms,
+            rtt_ms,
+        });
+        self.offset_ms().unwrap_or(0)
+    }
+
+    pub fn offset_ms(&self) -> Option<i64> {
+        self.samples.iter().min_by_key(|s| s.rtt_ms).map(|s| s.offset_ms)
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_min_rtt_sample_wins() {
+        let mut c = ClockSync::default();
+        assert_eq!(c.offset_ms(), None);
+        // Central 5 s ahead; a noisy high-RTT sample is outvoted by a clean one
+        assert_eq!(c.record(105_000 + 400, Some(2_000), 100_000), 6_400);
+        assert_eq!(c.record(205_000 - 10, Some(20), 200_000), 5_000);
+        assert_eq!(c.record(305_900, Some(1_000), 300_000), 5_000);
+        // Old samples age out of the window
+        for i in 0..WINDOW as i64 {
+            c.record(400_000 + i + 1_000, Some(100), 400_000 + i);
+        }
+        assert_eq!(c.offset_ms(), Some(1_050));
+    }
+}
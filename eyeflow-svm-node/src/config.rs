@@ -1,4 +1,6 @@
-/// Configuration — loaded from environment variables / .env file (spec §8.4)
+//! Configuration — loaded from environment variables / .env file (spec §8.4)
+
+use std::collections::BTreeMap;
 use std::env;
 
 #[derive(Debug, Clone)]
@@ -7,6 +9,14 @@ pub struct Config {
     pub node_id: String,
     /// Tier this node belongs to: CENTRAL | LINUX | MCU | ANY
     pub node_tier: String,
+    /// Node labels matched against IR node_selector (SVM_NODE_LABELS="site=a,zone=3")
+    pub node_labels: BTreeMap<String, String>,
+    /// Local capabilities beyond this build's features, e.g. attached hardware
+    /// (SVM_NODE_CAPABILITIES="modbus,gpio"); advertised and matched against
+    /// instruction `requires` operands
+    pub node_capabilities: Vec<String>,
+    /// Re-probe devices / brokers every N seconds (0 = at start-up only)
+    pub capability_probe_secs: u64,
     /// WebSocket URL of the NestJS central node (spec §8.2)
     pub central_ws_url: String,
     /// HTTP base URL of the central node (for REST health + logs)
@@ -15,14 +25,76 @@ pub struct Config {
     pub auth_token: String,
     /// Ed25519 private key PEM — used for audit event signatures
     pub signing_private_key_pem: Option<String>,
+    /// mTLS client certificate chain (PEM) for the central link (unset = no client cert)
+    pub tls_cert_path: Option<String>,
+    /// Private key of `tls_cert_path` (PKCS#8 PEM)
+    pub tls_key_path: Option<String>,
+    /// Extra CA bundle trusted for central's certificate (PEM)
+    pub tls_ca_path: Option<String>,
+    /// Renew the client certificate when it expires within this many seconds
+    pub tls_renew_before_secs: u64,
+    /// Seconds between client certificate expiry / on-disk change checks
+    pub tls_check_secs: u64,
+    /// Keystore holding the node key, created on first boot (see provision.rs)
+    pub keystore_path: Option<String>,
+    /// One-time bootstrap token sent with the enrollment request
+    pub enrollment_token: Option<String>,
+    /// Seconds between enrollment requests while quarantined
+    pub enrollment_poll_secs: u64,
+    /// Pinned ops public key (SPKI PEM or hex) — CONFIG_UPDATE must be signed by it
+    pub config_ops_public_key: Option<String>,
+    /// `issuedAt` of the last applied signed CONFIG_UPDATE, kept across restarts
+    pub config_update_state_path: String,
     /// Path for the offline buffer file (spec §8.3)
     pub offline_buffer_path: String,
     /// Maximum number of events in the offline buffer
     pub offline_buffer_max: usize,
-    /// Reconnect interval in seconds when central node is unreachable
+    /// Autosave the offline buffer every N seconds while dirty (0 = disabled)
+    pub offline_autosave_secs: u64,
+    /// Also persist after every N enqueued events (0 = disabled)
+    pub offline_persist_every: usize,
+    /// Undelivered security alerts kept in the offline buffer's alert queue
+    pub alert_queue_max: usize,
+    /// Collect security alerts for this long before sending an ALERT frame
+    pub alert_batch_ms: u64,
+    /// Free bytes below which buffer / audit disks are under pressure (0 = no watchdog)
+    pub disk_pressure_bytes: u64,
+    /// Free-space check interval of the disk watchdog (seconds)
+    pub disk_check_secs: u64,
+    /// Base reconnect interval in seconds when central node is unreachable
     pub reconnect_interval_secs: u64,
+    /// Cap on the jittered exponential reconnect backoff (seconds)
+    pub reconnect_max_secs: u64,
+    /// How long to wait for central's REGISTER challenge (0 = don't wait)
+    pub register_challenge_timeout_ms: u64,
+    /// Accept central's offer of the compact AUDIT_FLUSH encoding
+    pub flush_compact: bool,
+    /// Largest WebSocket message accepted (bytes)
+    pub ws_max_message_bytes: usize,
+    /// Largest single WebSocket frame accepted (bytes)
+    pub ws_max_frame_bytes: usize,
+    /// Deepest JSON nesting accepted in a text frame
+    pub ws_max_json_depth: usize,
+    /// Ping central every N seconds to detect dead connections (0 = disabled)
+    pub keepalive_interval_secs: u64,
+    /// Reconnect when a keepalive ping goes unanswered this long (seconds)
+    pub keepalive_timeout_secs: u64,
     /// Log level (TRACE | DEBUG | INFO | WARN | ERROR)
     pub log_level: String,
+    /// Log line format: compact | json
+    pub log_format: String,
+    /// Also log to this file, rotated (unset = stdout only)
+    pub log_file: Option<String>,
+    /// Time-based rotation of the log file: never | hourly | daily
+    pub log_rotation: String,
+    /// Size-based rotation of the log file (bytes, 0 = none)
+    pub log_max_bytes: u64,
+    /// Rotated log files kept
+    pub log_max_files: usize,
+    /// Warn when the clock offset to central exceeds this (ms)
+    pub clock_drift_warn_ms: u64,
+    /// Stamp the measured clock offset on audit events
+    pub audit_clock_offset: bool,
 
     // ── Vault configuration (spec §6.1 + §13.2) ───────────────────────────
     /// HashiCorp Vault address (e.g. "http://vault:8200")
@@ -31,13 +103,378 @@ pub struct Config {
     pub vault_token: Option<String>,
     /// HashiCorp Vault namespace (Enterprise feature; empty for OSS)
     pub vault_namespace: Option<String>,
+    /// KV engine version of mounts not in vault_kv_mounts (1 or 2)
+    pub vault_kv_version: u8,
+    /// KV mounts as `mount[=1|2]` (may contain '/'; matched longest first)
+    pub vault_kv_mounts: Vec<String>,
+    /// Resolve a slice's Vault paths concurrently before it runs
+    pub vault_prefetch: bool,
+    /// Consecutive unreachable lookups after which instructions needing a
+    /// Vault secret fail up front with VAULT_UNAVAILABLE (0 = never)
+    pub vault_prefail_after: u32,
+    /// While pre-failing, let one lookup through to Vault this often (seconds)
+    pub vault_probe_secs: u64,
 
     // IR version compatibility (spec §5.3)
     pub ir_version_major: u32,
+    /// Ed25519 key IR artifacts must be signed with (None = SVM_CONFIG_OPS_PUBLIC_KEY)
+    pub ir_public_key: Option<String>,
+    /// Development only: run unsigned and format-version-0 artifacts, bare
+    /// base64 IR payloads included
+    pub allow_unsigned_ir: bool,
+
+    // ── IR decode limits ───────────────────────────────────────────────────
+    /// Maximum encoded artifact size (bytes)
+    pub ir_max_bytes: usize,
+    /// Maximum instructions per artifact
+    pub ir_max_instructions: usize,
+    /// Maximum size of a single operands_json (bytes)
+    pub ir_max_operand_bytes: usize,
+    /// Maximum few-shot examples per instruction
+    pub ir_max_few_shot: usize,
+
+    // ── IR allow-list ──────────────────────────────────────────────────────
+    /// File of approved artifact checksums, one per line (None = none provisioned)
+    pub ir_allowlist_file: Option<String>,
+    /// "enforce" | "audit" (run unlisted artifacts, alert only) | "off"
+    pub ir_allowlist_mode: Option<String>,
 
     // ── HealthMonitor (spec §8) ────────────────────────────────────────────
     /// TCP port for the /health, /metrics, /ready HTTP endpoints (default: 9090)
     pub health_port: u16,
+    /// Bearer token for authenticated endpoints such as /results (unset = disabled)
+    pub health_api_token: Option<String>,
+    /// Base URL services use to reach this listener for CALL_ACTION callbacks (unset = disabled)
+    pub callback_base_url: Option<String>,
+    /// Largest accepted callback body (bytes)
+    pub callback_max_bytes: usize,
+    /// Push metrics here too, for nodes that cannot be scraped (unset = pull only)
+    pub metrics_push_url: Option<String>,
+    /// "pushgateway" | "remote_write"
+    pub metrics_push_mode: String,
+    /// Seconds between metric pushes
+    pub metrics_push_interval_secs: u64,
+    /// Vault path of the push credentials ("user:password" or a bearer token)
+    pub metrics_push_vault_path: Option<String>,
+    /// Workflows with their own execution counters (LRU, 0 = off)
+    pub workflow_metrics_max: usize,
+
+    // ── Slice scheduling ───────────────────────────────────────────────────
+    /// IR_DISTRIBUTIONs queued while a slice runs (least urgent refused beyond)
+    pub sched_queue_max: usize,
+    /// Waiting this long raises a queued slice one priority level (0 = no aging)
+    pub sched_aging_ms: u64,
+
+    // ── Result retention ───────────────────────────────────────────────────
+    /// SliceExecutionResults kept locally (0 = retention off)
+    pub results_max: usize,
+    /// Age after which a retained result is discarded (seconds)
+    pub results_retention_secs: u64,
+    /// Snapshot file for retained results (unset = memory only)
+    pub results_path: Option<String>,
+
+    // ── Session resume ─────────────────────────────────────────────────────
+    /// Snapshot file for the sent-but-unacked result ledger (unset = memory only)
+    pub session_path: Option<String>,
+    /// Unacked results kept for central's RESUME (0 = off)
+    pub session_max_unacked: usize,
+
+    // ── Audit log (export) ─────────────────────────────────────────────────
+    /// Append-only NDJSON of every audit event shipped (unset = no export)
+    pub audit_log_path: Option<String>,
+    /// Audit chain + log self-test interval (seconds, 0 = before drains only; audit_verify.rs)
+    pub audit_verify_secs: u64,
+    /// Per event-type sampling / roll-up, JSON (unset = every event; see audit_policy.rs)
+    pub audit_policy: Option<String>,
+    /// SECRET_ACCESS audit event per secret lookup (secret_audit.rs)
+    pub audit_secret_access: bool,
+    /// Scrub values resolved from secrets out of audit, errors and registers (taint.rs)
+    pub secret_taint: bool,
+
+    // ── Distributed resource locks (spec §6.5) ─────────────────────────────
+    /// Redis instances for Redlock (empty = local arbitration only)
+    pub dlock_redis_urls: Vec<String>,
+    /// Lease duration; renewed every ttl/3 while held (ms)
+    pub dlock_ttl_ms: u64,
+
+    // ── Service catalogue (spec §6.1) ──────────────────────────────────────
+    /// Path of the persisted service catalogue snapshot
+    pub catalog_path: String,
+    /// Interval between catalogue pulls from central (0 = push-only)
+    pub catalog_sync_interval_secs: u64,
+
+    // ── Plan-scoped shared state ───────────────────────────────────────────
+    /// Lifetime of a plan context after its last write (seconds)
+    pub plan_state_ttl_secs: u64,
+    /// Snapshot file for plan contexts (unset = memory only)
+    pub plan_state_path: Option<String>,
+
+    // ── Warm standby ───────────────────────────────────────────────────────
+    /// Run as one of a leader/standby pair
+    pub standby_enabled: bool,
+    /// Pair identifier shared by both nodes
+    pub standby_group: String,
+    /// Leader lease backend: redis | file | central
+    pub standby_lease: String,
+    /// Lease file on a shared volume (file backend)
+    pub standby_lease_path: Option<String>,
+    /// Leader lease duration; failover takes up to this long (ms)
+    pub standby_lease_ttl_ms: u64,
+    /// Shared directory for artifact handoff (unset = no handoff)
+    pub standby_handoff_dir: Option<String>,
+
+    // ── Action write-ahead log ─────────────────────────────────────────────
+    /// Intent/completion journal for CALL_ACTION / CALL_SERVICE
+    pub action_wal_path: String,
+
+    // ── Parallel fan-out ───────────────────────────────────────────────────
+    /// Branches of a PARALLEL_SPAWN in flight at once, unless the SPAWN sets
+    /// `maxConcurrency` (0 = unlimited)
+    pub parallel_max_concurrency: usize,
+
+    // ── Prepared-IR cache ──────────────────────────────────────────────────
+    /// Decoded artifacts kept for reuse, keyed by payload SHA-256 (0 = disabled)
+    pub ir_cache_size: usize,
+    /// Nesting limit of CALL_SLICE sub-workflows
+    pub call_slice_max_depth: usize,
+
+    // ── Register memory ────────────────────────────────────────────────────
+    /// Ceiling on the serialized size of a slice's register file (0 = unlimited)
+    pub register_memory_max_bytes: usize,
+
+    // ── Binary registers ───────────────────────────────────────────────────
+    /// Largest binary body kept as a blob register (bytes)
+    pub blob_max_bytes: usize,
+    /// Ceiling on all blobs held; least recently used are evicted (bytes)
+    pub blob_store_max_bytes: usize,
+
+    // ── Tabular transforms ─────────────────────────────────────────────────
+    /// Largest CSV / NDJSON text a TRANSFORM parses or produces (bytes)
+    pub tabular_max_bytes: usize,
+    /// Most rows / lines a TRANSFORM parses or produces
+    pub tabular_max_rows: usize,
+
+    // ── Record / replay ────────────────────────────────────────────────────
+    /// Write every slice's external responses here (unset = off)
+    pub record_dir: Option<String>,
+    /// Answer external dispatches from this recording instead of calling out
+    pub replay_file: Option<String>,
+
+    // ── Peer delegation ────────────────────────────────────────────────────
+    /// Peers that run what this node cannot (SVM_PEERS="node-b=ws://10.0.0.7:9190")
+    pub peers: BTreeMap<String, String>,
+    /// Ed25519 public keys (hex) of those peers (SVM_PEER_KEYS="node-b=<hex>")
+    pub peer_keys: BTreeMap<String, String>,
+    /// Port accepting delegations from peers (0 = not accepting)
+    pub peer_port: u16,
+    /// Deadline for a delegated instruction or slice (ms)
+    pub peer_timeout_ms: u64,
+
+    // ── Tenant quotas (defaults; CONFIG_UPDATE tenantQuotas overrides) ─────
+    /// Slices a single tenant may run at once (0 = unlimited)
+    pub tenant_max_concurrent: usize,
+    /// Slices a single tenant may start per minute (0 = unlimited)
+    pub tenant_rate_per_min: u32,
+    /// Offline buffer events kept per tenant (0 = only OFFLINE_BUFFER_MAX)
+    pub offline_tenant_max_events: usize,
+
+    // ── Data lineage ───────────────────────────────────────────────────────
+    /// Attach per-register lineage to SliceExecutionResult
+    pub lineage_enabled: bool,
+
+    // ── PII masking (workflows override with pii_policy) ───────────────────
+    /// Built-in patterns masked by default: "email", "card"
+    pub pii_mask: Vec<String>,
+    /// Field paths masked by default, e.g. "$.customer.ssn"
+    pub pii_paths: Vec<String>,
+
+    // ── Local filesystem resources ─────────────────────────────────────────
+    /// Sandbox root for `file://` resources (unset = file:// disabled)
+    pub fs_sandbox_root: Option<String>,
+    /// Maximum size of a single file read via `file://` (bytes)
+    pub fs_max_bytes: u64,
+
+    // ── Local devices (gpio:// serial:// ble://) ──────────────────────────────────
+    /// Device path prefixes CALL_ACTION may open (empty = devices disabled)
+    pub device_allow: Vec<String>,
+    /// HCI adapter index for ble:// resources (0 = hci0)
+    pub ble_adapter: u16,
+    /// Scan deadline when a ble:// URL names no address (ms)
+    pub ble_scan_timeout_ms: u64,
+    /// Follow kernel uevents for device plug / unplug (hotplug.rs)
+    pub device_hotplug: bool,
+    /// How long an instruction waits for an unplugged device to be back (ms, 0 = fail at once)
+    pub device_wait_ms: u64,
+
+    // ── Cameras (rtsp:// camera+http://) ──────────────────────────────────
+    /// ffmpeg binary grabbing RTSP / MJPEG frames
+    pub ffmpeg_path: String,
+    /// Deadline of one snapshot (ms)
+    pub camera_timeout_ms: u64,
+
+    // ── SNMP (snmp://) ─────────────────────────────────────────────────────
+    /// Wait for one SNMP response (ms)
+    pub snmp_timeout_ms: u64,
+    /// Resends of an unanswered SNMP request
+    pub snmp_retries: u32,
+    /// Varbinds one SNMP walk may return
+    pub snmp_max_varbinds: usize,
+
+    // ── MCU bridge (mcu://) ─────────────────────────────────────────────────
+    /// ttys of downstream MCUs, `path[@baud]`, trailing `*` = enumerate (empty = bridge off)
+    pub mcu_ports: Vec<String>,
+    /// Wait for an MCU's RESULT (ms)
+    pub mcu_timeout_ms: u64,
+
+    // ── Kafka sink ─────────────────────────────────────────────────────────
+    /// Bootstrap brokers host:port (empty = Kafka disabled)
+    pub kafka_brokers: Vec<String>,
+    /// Mirror audit events to this topic
+    pub kafka_audit_topic: Option<String>,
+    /// all | leader
+    pub kafka_acks: String,
+    /// Per-request broker timeout (ms)
+    pub kafka_timeout_ms: u64,
+    /// Records waiting for a reachable broker
+    pub kafka_buffer_path: String,
+    /// Redelivery interval while brokers are down (seconds)
+    pub kafka_retry_secs: u64,
+    /// PLAINTEXT | SSL | SASL_PLAINTEXT | SASL_SSL
+    pub kafka_security_protocol: String,
+    /// PLAIN | SCRAM-SHA-256 | SCRAM-SHA-512
+    pub kafka_sasl_mechanism: String,
+    pub kafka_sasl_username: Option<String>,
+    pub kafka_sasl_password: Option<String>,
+    /// CA bundle trusted for the brokers' certificates (PEM)
+    pub kafka_ssl_ca_path: Option<String>,
+
+    // ── Zigbee / Z-Wave over MQTT (zigbee:// zwave://) ─────────────────────
+    /// mqtt://[user:password@]host[:port] of the bridges' broker (None = disabled)
+    pub mqtt_url: Option<String>,
+    /// Wait for a PUBACK, device state or gateway API reply (ms)
+    pub mqtt_timeout_ms: u64,
+    /// zigbee2mqtt base topic
+    pub zigbee2mqtt_topic: String,
+    /// Z-Wave JS UI topic prefix
+    pub zwave_topic: String,
+    /// Z-Wave JS UI gateway name (MQTT API topics)
+    pub zwave_gateway: String,
+
+    // ── SMS / voice alerts (sms:// voice://) ──────────────────────────────
+    /// Page sent when SUPERVISED_RECOMPILE triggers (None = only per-instruction `alert`)
+    pub alert_url: Option<String>,
+    /// Vault path of the provider credentials for `alert_url`
+    pub alert_vault_path: Option<String>,
+    /// Minimum interval between pages for the same workflow step (seconds)
+    pub alert_cooldown_secs: u64,
+
+    // ── Redis shared state ─────────────────────────────────────────────────
+    /// redis:// URL for STORE_MEMORY namespaces and the response cache (None = disabled)
+    pub redis_url: Option<String>,
+    /// Prefix of every key this node writes
+    pub redis_prefix: String,
+    /// Per-command timeout (ms)
+    pub redis_timeout_ms: u64,
+    /// In-memory response cache entries when Redis is not configured (0 = disabled)
+    pub response_cache_size: usize,
+
+    // ── HTTP response cache ────────────────────────────────────────────────
+    /// Cached CALL_SERVICE / LOAD_RESOURCE answers held in memory (0 = disabled)
+    pub http_cache_size: usize,
+    /// Also persist them here (None = memory only)
+    pub http_cache_dir: Option<String>,
+
+    // ── Health score ───────────────────────────────────────────────────────
+    /// Weights / limits JSON (health_score.rs; None = defaults)
+    pub health_thresholds: Option<String>,
+    /// Filesystem whose free space is scored (None = the offline buffer's)
+    pub health_disk_path: Option<String>,
+
+    // ── Uplink shaping ─────────────────────────────────────────────────────
+    /// Upstream token-bucket rate (bytes/s, 0 = unlimited)
+    pub uplink_bytes_per_sec: u64,
+    /// Bucket size (bytes, 0 = one second of rate)
+    pub uplink_burst_bytes: u64,
+    /// Events per AUDIT_FLUSH frame while shaped
+    pub uplink_flush_chunk: usize,
+    /// HTTP request bodies at least this large are shaped (bytes)
+    pub uplink_bulk_min_bytes: usize,
+
+    // ── Host resolution ────────────────────────────────────────────────────
+    /// Static `name=ip[|ip…]` entries, ahead of DNS
+    pub dns_hosts: Vec<String>,
+    /// hosts(5)-format file merged into them
+    pub dns_hosts_file: Option<String>,
+    /// Keep DNS answers this long (s, 0 = no cache)
+    pub dns_cache_ttl_secs: u64,
+
+    // ── Chaos mode (test benches only) ─────────────────────────────────────
+    /// Fault-injection spec, e.g. "call_service.fail=20,central.drop=5" (None = off)
+    pub chaos: Option<String>,
+    /// Seed for reproducible injections (None = random)
+    pub chaos_seed: Option<u64>,
+
+    // ── SQL connector (feature `sql`) ──────────────────────────────────────
+    /// Connections per database pool (also the ResourceArbiter capacity)
+    #[cfg_attr(not(feature = "sql"), allow(dead_code))]
+    pub sql_pool_size: u32,
+
+    // ── S3 / object storage ────────────────────────────────────────────────
+    /// S3-compatible endpoint (e.g. "http://minio:9000"); None = AWS
+    pub s3_endpoint: Option<String>,
+    /// Signing region (default "us-east-1")
+    pub s3_region: String,
+    /// Maximum object size streamed by LOAD_RESOURCE (bytes)
+    pub s3_max_bytes: u64,
+    /// Directory for objects fetched with `"target": "file"`
+    pub s3_temp_dir: String,
+
+    // ── Connector plugins (feature `plugins`) ──────────────────────────────
+    /// Directory of connector plugin libraries (`*.so`); None = no plugins
+    pub plugin_dir: Option<String>,
+
+    // ── Signed assets (ASSET_DISTRIBUTION) ─────────────────────────────────
+    /// Asset cache, files named by checksum
+    pub asset_dir: String,
+    /// Ed25519 key assets must be signed with (None = SVM_CONFIG_OPS_PUBLIC_KEY)
+    pub asset_public_key: Option<String>,
+    /// Largest asset accepted (bytes)
+    pub asset_max_bytes: u64,
+
+    // ── ONNX inference (feature `onnx`) ────────────────────────────────────
+    /// Loaded models kept in memory
+    #[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+    pub onnx_max_sessions: usize,
+
+    // ── Self-update (RELEASE) ──────────────────────────────────────────────
+    /// Accept signed RELEASE manifests from central
+    pub update_enabled: bool,
+    /// Ed25519 key releases must be signed with (None = SVM_CONFIG_OPS_PUBLIC_KEY)
+    pub update_public_key: Option<String>,
+    /// Binary replaced by an update (None = the running executable)
+    pub update_binary_path: Option<String>,
+    /// Largest release binary accepted (bytes)
+    pub update_max_bytes: u64,
+    /// Seconds without a slice before a staged release is applied
+    pub update_idle_secs: u64,
+    /// Window in which a new version must prove healthy (seconds)
+    pub update_probation_secs: u64,
+    /// Seconds /ready must hold in a row to confirm a new version
+    pub update_healthy_secs: u64,
+    /// Starts of a new version before it is rolled back as crashing
+    pub update_max_boots: u32,
+    /// "exec" (replace the process) or "systemd" (exit, the unit restarts it)
+    pub update_restart: String,
+
+    // ── Instruction middleware ─────────────────────────────────────────────
+    /// Built-in middlewares, in order, e.g. "opcode_metrics,deny_opcodes=LLM_CALL"
+    pub middleware: Vec<String>,
+
+    // ── CPU budgets ────────────────────────────────────────────────────────
+    /// Opcodes run on the blocking pool within a budget, e.g. "TRANSFORM=1000" (ms)
+    pub cpu_budgets: Vec<String>,
+    /// Yield to the scheduler after this long without doing so (0 = never)
+    pub yield_interval_ms: u64,
 }
 
 impl Config {
@@ -52,40 +489,621 @@ impl Config {
         Config {
             node_id,
             node_tier: env::var("SVM_NODE_TIER").unwrap_or_else(|_| "LINUX".into()),
+            node_labels: env::var("SVM_NODE_LABELS")
+                .map(|v| crate::placement::parse_labels(&v))
+                .unwrap_or_default(),
+            node_capabilities: env::var("SVM_NODE_CAPABILITIES")
+                .map(|v| v.split(',').map(|c| c.trim().to_owned()).filter(|c| !c.is_empty()).collect())
+                .unwrap_or_default(),
+            capability_probe_secs: env::var("SVM_CAPABILITY_PROBE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
             central_ws_url: env::var("CENTRAL_WS_URL")
                 .unwrap_or_else(|_| "ws://localhost:3000/nodes".into()),
             central_http_url: env::var("CENTRAL_HTTP_URL")
                 .unwrap_or_else(|_| "http://localhost:3000".into()),
             auth_token: env::var("SVM_AUTH_TOKEN").unwrap_or_default(),
             signing_private_key_pem: env::var("SVM_SIGNING_PRIVATE_KEY_PEM").ok(),
+            tls_cert_path: env::var("SVM_TLS_CERT_PATH").ok().filter(|p| !p.is_empty()),
+            tls_key_path: env::var("SVM_TLS_KEY_PATH").ok().filter(|p| !p.is_empty()),
+            tls_ca_path: env::var("SVM_TLS_CA_PATH").ok().filter(|p| !p.is_empty()),
+            tls_renew_before_secs: env::var("SVM_TLS_RENEW_BEFORE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(7 * 86_400),
+            tls_check_secs: env::var("SVM_TLS_CHECK_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3_600),
+            keystore_path: env::var("SVM_KEYSTORE_PATH").ok().filter(|p| !p.is_empty()),
+            enrollment_token: env::var("SVM_ENROLLMENT_TOKEN").ok().filter(|t| !t.is_empty()),
+            enrollment_poll_secs: env::var("SVM_ENROLLMENT_POLL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
+            config_ops_public_key: env::var("SVM_CONFIG_OPS_PUBLIC_KEY").ok(),
+            config_update_state_path: env::var("SVM_CONFIG_UPDATE_STATE_PATH")
+                .unwrap_or_else(|_| "/tmp/eyeflow_svm_config_update.json".into()),
+
             offline_buffer_path: env::var("OFFLINE_BUFFER_PATH")
                 .unwrap_or_else(|_| "/tmp/eyeflow_svm_offline.ndjson".into()),
             offline_buffer_max: env::var("OFFLINE_BUFFER_MAX")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(10_000),
+            offline_autosave_secs: env::var("OFFLINE_BUFFER_AUTOSAVE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
+            offline_persist_every: env::var("OFFLINE_BUFFER_PERSIST_EVERY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100),
+            alert_queue_max: env::var("SVM_ALERT_QUEUE_MAX")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1_000),
+            alert_batch_ms: env::var("SVM_ALERT_BATCH_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(250),
+            disk_pressure_bytes: env::var("SVM_DISK_PRESSURE_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(128 << 20),
+            disk_check_secs: env::var("SVM_DISK_CHECK_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(15),
             reconnect_interval_secs: env::var("RECONNECT_INTERVAL_SECS")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(15),
+            reconnect_max_secs: env::var("SVM_RECONNECT_MAX_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+            register_challenge_timeout_ms: env::var("SVM_REGISTER_CHALLENGE_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5_000),
+            flush_compact: env::var("SVM_FLUSH_COMPACT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(true),
+            ws_max_message_bytes: env::var("SVM_WS_MAX_MESSAGE_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(16 << 20),
+            ws_max_frame_bytes: env::var("SVM_WS_MAX_FRAME_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(4 << 20),
+            ws_max_json_depth: env::var("SVM_WS_MAX_JSON_DEPTH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(32),
+            keepalive_interval_secs: env::var("SVM_KEEPALIVE_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(15),
+            keepalive_timeout_secs: env::var("SVM_KEEPALIVE_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10),
             log_level: env::var("RUST_LOG").unwrap_or_else(|_| "info".into()),
+            log_format: env::var("SVM_LOG_FORMAT").unwrap_or_else(|_| "compact".into()),
+            log_file: env::var("SVM_LOG_FILE").ok().filter(|p| !p.is_empty()),
+            log_rotation: env::var("SVM_LOG_ROTATION").unwrap_or_else(|_| "daily".into()),
+            log_max_bytes: env::var("SVM_LOG_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10 << 20),
+            log_max_files: env::var("SVM_LOG_MAX_FILES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(7),
+            clock_drift_warn_ms: env::var("SVM_CLOCK_DRIFT_WARN_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2_000),
+            audit_clock_offset: env::var("SVM_AUDIT_CLOCK_OFFSET")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
 
             // Vault (spec §6.1 + §13.2)
             vault_addr:      env::var("VAULT_ADDR").ok(),
             vault_token:     env::var("VAULT_TOKEN").ok(),
             vault_namespace: env::var("VAULT_NAMESPACE").ok(),
+            vault_kv_version: env::var("VAULT_KV_VERSION")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2),
+            vault_kv_mounts: env::var("VAULT_KV_MOUNTS")
+                .map(|v| v.split(',').map(|s| s.trim().to_owned()).filter(|s| !s.is_empty()).collect())
+                .unwrap_or_default(),
+            vault_prefetch: env::var("SVM_VAULT_PREFETCH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(true),
+            vault_prefail_after: env::var("SVM_VAULT_PREFAIL_AFTER")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            vault_probe_secs: env::var("SVM_VAULT_PROBE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
 
             // IR version compatibility (spec §5.3)
             ir_version_major: env::var("SVM_IR_VERSION_MAJOR")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(1),
+            ir_public_key: env::var("SVM_IR_PUBLIC_KEY").ok().filter(|s| !s.is_empty()),
+            allow_unsigned_ir: env::var("SVM_ALLOW_UNSIGNED_IR")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+
+
+            // IR decode limits
+            ir_max_bytes: env::var("SVM_IR_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(8 * 1024 * 1024),
+            ir_max_instructions: env::var("SVM_IR_MAX_INSTRUCTIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000),
+            ir_max_operand_bytes: env::var("SVM_IR_MAX_OPERAND_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(256 * 1024),
+            ir_max_few_shot: env::var("SVM_IR_MAX_FEW_SHOT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(64),
+
+            // IR allow-list
+            ir_allowlist_file: env::var("SVM_IR_ALLOWLIST_FILE").ok().filter(|s| !s.is_empty()),
+            ir_allowlist_mode: env::var("SVM_IR_ALLOWLIST_MODE").ok().filter(|s| !s.is_empty()),
 
             // Health monitor
             health_port: env::var("SVM_HEALTH_PORT")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(9090),
+            health_api_token: env::var("SVM_HEALTH_API_TOKEN").ok().filter(|t| !t.is_empty()),
+            callback_base_url: env::var("SVM_CALLBACK_BASE_URL").ok().filter(|u| !u.is_empty()),
+            callback_max_bytes: env::var("SVM_CALLBACK_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1 << 20),
+            metrics_push_url: env::var("SVM_METRICS_PUSH_URL").ok().filter(|u| !u.is_empty()),
+            metrics_push_mode: env::var("SVM_METRICS_PUSH_MODE").unwrap_or_else(|_| "pushgateway".into()),
+            metrics_push_interval_secs: env::var("SVM_METRICS_PUSH_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            metrics_push_vault_path: env::var("SVM_METRICS_PUSH_VAULT_PATH").ok().filter(|p| !p.is_empty()),
+            workflow_metrics_max: env::var("SVM_WORKFLOW_METRICS_MAX")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(50),
+
+            // Slice scheduling
+            sched_queue_max: env::var("SVM_SCHED_QUEUE_MAX")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(256),
+            sched_aging_ms: env::var("SVM_SCHED_AGING_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100),
+
+            // Result retention
+            results_max: env::var("SVM_RESULTS_MAX")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(200),
+            results_retention_secs: env::var("SVM_RESULTS_RETENTION_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(86_400),
+            results_path: env::var("SVM_RESULTS_PATH").ok(),
+
+            // Session resume
+            session_path: env::var("SVM_SESSION_PATH").ok().filter(|p| !p.is_empty()),
+            session_max_unacked: env::var("SVM_SESSION_MAX_UNACKED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1_000),
+
+            // Audit log
+            audit_log_path: env::var("SVM_AUDIT_LOG_PATH").ok().filter(|p| !p.is_empty()),
+            audit_verify_secs: env::var("SVM_AUDIT_VERIFY_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+            audit_policy: env::var("SVM_AUDIT_POLICY").ok().filter(|p| !p.trim().is_empty()),
+            audit_secret_access: env::var("SVM_AUDIT_SECRET_ACCESS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            secret_taint: env::var("SVM_SECRET_TAINT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(true),
+
+            // Distributed resource locks
+            dlock_redis_urls: env::var("SVM_DLOCK_REDIS_URLS")
+                .map(|v| v.split(',').map(|s| s.trim().to_owned()).filter(|s| !s.is_empty()).collect())
+                .unwrap_or_default(),
+            dlock_ttl_ms: env::var("SVM_DLOCK_TTL_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000),
+
+            // Service catalogue
+            catalog_path: env::var("SVM_CATALOG_PATH")
+                .unwrap_or_else(|_| "/tmp/eyeflow_svm_catalog.json".into()),
+            catalog_sync_interval_secs: env::var("SVM_CATALOG_SYNC_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+
+            // Plan-scoped shared state
+            plan_state_ttl_secs: env::var("SVM_PLAN_STATE_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600),
+            plan_state_path: env::var("SVM_PLAN_STATE_PATH").ok(),
+
+            // Warm standby
+            standby_enabled: env::var("SVM_STANDBY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            standby_group: env::var("SVM_STANDBY_GROUP").unwrap_or_else(|_| "default".into()),
+            standby_lease: env::var("SVM_STANDBY_LEASE").unwrap_or_else(|_| "central".into()),
+            standby_lease_path: env::var("SVM_STANDBY_LEASE_PATH").ok(),
+            standby_lease_ttl_ms: env::var("SVM_STANDBY_LEASE_TTL_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000),
+            standby_handoff_dir: env::var("SVM_STANDBY_HANDOFF_DIR").ok(),
+
+            // Action write-ahead log
+            action_wal_path: env::var("SVM_ACTION_WAL_PATH")
+                .unwrap_or_else(|_| "/tmp/eyeflow_svm_actions.wal".into()),
+
+            // Parallel fan-out
+            parallel_max_concurrency: env::var("SVM_PARALLEL_MAX_CONCURRENCY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(8),
+
+            // Prepared-IR cache
+            ir_cache_size: env::var("SVM_IR_CACHE_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(64),
+            call_slice_max_depth: env::var("SVM_CALL_SLICE_MAX_DEPTH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(4),
+
+            // Register memory
+            register_memory_max_bytes: env::var("SVM_REGISTER_MEMORY_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(64 * 1024 * 1024),
+
+            // Binary registers
+            blob_max_bytes: env::var("SVM_BLOB_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(16 * 1024 * 1024),
+            blob_store_max_bytes: env::var("SVM_BLOB_STORE_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(256 * 1024 * 1024),
+
+            // Tabular transforms
+            tabular_max_bytes: env::var("SVM_TABULAR_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(8 * 1024 * 1024),
+            tabular_max_rows: env::var("SVM_TABULAR_MAX_ROWS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100_000),
+
+            // Record / replay
+            record_dir: env::var("SVM_RECORD_DIR").ok(),
+            replay_file: env::var("SVM_REPLAY_FILE").ok(),
+
+            // Peer delegation
+            peers: env::var("SVM_PEERS")
+                .map(|v| crate::placement::parse_labels(&v))
+                .unwrap_or_default(),
+            peer_keys: env::var("SVM_PEER_KEYS")
+                .map(|v| crate::placement::parse_labels(&v))
+                .unwrap_or_default(),
+            peer_port: env::var("SVM_PEER_PORT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            peer_timeout_ms: env::var("SVM_PEER_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30_000),
+
+            // Tenant quotas
+            tenant_max_concurrent: env::var("SVM_TENANT_MAX_CONCURRENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            tenant_rate_per_min: env::var("SVM_TENANT_RATE_PER_MIN")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            offline_tenant_max_events: env::var("SVM_OFFLINE_TENANT_MAX_EVENTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+
+            // Data lineage
+            lineage_enabled: env::var("SVM_LINEAGE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+
+            // PII masking
+            pii_mask: env::var("SVM_PII_MASK")
+                .map(|v| v.split(',').map(|p| p.trim().to_owned()).filter(|p| !p.is_empty()).collect())
+                .unwrap_or_default(),
+            pii_paths: env::var("SVM_PII_PATHS")
+                .map(|v| v.split(',').map(|p| p.trim().to_owned()).filter(|p| !p.is_empty()).collect())
+                .unwrap_or_default(),
+
+            // Local filesystem resources
+            fs_sandbox_root: env::var("SVM_FS_SANDBOX_ROOT").ok(),
+            fs_max_bytes: env::var("SVM_FS_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(16 * 1024 * 1024),
+
+            // Local devices
+            device_allow: env::var("SVM_DEVICE_ALLOW")
+                .map(|v| v.split(',').map(|p| p.trim().to_owned()).filter(|p| !p.is_empty()).collect())
+                .unwrap_or_default(),
+            ble_adapter: env::var("SVM_BLE_ADAPTER")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            ble_scan_timeout_ms: env::var("SVM_BLE_SCAN_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000),
+            device_hotplug: env::var("SVM_DEVICE_HOTPLUG")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(true),
+            device_wait_ms: env::var("SVM_DEVICE_WAIT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30_000),
+
+            // Cameras
+            ffmpeg_path: env::var("SVM_FFMPEG_PATH").unwrap_or_else(|_| "ffmpeg".into()),
+            camera_timeout_ms: env::var("SVM_CAMERA_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000),
+
+            // SNMP
+            snmp_timeout_ms: env::var("SVM_SNMP_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2_000),
+            snmp_retries: env::var("SVM_SNMP_RETRIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1),
+            snmp_max_varbinds: env::var("SVM_SNMP_MAX_VARBINDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000),
+
+            // MCU bridge
+            mcu_ports: env::var("SVM_MCU_PORTS")
+                .map(|v| v.split(',').map(|p| p.trim().to_owned()).filter(|p| !p.is_empty()).collect())
+                .unwrap_or_default(),
+            mcu_timeout_ms: env::var("SVM_MCU_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2_000),
+
+            // Kafka sink
+            kafka_brokers: env::var("SVM_KAFKA_BROKERS")
+                .map(|v| v.split(',').map(|b| b.trim().to_owned()).filter(|b| !b.is_empty()).collect())
+                .unwrap_or_default(),
+            kafka_audit_topic: env::var("SVM_KAFKA_AUDIT_TOPIC").ok().filter(|s| !s.is_empty()),
+            kafka_acks: env::var("SVM_KAFKA_ACKS").unwrap_or_else(|_| "all".into()),
+            kafka_timeout_ms: env::var("SVM_KAFKA_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5_000),
+            kafka_buffer_path: env::var("SVM_KAFKA_BUFFER_PATH")
+                .unwrap_or_else(|_| "/tmp/eyeflow_svm_kafka.ndjson".into()),
+            kafka_retry_secs: env::var("SVM_KAFKA_RETRY_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10),
+            kafka_security_protocol: env::var("SVM_KAFKA_SECURITY_PROTOCOL").unwrap_or_else(|_| "PLAINTEXT".into()),
+            kafka_sasl_mechanism: env::var("SVM_KAFKA_SASL_MECHANISM").unwrap_or_else(|_| "PLAIN".into()),
+            kafka_sasl_username: env::var("SVM_KAFKA_SASL_USERNAME").ok().filter(|s| !s.is_empty()),
+            kafka_sasl_password: env::var("SVM_KAFKA_SASL_PASSWORD").ok().filter(|s| !s.is_empty()),
+            kafka_ssl_ca_path: env::var("SVM_KAFKA_SSL_CA_PATH").ok().filter(|s| !s.is_empty()),
+
+
+            // Zigbee / Z-Wave over MQTT
+            mqtt_url: env::var("SVM_MQTT_URL").ok().filter(|s| !s.is_empty()),
+            mqtt_timeout_ms: env::var("SVM_MQTT_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5_000),
+            zigbee2mqtt_topic: env::var("SVM_ZIGBEE2MQTT_TOPIC").unwrap_or_else(|_| "zigbee2mqtt".into()),
+            zwave_topic: env::var("SVM_ZWAVE_TOPIC").unwrap_or_else(|_| "zwave".into()),
+            zwave_gateway: env::var("SVM_ZWAVE_GATEWAY").unwrap_or_else(|_| "zwave-js-ui".into()),
+
+            // SMS / voice alerts
+            alert_url: env::var("SVM_ALERT_URL").ok().filter(|s| !s.is_empty()),
+            alert_vault_path: env::var("SVM_ALERT_VAULT_PATH").ok().filter(|s| !s.is_empty()),
+            alert_cooldown_secs: env::var("SVM_ALERT_COOLDOWN_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(900),
+
+            // Redis shared state
+            redis_url: env::var("SVM_REDIS_URL").ok().filter(|s| !s.is_empty()),
+            redis_prefix: env::var("SVM_REDIS_PREFIX").unwrap_or_else(|_| "eyeflow:".into()),
+            redis_timeout_ms: env::var("SVM_REDIS_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1_000),
+            response_cache_size: env::var("SVM_RESPONSE_CACHE_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1_024),
+
+            // HTTP response cache
+            http_cache_size: env::var("SVM_HTTP_CACHE_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(256),
+            http_cache_dir: env::var("SVM_HTTP_CACHE_DIR").ok().filter(|s| !s.is_empty()),
+
+            // Health score
+            health_thresholds: env::var("SVM_HEALTH_THRESHOLDS").ok().filter(|s| !s.is_empty()),
+            health_disk_path: env::var("SVM_HEALTH_DISK_PATH").ok().filter(|s| !s.is_empty()),
+
+            // Uplink shaping
+            uplink_bytes_per_sec: env::var("SVM_UPLINK_BYTES_PER_SEC")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            uplink_burst_bytes: env::var("SVM_UPLINK_BURST_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            uplink_flush_chunk: env::var("SVM_UPLINK_FLUSH_CHUNK")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(200),
+            uplink_bulk_min_bytes: env::var("SVM_UPLINK_BULK_MIN_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(16 * 1024),
+
+            // Host resolution
+            dns_hosts: env::var("SVM_DNS_HOSTS")
+                .map(|v| v.split(',').map(|h| h.trim().to_owned()).filter(|h| !h.is_empty()).collect())
+                .unwrap_or_default(),
+            dns_hosts_file: env::var("SVM_DNS_HOSTS_FILE").ok().filter(|s| !s.is_empty()),
+            dns_cache_ttl_secs: env::var("SVM_DNS_CACHE_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+
+            // Chaos mode
+            chaos: env::var("SVM_CHAOS").ok().filter(|s| !s.is_empty()),
+            chaos_seed: env::var("SVM_CHAOS_SEED").ok().and_then(|v| v.parse().ok()),
+
+            // SQL connector
+            sql_pool_size: env::var("SVM_SQL_POOL_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
+
+            // S3 / object storage
+            s3_endpoint: env::var("SVM_S3_ENDPOINT").ok(),
+            s3_region: env::var("SVM_S3_REGION").unwrap_or_else(|_| "us-east-1".into()),
+            s3_max_bytes: env::var("SVM_S3_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(64 * 1024 * 1024),
+            s3_temp_dir: env::var("SVM_S3_TEMP_DIR")
+                .unwrap_or_else(|_| std::env::temp_dir().to_string_lossy().into_owned()),
+
+            // Connector plugins
+            plugin_dir: env::var("SVM_PLUGIN_DIR").ok().filter(|s| !s.is_empty()),
+
+            // Signed assets
+            asset_dir: env::var("SVM_ASSET_DIR")
+                .unwrap_or_else(|_| "/tmp/eyeflow_svm_assets".into()),
+            asset_public_key: env::var("SVM_ASSET_PUBLIC_KEY").ok().filter(|s| !s.is_empty()),
+            asset_max_bytes: env::var("SVM_ASSET_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(256 * 1024 * 1024),
+
+            // ONNX inference
+            onnx_max_sessions: env::var("SVM_ONNX_MAX_SESSIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(4),
+
+            // Self-update
+            update_enabled: env::var("SVM_UPDATE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            update_public_key: env::var("SVM_UPDATE_PUBLIC_KEY").ok().filter(|s| !s.is_empty()),
+            update_binary_path: env::var("SVM_UPDATE_BINARY_PATH").ok().filter(|s| !s.is_empty()),
+            update_max_bytes: env::var("SVM_UPDATE_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(256 * 1024 * 1024),
+            update_idle_secs: env::var("SVM_UPDATE_IDLE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            update_probation_secs: env::var("SVM_UPDATE_PROBATION_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+            update_healthy_secs: env::var("SVM_UPDATE_HEALTHY_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            update_max_boots: env::var("SVM_UPDATE_MAX_BOOTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3),
+            update_restart: env::var("SVM_UPDATE_RESTART")
+                .unwrap_or_else(|_| "exec".into()),
+
+            // Instruction middleware
+            middleware: env::var("SVM_MIDDLEWARE")
+                .map(|v| v.split(',').map(|s| s.trim().to_owned()).filter(|s| !s.is_empty()).collect())
+                .unwrap_or_default(),
+
+            // CPU budgets
+            cpu_budgets: env::var("SVM_CPU_BUDGETS")
+                .unwrap_or_else(|_| "TRANSFORM=1000".into())
+                .split(',')
+                .map(|s| s.trim().to_owned())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            yield_interval_ms: env::var("SVM_YIELD_INTERVAL_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10),
         }
     }
 }
@@ -0,0 +1,184 @@
+//! Signed CONFIG_UPDATE — live config only from the pinned ops key
+//!
+//! CONFIG_UPDATE is applied live (service catalogue, resource capacities,
+//! tenant quotas), so whoever controls the central connection could
+//! reconfigure the node.  With SVM_CONFIG_OPS_PUBLIC_KEY set (SPKI PEM or 64
+//! hex chars), only signed updates are applied:
+//!
+//!   { "type": "CONFIG_UPDATE",
+//!     "signedPayload": "<payload JSON text>",
+//!     "signature":     "<base64 Ed25519 over the UTF-8 bytes of signedPayload>" }
+//!
+//! The signature covers the exact text, so no JSON canonicalisation is
+//! needed on either side.  The payload must carry `issuedAt` (epoch ms),
+//! strictly greater than the last applied update's, so a captured update
+//! cannot be replayed.  That high-water mark is saved before the update is
+//! applied (SVM_CONFIG_UPDATE_STATE_PATH, default
+//! /tmp/eyeflow_svm_config_update.json), so a restart does not reopen the
+//! replay window; an update whose mark cannot be saved is refused.
+//! Rejections raise CONFIG_UPDATE_SIGNATURE_INVALID and append a
+//! CONFIG_UPDATE_REJECTED audit event.
+//!
+//! Without a pinned key the plain `payload` is applied, as before, with a
+//! warning.
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// DER prefix of an SPKI Ed25519 public key (RFC 8410); the 32-byte key follows.
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Parse `-----BEGIN PUBLIC KEY-----` (SPKI Ed25519) or a raw 32-byte hex key.
+pub fn verifying_key_from_str(key: &str) -> Result<VerifyingKey> {
+    let key = key.trim();
+    let raw: [u8; 32] = if key.starts_with("-----") {
+        let body: String = key.replace("\\n", "\n")
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with("-----"))
+            .collect();
+        let der = B64.decode(body).map_err(|e| anyhow!("ops key PEM: invalid base64: {e}"))?;
+        if der.len() != 44 || der[..12] != ED25519_SPKI_PREFIX {
+            return Err(anyhow!("ops key PEM is not an SPKI Ed25519 public key"));
+        }
+        der[12..].try_into().expect("length checked")
+    } else {
+        hex::decode(key).ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| anyhow!("ops key must be an SPKI PEM or 64 hex characters"))?
+    };
+    VerifyingKey::from_bytes(&raw).map_err(|e| anyhow!("invalid ops key: {e}"))
+}
+
+#[derive(Debug)]
+pub struct ConfigVerifier {
+    key: Option<VerifyingKey>,
+    /// `issuedAt` of the last applied signed update
+    last_issued_at: Mutex<i64>,
+    /// Where `last_issued_at` is kept across restarts (None = memory only)
+    state_path: Option<PathBuf>,
+}
+
+impl ConfigVerifier {
+    pub fn new(ops_public_key: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            key: ops_public_key.map(verifying_key_from_str).transpose()?,
+            last_issued_at: Mutex::new(i64::MIN),
+            state_path: None,
+        })
+    }
+
+    /// Keep the replay high-water mark in `path`, starting from the one
+    /// saved there.
+    pub fn with_state(mut self, path: PathBuf) -> Self {
+        let saved = std::fs::read(&path).ok()
+            .and_then(|b| serde_json::from_slice::<Value>(&b).ok())
+            .and_then(|v| v.get("lastIssuedAt").and_then(Value::as_i64));
+        if let Some(saved) = saved {
+            *self.last_issued_at.get_mut().unwrap_or_else(|e| e.into_inner()) = saved;
+        }
+        self.state_path = Some(path);
+        self
+    }
+
+    fn save(&self, issued_at: i64) -> Result<()> {
+        let Some(path) = &self.state_path else { return Ok(()) };
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".part");
+        std::fs::write(&tmp, serde_json::to_vec(&json!({ "lastIssuedAt": issued_at }))?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn requires_signature(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// The payload of a CONFIG_UPDATE `frame` that may be applied.
+    pub fn verify(&self, frame: &Value) -> Result<Value> {
+        let Some(key) = &self.key else {
+            return Ok(frame.get("payload").cloned().unwrap_or(Value::Null));
+        };
+        let text = frame.get("signedPayload").and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("unsigned CONFIG_UPDATE (no signedPayload)"))?;
+        let sig = frame.get("signature").and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("CONFIG_UPDATE has no signature"))?;
+        let sig = B64.decode(sig).ok()
+            .and_then(|b| Signature::from_slice(&b).ok())
+            .ok_or_else(|| anyhow!("malformed CONFIG_UPDATE signature"))?;
+        key.verify(text.as_bytes(), &sig)
+            .map_err(|_| anyhow!("CONFIG_UPDATE signature does not match the ops key"))?;
+
+        let payload: Value = serde_json::from_str(text)?;
+        let issued_at = payload.get("issuedAt").and_then(|v| v.as_i64())
+            .ok_or_else(|| anyhow!("signed CONFIG_UPDATE has no issuedAt"))?;
+        let mut last = self.last_issued_at.lock().unwrap_or_else(|e| e.into_inner());
+        if issued_at <= *last {
+            return Err(anyhow!("replayed CONFIG_UPDATE (issuedAt {issued_at} <= {})", *last));
+        }
+        self.save(issued_at)
+            .map_err(|e| anyhow!("CONFIG_UPDATE replay state could not be saved: {e}"))?;
+        *last = issued_at;
+        Ok(payload)
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use serde_json::json;
+
+    #[test]
+    fn test_verify_signed_update() {
+        let ops = SigningKey::from_bytes(&[7u8; 32]);
+        let v = ConfigVerifier::new(Some(&hex::encode(ops.verifying_key().as_bytes()))).unwrap();
+        let signed = |text: &str, key: &SigningKey| json!({
+            "type": "CONFIG_UPDATE",
+            "signedPayload": text,
+            "signature": B64.encode(key.sign(text.as_bytes()).to_bytes()),
+        });
+
+        let text = r#"{"issuedAt":1000,"tenantQuotas":{"*":{"maxConcurrent":2}}}"#;
+        assert_eq!(v.verify(&signed(text, &ops)).unwrap()["tenantQuotas"]["*"]["maxConcurrent"], 2);
+        // Replay of the same update
+        assert!(v.verify(&signed(text, &ops)).is_err());
+        // Wrong key, tampered text, unsigned
+        let rogue = SigningKey::from_bytes(&[9u8; 32]);
+        assert!(v.verify(&signed(r#"{"issuedAt":2000}"#, &rogue)).is_err());
+        let mut tampered = signed(r#"{"issuedAt":3000}"#, &ops);
+        tampered["signedPayload"] = json!(r#"{"issuedAt":3001}"#);
+        assert!(v.verify(&tampered).is_err());
+        assert!(v.verify(&json!({ "type": "CONFIG_UPDATE", "payload": {} })).is_err());
+
+        // The high-water mark survives a restart
+        let path = std::env::temp_dir().join(format!("eyeflow_config_sig_{}.json", uuid::Uuid::new_v4().simple()));
+        let key = hex::encode(ops.verifying_key().as_bytes());
+        let first = ConfigVerifier::new(Some(&key)).unwrap().with_state(path.clone());
+        first.verify(&signed(r#"{"issuedAt":5000}"#, &ops)).unwrap();
+        let restarted = ConfigVerifier::new(Some(&key)).unwrap().with_state(path.clone());
+        assert!(restarted.verify(&signed(r#"{"issuedAt":5000}"#, &ops)).is_err());
+        assert!(restarted.verify(&signed(r#"{"issuedAt":4000}"#, &ops)).is_err());
+        restarted.verify(&signed(r#"{"issuedAt":6000}"#, &ops)).unwrap();
+        let _ = std::fs::remove_file(path);
+
+        // No pinned key: plain payloads pass through
+
+        let open = ConfigVerifier::new(None).unwrap();
+        assert_eq!(open.verify(&json!({ "payload": { "a": 1 } })).unwrap(), json!({ "a": 1 }));
+
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----",
+            B64.encode([&ED25519_SPKI_PREFIX[..], ops.verifying_key().as_bytes()].concat()),
+        );
+        assert_eq!(verifying_key_from_str(&pem).unwrap(), ops.verifying_key());
+    }
+}
@@ -0,0 +1,463 @@
+//! BLE connector — LOAD_RESOURCE from GATT characteristics of nearby sensors (Linux)
+//!
+//!   ble://AA:BB:CC:DD:EE:FF/<service>/<characteristic>   connect to that sensor
+//!   ble://*/<service>/<characteristic>                   first one advertising
+//!                                                        <service> in a scan
+//!   ble://AA:BB:CC:DD:EE:FF/<characteristic>             search every service
+//!
+//! UUIDs are 16-bit ("2a6e") or full 128-bit.  Operands:
+//!
+//!   mode          read (default) | notify — subscribe and collect updates
+//!   count         notifications to collect (default 1)
+//!   timeoutMs     connect + read / notify deadline (default 5000)
+//!   decode        u8 | i8 | u16le | i16le | u32le | i32le | f32le | utf8
+//!                 (default: the raw bytes as an array)
+//!   scale         multiplier applied to a decoded number
+//!
+//! → { "address", "characteristic", "hex", "value" } plus, for notify,
+//!   "samples": [{ "hex", "value", "receivedAt" }].
+//!
+//! The central role goes through btleplug, i.e. BlueZ over D-Bus: bluetoothd
+//! must be running, and it learns public vs random addresses from the
+//! advertisements.  A sensor given by MAC is looked up among the devices
+//! BlueZ knows, then scanned for.
+//!
+//!   SVM_BLE_ADAPTER            HCI adapter index (default 0 = hci0)
+//!   SVM_BLE_SCAN_TIMEOUT_MS    scan deadline (default 10000)
+//!
+//! The SVM serialises use of the adapter through the ResourceArbiter (key
+//! `ble:hci<N>`): one scan or connection at a time per radio.
+
+use anyhow::Result;
+use btleplug::api::{Central, CentralEvent, CharPropFlags, Characteristic, Manager as _, Peripheral as _, PeripheralProperties, ScanFilter, Service, ValueNotification};
+use btleplug::platform::{Adapter, Manager, Peripheral};
+use futures_util::{Stream, StreamExt};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use tokio::time::Instant;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::errors::{ErrorCode, ExecError};
+
+// ── Endpoints ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    /// Sensor address, or None to scan for `service`
+    pub address: Option<[u8; 6]>,
+    pub service: Option<Uuid>,
+    pub characteristic: Uuid,
+}
+
+impl Endpoint {
+    pub fn parse(url: &str) -> Result<Self> {
+        let rest = url.strip_prefix("ble://")
+            .ok_or_else(|| ExecError::validation(format!("not a BLE URL: {url}")))?;
+        let parts: Vec<&str> = rest.trim_end_matches('/').split('/').collect();
+        let bad = |what: &str| ExecError::validation(format!("{url}: {what}"));
+        let address = match parts[0] {
+            "*" => None,
+            mac => Some(parse_mac(mac).ok_or_else(|| bad("expected a MAC address or *"))?),
+        };
+        let (service, characteristic) = match parts[1..] {
+            [svc, chr] => (Some(svc), chr),
+            [chr] => (None, chr),
+            _ => return Err(bad("expected ble://<mac|*>/[<service>/]<characteristic>")),
+        };
+        let service = service.map(|s| parse_uuid(s).ok_or_else(|| bad("bad service UUID"))).transpose()?;
+        if address.is_none() && service.is_none() {
+            return Err(bad("scanning (*) needs a service UUID"));
+        }
+        Ok(Self {
+            address,
+            service,
+            characteristic: parse_uuid(characteristic).ok_or_else(|| bad("bad characteristic UUID"))?,
+        })
+    }
+
+    /// True when the advertised `props` belong to the sensor this endpoint
+    /// names (its MAC, or any advertiser of its service).
+    fn matches(&self, props: &PeripheralProperties) -> bool {
+        match (self.address, self.service) {
+            (Some(mac), _) => props.address.into_inner() == mac,
+            (None, Some(service)) => props.services.contains(&service),
+            (None, None) => false,
+        }
+    }
+}
+
+/// "AA:BB:CC:DD:EE:FF" → bytes in display order.
+fn parse_mac(s: &str) -> Option<[u8; 6]> {
+    let bytes: Vec<u8> = s.split(':').map(|b| u8::from_str_radix(b, 16).ok()).collect::<Option<_>>()?;
+    bytes.try_into().ok()
+}
+
+fn format_mac(mac: &[u8; 6]) -> String {
+    mac.iter().map(|b| format!("{b:02X}")).collect::<Vec<_>>().join(":")
+}
+
+/// Bluetooth base UUID 0000xxxx-0000-1000-8000-00805F9B34FB.
+const BASE_UUID: u128 = 0x0000_0000_0000_1000_8000_0080_5F9B_34FB;
+
+fn parse_uuid(s: &str) -> Option<Uuid> {
+    let hex: String = s.chars().filter(|c| *c != '-').collect();
+    match hex.len() {
+        4 | 8 => u32::from_str_radix(&hex, 16).ok().map(|short| Uuid::from_u128(BASE_UUID | (short as u128) << 96)),
+        32 => u128::from_str_radix(&hex, 16).ok().map(Uuid::from_u128),
+        _ => None,
+    }
+}
+
+fn format_uuid(uuid: Uuid) -> String {
+    let n = uuid.as_u128();
+    if n & !(0xFFFF_FFFF << 96) == BASE_UUID {
+        return format!("{:04x}", n >> 96);
+    }
+    uuid.hyphenated().to_string()
+}
+
+// ── Connector ─────────────────────────────────────────────────────────────────
+
+pub struct BleConnector {
+    adapter: u16,
+    scan_timeout: Duration,
+    /// BlueZ adapter, looked up on first use (bluetoothd may start after us)
+    central: OnceCell<Adapter>,
+}
+
+impl BleConnector {
+    pub fn new(adapter: u16, scan_timeout: Duration) -> Self {
+        Self { adapter, scan_timeout, central: OnceCell::new() }
+    }
+
+    /// ResourceArbiter key of the radio.
+    pub fn arbiter_key(&self) -> String {
+        format!("ble:hci{}", self.adapter)
+    }
+
+    /// Read (or collect notifications of) the characteristic at `url`.
+    pub async fn load(&self, url: &str, operands: &Value) -> Result<Value> {
+        let endpoint = Endpoint::parse(url)?;
+        let opts = ReadOptions::from_operands(operands)?;
+        debug!("[Ble] {url}");
+        let central = self.central().await?;
+        let peripheral = self.find(central, &endpoint).await?;
+        let address = peripheral.address().into_inner();
+        let out = fetch(&peripheral, &endpoint, &opts, Instant::now() + opts.timeout).await;
+        if let Err(e) = peripheral.disconnect().await {
+            debug!("[Ble] disconnecting {}: {e}", format_mac(&address));
+        }
+        let mut out = out?;
+        out["address"] = format_mac(&address).into();
+        out["characteristic"] = format_uuid(endpoint.characteristic).into();
+        Ok(out)
+    }
+
+    /// The `hci<N>` adapter from BlueZ.
+    async fn central(&self) -> Result<&Adapter> {
+        self.central.get_or_try_init(|| async {
+            let name = format!("hci{}", self.adapter);
+            let unavailable = |e: btleplug::Error| ExecError::new(ErrorCode::Unsupported, format!("BlueZ: {e}"));
+            let manager = Manager::new().await.map_err(unavailable)?;
+            for adapter in manager.adapters().await.map_err(unavailable)? {
+                let info = adapter.adapter_info().await.map_err(unavailable)?;
+                if info.split_whitespace().next() == Some(name.as_str()) {
+                    return Ok(adapter);
+                }
+            }
+            Err(ExecError::new(ErrorCode::Unsupported, format!("no Bluetooth adapter {name}")).into())
+        }).await
+    }
+
+    /// The sensor `endpoint` names: a known device with its MAC, else the
+    /// first match of an LE scan.
+    async fn find(&self, central: &Adapter, endpoint: &Endpoint) -> Result<Peripheral> {
+        if endpoint.address.is_some() {
+            for p in central.peripherals().await.map_err(|e| ble_error("listing devices", e))? {
+                if p.properties().await.ok().flatten().is_some_and(|props| endpoint.matches(&props)) {
+                    return Ok(p);
+                }
+            }
+        }
+        let mut events = central.events().await.map_err(|e| ble_error("BLE scan", e))?;
+        let filter = ScanFilter { services: endpoint.service.filter(|_| endpoint.address.is_none()).into_iter().collect() };
+        central.start_scan(filter).await.map_err(|e| ble_error("BLE scan", e))?;
+        let deadline = Instant::now() + self.scan_timeout;
+        let found = async {
+            while let Some(event) = events.next().await {
+                let (CentralEvent::DeviceDiscovered(id) | CentralEvent::DeviceUpdated(id) | CentralEvent::ServicesAdvertisement { id, .. }) = event else { continue };
+                let Ok(p) = central.peripheral(&id).await else { continue };
+                if p.properties().await.ok().flatten().is_some_and(|props| endpoint.matches(&props)) {
+                    return Some(p);
+                }
+            }
+            None
+        };
+        let found = tokio::time::timeout_at(deadline, found).await;
+        let _ = central.stop_scan().await;
+        let what = match (endpoint.address, endpoint.service) {
+            (Some(mac), _) => format_mac(&mac),
+            (None, service) => format!("a device advertising {}", format_uuid(service.unwrap_or_default())),
+        };
+        match found {
+            Ok(Some(p)) => {
+                debug!("[Ble] found {}", format_mac(&p.address().into_inner()));
+                Ok(p)
+            }
+            Ok(None) => Err(ExecError::new(ErrorCode::Network, "BLE scan: adapter event stream ended").into()),
+            Err(_) => Err(ExecError::new(ErrorCode::Timeout, format!("BLE scan: {what} not found before the deadline")).into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ReadOptions {
+    notify: bool,
+    count: usize,
+    timeout: Duration,
+    decode: Option<String>,
+    scale: Option<f64>,
+}
+
+impl ReadOptions {
+    fn from_operands(op: &Value) -> Result<Self> {
+        let notify = match op.get("mode").and_then(|v| v.as_str()).unwrap_or("read") {
+            "read" => false,
+            "notify" => true,
+            other => return Err(ExecError::validation(format!("BLE mode must be read or notify, got '{other}'"))),
+        };
+        Ok(Self {
+            notify,
+            count: op.get("count").and_then(|v| v.as_u64()).unwrap_or(1).max(1) as usize,
+            timeout: Duration::from_millis(op.get("timeoutMs").and_then(|v| v.as_u64()).unwrap_or(5_000)),
+            decode: op.get("decode").and_then(|v| v.as_str()).map(String::from),
+            scale: op.get("scale").and_then(|v| v.as_f64()),
+        })
+    }
+
+    /// Register value of a characteristic value.
+    fn decode(&self, b: &[u8]) -> Result<Value> {
+        let need = |n: usize| {
+            b.get(..n).ok_or_else(|| ExecError::validation(format!("BLE value has {} byte(s), decode needs {n}", b.len())))
+        };
+        let number = match self.decode.as_deref() {
+            None => return Ok(json!(b)),
+            Some("utf8") => return Ok(String::from_utf8_lossy(b).into_owned().into()),
+            Some("u8") => need(1)?[0] as f64,
+            Some("i8") => need(1)?[0] as i8 as f64,
+            Some("u16le") => u16::from_le_bytes(need(2)?.try_into()?) as f64,
+            Some("i16le") => i16::from_le_bytes(need(2)?.try_into()?) as f64,
+            Some("u32le") => u32::from_le_bytes(need(4)?.try_into()?) as f64,
+            Some("i32le") => i32::from_le_bytes(need(4)?.try_into()?) as f64,
+            Some("f32le") => f32::from_le_bytes(need(4)?.try_into()?) as f64,
+            Some(other) => return Err(ExecError::validation(format!("unknown BLE decode '{other}'"))),
+        };
+        Ok(match self.scale {
+            Some(s) => json!(number * s),
+            None if number.fract() == 0.0 => json!(number as i64),
+            None => json!(number),
+        })
+    }
+}
+
+// ── GATT ──────────────────────────────────────────────────────────────────────
+
+fn ble_error(what: &str, e: btleplug::Error) -> anyhow::Error {
+    use btleplug::Error as E;
+    let code = match &e {
+        E::PermissionDenied => ErrorCode::Auth,
+        E::DeviceNotFound | E::NoSuchCharacteristic | E::UnexpectedCharacteristic => ErrorCode::NotFound,
+        E::NotSupported(_) => ErrorCode::Unsupported,
+        E::TimedOut(_) => ErrorCode::Timeout,
+        E::Uuid(_) | E::InvalidBDAddr(_) => ErrorCode::Validation,
+        // Dropped links and BlueZ / D-Bus failures (e.g. le-connection-abort-by-local)
+        _ => ErrorCode::Network,
+    };
+    ExecError::new(code, format!("{what}: {e}")).into()
+}
+
+/// Run a btleplug call, failing with TIMEOUT at `deadline`.
+async fn within<T>(deadline: Instant, what: &str, call: impl Future<Output = btleplug::Result<T>>) -> Result<T> {
+    match tokio::time::timeout_at(deadline, call).await {
+        Ok(out) => out.map_err(|e| ble_error(what, e)),
+        Err(_) => Err(ExecError::new(ErrorCode::Timeout, format!("{what}: timed out")).into()),
+    }
+}
+
+/// The characteristic `endpoint` names among the discovered `services`.
+fn find_characteristic(services: &BTreeSet<Service>, endpoint: &Endpoint) -> Result<Characteristic> {
+    let scope: Vec<&Service> = match endpoint.service {
+        Some(uuid) => vec![services.iter().find(|s| s.uuid == uuid).ok_or_else(|| ExecError::new(
+            ErrorCode::NotFound,
+            format!("service {} not found", format_uuid(uuid)),
+        ))?],
+        None => services.iter().collect(),
+    };
+    scope.iter()
+        .flat_map(|s| &s.characteristics)
+        .find(|c| c.uuid == endpoint.characteristic)
+        .cloned()
+        .ok_or_else(|| ExecError::new(
+            ErrorCode::NotFound,
+            format!("characteristic {} not found", format_uuid(endpoint.characteristic)),
+        ).into())
+}
+
+/// Connect, locate the characteristic and read it or collect its updates.
+async fn fetch(peripheral: &Peripheral, endpoint: &Endpoint, opts: &ReadOptions, deadline: Instant) -> Result<Value> {
+    let target = format!("connecting to {}", format_mac(&peripheral.address().into_inner()));
+    if !peripheral.is_connected().await.unwrap_or(false) {
+        within(deadline, &target, peripheral.connect()).await?;
+    }
+    within(deadline, "service discovery", peripheral.discover_services()).await?;
+    let chr = find_characteristic(&peripheral.services(), endpoint)?;
+
+    if !opts.notify {
+        let value = within(deadline, "BLE read", peripheral.read(&chr)).await?;
+        return Ok(json!({ "hex": hex::encode(&value), "value": opts.decode(&value)? }));
+    }
+
+    if !chr.properties.intersects(CharPropFlags::NOTIFY | CharPropFlags::INDICATE) {
+        return Err(ExecError::validation(format!("characteristic {} does not support notifications", format_uuid(chr.uuid))));
+    }
+    // Listen before subscribing so the first update is not missed
+    let updates = within(deadline, "BLE notify", peripheral.notifications()).await?;
+    within(deadline, "enabling notifications", peripheral.subscribe(&chr)).await?;
+    let out = collect(updates, chr.uuid, opts, deadline).await;
+    let _ = peripheral.unsubscribe(&chr).await;
+    out
+}
+
+/// Gather `opts.count` updates of `uuid`, keeping what arrived before the
+/// deadline.
+async fn collect(mut updates: impl Stream<Item = ValueNotification> + Unpin, uuid: Uuid, opts: &ReadOptions, deadline: Instant) -> Result<Value> {
+    let mut samples = Vec::new();
+    while samples.len() < opts.count {
+        let update = match tokio::time::timeout_at(deadline, updates.next()).await {
+            Ok(Some(update)) => update,
+            Ok(None) if samples.is_empty() => return Err(ExecError::new(ErrorCode::Network, "BLE device disconnected").into()),
+            Err(_) if samples.is_empty() => return Err(ExecError::new(ErrorCode::Timeout, "BLE notify: timed out").into()),
+            Ok(None) | Err(_) => break,
+        };
+        if update.uuid == uuid {
+            samples.push(json!({
+                "hex": hex::encode(&update.value),
+                "value": opts.decode(&update.value)?,
+                "receivedAt": chrono::Utc::now().to_rfc3339(),
+            }));
+        }
+    }
+    let latest = samples.last().cloned().unwrap_or(Value::Null);
+    Ok(json!({ "hex": latest["hex"], "value": latest["value"], "samples": samples }))
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn characteristic(service: &str, uuid: &str, properties: CharPropFlags) -> Characteristic {
+        Characteristic {
+            uuid: parse_uuid(uuid).unwrap(),
+            service_uuid: parse_uuid(service).unwrap(),
+            properties,
+            descriptors: BTreeSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_endpoints_and_advertising() {
+        let ep = Endpoint::parse("ble://a4:C1:38:00:11:22/181a/2a6e").unwrap();
+        assert_eq!(ep.address, Some([0xA4, 0xC1, 0x38, 0x00, 0x11, 0x22]));
+        assert_eq!(format_uuid(ep.service.unwrap()), "181a");
+        assert_eq!(parse_uuid("00002a6e-0000-1000-8000-00805f9b34fb"), Some(ep.characteristic));
+        assert_eq!(format_uuid(parse_uuid("6e400001b5a3f393e0a9e50e24dcca9e").unwrap()), "6e400001-b5a3-f393-e0a9-e50e24dcca9e");
+        assert!(Endpoint::parse("ble://*/2a6e").is_err());
+        assert!(Endpoint::parse("ble://a4:c1:38/2a6e").is_err());
+
+        // A scan matches on the advertised service list, a MAC on the address
+        let scan = Endpoint::parse("ble://*/181a/2a6e").unwrap();
+        assert!(scan.address.is_none());
+        let mut props = PeripheralProperties {
+            address: [0xA4, 0xC1, 0x38, 0x00, 0x11, 0x22].into(),
+            services: vec![parse_uuid("180f").unwrap(), parse_uuid("181a").unwrap()],
+            ..Default::default()
+        };
+        assert!(scan.matches(&props) && ep.matches(&props));
+        props.services.truncate(1);
+        props.address = [0xA4, 0xC1, 0x38, 0x00, 0x11, 0x23].into();
+        assert!(!scan.matches(&props) && !ep.matches(&props));
+    }
+
+    #[test]
+    fn test_characteristic_lookup() {
+        let services: BTreeSet<Service> = [
+            Service {
+                uuid: parse_uuid("180f").unwrap(),
+                primary: true,
+                characteristics: [characteristic("180f", "2a19", CharPropFlags::READ)].into(),
+            },
+            Service {
+                uuid: parse_uuid("181a").unwrap(),
+                primary: true,
+                characteristics: [
+                    characteristic("181a", "2a6e", CharPropFlags::READ | CharPropFlags::NOTIFY),
+                    characteristic("181a", "2a6f", CharPropFlags::READ),
+                ].into(),
+            },
+        ].into();
+        let found = find_characteristic(&services, &Endpoint::parse("ble://*/181a/2a6e").unwrap()).unwrap();
+        assert_eq!(found.properties, CharPropFlags::READ | CharPropFlags::NOTIFY);
+        // Without a service every one is searched
+        let battery = find_characteristic(&services, &Endpoint::parse("ble://A4:C1:38:00:11:22/2a19").unwrap()).unwrap();
+        assert_eq!(format_uuid(battery.service_uuid), "180f");
+
+        let missing = |url: &str| crate::errors::classify(&find_characteristic(&services, &Endpoint::parse(url).unwrap()).unwrap_err());
+        assert_eq!(missing("ble://*/181a/2a19"), ErrorCode::NotFound);
+        assert_eq!(missing("ble://*/1809/2a1c"), ErrorCode::NotFound);
+    }
+
+    #[test]
+    fn test_register_decoding() {
+        let opts = |op: Value| ReadOptions::from_operands(&op).unwrap();
+        assert!((opts(json!({ "decode": "i16le", "scale": 0.01 })).decode(&[0x0A, 0x09]).unwrap().as_f64().unwrap() - 23.14).abs() < 1e-9);
+        assert_eq!(opts(json!({ "decode": "i16le" })).decode(&[0xF6, 0xFF]).unwrap(), -10);
+        assert_eq!(opts(json!({ "decode": "u32le" })).decode(&[1, 0, 0, 0x80]).unwrap(), 2147483649u64);
+        assert_eq!(opts(json!({ "decode": "utf8" })).decode(b"v1.2").unwrap(), "v1.2");
+        assert_eq!(opts(json!({})).decode(&[0x2C, 0x01]).unwrap(), json!([44, 1]));
+        let err = opts(json!({ "decode": "f32le" })).decode(&[0, 0]).unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::Validation);
+        assert!(ReadOptions::from_operands(&json!({ "mode": "write" })).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_notifications() {
+        let temperature = parse_uuid("2a6e").unwrap();
+        let update = |uuid: &str, value: &[u8]| ValueNotification { uuid: parse_uuid(uuid).unwrap(), value: value.to_vec() };
+        let opts = ReadOptions::from_operands(&json!({ "mode": "notify", "count": 2, "decode": "i16le" })).unwrap();
+        let deadline = || Instant::now() + Duration::from_millis(200);
+
+        // Updates of other characteristics are skipped
+        let updates = futures_util::stream::iter([update("2a6e", &[0x2C, 0x01]), update("2a6f", &[0xFF]), update("2a6e", &[0xF6, 0xFF])]);
+        let out = collect(updates, temperature, &opts, deadline()).await.unwrap();
+        let values: Vec<&Value> = out["samples"].as_array().unwrap().iter().map(|s| &s["value"]).collect();
+        assert_eq!(values, [&json!(300), &json!(-10)]);
+        assert_eq!(out["value"], -10);
+
+        // The deadline keeps what arrived; nothing at all is a TIMEOUT
+        let updates = futures_util::stream::iter([update("2a6e", &[0x2C, 0x01])]).chain(futures_util::stream::pending());
+        let out = collect(updates, temperature, &opts, deadline()).await.unwrap();
+        assert_eq!(out["samples"].as_array().unwrap().len(), 1);
+        let err = collect(futures_util::stream::pending(), temperature, &opts, deadline()).await.unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::Timeout);
+
+        // A device that drops the link before any update
+        let err = collect(futures_util::stream::empty(), temperature, &opts, deadline()).await.unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::Network, "{err}");
+    }
+}
@@ -0,0 +1,194 @@
+//! Camera connector — JPEG snapshots from RTSP and HTTP cameras
+//!
+//! LOAD_RESOURCE grabs one frame and keeps it as a binary register
+//! (blob.rs), so vision workflows pass the image straight to a CALL_SERVICE
+//! or LLM_CALL:
+//!
+//!   rtsp://cam-3.local:554/stream1        one frame through ffmpeg
+//!   rtsps://…                             the same over TLS
+//!   camera+http://cam-7.local/snap.jpg    a snapshot URL (camera+https too);
+//!                                         an MJPEG stream answer goes
+//!                                         through ffmpeg
+//!
+//! operands_json may scale and re-encode the frame (ffmpeg only):
+//!
+//!   { "width": 640, "quality": 5 }        width in px (height keeps the
+//!                                         aspect ratio), JPEG q 2..=31
+//!
+//! The register holds the blob reference plus where and when:
+//!
+//!   { "$blob": "sha256:…", "mime": "image/jpeg", "size", "camera", "capturedAt" }
+//!
+//! Credentials come from `credentials_vault_path` as "user:password" (RTSP
+//! userinfo, HTTP basic auth); `camera` and error messages never show them.
+//! One capture runs per camera at a time — the ResourceArbiter key is
+//! `camera:<host:port/path>` — and ffmpeg is killed after
+//! SVM_CAMERA_TIMEOUT_MS (default 10 000, TIMEOUT).  SVM_FFMPEG_PATH names
+//! the binary (default "ffmpeg"); without it snapshots fail with UNSUPPORTED.
+
+use anyhow::Result;
+use bytes::Bytes;
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::errors::{ErrorCode, ExecError};
+
+/// A captured frame.
+pub struct Snapshot {
+    pub mime: String,
+    pub bytes: Bytes,
+}
+
+pub struct CameraConnector {
+    http: reqwest::Client,
+    ffmpeg: String,
+    timeout: Duration,
+}
+
+impl CameraConnector {
+    pub fn new(http: reqwest::Client, ffmpeg: String, timeout: Duration) -> Self {
+        Self { http, ffmpeg, timeout }
+    }
+
+    /// ResourceArbiter key of the camera at `url`.
+    pub fn arbiter_key(url: &str) -> String {
+        let url = redact(url);
+        format!("camera:{}", url.split_once("://").map_or(url.as_str(), |(_, rest)| rest))
+    }
+
+    /// Grab one frame from `url`; `credentials` is "user:password".
+    pub async fn snapshot(&self, url: &str, operands: &Value, credentials: Option<&str>) -> Result<Snapshot> {
+        let what = format!("camera {}", redact(url));
+        match url.strip_prefix("camera+") {
+            Some(http_url) => self.http_snapshot(http_url, operands, credentials, &what).await,
+            None => self.ffmpeg(&with_userinfo(url, credentials), operands, &what).await,
+        }
+    }
+
+    async fn http_snapshot(&self, url: &str, operands: &Value, credentials: Option<&str>, what: &str) -> Result<Snapshot> {
+        let mut req = self.http.get(url).timeout(self.timeout);
+        if let Some((user, password)) = credentials.and_then(|c| c.split_once(':')) {
+            req = req.basic_auth(user, Some(password));
+        }
+        let resp = req.send().await?;
+        if !resp.status().is_success() {
+            return Err(ExecError::http(what, resp.status()));
+        }
+        let mime = resp.headers().get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(';').next())
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        // A still image is kept as is unless it has to be re-encoded
+        let reencode = operands.get("width").is_some() || operands.get("quality").is_some();
+        if mime.starts_with("image/") && !reencode {
+            return Ok(Snapshot { mime, bytes: resp.bytes().await? });
+        }
+        // An MJPEG stream (multipart/x-mixed-replace) never ends: ffmpeg
+        // reads its first frame
+        drop(resp);
+        self.ffmpeg(&with_userinfo(url, credentials), operands, what).await
+    }
+
+    async fn ffmpeg(&self, url: &str, operands: &Value, what: &str) -> Result<Snapshot> {
+        let mut cmd = Command::new(&self.ffmpeg);
+        cmd.args(["-nostdin", "-hide_banner", "-loglevel", "error"]);
+        if url.starts_with("rtsp") {
+            cmd.args(["-rtsp_transport", "tcp"]);
+        }
+        cmd.args(["-i", url, "-frames:v", "1"]);
+        if let Some(width) = operands.get("width").and_then(Value::as_u64) {
+            cmd.args(["-vf", &format!("scale={width}:-2")]);
+        }
+        let quality = operands.get("quality").and_then(Value::as_u64).unwrap_or(3).clamp(2, 31);
+        cmd.args(["-q:v", &quality.to_string(), "-f", "image2", "-c:v", "mjpeg", "pipe:1"]);
+        cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true);
+
+        let child = cmd.spawn().map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => ExecError::new(
+                ErrorCode::Unsupported,
+                format!("{what}: '{}' not found — install ffmpeg or set SVM_FFMPEG_PATH", self.ffmpeg),
+            ).into(),
+            _ => anyhow::Error::from(e),
+        })?;
+        // Dropping the future on timeout kills ffmpeg (kill_on_drop)
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output()).await
+            .map_err(|_| ExecError::new(ErrorCode::Timeout, format!("{what}: no frame within {:?}", self.timeout)))??;
+        if !output.status.success() || output.stdout.is_empty() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = redact(stderr.lines().last().unwrap_or("no frame"));
+            return Err(ExecError::new(ErrorCode::Upstream, format!("{what}: ffmpeg {}: {reason}", output.status)).into());
+        }
+        debug!("[Camera] {what}: {} byte frame", output.stdout.len());
+        Ok(Snapshot { mime: "image/jpeg".into(), bytes: output.stdout.into() })
+    }
+}
+
+/// `url` with `user:password` as its userinfo (unless it already has one).
+fn with_userinfo(url: &str, credentials: Option<&str>) -> String {
+    match (credentials, url.split_once("://")) {
+        (Some(creds), Some((scheme, rest))) if !rest.split('/').next().unwrap_or_default().contains('@') => {
+            format!("{scheme}://{creds}@{rest}")
+        }
+        _ => url.to_owned(),
+    }
+}
+
+/// `text` with the userinfo of every URL in it removed.
+pub fn redact(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find("://") {
+        let (head, tail) = rest.split_at(at + 3);
+        out.push_str(head);
+        let authority = &tail[..tail.find(['/', ' ', '\'', '"']).unwrap_or(tail.len())];
+        rest = authority.rfind('@').map_or(tail, |i| &tail[i + 1..]);
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Register value of a frame kept in the blob store as `reference`.
+pub fn register(mut reference: Value, url: &str) -> Value {
+    if let Value::Object(map) = &mut reference {
+        map.insert("camera".into(), json!(redact(url)));
+        map.insert("capturedAt".into(), json!(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)));
+    }
+    reference
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::is_camera_url;
+    use crate::errors::classify;
+
+    #[tokio::test]
+    async fn test_urls_credentials_and_ffmpeg_failures() {
+        assert!(is_camera_url("rtsp://cam/stream") && is_camera_url("camera+https://cam/snap.jpg"));
+        assert!(!is_camera_url("https://cam/snap.jpg"));
+        assert_eq!(with_userinfo("rtsp://cam:554/s1", Some("op:pw")), "rtsp://op:pw@cam:554/s1");
+        assert_eq!(redact("Error opening rtsp://op:pw@cam:554/s1: 401"), "Error opening rtsp://cam:554/s1: 401");
+        assert_eq!(CameraConnector::arbiter_key("rtsp://op:pw@cam:554/s1"), "camera:cam:554/s1");
+
+        let missing = CameraConnector::new(reqwest::Client::new(), "/nonexistent/ffmpeg".into(), Duration::from_secs(1));
+        let err = missing.snapshot("rtsp://cam/s1", &Value::Null, None).await.err().unwrap();
+        assert_eq!(classify(&err), ErrorCode::Unsupported);
+
+        // ffmpeg refused by the camera: UPSTREAM, password left out
+        let fake = std::env::temp_dir().join(format!("fake-ffmpeg-{}", std::process::id()));
+        std::fs::write(&fake, "#!/bin/sh\necho 'rtsp://op:pw@cam/s1: 401 Unauthorized' >&2\nexit 1\n").unwrap();
+        std::fs::set_permissions(&fake, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+        let failing = CameraConnector::new(reqwest::Client::new(), fake.display().to_string(), Duration::from_secs(5));
+        let err = failing.snapshot("rtsp://cam/s1", &json!({ "width": 320 }), Some("op:pw")).await.err().unwrap();
+        std::fs::remove_file(&fake).ok();
+        assert_eq!(classify(&err), ErrorCode::Upstream);
+        assert!(err.to_string().contains("rtsp://cam/s1: 401") && !err.to_string().contains("pw"), "{err}");
+    }
+}
@@ -0,0 +1,339 @@
+//! Local device connector — GPIO and serial endpoints for CALL_ACTION (Linux)
+//!
+//! Raspberry-Pi-class nodes drive relays and RS-232/485 equipment directly
+//! instead of going through an HTTP bridge:
+//!
+//!   gpio://gpiochip0/17                  line 17 of /dev/gpiochip0
+//!       input { "value": true|false|0|1 }        drive the line (held until
+//!                                                the next write or restart)
+//!             { "value": 1, "pulseMs": 500 }     drive, wait, restore
+//!             {} / null                          read the line as an input
+//!       → { "chip", "line", "value" }
+//!
+//!   serial://dev/ttyUSB0?baud=9600       /dev/ttyUSB0, raw 8N1
+//!       input "text" | { "data": "text" } | { "hex": "0102ff" }
+//!             + "until": "\n", "readBytes": n, "readTimeoutMs": ms
+//!               to collect a response (no read unless one is given)
+//!       → { "device", "written", "response", "responseHex", "complete" }
+//!         complete = false when the read ended (timeout, hang-up) before
+//!         "until" or "readBytes" was satisfied; the partial response is kept
+//!
+//! GPIO goes through the kernel's GPIO character device (gpio-cdev), so no
+//! sysfs export and no native library; serial ports through tokio-serial.
+//!
+//! Only devices under a prefix listed in SVM_DEVICE_ALLOW (comma-separated,
+//! e.g. "/dev/gpiochip0,/dev/ttyUSB") can be opened, and device paths may
+//! not contain `..`; the connector is disabled when it is unset.  The SVM serialises access per device through
+//! the ResourceArbiter (key `device:<path>`), never preemptively.  A device
+//! unplugged under a workflow pauses it instead of failing (hotplug.rs).
+
+use anyhow::{anyhow, Result};
+use gpio_cdev::{Chip, LineHandle, LineRequestFlags};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::os::fd::{FromRawFd, IntoRawFd};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_serial::{DataBits, FlowControl, Parity, SerialPortBuilder, SerialPortBuilderExt, StopBits};
+use tracing::debug;
+
+use crate::errors::{ErrorCode, ExecError};
+
+// ── Endpoints ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Endpoint {
+    Gpio { chip: String, line: u32 },
+    Serial { device: String, baud: u32 },
+}
+
+impl Endpoint {
+    pub fn parse(url: &str) -> Result<Self> {
+        if let Some(rest) = url.strip_prefix("gpio://") {
+            let (chip, line) = rest.trim_matches('/').rsplit_once('/')
+                .ok_or_else(|| ExecError::validation(format!("{url}: expected gpio://<chip>/<line>")))?;
+            let line = line.parse()
+                .map_err(|_| ExecError::validation(format!("{url}: line must be a number")))?;
+            let chip = if chip.starts_with("dev/") { format!("/{chip}") } else { format!("/dev/{chip}") };
+            return Ok(Self::Gpio { chip: confined(url, chip)?, line });
+        }
+        if let Some(rest) = url.strip_prefix("serial://") {
+            let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+            let mut baud = 9_600;
+            for (k, v) in query.split('&').filter_map(|kv| kv.split_once('=')) {
+                if k == "baud" {
+                    baud = v.parse().map_err(|_| ExecError::validation(format!("{url}: bad baud rate")))?;
+                }
+            }
+            return Ok(Self::Serial { device: confined(url, format!("/{}", path.trim_start_matches('/')))?, baud });
+        }
+        Err(ExecError::validation(format!("not a device URL: {url}")))
+    }
+
+    /// Device node the endpoint opens — the arbiter key.
+    pub fn device(&self) -> &str {
+        match self {
+            Self::Gpio { chip, .. } => chip,
+            Self::Serial { device, .. } => device,
+        }
+    }
+}
+
+/// `path`, unless `..` could lead it out of an SVM_DEVICE_ALLOW prefix.
+fn confined(url: &str, path: String) -> Result<String> {
+    if path.split('/').any(|c| c == "..") {
+        return Err(ExecError::validation(format!("{url}: '..' is not allowed in a device path")));
+    }
+    Ok(path)
+}
+
+// ── Connector ─────────────────────────────────────────────────────────────────
+
+pub struct DeviceConnector {
+    allow: Vec<String>,
+    /// Output lines stay requested so that their value holds between calls
+    lines: Arc<Mutex<HashMap<(String, u32), LineHandle>>>,
+}
+
+impl DeviceConnector {
+    pub fn new(allow: Vec<String>) -> Self {
+        Self { allow, lines: Arc::default() }
+    }
+
+    /// Perform the action `input` on the device at `url`.
+    pub async fn call(&self, url: &str, input: Option<&Value>) -> Result<Value> {
+        let endpoint = Endpoint::parse(url)?;
+        if !self.allow.iter().any(|p| endpoint.device().starts_with(p.as_str())) {
+            return Err(ExecError::new(
+                ErrorCode::Auth,
+                format!("{} is not under SVM_DEVICE_ALLOW", endpoint.device()),
+            ).fatal().into());
+        }
+        let input = input.cloned().unwrap_or(Value::Null);
+        let lines = self.lines.clone();
+        debug!("[Device] {url}");
+        match endpoint {
+            Endpoint::Gpio { chip, line } => tokio::task::spawn_blocking(move || gpio(&lines, &chip, line, &input)).await?,
+            Endpoint::Serial { device, baud } => serial(&device, baud, &input).await,
+        }
+    }
+
+    /// Drop the line handles held on `chip` (unplugged or plugged back,
+    /// hotplug.rs): the next call requests them again.
+    pub fn release(&self, chip: &str) {
+        self.lines.lock().unwrap_or_else(|e| e.into_inner()).retain(|(c, _), _| c != chip);
+    }
+}
+
+// ── GPIO ──────────────────────────────────────────────────────────────────────
+
+fn request_line(chip: &str, line: u32, flags: LineRequestFlags, value: u8) -> Result<LineHandle> {
+    let mut chip_handle = Chip::new(chip).map_err(|e| anyhow!("opening {chip}: {e}"))?;
+    chip_handle.get_line(line)
+        .and_then(|l| l.request(flags, value, "eyeflow-svm"))
+        .map_err(|e| anyhow!("requesting {chip} line {line}: {e}"))
+}
+
+fn gpio(lines: &Mutex<HashMap<(String, u32), LineHandle>>, chip: &str, line: u32, input: &Value) -> Result<Value> {
+    let mut lines = lines.lock().unwrap_or_else(|e| e.into_inner());
+    let key = (chip.to_owned(), line);
+    let wanted = input.get("value").map(|v| match v {
+        Value::Bool(b) => Ok(*b as u8),
+        Value::Number(n) if n.as_u64() == Some(0) || n.as_u64() == Some(1) => Ok(n.as_u64().unwrap_or(0) as u8),
+        other => Err(ExecError::validation(format!("gpio value must be 0/1 or a boolean, got {other}"))),
+    }).transpose()?;
+
+    let Some(value) = wanted else {
+        // Read: reuse the output handle if we drive the line, else request it as input
+        let value = match lines.get(&key) {
+            Some(handle) => handle.get_value()?,
+            None => request_line(chip, line, LineRequestFlags::INPUT, 0)?.get_value()?,
+        };
+        return Ok(json!({ "chip": chip, "line": line, "value": value }));
+    };
+
+    let previous = match lines.get(&key) {
+        Some(handle) => {
+            let previous = handle.get_value()?;
+            handle.set_value(value)?;
+            previous
+        }
+        None => {
+            lines.insert(key.clone(), request_line(chip, line, LineRequestFlags::OUTPUT, value)?);
+            1 - value
+        }
+    };
+    if let Some(ms) = input.get("pulseMs").and_then(|v| v.as_u64()) {
+        std::thread::sleep(Duration::from_millis(ms));
+        lines[&key].set_value(previous)?;
+        return Ok(json!({ "chip": chip, "line": line, "value": previous, "pulsedMs": ms }));
+    }
+    Ok(json!({ "chip": chip, "line": line, "value": value }))
+}
+
+// ── Serial ────────────────────────────────────────────────────────────────────
+
+const BAUD_RATES: [u32; 11] = [1_200, 2_400, 4_800, 9_600, 19_200, 38_400, 57_600, 115_200, 230_400, 460_800, 921_600];
+
+/// Port settings for `device`: raw 8N1 at `baud`, no flow control.
+fn port_builder(device: &str, baud: u32) -> Result<SerialPortBuilder> {
+    if !BAUD_RATES.contains(&baud) {
+        return Err(ExecError::validation(format!("unsupported baud rate {baud}")));
+    }
+    Ok(tokio_serial::new(device, baud)
+        .data_bits(DataBits::Eight)
+        .parity(Parity::None)
+        .stop_bits(StopBits::One)
+        .flow_control(FlowControl::None))
+}
+
+/// Open `device` raw 8N1 at `baud` as a blocking handle for the MCU bridge's
+/// reader thread (mcu.rs).  Not exclusive: the bridge reopens it after errors.
+pub(crate) fn open_serial(device: &str, baud: u32) -> Result<File> {
+    let mut port = port_builder(device, baud)?.open_native().map_err(|e| anyhow!("opening {device}: {e}"))?;
+    port.set_exclusive(false).map_err(|e| anyhow!("opening {device}: {e}"))?;
+    // SAFETY: the descriptor is released by the port and owned by the File from here on.
+    Ok(unsafe { File::from_raw_fd(port.into_raw_fd()) })
+}
+
+async fn serial(device: &str, baud: u32, input: &Value) -> Result<Value> {
+    let payload = match input {
+        Value::String(s) => s.as_bytes().to_vec(),
+        _ => match (input.get("data").and_then(|v| v.as_str()), input.get("hex").and_then(|v| v.as_str())) {
+            (Some(text), _) => text.as_bytes().to_vec(),
+            (None, Some(h)) => hex::decode(h).map_err(|e| ExecError::validation(format!("serial hex: {e}")))?,
+            (None, None) => Vec::new(),
+        },
+    };
+    let until = input.get("until").and_then(|v| v.as_str()).map(str::as_bytes);
+    let read_bytes = input.get("readBytes").and_then(|v| v.as_u64()).map(|n| n as usize);
+    let timeout = input.get("readTimeoutMs").and_then(|v| v.as_u64())
+        .unwrap_or(if until.is_some() || read_bytes.is_some() { 1_000 } else { 0 });
+
+    let mut port = port_builder(device, baud)?.open_native_async().map_err(|e| anyhow!("opening {device}: {e}"))?;
+    port.write_all(&payload).await.map_err(|e| anyhow!("writing {device}: {e}"))?;
+
+    let mut response = Vec::new();
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout);
+    let done = |r: &[u8]| {
+        read_bytes.is_some_and(|n| r.len() >= n) || until.is_some_and(|u| !u.is_empty() && r.ends_with(u))
+    };
+    while timeout > 0 && !done(&response) {
+        let mut buf = [0u8; 512];
+        let max = read_bytes.map_or(buf.len(), |n| (n - response.len()).min(buf.len()));
+        match tokio::time::timeout_at(deadline, port.read(&mut buf[..max])).await {
+            Ok(Ok(0)) | Err(_) => break,
+            Ok(Ok(n)) => response.extend_from_slice(&buf[..n]),
+            Ok(Err(e)) => return Err(anyhow!("reading {device}: {e}")),
+        }
+    }
+    Ok(json!({
+        "device": device,
+        "written": payload.len(),
+        "response": String::from_utf8_lossy(&response),
+        "responseHex": hex::encode(&response),
+        "complete": timeout == 0 || done(&response),
+    }))
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_serial::{SerialPort, SerialStream};
+
+    #[tokio::test]
+    async fn test_endpoints_and_serial_exchange() {
+        assert_eq!(
+            Endpoint::parse("gpio://gpiochip0/17").unwrap(),
+            Endpoint::Gpio { chip: "/dev/gpiochip0".into(), line: 17 },
+        );
+        assert_eq!(
+            Endpoint::parse("serial://dev/ttyUSB0?baud=115200").unwrap(),
+            Endpoint::Serial { device: "/dev/ttyUSB0".into(), baud: 115_200 },
+        );
+        assert!(Endpoint::parse("gpio://gpiochip0/x").is_err());
+
+        // A pseudo-terminal stands in for the serial device
+        let (master, slave) = pty();
+        let peer = answer(master, b"OK 42\n", false);
+
+        let denied = DeviceConnector::new(vec!["/dev/ttyUSB".into()]);
+        let url = format!("serial:/{slave}?baud=9600");
+        assert!(denied.call(&url, Some(&json!("PING\n"))).await.is_err());
+
+        let conn = DeviceConnector::new(vec!["/dev/pts/".into()]);
+        let out = conn.call(&url, Some(&json!({ "data": "PING\n", "until": "\n", "readTimeoutMs": 2000 })))
+            .await.unwrap();
+        let (_master, got) = peer.await.unwrap();
+        assert_eq!(got, b"PING\n");
+        assert_eq!(out["written"], 5);
+        assert_eq!(out["response"], "OK 42\n");
+        assert_eq!(out["complete"], true);
+    }
+
+    /// A pseudo-terminal pair: the master side and the slave's path (the
+    /// slave handle is closed so the connector can open it).
+    fn pty() -> (SerialStream, String) {
+        let (master, slave) = SerialStream::pair().unwrap();
+        let path = slave.name().unwrap();
+        drop(slave);
+        (master, path)
+    }
+
+    /// Read one request line on `master`, then answer with `reply`; keeps the
+    /// master open (closing it hangs up the slave side) unless `hang_up`.
+    fn answer(mut master: SerialStream, reply: &'static [u8], hang_up: bool) -> tokio::task::JoinHandle<(Option<SerialStream>, Vec<u8>)> {
+        tokio::spawn(async move {
+            let mut got = Vec::new();
+            let mut buf = [0u8; 64];
+            while !got.ends_with(b"\n") {
+                let n = master.read(&mut buf).await.unwrap();
+                got.extend_from_slice(&buf[..n]);
+            }
+            master.write_all(reply).await.unwrap();
+            ((!hang_up).then_some(master), got)
+        })
+    }
+
+    #[tokio::test]
+    async fn test_malformed_truncated_and_hung_up_serial() {
+        let conn = DeviceConnector::new(vec!["/dev/pts/".into(), "/dev/ttyUSB".into()]);
+        let validation = |e: anyhow::Error| crate::errors::classify(&e) == ErrorCode::Validation;
+        assert!(validation(Endpoint::parse("serial://dev/ttyUSB0/../sda").unwrap_err()));
+        assert!(validation(Endpoint::parse("gpio://../mem/0").unwrap_err()));
+        assert!(validation(Endpoint::parse("serial://dev/ttyUSB0?baud=fast").unwrap_err()));
+        assert!(validation(conn.call("serial://dev/ttyUSB0/../../etc/passwd", Some(&json!("x"))).await.unwrap_err()));
+        assert!(validation(conn.call("serial://dev/ttyUSB0?baud=1234", Some(&json!("x"))).await.unwrap_err()));
+        assert!(validation(conn.call("serial://dev/ttyUSB0", Some(&json!({ "hex": "0g" }))).await.unwrap_err()));
+
+        // A reply cut short keeps what arrived and says so
+        let (master, slave) = pty();
+        let peer = answer(master, b"OK 4", false);
+        let url = format!("serial:/{slave}");
+        let out = conn.call(&url, Some(&json!({ "data": "PING\n", "until": "\n", "readTimeoutMs": 200 }))).await.unwrap();
+        assert_eq!((&out["response"], &out["complete"]), (&json!("OK 4"), &json!(false)));
+        drop(peer.await.unwrap());
+
+        // The device hangs up mid-reply; the next call opens it afresh
+        let (master, slave) = pty();
+        let peer = answer(master, b"", true);
+        let url = format!("serial:/{slave}");
+        let out = conn.call(&url, Some(&json!({ "data": "PING\n", "readBytes": 4, "readTimeoutMs": 2000 }))).await;
+        peer.await.unwrap();
+        match out {
+            Ok(out) => assert_eq!((&out["response"], &out["complete"]), (&json!(""), &json!(false))),
+            Err(e) => assert!(e.to_string().starts_with("reading"), "{e}"),
+        }
+
+        let (master, slave) = pty();
+        let peer = answer(master, b"PONG", false);
+        let out = conn.call(&format!("serial:/{slave}"), Some(&json!({ "data": "PING\n", "readBytes": 4, "readTimeoutMs": 2000 })))
+            .await.unwrap();
+        assert_eq!((&out["response"], &out["complete"]), (&json!("PONG"), &json!(true)));
+        drop(peer.await.unwrap());
+    }
+}
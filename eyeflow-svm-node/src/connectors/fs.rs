@@ -0,0 +1,350 @@
+//! Local filesystem connector — `file://` resources for LOAD_RESOURCE
+//!
+//! Lets on-prem "data drop" directories be consumed without a local HTTP
+//! server.  Every path is resolved relative to a single sandbox root
+//! (SVM_FS_SANDBOX_ROOT); the connector is disabled when it is unset.
+//!
+//!   file://exports/orders.csv       → array of row objects (header row = keys)
+//!   file://exports/readings.ndjson  → array of JSON values (one per line)
+//!   file://exports/setpoints.json   → JSON value
+//!   file://exports/*.csv            → listing: [{ path, size, modified }]
+//!
+//! The format is taken from the file extension unless operands_json carries
+//! an explicit `"format": "csv" | "json" | "ndjson" | "text"`.
+//!
+//! Path traversal protection: `..` components are rejected outright, and the
+//! canonicalised target must stay under the canonicalised root — which also
+//! defeats symlinks pointing outside the sandbox.
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::path::{Component, Path, PathBuf};
+use tokio::fs;
+use tracing::debug;
+
+use crate::errors::ExecError;
+
+// ── Connector ─────────────────────────────────────────────────────────────────
+
+pub struct FsConnector {
+    root: Option<PathBuf>,
+    max_bytes: u64,
+}
+
+impl FsConnector {
+    pub fn new(root: Option<PathBuf>, max_bytes: u64) -> Self {
+        Self { root, max_bytes }
+    }
+
+    /// Read (or list) the resource addressed by a `file://` URL.
+    pub async fn load(&self, url: &str, format_hint: Option<&str>) -> Result<Value> {
+        let root = self.root.as_ref()
+            .ok_or_else(|| anyhow!("file:// resources disabled — SVM_FS_SANDBOX_ROOT is not set"))?;
+        let rel = url.strip_prefix("file://")
+            .ok_or_else(|| anyhow!("not a file:// URL: {url}"))?
+            .trim_start_matches('/');
+        let rel = Path::new(rel);
+        check_relative(rel)?;
+
+        let root = fs::canonicalize(root).await
+            .map_err(|e| anyhow!("sandbox root {root:?} unavailable: {e}"))?;
+
+        let name = rel.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if is_glob(name) {
+            let dir_rel = rel.parent().unwrap_or(Path::new(""));
+            if dir_rel.to_str().map(is_glob).unwrap_or(true) {
+                return Err(anyhow!("wildcards are only supported in the last path segment: {url}"));
+            }
+            return self.list(&root, dir_rel, name).await;
+        }
+
+        let path = confine(&root, rel).await?;
+        let meta = fs::metadata(&path).await?;
+        if !meta.is_file() {
+            return Err(anyhow!("{url} is not a regular file"));
+        }
+        if meta.len() > self.max_bytes {
+            return Err(anyhow!(
+                "{url} is {} bytes — exceeds SVM_FS_MAX_BYTES={}", meta.len(), self.max_bytes
+            ));
+        }
+
+        let text = fs::read_to_string(&path).await?;
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let format = format_hint.unwrap_or(ext).to_lowercase();
+        debug!("[Fs] reading {path:?} as {format}");
+
+        match format.as_str() {
+            "json" => Ok(serde_json::from_str(&text)?),
+            "ndjson" | "jsonl" => parse_ndjson(&text),
+            "csv" => parse_csv(&text),
+            _ => Ok(Value::String(text)),
+        }
+    }
+
+    async fn list(&self, root: &Path, dir_rel: &Path, pattern: &str) -> Result<Value> {
+        let dir = confine(root, dir_rel).await?;
+        let mut entries = fs::read_dir(&dir).await?;
+        let mut out = Vec::new();
+
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !wildcard_match(pattern, &name) {
+                continue;
+            }
+            let meta = entry.metadata().await?;
+            if !meta.is_file() {
+                continue;
+            }
+            let modified = meta.modified().ok()
+                .map(|t| chrono::DateTime::<chrono::Utc>::from(t)
+                    .to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+            out.push(json!({
+                "path": dir_rel.join(&name).to_string_lossy(),
+                "size": meta.len(),
+                "modified": modified,
+            }));
+        }
+
+        out.sort_by(|a, b| a["path"].as_str().cmp(&b["path"].as_str()));
+        Ok(Value::Array(out))
+    }
+}
+
+// ── Sandbox helpers ───────────────────────────────────────────────────────────
+
+/// Reject absolute paths and `..` components before touching the filesystem.
+fn check_relative(rel: &Path) -> Result<()> {
+    for c in rel.components() {
+        match c {
+            Component::Normal(_) | Component::CurDir => {}
+            _ => return Err(anyhow!("path {rel:?} escapes the sandbox")),
+        }
+    }
+    Ok(())
+}
+
+/// Resolve `rel` under `root` and verify the canonical result stays inside it.
+async fn confine(root: &Path, rel: &Path) -> Result<PathBuf> {
+    let canonical = fs::canonicalize(root.join(rel)).await
+        .map_err(|e| anyhow!("{rel:?}: {e}"))?;
+    if !canonical.starts_with(root) {
+        return Err(anyhow!("path {rel:?} escapes the sandbox"));
+    }
+    Ok(canonical)
+}
+
+fn is_glob(s: &str) -> bool {
+    s.contains(['*', '?'])
+}
+
+/// Minimal wildcard matcher: `*` = any run of characters, `?` = one character.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0usize, 0usize);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ni));
+            pi += 1;
+        } else if let Some((sp, sn)) = star {
+            pi = sp + 1;
+            ni = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+// ── Format parsers ────────────────────────────────────────────────────────────
+
+/// Parse NDJSON into a JSON array; blank lines are skipped.
+pub fn parse_ndjson(text: &str) -> Result<Value> {
+    let mut out = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        out.push(serde_json::from_str(line)
+            .map_err(|e| anyhow!("NDJSON line {}: {e}", i + 1))?);
+    }
+    Ok(Value::Array(out))
+}
+
+/// Parse RFC 4180 CSV into an array of objects keyed by the header row.
+///
+/// Quoted fields may contain commas, doubled quotes and newlines.  Cells are
+/// coerced: empty → null, integers / floats → numbers, true/false → booleans.
+pub fn parse_csv(text: &str) -> Result<Value> {
+    let table = split_csv(text, CsvOptions::default())?;
+    let header = match table.columns {
+        Some(h) => h,
+        None => return Ok(Value::Array(vec![])),
+    };
+
+    let out = table.rows.into_iter()
+        .filter(|r| !(r.len() == 1 && r[0].is_empty()))
+        .map(|row| {
+            let mut obj = serde_json::Map::new();
+            for (i, key) in header.iter().enumerate() {
+                let cell = row.get(i).map(String::as_str).unwrap_or("");
+                obj.insert(key.clone(), coerce_cell(cell));
+            }
+            Value::Object(obj)
+        })
+        .collect();
+    Ok(Value::Array(out))
+}
+
+/// How `split_csv` reads a text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    /// The first row names the columns
+    pub header: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self { delimiter: ',', header: true }
+    }
+}
+
+/// A CSV text split into cells.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CsvTable {
+    /// The header row, when `CsvOptions::header` and the text is not empty
+    pub columns: Option<Vec<String>>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Split RFC 4180 text into rows of string cells (shared with tabular.rs).
+///
+/// Quoted cells may hold delimiters, "" and line breaks; LF or CRLF line
+/// ends are read.  An unterminated quote fails with VALIDATION.
+pub fn split_csv(text: &str, options: CsvOptions) -> Result<CsvTable> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    let mut line = 1usize;
+
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => in_quotes = false,
+            ('\n', true) => {
+                line += 1;
+                field.push(c);
+            }
+            ('"', false) if field.is_empty() => in_quotes = true,
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+                line += 1;
+            }
+            (c, false) if c == options.delimiter => row.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(ExecError::validation(format!("CSV: unterminated quoted field at line {line}")));
+    }
+    // A final line without a line break
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    let columns = if options.header && !rows.is_empty() { Some(rows.remove(0)) } else { None };
+    Ok(CsvTable { columns, rows })
+}
+
+fn coerce_cell(cell: &str) -> Value {
+    if cell.is_empty() {
+        return Value::Null;
+    }
+    if let Ok(i) = cell.parse::<i64>() {
+        return Value::from(i);
+    }
+    if let Ok(f) = cell.parse::<f64>() {
+        if f.is_finite() {
+            return Value::from(f);
+        }
+    }
+    match cell {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::String(cell.to_owned()),
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.csv", "orders.csv"));
+        assert!(wildcard_match("line_?.json", "line_3.json"));
+        assert!(wildcard_match("*", "anything"));
+        assert!(!wildcard_match("*.csv", "orders.csv.bak"));
+        assert!(!wildcard_match("line_?.json", "line_10.json"));
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let csv = "id,name,temp\n1,\"Pump, north\",21.5\n2,\"say \"\"hi\"\"\",\n";
+        let v = parse_csv(csv).unwrap();
+        assert_eq!(v[0]["id"], 1);
+        assert_eq!(v[0]["name"], "Pump, north");
+        assert_eq!(v[0]["temp"], 21.5);
+        assert_eq!(v[1]["name"], "say \"hi\"");
+        assert_eq!(v[1]["temp"], Value::Null);
+        assert!(parse_csv("a\n\"open").is_err());
+
+        let table = split_csv("a;b\r\n\"x;1\";2\r\n", CsvOptions { delimiter: ';', header: false }).unwrap();
+        assert_eq!(table.columns, None);
+        assert_eq!(table.rows, vec![vec!["a", "b"], vec!["x;1", "2"]]);
+        let table = split_csv("a\tb\n1\t2", CsvOptions { delimiter: '\t', header: true }).unwrap();
+        assert_eq!(table.columns, Some(vec!["a".to_owned(), "b".to_owned()]));
+        assert_eq!(table.rows, vec![vec!["1", "2"]]);
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_read_list_and_traversal() {
+        let root = std::env::temp_dir().join(format!("eyeflow_fs_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("drop")).unwrap();
+        std::fs::write(root.join("drop/a.ndjson"), "{\"v\":1}\n\n{\"v\":2}\n").unwrap();
+        std::fs::write(root.join("drop/b.json"), "{\"ok\":true}").unwrap();
+
+        let fs = FsConnector::new(Some(root.clone()), 1024);
+        let v = fs.load("file://drop/a.ndjson", None).await.unwrap();
+        assert_eq!(v.as_array().unwrap().len(), 2);
+        assert_eq!(fs.load("file:///drop/b.json", None).await.unwrap()["ok"], true);
+
+        let listing = fs.load("file://drop/*.json", None).await.unwrap();
+        assert_eq!(listing.as_array().unwrap().len(), 1);
+        assert_eq!(listing[0]["path"], "drop/b.json");
+
+        assert!(fs.load("file://drop/../../etc/passwd", None).await.is_err());
+        assert!(FsConnector::new(None, 1024).load("file://drop/b.json", None).await.is_err());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
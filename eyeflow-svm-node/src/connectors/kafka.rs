@@ -0,0 +1,485 @@
+//! Kafka connector — producer sink for CALL_ACTION and audit mirroring
+//!
+//!   kafka://<topic>[?key=<path>]   CALL_ACTION: publish the input JSON to
+//!                                  <topic>, keyed by the input field at <path>
+//!                                  (dot notation; no key = random partition)
+//!   → { "topic", "partition", "offset" }  or  { "topic", "buffered": true }
+//!
+//!   SVM_KAFKA_BROKERS         bootstrap brokers host:port,… (unset = disabled)
+//!   SVM_KAFKA_AUDIT_TOPIC     also publish every audit event here, keyed by
+//!                             node id so the chain stays in order (optional)
+//!   SVM_KAFKA_ACKS            all (default) | leader
+//!   SVM_KAFKA_TIMEOUT_MS      delivery timeout per record (default 5000)
+//!   SVM_KAFKA_BUFFER_PATH     records awaiting a broker (default
+//!                             /tmp/eyeflow_svm_kafka.ndjson)
+//!   SVM_KAFKA_RETRY_SECS      redelivery interval while brokers are down
+//!                             (default 10)
+//!
+//! Broker connections follow the client's security.protocol:
+//!
+//!   SVM_KAFKA_SECURITY_PROTOCOL  PLAINTEXT (default) | SSL | SASL_PLAINTEXT
+//!                                | SASL_SSL
+//!   SVM_KAFKA_SASL_MECHANISM     PLAIN (default) | SCRAM-SHA-256 |
+//!                                SCRAM-SHA-512
+//!   SVM_KAFKA_SASL_USERNAME      SASL credentials (required for SASL_*)
+//!   SVM_KAFKA_SASL_PASSWORD
+//!   SVM_KAFKA_SSL_CA_PATH        CA bundle trusted for the brokers (PEM;
+//!                                default: the system roots)
+//!
+//! Records are produced through librdkafka (rdkafka's FutureProducer) with
+//! Java-compatible murmur2 key partitioning.  A failed SASL exchange or a
+//! refused topic is an AUTH error.
+//!
+//! When no broker is reachable, records go to an OfflineBuffer of their own
+//! (capped at OFFLINE_BUFFER_MAX, persisted) and are redelivered in order
+//! once one is; while a backlog exists new records queue behind it.  Under
+//! disk pressure (disk_watch.rs) that buffer refuses new records.
+//! Delivery is at-least-once.  Audit events always go through the buffer so
+//! mirroring never holds up execution.
+
+use anyhow::{anyhow, Context, Result};
+use rdkafka::config::ClientConfig;
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify};
+use tracing::{debug, info, warn};
+
+use crate::audit::AuditEvent;
+use crate::config::Config;
+use crate::disk_watch::DiskWatch;
+use crate::errors::{ErrorCode, ExecError};
+use crate::health::MetricsCollector;
+use crate::offline::{BufferedEvent, OfflineBuffer};
+
+/// Persist the buffer every N redelivered records (and when it empties).
+const PERSIST_EVERY: u64 = 100;
+
+// ── Records ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Record {
+    pub topic: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub value: Value,
+    pub timestamp_ms: i64,
+}
+
+impl Record {
+    pub fn new(topic: impl Into<String>, key: Option<String>, value: Value) -> Self {
+        Self { topic: topic.into(), key, value, timestamp_ms: chrono::Utc::now().timestamp_millis() }
+    }
+}
+
+/// `kafka://topic?key=path` → (topic, key path).
+pub fn parse_url(url: &str) -> Result<(String, Option<String>)> {
+    let rest = url.strip_prefix("kafka://")
+        .ok_or_else(|| ExecError::validation(format!("not a Kafka URL: {url}")))?;
+    let (topic, query) = rest.split_once('?').unwrap_or((rest, ""));
+    if topic.is_empty() || topic.contains('/') {
+        return Err(ExecError::validation(format!("{url}: expected kafka://<topic>")));
+    }
+    let key = query.split('&')
+        .filter_map(|kv| kv.split_once('='))
+        .find(|(k, _)| *k == "key")
+        .map(|(_, v)| v.to_owned());
+    Ok((topic.to_owned(), key))
+}
+
+// ── Producer ──────────────────────────────────────────────────────────────────
+
+/// librdkafka settings for `config`: brokers, acks, delivery timeout and the
+/// security.protocol / SASL / SSL properties.
+fn client_config(config: &Config) -> Result<ClientConfig> {
+    let acks = match config.kafka_acks.as_str() {
+        "all" => "all",
+        "leader" => "1",
+        other => return Err(anyhow!("SVM_KAFKA_ACKS must be all or leader, got '{other}'")),
+    };
+    let mut cc = ClientConfig::new();
+    cc.set("bootstrap.servers", config.kafka_brokers.join(","))
+        .set("client.id", format!("eyeflow-svm-{}", config.node_id))
+        .set("acks", acks)
+        .set("message.timeout.ms", config.kafka_timeout_ms.to_string())
+        .set("partitioner", "murmur2_random");
+
+    let protocol = config.kafka_security_protocol.to_ascii_uppercase();
+    match protocol.as_str() {
+        "PLAINTEXT" | "SSL" => {}
+        "SASL_PLAINTEXT" | "SASL_SSL" => {
+            let mechanism = config.kafka_sasl_mechanism.to_ascii_uppercase();
+            if !matches!(mechanism.as_str(), "PLAIN" | "SCRAM-SHA-256" | "SCRAM-SHA-512") {
+                return Err(anyhow!("SVM_KAFKA_SASL_MECHANISM must be PLAIN, SCRAM-SHA-256 or SCRAM-SHA-512, got '{}'", config.kafka_sasl_mechanism));
+            }
+            let (Some(username), Some(password)) = (&config.kafka_sasl_username, &config.kafka_sasl_password) else {
+                return Err(anyhow!("{protocol} needs SVM_KAFKA_SASL_USERNAME and SVM_KAFKA_SASL_PASSWORD"));
+            };
+            cc.set("sasl.mechanism", mechanism)
+                .set("sasl.username", username)
+                .set("sasl.password", password);
+        }
+        _ => return Err(anyhow!("SVM_KAFKA_SECURITY_PROTOCOL must be PLAINTEXT, SSL, SASL_PLAINTEXT or SASL_SSL, got '{}'", config.kafka_security_protocol)),
+    }
+    cc.set("security.protocol", protocol);
+    if let Some(ca) = &config.kafka_ssl_ca_path {
+        cc.set("ssl.ca.location", ca);
+    }
+    Ok(cc)
+}
+
+/// Map a librdkafka failure onto the error taxonomy: unreachable or slow
+/// brokers are transient (the record is buffered), refused credentials are
+/// AUTH, records the broker can never accept are VALIDATION.
+fn kafka_error(topic: &str, e: KafkaError) -> anyhow::Error {
+    use RDKafkaErrorCode as C;
+    let code = match e.rdkafka_error_code() {
+        Some(C::Authentication | C::SaslAuthenticationFailed | C::UnsupportedSASLMechanism
+            | C::TopicAuthorizationFailed | C::ClusterAuthorizationFailed) => ErrorCode::Auth,
+        Some(C::MessageTimedOut | C::RequestTimedOut | C::OperationTimedOut) => ErrorCode::Timeout,
+        Some(C::AllBrokersDown | C::BrokerTransportFailure | C::Resolve | C::NetworkException
+            | C::BrokerNotAvailable) => ErrorCode::Network,
+        Some(C::QueueFull) => ErrorCode::Quota,
+        Some(C::MessageSizeTooLarge | C::InvalidMessage | C::InvalidRecord | C::InvalidTopic) => ErrorCode::Validation,
+        _ => ErrorCode::Upstream,
+    };
+    ExecError::new(code, format!("{topic}: {e}")).into()
+}
+
+/// Produce `record` and wait for its acknowledgement → (partition, offset).
+async fn send(producer: &FutureProducer, record: &Record, timeout: Duration) -> Result<(i32, i64)> {
+    let value = serde_json::to_vec(&record.value)?;
+    let mut out = FutureRecord::<String, _>::to(&record.topic)
+        .payload(&value)
+        .timestamp(record.timestamp_ms);
+    if let Some(key) = &record.key {
+        out = out.key(key);
+    }
+    producer.send(out, Timeout::After(timeout)).await
+        .map_err(|(e, _)| kafka_error(&record.topic, e))
+}
+
+// ── Sink ──────────────────────────────────────────────────────────────────────
+
+pub struct KafkaSink {
+    producer: FutureProducer,
+    timeout: Duration,
+    buffer: Mutex<OfflineBuffer>,
+    wake: Notify,
+    retry: Duration,
+    audit_topic: Option<String>,
+    depth: AtomicU64,
+    delivered: AtomicU64,
+    buffered: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl KafkaSink {
+    /// None when SVM_KAFKA_BROKERS is not set.  Restores any persisted backlog.
+    /// Records are refused while `disk` reports pressure.
+    pub async fn from_config(config: &Config, disk: Option<Arc<DiskWatch>>) -> Result<Option<Arc<Self>>> {
+        if config.kafka_brokers.is_empty() {
+            return Ok(None);
+        }
+        let producer = client_config(config)?.create().context("Kafka producer")?;
+        let path = PathBuf::from(&config.kafka_buffer_path);
+        crate::offline::ensure_parent(&path).await?;
+        let mut buffer = OfflineBuffer::new(&path, config.offline_buffer_max).with_disk_watch(disk);
+        match buffer.load().await {
+            Ok(0) => {}
+            Ok(n) => info!("[Kafka] {n} buffered record(s) restored"),
+            Err(e) => warn!("[Kafka] failed to load buffer: {e}"),
+        }
+        let sink = Self::new(producer, buffer, config.kafka_audit_topic.clone(), Duration::from_secs(config.kafka_retry_secs.max(1)))
+            .with_timeout(Duration::from_millis(config.kafka_timeout_ms));
+        Ok(Some(Arc::new(sink)))
+    }
+
+    fn new(producer: FutureProducer, buffer: OfflineBuffer, audit_topic: Option<String>, retry: Duration) -> Self {
+        Self {
+            depth: AtomicU64::new(buffer.len() as u64),
+            producer,
+            timeout: Duration::from_millis(5000),
+            buffer: Mutex::new(buffer),
+            wake: Notify::new(),
+            retry,
+            audit_topic,
+            delivered: AtomicU64::new(0),
+            buffered: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Publish now, or buffer the record when no broker is reachable.
+    pub async fn publish(&self, record: Record) -> Result<Value> {
+        let topic = record.topic.clone();
+        if self.buffer.lock().await.is_empty() {
+            match send(&self.producer, &record, self.timeout).await {
+                Ok((partition, offset)) => {
+                    self.delivered.fetch_add(1, Ordering::Relaxed);
+                    return Ok(json!({ "topic": topic, "partition": partition, "offset": offset }));
+                }
+                Err(e) if crate::errors::classify(&e).is_transient() => {
+                    warn!("[Kafka] {topic}: {e} — buffering");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if self.enqueue(vec![record]).await == 0 {
+            return Err(ExecError::new(ErrorCode::Quota, format!("{topic}: broker unreachable and disk under pressure — record refused")).into());
+        }
+        Ok(json!({ "topic": topic, "buffered": true }))
+    }
+
+    /// Queue `events` for the audit topic, if one is configured.
+    pub async fn mirror_audit(&self, events: &[AuditEvent]) {
+        let Some(topic) = &self.audit_topic else { return };
+        if events.is_empty() {
+            return;
+        }
+        let records = events.iter()
+            .map(|ev| Record::new(topic, Some(ev.node_id.clone()), json!(ev)))
+            .collect();
+        self.enqueue(records).await;
+    }
+
+    /// Buffer `records`; returns how many were accepted.
+    async fn enqueue(&self, records: Vec<Record>) -> usize {
+        let mut buffer = self.buffer.lock().await;
+        let mut accepted = 0;
+        for record in records {
+            if buffer.enqueue_kafka_record(json!(record)) {
+                accepted += 1;
+            }
+        }
+        self.buffered.fetch_add(accepted as u64, Ordering::Relaxed);
+        if let Err(e) = buffer.persist().await {
+            warn!("[Kafka] failed to persist buffer: {e}");
+        }
+        self.depth.store(buffer.len() as u64, Ordering::Relaxed);
+        self.wake.notify_one();
+        accepted
+    }
+
+    /// Redeliver buffered records in order.  False when a broker is still
+    /// unreachable (the rest stays queued).
+    async fn deliver_buffered(&self) -> bool {
+        let mut sent = 0u64;
+        loop {
+            // Only this task removes records, so the front stays put while
+            // the buffer is unlocked for the send
+            let next = self.buffer.lock().await.front().cloned();
+            let outcome = match next {
+                None => {
+                    if let Err(e) = self.buffer.lock().await.clear_disk().await {
+                        warn!("[Kafka] failed to clear buffer file: {e}");
+                    }
+                    return true;
+                }
+                Some(BufferedEvent::KafkaRecord { payload, .. }) => match serde_json::from_value::<Record>(payload) {
+                    Ok(record) => send(&self.producer, &record, self.timeout).await.map(|_| ()),
+                    Err(e) => Err(e.into()),
+                },
+                Some(_) => Err(anyhow!("not a Kafka record")),
+            };
+            match outcome {
+                Ok(()) => {
+                    self.delivered.fetch_add(1, Ordering::Relaxed);
+                    sent += 1;
+                }
+                Err(e) if crate::errors::classify(&e).is_transient() => {
+                    debug!("[Kafka] redelivery paused: {e}");
+                    return false;
+                }
+                Err(e) => {
+                    warn!("[Kafka] dropping buffered record: {e}");
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            let mut buffer = self.buffer.lock().await;
+            buffer.pop_front();
+            self.depth.store(buffer.len() as u64, Ordering::Relaxed);
+            if sent.is_multiple_of(PERSIST_EVERY) && !buffer.is_empty() {
+                if let Err(e) = buffer.persist().await {
+                    warn!("[Kafka] failed to persist buffer: {e}");
+                }
+            }
+        }
+    }
+
+    /// Redelivery task: drain the backlog whenever records are buffered,
+    /// retrying every SVM_KAFKA_RETRY_SECS while brokers are down.
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            while !self.deliver_buffered().await {
+                tokio::time::sleep(self.retry).await;
+            }
+            self.wake.notified().await;
+        }
+    }
+}
+
+impl MetricsCollector for KafkaSink {
+    fn prometheus(&self, node_id: &str) -> String {
+        let mut out = String::from(
+            "# HELP eyeflow_kafka_records_total Kafka records by outcome\n\
+             # TYPE eyeflow_kafka_records_total counter\n",
+        );
+        for (result, n) in [("delivered", &self.delivered), ("buffered", &self.buffered), ("dropped", &self.dropped)] {
+            out.push_str(&format!(
+                "eyeflow_kafka_records_total{{node_id=\"{node_id}\",result=\"{result}\"}} {}\n",
+                n.load(Ordering::Relaxed),
+            ));
+        }
+        out.push_str(&format!(
+            "# HELP eyeflow_kafka_buffer_depth Records waiting for a reachable broker\n\
+             # TYPE eyeflow_kafka_buffer_depth gauge\n\
+             eyeflow_kafka_buffer_depth{{node_id=\"{node_id}\"}} {}\n",
+            self.depth.load(Ordering::Relaxed),
+        ));
+        out
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rdkafka::consumer::{BaseConsumer, Consumer};
+    use rdkafka::mocking::MockCluster;
+    use rdkafka::{Message, Offset, TopicPartitionList};
+
+    fn config() -> Config {
+        let mut config = Config::from_env();
+        config.kafka_brokers = vec!["kafka-1:9092".into(), "kafka-2:9092".into()];
+        config.kafka_acks = "all".into();
+        config.kafka_security_protocol = "PLAINTEXT".into();
+        config.kafka_sasl_mechanism = "PLAIN".into();
+        config.kafka_sasl_username = None;
+        config.kafka_sasl_password = None;
+        config.kafka_ssl_ca_path = None;
+        config
+    }
+
+    #[test]
+    fn test_client_config() {
+        let cc = client_config(&config()).unwrap();
+        assert_eq!(cc.get("bootstrap.servers"), Some("kafka-1:9092,kafka-2:9092"));
+        assert_eq!(cc.get("acks"), Some("all"));
+        assert_eq!(cc.get("security.protocol"), Some("PLAINTEXT"));
+        assert_eq!(cc.get("sasl.mechanism"), None);
+
+        let mut c = config();
+        c.kafka_acks = "leader".into();
+        c.kafka_security_protocol = "sasl_ssl".into();
+        c.kafka_sasl_mechanism = "scram-sha-512".into();
+        c.kafka_sasl_username = Some("svm".into());
+        c.kafka_sasl_password = Some("secret".into());
+        c.kafka_ssl_ca_path = Some("/etc/eyeflow/kafka-ca.pem".into());
+        let cc = client_config(&c).unwrap();
+        assert_eq!(cc.get("acks"), Some("1"));
+        assert_eq!(cc.get("security.protocol"), Some("SASL_SSL"));
+        assert_eq!(cc.get("sasl.mechanism"), Some("SCRAM-SHA-512"));
+        assert_eq!(cc.get("sasl.username"), Some("svm"));
+        assert_eq!(cc.get("ssl.ca.location"), Some("/etc/eyeflow/kafka-ca.pem"));
+
+        // SASL without credentials, unknown mechanism / protocol / acks
+        c.kafka_sasl_password = None;
+        assert!(client_config(&c).unwrap_err().to_string().contains("SVM_KAFKA_SASL_PASSWORD"));
+        c.kafka_sasl_password = Some("secret".into());
+        c.kafka_sasl_mechanism = "GSSAPI".into();
+        assert!(client_config(&c).is_err());
+        let mut c = config();
+        c.kafka_security_protocol = "TLS".into();
+        assert!(client_config(&c).is_err());
+        let mut c = config();
+        c.kafka_acks = "none".into();
+        assert!(client_config(&c).is_err());
+    }
+
+    #[test]
+    fn test_error_codes() {
+        use RDKafkaErrorCode as C;
+        for (code, expected) in [
+            (C::SaslAuthenticationFailed, ErrorCode::Auth),
+            (C::TopicAuthorizationFailed, ErrorCode::Auth),
+            (C::MessageTimedOut, ErrorCode::Timeout),
+            (C::AllBrokersDown, ErrorCode::Network),
+            (C::QueueFull, ErrorCode::Quota),
+            (C::MessageSizeTooLarge, ErrorCode::Validation),
+            (C::NotLeaderForPartition, ErrorCode::Upstream),
+        ] {
+            let e = kafka_error("events", KafkaError::MessageProduction(code));
+            assert_eq!(crate::errors::classify(&e), expected, "{code:?}");
+        }
+    }
+
+    /// Values on events/0 from the start, in order.
+    fn consume(bootstrap: &str) -> Vec<String> {
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", bootstrap)
+            .set("group.id", "test")
+            .create()
+            .unwrap();
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition_offset("events", 0, Offset::Beginning).unwrap();
+        consumer.assign(&tpl).unwrap();
+        let mut values = Vec::new();
+        while let Some(msg) = consumer.poll(Duration::from_secs(5)) {
+            let msg = msg.unwrap();
+            values.push(String::from_utf8(msg.payload().unwrap().to_vec()).unwrap());
+            if values.len() == 3 {
+                break;
+            }
+        }
+        values
+    }
+
+    #[tokio::test]
+    async fn test_buffer_until_broker_is_reachable() {
+        let mock = MockCluster::new(1).unwrap();
+        mock.create_topic("events", 1, 1).unwrap();
+        let bootstrap = mock.bootstrap_servers();
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &bootstrap)
+            .set("message.timeout.ms", "1000")
+            .create()
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("eyeflow_kafka_{}.ndjson", uuid::Uuid::new_v4()));
+        let sink = KafkaSink::new(producer, OfflineBuffer::new(&path, 100), Some("audit".into()), Duration::from_secs(1))
+            .with_timeout(Duration::from_secs(1));
+
+        // Broker down: buffered, and later records queue behind it
+        mock.broker_down(1).unwrap();
+        let out = sink.publish(Record::new("events", Some("pump-1".into()), json!({ "rpm": 1200 }))).await.unwrap();
+        assert_eq!(out, json!({ "topic": "events", "buffered": true }));
+        sink.publish(Record::new("events", None, json!({ "rpm": 0 }))).await.unwrap();
+        assert!(!sink.deliver_buffered().await);
+        assert_eq!(sink.depth.load(Ordering::Relaxed), 2);
+        assert!(path.exists());
+
+        // Broker back: backlog drains in order, then records go straight out
+        mock.broker_up(1).unwrap();
+        assert!(sink.deliver_buffered().await);
+        assert_eq!(sink.depth.load(Ordering::Relaxed), 0);
+        assert!(!path.exists());
+        let out = sink.publish(Record::new("events", None, json!(1))).await.unwrap();
+        assert_eq!(out, json!({ "topic": "events", "partition": 0, "offset": 2 }));
+        assert_eq!(consume(&bootstrap), ["{\"rpm\":1200}", "{\"rpm\":0}", "1"]);
+        assert!(sink.prometheus("n1").contains("result=\"delivered\"} 3"));
+    }
+}
@@ -0,0 +1,60 @@
+//! Connectors — non-HTTP resource and service backends for the SVM.
+//!
+//! Each connector owns one URL scheme (or ServiceFormat) and is selected by
+//! the opcode handlers in `svm.rs` from `dispatch_metadata.endpoint_url`:
+//!
+//!   file://   — sandboxed local filesystem (fs.rs)
+//!   postgres:// mysql:// sqlite:// — SQL databases (sql.rs, feature `sql`)
+//!   s3://     — S3-compatible object storage (s3.rs)
+//!   kafka://  — topic producer for CALL_ACTION + audit mirroring (kafka.rs)
+//!   zigbee:// zwave:// — devices behind zigbee2mqtt / Z-Wave JS UI (mqtt.rs)
+//!   sms:// voice:// — alerts via Twilio-compatible APIs / MessageBird (notify.rs)
+//!   gpio:// serial:// — local devices for CALL_ACTION (device.rs, Linux)
+//!   ble://    — GATT characteristics of BLE sensors (ble.rs, Linux)
+//!   rtsp:// camera+http:// — JPEG snapshots of IP cameras (camera.rs)
+//!   snmp://   — SNMP v2c/v3 GET and WALK on network equipment (snmp.rs)
+//!   plugin:// — customer connectors from SVM_PLUGIN_DIR (plugin.rs, feature `plugins`)
+
+#[cfg(target_os = "linux")]
+pub mod ble;
+pub mod camera;
+#[cfg(target_os = "linux")]
+pub mod device;
+pub mod fs;
+pub mod kafka;
+pub mod mqtt;
+pub mod notify;
+pub mod plugin;
+pub mod s3;
+pub mod snmp;
+#[cfg(feature = "sql")]
+pub mod sql;
+
+/// True when `url` addresses a database handled by the SQL connector.
+pub fn is_sql_url(url: &str) -> bool {
+    ["postgres://", "postgresql://", "mysql://", "sqlite:"]
+        .iter()
+        .any(|p| url.starts_with(p))
+}
+
+/// True when `url` addresses an IP camera snapshot.
+pub fn is_camera_url(url: &str) -> bool {
+    ["rtsp://", "rtsps://", "camera+http://", "camera+https://"]
+        .iter()
+        .any(|p| url.starts_with(p))
+}
+
+/// True when `url` addresses a Zigbee or Z-Wave device behind an MQTT bridge.
+pub fn is_bridge_url(url: &str) -> bool {
+    url.starts_with("zigbee://") || url.starts_with("zwave://")
+}
+
+/// True when `url` pages someone by SMS or voice call.
+pub fn is_alert_url(url: &str) -> bool {
+    url.starts_with("sms://") || url.starts_with("voice://")
+}
+
+/// True when `url` addresses a local GPIO line or serial port.
+pub fn is_device_url(url: &str) -> bool {
+    url.starts_with("gpio://") || url.starts_with("serial://")
+}
@@ -0,0 +1,709 @@
+//! Zigbee / Z-Wave connector — devices behind zigbee2mqtt and Z-Wave JS UI
+//!
+//! Smart-facility workflows address devices by their friendly name; the
+//! bridges' MQTT conventions are handled here:
+//!
+//!   zigbee://                     LOAD_RESOURCE: devices known to zigbee2mqtt
+//!   zigbee://<friendly name>      LOAD_RESOURCE: { device, state, available }
+//!                                 CALL_ACTION: publish the input object to
+//!                                 <base>/<name>/set → { device, sent }
+//!       `?confirm`                CALL_ACTION also waits for the state the
+//!                                 device reports back → { …, state }
+//!   zwave://                      LOAD_RESOURCE: nodes (gateway API getNodes)
+//!   zwave://<node>                LOAD_RESOURCE: { device, id, values, available },
+//!                                 values keyed "<cc>/<endpoint>/<property>"
+//!   zwave://<node>/<cc>/<endpoint>/<property>[/<key>]
+//!                                 LOAD_RESOURCE: { device, value }
+//!                                 CALL_ACTION: publish { "value": input } (or
+//!                                 the input's own `value`) to …/set
+//!
+//! Names are percent-encoded in URLs ("zigbee://Kitchen%20Lamp").  A Z-Wave
+//! node is its name, or `nodeID_<id>` while unnamed; Z-Wave JS UI must use
+//! named topics and the JSON time-value payload (its defaults).  Unknown
+//! devices fail with NOT_FOUND.
+//!
+//!   SVM_MQTT_URL              mqtt://[user:password@]host[:port] (unset = off)
+//!   SVM_MQTT_TIMEOUT_MS       wait for a PUBACK, state or API reply (default 5000)
+//!   SVM_ZIGBEE2MQTT_TOPIC     zigbee2mqtt base topic (default zigbee2mqtt)
+//!   SVM_ZWAVE_TOPIC           Z-Wave JS UI prefix (default zwave)
+//!   SVM_ZWAVE_GATEWAY         Z-Wave JS UI gateway name (default zwave-js-ui)
+//!
+//! One rumqttc connection (MQTT 3.1.1, clean session) subscribes to both
+//! bridges' topic trees and keeps the last payload of every topic, so reads
+//! are served locally — retained device lists and states arrive on
+//! subscribe; a zigbee2mqtt device without a known state is asked with
+//! `/get`.  Commands are published at QoS 1 and succeed on the broker's
+//! PUBACK.  They are only queued while connected, and the ones a dropped
+//! session had not delivered are discarded rather than replayed late.  The
+//! connection is re-established with backoff (backoff.rs); plaintext only.
+
+use anyhow::{anyhow, Result};
+use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Outgoing, Packet, QoS, SubscribeFilter, SubscribeReasonCode};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot, watch};
+use tracing::{debug, info, warn};
+
+use crate::backoff::Backoff;
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+use crate::health::MetricsCollector;
+
+const KEEPALIVE: Duration = Duration::from_secs(30);
+/// Largest packet accepted from (and sent to) the broker.
+const MAX_PACKET_BYTES: usize = 4 << 20;
+/// Requests queued for the event loop.
+const REQUEST_CAPACITY: usize = 64;
+
+// ── Bridge ────────────────────────────────────────────────────────────────────
+
+/// Connection options for an mqtt://[user:password@]host[:port] URL.
+fn mqtt_options(url: &str, client_id: &str) -> Result<MqttOptions> {
+    let u = reqwest::Url::parse(url).map_err(|e| anyhow!("invalid MQTT URL {url}: {e}"))?;
+    if u.scheme() != "mqtt" && u.scheme() != "tcp" {
+        return Err(anyhow!("unsupported MQTT URL scheme: {} (plaintext mqtt:// only)", u.scheme()));
+    }
+    let host = u.host_str().ok_or_else(|| anyhow!("MQTT URL {url} has no host"))?;
+    let mut options = MqttOptions::new(client_id, host, u.port().unwrap_or(1883));
+    options
+        .set_keep_alive(KEEPALIVE)
+        .set_clean_session(true)
+        .set_max_packet_size(MAX_PACKET_BYTES, MAX_PACKET_BYTES)
+        .set_request_channel_capacity(REQUEST_CAPACITY);
+    if !u.username().is_empty() {
+        options.set_credentials(unescape(u.username()), u.password().map(unescape).unwrap_or_default());
+    }
+    Ok(options)
+}
+
+pub struct MqttBridge {
+    addr: String,
+    timeout: Duration,
+    zigbee_base: String,
+    zwave_base: String,
+    zwave_gateway: String,
+    client: AsyncClient,
+    /// Taken by `run`
+    eventloop: Mutex<Option<EventLoop>>,
+    /// Last payload of every bridge topic (JSON, else a string)
+    topics: Mutex<HashMap<String, Value>>,
+    /// Every message as it arrives
+    messages: broadcast::Sender<(String, Value)>,
+    /// Publishes queued for the event loop, in order; each gets its packet
+    /// id when the event loop sends it
+    sending: Mutex<VecDeque<oneshot::Sender<()>>>,
+    /// PUBACKs being waited for
+    pending: Mutex<HashMap<u16, oneshot::Sender<()>>>,
+    connected: watch::Sender<bool>,
+    /// Z-Wave nodes from the last getNodes
+    zwave_nodes: tokio::sync::Mutex<Option<Vec<Value>>>,
+    published: AtomicU64,
+    received: AtomicU64,
+    connects: AtomicU64,
+}
+
+impl MqttBridge {
+    /// The bridge connector, or None when SVM_MQTT_URL is unset.
+    pub fn from_config(config: &Config) -> Result<Option<Arc<Self>>> {
+        let Some(url) = config.mqtt_url.as_deref() else { return Ok(None) };
+        Ok(Some(Arc::new(Self::new(
+            mqtt_options(url, &format!("eyeflow-svm-{}", config.node_id))?,
+            Duration::from_millis(config.mqtt_timeout_ms),
+            [&config.zigbee2mqtt_topic, &config.zwave_topic, &config.zwave_gateway].map(|s| s.trim_matches('/').to_owned()),
+        ))))
+    }
+
+    fn new(options: MqttOptions, timeout: Duration, [zigbee_base, zwave_base, zwave_gateway]: [String; 3]) -> Self {
+        let (host, port) = options.broker_address();
+        let (client, mut eventloop) = AsyncClient::new(options, REQUEST_CAPACITY);
+        eventloop.network_options.set_connection_timeout(timeout.as_secs().max(1));
+        Self {
+            addr: format!("{host}:{port}"),
+            timeout,
+            zigbee_base,
+            zwave_base,
+            zwave_gateway,
+            client,
+            eventloop: Mutex::new(Some(eventloop)),
+            topics: Mutex::new(HashMap::new()),
+            messages: broadcast::channel(256).0,
+            sending: Mutex::new(VecDeque::new()),
+            pending: Mutex::new(HashMap::new()),
+            connected: watch::channel(false).0,
+            zwave_nodes: tokio::sync::Mutex::new(None),
+            published: AtomicU64::new(0),
+            received: AtomicU64::new(0),
+            connects: AtomicU64::new(0),
+        }
+    }
+
+    /// Keep the broker connection up; runs for the node's lifetime.
+    pub async fn run(self: Arc<Self>) {
+        let Some(mut eventloop) = self.eventloop.lock().unwrap_or_else(|e| e.into_inner()).take() else { return };
+        let filters = [format!("{}/#", self.zigbee_base), format!("{}/#", self.zwave_base)];
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(60));
+        let mut established = false;
+        loop {
+            match eventloop.poll().await {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    let subscribe = filters.iter().map(|f| SubscribeFilter::new(f.clone(), QoS::AtMostOnce));
+                    if let Err(e) = self.client.try_subscribe_many(subscribe) {
+                        warn!("[MQTT] subscribe: {e}");
+                    }
+                    established = true;
+                    self.connects.fetch_add(1, Ordering::Relaxed);
+                    self.connected.send_replace(true);
+                    info!("[MQTT] connected to {} ({})", self.addr, filters.join(", "));
+                }
+                Ok(Event::Incoming(Packet::Publish(publish))) => self.on_message(publish.topic, &publish.payload),
+                Ok(Event::Incoming(Packet::PubAck(ack))) => {
+                    if let Some(waiter) = self.pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&ack.pkid) {
+                        let _ = waiter.send(());
+                    }
+                }
+                Ok(Event::Incoming(Packet::SubAck(ack))) if ack.return_codes.contains(&SubscribeReasonCode::Failure) => {
+                    warn!("[MQTT] broker refused a subscription ({})", filters.join(", "));
+                }
+                Ok(Event::Outgoing(Outgoing::Publish(pkid))) => {
+                    if let Some(waiter) = self.sending.lock().unwrap_or_else(|e| e.into_inner()).pop_front() {
+                        self.pending.lock().unwrap_or_else(|e| e.into_inner()).insert(pkid, waiter);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!("[MQTT] {}: {e}", self.addr);
+                    {
+                        // Under the queue lock so no publish slips in between
+                        let mut sending = self.sending.lock().unwrap_or_else(|e| e.into_inner());
+                        self.connected.send_replace(false);
+                        sending.clear();
+                        eventloop.clean();
+                        eventloop.pending.clear();
+                    }
+                    // Waiters see the connection loss instead of their timeout
+                    self.pending.lock().unwrap_or_else(|e| e.into_inner()).clear();
+                    let delay = {
+                        let mut rng = rand::thread_rng();
+                        if std::mem::take(&mut established) { backoff.after_session(&mut rng) } else { backoff.after_network_error(&mut rng) }
+                    };
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    fn on_message(&self, topic: String, payload: &[u8]) {
+        // Our own commands echo back through the wildcard subscriptions
+        if topic.ends_with("/set") || topic.ends_with("/get") {
+            return;
+        }
+        self.received.fetch_add(1, Ordering::Relaxed);
+        let value = serde_json::from_slice(payload)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(payload).into_owned()));
+        let mut topics = self.topics.lock().unwrap_or_else(|e| e.into_inner());
+        if payload.is_empty() {
+            topics.remove(&topic); // retained message cleared
+        } else {
+            topics.insert(topic.clone(), value.clone());
+        }
+        drop(topics);
+        let _ = self.messages.send((topic, value));
+    }
+
+    fn cached(&self, topic: &str) -> Option<Value> {
+        self.topics.lock().unwrap_or_else(|e| e.into_inner()).get(topic).cloned()
+    }
+
+    /// Queue `payload` at QoS 1 while connected.
+    fn enqueue(&self, topic: &str, payload: &Value) -> Result<oneshot::Receiver<()>> {
+        let mut sending = self.sending.lock().unwrap_or_else(|e| e.into_inner());
+        if !*self.connected.borrow() {
+            return Err(ExecError::new(ErrorCode::Network, format!("MQTT: connection lost before {topic} was sent")).into());
+        }
+        let (ack, acked) = oneshot::channel();
+        sending.push_back(ack);
+        if let Err(e) = self.client.try_publish(topic, QoS::AtLeastOnce, false, payload.to_string()) {
+            sending.pop_back();
+            return Err(ExecError::new(ErrorCode::Network, format!("MQTT: cannot publish {topic}: {e}")).into());
+        }
+        Ok(acked)
+    }
+
+    /// Publish `payload` at QoS 1 and wait for the broker's PUBACK.
+    async fn publish(&self, topic: &str, payload: &Value) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        let mut connected = self.connected.subscribe();
+        if tokio::time::timeout_at(deadline, connected.wait_for(|c| *c)).await.is_err() {
+            return Err(ExecError::new(ErrorCode::Network, format!("MQTT broker {} is not reachable", self.addr)).into());
+        }
+        let acked = self.enqueue(topic, payload)?;
+        match tokio::time::timeout_at(deadline, acked).await {
+            Ok(Ok(())) => {
+                self.published.fetch_add(1, Ordering::Relaxed);
+                debug!("[MQTT] published {topic}");
+                Ok(())
+            }
+            Ok(Err(_)) => Err(ExecError::new(ErrorCode::Network, format!("MQTT: connection lost before {topic} was acknowledged")).into()),
+            Err(_) => Err(ExecError::new(ErrorCode::Timeout, format!("MQTT: {topic} not acknowledged within {:?}", self.timeout)).into()),
+        }
+    }
+
+    /// Wait for the next message on `topic` from `rx` (subscribed beforehand).
+    async fn next_on(&self, rx: &mut broadcast::Receiver<(String, Value)>, topic: &str) -> Result<Value> {
+        let wait = async {
+            loop {
+                match rx.recv().await {
+                    Ok((t, value)) if t == topic => return Ok(value),
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return Err(anyhow!("MQTT bridge stopped")),
+                }
+            }
+        };
+        tokio::time::timeout(self.timeout, wait).await.map_err(|_| {
+            ExecError::new(ErrorCode::Timeout, format!("MQTT: nothing on {topic} within {:?}", self.timeout))
+        })?
+    }
+
+    /// The cached payload of `topic`, else the next one to arrive.
+    async fn current(&self, topic: &str) -> Result<Value> {
+        let mut rx = self.messages.subscribe();
+        match self.cached(topic) {
+            Some(value) => Ok(value),
+            None => self.next_on(&mut rx, topic).await,
+        }
+    }
+
+    /// LOAD_RESOURCE `zigbee://` / `zwave://`.
+    pub async fn load(&self, url: &str) -> Result<Value> {
+        match Target::parse(url)? {
+            Target::Zigbee { name, .. } if name.is_empty() => self.zigbee_devices().await,
+            Target::Zigbee { name, .. } => self.zigbee_state(&name).await,
+            Target::Zwave { name, .. } if name.is_empty() => self.zwave_list().await,
+            Target::Zwave { name, path } => self.zwave_values(&name, path.as_deref()).await,
+        }
+    }
+
+    /// CALL_ACTION `zigbee://` / `zwave://`.
+    pub async fn call(&self, url: &str, input: Option<&Value>) -> Result<Value> {
+        let input = input.cloned().unwrap_or(Value::Null);
+        match Target::parse(url)? {
+            Target::Zigbee { name, confirm } if !name.is_empty() => self.zigbee_command(&name, input, confirm).await,
+            Target::Zwave { name, path: Some(path) } => self.zwave_command(&name, &path, input).await,
+            _ => Err(ExecError::validation(format!("{url}: CALL_ACTION needs a device (and for Z-Wave a value path)"))),
+        }
+    }
+
+    // ── zigbee2mqtt ──
+
+    async fn zigbee_device(&self, name: &str) -> Result<Value> {
+        let devices = self.current(&format!("{}/bridge/devices", self.zigbee_base)).await?;
+        devices.as_array().into_iter().flatten()
+            .find(|d| d["friendly_name"] == name)
+            .cloned()
+            .ok_or_else(|| ExecError::new(ErrorCode::NotFound, format!("zigbee2mqtt has no device '{name}'")).into())
+    }
+
+    fn zigbee_available(&self, name: &str) -> Value {
+        match self.cached(&format!("{}/{name}/availability", self.zigbee_base)) {
+            Some(Value::Object(o)) => json!(o.get("state").and_then(Value::as_str) == Some("online")),
+            Some(Value::String(s)) => json!(s == "online"),
+            _ => Value::Null,
+        }
+    }
+
+    async fn zigbee_devices(&self) -> Result<Value> {
+        let devices = self.current(&format!("{}/bridge/devices", self.zigbee_base)).await?;
+        let list: Vec<Value> = devices.as_array().into_iter().flatten()
+            .filter(|d| d["type"] != "Coordinator")
+            .map(|d| {
+                let name = d["friendly_name"].as_str().unwrap_or_default();
+                json!({
+                    "name": name,
+                    "ieee": d["ieee_address"],
+                    "type": d["type"],
+                    "model": d["definition"]["model"],
+                    "vendor": d["definition"]["vendor"],
+                    "description": d["definition"]["description"],
+                    "available": self.zigbee_available(name),
+                })
+            })
+            .collect();
+        Ok(json!({ "devices": list }))
+    }
+
+    async fn zigbee_state(&self, name: &str) -> Result<Value> {
+        self.zigbee_device(name).await?;
+        let topic = format!("{}/{name}", self.zigbee_base);
+        let mut rx = self.messages.subscribe();
+        let state = match self.cached(&topic) {
+            Some(state) => state,
+            None => {
+                self.publish(&format!("{topic}/get"), &json!({ "state": "" })).await?;
+                self.next_on(&mut rx, &topic).await?
+            }
+        };
+        Ok(json!({ "device": name, "state": state, "available": self.zigbee_available(name) }))
+    }
+
+    async fn zigbee_command(&self, name: &str, input: Value, confirm: bool) -> Result<Value> {
+        if !input.is_object() {
+            return Err(ExecError::validation(format!("zigbee://{name}: the command must be an object, e.g. {{\"state\": \"ON\"}}")));
+        }
+        self.zigbee_device(name).await?;
+        let topic = format!("{}/{name}", self.zigbee_base);
+        let mut rx = self.messages.subscribe();
+        self.publish(&format!("{topic}/set"), &input).await?;
+        let mut out = json!({ "device": name, "sent": input });
+        if confirm {
+            out["state"] = self.next_on(&mut rx, &topic).await?;
+        }
+        Ok(out)
+    }
+
+    // ── Z-Wave JS UI ──
+
+    /// Call the gateway's MQTT API.
+    async fn zwave_api(&self, api: &str, args: Value) -> Result<Value> {
+        let topic = format!("{}/_CLIENTS/ZWAVE_GATEWAY-{}/api/{api}", self.zwave_base, self.zwave_gateway);
+        let mut rx = self.messages.subscribe();
+        self.publish(&format!("{topic}/set"), &json!({ "args": args })).await?;
+        let reply = self.next_on(&mut rx, &topic).await?;
+        if reply["success"] != true {
+            let message = reply["message"].as_str().unwrap_or("failed");
+            return Err(ExecError::new(ErrorCode::Upstream, format!("Z-Wave JS UI {api}: {message}")).into());
+        }
+        Ok(reply["result"].clone())
+    }
+
+    async fn zwave_nodes(&self, refresh: bool) -> Result<Vec<Value>> {
+        let mut nodes = self.zwave_nodes.lock().await;
+        if refresh || nodes.is_none() {
+            let result = self.zwave_api("getNodes", json!([])).await?;
+            *nodes = Some(result.as_array().cloned().unwrap_or_default());
+        }
+        Ok(nodes.clone().unwrap_or_default())
+    }
+
+    /// Topic segment naming a node.
+    fn zwave_node_name(node: &Value) -> String {
+        match node["name"].as_str().filter(|n| !n.is_empty()) {
+            Some(name) => name.to_owned(),
+            None => format!("nodeID_{}", node["id"]),
+        }
+    }
+
+    /// The node called `name` and its topic prefix; refreshes the node list
+    /// once when the name is unknown.
+    async fn zwave_node(&self, name: &str) -> Result<(Value, String)> {
+        for refresh in [false, true] {
+            let node = self.zwave_nodes(refresh).await?.into_iter().find(|n| Self::zwave_node_name(n) == name);
+            if let Some(node) = node {
+                let prefix = match node["loc"].as_str().filter(|l| !l.is_empty()) {
+                    Some(loc) => format!("{}/{loc}/{name}", self.zwave_base),
+                    None => format!("{}/{name}", self.zwave_base),
+                };
+                return Ok((node, prefix));
+            }
+        }
+        Err(ExecError::new(ErrorCode::NotFound, format!("Z-Wave JS UI has no node '{name}'")).into())
+    }
+
+    fn zwave_available(node: &Value) -> Value {
+        match node["status"].as_str() {
+            Some("Dead") => json!(false),
+            Some("Alive" | "Awake" | "Asleep") => json!(true),
+            _ => Value::Null,
+        }
+    }
+
+    async fn zwave_list(&self) -> Result<Value> {
+        let list: Vec<Value> = self.zwave_nodes(true).await?.iter()
+            .filter(|n| n["isControllerNode"] != true)
+            .map(|n| json!({
+                "name": Self::zwave_node_name(n),
+                "id": n["id"],
+                "location": n["loc"],
+                "status": n["status"],
+                "manufacturer": n["manufacturer"],
+                "product": n["productDescription"],
+                "available": Self::zwave_available(n),
+            }))
+            .collect();
+        Ok(json!({ "devices": list }))
+    }
+
+    async fn zwave_values(&self, name: &str, path: Option<&str>) -> Result<Value> {
+        let (node, prefix) = self.zwave_node(name).await?;
+        if let Some(path) = path {
+            let value = self.current(&format!("{prefix}/{path}")).await?;
+            return Ok(json!({ "device": name, "value": time_value(value) }));
+        }
+        let start = format!("{prefix}/");
+        let values: Map<String, Value> = self.topics.lock().unwrap_or_else(|e| e.into_inner()).iter()
+            .filter_map(|(topic, value)| Some((topic.strip_prefix(&start)?.to_owned(), time_value(value.clone()))))
+            .collect();
+        Ok(json!({ "device": name, "id": node["id"], "values": values, "available": Self::zwave_available(&node) }))
+    }
+
+    async fn zwave_command(&self, name: &str, path: &str, input: Value) -> Result<Value> {
+        let (_, prefix) = self.zwave_node(name).await?;
+        let value = match input {
+            Value::Object(mut o) if o.contains_key("value") => o.remove("value").unwrap_or_default(),
+            other => other,
+        };
+        let payload = json!({ "value": value });
+        self.publish(&format!("{prefix}/{path}/set"), &payload).await?;
+        Ok(json!({ "device": name, "sent": payload }))
+    }
+}
+
+/// The value of a Z-Wave JS UI time-value payload.
+fn time_value(value: Value) -> Value {
+    match value {
+        Value::Object(mut o) if o.contains_key("value") && o.contains_key("time") => o.remove("value").unwrap_or_default(),
+        other => other,
+    }
+}
+
+/// Decode %XX escapes.
+fn unescape(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// What a `zigbee://` or `zwave://` URL addresses.
+#[derive(Debug, PartialEq)]
+enum Target {
+    Zigbee { name: String, confirm: bool },
+    Zwave { name: String, path: Option<String> },
+}
+
+impl Target {
+    fn parse(url: &str) -> Result<Self> {
+        let (rest, query) = url.split_once('?').unwrap_or((url, ""));
+        let options: Vec<&str> = query.split('&').filter(|o| !o.is_empty()).collect();
+        if let Some(bad) = options.iter().find(|o| **o != "confirm") {
+            return Err(ExecError::validation(format!("{url}: unknown option '{bad}'")));
+        }
+        if let Some(name) = rest.strip_prefix("zigbee://") {
+            // Friendly names may contain '/'
+            return Ok(Self::Zigbee { name: unescape(name.trim_end_matches('/')), confirm: !options.is_empty() });
+        }
+        if let Some(rest) = rest.strip_prefix("zwave://") {
+            if !options.is_empty() {
+                return Err(ExecError::validation(format!("{url}: zwave:// takes no options")));
+            }
+            let (name, path) = match rest.trim_end_matches('/').split_once('/') {
+                Some((name, path)) => (name, Some(path.split('/').map(unescape).collect::<Vec<_>>().join("/"))),
+                None => (rest.trim_end_matches('/'), None),
+            };
+            return Ok(Self::Zwave { name: unescape(name), path });
+        }
+        Err(ExecError::validation(format!("not a zigbee:// or zwave:// URL: {url}")))
+    }
+}
+
+impl MetricsCollector for MqttBridge {
+    fn prometheus(&self, node_id: &str) -> String {
+        format!(
+            "# HELP eyeflow_mqtt_connected Whether the Zigbee / Z-Wave MQTT broker is connected\n\
+             # TYPE eyeflow_mqtt_connected gauge\n\
+             eyeflow_mqtt_connected{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_mqtt_connects_total MQTT sessions established\n\
+             # TYPE eyeflow_mqtt_connects_total counter\n\
+             eyeflow_mqtt_connects_total{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_mqtt_messages_total MQTT messages by direction\n\
+             # TYPE eyeflow_mqtt_messages_total counter\n\
+             eyeflow_mqtt_messages_total{{node_id=\"{node_id}\",direction=\"published\"}} {}\n\
+             eyeflow_mqtt_messages_total{{node_id=\"{node_id}\",direction=\"received\"}} {}\n",
+            u8::from(*self.connected.borrow()),
+            self.connects.load(Ordering::Relaxed),
+            self.published.load(Ordering::Relaxed),
+            self.received.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+    use rumqttc::{mqttbytes, ConnAck, ConnectReturnCode, PubAck, Publish, SubAck};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// One client session on a fake broker.
+    struct Session {
+        stream: TcpStream,
+        buf: BytesMut,
+    }
+
+    impl Session {
+        async fn accept(listener: &TcpListener) -> Self {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut session = Self { stream, buf: BytesMut::new() };
+            let Some(Packet::Connect(_)) = session.read().await else { panic!("CONNECT") };
+            session.send(|b| ConnAck::new(ConnectReturnCode::Success, false).write(b)).await;
+            session
+        }
+
+        async fn read(&mut self) -> Option<Packet> {
+            loop {
+                match mqttbytes::v4::read(&mut self.buf, MAX_PACKET_BYTES) {
+                    Ok(packet) => return Some(packet),
+                    Err(mqttbytes::Error::InsufficientBytes(_)) => {
+                        if self.stream.read_buf(&mut self.buf).await.ok()? == 0 {
+                            return None;
+                        }
+                    }
+                    Err(e) => panic!("{e:?}"),
+                }
+            }
+        }
+
+        async fn send(&mut self, write: impl FnOnce(&mut BytesMut) -> Result<usize, mqttbytes::Error>) {
+            let mut out = BytesMut::new();
+            write(&mut out).unwrap();
+            self.stream.write_all(&out).await.unwrap();
+        }
+
+        async fn publish(&mut self, topic: &str, value: &Value) {
+            self.send(|b| Publish::new(topic, QoS::AtMostOnce, value.to_string()).write(b)).await;
+        }
+    }
+
+    fn bridge(url: &str) -> Arc<MqttBridge> {
+        let names = ["zigbee2mqtt", "zwave", "zwave-js-ui"].map(String::from);
+        let bridge = Arc::new(MqttBridge::new(mqtt_options(url, "test").unwrap(), Duration::from_secs(2), names));
+        tokio::spawn(bridge.clone().run());
+        bridge
+    }
+
+    /// Fake broker with zigbee2mqtt and Z-Wave JS UI behind it.
+    async fn broker(listener: TcpListener) {
+        let mut session = Session::accept(&listener).await;
+        let retained = [
+            ("zigbee2mqtt/bridge/devices", json!([
+                { "friendly_name": "Coordinator", "type": "Coordinator" },
+                { "friendly_name": "Kitchen Lamp", "type": "Router", "ieee_address": "0x00124b0001",
+                  "definition": { "model": "LED1545G12", "vendor": "IKEA" } },
+            ])),
+            ("zigbee2mqtt/Kitchen Lamp", json!({ "state": "OFF", "brightness": 80 })),
+            ("zigbee2mqtt/Kitchen Lamp/availability", json!({ "state": "online" })),
+            ("zwave/Basement/Boiler/37/0/currentValue", json!({ "time": 1, "value": true })),
+        ];
+        while let Some(packet) = session.read().await {
+            let mut replies = Vec::new();
+            match packet {
+                Packet::Subscribe(sub) => {
+                    let codes = vec![SubscribeReasonCode::Success(QoS::AtMostOnce); sub.filters.len()];
+                    session.send(|b| SubAck::new(sub.pkid, codes).write(b)).await;
+                    replies.extend(retained.iter().map(|(t, v)| (t.to_string(), v.clone())));
+                }
+                Packet::Publish(publish) => {
+                    assert_eq!(publish.qos, QoS::AtLeastOnce);
+                    session.send(|b| PubAck::new(publish.pkid).write(b)).await;
+                    let payload: Value = serde_json::from_slice(&publish.payload).unwrap();
+                    match publish.topic.as_str() {
+                        "zigbee2mqtt/Kitchen Lamp/set" => replies.push(("zigbee2mqtt/Kitchen Lamp".into(), json!({ "state": payload["state"], "brightness": 80 }))),
+                        "zwave/_CLIENTS/ZWAVE_GATEWAY-zwave-js-ui/api/getNodes/set" => replies.push((
+                            "zwave/_CLIENTS/ZWAVE_GATEWAY-zwave-js-ui/api/getNodes".into(),
+                            json!({ "success": true, "result": [{ "id": 5, "name": "Boiler", "loc": "Basement", "status": "Alive" }] }),
+                        )),
+                        "zwave/Basement/Boiler/37/0/targetValue/set" => assert_eq!(payload, json!({ "value": false })),
+                        other => panic!("unexpected publish to {other}"),
+                    }
+                }
+                _ => {}
+            }
+            for (topic, value) in replies {
+                session.publish(&topic, &value).await;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_zigbee_and_zwave_over_mqtt() {
+        assert_eq!(Target::parse("zwave://Boiler/37/0/currentValue").unwrap(), Target::Zwave { name: "Boiler".into(), path: Some("37/0/currentValue".into()) });
+        assert!(Target::parse("zigbee://Lamp?retain").is_err());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let bridge = bridge(&format!("mqtt://edge:pw@{}", listener.local_addr().unwrap()));
+        tokio::spawn(broker(listener));
+
+        let devices = bridge.load("zigbee://").await.unwrap();
+        assert_eq!(devices["devices"].as_array().unwrap().len(), 1);
+        assert_eq!(devices["devices"][0]["available"], true);
+        let lamp = bridge.load("zigbee://Kitchen%20Lamp").await.unwrap();
+        assert_eq!(lamp["state"]["state"], "OFF");
+        let on = bridge.call("zigbee://Kitchen%20Lamp?confirm", Some(&json!({ "state": "ON" }))).await.unwrap();
+        assert_eq!(on["state"]["state"], "ON");
+        assert_eq!(bridge.load("zigbee://Kitchen%20Lamp").await.unwrap()["state"]["state"], "ON");
+        let err = bridge.load("zigbee://Garage").await.unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::NotFound);
+
+        let boiler = bridge.load("zwave://Boiler").await.unwrap();
+        assert_eq!(boiler["values"], json!({ "37/0/currentValue": true }));
+        assert_eq!(boiler["available"], true);
+        bridge.call("zwave://Boiler/37/0/targetValue", Some(&json!(false))).await.unwrap();
+        assert!(bridge.prometheus("n").contains("eyeflow_mqtt_connected{node_id=\"n\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_and_fails_unacknowledged_publish() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let bridge = bridge(&format!("mqtt://{}", listener.local_addr().unwrap()));
+        tokio::spawn(async move {
+            for state in ["OFF", "ON"] {
+                let mut session = Session::accept(&listener).await;
+                while let Some(packet) = session.read().await {
+                    match packet {
+                        Packet::Subscribe(sub) => {
+                            session.send(|b| SubAck::new(sub.pkid, vec![SubscribeReasonCode::Success(QoS::AtMostOnce); 2]).write(b)).await;
+                            session.publish("zigbee2mqtt/bridge/devices", &json!([{ "friendly_name": "Lamp", "type": "Router" }])).await;
+                            session.publish("zigbee2mqtt/Lamp", &json!({ "state": state })).await;
+                        }
+                        // The first session drops before the PUBACK
+                        Packet::Publish(_) if state == "OFF" => break,
+                        Packet::Publish(publish) => session.send(|b| PubAck::new(publish.pkid).write(b)).await,
+                        _ => {}
+                    }
+                }
+            }
+            std::future::pending::<()>().await;
+        });
+
+        assert_eq!(bridge.load("zigbee://Lamp").await.unwrap()["state"]["state"], "OFF");
+        let err = bridge.call("zigbee://Lamp", Some(&json!({ "state": "ON" }))).await.unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::Network, "{err}");
+
+        // The second session re-subscribes and refreshes the retained state
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while bridge.cached("zigbee2mqtt/Lamp") != Some(json!({ "state": "ON" })) {
+            assert!(std::time::Instant::now() < deadline, "no reconnect");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        bridge.call("zigbee://Lamp", Some(&json!({ "state": "OFF" }))).await.unwrap();
+        assert!(bridge.prometheus("n").contains("eyeflow_mqtt_connects_total{node_id=\"n\"} 2"));
+    }
+}
@@ -0,0 +1,368 @@
+//! SMS / voice alerts — Twilio-compatible and MessageBird APIs
+//!
+//! Pages a human from the node itself, so a critical on-site failure is not
+//! lost when nobody is watching the central UI:
+//!
+//!   sms://+15551234567[,+15557654321]        CALL_ACTION: text the input
+//!   voice://+15551234567[?language=en-US&repeat=2]
+//!                                            CALL_ACTION: call and read it out
+//!   → { "channel", "provider", "to", "ids" [, "failed": [{ "to", "error" }]] }
+//!
+//! The input is the message itself, or an object with `message` (or `body`
+//! / `text`); SMS bodies are capped at 1600 characters.  Recipients are
+//! E.164 numbers and get the message independently: the action fails only
+//! when nobody could be reached.
+//!
+//! The provider and its credentials come from `credentials_vault_path`:
+//!
+//!   { "provider": "twilio", "accountSid": "AC…", "authToken": "…",
+//!     "from": "+15550001111", "baseUrl": "https://api.twilio.com" }
+//!   { "provider": "messagebird", "accessKey": "…", "from": "EyeFlow",
+//!     "baseUrl": "https://rest.messagebird.com" }
+//!
+//! `baseUrl` is optional; pointing it elsewhere serves Twilio-compatible
+//! APIs (SignalWire, …).
+//!
+//! SUPERVISED_RECOMPILE ends with a page when the instruction's fallback
+//! config sets `alert` (an sms:// or voice:// URL) and `alertVaultPath`, or
+//! the node sets SVM_ALERT_URL / SVM_ALERT_VAULT_PATH.  One page per
+//! workflow step every SVM_ALERT_COOLDOWN_SECS (default 900) — a flapping
+//! step does not ring the on-call phone in a loop.
+
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+use crate::health::MetricsCollector;
+
+const TWILIO_URL: &str = "https://api.twilio.com";
+const MESSAGEBIRD_URL: &str = "https://rest.messagebird.com";
+/// Longest SMS body the providers accept (concatenated segments).
+const MAX_SMS_CHARS: usize = 1600;
+/// Deadline of one provider request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+// ── Targets ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Channel {
+    Sms,
+    Voice,
+}
+
+#[derive(Debug, PartialEq)]
+struct Target {
+    channel: Channel,
+    to: Vec<String>,
+    language: String,
+    repeat: u32,
+}
+
+impl Target {
+    fn parse(url: &str) -> Result<Self> {
+        let (channel, rest) = if let Some(rest) = url.strip_prefix("sms://") {
+            (Channel::Sms, rest)
+        } else if let Some(rest) = url.strip_prefix("voice://") {
+            (Channel::Voice, rest)
+        } else {
+            return Err(ExecError::validation(format!("not an sms:// or voice:// URL: {url}")));
+        };
+        let (numbers, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let to: Vec<String> = numbers.split(',').map(|n| n.trim().replace("%2B", "+")).filter(|n| !n.is_empty()).collect();
+        if to.is_empty() {
+            return Err(ExecError::validation(format!("{url}: no recipient")));
+        }
+        if let Some(bad) = to.iter().find(|n| !is_e164(n)) {
+            return Err(ExecError::validation(format!("{url}: '{bad}' is not an E.164 number (+<country><number>)")));
+        }
+        let mut target = Self { channel, to, language: "en-US".into(), repeat: 2 };
+        for kv in query.split('&').filter(|kv| !kv.is_empty()) {
+            match (channel, kv.split_once('=').unwrap_or((kv, ""))) {
+                (Channel::Voice, ("language", lang)) if !lang.is_empty() => target.language = lang.to_owned(),
+                (Channel::Voice, ("repeat", n)) => {
+                    target.repeat = n.parse().ok().filter(|n| (1..=5).contains(n))
+                        .ok_or_else(|| ExecError::validation(format!("{url}: repeat must be 1..=5")))?;
+                }
+                (_, (k, _)) => return Err(ExecError::validation(format!("{url}: unknown option '{k}'"))),
+            }
+        }
+        Ok(target)
+    }
+}
+
+fn is_e164(number: &str) -> bool {
+    number.strip_prefix('+').is_some_and(|d| (6..=15).contains(&d.len()) && d.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// The text to send: the input string, or its `message` / `body` / `text`.
+fn message_of(input: Option<&Value>) -> Result<String> {
+    let text = match input {
+        Some(Value::String(s)) => Some(s.as_str()),
+        Some(Value::Object(o)) => ["message", "body", "text"].iter().find_map(|k| o.get(*k).and_then(Value::as_str)),
+        _ => None,
+    };
+    text.map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_owned)
+        .ok_or_else(|| ExecError::validation("alert: the input must be a message or an object with `message`"))
+}
+
+// ── Providers ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "provider", rename_all = "lowercase")]
+enum Provider {
+    #[serde(rename_all = "camelCase")]
+    Twilio { account_sid: String, auth_token: String, from: String, base_url: Option<String> },
+    #[serde(rename_all = "camelCase")]
+    Messagebird { access_key: String, from: String, base_url: Option<String> },
+}
+
+impl Provider {
+    fn from_secret(secret: &str) -> Result<Self> {
+        serde_json::from_str(secret).map_err(|e| ExecError::validation(format!(
+            "alert credential secret: {e} (expected provider twilio or messagebird)"
+        )))
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Twilio { .. } => "twilio",
+            Self::Messagebird { .. } => "messagebird",
+        }
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// ── Notifier ──────────────────────────────────────────────────────────────────
+
+pub struct Notifier {
+    http: reqwest::Client,
+    cooldown: Duration,
+    /// Last page per workflow step (SUPERVISED_RECOMPILE)
+    paged: Mutex<HashMap<String, Instant>>,
+    sent: AtomicU64,
+    failed: AtomicU64,
+    suppressed: AtomicU64,
+}
+
+impl Notifier {
+    pub fn new(http: reqwest::Client, cooldown: Duration) -> Self {
+        Self {
+            http,
+            cooldown,
+            paged: Mutex::new(HashMap::new()),
+            sent: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_config(config: &Config, http: reqwest::Client) -> Self {
+        Self::new(http, Duration::from_secs(config.alert_cooldown_secs))
+    }
+
+    /// Whether `key` may page now; starts its cooldown when it may.
+    pub fn page_allowed(&self, key: &str) -> bool {
+        let mut paged = self.paged.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        paged.retain(|_, at| now.duration_since(*at) < self.cooldown);
+        if paged.contains_key(key) {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        paged.insert(key.to_owned(), now);
+        true
+    }
+
+    /// CALL_ACTION `sms://` / `voice://`; `secret` is the provider credential.
+    pub async fn send(&self, url: &str, secret: &str, input: Option<&Value>) -> Result<Value> {
+        let target = Target::parse(url)?;
+        let provider = Provider::from_secret(secret)?;
+        let message = message_of(input)?;
+        if target.channel == Channel::Sms && message.chars().count() > MAX_SMS_CHARS {
+            return Err(ExecError::validation(format!("alert: SMS longer than {MAX_SMS_CHARS} characters")));
+        }
+
+        let mut ids = Vec::new();
+        let mut failed = Vec::new();
+        match &provider {
+            // One request per recipient
+            Provider::Twilio { .. } => {
+                for to in &target.to {
+                    match self.twilio(&provider, &target, to, &message).await {
+                        Ok(id) => ids.push(id),
+                        Err(e) => failed.push((to.clone(), e)),
+                    }
+                }
+            }
+            Provider::Messagebird { .. } => match self.messagebird(&provider, &target, &message).await {
+                Ok(id) => ids.push(id),
+                Err(e) => failed.extend(target.to.iter().map(|to| (to.clone(), anyhow::anyhow!("{e}")))),
+            },
+        }
+        self.sent.fetch_add(ids.len() as u64, Ordering::Relaxed);
+        self.failed.fetch_add(failed.len() as u64, Ordering::Relaxed);
+        if ids.is_empty() {
+            let (to, e) = failed.into_iter().next().expect("no recipient was reached");
+            warn!("[Alert] {} via {} to {to} failed: {e}", url.split("://").next().unwrap_or_default(), provider.name());
+            return Err(e.context(format!("alert to {to}")));
+        }
+        let mut out = json!({
+            "channel": match target.channel { Channel::Sms => "sms", Channel::Voice => "voice" },
+            "provider": provider.name(),
+            "to": target.to,
+            "ids": ids,
+        });
+        if !failed.is_empty() {
+            out["failed"] = failed.iter().map(|(to, e)| json!({ "to": to, "error": e.to_string() })).collect();
+        }
+        debug!("[Alert] {out}");
+        Ok(out)
+    }
+
+    async fn twilio(&self, provider: &Provider, target: &Target, to: &str, message: &str) -> Result<String> {
+        let Provider::Twilio { account_sid, auth_token, from, base_url } = provider else { unreachable!() };
+        let base = base_url.as_deref().unwrap_or(TWILIO_URL).trim_end_matches('/');
+        let (resource, content) = match target.channel {
+            Channel::Sms => ("Messages", ("Body", message.to_owned())),
+            Channel::Voice => ("Calls", ("Twiml", format!(
+                "<Response><Say language=\"{}\" loop=\"{}\">{}</Say></Response>",
+                xml_escape(&target.language), target.repeat, xml_escape(message),
+            ))),
+        };
+        let url = format!("{base}/2010-04-01/Accounts/{account_sid}/{resource}.json");
+        let resp = self.http.post(&url)
+            .basic_auth(account_sid, Some(auth_token))
+            .form(&[("To", to), ("From", from), (content.0, &content.1)])
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?;
+        let status = resp.status();
+        let body: Value = resp.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            let detail = body["message"].as_str().unwrap_or_default();
+            return Err(ExecError::http(format!("Twilio {resource} ({detail})"), status));
+        }
+        body["sid"].as_str().map(str::to_owned)
+            .ok_or_else(|| ExecError::new(ErrorCode::Upstream, format!("Twilio {resource}: no sid in the response")).into())
+    }
+
+    async fn messagebird(&self, provider: &Provider, target: &Target, message: &str) -> Result<String> {
+        let Provider::Messagebird { access_key, from, base_url } = provider else { unreachable!() };
+        let base = base_url.as_deref().unwrap_or(MESSAGEBIRD_URL).trim_end_matches('/');
+        let (resource, body) = match target.channel {
+            Channel::Sms => ("messages", json!({ "originator": from, "recipients": target.to, "body": message })),
+            Channel::Voice => ("voicemessages", json!({
+                "originator": from, "recipients": target.to, "body": message,
+                "language": target.language.to_lowercase(), "repeat": target.repeat,
+            })),
+        };
+        let resp = self.http.post(format!("{base}/{resource}"))
+            .header("Authorization", format!("AccessKey {access_key}"))
+            .json(&body)
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?;
+        let status = resp.status();
+        let body: Value = resp.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            let detail = body["errors"][0]["description"].as_str().unwrap_or_default();
+            return Err(ExecError::http(format!("MessageBird {resource} ({detail})"), status));
+        }
+        body["id"].as_str().map(str::to_owned)
+            .ok_or_else(|| ExecError::new(ErrorCode::Upstream, format!("MessageBird {resource}: no id in the response")).into())
+    }
+}
+
+impl MetricsCollector for Notifier {
+    fn prometheus(&self, node_id: &str) -> String {
+        let mut out = String::from(
+            "# HELP eyeflow_alerts_total SMS / voice alert recipients by outcome\n\
+             # TYPE eyeflow_alerts_total counter\n",
+        );
+        for (result, n) in [("sent", &self.sent), ("failed", &self.failed), ("suppressed", &self.suppressed)] {
+            out.push_str(&format!(
+                "eyeflow_alerts_total{{node_id=\"{node_id}\",result=\"{result}\"}} {}\n",
+                n.load(Ordering::Relaxed),
+            ));
+        }
+        out
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_twilio_and_messagebird_requests() {
+        assert!(Target::parse("sms://5551234").is_err());
+        assert!(Target::parse("sms://+15551234567?repeat=2").is_err());
+        assert_eq!(Target::parse("voice://+15551234567?repeat=3").unwrap().repeat, 3);
+
+        // Answers every request with the request line + body echoed back in the id
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            loop {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 8192];
+                let mut n = 0;
+                let request = loop {
+                    n += sock.read(&mut buf[n..]).await.unwrap();
+                    let text = String::from_utf8_lossy(&buf[..n]).into_owned();
+                    let Some((head, body)) = text.split_once("\r\n\r\n") else { continue };
+                    let len = head.lines().find_map(|l| l.to_lowercase().strip_prefix("content-length: ").map(|v| v.parse::<usize>().unwrap())).unwrap_or(0);
+                    if body.len() >= len {
+                        break text;
+                    }
+                };
+                let line = request.lines().next().unwrap().to_owned();
+                let body = request.split_once("\r\n\r\n").unwrap().1;
+                let (status, answer) = if body.contains("15550000000") {
+                    ("400 Bad Request", json!({ "message": "unreachable" }))
+                } else {
+                    ("201 Created", json!({ "sid": format!("{line} {body}"), "id": format!("{line} {body}") }))
+                };
+                let answer = answer.to_string();
+                let reply = format!("HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{answer}", answer.len());
+                sock.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+
+        let notifier = Notifier::new(reqwest::Client::new(), Duration::from_secs(60));
+        let twilio = json!({ "provider": "twilio", "accountSid": "AC1", "authToken": "t", "from": "+15550001111", "baseUrl": base }).to_string();
+        let out = notifier.send("sms://+15551234567,+15550000000", &twilio, Some(&json!({ "message": "Line 3 <down>" }))).await.unwrap();
+        let id = out["ids"][0].as_str().unwrap();
+        assert!(id.starts_with("POST /2010-04-01/Accounts/AC1/Messages.json"), "{id}");
+        assert!(id.contains("Body=Line+3+%3Cdown%3E"), "{id}");
+        assert_eq!(out["failed"][0]["to"], "+15550000000");
+        let out = notifier.send("voice://+15551234567", &twilio, Some(&json!("Boiler <fault>"))).await.unwrap();
+        assert!(out["ids"][0].as_str().unwrap().contains("Boiler+%26lt%3Bfault%26gt%3B"), "{out}");
+        let err = notifier.send("sms://+15550000000", &twilio, Some(&json!("x"))).await.unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::Validation);
+
+        let bird = json!({ "provider": "messagebird", "accessKey": "k", "from": "EyeFlow", "baseUrl": base }).to_string();
+        let out = notifier.send("voice://+15551234567?language=fr-FR", &bird, Some(&json!({ "text": "Alerte" }))).await.unwrap();
+        let id = out["ids"][0].as_str().unwrap();
+        assert!(id.starts_with("POST /voicemessages") && id.contains("\"language\":\"fr-fr\""), "{id}");
+
+        assert!(notifier.page_allowed("wf/step"));
+        assert!(!notifier.page_allowed("wf/step"));
+        assert!(notifier.prometheus("n").contains("result=\"suppressed\"} 1"));
+    }
+}
@@ -0,0 +1,321 @@
+//! Connector plugins — customer connectors loaded from SVM_PLUGIN_DIR
+//!
+//! Proprietary PLC protocols or licensed SDKs ship as shared libraries
+//! dropped into SVM_PLUGIN_DIR.  With feature `plugins` (Linux), every
+//! `*.so` there is loaded with dlopen at startup; a library that fails to
+//! load is skipped with a warning.  A plugin exports a C ABI exchanging JSON:
+//!
+//!   uint32_t    eyeflow_plugin_abi(void);          /* must return PLUGIN_ABI (1) */
+//!   const char *eyeflow_plugin_manifest(void);     /* {"name":"s7comm","version":"1.0","formats":["S7COMM"]} */
+//!   char       *eyeflow_plugin_call(const char *request);
+//!   void        eyeflow_plugin_free(char *response);
+//!
+//! CALL_SERVICE reaches a plugin with endpoint_url `plugin://<name>/<target>`.
+//! The request is
+//!
+//!   { "url": "plugin://s7comm/db1.dbw20", "target": "db1.dbw20", "method": "GET",
+//!     "operands": {...}, "input": <src[0]> }
+//!
+//! and the response `{ "ok": <value> }` or `{ "error": "<message>" }`
+//! (UPSTREAM).  Calls run on the blocking pool.
+//!
+//! Loaded plugins are advertised in REGISTER capabilities: their manifests
+//! under `plugins`, their formats appended to `serviceFormats`, and the
+//! feature `plugin:<name>` that instructions addressing them require.
+//! Plugins run in-process with the node's privileges — install only
+//! trusted libraries.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+use crate::proto::llmir::DispatchMetadata;
+
+/// ABI version plugins must report.
+#[cfg_attr(not(all(feature = "plugins", target_os = "linux")), allow(dead_code))]
+pub const PLUGIN_ABI: u32 = 1;
+
+/// What a plugin says about itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    /// Service formats it implements (advertised in `serviceFormats`)
+    #[serde(default)]
+    pub formats: Vec<String>,
+}
+
+/// A connector plugin (native library, or registered in-process).
+pub trait ConnectorPlugin: Send + Sync {
+    fn manifest(&self) -> &Manifest;
+
+    /// Handle one request; runs on the blocking pool.
+    fn call(&self, request: &Value) -> Result<Value>;
+}
+
+/// True when `url` addresses a connector plugin.
+pub fn is_plugin_url(url: &str) -> bool {
+    url.starts_with("plugin://")
+}
+
+/// (plugin name, target) of a `plugin://` URL.
+fn parse(url: &str) -> Option<(&str, &str)> {
+    let rest = url.strip_prefix("plugin://")?;
+    Some(rest.split_once('/').unwrap_or((rest, "")))
+}
+
+/// Feature an instruction addressing `url` requires.
+pub fn required_feature(url: &str) -> Option<String> {
+    parse(url).map(|(name, _)| format!("plugin:{name}"))
+}
+
+// ── Host ──────────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct PluginHost {
+    plugins: BTreeMap<String, Arc<dyn ConnectorPlugin>>,
+}
+
+impl std::fmt::Debug for PluginHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.plugins.keys()).finish()
+    }
+}
+
+impl PluginHost {
+    /// Plugins of SVM_PLUGIN_DIR (none when unset).
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let mut host = Self::default();
+        if let Some(dir) = &config.plugin_dir {
+            host.load_dir(Path::new(dir))?;
+        }
+        Ok(host)
+    }
+
+    /// Load every `*.so` of `dir`, skipping those that fail.
+    #[cfg(all(feature = "plugins", target_os = "linux"))]
+    fn load_dir(&mut self, dir: &Path) -> Result<()> {
+        let mut paths: Vec<_> = std::fs::read_dir(dir)
+            .map_err(|e| anyhow!("SVM_PLUGIN_DIR {}: {e}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|x| x == "so"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match native::NativePlugin::load(&path).and_then(|p| self.register(Arc::new(p))) {
+                Ok(()) => tracing::info!("[Plugin] loaded {}", path.display()),
+                Err(e) => warn!("[Plugin] skipping {}: {e:#}", path.display()),
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(all(feature = "plugins", target_os = "linux")))]
+    fn load_dir(&mut self, dir: &Path) -> Result<()> {
+        warn!("[Plugin] SVM_PLUGIN_DIR={} ignored — rebuild with `--features plugins` (Linux)", dir.display());
+        Ok(())
+    }
+
+    /// Add a plugin (names are unique).
+    #[cfg_attr(not(all(feature = "plugins", target_os = "linux")), allow(dead_code))]
+    pub fn register(&mut self, plugin: Arc<dyn ConnectorPlugin>) -> Result<()> {
+        let name = plugin.manifest().name.clone();
+        if name.is_empty() || name.contains('/') {
+            return Err(anyhow!("invalid plugin name '{name}'"));
+        }
+        if self.plugins.contains_key(&name) {
+            return Err(anyhow!("plugin '{name}' is already loaded"));
+        }
+        self.plugins.insert(name, plugin);
+        Ok(())
+    }
+
+    pub fn manifests(&self) -> Vec<&Manifest> {
+        self.plugins.values().map(|p| p.manifest()).collect()
+    }
+
+    /// `plugin:<name>` of every loaded plugin.
+    pub fn features(&self) -> impl Iterator<Item = String> + '_ {
+        self.plugins.keys().map(|name| format!("plugin:{name}"))
+    }
+
+    /// Service formats the plugins add.
+    pub fn formats(&self) -> Vec<String> {
+        let mut formats: Vec<String> = self.plugins.values().flat_map(|p| p.manifest().formats.clone()).collect();
+        formats.sort();
+        formats.dedup();
+        formats
+    }
+
+    /// Dispatch a CALL_SERVICE to the plugin its endpoint_url names.
+    pub async fn call(&self, dm: &DispatchMetadata, operands: &Value, input: Option<&Value>) -> Result<Value> {
+        let (name, target) = parse(&dm.endpoint_url)
+            .ok_or_else(|| ExecError::validation(format!("{}: not a plugin:// URL", dm.endpoint_url)))?;
+        let plugin = self.plugins.get(name).cloned().ok_or_else(|| ExecError::new(
+            ErrorCode::NotFound,
+            format!("{}: connector plugin '{name}' is not loaded on this node", dm.service_id),
+        ))?;
+        let request = json!({
+            "url": dm.endpoint_url,
+            "target": target,
+            "method": dm.method,
+            "operands": operands,
+            "input": input,
+        });
+        let response = tokio::task::spawn_blocking(move || plugin.call(&request)).await
+            .map_err(|e| anyhow!("plugin {name}: {e}"))??;
+        match (response.get("ok"), response.get("error")) {
+            (_, Some(err)) => Err(ExecError::new(
+                ErrorCode::Upstream,
+                format!("plugin {name}: {}", err.as_str().map_or_else(|| err.to_string(), str::to_owned)),
+            ).into()),
+            (Some(ok), None) => Ok(ok.clone()),
+            (None, None) => Err(ExecError::validation(format!("plugin {name}: response has neither `ok` nor `error`"))),
+        }
+    }
+}
+
+// ── Native libraries (feature `plugins`) ──────────────────────────────────────
+
+#[cfg(all(feature = "plugins", target_os = "linux"))]
+mod native {
+    use super::*;
+    use std::ffi::{c_char, c_void, CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+
+    type AbiFn = unsafe extern "C" fn() -> u32;
+    type ManifestFn = unsafe extern "C" fn() -> *const c_char;
+    type CallFn = unsafe extern "C" fn(*const c_char) -> *mut c_char;
+    type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+    /// A dlopen'ed plugin (never unloaded).
+    pub struct NativePlugin {
+        manifest: Manifest,
+        call: CallFn,
+        free: FreeFn,
+    }
+
+    fn dlerror() -> String {
+        // SAFETY: dlerror returns NULL or a NUL-terminated thread-local string
+        let err = unsafe { libc::dlerror() };
+        if err.is_null() {
+            "unknown dlopen error".into()
+        } else {
+            unsafe { CStr::from_ptr(err) }.to_string_lossy().into_owned()
+        }
+    }
+
+    fn symbol(handle: *mut c_void, name: &CStr) -> Result<*mut c_void> {
+        // SAFETY: `handle` comes from dlopen and `name` is NUL-terminated
+        let sym = unsafe { libc::dlsym(handle, name.as_ptr()) };
+        if sym.is_null() {
+            return Err(anyhow!("missing symbol {}: {}", name.to_string_lossy(), dlerror()));
+        }
+        Ok(sym)
+    }
+
+    impl NativePlugin {
+        pub fn load(path: &Path) -> Result<Self> {
+            let c_path = CString::new(path.as_os_str().as_bytes())?;
+            // SAFETY: loading runs the library's initialisers — plugins are trusted code
+            let handle = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+            if handle.is_null() {
+                return Err(anyhow!("{}", dlerror()));
+            }
+            // SAFETY: the symbols have the documented C signatures
+            let (abi, manifest, call, free) = unsafe {
+                (
+                    std::mem::transmute::<*mut c_void, AbiFn>(symbol(handle, c"eyeflow_plugin_abi")?),
+                    std::mem::transmute::<*mut c_void, ManifestFn>(symbol(handle, c"eyeflow_plugin_manifest")?),
+                    std::mem::transmute::<*mut c_void, CallFn>(symbol(handle, c"eyeflow_plugin_call")?),
+                    std::mem::transmute::<*mut c_void, FreeFn>(symbol(handle, c"eyeflow_plugin_free")?),
+                )
+            };
+            let version = unsafe { abi() };
+            if version != PLUGIN_ABI {
+                return Err(anyhow!("plugin ABI {version}, node speaks {PLUGIN_ABI}"));
+            }
+            let text = unsafe { manifest() };
+            if text.is_null() {
+                return Err(anyhow!("eyeflow_plugin_manifest returned NULL"));
+            }
+            let manifest: Manifest = serde_json::from_slice(unsafe { CStr::from_ptr(text) }.to_bytes())
+                .map_err(|e| anyhow!("manifest: {e}"))?;
+            Ok(Self { manifest, call, free })
+        }
+    }
+
+    impl ConnectorPlugin for NativePlugin {
+        fn manifest(&self) -> &Manifest {
+            &self.manifest
+        }
+
+        fn call(&self, request: &Value) -> Result<Value> {
+            let request = CString::new(request.to_string())?;
+            // SAFETY: `request` outlives the call; the response is released with the plugin's own free
+            let response = unsafe { (self.call)(request.as_ptr()) };
+            if response.is_null() {
+                return Err(anyhow!("plugin {} returned NULL", self.manifest.name));
+            }
+            let parsed = serde_json::from_slice(unsafe { CStr::from_ptr(response) }.to_bytes());
+            unsafe { (self.free)(response) };
+            parsed.map_err(|e| anyhow!("plugin {}: invalid response: {e}", self.manifest.name))
+        }
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Register(Manifest);
+
+    impl ConnectorPlugin for Register {
+        fn manifest(&self) -> &Manifest {
+            &self.0
+        }
+
+        fn call(&self, request: &Value) -> Result<Value> {
+            Ok(match request["target"].as_str() {
+                Some("db1.dbw20") => json!({ "ok": { "value": 42, "input": request["input"] } }),
+                _ => json!({ "error": "no such address" }),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plugins_dispatch_and_advertise() {
+        let mut host = PluginHost::default();
+        let manifest = Manifest { name: "s7comm".into(), version: "1.0".into(), formats: vec!["S7COMM".into()] };
+        host.register(Arc::new(Register(manifest.clone()))).unwrap();
+        assert!(host.register(Arc::new(Register(manifest))).is_err());
+        assert_eq!(host.features().collect::<Vec<_>>(), ["plugin:s7comm"]);
+        assert_eq!(host.formats(), ["S7COMM"]);
+
+        let dm = |url: &str| DispatchMetadata { endpoint_url: url.into(), service_id: "plc".into(), ..Default::default() };
+        let out = host.call(&dm("plugin://s7comm/db1.dbw20"), &json!({}), Some(&json!(7))).await.unwrap();
+        assert_eq!(out, json!({ "value": 42, "input": 7 }));
+        let err = host.call(&dm("plugin://s7comm/db9"), &json!({}), None).await.unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::Upstream);
+        let err = host.call(&dm("plugin://opcua/ns=2"), &json!({}), None).await.unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::NotFound);
+        assert_eq!(required_feature("plugin://opcua/ns=2").as_deref(), Some("plugin:opcua"));
+
+        // Files that are not plugins are skipped, not fatal
+        let dir = std::env::temp_dir().join(format!("eyeflow_plugins_{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("broken.so"), b"not an ELF").unwrap();
+        let config = Config { plugin_dir: Some(dir.to_string_lossy().into_owned()), ..Config::from_env() };
+        assert!(PluginHost::from_config(&config).unwrap().manifests().is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,999 @@
+//! SNMP connector — GET / WALK on network equipment (v2c, v3 USM)
+//!
+//! LOAD_RESOURCE reads MIB objects from switches, routers and UPSes, so
+//! network-monitoring workflows run on the edge node next to them:
+//!
+//!   snmp://switch-1.local/1.3.6.1.2.1.1.3.0              GET sysUpTime.0
+//!   snmp://10.0.0.2:1161/1.3.6.1.2.1.1.5.0,1.3.6.1.2.1.1.3.0
+//!                                                        GET several objects
+//!   snmp://switch-1.local/1.3.6.1.2.1.2.2.1.10?walk      WALK ifInOctets
+//!       (GETBULK, `&maxRepetitions=n` bindings per request, default 10)
+//!
+//!   → { "host", "varbinds": [{ "oid", "type", "value" }], "values": { oid: value } }
+//!
+//! Integers, counters, gauges and TimeTicks are numbers; OCTET STRINGs are
+//! text, or colon-separated hex when not printable (MAC addresses); OIDs
+//! and IpAddresses are dotted strings; noSuchObject / noSuchInstance have a
+//! null value.  A walk ends with the subtree, or after
+//! SVM_SNMP_MAX_VARBINDS bindings (default 10 000) with `"truncated": true`.
+//!
+//! Credentials come from `credentials_vault_path` (community "public"
+//! without one), never from the URL:
+//!
+//!   "private"  or  { "community": "private" }                        v2c
+//!   { "user": "monitor", "authProtocol": "SHA", "authPassword": "…",
+//!     "privProtocol": "AES", "privPassword": "…", "context": "" }   v3
+//!
+//! SNMPv3 uses the USM (keys and ciphers in snmp/usm.rs): authProtocol MD5,
+//! SHA (default) or SHA256 (HMAC-SHA-256-192), privProtocol AES (128-bit
+//! CFB, default) or DES (where the OpenSSL build still ships it).  Without
+//! authPassword the requests are noAuthNoPriv, without privPassword
+//! authNoPriv.  The agent's engine ID, boots and time are discovered once
+//! per agent and resynchronised on notInTimeWindow.
+//!
+//! A request times out after SVM_SNMP_TIMEOUT_MS (default 2000) and is sent
+//! SVM_SNMP_RETRIES more times (default 1) before failing with TIMEOUT — a
+//! v2c agent drops a wrong community silently.  USM rejections (unknown
+//! user, wrong digest, …) fail with AUTH; an error status from the agent
+//! with UPSTREAM (AUTH for noAccess / authorizationError).
+
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tracing::debug;
+
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+
+mod usm;
+
+pub use usm::{AuthProtocol, PrivProtocol};
+use usm::{crypt, hmac, localize};
+
+const DEFAULT_PORT: u16 = 161;
+const DEFAULT_REPETITIONS: u32 = 10;
+/// msgMaxSize advertised in SNMPv3 requests
+const MAX_MESSAGE: i64 = 65_507;
+
+// ── BER ───────────────────────────────────────────────────────────────────────
+
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const NULL: u8 = 0x05;
+const OBJECT_ID: u8 = 0x06;
+const SEQUENCE: u8 = 0x30;
+const IP_ADDRESS: u8 = 0x40;
+const COUNTER32: u8 = 0x41;
+const GAUGE32: u8 = 0x42;
+const TIME_TICKS: u8 = 0x43;
+const OPAQUE: u8 = 0x44;
+const COUNTER64: u8 = 0x46;
+const NO_SUCH_OBJECT: u8 = 0x80;
+const NO_SUCH_INSTANCE: u8 = 0x81;
+const END_OF_MIB_VIEW: u8 = 0x82;
+
+const GET: u8 = 0xa0;
+const RESPONSE: u8 = 0xa2;
+const GET_BULK: u8 = 0xa5;
+const REPORT: u8 = 0xa8;
+
+fn length(len: usize) -> Vec<u8> {
+    if len < 0x80 {
+        return vec![len as u8];
+    }
+    let bytes: Vec<u8> = len.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+    let mut out = vec![0x80 | bytes.len() as u8];
+    out.extend(bytes);
+    out
+}
+
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    out.extend(length(content.len()));
+    out.extend_from_slice(content);
+    out
+}
+
+/// Bytes in front of the content of a TLV holding `len` bytes.
+fn header_len(len: usize) -> usize {
+    1 + length(len).len()
+}
+
+fn integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    while start < 7
+        && ((bytes[start] == 0 && bytes[start + 1] & 0x80 == 0) || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    tlv(INTEGER, &bytes[start..])
+}
+
+fn octets(bytes: &[u8]) -> Vec<u8> {
+    tlv(OCTET_STRING, bytes)
+}
+
+fn encode_oid(oid: &[u32]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let first = oid[0] * 40 + oid[1];
+    for sub in std::iter::once(first).chain(oid[2..].iter().copied()) {
+        let mut chunk = vec![(sub & 0x7f) as u8];
+        let mut rest = sub >> 7;
+        while rest > 0 {
+            chunk.push((rest & 0x7f) as u8 | 0x80);
+            rest >>= 7;
+        }
+        out.extend(chunk.iter().rev());
+    }
+    tlv(OBJECT_ID, &out)
+}
+
+fn decode_oid(bytes: &[u8]) -> Vec<u32> {
+    let mut subs = Vec::new();
+    let mut acc = 0u32;
+    for &b in bytes {
+        acc = acc.wrapping_shl(7) | u32::from(b & 0x7f);
+        if b & 0x80 == 0 {
+            subs.push(acc);
+            acc = 0;
+        }
+    }
+    let Some(&first) = subs.first() else { return subs };
+    let mut out = if first < 80 { vec![first / 40, first % 40] } else { vec![2, first - 80] };
+    out.extend(&subs[1..]);
+    out
+}
+
+/// "1.3.6.1.2.1.1.3.0" (a leading dot is allowed).
+pub fn parse_oid(text: &str) -> Result<Vec<u32>> {
+    let bad = || ExecError::validation(format!("SNMP: '{text}' is not a numeric OID"));
+    let oid: Vec<u32> = text.trim().trim_start_matches('.').split('.')
+        .map(|s| s.parse().map_err(|_| bad()))
+        .collect::<Result<_>>()?;
+    if oid.len() < 2 || oid[0] > 2 || (oid[0] < 2 && oid[1] >= 40) {
+        return Err(bad());
+    }
+    Ok(oid)
+}
+
+fn oid_string(oid: &[u32]) -> String {
+    oid.iter().map(u32::to_string).collect::<Vec<_>>().join(".")
+}
+
+fn malformed() -> anyhow::Error {
+    ExecError::validation("SNMP: malformed message")
+}
+
+/// Reads TLVs off the front of a buffer.
+struct Ber<'a>(&'a [u8]);
+
+impl<'a> Ber<'a> {
+    fn next(&mut self) -> Result<(u8, &'a [u8])> {
+        let (&tag, rest) = self.0.split_first().ok_or_else(malformed)?;
+        let (&first, mut rest) = rest.split_first().ok_or_else(malformed)?;
+        let len = if first < 0x80 {
+            usize::from(first)
+        } else {
+            let n = usize::from(first & 0x7f);
+            if n == 0 || n > 4 || rest.len() < n {
+                return Err(malformed());
+            }
+            let len = rest[..n].iter().fold(0usize, |acc, b| acc << 8 | usize::from(*b));
+            rest = &rest[n..];
+            len
+        };
+        if rest.len() < len {
+            return Err(malformed());
+        }
+        let (content, rest) = rest.split_at(len);
+        self.0 = rest;
+        Ok((tag, content))
+    }
+
+    fn expect(&mut self, tag: u8) -> Result<&'a [u8]> {
+        match self.next()? {
+            (t, content) if t == tag => Ok(content),
+            (t, _) => Err(ExecError::validation(format!("SNMP: expected tag 0x{tag:02x}, got 0x{t:02x}"))),
+        }
+    }
+
+    fn integer(&mut self) -> Result<i64> {
+        Ok(signed(self.expect(INTEGER)?))
+    }
+}
+
+fn signed(bytes: &[u8]) -> i64 {
+    let init = if bytes.first().is_some_and(|b| b & 0x80 != 0) { -1 } else { 0 };
+    bytes.iter().fold(init, |acc, b| acc << 8 | i64::from(*b))
+}
+
+fn unsigned(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |acc, b| acc << 8 | u64::from(*b))
+}
+
+// ── PDUs ──────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+struct VarBind {
+    oid: Vec<u32>,
+    tag: u8,
+    value: Value,
+}
+
+#[derive(Debug)]
+struct Pdu {
+    tag: u8,
+    request_id: i64,
+    error_status: i64,
+    error_index: i64,
+    varbinds: Vec<VarBind>,
+}
+
+const ERROR_STATUS: &[&str] = &[
+    "noError", "tooBig", "noSuchName", "badValue", "readOnly", "genErr", "noAccess", "wrongType",
+    "wrongLength", "wrongEncoding", "wrongValue", "noCreation", "inconsistentValue",
+    "resourceUnavailable", "commitFailed", "undoFailed", "authorizationError", "notWritable",
+    "inconsistentName",
+];
+
+fn type_name(tag: u8) -> &'static str {
+    match tag {
+        INTEGER => "INTEGER",
+        OCTET_STRING => "OCTET STRING",
+        NULL => "NULL",
+        OBJECT_ID => "OBJECT IDENTIFIER",
+        IP_ADDRESS => "IpAddress",
+        COUNTER32 => "Counter32",
+        GAUGE32 => "Gauge32",
+        TIME_TICKS => "TimeTicks",
+        OPAQUE => "Opaque",
+        COUNTER64 => "Counter64",
+        NO_SUCH_OBJECT => "noSuchObject",
+        NO_SUCH_INSTANCE => "noSuchInstance",
+        END_OF_MIB_VIEW => "endOfMibView",
+        _ => "unknown",
+    }
+}
+
+fn decode_value(tag: u8, bytes: &[u8]) -> Value {
+    match tag {
+        INTEGER => json!(signed(bytes)),
+        OCTET_STRING => match std::str::from_utf8(bytes) {
+            Ok(s) if !s.chars().any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t')) => json!(s),
+            _ => json!(bytes.iter().map(|b| format!("{b:02x}")).collect::<Vec<_>>().join(":")),
+        },
+        OBJECT_ID => json!(oid_string(&decode_oid(bytes))),
+        IP_ADDRESS if bytes.len() == 4 => json!(format!("{}.{}.{}.{}", bytes[0], bytes[1], bytes[2], bytes[3])),
+        COUNTER32 | GAUGE32 | TIME_TICKS | COUNTER64 => json!(unsigned(bytes)),
+        IP_ADDRESS | OPAQUE => json!(hex::encode(bytes)),
+        _ => Value::Null,
+    }
+}
+
+/// A request PDU for `oids` (values NULL); `params` are error-status /
+/// error-index, or non-repeaters / max-repetitions for GETBULK.
+fn encode_pdu(tag: u8, request_id: i32, params: (i64, i64), oids: &[Vec<u32>]) -> Vec<u8> {
+    let mut list = Vec::new();
+    for oid in oids {
+        let mut varbind = encode_oid(oid);
+        varbind.extend(tlv(NULL, &[]));
+        list.extend(tlv(SEQUENCE, &varbind));
+    }
+    let mut content = integer(request_id.into());
+    content.extend(integer(params.0));
+    content.extend(integer(params.1));
+    content.extend(tlv(SEQUENCE, &list));
+    tlv(tag, &content)
+}
+
+fn decode_pdu(tag: u8, content: &[u8]) -> Result<Pdu> {
+    let mut r = Ber(content);
+    let request_id = r.integer()?;
+    let error_status = r.integer()?;
+    let error_index = r.integer()?;
+    let mut list = Ber(r.expect(SEQUENCE)?);
+    let mut varbinds = Vec::new();
+    while !list.0.is_empty() {
+        let mut varbind = Ber(list.expect(SEQUENCE)?);
+        let oid = decode_oid(varbind.expect(OBJECT_ID)?);
+        let (tag, value) = varbind.next()?;
+        varbinds.push(VarBind { oid, tag, value: decode_value(tag, value) });
+    }
+    Ok(Pdu { tag, request_id, error_status, error_index, varbinds })
+}
+
+/// request-id (v2c) or msgID (v3) of a message.
+fn message_id(buf: &[u8]) -> Option<i32> {
+    let mut r = Ber(Ber(buf).expect(SEQUENCE).ok()?);
+    let id = match r.integer().ok()? {
+        1 => {
+            r.expect(OCTET_STRING).ok()?;
+            Ber(r.next().ok()?.1).integer().ok()?
+        }
+        3 => Ber(r.expect(SEQUENCE).ok()?).integer().ok()?,
+        _ => return None,
+    };
+    i32::try_from(id).ok()
+}
+
+fn v2c_message(community: &str, pdu: &[u8]) -> Vec<u8> {
+    let mut content = integer(1);
+    content.extend(octets(community.as_bytes()));
+    content.extend_from_slice(pdu);
+    tlv(SEQUENCE, &content)
+}
+
+fn v2c_decode(buf: &[u8]) -> Result<Pdu> {
+    let mut r = Ber(Ber(buf).expect(SEQUENCE)?);
+    if r.integer()? != 1 {
+        return Err(malformed());
+    }
+    r.expect(OCTET_STRING)?;
+    let (tag, pdu) = r.next()?;
+    decode_pdu(tag, pdu)
+}
+
+// ── USM (RFC 3414, 3826, 7860) ────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Usm {
+    pub user: String,
+    pub auth_protocol: Option<AuthProtocol>,
+    pub auth_password: Option<String>,
+    pub priv_protocol: Option<PrivProtocol>,
+    pub priv_password: Option<String>,
+    #[serde(default)]
+    pub context: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum Credentials {
+    Community(String),
+    Usm(Usm),
+}
+
+impl Credentials {
+    /// From the Vault secret (None = community "public").
+    pub fn from_secret(secret: Option<&str>) -> Result<Self> {
+        let Some(secret) = secret else {
+            return Ok(Self::Community("public".into()));
+        };
+        if !secret.trim_start().starts_with('{') {
+            return Ok(Self::Community(secret.trim().to_owned()));
+        }
+        let value: Value = serde_json::from_str(secret)
+            .map_err(|e| ExecError::validation(format!("SNMP credential secret is not valid JSON: {e}")))?;
+        if let Some(community) = value.get("community").and_then(Value::as_str) {
+            return Ok(Self::Community(community.to_owned()));
+        }
+        let usm: Usm = serde_json::from_value(value)
+            .map_err(|e| ExecError::validation(format!("SNMP credential secret: {e}")))?;
+        if usm.priv_password.is_some() && usm.auth_password.is_none() {
+            return Err(ExecError::validation("SNMPv3 privacy needs an authPassword"));
+        }
+        Ok(Self::Usm(usm))
+    }
+}
+
+const FLAG_AUTH: u8 = 0x01;
+const FLAG_PRIV: u8 = 0x02;
+const FLAG_REPORTABLE: u8 = 0x04;
+
+/// Keys localized to one engine.
+#[derive(Debug, Clone)]
+struct Keys {
+    auth: Option<(AuthProtocol, Vec<u8>)>,
+    privacy: Option<(PrivProtocol, Vec<u8>)>,
+}
+
+impl Keys {
+    fn new(usm: &Usm, engine: &[u8]) -> Result<Self> {
+        let protocol = usm.auth_protocol.unwrap_or(AuthProtocol::Sha1);
+        let key = |password: &String| localize(protocol, password, engine);
+        Ok(Self {
+            auth: usm.auth_password.as_ref().map(key).transpose()?.map(|k| (protocol, k)),
+            privacy: usm.priv_password.as_ref().map(key).transpose()?
+                .map(|k| (usm.priv_protocol.unwrap_or(PrivProtocol::Aes), k)),
+        })
+    }
+
+    fn flags(&self) -> u8 {
+        (if self.auth.is_some() { FLAG_AUTH } else { 0 }) | (if self.privacy.is_some() { FLAG_PRIV } else { 0 })
+    }
+}
+
+/// An authoritative SNMP engine (the agent).
+#[derive(Debug, Clone)]
+struct Engine {
+    id: Vec<u8>,
+    boots: u32,
+    time: u32,
+    at: Instant,
+}
+
+impl Engine {
+    /// Estimated engine time now.
+    fn time(&self) -> u32 {
+        self.time.saturating_add(self.at.elapsed().as_secs() as u32)
+    }
+}
+
+struct V3<'a> {
+    msg_id: i32,
+    flags: u8,
+    engine: &'a [u8],
+    boots: u32,
+    time: u32,
+    user: &'a [u8],
+    context: &'a [u8],
+    pdu: &'a [u8],
+    salt: [u8; 8],
+}
+
+fn v3_message(m: &V3, keys: Option<&Keys>) -> Result<Vec<u8>> {
+    let mut scoped = octets(m.engine);
+    scoped.extend(octets(m.context));
+    scoped.extend_from_slice(m.pdu);
+    let scoped = tlv(SEQUENCE, &scoped);
+    let (data, salt) = match keys.and_then(|k| k.privacy.as_ref()) {
+        Some((protocol, key)) => {
+            let salt = match protocol {
+                PrivProtocol::Aes => m.salt.to_vec(),
+                PrivProtocol::Des => [&m.boots.to_be_bytes()[..], &m.salt[4..]].concat(),
+            };
+            (octets(&crypt(true, *protocol, key, (m.boots, m.time), &salt, &scoped)?), salt)
+        }
+        None => (scoped, Vec::new()),
+    };
+    let auth = keys.and_then(|k| k.auth.as_ref());
+    let mac_len = auth.map_or(0, |(p, _)| p.mac_len());
+
+    let mut global = integer(m.msg_id.into());
+    global.extend(integer(MAX_MESSAGE));
+    global.extend(octets(&[m.flags]));
+    global.extend(integer(3));
+    let mut head = integer(3);
+    head.extend(tlv(SEQUENCE, &global));
+    let mut usm_head = octets(m.engine);
+    usm_head.extend(integer(m.boots.into()));
+    usm_head.extend(integer(m.time.into()));
+    usm_head.extend(octets(m.user));
+    let mut usm = usm_head.clone();
+    usm.extend(octets(&vec![0; mac_len]));
+    usm.extend(octets(&salt));
+    let usm_seq = tlv(SEQUENCE, &usm);
+    let mut content = head.clone();
+    content.extend(octets(&usm_seq));
+    content.extend(data);
+    let mut message = tlv(SEQUENCE, &content);
+
+    // The MAC covers the whole message with its own field zeroed
+    if let Some((protocol, key)) = auth {
+        let at = header_len(content.len()) + head.len() + header_len(usm_seq.len())
+            + header_len(usm.len()) + usm_head.len() + header_len(mac_len);
+        let mac = hmac(*protocol, key, &message)?;
+        message[at..at + mac_len].copy_from_slice(&mac);
+    }
+    Ok(message)
+}
+
+struct V3Reply {
+    flags: u8,
+    engine: Engine,
+    pdu: Pdu,
+}
+
+/// Decode (authenticate, decrypt) an SNMPv3 message.
+fn v3_decode(buf: &[u8], keys: Option<&Keys>) -> Result<V3Reply> {
+    let mut r = Ber(Ber(buf).expect(SEQUENCE)?);
+    if r.integer()? != 3 {
+        return Err(malformed());
+    }
+    let mut global = Ber(r.expect(SEQUENCE)?);
+    global.integer()?;
+    global.integer()?;
+    let flags = global.expect(OCTET_STRING)?.first().copied().unwrap_or(0);
+    let mut usm = Ber(Ber(r.expect(OCTET_STRING)?).expect(SEQUENCE)?);
+    let id = usm.expect(OCTET_STRING)?.to_vec();
+    let boots = usm.integer()? as u32;
+    let time = usm.integer()? as u32;
+    usm.expect(OCTET_STRING)?;
+    let mac = usm.expect(OCTET_STRING)?;
+    let salt = usm.expect(OCTET_STRING)?;
+
+    if let (true, Some((protocol, key))) = (flags & FLAG_AUTH != 0, keys.and_then(|k| k.auth.as_ref())) {
+        let at = mac.as_ptr() as usize - buf.as_ptr() as usize;
+        let mut zeroed = buf.to_vec();
+        zeroed[at..at + mac.len()].fill(0);
+        let expected = hmac(*protocol, key, &zeroed)?;
+        if expected.len() != mac.len() || !openssl::memcmp::eq(&expected, mac) {
+            return Err(ExecError::new(ErrorCode::Auth, "SNMPv3 message failed authentication").into());
+        }
+    }
+    let decrypted;
+    let scoped = if flags & FLAG_PRIV != 0 {
+        let (protocol, key) = keys.and_then(|k| k.privacy.as_ref())
+            .ok_or_else(|| ExecError::new(ErrorCode::Auth, "SNMPv3 message is encrypted but no privPassword is set"))?;
+        decrypted = crypt(false, *protocol, key, (boots, time), salt, r.expect(OCTET_STRING)?)?;
+        &decrypted[..]
+    } else {
+        r.0
+    };
+    let mut scoped = Ber(Ber(scoped).expect(SEQUENCE)?);
+    scoped.expect(OCTET_STRING)?;
+    scoped.expect(OCTET_STRING)?;
+    let (tag, pdu) = scoped.next()?;
+    Ok(V3Reply { flags, engine: Engine { id, boots, time, at: Instant::now() }, pdu: decode_pdu(tag, pdu)? })
+}
+
+/// usmStats counter a REPORT carries.
+fn usm_stat(oid: &[u32]) -> Option<&'static str> {
+    let n = oid.strip_prefix(&[1, 3, 6, 1, 6, 3, 15, 1, 1][..])?.first()?;
+    ["unsupportedSecLevels", "notInTimeWindows", "unknownUserNames", "unknownEngineIDs", "wrongDigests", "decryptionErrors"]
+        .get((*n as usize).checked_sub(1)?)
+        .copied()
+}
+
+// ── Connector ─────────────────────────────────────────────────────────────────
+
+/// What an `snmp://` URL asks for.
+#[derive(Debug, PartialEq)]
+struct Target {
+    host: String,
+    oids: Vec<Vec<u32>>,
+    walk: bool,
+    max_repetitions: u32,
+}
+
+impl Target {
+    fn parse(url: &str) -> Result<Self> {
+        let rest = url.strip_prefix("snmp://")
+            .ok_or_else(|| ExecError::validation(format!("not an SNMP URL: {url}")))?;
+        let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (host, oids) = rest.split_once('/')
+            .filter(|(h, _)| !h.is_empty())
+            .ok_or_else(|| ExecError::validation(format!("{url}: expected snmp://<host>[:port]/<oid>[,<oid>…]")))?;
+        if host.contains('@') {
+            return Err(ExecError::validation(format!("{url}: SNMP credentials belong in credentials_vault_path")));
+        }
+        let host = if host.ends_with(']') || !host.contains(':') { format!("{host}:{DEFAULT_PORT}") } else { host.to_owned() };
+        let oids = oids.split(',').filter(|o| !o.is_empty()).map(parse_oid).collect::<Result<Vec<_>>>()?;
+        if oids.is_empty() {
+            return Err(ExecError::validation(format!("{url}: no OID")));
+        }
+        let mut target = Self { host, oids, walk: false, max_repetitions: DEFAULT_REPETITIONS };
+        for kv in query.split('&').filter(|kv| !kv.is_empty()) {
+            match kv.split_once('=').unwrap_or((kv, "")) {
+                ("walk", v) => target.walk = v != "false",
+                ("maxRepetitions", n) => {
+                    target.max_repetitions = n.parse().ok().filter(|n| (1..=1000).contains(n))
+                        .ok_or_else(|| ExecError::validation(format!("{url}: maxRepetitions must be 1..=1000")))?;
+                }
+                (k, _) => return Err(ExecError::validation(format!("{url}: unknown option '{k}'"))),
+            }
+        }
+        Ok(target)
+    }
+}
+
+/// One agent, for the duration of a LOAD_RESOURCE.
+struct Session {
+    host: String,
+    addr: SocketAddr,
+    socket: UdpSocket,
+    credentials: Credentials,
+    /// SNMPv3: the agent's engine and the keys localized to it
+    usm: Option<(Engine, Keys)>,
+}
+
+pub struct SnmpConnector {
+    timeout: Duration,
+    retries: u32,
+    max_varbinds: usize,
+    /// Engines discovered per agent (SNMPv3)
+    engines: Mutex<HashMap<SocketAddr, Engine>>,
+    next_id: AtomicI32,
+    salt: AtomicU64,
+}
+
+impl SnmpConnector {
+    pub fn new(timeout: Duration, retries: u32, max_varbinds: usize) -> Self {
+        Self {
+            timeout,
+            retries,
+            max_varbinds,
+            engines: Mutex::new(HashMap::new()),
+            next_id: AtomicI32::new(rand::random::<i32>() & 0x3fff_ffff),
+            salt: AtomicU64::new(rand::random()),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(Duration::from_millis(config.snmp_timeout_ms), config.snmp_retries, config.snmp_max_varbinds)
+    }
+
+    fn next_id(&self) -> i32 {
+        self.next_id.fetch_add(1, Ordering::Relaxed) & 0x7fff_ffff
+    }
+
+    /// LOAD_RESOURCE `url`; `secret` is the Vault credential secret.
+    pub async fn load(&self, url: &str, secret: Option<&str>) -> Result<Value> {
+        let target = Target::parse(url)?;
+        let credentials = Credentials::from_secret(secret)?;
+        let network = |e: std::io::Error| ExecError::new(ErrorCode::Network, format!("SNMP {}: {e}", target.host));
+        let addr = tokio::net::lookup_host(&target.host).await.map_err(network)?
+            .next()
+            .ok_or_else(|| ExecError::new(ErrorCode::Network, format!("SNMP {}: no address", target.host)))?;
+        let socket = UdpSocket::bind(if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" }).await.map_err(network)?;
+        socket.connect(addr).await.map_err(network)?;
+        let mut session = Session { host: target.host.clone(), addr, socket, credentials, usm: None };
+        debug!("[SNMP] {} {}", if target.walk { "WALK" } else { "GET" }, url);
+
+        let mut varbinds = Vec::new();
+        let mut truncated = false;
+        if target.walk {
+            for root in &target.oids {
+                let mut last = root.clone();
+                'walk: loop {
+                    let params = (0, i64::from(target.max_repetitions));
+                    let pdu = self.request(&mut session, GET_BULK, std::slice::from_ref(&last), params).await?;
+                    if pdu.varbinds.is_empty() {
+                        break;
+                    }
+                    for varbind in pdu.varbinds {
+                        if varbind.tag == END_OF_MIB_VIEW || !varbind.oid.starts_with(root) {
+                            break 'walk;
+                        }
+                        if varbind.oid <= last {
+                            return Err(ExecError::new(ErrorCode::Upstream, format!(
+                                "SNMP {}: agent returned {} out of order", target.host, oid_string(&varbind.oid),
+                            )).into());
+                        }
+                        if varbinds.len() >= self.max_varbinds {
+                            truncated = true;
+                            break 'walk;
+                        }
+                        last.clone_from(&varbind.oid);
+                        varbinds.push(varbind);
+                    }
+                }
+            }
+        } else {
+            varbinds = self.request(&mut session, GET, &target.oids, (0, 0)).await?.varbinds;
+        }
+
+        let mut values = Map::new();
+        let list: Vec<Value> = varbinds.into_iter().map(|v| {
+            let oid = oid_string(&v.oid);
+            values.insert(oid.clone(), v.value.clone());
+            json!({ "oid": oid, "type": type_name(v.tag), "value": v.value })
+        }).collect();
+        let mut out = json!({ "host": target.host, "varbinds": list, "values": values });
+        if truncated {
+            out["truncated"] = json!(true);
+        }
+        Ok(out)
+    }
+
+    /// Send one request PDU and return the agent's RESPONSE.
+    async fn request(&self, session: &mut Session, tag: u8, oids: &[Vec<u32>], params: (i64, i64)) -> Result<Pdu> {
+        let request_id = self.next_id();
+        let pdu = encode_pdu(tag, request_id, params, oids);
+        let usm = match &session.credentials {
+            Credentials::Community(community) => {
+                let reply = self.exchange(session, &v2c_message(community, &pdu), request_id).await?;
+                return check(v2c_decode(&reply)?, &session.host, request_id);
+            }
+            Credentials::Usm(usm) => usm.clone(),
+        };
+        // A second round after a resynchronisation (notInTimeWindow, new engine)
+        for _ in 0..2 {
+            if session.usm.is_none() {
+                let engine = self.discover(session).await?;
+                let (u, id) = (usm.clone(), engine.id.clone());
+                let keys = tokio::task::spawn_blocking(move || Keys::new(&u, &id)).await??;
+                session.usm = Some((engine, keys));
+            }
+            let (engine, keys) = session.usm.as_ref().expect("discovered above");
+            let msg_id = self.next_id();
+            let message = v3_message(&V3 {
+                msg_id,
+                flags: keys.flags() | FLAG_REPORTABLE,
+                engine: &engine.id,
+                boots: engine.boots,
+                time: engine.time(),
+                user: usm.user.as_bytes(),
+                context: usm.context.as_bytes(),
+                pdu: &pdu,
+                salt: self.salt.fetch_add(1, Ordering::Relaxed).to_be_bytes(),
+            }, Some(keys))?;
+            let reply = v3_decode(&self.exchange(session, &message, msg_id).await?, Some(keys))?;
+            let sent_auth = keys.auth.is_some();
+
+            if reply.pdu.tag == REPORT {
+                let oid = reply.pdu.varbinds.first().map(|v| v.oid.clone()).unwrap_or_default();
+                match usm_stat(&oid) {
+                    Some("notInTimeWindows") if reply.engine.id == engine.id => {
+                        debug!("[SNMP] {}: engine time resynchronised", session.host);
+                        if let Some((engine, _)) = session.usm.as_mut() {
+                            *engine = reply.engine;
+                        }
+                    }
+                    Some("unknownEngineIDs" | "notInTimeWindows") => {
+                        self.engines.lock().unwrap_or_else(|e| e.into_inner()).remove(&session.addr);
+                        session.usm = None;
+                    }
+                    stat => {
+                        let what = stat.map_or_else(|| oid_string(&oid), str::to_owned);
+                        return Err(ExecError::new(ErrorCode::Auth, format!("SNMPv3 {}: agent reported {what}", session.host)).into());
+                    }
+                }
+                continue;
+            }
+            if sent_auth && reply.flags & FLAG_AUTH == 0 {
+                return Err(ExecError::new(ErrorCode::Auth, format!("SNMPv3 {}: unauthenticated response", session.host)).into());
+            }
+            if sent_auth {
+                self.engines.lock().unwrap_or_else(|e| e.into_inner()).insert(session.addr, reply.engine.clone());
+            }
+            return check(reply.pdu, &session.host, request_id);
+        }
+        Err(ExecError::new(ErrorCode::Auth, format!("SNMPv3 {}: could not synchronise with the agent's engine", session.host)).into())
+    }
+
+    /// The agent's engine: cached, else discovered with an empty request.
+    async fn discover(&self, session: &Session) -> Result<Engine> {
+        if let Some(engine) = self.engines.lock().unwrap_or_else(|e| e.into_inner()).get(&session.addr) {
+            return Ok(engine.clone());
+        }
+        let msg_id = self.next_id();
+        let pdu = encode_pdu(GET, self.next_id(), (0, 0), &[]);
+        let message = v3_message(&V3 {
+            msg_id, flags: FLAG_REPORTABLE, engine: &[], boots: 0, time: 0, user: &[], context: &[], pdu: &pdu, salt: [0; 8],
+        }, None)?;
+        let reply = v3_decode(&self.exchange(session, &message, msg_id).await?, None)?;
+        if reply.engine.id.is_empty() {
+            return Err(ExecError::new(ErrorCode::Upstream, format!("SNMPv3 {}: engine discovery returned no engine ID", session.host)).into());
+        }
+        debug!("[SNMP] {}: engine {} (boots {})", session.host, hex::encode(&reply.engine.id), reply.engine.boots);
+        Ok(reply.engine)
+    }
+
+    /// Send `request` until a message with `id` comes back (SVM_SNMP_RETRIES).
+    async fn exchange(&self, session: &Session, request: &[u8], id: i32) -> Result<Vec<u8>> {
+        let mut buf = vec![0u8; 65_535];
+        for _ in 0..=self.retries {
+            session.socket.send(request).await
+                .map_err(|e| ExecError::new(ErrorCode::Network, format!("SNMP {}: {e}", session.host)))?;
+            let deadline = tokio::time::Instant::now() + self.timeout;
+            loop {
+                match tokio::time::timeout_at(deadline, session.socket.recv(&mut buf)).await {
+                    Ok(Ok(n)) if message_id(&buf[..n]) == Some(id) => return Ok(buf[..n].to_vec()),
+                    // A late answer to an earlier attempt
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => {
+                        return Err(ExecError::new(ErrorCode::Network, format!("SNMP {}: {e}", session.host)).into());
+                    }
+                    Err(_) => break,
+                }
+            }
+        }
+        Err(ExecError::new(ErrorCode::Timeout, format!(
+            "SNMP {}: no answer after {} attempt(s) of {:?}", session.host, self.retries + 1, self.timeout,
+        )).into())
+    }
+}
+
+/// A RESPONSE to request `id` without an error status.
+fn check(pdu: Pdu, host: &str, id: i32) -> Result<Pdu> {
+    if pdu.tag != RESPONSE {
+        return Err(ExecError::validation(format!("SNMP {host}: unexpected PDU type 0x{:02x}", pdu.tag)));
+    }
+    if pdu.request_id != i64::from(id) {
+        return Err(ExecError::validation(format!("SNMP {host}: response to request {}, expected {id}", pdu.request_id)));
+    }
+
+    if pdu.error_status != 0 {
+        let name = usize::try_from(pdu.error_status).ok().and_then(|s| ERROR_STATUS.get(s)).unwrap_or(&"error");
+        let code = if matches!(pdu.error_status, 6 | 16) { ErrorCode::Auth } else { ErrorCode::Upstream };
+        return Err(ExecError::new(code, format!("SNMP {host}: {name} (varbind {})", pdu.error_index)).into());
+    }
+    Ok(pdu)
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RESPONSE / REPORT PDU with typed values.
+    fn response(tag: u8, request_id: i64, varbinds: &[(&str, u8, &[u8])]) -> Vec<u8> {
+        let mut list = Vec::new();
+        for (oid, t, value) in varbinds {
+            let mut varbind = encode_oid(&parse_oid(oid).unwrap());
+            varbind.extend(tlv(*t, value));
+            list.extend(tlv(SEQUENCE, &varbind));
+        }
+        let mut content = integer(request_id);
+        content.extend(integer(0));
+        content.extend(integer(0));
+        content.extend(tlv(SEQUENCE, &list));
+        tlv(tag, &content)
+    }
+
+    /// Fake agent: ifInOctets.1 / .2 and sysName; v2c community "private",
+    /// v3 user "monitor" (SHA / AES).
+    async fn agent(socket: UdpSocket, keys: Keys) {
+        let engine = b"\x80\x00\x1f\x88\x04edge".to_vec();
+        let mut buf = vec![0u8; 65_535];
+        loop {
+            let (n, peer) = socket.recv_from(&mut buf).await.unwrap();
+            let request = &buf[..n];
+            let msg_id = message_id(request).unwrap();
+            let table: [(&str, u8, &[u8]); 3] = [
+                ("1.3.6.1.2.1.2.2.1.10.1", COUNTER32, &[0x64]),
+                ("1.3.6.1.2.1.2.2.1.10.2", COUNTER32, &[0x00, 0xc8]),
+                ("1.3.6.1.2.1.2.2.1.11.1", COUNTER32, &[0x01]),
+            ];
+            let answer = |pdu: &Pdu| {
+                if pdu.tag == GET_BULK {
+                    let start = &pdu.varbinds[0].oid;
+                    let next: Vec<_> = table.iter().filter(|(o, ..)| parse_oid(o).unwrap() > *start).take(2).copied().collect();
+                    response(RESPONSE, pdu.request_id, &next)
+                } else {
+                    let get: [(&str, u8, &[u8]); 2] = [("1.3.6.1.2.1.1.5.0", OCTET_STRING, b"edge-sw"), ("1.3.6.1.2.1.1.6.0", NO_SUCH_OBJECT, b"")];
+                    response(RESPONSE, pdu.request_id, &get)
+                }
+            };
+            let mut r = Ber(Ber(request).expect(SEQUENCE).unwrap());
+            let reply = if r.integer().unwrap() == 1 {
+                if r.expect(OCTET_STRING).unwrap() != b"private" {
+                    continue;
+                }
+                v2c_message("private", &answer(&v2c_decode(request).unwrap()))
+            } else {
+                match v3_decode(request, Some(&keys)) {
+                    Ok(r) if r.engine.id.is_empty() => {
+                        let report = response(REPORT, r.pdu.request_id, &[("1.3.6.1.6.3.15.1.1.4.0", COUNTER32, &[1])]);
+                        v3_message(&V3 {
+                            msg_id, flags: 0, engine: &engine, boots: 1, time: 100,
+                            user: b"", context: b"", pdu: &report, salt: [0; 8],
+                        }, None).unwrap()
+                    }
+                    Ok(r) => v3_message(&V3 {
+                        msg_id, flags: keys.flags(), engine: &engine, boots: 1, time: r.engine.time,
+                        user: b"monitor", context: b"", pdu: &answer(&r.pdu), salt: [7; 8],
+                    }, Some(&keys)).unwrap(),
+                    Err(_) => continue,
+                }
+            };
+            socket.send_to(&reply, peer).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_ber_round_trip() {
+        for value in [0, 1, 127, 128, 255, 256, -1, -128, -129, i32::MAX.into(), i32::MIN.into(), i64::MAX, i64::MIN] {
+            let encoded = integer(value);
+            assert_eq!(Ber(&encoded).integer().unwrap(), value);
+        }
+        // X.690 8.3: minimal two's complement
+        assert_eq!(integer(127), [0x02, 0x01, 0x7f]);
+        assert_eq!(integer(128), [0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(integer(-128), [0x02, 0x01, 0x80]);
+        assert_eq!(integer(-129), [0x02, 0x02, 0xff, 0x7f]);
+        // 8.1.3: short and long definite lengths
+        for len in [0, 0x7f, 0x80, 0xff, 0x100, 0x1_0000] {
+            let content = vec![0xaa; len];
+            let encoded = octets(&content);
+            assert_eq!(encoded.len(), header_len(len) + len);
+            let mut r = Ber(&encoded);
+            assert_eq!(r.expect(OCTET_STRING).unwrap(), &content[..]);
+            assert!(r.0.is_empty());
+        }
+        assert_eq!(&octets(&[0; 0x80])[..3], [0x04, 0x81, 0x80]);
+        assert_eq!(&octets(&[0; 0x100])[..4], [0x04, 0x82, 0x01, 0x00]);
+        // 8.19: sub-identifiers in base 128, the first two folded
+        assert_eq!(encode_oid(&parse_oid("1.3.6.1.2.1.1.3.0").unwrap()), hex::decode("06082b06010201010300").unwrap());
+        for oid in ["1.3.6.1.4.1.2021.11.9.0", "2.999.1", "1.3.6.1.4.1.4294967295", "0.39"] {
+            let oid = parse_oid(oid).unwrap();
+            assert_eq!(decode_oid(&encode_oid(&oid)[2..]), oid);
+        }
+        assert!(parse_oid("1.40").is_err());
+        assert!(parse_oid("3.1").is_err());
+        assert!(parse_oid("1.3.x").is_err());
+
+        let request = v2c_message("public", &encode_pdu(GET, 0x1234, (0, 0), &[parse_oid("1.3.6.1.2.1.1.5.0").unwrap()]));
+        assert_eq!(message_id(&request), Some(0x1234));
+        let pdu = v2c_decode(&request).unwrap();
+        assert_eq!((pdu.tag, pdu.request_id, pdu.varbinds.len()), (GET, 0x1234, 1));
+        assert_eq!((pdu.varbinds[0].tag, &pdu.varbinds[0].value), (NULL, &Value::Null));
+
+        // Typed values
+        let reply = v2c_message("public", &response(RESPONSE, 7, &[
+            ("1.3.6.1.2.1.4.20.1.1.0", IP_ADDRESS, &[10, 0, 0, 1]),
+            ("1.3.6.1.2.1.1.3.0", TIME_TICKS, &[0x00, 0xff, 0xff, 0xff, 0xff]),
+            ("1.3.6.1.2.1.31.1.1.1.6.1", COUNTER64, &[0xff; 8]),
+            ("1.3.6.1.2.1.2.2.1.6.1", OCTET_STRING, &[0x00, 0x1b, 0x21]),
+            ("1.3.6.1.2.1.1.2.0", OBJECT_ID, &encode_oid(&[1, 3, 6, 1, 4, 1, 9])[2..]),
+            ("1.3.6.1.2.1.1.9.0", END_OF_MIB_VIEW, &[]),
+        ]));
+        let values: Vec<_> = v2c_decode(&reply).unwrap().varbinds.into_iter().map(|v| v.value).collect();
+        assert_eq!(values, [json!("10.0.0.1"), json!(4_294_967_295u64), json!(u64::MAX), json!("00:1b:21"), json!("1.3.6.1.4.1.9"), Value::Null]);
+
+        // Truncated and malformed messages fail with VALIDATION, never panic
+        for cut in 0..reply.len() {
+            assert_eq!(crate::errors::classify(&v2c_decode(&reply[..cut]).unwrap_err()), ErrorCode::Validation);
+            assert_eq!(message_id(&reply[..cut]), None);
+            assert!(v3_decode(&reply[..cut], None).is_err());
+        }
+
+        for bad in [&[0x30, 0x85, 0, 0, 0, 0, 1][..], &[0x30, 0x80], &[0x30, 0x82, 0xff], &[0x02, 0x01, 0x00]] {
+            assert_eq!(crate::errors::classify(&v2c_decode(bad).unwrap_err()), ErrorCode::Validation);
+        }
+        let mut wrong_version = request.clone();
+        wrong_version[4] = 2;
+        assert!(v2c_decode(&wrong_version).is_err());
+    }
+
+    #[test]
+    fn test_v3_message_round_trip() {
+        // Authenticated, encrypted message round trip; any flipped bit fails the MAC
+        let usm = Usm {
+            user: "monitor".into(), auth_protocol: Some(AuthProtocol::Sha1), auth_password: Some("authpass1".into()),
+            priv_protocol: Some(PrivProtocol::Aes), priv_password: Some("privpass1".into()), context: String::new(),
+        };
+        let engine = b"\x80\x00\x1f\x88\x04edge";
+        let keys = Keys::new(&usm, engine).unwrap();
+        let pdu = encode_pdu(GET, 42, (0, 0), &[parse_oid("1.3.6.1.2.1.1.5.0").unwrap()]);
+        let message = v3_message(&V3 {
+            msg_id: 9, flags: keys.flags() | FLAG_REPORTABLE, engine, boots: 3, time: 1000,
+            user: b"monitor", context: b"", pdu: &pdu, salt: [5; 8],
+        }, Some(&keys)).unwrap();
+        assert_eq!(message_id(&message), Some(9));
+        let reply = v3_decode(&message, Some(&keys)).unwrap();
+        assert_eq!((reply.engine.id.as_slice(), reply.engine.boots, reply.engine.time), (&engine[..], 3, 1000));
+        assert_eq!((reply.pdu.tag, reply.pdu.request_id), (GET, 42));
+        let mut tampered = message.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(crate::errors::classify(&v3_decode(&tampered, Some(&keys)).err().unwrap()), ErrorCode::Auth);
+        let other = Keys::new(&Usm { auth_password: Some("otherpass".into()), ..usm }, engine).unwrap();
+        assert_eq!(crate::errors::classify(&v3_decode(&message, Some(&other)).err().unwrap()), ErrorCode::Auth);
+        assert!(v3_decode(&message, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_and_walk_over_v2c_and_v3() {
+        assert!(Target::parse
+("snmp://sw/1.3.6.1?walk&maxRepetitions=0").is_err());
+        assert!(Target::parse("snmp://public@sw/1.3.6.1.2.1.1.3.0").is_err());
+
+        let secret = r#"{ "user": "monitor", "authProtocol": "SHA", "authPassword": "authpass1", "privProtocol": "AES", "privPassword": "privpass1" }"#;
+        let Credentials::Usm(usm) = Credentials::from_secret(Some(secret)).unwrap() else { panic!("v3 secret") };
+        let keys = Keys::new(&usm, b"\x80\x00\x1f\x88\x04edge").unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        tokio::spawn(agent(socket, keys));
+
+        let snmp = SnmpConnector::new(Duration::from_millis(300), 0, 100);
+        for secret in ["private", secret] {
+            let get = snmp.load(&format!("snmp://127.0.0.1:{port}/1.3.6.1.2.1.1.5.0,1.3.6.1.2.1.1.6.0"), Some(secret)).await.unwrap();
+            assert_eq!(get["values"]["1.3.6.1.2.1.1.5.0"], "edge-sw");
+            assert_eq!(get["varbinds"][1]["type"], "noSuchObject");
+            let walk = snmp.load(&format!("snmp://127.0.0.1:{port}/1.3.6.1.2.1.2.2.1.10?walk"), Some(secret)).await.unwrap();
+            assert_eq!(walk["values"], json!({ "1.3.6.1.2.1.2.2.1.10.1": 100, "1.3.6.1.2.1.2.2.1.10.2": 200 }), "{walk}");
+        }
+        // Wrong community: dropped by the agent
+        let err = snmp.load(&format!("snmp://127.0.0.1:{port}/1.3.6.1.2.1.1.5.0"), None).await.unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::Timeout);
+    }
+}
@@ -0,0 +1,228 @@
+//! SNMPv3 USM cryptography — key localization, message digests, privacy
+//!
+//!   RFC 3414  password → Ku → localized key; HMAC-MD5-96, HMAC-SHA-96;
+//!             CBC-DES with IV = pre-IV ⊕ salt
+//!   RFC 3826  CFB128-AES-128 with IV = engineBoots ‖ engineTime ‖ salt
+//!   RFC 7860  HMAC-SHA-256-192
+//!
+//! Hand-written because the node's build has no SNMP crate to link, while
+//! OpenSSL (digests, HMAC, ciphers) is already linked for TLS.  Only the
+//! glue the RFCs define lives here, each piece pinned to test vectors below.
+
+use anyhow::Result;
+use openssl::hash::{Hasher, MessageDigest};
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use openssl::symm::{Cipher, Crypter, Mode};
+use serde::Deserialize;
+
+use super::malformed;
+use crate::errors::{ErrorCode, ExecError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum AuthProtocol {
+    #[serde(rename = "MD5", alias = "md5")]
+    Md5,
+    #[serde(rename = "SHA", alias = "sha", alias = "SHA1", alias = "sha1")]
+    Sha1,
+    #[serde(rename = "SHA256", alias = "sha256", alias = "SHA-256")]
+    Sha256,
+}
+
+impl AuthProtocol {
+    fn digest(self) -> MessageDigest {
+        match self {
+            Self::Md5 => MessageDigest::md5(),
+            Self::Sha1 => MessageDigest::sha1(),
+            Self::Sha256 => MessageDigest::sha256(),
+        }
+    }
+
+    /// Truncated HMAC carried in msgAuthenticationParameters.
+    pub(super) fn mac_len(self) -> usize {
+        match self {
+            Self::Md5 | Self::Sha1 => 12,
+            Self::Sha256 => 24,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum PrivProtocol {
+    #[serde(rename = "DES", alias = "des")]
+    Des,
+    #[serde(rename = "AES", alias = "aes", alias = "AES128")]
+    Aes,
+}
+
+/// Password → key localized to `engine` (RFC 3414 A.2).
+pub(super) fn localize(protocol: AuthProtocol, password: &str, engine: &[u8]) -> Result<Vec<u8>> {
+    let ku = password_key(protocol, password)?;
+    let mut hasher = Hasher::new(protocol.digest())?;
+    hasher.update(&ku)?;
+    hasher.update(engine)?;
+    hasher.update(&ku)?;
+    Ok(hasher.finish()?.to_vec())
+}
+
+/// Password → Ku, the digest of the password repeated over 1 MiB (RFC 3414 A.2.1).
+fn password_key(protocol: AuthProtocol, password: &str) -> Result<Vec<u8>> {
+    let password = password.as_bytes();
+    if password.is_empty() {
+        return Err(ExecError::validation("SNMPv3 passwords must not be empty"));
+    }
+    let mut hasher = Hasher::new(protocol.digest())?;
+    let mut chunk = [0u8; 64];
+    let mut index = 0;
+    for _ in 0..(1 << 20) / chunk.len() {
+        for b in chunk.iter_mut() {
+            *b = password[index % password.len()];
+            index += 1;
+        }
+        hasher.update(&chunk)?;
+    }
+    Ok(hasher.finish()?.to_vec())
+}
+
+pub(super) fn hmac(protocol: AuthProtocol, key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let key = PKey::hmac(key)?;
+    let mut mac = Signer::new(protocol.digest(), &key)?.sign_oneshot_to_vec(data)?;
+    mac.truncate(protocol.mac_len());
+    Ok(mac)
+}
+
+/// Encrypt / decrypt a scoped PDU; `salt` is msgPrivacyParameters.
+pub(super) fn crypt(encrypt: bool, protocol: PrivProtocol, key: &[u8], (boots, time): (u32, u32), salt: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    if salt.len() != 8 || key.len() < 16 {
+        return Err(malformed());
+    }
+    let mut data = data.to_vec();
+    let (cipher, key, iv): (Cipher, &[u8], Vec<u8>) = match protocol {
+        PrivProtocol::Aes => {
+            let iv = [&boots.to_be_bytes()[..], &time.to_be_bytes(), salt].concat();
+            (Cipher::aes_128_cfb128(), &key[..16], iv)
+        }
+        PrivProtocol::Des => {
+            if encrypt {
+                data.resize(data.len().div_ceil(8) * 8, 0);
+            } else if !data.len().is_multiple_of(8) {
+                return Err(malformed());
+            }
+            let iv = key[8..16].iter().zip(salt).map(|(k, s)| k ^ s).collect();
+            (Cipher::des_cbc(), &key[..8], iv)
+        }
+    };
+    let mode = if encrypt { Mode::Encrypt } else { Mode::Decrypt };
+    let mut crypter = Crypter::new(cipher, mode, key, Some(&iv))
+        .map_err(|e| ExecError::new(ErrorCode::Unsupported, format!("SNMPv3 {protocol:?} privacy: {e}")))?;
+    crypter.pad(false);
+    let mut out = vec![0; data.len() + cipher.block_size()];
+    let n = crypter.update(&data, &mut out)?;
+    let n = n + crypter.finalize(&mut out[n..])?;
+    out.truncate(n);
+    Ok(out)
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        hex::decode(s).unwrap()
+    }
+
+    #[test]
+    fn test_key_localization() {
+        // RFC 3414 A.3.1 / A.3.2
+        let engine = hex("000000000000000000000002");
+        assert_eq!(hex::encode(password_key(AuthProtocol::Md5, "maplesyrup").unwrap()), "9faf3283884e92834ebc9847d8edd963");
+        assert_eq!(hex::encode(password_key(AuthProtocol::Sha1, "maplesyrup").unwrap()), "9fb5cc0381497b3793528939ff788d5d79145211");
+        assert_eq!(hex::encode(localize(AuthProtocol::Md5, "maplesyrup", &engine).unwrap()), "526f5eed9fcce26f8964c2930787d82b");
+        assert_eq!(hex::encode(localize(AuthProtocol::Sha1, "maplesyrup", &engine).unwrap()), "6695febc9288e36282235fc7151f128497b38f3f");
+        // RFC 7860 publishes no key vectors; these were cross-checked against
+        // a plain hashlib implementation of A.2.1 (the second password does
+        // not divide the 64-byte chunks)
+        assert_eq!(
+            hex::encode(password_key(AuthProtocol::Sha256, "maplesyrup").unwrap()),
+            "ab51014d1e077f6017df2b12bee5f5aa72993177e9bb569c4dff5a4ca0b4afac",
+        );
+        assert_eq!(
+            hex::encode(localize(AuthProtocol::Sha256, "maplesyrup", &engine).unwrap()),
+            "8982e0e549e866db361a6b625d84cccc11162d453ee8ce3a6445c2d6776f0f8b",
+        );
+        let odd = format!("{}yz", "x".repeat(63));
+        assert_eq!(hex::encode(localize(AuthProtocol::Sha1, &odd, b"\x80\x00\x1f\x88\x04edge").unwrap()), "c17f68e9aed39e906e75fe613835bc4a0de76cf4");
+        assert!(localize(AuthProtocol::Sha1, "", &engine).is_err());
+    }
+
+    #[test]
+    fn test_hmac_truncation() {
+        // HMAC-96 (RFC 3414 6.3, 7.3) and HMAC-SHA-256-192 (RFC 7860) over
+        // RFC 2202 / RFC 4231 test cases 1–3
+        let cases: [(&[u8], &[u8], [&str; 3]); 3] = [
+            (&[0x0b; 20], b"Hi There", [
+                "9294727a3638bb1c13f48ef8",
+                "b617318655057264e28bc0b6",
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da7",
+            ]),
+            (b"Jefe", b"what do ya want for nothing?", [
+                "750c783e6ab0b503eaa86e31",
+                "effcdf6ae5eb2fa2d27416d5",
+                "5bdcc146bf60754e6a042426089575c75a003f089d273983",
+            ]),
+            (&[0xaa; 20], &[0xdd; 50], [
+                "56be34521d144c88dbb8c733",
+                "125d7342b9ac11cd91a39af4",
+                "773ea91e36800e46854db8ebd09181a72959098b3ef8c122",
+            ]),
+        ];
+        for (key, data, macs) in cases {
+            for (protocol, mac) in [AuthProtocol::Md5, AuthProtocol::Sha1, AuthProtocol::Sha256].into_iter().zip(macs) {
+                // RFC 2202 keys MD5 with the first 16 bytes
+                let key = if protocol == AuthProtocol::Md5 && key.len() == 20 { &key[..16] } else { key };
+                assert_eq!(hex::encode(hmac(protocol, key, data).unwrap()), mac, "{protocol:?}");
+                assert_eq!(mac.len(), protocol.mac_len() * 2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_privacy_vectors() {
+        // RFC 3826 3.1.2.1: IV = engineBoots ‖ engineTime ‖ salt; with this
+        // IV AES-128-CFB matches SP 800-38A F.3.13 (all four blocks)
+        let key = hex("2b7e151628aed2a6abf7158809cf4f3c");
+        let salt = hex("08090a0b0c0d0e0f");
+        let clock = (0x0001_0203, 0x0405_0607);
+        let plain = hex("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+                         30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710");
+        let expected = "3b3fd92eb72dad20333449f8e83cfb4ac8a64537a0b3a93fcde3cdad9f1ce58b\
+                        26751f67a3cbb140b1808cf187a4f4dfc04b05357c5d1c0eeac4c66f9ff7f2e6";
+        let cipher = crypt(true, PrivProtocol::Aes, &key, clock, &salt, &plain).unwrap();
+        assert_eq!(hex::encode(&cipher), expected);
+        assert_eq!(crypt(false, PrivProtocol::Aes, &key, clock, &salt, &cipher).unwrap(), plain);
+        // CFB needs no padding: a partial block is a prefix of the full one
+        let partial = crypt(true, PrivProtocol::Aes, &key, clock, &salt, &plain[..21]).unwrap();
+        assert_eq!(hex::encode(partial), expected[..42]);
+        assert!(crypt(true, PrivProtocol::Aes, &key, (0, 0), &salt[..4], &plain).is_err());
+        assert!(crypt(true, PrivProtocol::Aes, &key[..8], clock, &salt, &plain).is_err());
+
+        // RFC 3414 8.1.1.1: the DES key is the first half of the privacy key,
+        // the IV its second half ⊕ salt.  With a zero salt CBC-DES matches
+        // FIPS 81 table C1 ("Now is the time for all ").
+        let key = hex("0123456789abcdef1234567890abcdef");
+        let plain = b"Now is the time for all ";
+        match crypt(true, PrivProtocol::Des, &key, (0, 0), &[0; 8], plain) {
+            // OpenSSL 3 builds without the legacy provider
+            Err(e) => assert_eq!(crate::errors::classify(&e), ErrorCode::Unsupported, "{e}"),
+            Ok(cipher) => {
+                assert_eq!(hex::encode(&cipher), "e5c7cdde872bf27c43e934008c389c0f683788499a7c05f6");
+                assert_eq!(crypt(false, PrivProtocol::Des, &key, (0, 0), &[0; 8], &cipher).unwrap(), plain);
+                // Zero-padded to whole blocks on the way out; ragged input fails
+                assert_eq!(crypt(true, PrivProtocol::Des, &key, (0, 0), &[0; 8], &plain[..20]).unwrap().len(), 24);
+                assert!(crypt(false, PrivProtocol::Des, &key, (0, 0), &[0; 8], &cipher[..20]).is_err());
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! SQL connector — Postgres / MySQL / SQLite for LOAD_RESOURCE + CALL_SERVICE
+//!
+//! Compiled only with the `sql` Cargo feature (sqlx Any driver).  Selected when
+//! `dispatch_metadata.endpoint_url` uses a database scheme:
+//!
+//!   postgres://user@db-host/plant      mysql://user@erp-host/erp
+//!   sqlite:///var/lib/eyeflow/local.db
+//!
+//! Credentials come from Vault (`credentials_vault_path`): a secret holding a
+//! full DSN replaces the endpoint URL, anything else is used as the password.
+//!
+//! Queries are always parameterised — values never get spliced into SQL text:
+//!
+//!   operands_json = {
+//!     "query":  "SELECT ts, value FROM readings WHERE sensor = $1 AND ts > $2",
+//!     "params": ["$.sensorId", "2025-01-01T00:00:00Z"],
+//!     "mode":   "query" | "execute"          (optional; inferred from the SQL)
+//!   }
+//!
+//! String params starting with `$.` are resolved against the input register.
+//! `query` mode returns a JSON array of row objects; `execute` mode returns
+//! `{ "rowsAffected": n }`.
+//!
+//! One pool per DSN, sized SVM_SQL_POOL_SIZE and keyed without the password:
+//! when the Vault credentials rotate, the stale pool is replaced and closed
+//! once its running statements finish.  Concurrency across workflows is
+//! governed by the ResourceArbiter (capacity = pool size) before a connection
+//! is checked out, so priority policies apply to database access too.
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use sqlx::any::{AnyPoolOptions, AnyRow, AnyTypeInfoKind};
+use sqlx::AnyPool;
+use sqlx::{Column, Row, ValueRef};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{debug, info};
+
+use crate::svm::extract_dot_path;
+
+pub struct SqlConnector {
+    /// Redacted DSN → the full DSN the pool was built from, and the pool
+    pools: RwLock<HashMap<String, (String, AnyPool)>>,
+    pool_size: u32,
+}
+
+impl SqlConnector {
+    pub fn new(pool_size: u32) -> Self {
+        sqlx::any::install_default_drivers();
+        Self {
+            pools: RwLock::new(HashMap::new()),
+            pool_size: pool_size.max(1),
+        }
+    }
+
+    pub fn pool_size(&self) -> u32 {
+        self.pool_size
+    }
+
+    /// Run the statement described by `operands` against `dsn`.
+    pub async fn run(
+        &self,
+        dsn: &str,
+        operands: &Value,
+        input: Option<&Value>,
+        acquire_timeout: Duration,
+    ) -> Result<Value> {
+        let query = operands.get("query").and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("SQL operands missing \"query\""))?;
+        let params: Vec<Value> = operands.get("params")
+            .and_then(|v| v.as_array())
+            .map(|a| a.iter().map(|p| resolve_param(p, input)).collect())
+            .unwrap_or_default();
+        let mode = operands.get("mode").and_then(|v| v.as_str())
+            .unwrap_or_else(|| infer_mode(query));
+
+        let pool = self.pool_for(dsn, acquire_timeout).await?;
+
+        let mut q = sqlx::query(query);
+        for p in &params {
+            q = match p {
+                Value::Null      => q.bind(None::<String>),
+                Value::Bool(b)   => q.bind(*b),
+                Value::Number(n) => match n.as_i64() {
+                    Some(i) => q.bind(i),
+                    None    => q.bind(n.as_f64().unwrap_or_default()),
+                },
+                Value::String(s) => q.bind(s.clone()),
+                other            => q.bind(other.to_string()),
+            };
+        }
+
+        debug!("[Sql] {mode} on {} ({} params)", redact(dsn), params.len());
+        if mode == "execute" {
+            let res = q.execute(&pool).await?;
+            Ok(serde_json::json!({ "rowsAffected": res.rows_affected() }))
+        } else {
+            let rows = q.fetch_all(&pool).await?;
+            rows.iter().map(row_to_json).collect::<Result<Vec<_>>>().map(Value::Array)
+        }
+    }
+
+    async fn pool_for(&self, dsn: &str, acquire_timeout: Duration) -> Result<AnyPool> {
+        let key = redact(dsn);
+        if let Some((_, p)) = self.pools.read().await.get(&key).filter(|(d, _)| d == dsn) {
+            return Ok(p.clone());
+        }
+        let mut pools = self.pools.write().await;
+        if let Some((_, p)) = pools.get(&key).filter(|(d, _)| d == dsn) {
+            return Ok(p.clone());
+        }
+        let pool = AnyPoolOptions::new()
+            .max_connections(self.pool_size)
+            .acquire_timeout(acquire_timeout)
+            .connect_lazy(dsn)
+            .map_err(|e| anyhow!("SQL pool for {key}: {e}"))?;
+        match pools.insert(key.clone(), (dsn.to_owned(), pool.clone())) {
+            Some((_, stale)) => {
+                info!("[Sql] credentials for {key} changed — pool replaced (size={})", self.pool_size);
+                // Statements already running on the stale pool finish first
+                tokio::spawn(async move { stale.close().await });
+            }
+            None => info!("[Sql] pool created for {key} (size={})", self.pool_size),
+        }
+        Ok(pool)
+    }
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Merge a Vault secret into the endpoint DSN.
+pub fn with_credentials(dsn: &str, secret: &str) -> Result<String> {
+    if secret.contains("://") {
+        return Ok(secret.to_owned());
+    }
+    let mut url = reqwest::Url::parse(dsn).map_err(|e| anyhow!("invalid SQL DSN: {e}"))?;
+    url.set_password(Some(secret))
+        .map_err(|_| anyhow!("SQL DSN {} cannot carry a password", redact(dsn)))?;
+    Ok(url.to_string())
+}
+
+/// DSN without password or query — safe for logs and error strings.
+fn redact(dsn: &str) -> String {
+    match reqwest::Url::parse(dsn) {
+        Ok(mut u) => {
+            let _ = u.set_password(None);
+            u.set_query(None);
+            u.to_string()
+        }
+        Err(_) => "<invalid dsn>".to_owned(),
+    }
+}
+
+fn infer_mode(query: &str) -> &'static str {
+    let first = query.split_whitespace().next().unwrap_or("").to_uppercase();
+    let returns_rows = matches!(first.as_str(), "SELECT" | "WITH" | "SHOW" | "PRAGMA" | "VALUES" | "EXPLAIN")
+        || query.to_uppercase().contains(" RETURNING ");
+    if returns_rows { "query" } else { "execute" }
+}
+
+fn resolve_param(p: &Value, input: Option<&Value>) -> Value {
+    match p.as_str() {
+        Some(path) if path.starts_with("$.") => extract_dot_path(input.unwrap_or(&Value::Null), path),
+        _ => p.clone(),
+    }
+}
+
+fn row_to_json(row: &AnyRow) -> Result<Value> {
+    let mut obj = serde_json::Map::new();
+    for (i, col) in row.columns().iter().enumerate() {
+        let raw = row.try_get_raw(i)?;
+        let value = if raw.is_null() {
+            Value::Null
+        } else {
+            match raw.type_info().kind() {
+                AnyTypeInfoKind::Bool => Value::from(row.try_get::<bool, _>(i)?),
+                AnyTypeInfoKind::SmallInt
+                | AnyTypeInfoKind::Integer
+                | AnyTypeInfoKind::BigInt => Value::from(row.try_get::<i64, _>(i)?),
+                AnyTypeInfoKind::Real
+                | AnyTypeInfoKind::Double => Value::from(row.try_get::<f64, _>(i)?),
+                AnyTypeInfoKind::Blob => {
+                    use base64::Engine;
+                    let bytes: Vec<u8> = row.try_get(i)?;
+                    Value::String(base64::engine::general_purpose::STANDARD.encode(bytes))
+                }
+                AnyTypeInfoKind::Text | AnyTypeInfoKind::Null => {
+                    Value::String(row.try_get::<String, _>(i)?)
+                }
+            }
+        };
+        obj.insert(col.name().to_owned(), value);
+    }
+    Ok(Value::Object(obj))
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_credentials_and_redact() {
+        let dsn = with_credentials("postgres://svc@db:5432/plant", "p@ss").unwrap();
+        assert_eq!(dsn, "postgres://svc:p%40ss@db:5432/plant");
+        assert_eq!(redact(&dsn), "postgres://svc@db:5432/plant");
+        assert_eq!(redact("mysql://u:p@h/erp?password=p"), "mysql://u@h/erp");
+        assert_eq!(with_credentials("postgres://x@db/a", "mysql://u:p@h/b").unwrap(), "mysql://u:p@h/b");
+    }
+
+    #[test]
+    fn test_infer_mode() {
+        assert_eq!(infer_mode("  select 1"), "query");
+        assert_eq!(infer_mode("UPDATE t SET a = 1"), "execute");
+        assert_eq!(infer_mode("INSERT INTO t VALUES (1) RETURNING id"), "query");
+    }
+
+    #[tokio::test]
+    async fn test_rotated_credentials_replace_the_pool() {
+        let sql = SqlConnector::new(1);
+        let t = Duration::from_secs(5);
+        let old = sql.pool_for("postgres://svc:old@db/plant", t).await.unwrap();
+        assert!(!sql.pool_for("postgres://svc:old@db/plant", t).await.unwrap().is_closed());
+        let new = sql.pool_for("postgres://svc:new@db/plant", t).await.unwrap();
+        sql.pool_for("postgres://report:pw@db/plant", t).await.unwrap();
+        let pools = sql.pools.read().await;
+        assert_eq!(pools.len(), 2);
+        assert_eq!(pools["postgres://svc@db/plant"].0, "postgres://svc:new@db/plant");
+        drop(pools);
+        tokio::time::timeout(t, async {
+            while !old.is_closed() {
+                tokio::task::yield_now().await;
+            }
+        }).await.unwrap();
+        assert!(!new.is_closed());
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_roundtrip() {
+        let sql = SqlConnector::new(1);
+        let dsn = "sqlite::memory:";
+        let t = Duration::from_secs(5);
+        sql.run(dsn, &serde_json::json!({"query": "CREATE TABLE r (id INTEGER, v REAL, s TEXT)"}), None, t)
+            .await.unwrap();
+        let ins = serde_json::json!({
+            "query": "INSERT INTO r VALUES ($1, $2, $3)",
+            "params": [1, 2.5, "$.name"],
+        });
+        let out = sql.run(dsn, &ins, Some(&serde_json::json!({"name": "pump"})), t).await.unwrap();
+        assert_eq!(out["rowsAffected"], 1);
+
+        let rows = sql.run(dsn, &serde_json::json!({"query": "SELECT * FROM r"}), None, t).await.unwrap();
+        assert_eq!(rows[0]["id"], 1);
+        assert_eq!(rows[0]["v"], 2.5);
+        assert_eq!(rows[0]["s"], "pump");
+    }
+}
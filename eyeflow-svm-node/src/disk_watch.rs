@@ -0,0 +1,249 @@
+//! Disk watchdog — free space under the offline buffer and audit files
+//!
+//! A full disk used to surface only as a warn! from every failed persist.
+//! The watchdog samples free space on the filesystems holding the offline
+//! buffer, the audit log (SVM_AUDIT_LOG_PATH) and the Kafka buffer (when
+//! SVM_KAFKA_BROKERS is set):
+//!
+//!   SVM_DISK_PRESSURE_BYTES   free bytes below which a disk is under
+//!                             pressure (default 128 MiB, 0 = no watchdog);
+//!                             the pressure clears at 125% of it, so it does
+//!                             not flap around the threshold
+//!   SVM_DISK_CHECK_SECS       check interval (default 15)
+//!
+//! Under pressure:
+//!
+//!   – the offline buffers refuse low-priority events (Kafka mirror records,
+//!     trigger fires); audit events, execution results and alerts are kept
+//!   – /health reports DISK_PRESSURE (health_score.rs) and `status` is at
+//!     best "degraded"
+//!   – a DISK_PRESSURE alert goes out in an ALERT frame, and another with
+//!     `"cleared": true` once space is back
+//!
+//!   eyeflow_disk_pressure                     1 while under pressure
+//!   eyeflow_disk_free_bytes{path}             last sample per watched directory
+//!   eyeflow_disk_refused_events_total         enqueues refused under pressure
+
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::health::{HealthState, MetricsCollector};
+use crate::health_score::disk_free_bytes;
+use crate::offline::OfflineBuffer;
+
+/// Directory holding `file` ("." for a bare file name).
+fn dir_of(file: &str) -> PathBuf {
+    Path::new(file).parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf()
+}
+
+#[derive(Debug)]
+pub struct DiskWatch {
+    /// Watched directories with their last free-space sample
+    samples: Mutex<Vec<(PathBuf, Option<u64>)>>,
+    threshold: u64,
+    interval: Duration,
+    pressure: AtomicBool,
+    refused: AtomicU64,
+}
+
+impl DiskWatch {
+    pub fn new(dirs: Vec<PathBuf>, threshold: u64, interval: Duration) -> Arc<Self> {
+        let mut samples: Vec<(PathBuf, Option<u64>)> = Vec::new();
+        for dir in dirs {
+            if !samples.iter().any(|(d, _)| *d == dir) {
+                samples.push((dir, None));
+            }
+        }
+        Arc::new(Self {
+            samples: Mutex::new(samples),
+            threshold,
+            interval,
+            pressure: AtomicBool::new(false),
+            refused: AtomicU64::new(0),
+        })
+    }
+
+    /// None when SVM_DISK_PRESSURE_BYTES is 0.
+    pub fn from_config(config: &Config) -> Option<Arc<Self>> {
+        if config.disk_pressure_bytes == 0 {
+            return None;
+        }
+        let mut dirs = vec![dir_of(&config.offline_buffer_path)];
+        dirs.extend(config.audit_log_path.as_deref().map(dir_of));
+        if !config.kafka_brokers.is_empty() {
+            dirs.push(dir_of(&config.kafka_buffer_path));
+        }
+        Some(Self::new(dirs, config.disk_pressure_bytes, Duration::from_secs(config.disk_check_secs.max(1))))
+    }
+
+    pub fn under_pressure(&self) -> bool {
+        self.pressure.load(Ordering::Relaxed)
+    }
+
+    /// Count an enqueue refused because of the pressure.
+    pub fn record_refused(&self) {
+        self.refused.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Sample every watched directory.
+    fn sample(&self) {
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        for (dir, free) in samples.iter_mut() {
+            *free = disk_free_bytes(dir);
+        }
+    }
+
+    /// The watched directory with the least free space.
+    fn lowest(&self) -> Option<(PathBuf, u64)> {
+        self.samples.lock().unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter_map(|(dir, free)| free.map(|f| (dir.clone(), f)))
+            .min_by_key(|(_, f)| *f)
+    }
+
+    /// Update the pressure flag from the last samples; returns the alert
+    /// payload when it flips.
+    fn evaluate(&self, node_id: &str) -> Option<Value> {
+        let (dir, free) = self.lowest()?;
+        let was = self.under_pressure();
+        let now = if was { free < self.threshold / 4 * 5 } else { free < self.threshold };
+        if now == was {
+            return None;
+        }
+        self.pressure.store(now, Ordering::Relaxed);
+        if now {
+            warn!("[Disk] {} MiB free under {dir:?} — refusing low-priority buffering", free >> 20);
+        } else {
+            info!("[Disk] {} MiB free under {dir:?} — pressure cleared", free >> 20);
+        }
+        Some(json!({
+            "type": "DISK_PRESSURE",
+            "nodeId": node_id,
+            "path": dir,
+            "freeBytes": free,
+            "thresholdBytes": self.threshold,
+            "cleared": !now,
+        }))
+    }
+
+    /// /health reason while under pressure.
+    fn detail(&self) -> Option<String> {
+        if !self.under_pressure() {
+            return None;
+        }
+        let (dir, free) = self.lowest()?;
+        Some(format!(
+            "{} MiB free under {} (threshold {} MiB) — low-priority events refused",
+            free >> 20, dir.display(), self.threshold >> 20
+        ))
+    }
+}
+
+impl MetricsCollector for DiskWatch {
+    fn prometheus(&self, node_id: &str) -> String {
+        let mut free = String::new();
+        for (dir, bytes) in self.samples.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            if let Some(bytes) = bytes {
+                free.push_str(&format!(
+                    "eyeflow_disk_free_bytes{{node_id=\"{node_id}\",path=\"{}\"}} {bytes}\n",
+                    dir.display()
+                ));
+            }
+        }
+        format!(
+            "# HELP eyeflow_disk_pressure 1 while a buffer / audit disk is below SVM_DISK_PRESSURE_BYTES\n\
+             # TYPE eyeflow_disk_pressure gauge\n\
+             eyeflow_disk_pressure{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_disk_free_bytes Free bytes under a watched directory\n\
+             # TYPE eyeflow_disk_free_bytes gauge\n\
+             {free}\
+             # HELP eyeflow_disk_refused_events_total Low-priority events refused under disk pressure\n\
+             # TYPE eyeflow_disk_refused_events_total counter\n\
+             eyeflow_disk_refused_events_total{{node_id=\"{node_id}\"}} {}\n",
+            u8::from(self.under_pressure()),
+            self.refused.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// ── Watchdog task ─────────────────────────────────────────────────────────────
+
+/// Sample free space every interval; on a change of state update /health
+/// and queue a DISK_PRESSURE alert for the next ALERT frame.
+pub async fn run(
+    watch: Arc<DiskWatch>,
+    offline: Arc<tokio::sync::Mutex<OfflineBuffer>>,
+    health: Arc<HealthState>,
+    alerts_ready: Arc<Notify>,
+) {
+    let mut ticker = tokio::time::interval(watch.interval);
+    loop {
+        ticker.tick().await;
+        let w = watch.clone();
+        if tokio::task::spawn_blocking(move || w.sample()).await.is_err() {
+            continue;
+        }
+        let alert = watch.evaluate(&health.node_id);
+        health.set_disk_pressure(watch.detail());
+        if let Some(alert) = alert {
+            offline.lock().await.enqueue_alert(alert);
+            alerts_ready.notify_one();
+        }
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_free(watch: &DiskWatch, free: &[u64]) {
+        let mut samples = watch.samples.lock().unwrap();
+        for ((_, f), bytes) in samples.iter_mut().zip(free) {
+            *f = Some(*bytes);
+        }
+    }
+
+    #[test]
+    fn test_pressure_hysteresis_and_refusals() {
+        let watch = DiskWatch::new(
+            vec!["/var/buffer".into(), "/var/audit".into(), "/var/buffer".into()],
+            100,
+            Duration::from_secs(1),
+        );
+        set_free(&watch, &[500, 400]);
+        assert!(watch.evaluate("n").is_none() && watch.detail().is_none());
+
+        // The fullest disk counts
+        set_free(&watch, &[500, 90]);
+        let alert = watch.evaluate("n").unwrap();
+        assert_eq!((alert["path"].as_str(), alert["freeBytes"].as_u64()), (Some("/var/audit"), Some(90)));
+        assert_eq!(alert["cleared"], false);
+        assert!(watch.under_pressure() && watch.detail().unwrap().contains("/var/audit"));
+
+        // Back over the threshold but under 125%: still under pressure
+        set_free(&watch, &[500, 110]);
+        assert!(watch.evaluate("n").is_none() && watch.under_pressure());
+        set_free(&watch, &[500, 130]);
+        assert_eq!(watch.evaluate("n").unwrap()["cleared"], true);
+
+        // Refusals by the offline buffer
+        let mut buf = OfflineBuffer::new("/nonexistent/offline.ndjson", 10).with_disk_watch(Some(watch.clone()));
+        set_free(&watch, &[50, 130]);
+        watch.evaluate("n");
+        assert!(!buf.enqueue_kafka_record(json!({ "topic": "t" })));
+        buf.enqueue_execution_result(json!({ "status": "SUCCESS" }));
+        assert_eq!(buf.len(), 1);
+        let metrics = watch.prometheus("n");
+        assert!(metrics.contains("eyeflow_disk_pressure{node_id=\"n\"} 1"), "{metrics}");
+        assert!(metrics.contains("eyeflow_disk_refused_events_total{node_id=\"n\"} 1"));
+        assert!(metrics.contains("eyeflow_disk_free_bytes{node_id=\"n\",path=\"/var/buffer\"} 50"));
+    }
+}
@@ -0,0 +1,206 @@
+//! Distributed resource locks — spec §6.5 across nodes
+//!
+//! Per-process arbitration cannot stop two Linux nodes from driving the same
+//! Modbus gateway at once.  When SVM_DLOCK_REDIS_URLS is set, single-slot
+//! resources are additionally locked in Redis (Redlock over N independent
+//! instances, majority quorum):
+//!
+//!   SET eyeflow:lock:{resource} {token} NX PX {ttl}      — acquire
+//!   INCR eyeflow:fence:{resource}                         — fencing token
+//!   compare-and-PEXPIRE / compare-and-DEL (Lua)           — renew / release
+//!
+//! The lease is renewed every ttl/3 while held.  If renewal loses quorum the
+//! holder is told to yield — exactly like a preemption — so a node that was
+//! partitioned away stops touching the resource.  The fencing token (highest
+//! counter seen across the quorum) is exposed to the dispatch so downstream
+//! systems can reject writes from a stale holder.
+//!
+//! Each instance is a redis ConnectionManager (redis_store::RedisConn); the
+//! scripts run as EVALSHA.
+
+use anyhow::{anyhow, Result};
+use redis::Script;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+use crate::redis_store::RedisConn;
+
+const RENEW_SCRIPT: &str =
+    "if redis.call('get', KEYS[1]) == ARGV[1] then return redis.call('pexpire', KEYS[1], ARGV[2]) else return 0 end";
+const RELEASE_SCRIPT: &str =
+    "if redis.call('get', KEYS[1]) == ARGV[1] then return redis.call('del', KEYS[1]) else return 0 end";
+
+/// Clock drift allowance subtracted from the lease validity (Redlock §4).
+const DRIFT_FACTOR: f64 = 0.01;
+
+// ── Redlock ───────────────────────────────────────────────────────────────────
+
+pub struct DistributedLock {
+    instances: Vec<RedisConn>,
+    ttl: Duration,
+    renew: Script,
+    release: Script,
+}
+
+/// A held distributed lease; renewal stops and the lock is released on drop.
+pub struct Lease {
+    pub fencing_token: u64,
+    /// Flips to `true` when the lease could not be renewed on a quorum.
+    pub lost: watch::Receiver<bool>,
+    renew_task: tokio::task::JoinHandle<()>,
+    lock: Arc<DistributedLock>,
+    key: String,
+    token: String,
+}
+
+impl DistributedLock {
+    pub fn new(urls: &[String], ttl_ms: u64) -> Result<Arc<Self>> {
+        let op_timeout = Duration::from_millis(250);
+        let instances = urls.iter()
+            .map(|u| RedisConn::open(u, op_timeout))
+            .collect::<Result<Vec<_>>>()?;
+        if instances.is_empty() {
+            return Err(anyhow!("no Redis instances configured"));
+        }
+        let ttl = Duration::from_millis(ttl_ms.max(1_000));
+        info!("[DLock] Redlock over {} instance(s), ttl={}ms", instances.len(), ttl.as_millis());
+        Ok(Arc::new(Self { instances, ttl, renew: Script::new(RENEW_SCRIPT), release: Script::new(RELEASE_SCRIPT) }))
+    }
+
+    fn quorum(&self) -> usize {
+        self.instances.len() / 2 + 1
+    }
+
+    /// Try to acquire `resource` until `deadline`; `None` when it stays held elsewhere.
+    pub async fn acquire(self: &Arc<Self>, resource: &str, deadline: Instant) -> Result<Option<Lease>> {
+        let key = format!("eyeflow:lock:{resource}");
+        let fence_key = format!("eyeflow:fence:{resource}");
+        let token = uuid::Uuid::new_v4().to_string();
+        let ttl_ms = self.ttl.as_millis() as u64;
+
+        loop {
+            let start = Instant::now();
+            let mut acquired = 0usize;
+            let mut fence = 0u64;
+            for inst in &self.instances {
+                let set = inst.query::<Option<String>>(redis::cmd("SET").arg(&key).arg(&token).arg("NX").arg("PX").arg(ttl_ms)).await;
+                if matches!(set, Ok(Some(ref s)) if s == "OK") {
+                    acquired += 1;
+                    if let Ok(n) = inst.query::<u64>(redis::cmd("INCR").arg(&fence_key)).await {
+                        fence = fence.max(n);
+                    }
+                }
+            }
+
+            let drift = Duration::from_millis((self.ttl.as_millis() as f64 * DRIFT_FACTOR) as u64 + 2);
+            let validity = self.ttl.checked_sub(start.elapsed() + drift);
+            if acquired >= self.quorum() && validity.is_some() {
+                debug!("[DLock] '{resource}' acquired (fence={fence}, {acquired}/{})", self.instances.len());
+                return Ok(Some(self.clone().start_lease(key, token, fence)));
+            }
+
+            // Failed — undo partial acquisition before retrying
+            self.release_all(&key, &token).await;
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            let backoff = Duration::from_millis(20 + rand::random::<u64>() % 80);
+            tokio::time::sleep(backoff.min(deadline.saturating_duration_since(Instant::now()))).await;
+        }
+    }
+
+    fn start_lease(self: Arc<Self>, key: String, token: String, fencing_token: u64) -> Lease {
+        let (lost_tx, lost) = watch::channel(false);
+        let lock = self.clone();
+        let (k, t) = (key.clone(), token.clone());
+        let renew_task = tokio::spawn(async move {
+            let ttl_ms = lock.ttl.as_millis() as u64;
+            let mut renew = lock.renew.key(&k);
+            renew.arg(&t).arg(ttl_ms);
+            let mut tick = tokio::time::interval(lock.ttl / 3);
+            tick.tick().await;
+            loop {
+                tick.tick().await;
+                let mut renewed = 0usize;
+                for inst in &lock.instances {
+                    if matches!(inst.invoke::<i64>(&renew).await, Ok(1)) {
+                        renewed += 1;
+                    }
+                }
+                if renewed < lock.quorum() {
+                    warn!("[DLock] lease on {k} lost ({renewed}/{} renewed) — yielding", lock.instances.len());
+                    let _ = lost_tx.send(true);
+                    return;
+                }
+            }
+        });
+        Lease { fencing_token, lost, renew_task, lock: self, key, token }
+    }
+
+    async fn release_all(&self, key: &str, token: &str) {
+        let mut release = self.release.key(key);
+        release.arg(token);
+        for inst in &self.instances {
+            let _ = inst.invoke::<i64>(&release).await;
+        }
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        self.renew_task.abort();
+        let (lock, key, token) = (self.lock.clone(), self.key.clone(), self.token.clone());
+        if let Ok(rt) = tokio::runtime::Handle::try_current() {
+            rt.spawn(async move { lock.release_all(&key, &token).await });
+        }
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redis_store::test_support::fake_redis;
+
+    #[tokio::test]
+    async fn test_quorum_and_fencing_token() {
+        // Two of three instances grant the lock; the fencing token is the
+        // highest counter among them
+        let (a, _) = fake_redis(|cmd| match cmd[0].as_str() {
+            "INCR" => b":7\r\n",
+            "EVALSHA" => b":1\r\n",
+            _ => b"+OK\r\n",
+        }).await;
+        let (b, _) = fake_redis(|cmd| match cmd[0].as_str() {
+            "INCR" => b":9\r\n",
+            "EVALSHA" => b":1\r\n",
+            _ => b"+OK\r\n",
+        }).await;
+        let held = |cmd: &[String]| -> &'static [u8] {
+            match cmd[0].as_str() {
+                "SET" => b"$-1\r\n",
+                "EVALSHA" => b":0\r\n",
+                _ => b"+OK\r\n",
+            }
+        };
+        let (c, _) = fake_redis(held).await;
+        let lock = DistributedLock::new(&[a, b, c], 3_000).unwrap();
+        let lease = lock.acquire("modbus:gw1", Instant::now() + Duration::from_secs(2)).await.unwrap().unwrap();
+        assert_eq!(lease.fencing_token, 9);
+        assert!(!*lease.lost.borrow());
+
+        // One grant out of three is no quorum
+        let (a, _) = fake_redis(|cmd| match cmd[0].as_str() {
+            "INCR" => b":3\r\n",
+            "EVALSHA" => b":1\r\n",
+            _ => b"+OK\r\n",
+        }).await;
+        let (b, _) = fake_redis(held).await;
+        let (c, _) = fake_redis(held).await;
+        let lock = DistributedLock::new(&[a, b, c], 3_000).unwrap();
+        assert!(lock.acquire("modbus:gw1", Instant::now() + Duration::from_millis(200)).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,238 @@
+//! Host resolution — static hosts table and DNS cache
+//!
+//! Industrial networks often have no DNS for OT devices.  Hostnames in
+//! dispatch_metadata endpoints and CENTRAL_WS_URL resolve through:
+//!
+//!   static table   ahead of DNS
+//!                  SVM_DNS_HOSTS       plc-1=10.0.0.5,historian=10.0.0.7|10.0.0.8
+//!                  SVM_DNS_HOSTS_FILE  hosts(5) format ("10.0.0.5  plc-1 plc-1.line3")
+//!   system DNS     answers kept SVM_DNS_CACHE_TTL_SECS (default 0 = not
+//!                  cached) whatever the record TTL says; a failed lookup
+//!                  falls back to the last answer, however old
+//!
+//! Names match case-insensitively, without a trailing dot.  The resolver is
+//! installed in the SVM's HTTP client (CALL_SERVICE, LOAD_RESOURCE, vault,
+//! fallback) and used for the central WebSocket; TLS still verifies the
+//! hostname, not the address.
+//!
+//! /metrics: eyeflow_dns_static_hosts, eyeflow_dns_cache_total{result}.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::config::Config;
+use crate::health::MetricsCollector;
+
+#[derive(Default)]
+struct Stats {
+    static_hits: AtomicU64,
+    cache_hits: AtomicU64,
+    lookups: AtomicU64,
+    stale: AtomicU64,
+    failures: AtomicU64,
+}
+
+type Cache = HashMap<String, (Vec<IpAddr>, Instant)>;
+
+/// Cheap to clone; clones share the table, cache and counters.
+#[derive(Clone)]
+pub struct Resolver {
+    hosts: Arc<HashMap<String, Vec<IpAddr>>>,
+    ttl: Duration,
+    cache: Arc<Mutex<Cache>>,
+    stats: Arc<Stats>,
+}
+
+fn normalize(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// `name=ip[|ip…]` entries.
+fn parse_entries(entries: &[String], hosts: &mut HashMap<String, Vec<IpAddr>>) {
+    for entry in entries {
+        let parsed = entry.split_once('=').and_then(|(name, ips)| {
+            let ips: Option<Vec<IpAddr>> = ips.split('|').map(|ip| ip.trim().parse().ok()).collect();
+            Some((normalize(name.trim()), ips?))
+        });
+        match parsed {
+            Some((name, ips)) if !name.is_empty() => {
+                hosts.insert(name, ips);
+            }
+            _ => warn!("[DNS] ignoring SVM_DNS_HOSTS entry '{entry}' (expected name=ip[|ip…])"),
+        }
+    }
+}
+
+/// hosts(5) lines; later lines add addresses to a name.
+fn parse_hosts_file(text: &str, hosts: &mut HashMap<String, Vec<IpAddr>>) {
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let Some(ip) = fields.next() else { continue };
+        let Ok(ip) = ip.parse::<IpAddr>() else {
+            warn!("[DNS] ignoring hosts line '{}'", line.trim());
+            continue;
+        };
+        for name in fields {
+            let ips = hosts.entry(normalize(name)).or_default();
+            if !ips.contains(&ip) {
+                ips.push(ip);
+            }
+        }
+    }
+}
+
+impl Resolver {
+    pub fn new(hosts: HashMap<String, Vec<IpAddr>>, ttl: Duration) -> Self {
+        Self {
+            hosts: Arc::new(hosts),
+            ttl,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+            stats: Arc::new(Stats::default()),
+        }
+    }
+
+    /// SVM_DNS_HOSTS, SVM_DNS_HOSTS_FILE and SVM_DNS_CACHE_TTL_SECS;
+    /// malformed entries are logged and skipped.
+    pub fn from_config(config: &Config) -> Self {
+        let mut hosts = HashMap::new();
+        if let Some(path) = &config.dns_hosts_file {
+            match std::fs::read_to_string(path) {
+                Ok(text) => parse_hosts_file(&text, &mut hosts),
+                Err(e) => warn!("[DNS] SVM_DNS_HOSTS_FILE {path}: {e}"),
+            }
+        }
+        parse_entries(&config.dns_hosts, &mut hosts);
+        Self::new(hosts, Duration::from_secs(config.dns_cache_ttl_secs))
+    }
+
+    /// Addresses of `host` (a name or a literal address).
+    pub async fn resolve_ips(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(vec![ip]);
+        }
+        let name = normalize(host);
+        if let Some(ips) = self.hosts.get(&name) {
+            self.stats.static_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(ips.clone());
+        }
+        if !self.ttl.is_zero() {
+            let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((ips, _)) = cache.get(&name).filter(|(_, at)| at.elapsed() < self.ttl) {
+                self.stats.cache_hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(ips.clone());
+            }
+        }
+        self.stats.lookups.fetch_add(1, Ordering::Relaxed);
+        let answer = tokio::net::lookup_host((name.as_str(), 0)).await
+            .map(|addrs| addrs.map(|a| a.ip()).collect::<Vec<_>>());
+        match answer {
+            Ok(ips) => {
+                if !self.ttl.is_zero() && !ips.is_empty() {
+                    self.cache.lock().unwrap_or_else(|e| e.into_inner()).insert(name, (ips.clone(), Instant::now()));
+                }
+                Ok(ips)
+            }
+            Err(e) => {
+                let stale = self.cache.lock().unwrap_or_else(|e| e.into_inner()).get(&name).map(|(ips, _)| ips.clone());
+                match stale {
+                    Some(ips) => {
+                        warn!("[DNS] lookup of {name} failed ({e}) — using the last answer");
+                        self.stats.stale.fetch_add(1, Ordering::Relaxed);
+                        Ok(ips)
+                    }
+                    None => {
+                        self.stats.failures.fetch_add(1, Ordering::Relaxed);
+                        Err(e)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Socket addresses of `host:port`.
+    pub async fn lookup(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        Ok(self.resolve_ips(host).await?.into_iter().map(|ip| SocketAddr::new(ip, port)).collect())
+    }
+}
+
+impl reqwest::dns::Resolve for Resolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let this = self.clone();
+        Box::pin(async move {
+            let addrs = this.lookup(name.as_str(), 0).await?;
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+impl MetricsCollector for Resolver {
+    fn prometheus(&self, node_id: &str) -> String {
+        let s = &self.stats;
+        let mut out = format!(
+            "# HELP eyeflow_dns_static_hosts Names in the static hosts table\n\
+             # TYPE eyeflow_dns_static_hosts gauge\n\
+             eyeflow_dns_static_hosts{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_dns_cache_total Name resolutions by how they were answered\n\
+             # TYPE eyeflow_dns_cache_total counter\n",
+            self.hosts.len(),
+        );
+        for (result, counter) in [
+            ("static", &s.static_hits),
+            ("hit", &s.cache_hits),
+            ("lookup", &s.lookups),
+            ("stale", &s.stale),
+            ("failed", &s.failures),
+        ] {
+            out.push_str(&format!(
+                "eyeflow_dns_cache_total{{node_id=\"{node_id}\",result=\"{result}\"}} {}\n",
+                counter.load(Ordering::Relaxed),
+            ));
+        }
+        out
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_static_table_routes_http() {
+        let mut hosts = HashMap::new();
+        parse_hosts_file("# OT cell 3\n127.0.0.1  plc-1.line3 historian  # local\nnot-an-ip x\n", &mut hosts);
+        parse_entries(&["PLC-2.=127.0.0.1|::1".into(), "broken".into()], &mut hosts);
+        assert_eq!(hosts.len(), 3);
+        assert_eq!(hosts["plc-2"].len(), 2);
+
+        let resolver = Resolver::new(hosts, Duration::ZERO);
+        assert_eq!(resolver.lookup("Plc-1.Line3", 502).await.unwrap(), ["127.0.0.1:502".parse().unwrap()]);
+        assert_eq!(resolver.resolve_ips("[::1]").await.unwrap(), ["::1".parse::<IpAddr>().unwrap()]);
+
+        // An HTTP dispatch to the table name reaches the local listener
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let n = sock.read(&mut buf).await.unwrap();
+            let host = String::from_utf8_lossy(&buf[..n]).lines().find(|l| l.to_ascii_lowercase().starts_with("host:")).unwrap_or_default().to_owned();
+            let body = host.trim_start_matches(|c: char| c != ' ').trim().to_owned();
+            let answer = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len());
+            sock.write_all(answer.as_bytes()).await.unwrap();
+        });
+        let http = reqwest::Client::builder().dns_resolver(Arc::new(resolver.clone())).build().unwrap();
+        let body = http.get(format!("http://historian:{port}/tags")).send().await.unwrap().text().await.unwrap();
+        assert_eq!(body, format!("historian:{port}"));
+        assert!(resolver.prometheus("n").contains("eyeflow_dns_cache_total{node_id=\"n\",result=\"static\"} 2"));
+    }
+}
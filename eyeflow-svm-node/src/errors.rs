@@ -0,0 +1,231 @@
+//! Structured execution errors — machine-readable failure taxonomy
+//!
+//! `SliceExecutionResult.error` used to be free text.  Failures now carry an
+//! `ErrorCode` and the index of the failing instruction:
+//!
+//!   NETWORK           connection refused / reset / DNS
+//!   TIMEOUT           request or resource wait exceeded its deadline
+//!   AUTH              401 / 403, missing or rejected credentials
+//!   VALIDATION        malformed IR, operands or response payloads
+//!   QUOTA             429, tenant quota, decode limits
+//!   VERSION_MISMATCH  IR major version the node cannot run
+//!   UNSUPPORTED       opcode / format / connector not available here
+//!   NOT_FOUND         404, missing file / object / secret
+//!   UPSTREAM          other non-2xx answer from a dependency
+//!   CANCELLED         execution aborted before completion
+//!   MEMORY_LIMIT      register file exceeded SVM_REGISTER_MEMORY_MAX_BYTES
+//!   VAULT_UNAVAILABLE Vault is down and the instruction needs a secret from it
+//!   INTERNAL          anything else
+//!
+//! Opcode handlers raise `ExecError` where they know the cause; everything
+//! else is classified from the error chain (reqwest, io, serde, timeouts).
+//! The code survives FallbackEngine (RETRY_WITH_BACKOFF only retries
+//! transient codes) and is reported in the result and the audit trail.
+//!
+//! A TRY handler catches only recoverable errors: MEMORY_LIMIT, CANCELLED
+//! and errors raised `fatal` (security refusals, exceeded CPU budgets) end
+//! the slice through any open TRY scope.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    Network,
+    Timeout,
+    Auth,
+    Validation,
+    Quota,
+    VersionMismatch,
+    Unsupported,
+    NotFound,
+    Upstream,
+    Cancelled,
+    MemoryLimit,
+    VaultUnavailable,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Network         => "NETWORK",
+            Self::Timeout         => "TIMEOUT",
+            Self::Auth            => "AUTH",
+            Self::Validation      => "VALIDATION",
+            Self::Quota           => "QUOTA",
+            Self::VersionMismatch => "VERSION_MISMATCH",
+            Self::Unsupported     => "UNSUPPORTED",
+            Self::NotFound        => "NOT_FOUND",
+            Self::Upstream        => "UPSTREAM",
+            Self::Cancelled       => "CANCELLED",
+            Self::MemoryLimit     => "MEMORY_LIMIT",
+            Self::VaultUnavailable => "VAULT_UNAVAILABLE",
+            Self::Internal        => "INTERNAL",
+        }
+    }
+
+    /// Whether retrying the same request can succeed.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Network | Self::Timeout | Self::Quota | Self::Upstream | Self::VaultUnavailable)
+    }
+
+    pub fn from_status(status: reqwest::StatusCode) -> Self {
+        match status.as_u16() {
+            401 | 403 => Self::Auth,
+            404 | 410 => Self::NotFound,
+            408 | 504 => Self::Timeout,
+            429 => Self::Quota,
+            400 | 422 => Self::Validation,
+            _ => Self::Upstream,
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// ── ExecError ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecError {
+    pub code: ErrorCode,
+    /// Failing instruction index (None = slice-level)
+    pub instruction: Option<i32>,
+    pub message: String,
+    /// Ends the slice even inside a TRY scope
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fatal: bool,
+}
+
+impl ExecError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self { code, instruction: None, message: message.into(), fatal: false }
+    }
+
+    /// Mark as not catchable by TRY (a policy refusal, not a failure).
+    pub fn fatal(mut self) -> Self {
+        self.fatal = true;
+        self
+    }
+
+    /// Whether a TRY handler may catch this error.
+    pub fn catchable(&self) -> bool {
+        !self.fatal && !matches!(self.code, ErrorCode::MemoryLimit | ErrorCode::Cancelled)
+    }
+
+    /// Malformed IR or operands.
+    pub fn validation(message: impl Into<String>) -> anyhow::Error {
+        Self::new(ErrorCode::Validation, message).into()
+    }
+
+    /// Non-2xx answer from `what` (e.g. "CALL_SERVICE http://…").
+    pub fn http(what: impl fmt::Display, status: reqwest::StatusCode) -> anyhow::Error {
+        Self::new(ErrorCode::from_status(status), format!("{what} → HTTP {status}")).into()
+    }
+
+    /// Typed view of any error, attributed to `instruction` if it has no index yet
+    /// (secrets resolved by the running slice are scrubbed from the message).
+    pub fn from_anyhow(err: &anyhow::Error, instruction: Option<i32>) -> Self {
+        match err.chain().find_map(|e| e.downcast_ref::<ExecError>()) {
+            Some(e) => Self {
+                code: e.code,
+                instruction: e.instruction.or(instruction),
+                message: crate::taint::scrub(&format!("{err:#}")),
+                fatal: e.fatal,
+            },
+            None => Self {
+                code: classify(err),
+                instruction,
+                message: crate::taint::scrub(&format!("{err:#}")),
+                fatal: false,
+            },
+        }
+    }
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ExecError {}
+
+/// Best-effort code for an untyped error, from the first recognised cause.
+pub fn classify(err: &anyhow::Error) -> ErrorCode {
+    for cause in err.chain() {
+        if let Some(e) = cause.downcast_ref::<ExecError>() {
+            return e.code;
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return if e.is_timeout() {
+                ErrorCode::Timeout
+            } else if let Some(status) = e.status() {
+                ErrorCode::from_status(status)
+            } else if e.is_decode() || e.is_body() {
+                ErrorCode::Validation
+            } else {
+                ErrorCode::Network
+            };
+        }
+        if cause.downcast_ref::<tokio::time::error::Elapsed>().is_some() {
+            return ErrorCode::Timeout;
+        }
+        if cause.downcast_ref::<crate::ir_limits::LimitViolation>().is_some() {
+            return ErrorCode::Quota;
+        }
+        if cause.downcast_ref::<serde_json::Error>().is_some() {
+            return ErrorCode::Validation;
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            use std::io::ErrorKind::*;
+            return match e.kind() {
+                NotFound => ErrorCode::NotFound,
+                PermissionDenied => ErrorCode::Auth,
+                TimedOut => ErrorCode::Timeout,
+                ConnectionRefused | ConnectionReset | ConnectionAborted | NotConnected => ErrorCode::Network,
+                _ => ErrorCode::Internal,
+            };
+        }
+    }
+    ErrorCode::Internal
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_classify() {
+        let typed = ExecError::http("CALL_SERVICE http://x", reqwest::StatusCode::UNAUTHORIZED);
+        assert_eq!(classify(&typed), ErrorCode::Auth);
+        // Context layers don't hide the typed cause
+        let wrapped = Err::<(), _>(typed).context("retry exhausted").unwrap_err();
+        let e = ExecError::from_anyhow(&wrapped, Some(7));
+        assert_eq!((e.code, e.instruction), (ErrorCode::Auth, Some(7)));
+        assert!(e.message.starts_with("retry exhausted: CALL_SERVICE"));
+
+        let io = anyhow::Error::new(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(classify(&io), ErrorCode::NotFound);
+        assert_eq!(classify(&anyhow::anyhow!("boom")), ErrorCode::Internal);
+        assert_eq!(ErrorCode::from_status(reqwest::StatusCode::BAD_GATEWAY), ErrorCode::Upstream);
+        assert!(ErrorCode::Upstream.is_transient() && !ErrorCode::Auth.is_transient());
+
+        // TRY catches failures, not aborts or refusals
+        assert!(ExecError::new(ErrorCode::Auth, "401").catchable());
+        assert!(!ExecError::new(ErrorCode::Auth, "denied").fatal().catchable());
+        assert!(!ExecError::new(ErrorCode::MemoryLimit, "full").catchable());
+        let fatal = anyhow::Error::new(ExecError::new(ErrorCode::Timeout, "budget").fatal());
+        assert!(!ExecError::from_anyhow(&fatal, None).catchable());
+
+    }
+}
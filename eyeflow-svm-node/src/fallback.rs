@@ -1,42 +1,54 @@
-/// FallbackEngine — spec §6.4
-///
-/// Five resilience strategies for handling instruction-level failures in the
-/// Rust SVM node.  The strategy is compiled into the LLM-IR at build time
-/// (field `fallback_strategy` in `IrInstruction.operands_json`) and applied at
-/// runtime by the SVM when an opcode handler returns an error.
-///
-/// Strategy matrix (spec §6.4):
-/// ┌─────────────────────────────┬────────────────────────────────────────────┐
-/// │ Strategy                    │ Behaviour                                  │
-/// ├─────────────────────────────┼────────────────────────────────────────────┤
-/// │ FAIL_SAFE                   │ Return a pre-defined safe default value;   │
-/// │                             │ continue pipeline execution                │
-/// │ DEGRADED_MODE               │ Skip the failed instruction; emit a WARN   │
-/// │                             │ audit event; continue with null register   │
-/// │ RETRY_WITH_BACKOFF          │ Retry up to `max_attempts` times with      │
-/// │                             │ exponential back-off (base 2s)             │
-/// │ LLM_REASONING               │ Forward the failure to the central LLM     │
-/// │                             │ service for dynamic re-planning (max 3     │
-/// │                             │ attempts); fall back to FAIL_SAFE if all   │
-/// │                             │ attempts fail                              │
-/// │ SUPERVISED_RECOMPILE        │ Notify central that the IR slice needs     │
-/// │                             │ recompilation; return FAIL_SAFE output     │
-/// │                             │ while human supervisor reviews the DAG     │
-/// └─────────────────────────────┴────────────────────────────────────────────┘
+//! FallbackEngine — spec §6.4
+//!
+//! Five resilience strategies for handling instruction-level failures in the
+//! Rust SVM node.  The strategy is compiled into the LLM-IR at build time
+//! (field `fallback_strategy` in `IrInstruction.operands_json`) and applied at
+//! runtime by the SVM when an opcode handler returns an error.
+//!
+//! Strategy matrix (spec §6.4):
+//! ┌─────────────────────────────┬────────────────────────────────────────────┐
+//! │ Strategy                    │ Behaviour                                  │
+//! ├─────────────────────────────┼────────────────────────────────────────────┤
+//! │ FAIL_SAFE                   │ Return a pre-defined safe default value;   │
+//! │                             │ continue pipeline execution                │
+//! │ DEGRADED_MODE               │ Skip the failed instruction; emit a WARN   │
+//! │                             │ audit event; continue with null register   │
+//! │ RETRY_WITH_BACKOFF          │ Retry up to `max_attempts` times with      │
+//! │                             │ exponential back-off (base 2s)             │
+//! │ LLM_REASONING               │ Forward the failure to the central LLM     │
+//! │                             │ service for dynamic re-planning (max 3     │
+//! │                             │ attempts); fall back to FAIL_SAFE if all   │
+//! │                             │ attempts fail                              │
+//! │ SUPERVISED_RECOMPILE        │ Notify central that the IR slice needs     │
+//! │                             │ recompilation; return FAIL_SAFE output     │
+//! │                             │ while human supervisor reviews the DAG,    │
+//! │                             │ then page on-site staff (`alert`)          │
+//! └─────────────────────────────┴────────────────────────────────────────────┘
+//!
+//! Every RETRY_WITH_BACKOFF attempt is recorded — attempt, delayMs before it,
+//! errorCode (null for the one that succeeded) and inputHash (SHA-256 of the
+//! input JSON, as the audit event's inputHash) — and attached to the
+//! instruction's audit event as `details.attempts`, or to a RETRY_EXHAUSTED
+//! event when every attempt failed.  A first attempt that succeeds is not
+//! recorded.
 
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 
 // ── Strategy enum ─────────────────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum FallbackStrategy {
     /// Return a pre-defined safe default; pipeline continues (spec §6.4)
+    #[default]
     FailSafe,
     /// Skip instruction; emit WARN; pipeline continues with null (spec §6.4)
     DegradedMode,
@@ -48,12 +60,6 @@ pub enum FallbackStrategy {
     SupervisedRecompile,
 }
 
-impl Default for FallbackStrategy {
-    fn default() -> Self {
-        Self::FailSafe
-    }
-}
-
 impl std::fmt::Display for FallbackStrategy {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -97,11 +103,44 @@ pub struct InstructionFallbackConfig {
     /// Base back-off in ms for RETRY_WITH_BACKOFF (default: 2000)
     #[serde(default = "default_backoff_base_ms")]
     pub backoff_base_ms: u64,
+    /// sms:// or voice:// URL paged by SUPERVISED_RECOMPILE (default: SVM_ALERT_URL)
+    pub alert: Option<String>,
+    /// Vault path of the alert provider credentials (default: SVM_ALERT_VAULT_PATH)
+    pub alert_vault_path: Option<String>,
 }
 
 fn default_max_attempts() -> u32 { 3 }
 fn default_backoff_base_ms() -> u64 { 2_000 }
 
+// ── Retry attempts ────────────────────────────────────────────────────────────
+
+tokio::task_local! {
+    /// RETRY_WITH_BACKOFF attempts made by the dispatch being audited.
+    static ATTEMPTS: Arc<Mutex<Vec<Value>>>;
+}
+
+/// Run `dispatch`, returning its output and its retry attempts (None when
+/// it did not retry).
+pub async fn tracked_attempts<F: Future>(dispatch: F) -> (F::Output, Option<Value>) {
+    let attempts = Arc::new(Mutex::new(Vec::new()));
+    let out = ATTEMPTS.scope(attempts.clone(), dispatch).await;
+    let attempts = std::mem::take(&mut *attempts.lock().unwrap_or_else(|e| e.into_inner()));
+    let retried = attempts.len() > 1 || attempts.first().is_some_and(|a| !a["errorCode"].is_null());
+    (out, retried.then_some(Value::Array(attempts)))
+}
+
+/// Record one attempt; `error` is None for the attempt that succeeded.
+pub fn record_attempt(attempt: usize, delay_ms: u64, error: Option<crate::errors::ErrorCode>, input: Option<&Value>) {
+    let input = serde_json::to_string(&input.unwrap_or(&Value::Null)).unwrap_or_else(|_| "null".to_owned());
+    let entry = json!({
+        "attempt": attempt,
+        "delayMs": delay_ms,
+        "errorCode": error,
+        "inputHash": hex::encode(Sha256::digest(input.as_bytes())),
+    });
+    let _ = ATTEMPTS.try_with(|a| a.lock().unwrap_or_else(|e| e.into_inner()).push(entry));
+}
+
 // ── FallbackEngine ────────────────────────────────────────────────────────────
 
 pub struct FallbackEngine {
@@ -154,7 +193,7 @@ impl FallbackEngine {
     /// * `workflow_id`– for logging / LLM_REASONING context
     /// * `service_id` – for SUPERVISED_RECOMPILE notification
     /// * `execute`    – async closure that re-runs the failed operation
-    ///                  (used by RETRY_WITH_BACKOFF and LLM_REASONING)
+    ///   (used by RETRY_WITH_BACKOFF and LLM_REASONING)
     pub async fn apply<F, Fut>(
         &self,
         strategy: FallbackStrategy,
@@ -168,8 +207,10 @@ impl FallbackEngine {
         F: Fn() -> Fut,
         Fut: std::future::Future<Output = Result<Value>>,
     {
+        let code = crate::errors::classify(&error);
         info!(
-            "[Fallback] applying strategy={strategy} for service={service_id} error=\"{error}\""
+            "[Fallback] applying strategy={strategy} for service={service_id} \
+             code={code} error=\"{error}\""
         );
 
         match strategy {
@@ -193,6 +234,11 @@ impl FallbackEngine {
             }
 
             // ── RETRY_WITH_BACKOFF ───────────────────────────────────────────
+            // Permanent failures (AUTH, VALIDATION, …) are not retried.
+            FallbackStrategy::RetryWithBackoff if !code.is_transient() => {
+                warn!("[Fallback] RETRY_WITH_BACKOFF: {code} is not transient — not retrying service={service_id}");
+                FallbackResult::Abort(error)
+            }
             FallbackStrategy::RetryWithBackoff => {
                 let max = cfg.max_attempts.max(1) as usize;
                 let base_ms = cfg.backoff_base_ms;
@@ -214,10 +260,10 @@ impl FallbackEngine {
                             return FallbackResult::Recovered(v);
                         }
                         Err(e) => {
-                            if attempt == max {
+                            if attempt == max || !crate::errors::classify(&e).is_transient() {
                                 warn!(
-                                    "[Fallback] RETRY_WITH_BACKOFF exhausted ({max} attempts) \
-                                     for service={service_id}: {e}"
+                                    "[Fallback] RETRY_WITH_BACKOFF gave up after {attempt}/{max} \
+                                     attempt(s) for service={service_id}: {e}"
                                 );
                                 return FallbackResult::Abort(e);
                             }
@@ -239,6 +285,7 @@ impl FallbackEngine {
                         "workflowId": workflow_id,
                         "serviceId":  service_id,
                         "error":      error.to_string(),
+                        "errorCode":  code,
                         "attempt":    attempt,
                         "nodeId":     self.node_id,
                     });
@@ -302,6 +349,7 @@ impl FallbackEngine {
                     "workflowId": workflow_id,
                     "serviceId":  service_id,
                     "error":      error.to_string(),
+                    "errorCode":  code,
                     "nodeId":     self.node_id,
                     "requestedAt": chrono::Utc::now().to_rfc3339(),
                 });
@@ -394,4 +442,22 @@ mod tests {
         assert_eq!(cfg.max_attempts, 5);
         assert_eq!(cfg.backoff_base_ms, 1000);
     }
+
+    #[tokio::test]
+    async fn test_attempts_are_tracked_only_on_retry() {
+        use crate::errors::ErrorCode;
+        let (_, none) = tracked_attempts(async { record_attempt(1, 0, None, None) }).await;
+        assert!(none.is_none());
+
+        let input = json!({ "id": 7 });
+        let (_, attempts) = tracked_attempts(async {
+            record_attempt(1, 0, Some(ErrorCode::Network), Some(&input));
+            record_attempt(2, 100, None, Some(&input));
+        }).await;
+        let attempts = attempts.unwrap();
+        assert_eq!((attempts[0]["errorCode"].as_str(), attempts[1]["delayMs"].as_u64()), (Some("NETWORK"), Some(100)));
+        assert_eq!(attempts[0]["inputHash"], attempts[1]["inputHash"]);
+        // Outside a tracked dispatch nothing is recorded
+        record_attempt(1, 0, None, None);
+    }
 }
@@ -0,0 +1,146 @@
+//! Large-payload JSON parsing — simd-json behind the `simd` feature
+//!
+//! CALL_SERVICE / LOAD_RESOURCE response bodies (cached or not) and offline
+//! buffer lines are parsed by `from_slice`.  In a build with
+//! `--features simd`, payloads of SIMD_MIN_BYTES or more go through
+//! simd-json, which picks its implementation at run time — AVX2 or SSE4.2 on
+//! x86-64, NEON on ARM64, a portable one elsewhere — so a single binary
+//! runs on every gateway of a fleet.  A payload simd-json refuses is handed
+//! to serde_json, which has the last word (and the error message).  Smaller
+//! payloads, and builds without the feature, use serde_json directly.
+//!
+//!   eyeflow-svm-node bench-json [--size-kib <n>] [--iterations <n>]
+//!
+//! times both parsers on a synthetic telemetry payload (default 1024 KiB,
+//! 50 iterations) and prints their throughput — run it on the target
+//! hardware before enabling the feature for a fleet.
+
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use std::time::Instant;
+
+/// Below this size the SIMD setup costs more than it saves.
+#[cfg_attr(not(any(feature = "simd", test)), allow(dead_code))]
+pub const SIMD_MIN_BYTES: usize = 4 * 1024;
+
+/// Parse `bytes` as JSON (simd-json for large payloads when available).
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> serde_json::Result<T> {
+    #[cfg(feature = "simd")]
+    if bytes.len() >= SIMD_MIN_BYTES {
+        // simd-json parses in place
+        let mut scratch = bytes.to_vec();
+        if let Ok(value) = simd_json::serde::from_slice(&mut scratch) {
+            return Ok(value);
+        }
+    }
+    serde_json::from_slice(bytes)
+}
+
+/// JSON body of an HTTP response (Null when unreadable or not JSON).
+pub async fn response_body(resp: reqwest::Response) -> serde_json::Value {
+    match resp.bytes().await {
+        Ok(bytes) => from_slice(&bytes).unwrap_or(serde_json::Value::Null),
+        Err(_) => serde_json::Value::Null,
+    }
+}
+
+/// Parser behind `from_slice` for a payload of `len` bytes.
+#[cfg(test)]
+pub fn parser_for(len: usize) -> &'static str {
+    if cfg!(feature = "simd") && len >= SIMD_MIN_BYTES { "simd-json" } else { "serde_json" }
+}
+
+// ── bench-json ────────────────────────────────────────────────────────────────
+
+const USAGE: &str = "usage: eyeflow-svm-node bench-json [--size-kib <n>] [--iterations <n>]";
+
+/// Readings shaped like a gateway's CALL_SERVICE answers, about `bytes` long.
+fn telemetry_payload(bytes: usize) -> Vec<u8> {
+    let mut out = String::from("{\"gateway\":\"gw-07\",\"readings\":[");
+    let mut n = 0u64;
+    while out.len() < bytes {
+        if n > 0 {
+            out.push(',');
+        }
+        out.push_str(&format!(
+            "{{\"sensor\":\"line-{}/temp-{}\",\"ts\":\"2026-10-16T08:{:02}:{:02}.{:03}Z\",\
+             \"value\":{}.{:03},\"unit\":\"°C\",\"quality\":{},\"tags\":[\"hall-b\",\"zone-{}\"]}}",
+            n % 12, n % 97, (n / 60) % 60, n % 60, n % 1000,
+            15 + n % 40, (n * 37) % 1000, !n.is_multiple_of(3), n % 5,
+        ));
+        n += 1;
+    }
+    out.push_str("]}");
+    out.into_bytes()
+}
+
+fn throughput(bytes: usize, iterations: u32, parse: impl Fn() -> bool) -> Result<f64> {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        if !parse() {
+            return Err(anyhow!("payload failed to parse"));
+        }
+    }
+    let secs = start.elapsed().as_secs_f64().max(f64::EPSILON);
+    Ok(bytes as f64 * f64::from(iterations) / secs / 1_048_576.0)
+}
+
+/// `bench-json` subcommand.
+pub fn bench(args: &[String]) -> Result<()> {
+    let (mut size_kib, mut iterations) = (1024usize, 50u32);
+    let mut it = args.iter();
+    while let Some(flag) = it.next() {
+        let value = it.next().ok_or_else(|| anyhow!("{flag} needs a value\n{USAGE}"))?;
+        match flag.as_str() {
+            "--size-kib" => size_kib = value.parse().map_err(|_| anyhow!("--size-kib: '{value}'\n{USAGE}"))?,
+            "--iterations" => iterations = value.parse().map_err(|_| anyhow!("--iterations: '{value}'\n{USAGE}"))?,
+            _ => return Err(anyhow!("unknown option {flag}\n{USAGE}")),
+        }
+    }
+    let payload = telemetry_payload(size_kib.max(1) * 1024);
+    let iterations = iterations.max(1);
+    println!(
+        "payload {} KiB, {iterations} iteration(s), {} {}",
+        payload.len() / 1024, std::env::consts::ARCH, std::env::consts::OS,
+    );
+
+    let serde = throughput(payload.len(), iterations, || {
+        serde_json::from_slice::<serde_json::Value>(&payload).is_ok()
+    })?;
+    println!("  serde_json   {serde:>9.1} MiB/s");
+    if !cfg!(feature = "simd") {
+        println!("  simd-json    not compiled in (build with --features simd)");
+        return Ok(());
+    }
+    let simd = throughput(payload.len(), iterations, || {
+        from_slice::<serde_json::Value>(&payload).is_ok()
+    })?;
+    println!("  simd-json    {simd:>9.1} MiB/s   ({:.2}× serde_json)", simd / serde);
+    Ok(())
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_large_and_small_payloads_parse_alike() {
+        let large = telemetry_payload(64 * 1024);
+        assert_eq!(parser_for(large.len()), if cfg!(feature = "simd") { "simd-json" } else { "serde_json" });
+        let fast: Value = from_slice(&large).unwrap();
+        assert_eq!(fast, serde_json::from_slice::<Value>(&large).unwrap());
+        assert_eq!(fast["readings"][3]["value"], json!(18.111));
+
+        let small = r#"{"ok":true,"n":1e3,"s":"é"}"#.as_bytes();
+        assert_eq!(parser_for(small.len()), "serde_json");
+        assert_eq!(from_slice::<Value>(small).unwrap(), json!({ "ok": true, "n": 1000.0, "s": "é" }));
+
+        // Invalid either way: serde_json's error
+        let mut broken = large.clone();
+        broken.truncate(large.len() - 2);
+        assert!(from_slice::<Value>(&broken).unwrap_err().is_eof());
+    }
+}
@@ -0,0 +1,230 @@
+//! Feature manifest — IR minor-version negotiation (spec §5.3)
+//!
+//! The major version gate (node.rs) only catches wire-format breaks.  Within
+//! a major version, artifacts may rely on opcodes, service formats or
+//! connectors that an older (or differently-built) node lacks.  This module is
+//! the single source of truth for what this build supports:
+//!
+//!   opcode:<IROpcode>        e.g. "opcode:CALL_MCP"
+//!   format:<ServiceFormat>   e.g. "format:HTTP", "format:ONNX" (feature `onnx`)
+//!   connector:<scheme>       e.g. "connector:s3", "connector:snmp",
+//!                            "connector:sql" (feature-gated),
+//!                            "connector:gpio" / "connector:serial" /
+//!                            "connector:ble" (Linux), "connector:kafka"
+//!                            (when SVM_KAFKA_BROKERS is set),
+//!                            "connector:zigbee" / "connector:zwave" (when
+//!                            SVM_MQTT_URL is set)
+//!                            — devices and ONNX Runtime are only advertised
+//!                            once found (capabilities.rs)
+//!   plugin:<name>            a connector plugin loaded from SVM_PLUGIN_DIR
+//!   redis_memory             STORE_MEMORY / LOAD_RESOURCE `redis` operands
+//!                            (when SVM_REDIS_URL is set)
+//!   action_callbacks         CALL_ACTION `async_callback` completion (when
+//!                            SVM_CALLBACK_BASE_URL is set)
+//!   <capability>             e.g. "plan_state", "preemption"
+//!   <local capability>       SVM_NODE_CAPABILITIES, e.g. "modbus" for an
+//!                            attached gateway; instructions name them in
+//!                            a `requires` operand
+//!
+//! The manifest is advertised in REGISTER capabilities.  Artifacts declare
+//! `required_features` / `optional_features`; requirements implied by the
+//! instructions themselves (opcode + dispatch format) are derived too, so an
+//! artifact that forgot to declare them is still refused precisely.
+
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::proto::llmir::{IrInstruction, IrOpcode, LlmIntermediateRepresentation, ServiceFormat};
+
+/// IR minor version implemented by this node (same major as SVM_IR_FORMAT_VERSION_MAJOR).
+pub const IR_FORMAT_VERSION_MINOR: u32 = 1;
+
+const OPCODES: &[IrOpcode] = &[
+    IrOpcode::LoadResource, IrOpcode::StoreMemory, IrOpcode::Validate,
+    IrOpcode::Branch, IrOpcode::Loop, IrOpcode::Jump,
+    IrOpcode::CallService, IrOpcode::CallAction, IrOpcode::CallMcp,
+    IrOpcode::Transform, IrOpcode::Aggregate, IrOpcode::Filter,
+    IrOpcode::ParallelSpawn, IrOpcode::ParallelMerge,
+    IrOpcode::LlmCall, IrOpcode::Return, IrOpcode::Switch,
+    IrOpcode::TryBegin, IrOpcode::TryEnd, IrOpcode::CallSlice,
+];
+
+/// Service formats this node can dispatch (GRPC / WASM / NATIVE / DOCKER / EMBEDDED_JS
+/// are central-only).
+const FORMATS: &[ServiceFormat] = &[
+    ServiceFormat::Http, ServiceFormat::Connector, ServiceFormat::Mcp,
+    ServiceFormat::LlmCallFormat,
+];
+
+pub fn supported_opcodes() -> Vec<&'static str> {
+    OPCODES.iter().map(|o| o.as_str_name()).collect()
+}
+
+pub fn supported_formats() -> Vec<&'static str> {
+    let mut out: Vec<&str> = FORMATS.iter().map(|f| f.as_str_name()).collect();
+    if cfg!(feature = "onnx") {
+        out.push(ServiceFormat::Onnx.as_str_name());
+    }
+    out
+}
+
+/// Every feature name this build supports.
+pub fn supported() -> BTreeSet<String> {
+    let mut out: BTreeSet<String> = BTreeSet::new();
+    out.extend(OPCODES.iter().map(|o| format!("opcode:{}", o.as_str_name())));
+    out.extend(supported_formats().into_iter().map(|f| format!("format:{f}")));
+    out.extend(["connector:file", "connector:s3", "connector:camera", "connector:snmp", "connector:sms", "connector:voice"].map(String::from));
+    if cfg!(feature = "sql") {
+        out.insert("connector:sql".into());
+    }
+    if cfg!(target_os = "linux") {
+        out.extend(["connector:gpio", "connector:serial", "connector:ble"].map(String::from));
+    }
+    out.extend(
+        ["service_catalog", "plan_state", "preemption", "distributed_locks", "ir_verifier", "action_wal", "placement"]
+            .map(String::from),
+    );
+    out
+}
+
+/// This build's features plus the node's local capabilities.
+pub fn node_features(config: &Config) -> BTreeSet<String> {
+    let mut out = supported();
+    out.extend(config.node_capabilities.iter().cloned());
+    if !config.kafka_brokers.is_empty() {
+        out.insert("connector:kafka".into());
+    }
+    if config.mqtt_url.is_some() {
+        out.extend(["connector:zigbee", "connector:zwave"].map(String::from));
+    }
+    if config.redis_url.is_some() {
+        out.insert("redis_memory".into());
+    }
+    if config.callback_base_url.is_some() {
+        out.insert("action_callbacks".into());
+    }
+    out
+}
+
+/// Features one instruction needs: its opcode, dispatch format (or SQL /
+/// device connector), Redis memory and the capabilities listed in its
+/// `requires` operand.
+pub fn required_by_instruction(instr: &IrInstruction, operands: &Value) -> BTreeSet<String> {
+    let mut req = BTreeSet::new();
+    if let Ok(op) = IrOpcode::try_from(instr.opcode) {
+        req.insert(format!("opcode:{}", op.as_str_name()));
+    }
+    let dispatches = matches!(
+        IrOpcode::try_from(instr.opcode),
+        Ok(IrOpcode::CallService | IrOpcode::LoadResource)
+    );
+    if let Some(dm) = instr.dispatch_metadata.as_ref().filter(|_| dispatches) {
+        if crate::connectors::is_sql_url(&dm.endpoint_url) {
+            req.insert("connector:sql".into());
+        } else if dm.endpoint_url.starts_with("ble://") {
+            req.insert("connector:ble".into());
+        } else if crate::connectors::is_camera_url(&dm.endpoint_url) {
+            req.insert("connector:camera".into());
+        } else if dm.endpoint_url.starts_with("snmp://") {
+            req.insert("connector:snmp".into());
+        } else if let Some((scheme, _)) = dm.endpoint_url.split_once("://").filter(|_| crate::connectors::is_bridge_url(&dm.endpoint_url)) {
+            req.insert(format!("connector:{scheme}"));
+        } else if let Some(plugin) = crate::connectors::plugin::required_feature(&dm.endpoint_url) {
+            req.insert(plugin);
+        } else if let Ok(f) = ServiceFormat::try_from(dm.format) {
+            req.insert(format!("format:{}", f.as_str_name()));
+        }
+    }
+    let action_connector = instr.dispatch_metadata.as_ref()
+        .filter(|_| matches!(IrOpcode::try_from(instr.opcode), Ok(IrOpcode::CallAction)))
+        .and_then(|dm| dm.endpoint_url.split_once("://"))
+        .filter(|(scheme, _)| matches!(*scheme, "gpio" | "serial" | "kafka" | "zigbee" | "zwave" | "sms" | "voice"));
+    if let Some((scheme, _)) = action_connector {
+        req.insert(format!("connector:{scheme}"));
+    }
+    let callback = instr.dispatch_metadata.as_ref()
+        .is_some_and(|dm| dm.async_callback.is_some() && matches!(IrOpcode::try_from(instr.opcode), Ok(IrOpcode::CallAction)));
+    if callback {
+        req.insert("action_callbacks".into());
+    }
+    let memory = matches!(IrOpcode::try_from(instr.opcode), Ok(IrOpcode::StoreMemory | IrOpcode::LoadResource));
+    if memory && operands.get("redis").is_some() {
+        req.insert("redis_memory".into());
+    }
+    if let Some(caps) = operands.get("requires").and_then(|r| r.as_array()) {
+        req.extend(caps.iter().filter_map(|c| c.as_str()).map(String::from));
+    }
+    req
+}
+
+/// Features implied by the instructions of `ir` (a peer can supply these
+/// per instruction, unlike slice-level `required_features`).
+pub fn instruction_features(ir: &LlmIntermediateRepresentation) -> BTreeSet<String> {
+    ir.instructions.values()
+        .flat_map(|instr| {
+            let operands = serde_json::from_str(&instr.operands_json).unwrap_or(Value::Null);
+            required_by_instruction(instr, &operands)
+        })
+        .collect()
+}
+
+/// Features `ir` needs: declared `required_features` plus those implied by
+/// its instructions.
+pub fn required_by(ir: &LlmIntermediateRepresentation) -> BTreeSet<String> {
+    let mut req = instruction_features(ir);
+    req.extend(ir.required_features.iter().cloned());
+    req
+}
+
+/// Outcome of negotiating an artifact against this node.
+#[derive(Debug, Default, PartialEq)]
+pub struct Negotiation {
+    /// Required features this node lacks — the slice must be refused
+    pub missing_required: Vec<String>,
+    /// Optional features this node lacks — execute, but degraded
+    pub missing_optional: Vec<String>,
+}
+
+/// Negotiate `ir` against the features in `have` (see `node_features`).
+pub fn negotiate(ir: &LlmIntermediateRepresentation, have: &BTreeSet<String>) -> Negotiation {
+    Negotiation {
+        missing_required: required_by(ir).into_iter().filter(|f| !have.contains(f)).collect(),
+        missing_optional: ir.optional_features.iter()
+            .filter(|f| !have.contains(*f))
+            .cloned()
+            .collect(),
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::llmir::{DispatchMetadata, IrInstruction};
+
+    #[test]
+    fn test_negotiate() {
+        let grpc = IrInstruction {
+            index: 1,
+            opcode: IrOpcode::CallService as i32,
+            dispatch_metadata: Some(DispatchMetadata { format: ServiceFormat::Grpc as i32, ..Default::default() }),
+            ..Default::default()
+        };
+        let ir = LlmIntermediateRepresentation {
+            instructions: [(1, grpc)].into(),
+            instruction_order: vec![1],
+            required_features: vec!["plan_state".into(), "opcode:TELEPORT".into()],
+            optional_features: vec!["connector:s3".into(), "gpu".into()],
+            ..Default::default()
+        };
+        let n = negotiate(&ir, &supported());
+        assert_eq!(n.missing_required, vec!["format:GRPC", "opcode:TELEPORT"]);
+        assert_eq!(n.missing_optional, vec!["gpu"]);
+
+        assert!(supported().contains("opcode:CALL_SERVICE"));
+        assert_eq!(negotiate(&LlmIntermediateRepresentation::default(), &supported()), Negotiation::default());
+    }
+}
@@ -0,0 +1,267 @@
+//! Compact AUDIT_FLUSH encoding — string dictionary + delta columns
+//!
+//! After a long outage thousands of flushed audit events repeat the same
+//! nodeId / workflowId / eventType / publicKeyHex, and each one restates the
+//! previous event's hash.  When central's CHALLENGE lists the encoding
+//!
+//!   { "type": "CHALLENGE", "payload": { "nonce": "...", "flushEncodings": ["dict-v1"] } }
+//!
+//! the node (REGISTER advertises `flushEncodings` too; SVM_FLUSH_COMPACT=false
+//! opts out) sends
+//!
+//!   { "type": "AUDIT_FLUSH", "encoding": "dict-v1", "header": {...},
+//!     "payload": { "strings": [...], "rows": [...] } }
+//!
+//! Rows are arrays; `s` is an index into `strings`, `t` a timestamp — an
+//! integer is milliseconds after the previous row's value of the same column
+//! (the first after 0), a string is kept verbatim when it would not
+//! round-trip byte for byte:
+//!
+//!   audit     ["A", enqueuedAt t, eventId, timestamp t, nodeId s, workflowId s,
+//!              workflowVersion, instructionId s, eventType s, inputHash,
+//!              outputHash, durationMs, details, previousEventHash, selfHash,
+//!              signature, publicKeyHex s, tenantId s, clockOffsetMs, parentSlice s]
+//!   other     ["R" | "T" | "K" | "S", enqueuedAt t, payload]
+//!                                           (result, trigger, Kafka, alert)
+//!
+//! previousEventHash is null when it is the selfHash of the audit row before
+//! it; optional fields are null when absent.  All rows are replayed events.
+//! `decode`, the reference decoder, is built for the tests.
+
+#[cfg(test)]
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::audit::AuditEvent;
+use crate::offline::BufferedEvent;
+
+/// Encoding name negotiated in CHALLENGE / REGISTER.
+pub const DICT_V1: &str = "dict-v1";
+
+// ── Encoder ───────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+struct Encoder {
+    strings: Vec<String>,
+    index: HashMap<String, usize>,
+    /// Running bases of the enqueuedAt and timestamp columns (ms)
+    enqueued_ms: i64,
+    timestamp_ms: i64,
+    last_self_hash: Option<String>,
+}
+
+fn millis(text: &str) -> Option<i64> {
+    let at = DateTime::parse_from_rfc3339(text).ok()?.with_timezone(&Utc);
+    // Only when decoding gives the identical string back (it is under the signature)
+    (at.to_rfc3339_opts(SecondsFormat::Millis, true) == text).then(|| at.timestamp_millis())
+}
+
+#[cfg(test)]
+fn format_millis(ms: i64) -> Result<String> {
+    DateTime::<Utc>::from_timestamp_millis(ms)
+        .map(|at| at.to_rfc3339_opts(SecondsFormat::Millis, true))
+        .ok_or_else(|| anyhow!("dict-v1: timestamp {ms} out of range"))
+}
+
+impl Encoder {
+    fn string(&mut self, s: &str) -> Value {
+        if let Some(&i) = self.index.get(s) {
+            return json!(i);
+        }
+        self.strings.push(s.to_owned());
+        self.index.insert(s.to_owned(), self.strings.len() - 1);
+        json!(self.strings.len() - 1)
+    }
+
+    fn optional(&mut self, s: Option<&str>) -> Value {
+        s.map_or(Value::Null, |s| self.string(s))
+    }
+
+    fn time(base: &mut i64, text: &str) -> Value {
+        match millis(text) {
+            Some(ms) => {
+                let delta = ms - *base;
+                *base = ms;
+                json!(delta)
+            }
+            None => json!(text),
+        }
+    }
+
+    fn row(&mut self, event: &BufferedEvent) -> Value {
+        let enqueued = Self::time(&mut self.enqueued_ms, event.enqueued_at());
+        let (tag, payload) = match event {
+            BufferedEvent::AuditEvent { payload, .. } => return self.audit_row(enqueued, payload),
+            BufferedEvent::ExecutionResult { payload, .. } => ("R", payload),
+            BufferedEvent::TriggerFire { payload, .. } => ("T", payload),
+            BufferedEvent::KafkaRecord { payload, .. } => ("K", payload),
+            BufferedEvent::SecurityAlert { payload, .. } => ("S", payload),
+        };
+        json!([tag, enqueued, payload])
+    }
+
+    fn audit_row(&mut self, enqueued: Value, ev: &AuditEvent) -> Value {
+        let previous = match &self.last_self_hash {
+            Some(hash) if *hash == ev.previous_event_hash => Value::Null,
+            _ => json!(ev.previous_event_hash),
+        };
+        self.last_self_hash = Some(ev.self_hash.clone());
+        json!([
+            "A",
+            enqueued,
+            ev.event_id,
+            Self::time(&mut self.timestamp_ms, &ev.timestamp),
+            self.string(&ev.node_id),
+            self.string(&ev.workflow_id),
+            ev.workflow_version,
+            self.optional(ev.instruction_id.as_deref()),
+            self.string(&ev.event_type),
+            ev.input_hash,
+            ev.output_hash,
+            ev.duration_ms,
+            ev.details,
+            previous,
+            ev.self_hash,
+            ev.signature,
+            self.string(&ev.public_key_hex),
+            self.optional(ev.tenant_id.as_deref()),
+            ev.clock_offset_ms,
+            self.optional(ev.parent_slice.as_deref()),
+        ])
+    }
+}
+
+/// dict-v1 payload of flushed events.
+pub fn encode(events: &[BufferedEvent]) -> Value {
+    let mut encoder = Encoder::default();
+    let rows: Vec<Value> = events.iter().map(|ev| encoder.row(ev)).collect();
+    json!({ "strings": encoder.strings, "rows": rows })
+}
+
+// ── Decoder ───────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+struct Decoder<'a> {
+    strings: Vec<&'a str>,
+    enqueued_ms: i64,
+    timestamp_ms: i64,
+    last_self_hash: Option<String>,
+}
+
+#[cfg(test)]
+impl<'a> Decoder<'a> {
+    fn string(&self, v: &Value) -> Result<String> {
+        let i = v.as_u64().ok_or_else(|| anyhow!("dict-v1: expected a string index, got {v}"))?;
+        self.strings.get(i as usize).map(|s| (*s).to_owned()).ok_or_else(|| anyhow!("dict-v1: string index {i} out of range"))
+    }
+
+    fn optional(&self, v: &Value) -> Result<Option<String>> {
+        if v.is_null() { Ok(None) } else { self.string(v).map(Some) }
+    }
+
+    fn time(base: &mut i64, v: &Value) -> Result<String> {
+        match v {
+            Value::String(s) => Ok(s.clone()),
+            _ => {
+                *base += v.as_i64().ok_or_else(|| anyhow!("dict-v1: bad timestamp {v}"))?;
+                format_millis(*base)
+            }
+        }
+    }
+
+    fn row(&mut self, row: &Value) -> Result<BufferedEvent> {
+        let cols = row.as_array().ok_or_else(|| anyhow!("dict-v1: row is not an array"))?;
+        let col = |i: usize| cols.get(i).unwrap_or(&Value::Null);
+        let enqueued_at = Self::time(&mut self.enqueued_ms, col(1))?;
+        let payload = || col(2).clone();
+        let event = match col(0).as_str() {
+            Some("R") => BufferedEvent::ExecutionResult { payload: payload(), enqueued_at, replayed: true },
+            Some("T") => BufferedEvent::TriggerFire { payload: payload(), enqueued_at, replayed: true },
+            Some("K") => BufferedEvent::KafkaRecord { payload: payload(), enqueued_at, replayed: true },
+            Some("S") => BufferedEvent::SecurityAlert { payload: payload(), enqueued_at, replayed: true },
+            Some("A") => {
+                let text = |i: usize| col(i).as_str().map(str::to_owned).ok_or_else(|| anyhow!("dict-v1: column {i} must be a string"));
+                let previous_event_hash = match col(13) {
+                    Value::Null => self.last_self_hash.clone().ok_or_else(|| anyhow!("dict-v1: unlinked first audit row"))?,
+                    _ => text(13)?,
+                };
+                let event = AuditEvent {
+                    event_id: text(2)?,
+                    timestamp: Self::time(&mut self.timestamp_ms, col(3))?,
+                    node_id: self.string(col(4))?,
+                    workflow_id: self.string(col(5))?,
+                    workflow_version: col(6).as_u64().map(|v| v as u32),
+                    instruction_id: self.optional(col(7))?,
+                    event_type: self.string(col(8))?,
+                    input_hash: text(9)?,
+                    output_hash: text(10)?,
+                    duration_ms: col(11).as_u64().unwrap_or(0),
+                    details: Some(col(12).clone()).filter(|d| !d.is_null()),
+                    previous_event_hash,
+                    self_hash: text(14)?,
+                    signature: text(15)?,
+                    public_key_hex: self.string(col(16))?,
+                    tenant_id: self.optional(col(17))?,
+                    clock_offset_ms: col(18).as_i64(),
+                    parent_slice: self.optional(col(19))?,
+                };
+                self.last_self_hash = Some(event.self_hash.clone());
+                BufferedEvent::AuditEvent { payload: event, enqueued_at, replayed: true }
+            }
+            other => return Err(anyhow!("dict-v1: unknown row kind {other:?}")),
+        };
+        Ok(event)
+    }
+}
+
+/// Events of a dict-v1 payload.
+#[cfg(test)]
+pub fn decode(payload: &Value) -> Result<Vec<BufferedEvent>> {
+    let strings = payload.get("strings").and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow!("dict-v1: missing strings"))?
+        .iter()
+        .map(|s| s.as_str().ok_or_else(|| anyhow!("dict-v1: non-string dictionary entry")))
+        .collect::<Result<Vec<_>>>()?;
+    let rows = payload.get("rows").and_then(|v| v.as_array()).ok_or_else(|| anyhow!("dict-v1: missing rows"))?;
+    let mut decoder = Decoder { strings, enqueued_ms: 0, timestamp_ms: 0, last_self_hash: None };
+    rows.iter().map(|row| decoder.row(row)).collect()
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditChain;
+    use crate::offline::OfflineBuffer;
+
+    #[test]
+    fn test_round_trip_and_size() {
+        let mut chain = AuditChain::new("node-a".into(), None).unwrap();
+        for i in 0..400 {
+            chain.append("wf-line-3", Some(2), Some(format!("i{}", i % 4)), "LOAD_RESOURCE", None, Some(&json!(i)), 3, None);
+        }
+        let mut buf = OfflineBuffer::new(std::env::temp_dir().join("unused-flush-codec.ndjson"), 10_000);
+        buf.notify_connected(false);
+        for event in chain.drain() {
+            buf.enqueue_audit_event(event);
+        }
+        buf.enqueue_execution_result(json!({ "workflowId": "wf-line-3", "status": "ok" }));
+        let (_, events) = buf.drain_for_flush();
+
+        let plain = serde_json::to_string(&events).unwrap();
+        let compact = encode(&events);
+        let decoded = decode(&compact).unwrap();
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), plain);
+        let ratio = compact.to_string().len() as f64 / plain.len() as f64;
+        assert!(ratio < 0.6, "dict-v1 is {ratio:.2} of plain JSON");
+
+        let ndjson = crate::audit_export::ndjson(&decoded.into_iter().filter_map(|e| match e {
+            BufferedEvent::AuditEvent { payload, .. } => Some(payload),
+            _ => None,
+        }).collect::<Vec<_>>()).unwrap();
+        assert!(crate::integrity::check(&ndjson, &[]).findings.is_empty());
+    }
+}
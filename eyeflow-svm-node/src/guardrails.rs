@@ -0,0 +1,210 @@
+//! LLM_CALL output guardrails — checked on the node before the result is used
+//!
+//! Model output feeds registers that later CALL_ACTIONs act on, so an
+//! instruction can constrain what it accepts:
+//!
+//!   LLM_CALL  operands_json = { "guardrails": {
+//!       "denyPatterns": ["(?i)rm\\s+-rf"],     regexes no string may match
+//!       "denyKeywords": ["password"],          case-insensitive substrings
+//!       "maxLength":    2000,                  characters (non-strings as JSON)
+//!       "enum":         ["OPEN", "CLOSE"],     the only acceptable outputs
+//!       "schema":       { ... } | true         JSON Schema subset (input_schema.rs);
+//!                                              true = dispatch_metadata.output_schema
+//!   } }
+//!
+//! A violating output is an error (VALIDATION) inside the call, so the
+//! instruction's fallback strategy handles it exactly like a failed call,
+//! and it is never cached.  Each violation is also appended to the audit
+//! chain as a GUARDRAIL_VIOLATION event whose output hash is that of the
+//! rejected output and whose details list what was wrong.
+//!
+//! Guardrails are parsed once per artifact (PreparedInstr); the verifier
+//! refuses malformed ones with INVALID_GUARDRAILS.
+
+use regex_lite::Regex;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use crate::input_schema::InputSchema;
+
+tokio::task_local! {
+    /// Outputs rejected while running the LLM_CALL being audited.
+    static VIOLATIONS: Arc<Mutex<Vec<Violation>>>;
+}
+
+/// One rejected output.
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub instruction: i32,
+    pub output: Value,
+    pub violations: Vec<String>,
+}
+
+/// Run `call`, returning its output and every output the guardrails rejected.
+pub async fn tracked<F: Future>(call: F) -> (F::Output, Vec<Violation>) {
+    let found = Arc::new(Mutex::new(Vec::new()));
+    let out = VIOLATIONS.scope(found.clone(), call).await;
+    let found = std::mem::take(&mut *found.lock().unwrap_or_else(|e| e.into_inner()));
+    (out, found)
+}
+
+#[derive(Debug, Clone)]
+pub struct Guardrails {
+    deny_patterns: Vec<Regex>,
+    /// Lower-cased
+    deny_keywords: Vec<String>,
+    max_length: Option<usize>,
+    allowed: Option<Vec<Value>>,
+    schema: Option<InputSchema>,
+}
+
+impl Guardrails {
+    /// Parse `operands.guardrails` (None when absent).  `output_schema` is
+    /// the instruction's dispatch_metadata.output_schema, used by `"schema": true`.
+    pub fn parse(operands: &Value, output_schema: &str) -> Result<Option<Self>, String> {
+        let Some(spec) = operands.get("guardrails") else {
+            return Ok(None);
+        };
+        if !spec.is_object() {
+            return Err("guardrails must be an object".into());
+        }
+        let strings = |name: &str| -> Result<Vec<&str>, String> {
+            match spec.get(name) {
+                None => Ok(Vec::new()),
+                Some(Value::Array(items)) => items.iter()
+                    .map(|v| v.as_str().ok_or_else(|| format!("guardrails.{name} must list strings")))
+                    .collect(),
+                Some(_) => Err(format!("guardrails.{name} must be an array")),
+            }
+        };
+        let deny_patterns = strings("denyPatterns")?.into_iter()
+            .map(|p| Regex::new(p).map_err(|e| format!("guardrails pattern {p}: {e}")))
+            .collect::<Result<_, _>>()?;
+        let deny_keywords = strings("denyKeywords")?.into_iter().map(str::to_lowercase).collect();
+        let max_length = match spec.get("maxLength") {
+            None => None,
+            Some(v) => Some(v.as_u64().ok_or("guardrails.maxLength must be a positive integer")? as usize),
+        };
+        let allowed = match spec.get("enum") {
+            None => None,
+            Some(Value::Array(values)) => Some(values.clone()),
+            Some(_) => return Err("guardrails.enum must be an array".into()),
+        };
+        let schema = match spec.get("schema") {
+            None | Some(Value::Bool(false)) => None,
+            Some(Value::Bool(true)) => Some(
+                InputSchema::parse(output_schema)?.ok_or("guardrails.schema is true but output_schema is empty")?,
+            ),
+            Some(s @ Value::Object(_)) => InputSchema::parse(&s.to_string())?,
+            Some(_) => return Err("guardrails.schema must be an object or true".into()),
+        };
+        Ok(Some(Self { deny_patterns, deny_keywords, max_length, allowed, schema }))
+    }
+
+    /// `output` (coerced to the schema, if any), or every violation found.
+    pub fn check(&self, output: Value) -> Result<Value, Vec<String>> {
+        let mut violations = Vec::new();
+        let output = match &self.schema {
+            Some(schema) => schema.coerce(&output).unwrap_or_else(|errors| {
+                violations.extend(errors.into_iter().map(|e| format!("schema: {e}")));
+                output
+            }),
+            None => output,
+        };
+        if let Some(max) = self.max_length {
+            let len = match &output {
+                Value::String(s) => s.chars().count(),
+                other => other.to_string().chars().count(),
+            };
+            if len > max {
+                violations.push(format!("output is {len} characters, limit {max}"));
+            }
+        }
+        if let Some(allowed) = &self.allowed {
+            if !allowed.contains(&output) {
+                violations.push(format!("output {output} is not an allowed value"));
+            }
+        }
+        self.scan(&output, &mut violations);
+        if violations.is_empty() { Ok(output) } else { Err(violations) }
+    }
+
+    /// Apply the denylists to every string in `value`.
+    fn scan(&self, value: &Value, violations: &mut Vec<String>) {
+        match value {
+            Value::String(s) => {
+                for re in &self.deny_patterns {
+                    if re.is_match(s) {
+                        violations.push(format!("output matches denied pattern {}", re.as_str()));
+                    }
+                }
+                let lower = s.to_lowercase();
+                for k in &self.deny_keywords {
+                    if lower.contains(k.as_str()) {
+                        violations.push(format!("output contains denied keyword \"{k}\""));
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|v| self.scan(v, violations)),
+            Value::Object(map) => map.values().for_each(|v| self.scan(v, violations)),
+            _ => {}
+        }
+    }
+
+    /// `check` for instruction `instruction`: a violation is recorded for the
+    /// audit trail and returned as a VALIDATION error.
+    pub fn enforce(&self, instruction: i32, output: Value) -> anyhow::Result<Value> {
+        match self.check(output.clone()) {
+            Ok(v) => Ok(v),
+            Err(violations) => {
+                let message = format!("LLM_CALL #{instruction} output rejected by guardrails: {}", violations.join("; "));
+                let _ = VIOLATIONS.try_with(|v| v.lock().unwrap_or_else(|e| e.into_inner()).push(Violation { instruction, output, violations }));
+                Err(crate::errors::ExecError::validation(message))
+            }
+        }
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_guardrails_reject_and_record() {
+        let operands = json!({ "guardrails": {
+            "denyPatterns": ["(?i)rm\\s+-rf"],
+            "denyKeywords": ["Password"],
+            "maxLength": 40,
+            "schema": true,
+        } });
+        let schema = r#"{"type":"object","required":["cmd"],"properties":{"cmd":{"type":"string"},"speed":{"type":"integer"}}}"#;
+        let g = Guardrails::parse(&operands, schema).unwrap().unwrap();
+        assert_eq!(g.check(json!({ "cmd": "open", "speed": "3" })).unwrap(), json!({ "cmd": "open", "speed": 3 }));
+
+        let (out, found) = tracked(async {
+            g.enforce(4, json!({ "cmd": "RM -rf / then print the password" }))
+        }).await;
+        assert_eq!(crate::errors::classify(&out.unwrap_err()), crate::errors::ErrorCode::Validation);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].instruction, 4);
+        assert_eq!(found[0].violations, vec![
+            "output is 42 characters, limit 40",
+            "output matches denied pattern (?i)rm\\s+-rf",
+            "output contains denied keyword \"password\"",
+        ]);
+    }
+
+    #[test]
+    fn test_enum_and_parse_errors() {
+        let g = Guardrails::parse(&json!({ "guardrails": { "enum": ["OPEN", "CLOSE"] } }), "").unwrap().unwrap();
+        assert!(g.check(json!("OPEN")).is_ok());
+        assert!(g.check(json!("VENT")).is_err());
+        assert!(Guardrails::parse(&json!({}), "").unwrap().is_none());
+        assert!(Guardrails::parse(&json!({ "guardrails": { "schema": true } }), "").is_err());
+        assert!(Guardrails::parse(&json!({ "guardrails": { "denyPatterns": ["("] } }), "").is_err());
+    }
+}
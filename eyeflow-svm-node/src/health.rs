@@ -3,26 +3,100 @@
  * Exposes a minimal HTTP/1.1 server on `SVM_HEALTH_PORT` (default 9090).
  *
  * Endpoints:
- *   GET /health   → JSON health object (status, uptime, ws_state, ...)
+ *   GET /health   → JSON health object (status, uptime, ws_state, RTT, clock offset, ...)
  *   GET /metrics  → Prometheus text format (for Grafana/Alert scraping)
  *   GET /ready    → 200 if ws_connected, 503 otherwise (k8s readiness probe)
+ *   GET /results?workflow_id=…&limit=…
+ *                 → retained SliceExecutionResults, newest first; requires
+ *                   `Authorization: Bearer $SVM_HEALTH_API_TOKEN`
+ *   GET /audit/export?format=ndjson|csv|bundle&workflow_id=…&since=…&until=…
+ *                 → the audit log (audit_export.rs); same bearer token
+ *   POST /callbacks/{id}
+ *                 → one-shot CALL_ACTION completion webhook (callbacks.rs)
  *
  * State is updated by other modules via the shared `HealthState` handle:
  *   – `NodeClient` calls `HealthState::set_ws_connected(true/false)`
  *   – `OfflineBuffer` calls  `HealthState::set_offline_depth(n)`
- *   – `Svm` calls            `HealthState::record_execution(elapsed_ms, ok)`
+ *   – `Svm` calls            `HealthState::record_execution(workflow_id, elapsed_ms, ok)`
+ *   – `NodeClient` calls     `HealthState::set_clock_offset(ms, exceeded)` on PING
+ *   – `NodeClient` calls     `HealthState::set_ws_rtt(ms)` on keepalive Pongs
+ *
+ *   – `VaultClient` calls    `HealthState::record_vault_lookup(error)` on lookups
+ *   – the disk watchdog calls `HealthState::set_disk_pressure(detail)` (disk_watch.rs)
+ *   – the audit self-test calls `HealthState::set_audit_chain(status)` (audit_verify.rs)
+ *   – the device watcher calls `HealthState::set_devices_missing(detail)` (hotplug.rs)
+ *
+ * /health `vault` (null until the first lookup) and eyeflow_vault_* carry
+ * reachability, the last success and the consecutive failures.
+ *
+ * `status`, /ready and eyeflow_node_healthy follow a weighted score over
+ * these (health_score.rs); /health carries the score and its reasons.
+ *
+ * Executions are also counted per workflow id for the most recently run
+ * workflows (workflow_stats.rs); /health lists the failing ones.
+ *
+ * Subsystems with their own series (e.g. the ResourceArbiter) register a
+ * `MetricsCollector`; its output is appended to /metrics.
+ *
+ * Nodes that cannot be scraped can also push the /metrics snapshot to a
+ * Pushgateway or remote-write endpoint (metrics_push.rs).
  *
  * No extra Cargo dependencies — uses raw `tokio::net::TcpListener`.
  */
 
-use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::Result;
+use serde::Serialize;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpListener;
-use tracing::{debug, error, info, warn};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use crate::audit_export::{AuditLog, Filter, Format};
+use crate::audit_verify::Status as AuditStatus;
+use crate::callbacks::{self, CallbackRegistry};
+use crate::health_score::{self, HealthThresholds, Inputs, Score};
+use crate::results::ResultStore;
+use crate::workflow_stats::WorkflowStats;
+
+/// Executions the error-rate check looks back over.
+const RECENT_EXECUTIONS: usize = 100;
+
+// ── Collectors ────────────────────────────────────────────────────────────────
+
+/// Source of additional Prometheus series rendered on /metrics.
+pub trait MetricsCollector: Send + Sync {
+    /// Prometheus text (HELP/TYPE + samples), labelled with `node_id`.
+    fn prometheus(&self, node_id: &str) -> String;
+}
+
+impl std::fmt::Debug for dyn MetricsCollector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MetricsCollector")
+    }
+}
+
+// ── Vault status ──────────────────────────────────────────────────────────────
+
+/// Vault reachability as seen by VaultClient lookups.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultStatus {
+    pub reachable: bool,
+    /// Unix seconds of the last lookup that reached / did not reach Vault
+    pub last_success: Option<u64>,
+    pub last_failure: Option<u64>,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
 
 // ── HealthState ───────────────────────────────────────────────────────────────
 
@@ -40,12 +114,44 @@ pub struct HealthState {
     pub executions_failed: AtomicU64,
     /// Total execution time accumulated (milliseconds) - for avg computation.
     pub exec_duration_ms_total: AtomicU64,
+    /// Offset to central's clock (ms, central − node); i64::MIN = not measured.
+    pub clock_offset_ms: AtomicI64,
+    /// Whether |clock_offset_ms| exceeds SVM_CLOCK_DRIFT_WARN_MS.
+    pub clock_drift_exceeded: AtomicBool,
+    /// Last keepalive round-trip time to central (ms); u64::MAX = not measured.
+    pub ws_rtt_ms: AtomicU64,
+    /// Connections dropped because a keepalive ping went unanswered.
+    pub keepalive_timeouts: AtomicU64,
+    /// Outcomes of the last RECENT_EXECUTIONS executions (true = failed).
+    recent: Mutex<VecDeque<bool>>,
+    /// Per-workflow counters (SVM_WORKFLOW_METRICS_MAX workflows).
+    workflows: WorkflowStats,
+    /// Vault reachability (None until the first lookup).
+    vault: Mutex<Option<VaultStatus>>,
+    /// Filesystem whose free space is scored.
+    disk_path: RwLock<Option<PathBuf>>,
+    /// Disk watchdog detail while under DISK_PRESSURE.
+    disk_pressure: RwLock<Option<String>>,
+    /// Last audit self-test result.
+    audit_chain: RwLock<Option<AuditStatus>>,
+    /// Device watcher detail while a device is unplugged.
+    devices_missing: RwLock<Option<String>>,
+    /// Weights and limits of the health score.
+    thresholds: RwLock<HealthThresholds>,
     /// Unix timestamp (seconds) when the node started.
     start_ts: u64,
     /// Node ID for identification.
     pub node_id: String,
     /// Node tier (CENTRAL / LINUX / MCU / ANY).
     pub node_tier: String,
+    /// Extra series appended to /metrics.
+    collectors: RwLock<Vec<Arc<dyn MetricsCollector>>>,
+    /// Backing store + bearer token for GET /results.
+    results: RwLock<Option<(Arc<ResultStore>, Option<String>)>>,
+    /// Audit log + bearer token for GET /audit/export.
+    audit_log: RwLock<Option<(Arc<AuditLog>, Option<String>)>>,
+    /// Pending CALL_ACTION callbacks for POST /callbacks/{id}.
+    callbacks: RwLock<Option<Arc<CallbackRegistry>>>,
 }
 
 impl HealthState {
@@ -56,12 +162,28 @@ impl HealthState {
             executions_total:    AtomicU64::new(0),
             executions_failed:   AtomicU64::new(0),
             exec_duration_ms_total: AtomicU64::new(0),
+            clock_offset_ms:     AtomicI64::new(i64::MIN),
+            clock_drift_exceeded: AtomicBool::new(false),
+            ws_rtt_ms:           AtomicU64::new(u64::MAX),
+            keepalive_timeouts:  AtomicU64::new(0),
+            recent:              Mutex::new(VecDeque::new()),
+            workflows:           WorkflowStats::new(50),
+            vault:               Mutex::new(None),
+            disk_path:           RwLock::new(None),
+            disk_pressure:       RwLock::new(None),
+            audit_chain:         RwLock::new(None),
+            devices_missing:     RwLock::new(None),
+            thresholds:          RwLock::new(HealthThresholds::default()),
             start_ts: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .map(|d| d.as_secs())
                 .unwrap_or(0),
             node_id:   node_id.to_owned(),
             node_tier: node_tier.to_owned(),
+            collectors: RwLock::new(Vec::new()),
+            results: RwLock::new(None),
+            audit_log: RwLock::new(None),
+            callbacks: RwLock::new(None),
         })
     }
 
@@ -77,16 +199,140 @@ impl HealthState {
         self.offline_depth.store(depth, Ordering::Relaxed);
     }
 
+    /// Update the measured clock offset to central.
+    pub fn set_clock_offset(&self, offset_ms: i64, exceeded: bool) {
+        self.clock_offset_ms.store(offset_ms, Ordering::Relaxed);
+        self.clock_drift_exceeded.store(exceeded, Ordering::Relaxed);
+    }
+
+    /// Measured clock offset to central (None until the first timed PING).
+    pub fn clock_offset(&self) -> Option<i64> {
+        Some(self.clock_offset_ms.load(Ordering::Relaxed)).filter(|o| *o != i64::MIN)
+    }
+
+    /// Update the last keepalive round-trip time to central.
+    pub fn set_ws_rtt(&self, rtt: Duration) {
+        self.ws_rtt_ms.store(rtt.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Last keepalive round-trip time (None until the first Pong).
+    pub fn ws_rtt_ms(&self) -> Option<u64> {
+        Some(self.ws_rtt_ms.load(Ordering::Relaxed)).filter(|r| *r != u64::MAX)
+    }
+
+    /// Count a connection dropped for an unanswered keepalive ping.
+    pub fn record_keepalive_timeout(&self) {
+        self.keepalive_timeouts.fetch_add(1, Ordering::Relaxed);
+        self.ws_rtt_ms.store(u64::MAX, Ordering::Relaxed);
+    }
+
     /// Record one IR execution result.
     ///
     /// `ok = true`  → success
     /// `ok = false` → fault (after all retries)
-    pub fn record_execution(&self, elapsed_ms: u64, ok: bool) {
+    pub fn record_execution(&self, workflow_id: &str, elapsed_ms: u64, ok: bool) {
+        self.workflows.record(workflow_id, ok);
         self.executions_total.fetch_add(1, Ordering::Relaxed);
         self.exec_duration_ms_total.fetch_add(elapsed_ms, Ordering::Relaxed);
         if !ok {
             self.executions_failed.fetch_add(1, Ordering::Relaxed);
         }
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == RECENT_EXECUTIONS {
+            recent.pop_front();
+        }
+        recent.push_back(!ok);
+    }
+
+    /// How many workflows get their own counters (SVM_WORKFLOW_METRICS_MAX).
+    pub fn set_workflow_metrics_max(&self, max: usize) {
+        self.workflows.set_capacity(max);
+    }
+
+    /// Record a Vault lookup: None reached Vault, Some(error) did not.
+    pub fn record_vault_lookup(&self, error: Option<String>) {
+        let mut vault = self.vault.lock().unwrap_or_else(|e| e.into_inner());
+        let status = vault.get_or_insert_with(VaultStatus::default);
+        status.reachable = error.is_none();
+        match error {
+            None => {
+                status.last_success = Some(unix_now());
+                status.consecutive_failures = 0;
+            }
+            Some(e) => {
+                status.last_failure = Some(unix_now());
+                status.consecutive_failures += 1;
+                status.last_error = Some(e);
+            }
+        }
+    }
+
+    /// Vault reachability (None until the first lookup).
+    pub fn vault_status(&self) -> Option<VaultStatus> {
+        self.vault.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Score free space on the filesystem holding `path`.
+    pub fn set_disk_path(&self, path: PathBuf) {
+        if let Ok(mut p) = self.disk_path.write() {
+            *p = Some(path);
+        }
+    }
+
+    /// DISK_PRESSURE reported by the disk watchdog (None = cleared).
+    pub fn set_disk_pressure(&self, detail: Option<String>) {
+        if let Ok(mut d) = self.disk_pressure.write() {
+            *d = detail;
+        }
+    }
+
+    /// Devices unplugged, reported by the device watcher (None = all present).
+    pub fn set_devices_missing(&self, detail: Option<String>) {
+        if let Ok(mut d) = self.devices_missing.write() {
+            *d = detail;
+        }
+    }
+
+    /// Latest audit self-test result.
+    pub fn set_audit_chain(&self, status: AuditStatus) {
+        if let Ok(mut a) = self.audit_chain.write() {
+            *a = Some(status);
+        }
+    }
+
+    /// Replace the health score thresholds (SVM_HEALTH_THRESHOLDS / CONFIG_UPDATE).
+    pub fn set_thresholds(&self, thresholds: HealthThresholds) {
+        if let Ok(mut t) = self.thresholds.write() {
+            *t = thresholds;
+        }
+    }
+
+    /// Append a collector's series to /metrics.
+    pub fn register_collector(&self, collector: Arc<dyn MetricsCollector>) {
+        if let Ok(mut c) = self.collectors.write() {
+            c.push(collector);
+        }
+    }
+
+    /// Serve `log` on GET /audit/export, guarded by `api_token` (None = 403).
+    pub fn set_audit_log(&self, log: Arc<AuditLog>, api_token: Option<String>) {
+        if let Ok(mut a) = self.audit_log.write() {
+            *a = Some((log, api_token));
+        }
+    }
+
+    /// Serve `store` on GET /results, guarded by `api_token` (None = 403).
+    pub fn set_result_store(&self, store: Arc<ResultStore>, api_token: Option<String>) {
+        if let Ok(mut r) = self.results.write() {
+            *r = Some((store, api_token));
+        }
+    }
+
+    /// Serve POST /callbacks/{id} from `registry`.
+    pub fn set_callbacks(&self, registry: Arc<CallbackRegistry>) {
+        if let Ok(mut c) = self.callbacks.write() {
+            *c = Some(registry);
+        }
     }
 
     // ── Computed metrics ──────────────────────────────────────────────────
@@ -107,10 +353,31 @@ impl HealthState {
         self.exec_duration_ms_total.load(Ordering::Relaxed) / total
     }
 
-    /// Whether the node is considered healthy (WS connected, offline buffer < 1000).
+    /// Weighted health score over the current state.
+    pub fn score(&self) -> Score {
+        let recent = {
+            let recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+            (recent.iter().filter(|failed| **failed).count() as u64, recent.len() as u64)
+        };
+        let disk_path = self.disk_path.read().ok().and_then(|p| p.clone());
+        let inputs = Inputs {
+            ws_connected: self.ws_connected.load(Ordering::Relaxed),
+            recent,
+            offline_depth: self.offline_depth.load(Ordering::Relaxed),
+            vault_reachable: self.vault_status().map(|v| v.reachable),
+            disk_free_bytes: disk_path.as_deref().and_then(health_score::disk_free_bytes),
+            disk_pressure: self.disk_pressure.read().ok().and_then(|d| d.clone()),
+            audit_corrupted: self.audit_chain.read().ok()
+                .and_then(|a| a.as_ref().filter(|a| a.status == "CORRUPTED").and_then(|a| a.detail.clone())),
+            devices_missing: self.devices_missing.read().ok().and_then(|d| d.clone()),
+        };
+        let thresholds = self.thresholds.read().map(|t| t.clone()).unwrap_or_default();
+        health_score::score(&thresholds, &inputs)
+    }
+
+    /// Whether the health score reaches `okScore`.
     pub fn is_healthy(&self) -> bool {
-        self.ws_connected.load(Ordering::Relaxed)
-            && self.offline_depth.load(Ordering::Relaxed) < 1000
+        self.score().status == "ok"
     }
 
     // ── Serialisation ─────────────────────────────────────────────────────
@@ -123,13 +390,26 @@ impl HealthState {
         let failed     = self.executions_failed.load(Ordering::Relaxed);
         let avg_ms     = self.avg_exec_ms();
         let uptime     = self.uptime_secs();
-        let status_str = if self.is_healthy() { "ok" } else { "degraded" };
+        let score      = self.score();
+        let status_str = score.status;
+        let reasons    = serde_json::to_string(&score.reasons()).unwrap_or_else(|_| "[]".into());
+        let offset     = self.clock_offset().map_or("null".to_owned(), |o| o.to_string());
+        let drift      = self.clock_drift_exceeded.load(Ordering::Relaxed);
+        let rtt        = self.ws_rtt_ms().map_or("null".to_owned(), |r| r.to_string());
+        let workflows  = self.workflows.to_json();
+        let vault      = serde_json::to_string(&self.vault_status()).unwrap_or_else(|_| "null".into());
+        let audit_chain = self.audit_chain.read().ok()
+            .and_then(|a| serde_json::to_string(&*a).ok())
+            .unwrap_or_else(|| "null".into());
+        let disk_state = if score.checks.iter().any(|c| c.check == "diskPressure") { "DISK_PRESSURE" } else { "OK" };
 
         format!(
-            r#"{{"status":"{status_str}","node_id":"{node_id}","tier":"{tier}",\
-"uptime_secs":{uptime},"ws_connected":{ws},"offline_depth":{offline},\
-"executions":{{"total":{total},"failed":{failed},"avg_ms":{avg_ms}}}}}"#,
+            r#"{{"status":"{status_str}","score":{score:.1},"reasons":{reasons},"node_id":"{node_id}","tier":"{tier}",\
+"uptime_secs":{uptime},"ws_connected":{ws},"ws_rtt_ms":{rtt},"offline_depth":{offline},"disk_state":"{disk_state}","audit_chain":{audit_chain},"vault":{vault},\
+"executions":{{"total":{total},"failed":{failed},"avg_ms":{avg_ms}}},"workflows":{workflows},\
+"clock":{{"offset_ms":{offset},"drift_exceeded":{drift}}}}}"#,
             status_str = status_str,
+            score      = score.score,
             node_id    = self.node_id,
             tier       = self.node_tier,
             uptime     = uptime,
@@ -151,11 +431,13 @@ impl HealthState {
         let failed     = self.executions_failed.load(Ordering::Relaxed);
         let avg_ms     = self.avg_exec_ms();
         let uptime     = self.uptime_secs();
-        let healthy    = if self.is_healthy() { 1 } else { 0 };
+        let score      = self.score();
+        let healthy    = if score.status == "ok" { 1 } else { 0 };
+        let timeouts   = self.keepalive_timeouts.load(Ordering::Relaxed);
         let node_id    = &self.node_id;
         let tier       = &self.node_tier;
 
-        format!(
+        let mut out = format!(
             "# HELP eyeflow_node_healthy 1 if node is healthy\n\
              # TYPE eyeflow_node_healthy gauge\n\
              eyeflow_node_healthy{{node_id=\"{node_id}\",tier=\"{tier}\"}} {healthy}\n\
@@ -176,8 +458,208 @@ impl HealthState {
              eyeflow_executions_failed{{node_id=\"{node_id}\"}} {failed}\n\
              # HELP eyeflow_execution_avg_ms Average IR execution duration (ms)\n\
              # TYPE eyeflow_execution_avg_ms gauge\n\
-             eyeflow_execution_avg_ms{{node_id=\"{node_id}\"}} {avg_ms}\n",
-        )
+             eyeflow_execution_avg_ms{{node_id=\"{node_id}\"}} {avg_ms}\n\
+             # HELP eyeflow_ws_keepalive_timeouts_total Connections dropped for an unanswered keepalive ping\n\
+             # TYPE eyeflow_ws_keepalive_timeouts_total counter\n\
+             eyeflow_ws_keepalive_timeouts_total{{node_id=\"{node_id}\"}} {timeouts}\n",
+        );
+        out.push_str(&format!(
+            "# HELP eyeflow_node_health_score Weighted health score (0-100)\n\
+             # TYPE eyeflow_node_health_score gauge\n\
+             eyeflow_node_health_score{{node_id=\"{node_id}\"}} {:.1}\n\
+             # HELP eyeflow_node_health_check Health check scores (0-1)\n\
+             # TYPE eyeflow_node_health_check gauge\n",
+            score.score,
+        ));
+        for check in &score.checks {
+            out.push_str(&format!(
+                "eyeflow_node_health_check{{node_id=\"{node_id}\",check=\"{}\"}} {:.3}\n",
+                check.check, check.score,
+            ));
+        }
+        out.push_str(&self.workflows.prometheus(node_id));
+        if let Some(vault) = self.vault_status() {
+            out.push_str(&format!(
+                "# HELP eyeflow_vault_reachable 1 if the last Vault lookup reached Vault\n\
+                 # TYPE eyeflow_vault_reachable gauge\n\
+                 eyeflow_vault_reachable{{node_id=\"{node_id}\"}} {}\n\
+                 # HELP eyeflow_vault_consecutive_failures Vault lookups in a row that did not reach Vault\n\
+                 # TYPE eyeflow_vault_consecutive_failures gauge\n\
+                 eyeflow_vault_consecutive_failures{{node_id=\"{node_id}\"}} {}\n",
+                u8::from(vault.reachable),
+                vault.consecutive_failures,
+            ));
+            if let Some(at) = vault.last_success {
+                out.push_str(&format!(
+                    "# HELP eyeflow_vault_last_success_timestamp_seconds Unix time of the last lookup that reached Vault\n\
+                     # TYPE eyeflow_vault_last_success_timestamp_seconds gauge\n\
+                     eyeflow_vault_last_success_timestamp_seconds{{node_id=\"{node_id}\"}} {at}\n",
+                ));
+            }
+        }
+        if let Some(rtt) = self.ws_rtt_ms() {
+            out.push_str(&format!(
+                "# HELP eyeflow_ws_rtt_ms Keepalive round-trip time to central (ms)\n\
+                 # TYPE eyeflow_ws_rtt_ms gauge\n\
+                 eyeflow_ws_rtt_ms{{node_id=\"{node_id}\"}} {rtt}\n",
+            ));
+        }
+        if let Some(offset) = self.clock_offset() {
+            out.push_str(&format!(
+                "# HELP eyeflow_clock_offset_ms Offset to central's clock (central - node, ms)\n\
+                 # TYPE eyeflow_clock_offset_ms gauge\n\
+                 eyeflow_clock_offset_ms{{node_id=\"{node_id}\"}} {offset}\n",
+            ));
+        }
+        if let Ok(collectors) = self.collectors.read() {
+            for c in collectors.iter() {
+                out.push_str(&c.prometheus(node_id));
+            }
+        }
+        out
+    }
+}
+
+// ── /results ──────────────────────────────────────────────────────────────────
+
+/// Default and maximum page size for GET /results.
+const RESULTS_DEFAULT_LIMIT: usize = 50;
+const RESULTS_MAX_LIMIT: usize = 1000;
+
+type Response = (&'static str, &'static str, String);
+
+fn json_error(status: &'static str, msg: &str) -> Response {
+    (status, "application/json", serde_json::json!({ "error": msg }).to_string())
+}
+
+/// Constant-time comparison so the token can't be guessed byte by byte.
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Check the request's bearer token against `token` (None = 403).
+fn authorize(request: &str, token: Option<&str>) -> Option<Response> {
+    let Some(token) = token else {
+        return Some(json_error("403 Forbidden", "SVM_HEALTH_API_TOKEN is not set"));
+    };
+    let bearer = request.lines()
+        .take_while(|l| !l.trim().is_empty())
+        .filter_map(|l| l.split_once(':'))
+        .find(|(k, _)| k.trim().eq_ignore_ascii_case("authorization"))
+        .and_then(|(_, v)| v.trim().strip_prefix("Bearer "))
+        .map(str::trim);
+    if !bearer.is_some_and(|b| token_matches(b, token)) {
+        return Some(json_error("401 Unauthorized", "missing or invalid bearer token"));
+    }
+    None
+}
+
+impl HealthState {
+    async fn results_response(&self, request: &str, target: &str) -> Response {
+        let Some((store, token)) = self.results.read().ok().and_then(|r| r.clone()) else {
+            return json_error("404 Not Found", "result retention disabled");
+        };
+        if let Some(denied) = authorize(request, token.as_deref()) {
+            return denied;
+        }
+
+        let url = match reqwest::Url::parse(&format!("http://localhost{target}")) {
+            Ok(u) => u,
+            Err(_) => return json_error("400 Bad Request", "malformed request target"),
+        };
+        let mut workflow_id = None;
+        let mut limit = RESULTS_DEFAULT_LIMIT;
+        for (k, v) in url.query_pairs() {
+            match k.as_ref() {
+                "workflow_id" => workflow_id = Some(v.into_owned()),
+                "limit" => match v.parse::<usize>() {
+                    Ok(n) => limit = n.min(RESULTS_MAX_LIMIT),
+                    Err(_) => return json_error("400 Bad Request", "limit must be a positive integer"),
+                },
+                _ => {}
+            }
+        }
+        let results = store.query(workflow_id.as_deref(), limit).await;
+        ("200 OK", "application/json", serde_json::json!({ "results": results }).to_string())
+    }
+}
+
+// ── /audit/export ─────────────────────────────────────────────────────────────
+
+impl HealthState {
+    async fn audit_export_response(&self, request: &str, target: &str) -> Response {
+        let Some((log, token)) = self.audit_log.read().ok().and_then(|a| a.clone()) else {
+            return json_error("404 Not Found", "audit log disabled (SVM_AUDIT_LOG_PATH)");
+        };
+        if let Some(denied) = authorize(request, token.as_deref()) {
+            return denied;
+        }
+
+        let url = match reqwest::Url::parse(&format!("http://localhost{target}")) {
+            Ok(u) => u,
+            Err(_) => return json_error("400 Bad Request", "malformed request target"),
+        };
+        let mut format = Format::Ndjson;
+        let mut filter = Filter::default();
+        let bad = |e: anyhow::Error| json_error("400 Bad Request", &e.to_string());
+        for (k, v) in url.query_pairs() {
+            match k.as_ref() {
+                "format" => match Format::parse(&v) {
+                    Ok(f) => format = f,
+                    Err(e) => return bad(e),
+                },
+                "workflow_id" => filter.workflow_id = Some(v.into_owned()),
+                "since" | "until" => match Filter::time(&k, &v) {
+                    Ok(t) if k == "since" => filter.since = Some(t),
+                    Ok(t) => filter.until = Some(t),
+                    Err(e) => return bad(e),
+                },
+                _ => {}
+            }
+        }
+        match log.export(format, &filter).await {
+            Ok(body) => ("200 OK", format.content_type(), body),
+            Err(e) => {
+                warn!("[Health] audit export: {e}");
+                json_error("500 Internal Server Error", &e.to_string())
+            }
+        }
+    }
+}
+
+// ── /callbacks ────────────────────────────────────────────────────────────────
+
+impl HealthState {
+    /// Read the callback body (`received` holds what has arrived so far,
+    /// headers included) and hand it to the waiting instruction.
+    async fn callback_response(&self, socket: &mut TcpStream, received: &[u8], route: &str) -> Response {
+        let Some(registry) = self.callbacks.read().ok().and_then(|c| c.clone()) else {
+            return json_error("404 Not Found", "callbacks disabled");
+        };
+        let Some(header_end) = received.windows(4).position(|w| w == b"\r\n\r\n") else {
+            return json_error("431 Request Header Fields Too Large", "headers too large");
+        };
+        let length = std::str::from_utf8(&received[..header_end]).unwrap_or("")
+            .lines()
+            .filter_map(|l| l.split_once(':'))
+            .find(|(k, _)| k.trim().eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, v)| v.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        if length > registry.max_bytes() {
+            return json_error("413 Payload Too Large", "callback body exceeds SVM_CALLBACK_MAX_BYTES");
+        }
+        let mut body = received[header_end + 4..].to_vec();
+        while body.len() < length {
+            let mut chunk = [0u8; 8192];
+            match tokio::time::timeout(Duration::from_secs(10), socket.read(&mut chunk)).await {
+                Ok(Ok(n)) if n > 0 => body.extend_from_slice(&chunk[..n]),
+                _ => return json_error("400 Bad Request", "incomplete callback body"),
+            }
+        }
+        body.truncate(length);
+        let (status, text) = registry.handle(route, &body);
+        (status, "application/json", text)
     }
 }
 
@@ -202,21 +684,26 @@ pub async fn run(state: Arc<HealthState>, port: u16) -> Result<()> {
             Ok((mut socket, peer)) => {
                 let state = state.clone();
                 tokio::spawn(async move {
-                    // Read request line (we only care about the path)
-                    let mut buf = [0u8; 512];
+                    // Read request line + headers (Authorization for /results)
+                    let mut buf = [0u8; 4096];
                     let n = match socket.read(&mut buf).await {
                         Ok(n) if n > 0 => n,
                         _ => return,
                     };
 
-                    let req = std::str::from_utf8(&buf[..n]).unwrap_or("");
-                    let path = req
-                        .lines()
-                        .next()
-                        .and_then(|l| l.split_whitespace().nth(1))
-                        .unwrap_or("/health");
+                    let req = String::from_utf8_lossy(&buf[..n]).into_owned();
+                    let mut request_line = req.lines().next().unwrap_or("").split_whitespace();
+                    let method = request_line.next().unwrap_or("GET");
+                    let path = request_line.next().unwrap_or("/health");
 
-                    let (status, content_type, body) = match path {
+                    let route = path.split('?').next().unwrap_or(path);
+                    let (status, content_type, body) = match route {
+                        _ if route.starts_with(callbacks::ROUTE_PREFIX) => match method {
+                            "POST" | "PUT" => state.callback_response(&mut socket, &buf[..n], route).await,
+                            _ => json_error("405 Method Not Allowed", "callbacks are POSTed"),
+                        },
+                        "/results" => state.results_response(&req, path).await,
+                        "/audit/export" => state.audit_export_response(&req, path).await,
                         "/metrics" => (
                             "200 OK",
                             "text/plain; version=0.0.4; charset=utf-8",
@@ -0,0 +1,316 @@
+//! Health score — weighted checks behind /health, /ready and eyeflow_node_healthy
+//!
+//! Each check scores 0..1; the node's score is their weighted mean × 100:
+//!
+//!   ws            1 while the central WebSocket is up, else 0
+//!   errorRate     failed share of the last 100 executions (from 10 on):
+//!                 1 up to `warn`, falling linearly to 0 at `max`
+//!   offlineDepth  offline buffer events, same ramp
+//!   vault         1 reachable, 0 not (VaultClient; unknown until first lookup)
+//!   disk          free bytes under SVM_HEALTH_DISK_PATH (default: the offline
+//!                 buffer's directory): 1 above `warn`, 0 at `min`
+//!   diskPressure  0 while the disk watchdog reports DISK_PRESSURE
+//!                 (disk_watch.rs; `disk` weight), which also holds status
+//!                 at "degraded" at best
+//!   auditChain    0 once the audit self-test found a corrupted segment
+//!                 (audit_verify.rs)
+//!   devices       0 while a watched device is unplugged (hotplug.rs),
+//!                 which also holds status at "degraded" at best
+//!
+//! Checks without data (no Vault, too few executions) are left out of the
+//! mean.  status is "ok" at `okScore` and above (is_healthy, /ready 200),
+//! "degraded" at `minScore` and above, else "unhealthy".  /health lists the
+//! checks that are not at 1 as `reasons`.
+//!
+//! Thresholds come from SVM_HEALTH_THRESHOLDS or CONFIG_UPDATE
+//! `payload.healthThresholds` (null restores the defaults); unset keys keep
+//! theirs:
+//!
+//!   { "weights": { "ws": 4, "errorRate": 2, "offlineDepth": 3, "vault": 1, "disk": 1, "audit": 1,
+//!                  "devices": 1 },
+//!     "errorRate": { "warn": 0.1, "max": 0.5 },
+//!     "offlineDepth": { "warn": 1000, "max": 1000 },
+//!     "diskFreeBytes": { "warn": 536870912, "min": 67108864 },
+//!     "okScore": 80, "minScore": 50 }
+//!
+//! The defaults keep the former rule: a node that is disconnected or holds
+//! 1000 offline events is not ok.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::Config;
+
+// ── Thresholds ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Weights {
+    pub ws: f64,
+    pub error_rate: f64,
+    pub offline_depth: f64,
+    pub vault: f64,
+    pub disk: f64,
+    pub audit: f64,
+    pub devices: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self { ws: 4.0, error_rate: 2.0, offline_depth: 3.0, vault: 1.0, disk: 1.0, audit: 1.0, devices: 1.0 }
+    }
+}
+
+/// Score 1 up to `warn`, 0 from `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Ramp {
+    pub warn: f64,
+    pub max: f64,
+}
+
+impl Ramp {
+    fn score(self, value: f64) -> f64 {
+        if value >= self.max {
+            0.0
+        } else if value <= self.warn {
+            1.0
+        } else {
+            (self.max - value) / (self.max - self.warn)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct DiskFloor {
+    pub warn: u64,
+    pub min: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HealthThresholds {
+    pub weights: Weights,
+    pub error_rate: Ramp,
+    pub offline_depth: Ramp,
+    pub disk_free_bytes: DiskFloor,
+    pub ok_score: f64,
+    pub min_score: f64,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            weights: Weights::default(),
+            error_rate: Ramp { warn: 0.1, max: 0.5 },
+            offline_depth: Ramp { warn: 1000.0, max: 1000.0 },
+            disk_free_bytes: DiskFloor { warn: 512 << 20, min: 64 << 20 },
+            ok_score: 80.0,
+            min_score: 50.0,
+        }
+    }
+}
+
+impl HealthThresholds {
+    /// Thresholds object (null = defaults).
+    pub fn parse(value: &Value) -> Result<Self> {
+        if value.is_null() {
+            return Ok(Self::default());
+        }
+        serde_json::from_value(value.clone()).map_err(|e| anyhow!("health thresholds: {e}"))
+    }
+
+    /// SVM_HEALTH_THRESHOLDS (defaults when unset).
+    pub fn from_config(config: &Config) -> Result<Self> {
+        match &config.health_thresholds {
+            Some(text) => Self::parse(&serde_json::from_str(text).map_err(|e| anyhow!("SVM_HEALTH_THRESHOLDS: {e}"))?),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+// ── Scoring ───────────────────────────────────────────────────────────────────
+
+/// What the checks look at.
+#[derive(Debug, Clone, Default)]
+pub struct Inputs {
+    pub ws_connected: bool,
+    /// (failed, total) over the recent executions
+    pub recent: (u64, u64),
+    pub offline_depth: usize,
+    pub vault_reachable: Option<bool>,
+    pub disk_free_bytes: Option<u64>,
+    /// Disk watchdog detail while under pressure
+    pub disk_pressure: Option<String>,
+    /// Audit self-test finding once a segment was quarantined
+    pub audit_corrupted: Option<String>,
+    /// Device watcher detail while a device is unplugged
+    pub devices_missing: Option<String>,
+}
+
+/// Fewer recent executions leave errorRate out.
+pub const MIN_RECENT: u64 = 10;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub check: &'static str,
+    pub score: f64,
+    pub weight: f64,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Score {
+    /// 0..100
+    pub score: f64,
+    pub status: &'static str,
+    pub checks: Vec<Check>,
+}
+
+impl Score {
+    /// Checks pulling the score down.
+    pub fn reasons(&self) -> Vec<&Check> {
+        self.checks.iter().filter(|c| c.score < 1.0 && c.weight > 0.0).collect()
+    }
+}
+
+pub fn score(t: &HealthThresholds, i: &Inputs) -> Score {
+    let w = &t.weights;
+    let mut checks = vec![Check {
+        check: "ws",
+        score: if i.ws_connected { 1.0 } else { 0.0 },
+        weight: w.ws,
+        detail: if i.ws_connected { "connected to central" } else { "central WebSocket down" }.into(),
+    }];
+    let (failed, total) = i.recent;
+    if total >= MIN_RECENT {
+        let rate = failed as f64 / total as f64;
+        checks.push(Check {
+            check: "errorRate",
+            score: t.error_rate.score(rate),
+            weight: w.error_rate,
+            detail: format!("{failed} of the last {total} executions failed ({:.0}%)", rate * 100.0),
+        });
+    }
+    checks.push(Check {
+        check: "offlineDepth",
+        score: t.offline_depth.score(i.offline_depth as f64),
+        weight: w.offline_depth,
+        detail: format!("{} event(s) in the offline buffer (max {})", i.offline_depth, t.offline_depth.max),
+    });
+    if let Some(up) = i.vault_reachable {
+        checks.push(Check {
+            check: "vault",
+            score: if up { 1.0 } else { 0.0 },
+            weight: w.vault,
+            detail: if up { "Vault reachable" } else { "Vault unreachable" }.into(),
+        });
+    }
+    if let Some(free) = i.disk_free_bytes {
+        let DiskFloor { warn, min } = t.disk_free_bytes;
+        let score = if free >= warn {
+            1.0
+        } else if free <= min {
+            0.0
+        } else {
+            (free - min) as f64 / (warn - min) as f64
+        };
+        checks.push(Check {
+            check: "disk",
+            score,
+            weight: w.disk,
+            detail: format!("{} MiB free (min {} MiB)", free >> 20, min >> 20),
+        });
+    }
+
+    if let Some(detail) = &i.disk_pressure {
+        checks.push(Check { check: "diskPressure", score: 0.0, weight: w.disk, detail: detail.clone() });
+    }
+    if let Some(detail) = &i.audit_corrupted {
+        checks.push(Check { check: "auditChain", score: 0.0, weight: w.audit, detail: detail.clone() });
+    }
+    if let Some(detail) = &i.devices_missing {
+        checks.push(Check { check: "devices", score: 0.0, weight: w.devices, detail: detail.clone() });
+    }
+
+    let weight: f64 = checks.iter().map(|c| c.weight.max(0.0)).sum();
+    let score = if weight > 0.0 {
+        checks.iter().map(|c| c.score * c.weight.max(0.0)).sum::<f64>() / weight * 100.0
+    } else {
+        100.0
+    };
+    let status = if score >= t.ok_score && i.disk_pressure.is_none() && i.devices_missing.is_none() {
+        "ok"
+    } else if score >= t.min_score {
+        "degraded"
+    } else {
+        "unhealthy"
+    };
+    Score { score, status, checks }
+}
+
+/// Free bytes on the filesystem holding `path` (None where unsupported).
+pub fn disk_free_bytes(path: &std::path::Path) -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        // SAFETY: `path` is NUL-terminated and `stat` is a valid out-pointer
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+        Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_defaults_keep_the_old_rule_and_thresholds_grade_it() {
+        let defaults = HealthThresholds::default();
+        let healthy = Inputs { ws_connected: true, offline_depth: 999, ..Default::default() };
+        assert_eq!(score(&defaults, &healthy).status, "ok");
+        assert_ne!(score(&defaults, &Inputs { offline_depth: 1000, ..healthy.clone() }).status, "ok");
+        assert_ne!(score(&defaults, &Inputs { ws_connected: false, ..healthy.clone() }).status, "ok");
+        // DISK_PRESSURE: 87.5 points, still not ok
+        let pressure = score(&defaults, &Inputs { disk_pressure: Some("2 MiB free".into()), ..healthy.clone() });
+        assert!((pressure.score - 87.5).abs() < 1e-9 && pressure.status == "degraded");
+        assert_eq!(pressure.reasons()[0].check, "diskPressure");
+        let unplugged = score(&defaults, &Inputs { devices_missing: Some("/dev/ttyUSB0 unplugged".into()), ..healthy.clone() });
+        assert_eq!((unplugged.status, unplugged.reasons()[0].check), ("degraded", "devices"));
+
+        let t = HealthThresholds::parse(&json!({
+            "offlineDepth": { "warn": 100, "max": 500 }, "weights": { "disk": 0 },
+        })).unwrap();
+        assert_eq!(t.weights.ws, 4.0);
+        let inputs = Inputs {
+            ws_connected: true,
+            recent: (5, 20),
+            offline_depth: 300,
+            vault_reachable: Some(false),
+            disk_free_bytes: Some(1),
+            disk_pressure: None,
+            audit_corrupted: None,
+            devices_missing: None,
+        };
+        let s = score(&t, &inputs);
+        // ws 4×1 + errorRate 2×0.625 + offlineDepth 3×0.5 + vault 1×0 over 10
+        assert!((s.score - 67.5).abs() < 1e-9, "{}", s.score);
+        assert_eq!(s.status, "degraded");
+        let reasons: Vec<&str> = s.reasons().iter().map(|c| c.check).collect();
+        assert_eq!(reasons, ["errorRate", "offlineDepth", "vault"]);
+        assert!(HealthThresholds::parse(&json!({ "okScore": "high" })).is_err());
+        assert!(disk_free_bytes(&std::env::temp_dir()).is_some_and(|b| b > 0) || cfg!(not(target_os = "linux")));
+    }
+}
@@ -0,0 +1,419 @@
+//! Device hotplug — USB serial adapters and GPIO chips coming and going
+//!
+//! Gateways reach their RS-485 buses through USB adapters that get
+//! unplugged, re-seated or re-enumerated.  The device watcher follows the
+//! kernel's uevents (NETLINK_KOBJECT_UEVENT, Linux) for the tty, gpio and
+//! usb subsystems and rescans the devices it watches — those under
+//! SVM_DEVICE_ALLOW and the SVM_MCU_PORTS ttys — once a burst of events has
+//! settled (500 ms).  Where the socket cannot be opened it rescans every 5 s.
+//!
+//! On a change:
+//!
+//!   plugged     MCU ports on the device reopen it at once instead of at
+//!               their next retry (mcu.rs)
+//!   either      GPIO lines held on the device are released, so they are
+//!               requested again (connectors/device.rs); capabilities are
+//!               re-probed (CAPABILITIES_UPDATE when connector:serial or
+//!               connector:gpio come or go, capabilities.rs) and /health
+//!               reports the devices missing
+//!
+//! A device is missing when it was seen since start-up (or is a bridged
+//! MCU tty) and is gone now; /health then has a `devices` check at 0 and
+//! `status` is at best "degraded" (health_score.rs).
+//!
+//! Workflows are paused instead of failing mid-slice: before the first
+//! instruction of a slice, and before each instruction driving a device
+//! (gpio://, serial://, mcu://), the SVM waits for a missing device to be
+//! back, up to SVM_DEVICE_WAIT_MS (default 30000, 0 = no wait).  A device
+//! still missing fails the instruction with TIMEOUT (transient) before it
+//! touches anything; devices never seen are left to the connector.
+//!
+//!   SVM_DEVICE_HOTPLUG=false    no watcher
+//!
+//!   eyeflow_device_present{device}          1 while present
+//!   eyeflow_device_events_total{action}     plugged / unplugged
+//!   eyeflow_device_waits_total{outcome}     resumed / timed_out
+
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+use crate::health::{HealthState, MetricsCollector};
+use crate::mcu::McuBridge;
+use crate::svm::Svm;
+
+/// Quiet time after a uevent before rescanning (an adapter is a burst).
+const SETTLE: Duration = Duration::from_millis(500);
+/// Rescan interval without kernel uevents.
+const POLL_EVERY: Duration = Duration::from_secs(5);
+/// Re-check interval of a paused instruction between rescans.
+const WAIT_STEP: Duration = Duration::from_millis(250);
+
+// ── Uevents ───────────────────────────────────────────────────────────────────
+
+/// A kernel uevent: "ACTION@DEVPATH\0KEY=VALUE\0…".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Uevent {
+    pub action: String,
+    pub subsystem: String,
+    pub devname: Option<String>,
+}
+
+impl Uevent {
+    pub fn parse(buf: &[u8]) -> Option<Self> {
+        let mut fields = buf.split(|b| *b == 0).map(String::from_utf8_lossy);
+        if !fields.next()?.contains('@') {
+            return None;
+        }
+        let mut event = Self { action: String::new(), subsystem: String::new(), devname: None };
+        for field in fields {
+            match field.split_once('=') {
+                Some(("ACTION", v)) => event.action = v.to_owned(),
+                Some(("SUBSYSTEM", v)) => event.subsystem = v.to_owned(),
+                Some(("DEVNAME", v)) => event.devname = Some(v.to_owned()),
+                _ => {}
+            }
+        }
+        (!event.action.is_empty()).then_some(event)
+    }
+
+    /// Whether the event can add or remove a watched device.
+    fn relevant(&self) -> bool {
+        matches!(self.action.as_str(), "add" | "remove" | "bind" | "unbind")
+            && matches!(self.subsystem.as_str(), "tty" | "gpio" | "usb" | "usb-serial")
+    }
+}
+
+#[cfg(target_os = "linux")]
+struct UeventSocket(tokio::io::unix::AsyncFd<std::os::fd::OwnedFd>);
+
+#[cfg(target_os = "linux")]
+impl UeventSocket {
+    /// Subscribe to the kernel's uevent multicast group.
+    fn open() -> std::io::Result<Self> {
+        use std::os::fd::FromRawFd;
+        // SAFETY: plain socket(2) / bind(2); the fd is owned right away
+        let fd = unsafe {
+            let fd = libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC | libc::SOCK_NONBLOCK,
+                libc::NETLINK_KOBJECT_UEVENT,
+            );
+            if fd < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            std::os::fd::OwnedFd::from_raw_fd(fd)
+        };
+        // SAFETY: a zeroed sockaddr_nl is valid; its size is passed along
+        let bound = unsafe {
+            use std::os::fd::AsRawFd;
+            let mut addr: libc::sockaddr_nl = std::mem::zeroed();
+            addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+            addr.nl_groups = 1;
+            libc::bind(
+                fd.as_raw_fd(),
+                (&addr as *const libc::sockaddr_nl).cast(),
+                std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if bound != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Self(tokio::io::unix::AsyncFd::new(fd)?))
+    }
+
+    async fn recv(&self, buf: &mut [u8]) -> std::io::Result<usize> {
+        use std::os::fd::AsRawFd;
+        loop {
+            let mut guard = self.0.readable().await?;
+            let read = guard.try_io(|fd| {
+                // SAFETY: `buf` is valid for `buf.len()` bytes
+                let n = unsafe { libc::recv(fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0) };
+                if n < 0 { Err(std::io::Error::last_os_error()) } else { Ok(n as usize) }
+            });
+            if let Ok(result) = read {
+                return result;
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+struct UeventSocket;
+
+#[cfg(not(target_os = "linux"))]
+impl UeventSocket {
+    fn open() -> std::io::Result<Self> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "uevents are Linux-only"))
+    }
+
+    async fn recv(&self, _buf: &mut [u8]) -> std::io::Result<usize> {
+        std::future::pending().await
+    }
+}
+
+// ── Watcher ───────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+struct Devices {
+    /// Seen since start-up, plus the bridged MCU ttys
+    known: BTreeSet<String>,
+    /// Present at the last rescan
+    present: BTreeSet<String>,
+}
+
+pub struct Hotplug {
+    allow: Vec<String>,
+    /// Bridged MCU ttys
+    mcu: Vec<String>,
+    wait: Duration,
+    devices: Mutex<Devices>,
+    /// Signalled after every rescan
+    rescanned: Notify,
+    plugged: AtomicU64,
+    unplugged: AtomicU64,
+    resumed: AtomicU64,
+    timed_out: AtomicU64,
+}
+
+impl Hotplug {
+    /// Watch the devices under `allow` and the MCU ttys `mcu` (scanned now).
+    pub fn new(allow: Vec<String>, mcu: Vec<String>, wait: Duration) -> Arc<Self> {
+        let hotplug = Arc::new(Self {
+            devices: Mutex::new(Devices { known: mcu.iter().cloned().collect(), ..Devices::default() }),
+            allow,
+            mcu,
+            wait,
+            rescanned: Notify::new(),
+            plugged: AtomicU64::new(0),
+            unplugged: AtomicU64::new(0),
+            resumed: AtomicU64::new(0),
+            timed_out: AtomicU64::new(0),
+        });
+        hotplug.rescan();
+        hotplug
+    }
+
+    /// None with SVM_DEVICE_HOTPLUG=false or nothing to watch.
+    pub fn from_config(config: &Config, mcu: Option<&McuBridge>) -> Option<Arc<Self>> {
+        let mcu = mcu.map(McuBridge::devices).unwrap_or_default();
+        if !config.device_hotplug || (config.device_allow.is_empty() && mcu.is_empty()) {
+            return None;
+        }
+        Some(Self::new(config.device_allow.clone(), mcu, Duration::from_millis(config.device_wait_ms)))
+    }
+
+    /// Rescan the watched devices; returns those (plugged, unplugged) since
+    /// the last rescan.
+    pub fn rescan(&self) -> (Vec<String>, Vec<String>) {
+        let mut now: BTreeSet<String> = crate::capabilities::devices(&self.allow).into_iter().collect();
+        now.extend(self.mcu.iter().filter(|d| Path::new(d.as_str()).exists()).cloned());
+        let mut devices = self.devices.lock().unwrap_or_else(|e| e.into_inner());
+        let plugged: Vec<String> = now.difference(&devices.present).cloned().collect();
+        let unplugged: Vec<String> = devices.present.difference(&now).cloned().collect();
+        devices.known.extend(now.iter().cloned());
+        devices.present = now;
+        drop(devices);
+        if !plugged.is_empty() {
+            info!("[Hotplug] plugged: {}", plugged.join(", "));
+        }
+        if !unplugged.is_empty() {
+            warn!("[Hotplug] unplugged: {}", unplugged.join(", "));
+        }
+        self.plugged.fetch_add(plugged.len() as u64, Ordering::Relaxed);
+        self.unplugged.fetch_add(unplugged.len() as u64, Ordering::Relaxed);
+        self.rescanned.notify_waiters();
+        (plugged, unplugged)
+    }
+
+    /// Known devices absent now.
+    fn missing(&self) -> Vec<String> {
+        let devices = self.devices.lock().unwrap_or_else(|e| e.into_inner());
+        devices.known.difference(&devices.present).cloned().collect()
+    }
+
+    /// /health reason while a device is missing.
+    pub fn detail(&self) -> Option<String> {
+        let missing = self.missing();
+        (!missing.is_empty()).then(|| format!("unplugged: {}", missing.join(", ")))
+    }
+
+    /// False for a known device that is gone (its node removed, or not back
+    /// at the last rescan).
+    fn is_present(&self, device: &str) -> bool {
+        let devices = self.devices.lock().unwrap_or_else(|e| e.into_inner());
+        !devices.known.contains(device) || (devices.present.contains(device) && Path::new(device).exists())
+    }
+
+    /// Wait up to SVM_DEVICE_WAIT_MS for `device` to be back if it is missing.
+    pub async fn wait_present(&self, device: &str) -> Result<()> {
+        if self.is_present(device) {
+            return Ok(());
+        }
+        info!("[Hotplug] {device} is unplugged — pausing up to {:?}", self.wait);
+        let deadline = Instant::now() + self.wait;
+        loop {
+            let rescanned = self.rescanned.notified();
+            if self.is_present(device) {
+                self.resumed.fetch_add(1, Ordering::Relaxed);
+                info!("[Hotplug] {device} is back — resuming");
+                return Ok(());
+            }
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                self.timed_out.fetch_add(1, Ordering::Relaxed);
+                return Err(ExecError::new(
+                    ErrorCode::Timeout,
+                    format!("{device} is unplugged and was not back within {:?}", self.wait),
+                ).into());
+            }
+            let _ = tokio::time::timeout(left.min(WAIT_STEP), rescanned).await;
+        }
+    }
+}
+
+impl MetricsCollector for Hotplug {
+    fn prometheus(&self, node_id: &str) -> String {
+        let mut present = String::new();
+        {
+            let devices = self.devices.lock().unwrap_or_else(|e| e.into_inner());
+            for device in &devices.known {
+                present.push_str(&format!(
+                    "eyeflow_device_present{{node_id=\"{node_id}\",device=\"{device}\"}} {}\n",
+                    u8::from(devices.present.contains(device)),
+                ));
+            }
+        }
+        let counter = |c: &AtomicU64| c.load(Ordering::Relaxed);
+        format!(
+            "# HELP eyeflow_device_present Whether a watched device is present\n\
+             # TYPE eyeflow_device_present gauge\n\
+             {present}\
+             # HELP eyeflow_device_events_total Devices plugged / unplugged since start-up\n\
+             # TYPE eyeflow_device_events_total counter\n\
+             eyeflow_device_events_total{{node_id=\"{node_id}\",action=\"plugged\"}} {}\n\
+             eyeflow_device_events_total{{node_id=\"{node_id}\",action=\"unplugged\"}} {}\n\
+             # HELP eyeflow_device_waits_total Instructions paused on an unplugged device\n\
+             # TYPE eyeflow_device_waits_total counter\n\
+             eyeflow_device_waits_total{{node_id=\"{node_id}\",outcome=\"resumed\"}} {}\n\
+             eyeflow_device_waits_total{{node_id=\"{node_id}\",outcome=\"timed_out\"}} {}\n",
+            counter(&self.plugged),
+            counter(&self.unplugged),
+            counter(&self.resumed),
+            counter(&self.timed_out),
+        )
+    }
+}
+
+// ── Watcher task ──────────────────────────────────────────────────────────────
+
+/// Wait for a relevant uevent (then for the burst to settle), or POLL_EVERY
+/// without a socket.
+async fn next_change(socket: Option<&UeventSocket>, buf: &mut [u8]) {
+    let Some(socket) = socket else {
+        return tokio::time::sleep(POLL_EVERY).await;
+    };
+    loop {
+        match socket.recv(buf).await {
+            Ok(n) if Uevent::parse(&buf[..n]).is_some_and(|e| e.relevant()) => break,
+            Ok(_) => {}
+            // ENOBUFS: events were dropped, rescan anyway
+            Err(e) => {
+                warn!("[Hotplug] uevent socket: {e}");
+                break;
+            }
+        }
+    }
+    let settle = tokio::time::sleep(SETTLE);
+    tokio::pin!(settle);
+    loop {
+        tokio::select! {
+            _ = &mut settle => return,
+            r = socket.recv(buf) => if r.is_err() { return },
+        }
+    }
+}
+
+/// Rescan on every device change; re-bind connectors, re-probe capabilities
+/// and update /health when the devices differ.
+pub async fn run(hotplug: Arc<Hotplug>, svm: Arc<Svm>, health: Arc<HealthState>) {
+    health.set_devices_missing(hotplug.detail());
+    let socket = UeventSocket::open()
+        .map_err(|e| warn!("[Hotplug] kernel uevents unavailable ({e}) — rescanning every {POLL_EVERY:?}"))
+        .ok();
+    let mut buf = vec![0u8; 8192];
+    loop {
+        next_change(socket.as_ref(), &mut buf).await;
+        let h = hotplug.clone();
+        let Ok((plugged, unplugged)) = tokio::task::spawn_blocking(move || h.rescan()).await else {
+            continue;
+        };
+        if plugged.is_empty() && unplugged.is_empty() {
+            continue;
+        }
+        for device in &unplugged {
+            svm.rebind_device(device, false);
+        }
+        for device in &plugged {
+            svm.rebind_device(device, true);
+        }
+        health.set_devices_missing(hotplug.detail());
+        svm.capabilities().refresh().await;
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unplugged_device_pauses_until_it_is_back() {
+        let event = Uevent::parse(b"add@/devices/usb1/1-1/1-1:1.0/ttyUSB0/tty/ttyUSB0\0ACTION=add\0SUBSYSTEM=tty\0DEVNAME=ttyUSB0\0").unwrap();
+        assert!(event.relevant() && event.devname.as_deref() == Some("ttyUSB0"));
+        assert!(Uevent::parse(b"libudev\0\xfe\xed").is_none());
+        assert!(!Uevent::parse(b"change@/x\0ACTION=change\0SUBSYSTEM=power_supply\0").unwrap().relevant());
+
+        let dir = std::env::temp_dir().join(format!("eyeflow-hotplug-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let adapter = dir.join("ttyUSB0").display().to_string();
+        std::fs::write(&adapter, "").unwrap();
+        let mcu = dir.join("ttyACM0").display().to_string();
+        let hotplug = Hotplug::new(vec![format!("{}/ttyUSB", dir.display())], vec![mcu.clone()], Duration::from_secs(5));
+        // The MCU tty was never there: missing from the start
+        assert_eq!(hotplug.detail(), Some(format!("unplugged: {mcu}")));
+        std::fs::write(&mcu, "").unwrap();
+        assert_eq!(hotplug.rescan(), (vec![mcu.clone()], vec![]));
+        assert_eq!(hotplug.detail(), None);
+        hotplug.wait_present("/dev/never-seen").await.unwrap();
+
+        // Unplugged mid-workflow: the instruction waits for the adapter
+        std::fs::remove_file(&adapter).unwrap();
+        assert_eq!(hotplug.rescan(), (vec![], vec![adapter.clone()]));
+        let waiting = tokio::spawn({
+            let (hotplug, adapter) = (hotplug.clone(), adapter.clone());
+            async move { hotplug.wait_present(&adapter).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+        std::fs::write(&adapter, "").unwrap();
+        hotplug.rescan();
+        waiting.await.unwrap().unwrap();
+
+        let impatient = Hotplug::new(vec![], vec![dir.join("ttyACM9").display().to_string()], Duration::ZERO);
+        let err = impatient.wait_present(&dir.join("ttyACM9").display().to_string()).await.unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::Timeout);
+        let metrics = hotplug.prometheus("n1");
+        assert!(metrics.contains("eyeflow_device_waits_total{node_id=\"n1\",outcome=\"resumed\"} 1"));
+        assert!(metrics.contains("eyeflow_device_events_total{node_id=\"n1\",action=\"unplugged\"} 1"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,385 @@
+//! HTTP response cache — idempotent CALL_SERVICE / LOAD_RESOURCE requests
+//!
+//! Periodic workflows poll slow upstream APIs every few seconds, mostly for
+//! data that has not changed.  HTTP answers are kept keyed by SHA-256 over
+//! method + URL + request body and reused while fresh:
+//!
+//!   freshness    the upstream's Cache-Control max-age (else Expires);
+//!                `no-store` is never cached, `no-cache` always revalidates
+//!   revalidate   a stale entry with an ETag / Last-Modified is revisited with
+//!                If-None-Match / If-Modified-Since; a 304 refreshes it
+//!   methods      GET and HEAD; POST only when the instruction sets ttlSecs
+//!
+//! Per instruction:
+//!
+//!   operands_json = { "httpCache": false }                 never cache
+//!   operands_json = { "httpCache": { "ttlSecs": 30 } }     fresh for 30 s,
+//!                                                          whatever the upstream says
+//!
+//! Unlike `cacheTtlSecs` (response_cache.rs), which caches an instruction's
+//! final result on request, this layer works on by default and follows the
+//! upstream's own caching rules.  Async-polled (202) operations bypass it.
+//!
+//! SVM_HTTP_CACHE_SIZE entries are held in memory (LRU, default 256,
+//! 0 = disabled); with SVM_HTTP_CACHE_DIR set, entries are also written
+//! there so they survive restarts.
+
+use anyhow::Result;
+use reqwest::header::{HeaderMap, CACHE_CONTROL, ETAG, EXPIRES, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+use crate::config::Config;
+use crate::errors::ExecError;
+use crate::fastjson;
+use crate::health::MetricsCollector;
+
+// ── Per-instruction rule ──────────────────────────────────────────────────────
+
+/// Whether, and for how long, an instruction's request may be cached.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheRule {
+    /// Freshness overriding the upstream's headers
+    pub ttl: Option<Duration>,
+}
+
+impl CacheRule {
+    /// Rule for a `method` request of an instruction with `operands` (None =
+    /// not cacheable).
+    pub fn for_request(operands: &Value, method: &str) -> Option<Self> {
+        let ttl = match operands.get("httpCache") {
+            Some(Value::Bool(false)) => return None,
+            Some(spec) => spec.get("ttlSecs").and_then(Value::as_u64).map(Duration::from_secs),
+            None => None,
+        };
+        match method.to_ascii_uppercase().as_str() {
+            "GET" | "HEAD" | "" => Some(Self { ttl }),
+            "POST" if ttl.is_some() => Some(Self { ttl }),
+            _ => None,
+        }
+    }
+}
+
+// ── Entries ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    body: Value,
+    /// Unix ms after which the entry must be revalidated
+    fresh_until_ms: u64,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    #[serde(skip)]
+    last_used: u64,
+}
+
+impl Entry {
+    fn validators(&self) -> Vec<(&'static str, String)> {
+        let mut v = Vec::new();
+        if let Some(etag) = &self.etag {
+            v.push(("If-None-Match", etag.clone()));
+        }
+        if let Some(lm) = &self.last_modified {
+            v.push(("If-Modified-Since", lm.clone()));
+        }
+        v
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+/// How long an answer with `headers` stays fresh (None = do not store).
+fn freshness(headers: &HeaderMap, rule: CacheRule) -> Option<Duration> {
+    let cc = headers.get(CACHE_CONTROL).and_then(|v| v.to_str().ok()).unwrap_or("").to_ascii_lowercase();
+    let directives: Vec<&str> = cc.split(',').map(str::trim).collect();
+    if directives.contains(&"no-store") {
+        return None;
+    }
+    if let Some(ttl) = rule.ttl {
+        return Some(ttl);
+    }
+    if directives.contains(&"no-cache") {
+        return Some(Duration::ZERO);
+    }
+    if let Some(secs) = directives.iter().find_map(|d| d.strip_prefix("max-age=")).and_then(|s| s.parse().ok()) {
+        return Some(Duration::from_secs(secs));
+    }
+    let expires = headers.get(EXPIRES).and_then(|v| v.to_str().ok())
+        .and_then(|v| chrono::DateTime::parse_from_rfc2822(v).ok())?;
+    let left = expires.timestamp_millis() - now_ms() as i64;
+    Some(Duration::from_millis(left.max(0) as u64))
+}
+
+// ── Cache ─────────────────────────────────────────────────────────────────────
+
+pub struct HttpCache {
+    capacity: usize,
+    dir: Option<PathBuf>,
+    entries: Mutex<HashMap<String, Entry>>,
+    tick: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    revalidated: AtomicU64,
+}
+
+impl HttpCache {
+    pub fn new(capacity: usize, dir: Option<PathBuf>) -> Arc<Self> {
+        if let Some(d) = &dir {
+            if let Err(e) = std::fs::create_dir_all(d) {
+                warn!("[HttpCache] {}: {e} — disk cache disabled", d.display());
+            }
+        }
+        Arc::new(Self {
+            capacity,
+            dir,
+            entries: Mutex::new(HashMap::new()),
+            tick: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            revalidated: AtomicU64::new(0),
+        })
+    }
+
+    pub fn from_config(config: &Config) -> Arc<Self> {
+        Self::new(config.http_cache_size, config.http_cache_dir.as_ref().map(PathBuf::from))
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Cache key of a request.
+    pub fn key(method: &str, url: &str, body: Option<&Value>) -> String {
+        let body_hash = body.map(|b| hex::encode(Sha256::digest(b.to_string().as_bytes()))).unwrap_or_default();
+        let method = if method.is_empty() { "GET".to_owned() } else { method.to_ascii_uppercase() };
+        hex::encode(Sha256::digest(format!("{method} {url}\n{body_hash}").as_bytes()))
+    }
+
+    /// JSON body of the request `key`: from the cache while fresh, else from
+    /// `send` (given the conditional headers to add).  Non-2xx answers are
+    /// errors attributed to `what`.
+    pub async fn fetch<F, Fut>(&self, key: &str, rule: CacheRule, what: &str, send: F) -> Result<Value>
+    where
+        F: FnOnce(Vec<(&'static str, String)>) -> Fut,
+        Fut: Future<Output = Result<reqwest::Response>>,
+    {
+        let cached = self.get(key).await;
+        if let Some(e) = cached.as_ref().filter(|e| e.fresh_until_ms > now_ms()) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            debug!("[HttpCache] hit {what}");
+            return Ok(e.body.clone());
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let resp = send(cached.as_ref().map(Entry::validators).unwrap_or_default()).await?;
+        let status = resp.status();
+
+        if status == StatusCode::NOT_MODIFIED {
+            if let Some(mut entry) = cached {
+                self.revalidated.fetch_add(1, Ordering::Relaxed);
+                debug!("[HttpCache] {what} not modified");
+                let body = entry.body.clone();
+                if let Some(ttl) = freshness(resp.headers(), rule) {
+                    entry.fresh_until_ms = now_ms() + ttl.as_millis() as u64;
+                    self.put(key, entry).await;
+                }
+                return Ok(body);
+            }
+        }
+        if !status.is_success() {
+            return Err(ExecError::http(what, status));
+        }
+        let headers = resp.headers().clone();
+        let body = fastjson::response_body(resp).await;
+        if status == StatusCode::OK {
+            match freshness(&headers, rule) {
+                Some(ttl) => {
+                    let header = |name| headers.get(name).and_then(|v: &reqwest::header::HeaderValue| v.to_str().ok()).map(str::to_owned);
+                    self.put(key, Entry {
+                        body: body.clone(),
+                        fresh_until_ms: now_ms() + ttl.as_millis() as u64,
+                        etag: header(ETAG),
+                        last_modified: header(LAST_MODIFIED),
+                        last_used: 0,
+                    }).await;
+                }
+                None => self.remove(key).await,
+            }
+        }
+        Ok(body)
+    }
+
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|d| d.join(format!("{key}.json")))
+    }
+
+    async fn get(&self, key: &str) -> Option<Entry> {
+        let tick = self.tick.fetch_add(1, Ordering::Relaxed);
+        if let Some(e) = self.entries.lock().unwrap_or_else(|e| e.into_inner()).get_mut(key) {
+            e.last_used = tick;
+            return Some(e.clone());
+        }
+        let path = self.path(key)?;
+        let entry: Entry = serde_json::from_slice(&tokio::fs::read(&path).await.ok()?).ok()?;
+        // Expired and nothing to revalidate with: useless
+        if entry.fresh_until_ms <= now_ms() && entry.etag.is_none() && entry.last_modified.is_none() {
+            let _ = tokio::fs::remove_file(&path).await;
+            return None;
+        }
+        self.insert(key, entry.clone());
+        Some(entry)
+    }
+
+    async fn put(&self, key: &str, entry: Entry) {
+        if let Some(path) = self.path(key) {
+            let tmp = path.with_extension("tmp");
+            let write = async {
+                tokio::fs::write(&tmp, serde_json::to_vec(&entry)?).await?;
+                tokio::fs::rename(&tmp, &path).await?;
+                anyhow::Ok(())
+            };
+            if let Err(e) = write.await {
+                warn!("[HttpCache] writing {}: {e}", path.display());
+            }
+        }
+        self.insert(key, entry);
+    }
+
+    fn insert(&self, key: &str, mut entry: Entry) {
+        entry.last_used = self.tick.fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= self.capacity && !entries.contains_key(key) {
+            let lru = entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k.clone());
+            if let Some(k) = lru {
+                entries.remove(&k);
+            }
+        }
+        entries.insert(key.to_owned(), entry);
+    }
+
+    async fn remove(&self, key: &str) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).remove(key);
+        if let Some(path) = self.path(key) {
+            let _ = tokio::fs::remove_file(path).await;
+        }
+    }
+}
+
+impl MetricsCollector for HttpCache {
+    fn prometheus(&self, node_id: &str) -> String {
+        let size = self.entries.lock().map(|e| e.len()).unwrap_or(0);
+        format!(
+            "# HELP eyeflow_http_cache_hits_total HTTP requests answered from the cache\n\
+             # TYPE eyeflow_http_cache_hits_total counter\n\
+             eyeflow_http_cache_hits_total{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_http_cache_misses_total Cacheable HTTP requests sent upstream\n\
+             # TYPE eyeflow_http_cache_misses_total counter\n\
+             eyeflow_http_cache_misses_total{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_http_cache_revalidated_total Stale entries confirmed by a 304\n\
+             # TYPE eyeflow_http_cache_revalidated_total counter\n\
+             eyeflow_http_cache_revalidated_total{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_http_cache_entries HTTP responses held in memory\n\
+             # TYPE eyeflow_http_cache_entries gauge\n\
+             eyeflow_http_cache_entries{{node_id=\"{node_id}\"}} {size}\n",
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+            self.revalidated.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_rules_and_freshness() {
+        assert_eq!(CacheRule::for_request(&Value::Null, "get"), Some(CacheRule { ttl: None }));
+        assert_eq!(CacheRule::for_request(&json!({ "httpCache": false }), "GET"), None);
+        assert_eq!(CacheRule::for_request(&Value::Null, "POST"), None);
+        let post = CacheRule::for_request(&json!({ "httpCache": { "ttlSecs": 30 } }), "POST").unwrap();
+        assert_eq!(post.ttl, Some(Duration::from_secs(30)));
+
+        let headers = |cc: &str| {
+            let mut h = HeaderMap::new();
+            h.insert(CACHE_CONTROL, cc.parse().unwrap());
+            h
+        };
+        let plain = CacheRule { ttl: None };
+        assert_eq!(freshness(&headers("public, max-age=60"), plain), Some(Duration::from_secs(60)));
+        assert_eq!(freshness(&headers("no-cache"), plain), Some(Duration::ZERO));
+        assert_eq!(freshness(&headers("no-store"), post), None);
+        assert_eq!(freshness(&headers("max-age=5"), post), Some(Duration::from_secs(30)));
+        assert_eq!(freshness(&HeaderMap::new(), plain), None);
+        assert_ne!(HttpCache::key("GET", "http://a/x", None), HttpCache::key("GET", "http://a/y", None));
+    }
+
+    #[tokio::test]
+    async fn test_serves_fresh_and_revalidates_stale() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            let answers = [
+                ("200 OK\r\nCache-Control: no-cache\r\nETag: \"v1\"", r#"{"level":7}"#),
+                ("304 Not Modified\r\nETag: \"v1\"", ""),
+            ];
+            for (head, body) in answers {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 2048];
+                let n = sock.read(&mut buf).await.unwrap();
+                seen.lock().unwrap().push(String::from_utf8_lossy(&buf[..n]).to_lowercase());
+                let answer = format!(
+                    "HTTP/1.1 {head}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len(),
+                );
+                sock.write_all(answer.as_bytes()).await.unwrap();
+            }
+        });
+
+        let dir = std::env::temp_dir().join(format!("eyeflow-http-cache-{}", uuid::Uuid::new_v4()));
+        let cache = HttpCache::new(8, Some(dir.clone()));
+        let http = reqwest::Client::new();
+        let url = format!("http://{addr}/tank");
+        let key = HttpCache::key("GET", &url, None);
+        let get = |cond: Vec<(&'static str, String)>| {
+            let mut req = http.get(&url);
+            for (k, v) in cond {
+                req = req.header(k, v);
+            }
+            async move { Ok(req.send().await?) }
+        };
+        let rule = CacheRule { ttl: None };
+        assert_eq!(cache.fetch(&key, rule, "GET tank", get).await.unwrap(), json!({ "level": 7 }));
+        // no-cache: revalidated with the ETag, answered 304 from the cache
+        assert_eq!(cache.fetch(&key, rule, "GET tank", get).await.unwrap(), json!({ "level": 7 }));
+        assert!(requests.lock().unwrap()[1].contains("if-none-match: \"v1\""));
+        assert_eq!(cache.revalidated.load(Ordering::Relaxed), 1);
+
+        // Fresh entries come from disk after a restart, without a request
+        let mut entry = cache.entries.lock().unwrap().get(&key).cloned().unwrap();
+        entry.fresh_until_ms = now_ms() + 60_000;
+        cache.put(&key, entry).await;
+        let restarted = HttpCache::new(8, Some(dir.clone()));
+        let body = restarted.fetch(&key, rule, "GET tank", |_| async { unreachable!() }).await.unwrap();
+        assert_eq!(body, json!({ "level": 7 }));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
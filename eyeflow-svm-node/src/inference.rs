@@ -0,0 +1,350 @@
+//! ONNX inference — CALL_SERVICE format ONNX (feature `onnx`)
+//!
+//! Anomaly detection and classification run next to the sensor, without a
+//! cloud round trip.  The model is a signed asset (assets.rs) referenced by
+//! the instruction, which feeds it its input register:
+//!
+//!   dispatch_metadata  { "format": ONNX, "endpoint_url": "onnx://weld-defects",
+//!                        "assets": [{ "name": "weld-defects", "kind": "onnx", "sha256": "<hex>" }] }
+//!   operands_json      {
+//!     "inputName": "images",            default: the model's first input
+//!     "shape":  [1, 16],                default: the register's own shape
+//!     "image":  { "width": 224, "height": 224, "layout": "nchw",
+//!                 "mean": [0.485, 0.456, 0.406], "std": [0.229, 0.224, 0.225] },
+//!     "labels": ["ok", "porosity", "crack"]
+//!   }
+//!
+//! Without an `onnx` asset, a `model` operand { "sha256", "url", "signature" }
+//! names it instead; the model is then downloaded into the asset cache on
+//! first use, under the same checksum and signature checks.
+//!
+//! The input register is a (nested) array of numbers, `{ "shape", "data" }`,
+//! or — with `image` — a binary register (blob.rs, e.g. a camera snapshot)
+//! decoded, resized and scaled to 0..1 RGB before mean / std.  The answer:
+//!
+//!   { "model": "weld-defects", "outputs": { "<name>": { "shape", "data" } },
+//!     "label": "crack", "class": 2, "score": 0.93 }     (last three with labels)
+//!
+//! SVM_ONNX_MAX_SESSIONS loaded models are kept (default 4).  ONNX Runtime
+//! itself is loaded at run time from ORT_DYLIB_PATH (default
+//! libonnxruntime.so); when it cannot be, or the node was built without
+//! `--features onnx`, the instruction fails with UNSUPPORTED.
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+
+use crate::assets::{AssetSpec, AssetStore};
+use crate::blob::BlobStore;
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+use crate::proto::llmir::DispatchMetadata;
+
+/// The model an instruction runs: its `onnx` asset in
+/// dispatch_metadata.assets, else the `model` operand.
+pub fn model_spec(dm: &DispatchMetadata, operands: &Value) -> Result<AssetSpec> {
+    let asset = dm.assets.iter().find(|a| a.kind == "onnx").or(dm.assets.first());
+    match (asset, operands.get("model")) {
+        (Some(a), _) => Ok(AssetSpec { name: a.name.clone(), kind: a.kind.clone(), sha256: a.sha256.clone(), ..Default::default() }),
+        (None, Some(model)) => {
+            let mut spec = AssetSpec::from_json(model)?;
+            spec.kind = "onnx".into();
+            Ok(spec)
+        }
+        (None, None) => Err(ExecError::validation("ONNX: no model — set dispatch_metadata.assets or operands.model")),
+    }
+}
+
+// ── Tensors ───────────────────────────────────────────────────────────────────
+
+/// A dense f32 tensor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tensor {
+    pub shape: Vec<i64>,
+    pub data: Vec<f32>,
+}
+
+impl Tensor {
+    /// `{ "shape", "data" }` or nested arrays; `shape` reshapes the data.
+    pub fn from_json(value: &Value, shape: Option<Vec<i64>>) -> Result<Self> {
+        let (own_shape, data) = match value {
+            Value::Object(obj) if obj.contains_key("data") => {
+                let mut t = Self::from_json(&obj["data"], None)?;
+                if let Some(s) = obj.get("shape").and_then(shape_of) {
+                    t.shape = s;
+                }
+                (t.shape, t.data)
+            }
+            _ => {
+                let mut shape = Vec::new();
+                let mut data = Vec::new();
+                flatten(value, 0, &mut shape, &mut data)?;
+                (shape, data)
+            }
+        };
+        let shape = shape.unwrap_or(own_shape);
+        let len: i64 = shape.iter().product();
+        if shape.iter().any(|d| *d < 0) || len as usize != data.len() {
+            return Err(ExecError::validation(format!("ONNX: shape {shape:?} does not hold {} value(s)", data.len())));
+        }
+        Ok(Self { shape, data })
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({ "shape": self.shape, "data": self.data })
+    }
+
+    /// RGB pixels (`width`·`height`·3 bytes) as a batch of one, scaled to
+    /// 0..1 then normalised by the `image` operand's mean / std.
+    #[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+    pub fn from_rgb(rgb: &[u8], width: u32, height: u32, image: &Value) -> Result<Self> {
+        let channel = |k: &str, default: f32| -> Result<[f32; 3]> {
+            match image.get(k) {
+                None => Ok([default; 3]),
+                Some(v) => v.as_array()
+                    .filter(|a| a.len() == 3)
+                    .and_then(|a| Some([a[0].as_f64()? as f32, a[1].as_f64()? as f32, a[2].as_f64()? as f32]))
+                    .ok_or_else(|| ExecError::validation(format!("ONNX: image.{k} must be three numbers"))),
+            }
+        };
+        let (mean, std) = (channel("mean", 0.0)?, channel("std", 1.0)?);
+        let (w, h) = (width as usize, height as usize);
+        let nhwc = image.get("layout").and_then(Value::as_str) == Some("nhwc");
+        let mut data = vec![0.0; w * h * 3];
+        for (i, px) in rgb.chunks_exact(3).take(w * h).enumerate() {
+            for c in 0..3 {
+                let v = (px[c] as f32 / 255.0 - mean[c]) / std[c];
+                data[if nhwc { i * 3 + c } else { c * w * h + i }] = v;
+            }
+        }
+        let shape = if nhwc { vec![1, h as i64, w as i64, 3] } else { vec![1, 3, h as i64, w as i64] };
+        Ok(Self { shape, data })
+    }
+}
+
+fn shape_of(v: &Value) -> Option<Vec<i64>> {
+    v.as_array()?.iter().map(Value::as_i64).collect()
+}
+
+fn flatten(v: &Value, depth: usize, shape: &mut Vec<i64>, data: &mut Vec<f32>) -> Result<()> {
+    match v {
+        Value::Array(items) => {
+            match shape.get(depth) {
+                None => shape.push(items.len() as i64),
+                Some(&len) if len != items.len() as i64 => {
+                    return Err(ExecError::validation(format!("ONNX: ragged input at depth {depth}")));
+                }
+                Some(_) => {}
+            }
+            items.iter().try_for_each(|item| flatten(item, depth + 1, shape, data))
+        }
+        Value::Number(n) if depth == shape.len() => {
+            data.push(n.as_f64().unwrap_or_default() as f32);
+            Ok(())
+        }
+        Value::Bool(b) if depth == shape.len() => {
+            data.push(if *b { 1.0 } else { 0.0 });
+            Ok(())
+        }
+        other => Err(ExecError::validation(format!("ONNX: input must be numbers, got {other}"))),
+    }
+}
+
+/// The answer: every output, plus the top class of the first one when the
+/// operands name `labels`.
+pub fn answer(model: &str, outputs: &[(String, Tensor)], labels: Option<&Vec<Value>>) -> Value {
+    let mut out = Map::new();
+    out.insert("model".into(), json!(model));
+    out.insert("outputs".into(), outputs.iter().map(|(name, t)| (name.clone(), t.to_json())).collect::<Map<_, _>>().into());
+    let top = outputs.first().and_then(|(_, t)| {
+        t.data.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1))
+    });
+    if let (Some(labels), Some((class, score))) = (labels, top) {
+        out.insert("label".into(), labels.get(class).cloned().unwrap_or(Value::Null));
+        out.insert("class".into(), json!(class));
+        out.insert("score".into(), json!(score));
+    }
+    Value::Object(out)
+}
+
+// ── Runtime ───────────────────────────────────────────────────────────────────
+
+pub struct Inference {
+    #[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+    assets: Arc<AssetStore>,
+    blobs: Arc<BlobStore>,
+    #[cfg(feature = "onnx")]
+    sessions: std::sync::Mutex<Vec<(String, Arc<std::sync::Mutex<ort::session::Session>>)>>,
+    #[cfg(feature = "onnx")]
+    max_sessions: usize,
+}
+
+impl Inference {
+    #[cfg_attr(not(feature = "onnx"), allow(unused_variables))]
+    pub fn new(config: &Config, assets: Arc<AssetStore>, blobs: Arc<BlobStore>) -> Self {
+        Self {
+            assets,
+            blobs,
+            #[cfg(feature = "onnx")]
+            sessions: std::sync::Mutex::new(Vec::new()),
+            #[cfg(feature = "onnx")]
+            max_sessions: config.onnx_max_sessions.max(1),
+        }
+    }
+
+    /// Run the model of `dm` (endpoint `onnx://<name>`) on `input`.
+    pub async fn run(&self, dm: &DispatchMetadata, operands: &Value, input: Option<&Value>) -> Result<Value> {
+        let name = dm.endpoint_url.strip_prefix("onnx://").unwrap_or(&dm.endpoint_url);
+        let spec = model_spec(dm, operands)?;
+        let input = input.unwrap_or(&Value::Null);
+        let shape = operands.get("shape").and_then(shape_of);
+        let tensor = match (operands.get("image"), self.blobs.resolve(input)?) {
+            (Some(image), Some(blob)) => decode_image(&blob.bytes, image)?,
+            (None, Some(_)) => return Err(ExecError::validation("ONNX: a binary input needs the `image` operand")),
+            (_, None) => Tensor::from_json(input, shape)?,
+        };
+        let input_name = operands.get("inputName").and_then(Value::as_str).map(str::to_owned);
+        let outputs = self.infer(&spec, input_name, tensor).await?;
+        Ok(answer(name, &outputs, operands.get("labels").and_then(Value::as_array)))
+    }
+
+    #[cfg(feature = "onnx")]
+    async fn infer(&self, spec: &AssetSpec, input_name: Option<String>, tensor: Tensor) -> Result<Vec<(String, Tensor)>> {
+        let session = self.session(spec).await?;
+        blocking(move || {
+            let mut session = session.lock().unwrap_or_else(|e| e.into_inner());
+            let input_name = match input_name {
+                Some(n) => n,
+                None => session.inputs.first().map(|i| i.name.clone())
+                    .ok_or_else(|| ExecError::validation("ONNX: the model has no input"))?,
+            };
+            let value = ort::value::Tensor::from_array((tensor.shape, tensor.data))?;
+            let outputs = session.run(ort::inputs![input_name => value])?;
+            outputs.iter().map(|(name, v)| {
+                let (shape, data): (&[i64], Vec<f32>) = if let Ok((s, d)) = v.try_extract_tensor::<f32>() {
+                    (s, d.to_vec())
+                } else if let Ok((s, d)) = v.try_extract_tensor::<i64>() {
+                    (s, d.iter().map(|x| *x as f32).collect())
+                } else {
+                    return Err(ExecError::validation(format!("ONNX: output '{name}' is not a float or int64 tensor")));
+                };
+                Ok((name.to_owned(), Tensor { shape: shape.to_vec(), data }))
+            }).collect()
+        }).await
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    async fn infer(&self, _spec: &AssetSpec, _input_name: Option<String>, _tensor: Tensor) -> Result<Vec<(String, Tensor)>> {
+        Err(ExecError::new(ErrorCode::Unsupported, "ONNX inference needs a node built with `--features onnx`").into())
+    }
+
+    /// The loaded session of `spec`, least recently used ones dropped.
+    #[cfg(feature = "onnx")]
+    async fn session(&self, spec: &AssetSpec) -> Result<Arc<std::sync::Mutex<ort::session::Session>>> {
+        let lock = || self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        {
+            let mut sessions = lock();
+            if let Some(i) = sessions.iter().position(|(id, _)| *id == spec.sha256) {
+                let entry = sessions.remove(i);
+                let session = entry.1.clone();
+                sessions.push(entry);
+                return Ok(session);
+            }
+        }
+        let bytes = self.assets.fetch(spec).await?;
+        let session = blocking(move || Ok(ort::session::Session::builder()?.commit_from_memory(&bytes)?)).await?;
+        let session = Arc::new(std::sync::Mutex::new(session));
+        let mut sessions = lock();
+        if sessions.len() >= self.max_sessions {
+            sessions.remove(0);
+        }
+        sessions.push((spec.sha256.clone(), session.clone()));
+        Ok(session)
+    }
+}
+
+/// Whether ONNX Runtime loads on this machine (capability probe).
+#[cfg(feature = "onnx")]
+pub async fn runtime_available() -> bool {
+    blocking(|| {
+        ort::session::Session::builder()?;
+        Ok(())
+    }).await.is_ok()
+}
+
+#[cfg(not(feature = "onnx"))]
+pub async fn runtime_available() -> bool {
+    false
+}
+
+/// Run ONNX Runtime on the blocking pool; a runtime that cannot be loaded
+/// panics inside `ort`, which surfaces here as UNSUPPORTED.
+#[cfg(feature = "onnx")]
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(f).await.unwrap_or_else(|e| Err(ExecError::new(
+        ErrorCode::Unsupported,
+        format!("ONNX Runtime unavailable (set ORT_DYLIB_PATH): {e}"),
+    ).into()))
+}
+
+#[cfg(feature = "onnx")]
+fn decode_image(bytes: &[u8], image: &Value) -> Result<Tensor> {
+    let dim = |k: &str| image.get(k).and_then(Value::as_u64).filter(|d| (1..=4096).contains(d))
+        .ok_or_else(|| ExecError::validation(format!("ONNX: image.{k} must be 1..=4096")));
+    let (width, height) = (dim("width")? as u32, dim("height")? as u32);
+    let decoded = image::load_from_memory(bytes)
+        .map_err(|e| ExecError::validation(format!("ONNX: cannot decode the input image: {e}")))?;
+    let rgb = decoded.resize_exact(width, height, image::imageops::FilterType::Triangle).to_rgb8();
+    Tensor::from_rgb(rgb.as_raw(), width, height, image)
+}
+
+#[cfg(not(feature = "onnx"))]
+fn decode_image(_bytes: &[u8], _image: &Value) -> Result<Tensor> {
+    Err(ExecError::new(ErrorCode::Unsupported, "ONNX inference needs a node built with `--features onnx`").into())
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_spec() {
+        use crate::proto::llmir::AssetRef;
+        let sha = "ab".repeat(32);
+        let dm = DispatchMetadata {
+            assets: vec![
+                AssetRef { name: "codes".into(), kind: "table".into(), sha256: "cd".repeat(32) },
+                AssetRef { name: "weld".into(), kind: "onnx".into(), sha256: sha.clone() },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(model_spec(&dm, &Value::Null).unwrap().name, "weld");
+        let operands = json!({ "model": { "sha256": format!("sha256:{sha}"), "url": "https://m/weld.onnx" } });
+        let spec = model_spec(&DispatchMetadata::default(), &operands).unwrap();
+        assert_eq!((spec.kind.as_str(), spec.url.as_deref()), ("onnx", Some("https://m/weld.onnx")));
+        assert!(model_spec(&DispatchMetadata::default(), &Value::Null).is_err());
+    }
+
+    #[test]
+    fn test_tensors_and_answer() {
+        let t = Tensor::from_json(&json!([[1, 2, 3], [4, 5, 6]]), None).unwrap();
+        assert_eq!((t.shape.as_slice(), t.data.len()), (&[2, 3][..], 6));
+        assert_eq!(Tensor::from_json(&json!({ "shape": [3, 2], "data": [1, 2, 3, 4, 5, 6] }), None).unwrap().shape, vec![3, 2]);
+        assert_eq!(Tensor::from_json(&json!([1, 2, 3, 4]), Some(vec![1, 4])).unwrap().shape, vec![1, 4]);
+        assert!(Tensor::from_json(&json!([[1, 2], [3]]), None).is_err());
+        assert!(Tensor::from_json(&json!([1, 2, 3]), Some(vec![2, 2])).is_err());
+
+        // 2×1 image: red then white pixel, channel-first
+        let image = json!({ "mean": [0.5, 0.5, 0.5], "std": [0.5, 0.5, 0.5] });
+        let t = Tensor::from_rgb(&[255, 0, 0, 255, 255, 255], 2, 1, &image).unwrap();
+        assert_eq!((t.shape, t.data), (vec![1, 3, 1, 2], vec![1.0, 1.0, -1.0, 1.0, -1.0, 1.0]));
+
+        let scores = Tensor { shape: vec![1, 3], data: vec![0.1, 0.7, 0.2] };
+        let labels = vec![json!("ok"), json!("porosity"), json!("crack")];
+        let out = answer("weld", &[("scores".into(), scores)], Some(&labels));
+        assert_eq!((out["label"].as_str(), out["class"].as_u64()), (Some("porosity"), Some(1)));
+        assert_eq!(out["outputs"]["scores"]["shape"], json!([1, 3]));
+    }
+}
@@ -0,0 +1,274 @@
+//! CALL_SERVICE input schema — coerce and validate before sending
+//!
+//! A payload the remote API rejects comes back as an opaque 400.  When the
+//! instruction's dispatch_metadata carries `input_schema` (a JSON Schema
+//! subset), the input register is checked locally first and the instruction
+//! fails with VALIDATION naming every offending field:
+//!
+//!   type                    string | number | integer | boolean | object |
+//!                           array | null, or a list of them
+//!   properties, required    missing required fields are errors unless the
+//!                           property has a `default`, which is filled in
+//!   additionalProperties    false drops unknown fields; a schema coerces them
+//!   items, minItems, maxItems
+//!   enum, minimum, maximum, minLength, maxLength, pattern
+//!
+//! Coercion happens before the checks: numeric strings become numbers or
+//! integers ("42", " 3.5 "), "true"/"false" become booleans, and numbers or
+//! booleans become strings where a string is expected.  The coerced value is
+//! what gets sent and audited.
+//!
+//! Schemas are parsed once per artifact (PreparedInstr); the verifier refuses
+//! a malformed one with INVALID_INPUT_SCHEMA.
+
+use regex_lite::Regex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct InputSchema {
+    schema: Value,
+    /// Compiled `pattern`s, keyed by their source
+    patterns: HashMap<String, Regex>,
+}
+
+impl InputSchema {
+    /// Parse the `input_schema` JSON (None when empty).
+    pub fn parse(json: &str) -> Result<Option<Self>, String> {
+        if json.trim().is_empty() {
+            return Ok(None);
+        }
+        let schema: Value = serde_json::from_str(json).map_err(|e| format!("input_schema is not JSON: {e}"))?;
+        if !schema.is_object() {
+            return Err("input_schema must be a JSON object".into());
+        }
+        let mut patterns = HashMap::new();
+        collect_patterns(&schema, &mut patterns)?;
+        Ok(Some(Self { schema, patterns }))
+    }
+
+    /// Coerced copy of `input`, or every violation found.
+    pub fn coerce(&self, input: &Value) -> Result<Value, Vec<String>> {
+        let mut errors = Vec::new();
+        let out = self.walk(&self.schema, input, "$", &mut errors);
+        if errors.is_empty() { Ok(out) } else { Err(errors) }
+    }
+
+    fn walk(&self, schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) -> Value {
+        let Some(s) = schema.as_object() else {
+            return value.clone();
+        };
+        let types: Vec<&str> = match s.get("type") {
+            Some(Value::String(t)) => vec![t.as_str()],
+            Some(Value::Array(ts)) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        let value = if types.is_empty() || types.iter().any(|t| is_type(value, t)) {
+            value.clone()
+        } else {
+            match types.iter().find_map(|t| convert(value, t)) {
+                Some(v) => v,
+                None => {
+                    errors.push(format!("{path}: expected {}, got {}", types.join(" or "), describe(value)));
+                    return value.clone();
+                }
+            }
+        };
+
+        let value = match value {
+            Value::Object(map) => Value::Object(self.walk_object(s, map, path, errors)),
+            Value::Array(items) => {
+                let items: Vec<Value> = match s.get("items") {
+                    Some(item_schema) => items.iter().enumerate()
+                        .map(|(i, v)| self.walk(item_schema, v, &format!("{path}[{i}]"), errors))
+                        .collect(),
+                    None => items,
+                };
+                if let Some(min) = s.get("minItems").and_then(Value::as_u64).filter(|&m| (items.len() as u64) < m) {
+                    errors.push(format!("{path}: at least {min} items required, got {}", items.len()));
+                }
+                if let Some(max) = s.get("maxItems").and_then(Value::as_u64).filter(|&m| items.len() as u64 > m) {
+                    errors.push(format!("{path}: at most {max} items allowed, got {}", items.len()));
+                }
+                Value::Array(items)
+            }
+            other => other,
+        };
+
+        if let Some(allowed) = s.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(&value) {
+                let list: Vec<String> = allowed.iter().map(Value::to_string).collect();
+                errors.push(format!("{path}: {value} is not one of {}", list.join(", ")));
+            }
+        }
+        if let Some(n) = value.as_f64() {
+            if let Some(min) = s.get("minimum").and_then(Value::as_f64).filter(|&m| n < m) {
+                errors.push(format!("{path}: {n} is below the minimum {min}"));
+            }
+            if let Some(max) = s.get("maximum").and_then(Value::as_f64).filter(|&m| n > m) {
+                errors.push(format!("{path}: {n} is above the maximum {max}"));
+            }
+        }
+        if let Some(text) = value.as_str() {
+            let len = text.chars().count() as u64;
+            if let Some(min) = s.get("minLength").and_then(Value::as_u64).filter(|&m| len < m) {
+                errors.push(format!("{path}: at least {min} characters required, got {len}"));
+            }
+            if let Some(max) = s.get("maxLength").and_then(Value::as_u64).filter(|&m| len > m) {
+                errors.push(format!("{path}: at most {max} characters allowed, got {len}"));
+            }
+            if let Some(p) = s.get("pattern").and_then(Value::as_str) {
+                if self.patterns.get(p).is_some_and(|re| !re.is_match(text)) {
+                    errors.push(format!("{path}: \"{text}\" does not match {p}"));
+                }
+            }
+        }
+        value
+    }
+
+    fn walk_object(&self, s: &Map<String, Value>, mut map: Map<String, Value>, path: &str, errors: &mut Vec<String>) -> Map<String, Value> {
+        let empty = Map::new();
+        let properties = s.get("properties").and_then(Value::as_object).unwrap_or(&empty);
+        let required: Vec<&str> = s.get("required").and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        let mut out = Map::new();
+        for (name, prop) in properties {
+            let field_path = format!("{path}.{name}");
+            match map.remove(name).filter(|v| !v.is_null() || !required.contains(&name.as_str())) {
+                Some(v) => {
+                    out.insert(name.clone(), self.walk(prop, &v, &field_path, errors));
+                }
+                None => match prop.get("default") {
+                    Some(d) => {
+                        out.insert(name.clone(), d.clone());
+                    }
+                    None if required.contains(&name.as_str()) => errors.push(format!("{field_path}: required")),
+                    None => {}
+                },
+            }
+        }
+        for name in required.iter().filter(|r| !properties.contains_key(**r)) {
+            if !map.contains_key(*name) {
+                errors.push(format!("{path}.{name}: required"));
+            }
+        }
+        // Fields the schema does not describe
+        match s.get("additionalProperties") {
+            Some(Value::Bool(false)) => {}
+            Some(extra @ Value::Object(_)) => {
+                for (name, v) in map {
+                    let checked = self.walk(extra, &v, &format!("{path}.{name}"), errors);
+                    out.insert(name, checked);
+                }
+            }
+            _ => out.extend(map),
+        }
+        out
+    }
+}
+
+fn collect_patterns(schema: &Value, out: &mut HashMap<String, Regex>) -> Result<(), String> {
+    match schema {
+        Value::Object(map) => {
+            if let Some(p) = map.get("pattern").and_then(Value::as_str) {
+                let re = Regex::new(p).map_err(|e| format!("input_schema pattern {p}: {e}"))?;
+                out.insert(p.to_owned(), re);
+            }
+            map.values().try_for_each(|v| collect_patterns(v, out))
+        }
+        Value::Array(items) => items.iter().try_for_each(|v| collect_patterns(v, out)),
+        _ => Ok(()),
+    }
+}
+
+fn is_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// `value` converted to `ty`, if it has an unambiguous representation.
+fn convert(value: &Value, ty: &str) -> Option<Value> {
+    match (ty, value) {
+        ("number", Value::String(s)) => s.trim().parse::<f64>().ok().filter(|f| f.is_finite())
+            .and_then(|f| serde_json::Number::from_f64(f).map(Value::Number))
+            .map(|n| s.trim().parse::<i64>().map(Value::from).unwrap_or(n)),
+        ("integer", Value::String(s)) => s.trim().parse::<i64>().ok().map(Value::from),
+        ("integer", Value::Number(n)) => n.as_f64().filter(|f| f.fract() == 0.0).map(|f| Value::from(f as i64)),
+        ("boolean", Value::String(s)) => match s.trim().to_ascii_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        ("string", Value::Number(n)) => Some(Value::String(n.to_string())),
+        ("string", Value::Bool(b)) => Some(Value::String(b.to_string())),
+        _ => None,
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("\"{s}\""),
+        Value::Object(_) => "an object".into(),
+        Value::Array(_) => "an array".into(),
+        other => other.to_string(),
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ORDER: &str = r#"{
+        "type": "object",
+        "required": ["sku", "qty"],
+        "additionalProperties": false,
+        "properties": {
+            "sku":      { "type": "string", "pattern": "^[A-Z]{3}-\\d+$" },
+            "qty":      { "type": "integer", "minimum": 1 },
+            "express":  { "type": "boolean", "default": false },
+            "price":    { "type": "number" },
+            "channel":  { "type": "string", "enum": ["web", "edge"] },
+            "tags":     { "type": "array", "items": { "type": "string" }, "maxItems": 2 }
+        }
+    }"#;
+
+    #[test]
+    fn test_coerces_and_drops_unknown_fields() {
+        let schema = InputSchema::parse(ORDER).unwrap().unwrap();
+        let out = schema.coerce(&json!({
+            "sku": "ABC-12", "qty": "3", "price": " 9.5 ", "tags": [7], "debug": true,
+        })).unwrap();
+        assert_eq!(out, json!({ "sku": "ABC-12", "qty": 3, "price": 9.5, "tags": ["7"], "express": false }));
+        assert!(InputSchema::parse("").unwrap().is_none());
+        assert!(InputSchema::parse(r#"{"pattern": "("}"#).is_err());
+    }
+
+    #[test]
+    fn test_reports_every_violation() {
+        let schema = InputSchema::parse(ORDER).unwrap().unwrap();
+        let errors = schema.coerce(&json!({
+            "sku": "abc", "qty": "three", "channel": "fax", "tags": ["a", "b", "c"],
+        })).unwrap_err();
+        assert_eq!(errors, vec![
+            "$.channel: \"fax\" is not one of \"web\", \"edge\"",
+            "$.qty: expected integer, got \"three\"",
+            "$.sku: \"abc\" does not match ^[A-Z]{3}-\\d+$",
+            "$.tags: at most 2 items allowed, got 3",
+        ]);
+        assert_eq!(schema.coerce(&Value::Null).unwrap_err(), vec!["$: expected object, got null"]);
+        assert_eq!(schema.coerce(&json!({ "sku": "ABC-1" })).unwrap_err(), vec!["$.qty: required"]);
+    }
+}
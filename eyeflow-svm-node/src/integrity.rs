@@ -0,0 +1,259 @@
+//! `verify` subcommand — integrity check of offline buffer and audit files
+//!
+//!   eyeflow-svm-node verify <file> [--public-key <key>]... [--json]
+//!
+//!   <file>         offline buffer (NDJSON of envelopes), audit NDJSON, a JSON
+//!                  array of events or a central export ({ "events": [...] })
+//!   --public-key   node key the events must be signed with: 64 hex chars, an
+//!                  SPKI PEM, or a file holding either; repeat to also accept
+//!                  peers' keys (delegated events).  Without it signatures are
+//!                  only checked against the key each event carries.
+//!
+//! Before shipping files off a disconnected site, operators can check that
+//! nothing was corrupted or tampered with.  Every entry is checked for:
+//!
+//!   structure   valid JSON of a known envelope / audit event layout
+//!   hash        selfHash recomputed from the hashed fields
+//!   signature   Ed25519 over selfHash, by a pinned key when given
+//!   link        previousEventHash of each event against the one before it
+//!               (a zero hash starts a new chain segment)
+//!
+//! Findings carry the line (or array item) number.  Exits non-zero when there
+//! are any.
+
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::PathBuf;
+
+use crate::audit::{self, AuditEvent};
+use crate::offline::BufferedEvent;
+
+const USAGE: &str = "usage: eyeflow-svm-node verify <file> [--public-key <key>]... [--json]";
+
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// ── Inputs ────────────────────────────────────────────────────────────────────
+
+struct Args {
+    file: PathBuf,
+    keys: Vec<String>,
+    json: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Args> {
+    let (mut file, mut keys, mut json) = (None, Vec::new(), false);
+    let mut it = args.iter();
+    while let Some(a) = it.next() {
+        match a.as_str() {
+            "--public-key" => {
+                let key = it.next().ok_or_else(|| anyhow!("--public-key needs a value\n{USAGE}"))?;
+                keys.push(load_key(key)?);
+            }
+            "--json" => json = true,
+            other if other.starts_with("--") => return Err(anyhow!("unknown argument '{other}'\n{USAGE}")),
+            other => file = Some(PathBuf::from(other)),
+        }
+    }
+    Ok(Args {
+        file: file.ok_or_else(|| anyhow!("a file to verify is required\n{USAGE}"))?,
+        keys,
+        json,
+    })
+}
+
+/// Hex of the key given inline or in a file.
+fn load_key(arg: &str) -> Result<String> {
+    let text = match std::fs::read_to_string(arg) {
+        Ok(text) => text,
+        Err(_) => arg.to_owned(),
+    };
+    let key = crate::config_sig::verifying_key_from_str(&text)
+        .with_context(|| format!("--public-key {arg}"))?;
+    Ok(hex::encode(key.to_bytes()))
+}
+
+/// Entries of `text` with their line (NDJSON) or item (JSON) number.
+fn entries(text: &str) -> Vec<(usize, std::result::Result<Value, String>)> {
+    match serde_json::from_str::<Value>(text) {
+        Ok(Value::Array(items)) => items.into_iter().enumerate().map(|(i, v)| (i + 1, Ok(v))).collect(),
+        Ok(Value::Object(obj)) if obj.get("events").is_some_and(Value::is_array) => {
+            let items = obj["events"].as_array().cloned().unwrap_or_default();
+            items.into_iter().enumerate().map(|(i, v)| (i + 1, Ok(v))).collect()
+        }
+        _ => text.lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(i, l)| (i + 1, serde_json::from_str(l).map_err(|e| e.to_string())))
+            .collect(),
+    }
+}
+
+// ── Report ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub line: usize,
+    /// "structure" | "hash" | "signature" | "link"
+    pub check: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub entries: usize,
+    pub audit_events: usize,
+    /// Chain segments (runs of linked events)
+    pub segments: usize,
+    /// Whether signatures were checked against pinned keys
+    pub pinned_keys: bool,
+    pub findings: Vec<Finding>,
+}
+
+/// Check every entry of `text`; `keys` (hex) pins the accepted signers.
+pub fn check(text: &str, keys: &[String]) -> Report {
+    let mut report = Report { pinned_keys: !keys.is_empty(), ..Default::default() };
+    let mut prev: Option<AuditEvent> = None;
+    let mut findings = Vec::new();
+    let finding = |line, check, message| Finding { line, check, message };
+
+    for (line, value) in entries(text) {
+        report.entries += 1;
+        let event = match value.and_then(|v| audit_event(v).map_err(|e| e.to_string())) {
+            Ok(Some(ev)) => ev,
+            Ok(None) => continue,
+            Err(e) => {
+                findings.push(finding(line, "structure", format!("unreadable entry: {e}")));
+                // The chain cannot be followed across a lost event
+                prev = None;
+                continue;
+            }
+        };
+        report.audit_events += 1;
+
+        if audit::compute_self_hash(&event) != event.self_hash {
+            findings.push(finding(line, "hash", format!("event {} selfHash mismatch", event.event_id)));
+        }
+        if !keys.is_empty() && !keys.contains(&event.public_key_hex) {
+            findings.push(finding(line, "signature", format!("event {} signed by unpinned key {}", event.event_id, event.public_key_hex)));
+        } else if !audit::signature_valid(&event) {
+            findings.push(finding(line, "signature", format!("event {} has an invalid signature", event.event_id)));
+        }
+        match &prev {
+            _ if event.previous_event_hash == GENESIS => report.segments += 1,
+            Some(p) if event.previous_event_hash != audit::link_hash(p) => {
+                findings.push(finding(line, "link", format!(
+                    "chain break: event {} does not link to {} (missing, reordered or altered events)",
+                    event.event_id, p.event_id,
+                )));
+                report.segments += 1;
+            }
+            Some(_) => {}
+            // First event of a file continuing an earlier flush
+            None => report.segments += 1,
+        }
+        prev = Some(event);
+    }
+    report.findings = findings;
+    report
+}
+
+/// The audit event in an entry: a bare event or an AUDIT_EVENT envelope
+/// (None for other envelope kinds).
+fn audit_event(value: Value) -> Result<Option<AuditEvent>> {
+    if value.get("kind").is_some() {
+        return Ok(match serde_json::from_value::<BufferedEvent>(value)? {
+            BufferedEvent::AuditEvent { payload, .. } => Some(payload),
+            _ => None,
+        });
+    }
+    Ok(Some(serde_json::from_value(value)?))
+}
+
+/// The audit events of `text` (any layout `check` reads), in file order.
+pub fn audit_events(text: &str) -> Result<Vec<AuditEvent>> {
+    let mut events = Vec::new();
+    for (line, value) in entries(text) {
+        let value = value.map_err(|e| anyhow!("entry {line}: {e}"))?;
+        if let Some(ev) = audit_event(value).with_context(|| format!("entry {line}"))? {
+            events.push(ev);
+        }
+    }
+    Ok(events)
+}
+
+impl Report {
+    pub fn render(&self, file: &str) -> String {
+        let mut out = format!(
+            "{file}: {} entr{}, {} audit event(s) in {} segment(s)\n",
+            self.entries, if self.entries == 1 { "y" } else { "ies" }, self.audit_events, self.segments,
+        );
+        if !self.pinned_keys && self.audit_events > 0 {
+            out.push_str("note: no --public-key given — signatures checked against each event's own key\n");
+        }
+        for f in &self.findings {
+            out.push_str(&format!("line {:>6}  {:<9}  {}\n", f.line, f.check, f.message));
+        }
+        out.push_str(if self.findings.is_empty() { "OK\n" } else { "FAILED\n" });
+        out
+    }
+}
+
+// ── Entry point ───────────────────────────────────────────────────────────────
+
+/// `verify` subcommand (args after the subcommand name).
+pub fn run(args: &[String]) -> Result<()> {
+    let args = parse_args(args)?;
+    let text = std::fs::read_to_string(&args.file).with_context(|| format!("reading {:?}", args.file))?;
+
+    let report = check(&text, &args.keys);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report.render(&args.file.display().to_string()));
+    }
+    if !report.findings.is_empty() {
+        return Err(anyhow!("{} integrity finding(s)", report.findings.len()));
+    }
+    Ok(())
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditChain;
+    use serde_json::json;
+
+    #[test]
+    fn test_verify_offline_buffer() {
+        let mut chain = AuditChain::new("node-a".into(), None).unwrap();
+        for i in 0..4 {
+            chain.append("wf", None, Some(format!("svc-{i}")), "CALL_SERVICE", None, None, i, None);
+        }
+        let key = chain.public_key_hex().to_owned();
+        let events = chain.drain();
+        let line = |ev: &AuditEvent| serde_json::to_string(&BufferedEvent::from_audit(ev.clone())).unwrap();
+
+        let mut lines: Vec<String> = events.iter().map(line).collect();
+        lines.insert(1, serde_json::to_string(&BufferedEvent::from_execution(json!({ "ok": true }))).unwrap());
+        let clean = lines.join("\n");
+        let report = check(&clean, std::slice::from_ref(&key));
+        assert!(report.findings.is_empty(), "{:?}", report.findings);
+        assert_eq!((report.entries, report.audit_events, report.segments), (5, 4, 1));
+
+        // Tampered output hash, a dropped event and a truncated line
+        let mut tampered = events[1].clone();
+        tampered.output_hash = "f".repeat(64);
+        let bad = [line(&events[0]), line(&tampered), line(&events[3]), "{\"kind\":\"AUDIT_EV".into()].join("\n");
+        let report = check(&bad, &[key]);
+        let found: Vec<(usize, &str)> = report.findings.iter().map(|f| (f.line, f.check)).collect();
+        assert_eq!(found, [(2, "hash"), (3, "link"), (4, "structure")]);
+
+        // Pinning another key rejects every event
+        let report = check(&clean, &["ab".repeat(32)]);
+        assert_eq!(report.findings.iter().filter(|f| f.check == "signature").count(), 4);
+    }
+}
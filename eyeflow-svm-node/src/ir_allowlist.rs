@@ -0,0 +1,258 @@
+//! IR allow-list — run only pre-approved artifacts, pinned by checksum
+//!
+//! Regulated sites approve workflows ahead of time.  The node then runs an
+//! artifact only if the SHA-256 of its IR payload (the IR cache checksum,
+//! 64 hex chars) is listed:
+//!
+//!   SVM_IR_ALLOWLIST_FILE   one checksum per line, `#` comments; re-read
+//!                           when its modification time changes
+//!   CONFIG_UPDATE           payload.irAllowList = { "checksums": [...],
+//!                           "mode": "enforce" | "audit" } replaces the file's
+//!                           list; null restores it.  Applied only from a
+//!                           signed update (SVM_CONFIG_OPS_PUBLIC_KEY).
+//!
+//! An unlisted artifact raises an IR_NOT_ALLOWLISTED security alert and
+//! audit event, and in `enforce` mode is refused (NOT_ALLOWLISTED, AUTH) —
+//! as is a CALL_SLICE into one.  SVM_IR_ALLOWLIST_MODE overrides the mode
+//! for development: `audit` runs unlisted artifacts (alerting only), `off`
+//! skips the check.  Without it, the list is enforced once one is
+//! provisioned.  Delegations from peers are checked against the checksum of
+//! the delegating node's artifact (admission.rs).
+//!
+//!   eyeflow_ir_allowlist_entries
+//!   eyeflow_ir_allowlist_unlisted_total{action="refused"|"allowed"}
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::health::MetricsCollector;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Off,
+    Audit,
+    Enforce,
+}
+
+impl Mode {
+    fn parse(text: &str) -> Result<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "audit" => Ok(Self::Audit),
+            "enforce" => Ok(Self::Enforce),
+            other => Err(anyhow!("IR allow-list mode '{other}' (expected enforce, audit or off)")),
+        }
+    }
+}
+
+/// Outcome of checking one artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Allowed,
+    /// Not listed; `enforced` = refuse it
+    Unlisted { enforced: bool },
+}
+
+/// Checksums of a list file or pushed list (`#` comments allowed).
+fn parse_checksums<'a>(entries: impl IntoIterator<Item = &'a str>) -> Result<HashSet<String>> {
+    entries.into_iter()
+        .map(|e| e.split('#').next().unwrap_or("").trim())
+        .filter(|e| !e.is_empty())
+        .map(|e| {
+            if e.len() == 64 && e.chars().all(|c| c.is_ascii_hexdigit()) {
+                Ok(e.to_ascii_lowercase())
+            } else {
+                Err(anyhow!("IR allow-list: '{e}' is not a SHA-256 checksum"))
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+struct ListFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    checksums: HashSet<String>,
+}
+
+impl ListFile {
+    fn read(path: PathBuf) -> Result<Self> {
+        let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        let text = std::fs::read_to_string(&path).map_err(|e| anyhow!("SVM_IR_ALLOWLIST_FILE {}: {e}", path.display()))?;
+        Ok(Self { checksums: parse_checksums(text.lines())?, path, modified })
+    }
+
+    /// Re-read the file if it changed (a bad edit keeps the previous list).
+    fn refresh(&mut self) {
+        let modified = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if modified == self.modified {
+            return;
+        }
+        match Self::read(self.path.clone()) {
+            Ok(fresh) => {
+                info!("[AllowList] reloaded {} ({} checksums)", self.path.display(), fresh.checksums.len());
+                *self = fresh;
+            }
+            Err(e) => {
+                warn!("[AllowList] keeping the previous list: {e}");
+                self.modified = modified;
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Pushed {
+    mode: Option<Mode>,
+    checksums: HashSet<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct IrAllowList {
+    /// SVM_IR_ALLOWLIST_MODE
+    mode_override: Option<Mode>,
+    file: Mutex<Option<ListFile>>,
+    pushed: RwLock<Option<Pushed>>,
+    refused: AtomicU64,
+    allowed_unlisted: AtomicU64,
+}
+
+impl IrAllowList {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let mode_override = config.ir_allowlist_mode.as_deref().map(Mode::parse).transpose()?;
+        let file = config.ir_allowlist_file.as_ref().map(|p| ListFile::read(p.into())).transpose()?;
+        if let Some(f) = &file {
+            info!("[AllowList] {} approved artifact(s) from {}", f.checksums.len(), f.path.display());
+        }
+        Ok(Self { mode_override, file: Mutex::new(file), ..Default::default() })
+    }
+
+    /// CONFIG_UPDATE `irAllowList` (null = back to the local file); returns
+    /// the number of checksums.
+    pub fn apply_update(&self, value: &Value) -> Result<usize> {
+        let pushed = if value.is_null() {
+            None
+        } else {
+            let entries = value.get("checksums").and_then(|c| c.as_array())
+                .ok_or_else(|| anyhow!("irAllowList.checksums must be an array"))?;
+            let checksums = parse_checksums(entries.iter().map(|e| e.as_str().unwrap_or("?")))?;
+            let mode = value.get("mode").and_then(|m| m.as_str()).map(Mode::parse).transpose()?;
+            Some(Pushed { mode, checksums })
+        };
+        let n = pushed.as_ref().map_or(0, |p| p.checksums.len());
+        *self.pushed.write().unwrap_or_else(|e| e.into_inner()) = pushed;
+        Ok(n)
+    }
+
+    /// Mode in effect: the local override, else the pushed mode, else
+    /// enforce once a list is provisioned.
+    pub fn mode(&self) -> Mode {
+        if let Some(mode) = self.mode_override {
+            return mode;
+        }
+        if let Some(p) = self.pushed.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            return p.mode.unwrap_or(Mode::Enforce);
+        }
+        if self.file.lock().unwrap_or_else(|e| e.into_inner()).is_some() { Mode::Enforce } else { Mode::Off }
+    }
+
+    fn contains(&self, checksum: &str) -> bool {
+        let checksum = checksum.to_ascii_lowercase();
+        if let Some(p) = self.pushed.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            return p.checksums.contains(&checksum);
+        }
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.as_mut().is_some_and(|f| {
+            f.refresh();
+            f.checksums.contains(&checksum)
+        })
+    }
+
+    /// Check the artifact with IR payload checksum `checksum`.
+    pub fn check(&self, checksum: &str) -> Verdict {
+        let mode = self.mode();
+        if mode == Mode::Off || self.contains(checksum) {
+            return Verdict::Allowed;
+        }
+        let enforced = mode == Mode::Enforce;
+        if enforced { &self.refused } else { &self.allowed_unlisted }.fetch_add(1, Ordering::Relaxed);
+        Verdict::Unlisted { enforced }
+    }
+
+    fn entries(&self) -> usize {
+        if let Some(p) = self.pushed.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            return p.checksums.len();
+        }
+        self.file.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map_or(0, |f| f.checksums.len())
+    }
+}
+
+impl MetricsCollector for IrAllowList {
+    fn prometheus(&self, node_id: &str) -> String {
+        format!(
+            "# HELP eyeflow_ir_allowlist_entries Approved artifact checksums in effect\n\
+             # TYPE eyeflow_ir_allowlist_entries gauge\n\
+             eyeflow_ir_allowlist_entries{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_ir_allowlist_unlisted_total Artifacts not on the IR allow-list, refused or run (audit mode)\n\
+             # TYPE eyeflow_ir_allowlist_unlisted_total counter\n\
+             eyeflow_ir_allowlist_unlisted_total{{node_id=\"{node_id}\",action=\"refused\"}} {}\n\
+             eyeflow_ir_allowlist_unlisted_total{{node_id=\"{node_id}\",action=\"allowed\"}} {}\n",
+            self.entries(),
+            self.refused.load(Ordering::Relaxed),
+            self.allowed_unlisted.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_file_push_and_override_modes() {
+        let approved = "ab".repeat(32);
+        let other = "cd".repeat(32);
+        let path = std::env::temp_dir().join(format!("eyeflow_allowlist_{}.txt", uuid::Uuid::new_v4().simple()));
+        std::fs::write(&path, format!("# line 3 approved set\n{}  # packaging v2\n", approved.to_uppercase())).unwrap();
+        let config = |mode: Option<&str>| Config {
+            ir_allowlist_file: Some(path.to_string_lossy().into_owned()),
+            ir_allowlist_mode: mode.map(String::from),
+            ..Config::from_env()
+        };
+
+        let list = IrAllowList::from_config(&config(None)).unwrap();
+        assert_eq!(list.check(&approved), Verdict::Allowed);
+        assert_eq!(list.check(&other), Verdict::Unlisted { enforced: true });
+        assert_eq!(list.check(""), Verdict::Unlisted { enforced: true });
+
+        // Pushed list replaces the file's; null restores it
+        assert_eq!(list.apply_update(&json!({ "checksums": [other], "mode": "audit" })).unwrap(), 1);
+        assert_eq!(list.check(&approved), Verdict::Unlisted { enforced: false });
+        assert!(list.apply_update(&json!({ "checksums": ["not-a-hash"] })).is_err());
+        list.apply_update(&Value::Null).unwrap();
+        assert_eq!(list.check(&approved), Verdict::Allowed);
+
+        // Edits to the file are picked up
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(&path, &other).unwrap();
+        assert_eq!(list.check(&other), Verdict::Allowed);
+        let metrics = list.prometheus("n");
+        assert!(metrics.contains("eyeflow_ir_allowlist_unlisted_total{node_id=\"n\",action=\"refused\"} 2"), "{metrics}");
+        assert!(metrics.contains("action=\"allowed\"} 1"));
+
+        assert_eq!(IrAllowList::from_config(&config(Some("off"))).unwrap().check(&other.replace('c', "e")), Verdict::Allowed);
+        assert_eq!(IrAllowList::default().mode(), Mode::Off);
+        assert!(IrAllowList::from_config(&config(Some("strict"))).is_err());
+        let _ = std::fs::remove_file(path);
+    }
+}
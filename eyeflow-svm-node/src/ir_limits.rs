@@ -0,0 +1,249 @@
+//! IR decode limits — hardening against hostile or corrupt artifacts
+//!
+//! prost materialises the whole message on decode, so an artifact claiming
+//! millions of instructions or multi-megabyte operand strings would be fully
+//! allocated before any check could run.  `decode_ir` first walks the raw
+//! protobuf wire format — without allocating — and enforces:
+//!
+//!   SVM_IR_MAX_BYTES           encoded artifact size (bounds total decoded size)
+//!   SVM_IR_MAX_INSTRUCTIONS    entries in `instructions` / `instruction_order`
+//!   SVM_IR_MAX_OPERAND_BYTES   length of any single `operands_json`
+//!   SVM_IR_MAX_FEW_SHOT        few_shot_examples per instruction
+//!
+//! Only then is the payload handed to prost.  A violation is reported to the
+//! caller, which raises a security alert with central.
+
+use anyhow::{anyhow, Result};
+use prost::Message;
+use std::fmt;
+
+use crate::config::Config;
+use crate::proto::llmir::LlmIntermediateRepresentation;
+
+// Field numbers from llm_ir.proto
+const IR_INSTRUCTIONS: u32 = 1;
+const IR_INSTRUCTION_ORDER: u32 = 2;
+const MAP_ENTRY_VALUE: u32 = 2;
+const INSTR_OPERANDS_JSON: u32 = 5;
+const INSTR_DISPATCH_METADATA: u32 = 8;
+const DM_FEW_SHOT_EXAMPLES: u32 = 17;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DecodeLimits {
+    pub max_bytes: usize,
+    pub max_instructions: usize,
+    pub max_operand_bytes: usize,
+    pub max_few_shot: usize,
+}
+
+impl From<&Config> for DecodeLimits {
+    fn from(c: &Config) -> Self {
+        Self {
+            max_bytes: c.ir_max_bytes,
+            max_instructions: c.ir_max_instructions,
+            max_operand_bytes: c.ir_max_operand_bytes,
+            max_few_shot: c.ir_max_few_shot,
+        }
+    }
+}
+
+/// Which limit an input broke — carried in the security alert
+/// (also used for WebSocket frames, see ws_limits.rs).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LimitViolation {
+    pub limit: &'static str,
+    pub actual: usize,
+    pub max: usize,
+}
+
+impl fmt::Display for LimitViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "limit {} exceeded: {} > {}", self.limit, self.actual, self.max)
+    }
+}
+
+impl std::error::Error for LimitViolation {}
+
+/// Check `bytes` against `limits`, then decode.
+///
+/// Limit violations surface as a `LimitViolation` inside the anyhow error
+/// (`err.downcast_ref::<LimitViolation>()`); other errors are malformed input.
+pub fn decode_ir(bytes: &[u8], limits: &DecodeLimits) -> Result<LlmIntermediateRepresentation> {
+    check(bytes, limits)?;
+    LlmIntermediateRepresentation::decode(bytes).map_err(|e| anyhow!("IR proto decode error: {e}"))
+}
+
+/// Reject payloads larger than `max_bytes` before they are decoded at all.
+pub fn check_size(len: usize, limits: &DecodeLimits) -> Result<()> {
+    if len > limits.max_bytes {
+        return Err(LimitViolation { limit: "SVM_IR_MAX_BYTES", actual: len, max: limits.max_bytes }.into());
+    }
+    Ok(())
+}
+
+/// Walk the encoded LLMIntermediateRepresentation and enforce `limits`.
+pub fn check(bytes: &[u8], limits: &DecodeLimits) -> Result<()> {
+    check_size(bytes.len(), limits)?;
+
+    let mut instructions = 0usize;
+    let mut order = 0usize;
+    for_each_field(bytes, |field, value| {
+        match (field, value) {
+            (IR_INSTRUCTIONS, Field::Bytes(entry)) => {
+                instructions += 1;
+                exceed("SVM_IR_MAX_INSTRUCTIONS", instructions, limits.max_instructions)?;
+                for_each_field(entry, |f, v| match (f, v) {
+                    (MAP_ENTRY_VALUE, Field::Bytes(instr)) => check_instruction(instr, limits),
+                    _ => Ok(()),
+                })
+            }
+            // packed (one length-delimited run) or unpacked (one varint per entry)
+            (IR_INSTRUCTION_ORDER, Field::Bytes(packed)) => {
+                order += packed.iter().filter(|b| *b & 0x80 == 0).count();
+                exceed("SVM_IR_MAX_INSTRUCTIONS", order, limits.max_instructions)
+            }
+            (IR_INSTRUCTION_ORDER, Field::Varint) => {
+                order += 1;
+                exceed("SVM_IR_MAX_INSTRUCTIONS", order, limits.max_instructions)
+            }
+            _ => Ok(()),
+        }
+    })
+}
+
+fn check_instruction(instr: &[u8], limits: &DecodeLimits) -> Result<()> {
+    for_each_field(instr, |field, value| match (field, value) {
+        (INSTR_OPERANDS_JSON, Field::Bytes(s)) => {
+            exceed("SVM_IR_MAX_OPERAND_BYTES", s.len(), limits.max_operand_bytes)
+        }
+        (INSTR_DISPATCH_METADATA, Field::Bytes(dm)) => {
+            let mut examples = 0usize;
+            for_each_field(dm, |f, _| {
+                if f == DM_FEW_SHOT_EXAMPLES {
+                    examples += 1;
+                }
+                exceed("SVM_IR_MAX_FEW_SHOT", examples, limits.max_few_shot)
+            })
+        }
+        _ => Ok(()),
+    })
+}
+
+fn exceed(limit: &'static str, actual: usize, max: usize) -> Result<()> {
+    if actual > max {
+        return Err(LimitViolation { limit, actual, max }.into());
+    }
+    Ok(())
+}
+
+// ── Wire-format walker ────────────────────────────────────────────────────────
+
+enum Field<'a> {
+    Varint,
+    Fixed,
+    Bytes(&'a [u8]),
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *buf.get(*pos).ok_or_else(|| anyhow!("truncated varint"))?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(anyhow!("varint too long"))
+}
+
+/// Call `f` for every top-level field of the message encoded in `buf`.
+fn for_each_field<'a>(buf: &'a [u8], mut f: impl FnMut(u32, Field<'a>) -> Result<()>) -> Result<()> {
+    let mut pos = 0usize;
+    while pos < buf.len() {
+        let key = read_varint(buf, &mut pos)?;
+        let field = (key >> 3) as u32;
+        let value = match key & 0x7 {
+            0 => {
+                read_varint(buf, &mut pos)?;
+                Field::Varint
+            }
+            1 | 5 => {
+                pos += if key & 0x7 == 1 { 8 } else { 4 };
+                Field::Fixed
+            }
+            2 => {
+                let len = read_varint(buf, &mut pos)? as usize;
+                let end = pos.checked_add(len).filter(|e| *e <= buf.len())
+                    .ok_or_else(|| anyhow!("length-delimited field #{field} overruns the buffer"))?;
+                let bytes = &buf[pos..end];
+                pos = end;
+                Field::Bytes(bytes)
+            }
+            wt => return Err(anyhow!("unsupported wire type {wt} in field #{field}")),
+        };
+        if pos > buf.len() {
+            return Err(anyhow!("truncated field #{field}"));
+        }
+        f(field, value)?;
+    }
+    Ok(())
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::llmir::{DispatchMetadata, FewShotExample, IrInstruction};
+
+    const LIMITS: DecodeLimits = DecodeLimits {
+        max_bytes: 4096,
+        max_instructions: 3,
+        max_operand_bytes: 64,
+        max_few_shot: 2,
+    };
+
+    fn encode(instrs: Vec<IrInstruction>) -> Vec<u8> {
+        LlmIntermediateRepresentation {
+            instruction_order: instrs.iter().map(|i| i.index).collect(),
+            instructions: instrs.into_iter().map(|i| (i.index, i)).collect(),
+            ..Default::default()
+        }
+        .encode_to_vec()
+    }
+
+    fn violation(bytes: &[u8]) -> Option<&'static str> {
+        check(bytes, &LIMITS).err()
+            .and_then(|e| e.downcast_ref::<LimitViolation>().map(|v| v.limit))
+    }
+
+    #[test]
+    fn test_within_limits_decodes() {
+        let bytes = encode(vec![IrInstruction { index: 1, operands_json: "{}".into(), ..Default::default() }]);
+        assert_eq!(decode_ir(&bytes, &LIMITS).unwrap().instructions.len(), 1);
+    }
+
+    #[test]
+    fn test_limits_enforced() {
+        let many = (0..4).map(|i| IrInstruction { index: i, ..Default::default() }).collect();
+        assert_eq!(violation(&encode(many)), Some("SVM_IR_MAX_INSTRUCTIONS"));
+
+        let big = IrInstruction { index: 1, operands_json: "x".repeat(65), ..Default::default() };
+        assert_eq!(violation(&encode(vec![big])), Some("SVM_IR_MAX_OPERAND_BYTES"));
+
+        let shots = IrInstruction {
+            index: 1,
+            dispatch_metadata: Some(DispatchMetadata {
+                few_shot_examples: vec![FewShotExample::default(); 3],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(violation(&encode(vec![shots])), Some("SVM_IR_MAX_FEW_SHOT"));
+
+        assert_eq!(violation(&vec![0u8; 5000]), Some("SVM_IR_MAX_BYTES"));
+        // Length prefix pointing past the end is malformed, not a limit hit
+        assert!(check(&[0x0a, 0xff, 0x01], &LIMITS).is_err());
+    }
+}
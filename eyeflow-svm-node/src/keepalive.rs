@@ -0,0 +1,122 @@
+//! Node-initiated keepalive — dead-connection detection on the central link
+//!
+//! Central's PINGs only show that central can still reach the node; a
+//! half-open TCP connection (NAT timeout, peer power loss) never errors on
+//! the node side and would stay "connected" forever.  The node therefore
+//! sends its own WebSocket Ping frame every SVM_KEEPALIVE_INTERVAL_SECS
+//! (default 15, 0 = disabled) carrying a sequence number.  If the matching
+//! Pong is not back within SVM_KEEPALIVE_TIMEOUT_SECS (default 10) the
+//! connection is dropped and the usual reconnect loop takes over.
+//!
+//! Round-trip times of answered pings are exposed on /metrics as
+//! `eyeflow_ws_rtt_ms`; dropped connections count in
+//! `eyeflow_ws_keepalive_timeouts_total`.
+
+use tokio::time::{Duration, Instant};
+
+/// What the connection loop should do once `Keepalive::next_wake` passes.
+#[derive(Debug, PartialEq)]
+pub enum Tick {
+    /// Nothing due yet
+    Idle,
+    /// Send a Ping frame with this payload
+    Ping(Vec<u8>),
+    /// The last ping went unanswered for this long — drop the connection
+    Dead(Duration),
+}
+
+#[derive(Debug)]
+pub struct Keepalive {
+    interval: Duration,
+    timeout: Duration,
+    seq: u64,
+    next_ping: Instant,
+    /// Sequence number and send time of the unanswered ping
+    outstanding: Option<(u64, Instant)>,
+}
+
+impl Keepalive {
+    /// A keepalive for a connection established at `now`
+    /// (`interval` zero = disabled).
+    pub fn new(interval: Duration, timeout: Duration, now: Instant) -> Self {
+        Self { interval, timeout, seq: 0, next_ping: now + interval, outstanding: None }
+    }
+
+    /// When `poll` next has something to do (None when disabled).
+    pub fn next_wake(&self) -> Option<Instant> {
+        if self.interval.is_zero() {
+            return None;
+        }
+        Some(match self.outstanding {
+            Some((_, sent)) => sent + self.timeout,
+            None => self.next_ping,
+        })
+    }
+
+    pub fn poll(&mut self, now: Instant) -> Tick {
+        if self.interval.is_zero() {
+            return Tick::Idle;
+        }
+        match self.outstanding {
+            Some((_, sent)) if now >= sent + self.timeout => Tick::Dead(now - sent),
+            Some(_) => Tick::Idle,
+            None if now >= self.next_ping => {
+                self.seq += 1;
+                self.outstanding = Some((self.seq, now));
+                self.next_ping = now + self.interval;
+                Tick::Ping(self.seq.to_be_bytes().to_vec())
+            }
+            None => Tick::Idle,
+        }
+    }
+
+    /// Match a Pong against the outstanding ping; returns the round-trip
+    /// time, or None for an unsolicited or stale Pong.
+    pub fn on_pong(&mut self, data: &[u8], now: Instant) -> Option<Duration> {
+        let seq = u64::from_be_bytes(data.try_into().ok()?);
+        match self.outstanding {
+            Some((expected, sent)) if expected == seq => {
+                self.outstanding = None;
+                Some(now - sent)
+            }
+            _ => None,
+        }
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ping_pong_and_timeout() {
+        let t0 = Instant::now();
+        let secs = Duration::from_secs;
+        let mut k = Keepalive::new(secs(15), secs(10), t0);
+        assert_eq!(k.next_wake(), Some(t0 + secs(15)));
+        assert_eq!(k.poll(t0 + secs(3)), Tick::Idle);
+
+        // First ping, answered after 40 ms
+        let Tick::Ping(first) = k.poll(t0 + secs(15)) else { panic!("expected a ping") };
+        assert_eq!(k.next_wake(), Some(t0 + secs(25)));
+        assert_eq!(k.on_pong(b"junk", t0 + secs(15)), None);
+        assert_eq!(
+            k.on_pong(&first, t0 + secs(15) + Duration::from_millis(40)),
+            Some(Duration::from_millis(40)),
+        );
+        assert_eq!(k.on_pong(&first, t0 + secs(16)), None, "already answered");
+        assert_eq!(k.next_wake(), Some(t0 + secs(30)));
+
+        // Second ping is never answered
+        let Tick::Ping(second) = k.poll(t0 + secs(30)) else { panic!("expected a ping") };
+        assert_ne!(first, second);
+        assert_eq!(k.poll(t0 + secs(35)), Tick::Idle);
+        assert_eq!(k.poll(t0 + secs(41)), Tick::Dead(secs(11)));
+
+        let mut off = Keepalive::new(Duration::ZERO, secs(10), t0);
+        assert_eq!(off.next_wake(), None);
+        assert_eq!(off.poll(t0 + secs(3600)), Tick::Idle);
+    }
+}
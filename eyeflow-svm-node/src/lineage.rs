@@ -0,0 +1,116 @@
+//! Data lineage across registers (SVM_LINEAGE=true)
+//!
+//! While a slice runs, every register-writing instruction replaces its
+//! `dest` lineage with the union of the lineages of the registers it reads,
+//! plus itself.  Instructions that pull data from outside the slice
+//! (LOAD_RESOURCE, CALL_SERVICE, CALL_ACTION, CALL_MCP, LLM_CALL) also add a
+//! leaf source "<OPCODE>:<service_id>", and the slice input register starts
+//! out as "input:R<n>".
+//!
+//! The result answers "which sensor readings fed this actuation decision":
+//! the lineage of the actuation's output register lists every contributing
+//! instruction and every external source, transitively.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::proto::llmir::{IrInstruction, IrOpcode, RegisterLineage};
+use crate::verifier::writes_dest;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Entry {
+    instructions: BTreeSet<i32>,
+    source_registers: BTreeSet<i32>,
+    sources: BTreeSet<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct LineageTracker {
+    regs: HashMap<i32, Entry>,
+}
+
+impl LineageTracker {
+    pub fn new(input_register: i32) -> Self {
+        let input = Entry {
+            sources: BTreeSet::from([format!("input:R{input_register}")]),
+            ..Default::default()
+        };
+        Self { regs: HashMap::from([(input_register, input)]) }
+    }
+
+    /// Account for `instr` having just written its `dest` register.
+    pub fn record(&mut self, instr: &IrInstruction, op: IrOpcode) {
+        if !writes_dest(op) {
+            return;
+        }
+        let mut entry = Entry::default();
+        for r in &instr.src {
+            entry.source_registers.insert(*r);
+            if let Some(parent) = self.regs.get(r) {
+                entry.instructions.extend(&parent.instructions);
+                entry.source_registers.extend(&parent.source_registers);
+                entry.sources.extend(parent.sources.iter().cloned());
+            }
+        }
+        entry.instructions.insert(instr.index);
+        if matches!(
+            op,
+            IrOpcode::LoadResource | IrOpcode::CallService | IrOpcode::CallAction
+                | IrOpcode::CallMcp | IrOpcode::LlmCall
+        ) {
+            let service = if instr.service_id.is_empty() {
+                format!("#{}", instr.index)
+            } else {
+                instr.service_id.clone()
+            };
+            entry.sources.insert(format!("{}:{service}", op.as_str_name()));
+        }
+        self.regs.insert(instr.dest, entry);
+    }
+
+    /// Lineage of each register in `outputs`, sorted by register index.
+    pub fn to_proto(&self, outputs: impl IntoIterator<Item = i32>) -> Vec<RegisterLineage> {
+        let mut out: Vec<RegisterLineage> = outputs.into_iter()
+            .filter_map(|r| self.regs.get(&r).map(|e| RegisterLineage {
+                register_index: r,
+                instructions: e.instructions.iter().copied().collect(),
+                source_registers: e.source_registers.iter().copied().collect(),
+                sources: e.sources.iter().cloned().collect(),
+            }))
+            .collect();
+        out.sort_by_key(|l| l.register_index);
+        out
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instr(index: i32, op: IrOpcode, dest: i32, src: Vec<i32>, service: &str) -> IrInstruction {
+        IrInstruction { index, opcode: op as i32, dest, src, service_id: service.into(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_lineage_is_transitive() {
+        let mut t = LineageTracker::new(0);
+        let steps = [
+            instr(1, IrOpcode::LoadResource, 1, vec![], "temp-sensor"),
+            instr(2, IrOpcode::LoadResource, 2, vec![], "humidity-sensor"),
+            instr(3, IrOpcode::Transform, 3, vec![1, 0], ""),
+            instr(4, IrOpcode::Branch, 0, vec![3], ""),
+            instr(5, IrOpcode::CallAction, 4, vec![3], "valve"),
+        ];
+        for s in &steps {
+            t.record(s, IrOpcode::try_from(s.opcode).unwrap());
+        }
+
+        let l = t.to_proto([4, 2]);
+        assert_eq!(l[0].register_index, 2);
+        assert_eq!(l[1].register_index, 4);
+        assert_eq!(l[1].instructions, vec![1, 3, 5]);
+        assert_eq!(l[1].source_registers, vec![0, 1, 3]);
+        assert_eq!(l[1].sources, vec!["CALL_ACTION:valve", "LOAD_RESOURCE:temp-sensor", "input:R0"]);
+    }
+}
@@ -0,0 +1,269 @@
+//! Logging — stdout plus an optional rotating log file, text or JSON
+//!
+//!   RUST_LOG                   filter (default info)
+//!   SVM_LOG_FORMAT             compact (default) | json — one object per line
+//!                              { timestamp, level, target, message, fields, spans }
+//!                              for SIEM agents
+//!   SVM_LOG_FILE               also write to this file (unset = stdout only)
+//!   SVM_LOG_ROTATION           never | hourly | daily (default daily)
+//!   SVM_LOG_MAX_BYTES          rotate once the file would exceed this (default
+//!                              10 MiB, 0 = no size limit)
+//!   SVM_LOG_MAX_FILES          rotated files kept: <file>.1 (newest) … <file>.N
+//!                              (default 7)
+//!
+//! Rotation is checked on every write, so a quiet node rotates on its next
+//! line rather than on the hour.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Map, Value};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriterExt};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::config::Config;
+
+/// Install the global subscriber described by `config`.
+pub fn init(config: &Config) -> Result<()> {
+    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| {
+            tracing_subscriber::EnvFilter::new(&config.log_level)
+        });
+
+    let writer = match &config.log_file {
+        Some(path) => {
+            let file = RollingFile::open(
+                path.into(),
+                Rotation::parse(&config.log_rotation)?,
+                config.log_max_bytes,
+                config.log_max_files,
+            )?;
+            BoxMakeWriter::new(std::io::stdout.and(Arc::new(file)))
+        }
+        None => BoxMakeWriter::new(std::io::stdout),
+    };
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .with_writer(writer)
+        .with_ansi(config.log_file.is_none());
+
+    let installed = match config.log_format.as_str() {
+        "json" => builder.event_format(JsonFormat).try_init(),
+        "compact" => builder.with_target(true).compact().try_init(),
+        other => return Err(anyhow!("SVM_LOG_FORMAT must be compact or json, got '{other}'")),
+    };
+    installed.map_err(|e| anyhow!("installing the log subscriber: {e}"))
+}
+
+// ── Rotating file ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rotation {
+    Never,
+    Hourly,
+    Daily,
+}
+
+impl Rotation {
+    fn parse(s: &str) -> Result<Self> {
+        match s {
+            "never" => Ok(Self::Never),
+            "hourly" => Ok(Self::Hourly),
+            "daily" => Ok(Self::Daily),
+            other => Err(anyhow!("SVM_LOG_ROTATION must be never, hourly or daily, got '{other}'")),
+        }
+    }
+
+    /// Period the timestamp falls in; a change triggers rotation.
+    fn period(self, unix_secs: i64) -> i64 {
+        match self {
+            Self::Never => 0,
+            Self::Hourly => unix_secs / 3_600,
+            Self::Daily => unix_secs / 86_400,
+        }
+    }
+}
+
+struct FileState {
+    file: File,
+    size: u64,
+    period: i64,
+}
+
+pub struct RollingFile {
+    path: PathBuf,
+    rotation: Rotation,
+    max_bytes: u64,
+    max_files: usize,
+    state: Mutex<FileState>,
+}
+
+impl RollingFile {
+    pub fn open(path: PathBuf, rotation: Rotation, max_bytes: u64, max_files: usize) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).with_context(|| format!("creating {parent:?}"))?;
+        }
+        let file = Self::append(&path)?;
+        let meta = file.metadata()?;
+        let modified = meta.modified().ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or_else(now_secs, |d| d.as_secs() as i64);
+        Ok(Self {
+            state: Mutex::new(FileState { file, size: meta.len(), period: rotation.period(modified) }),
+            path,
+            rotation,
+            max_bytes,
+            max_files,
+        })
+    }
+
+    fn append(path: &PathBuf) -> Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+            .with_context(|| format!("opening log file {path:?}"))
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{n}"));
+        name.into()
+    }
+
+    /// Shift <file>.k → <file>.k+1, dropping the oldest, then start afresh.
+    fn rotate(&self, state: &mut FileState) -> std::io::Result<()> {
+        state.file.flush()?;
+        if self.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated(self.max_files));
+            for n in (1..self.max_files).rev() {
+                let _ = std::fs::rename(self.rotated(n), self.rotated(n + 1));
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+        }
+        state.file = Self::append(&self.path).map_err(std::io::Error::other)?;
+        state.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for &RollingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let period = self.rotation.period(now_secs());
+        let too_big = self.max_bytes > 0 && state.size > 0 && state.size + buf.len() as u64 > self.max_bytes;
+        if too_big || period != state.period {
+            self.rotate(&mut state)?;
+            state.period = period;
+        }
+        state.file.write_all(buf)?;
+        state.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).file.flush()
+    }
+}
+
+fn now_secs() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+// ── JSON format ───────────────────────────────────────────────────────────────
+
+/// One JSON object per event.
+pub struct JsonFormat;
+
+#[derive(Default)]
+struct JsonFields(Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_owned(), Value::String(format!("{value:?}")));
+    }
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), Value::String(value.to_owned()));
+    }
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> std::fmt::Result {
+        let mut fields = JsonFields::default();
+        event.record(&mut fields);
+        let message = fields.0.remove("message").unwrap_or(Value::String(String::new()));
+        let meta = event.metadata();
+        let spans: Vec<&str> = ctx.event_scope()
+            .map(|scope| scope.from_root().map(|span| span.name()).collect())
+            .unwrap_or_default();
+        let line = json!({
+            "timestamp": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "level": meta.level().as_str(),
+            "target": meta.target(),
+            "message": message,
+            "fields": fields.0,
+            "spans": spans,
+        });
+        writeln!(writer, "{line}")
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_and_json_lines() {
+        let dir = std::env::temp_dir().join(format!("eyeflow_logs_{}", uuid::Uuid::new_v4()));
+        let path = dir.join("node.log");
+        let file = Arc::new(RollingFile::open(path.clone(), Rotation::Never, 100, 2).unwrap());
+
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(file.clone())
+            .with_ansi(false)
+            .event_format(JsonFormat)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("slice");
+            let _enter = span.enter();
+            for n in 0..6 {
+                tracing::warn!(n, ok = true, "line {n}");
+            }
+        });
+
+        // Each line is ~150 bytes > 100, so every write rotates; only 2 kept
+        assert!(path.exists() && path.with_extension("log.1").exists() && path.with_extension("log.2").exists());
+        assert!(!path.with_extension("log.3").exists());
+        let last: Value = serde_json::from_str(std::fs::read_to_string(&path).unwrap().trim()).unwrap();
+        assert_eq!(last["level"], "WARN");
+        assert_eq!(last["message"], "line 5");
+        assert_eq!(last["fields"], json!({ "n": 5, "ok": true }));
+        assert_eq!(last["spans"], json!(["slice"]));
+        let older: Value = serde_json::from_str(std::fs::read_to_string(path.with_extension("log.2")).unwrap().trim()).unwrap();
+        assert_eq!(older["message"], "line 3");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,45 +1,149 @@
-/// Eyeflow SVM Node — entry point (spec §6, §8)
-///
-/// Start-up sequence:
-///   1. Parse Config from environment variables (see src/config.rs)
-///   2. Initialise structured logging (RUST_LOG / SVM_LOG_LEVEL)
-///   3. Restore any persisted offline buffer (NDJSON file)
-///   4. Build AuditChain with Ed25519 signing key
-///   5. Build Svm executor
-///   6. Enter NodeClient.run() — reconnect loop with exponential back-off
+//! Eyeflow SVM Node — entry point (spec §6, §8)
+//!
+//! Start-up sequence:
+//!   1. Parse Config from environment variables (see src/config.rs)
+//!   2. Initialise structured logging (RUST_LOG, SVM_LOG_FORMAT, SVM_LOG_FILE — logging.rs)
+//!      (+ roll back a self-update that keeps crashing, update.rs)
+//!   3. Restore any persisted offline buffer (NDJSON file)
+//!   4. Build AuditChain with Ed25519 signing key
+//!   5. Restore the service catalogue (+ plan-scoped shared state)
+//!   6. Build Svm executor (+ peer delegation link, SVM_PEERS / SVM_PEER_PORT),
+//!      start the catalogue sync on its HTTP client,
+//!      probe devices, brokers and runtimes (capabilities.rs),
+//!      then follow devices being plugged / unplugged (hotplug.rs)
+//!   7. Enter NodeClient.run() — reconnect loop with exponential back-off
+//!      until SIGTERM / SIGINT, then drain and exit (STOPPING=1 to systemd)
+//!
+//! `eyeflow-svm-node replay …` runs the audit replay tool instead (replay.rs);
+//! `eyeflow-svm-node reproduce …` re-runs a recorded slice (recording.rs);
+//! `eyeflow-svm-node verify …` checks offline buffer / audit files (integrity.rs);
+//! `eyeflow-svm-node bench …` runs a load / soak test against mock endpoints (bench.rs);
+//! `eyeflow-svm-node bench-json …` compares the JSON parsers on this CPU (fastjson.rs);
+//! `eyeflow-svm-node version` prints the version (probed on staged releases, update.rs).
 
+mod admission;
+mod arbiter;
+mod assets;
+mod async_poll;
 mod audit;
+mod audit_export;
+mod audit_policy;
+mod audit_verify;
+mod backoff;
+mod bench;
+mod blob;
+mod budget;
+mod callbacks;
+mod capabilities;
+mod catalog;
+mod chaos;
+mod clock;
 mod config;
+mod config_sig;
+mod connectors;
+mod disk_watch;
+mod dlock;
+mod dns;
+mod errors;
 mod fallback;
+mod fastjson;
+mod features;
+mod flush_codec;
+mod guardrails;
 mod health;
+mod health_score;
+mod hotplug;
+mod http_cache;
+mod inference;
+mod input_schema;
+mod integrity;
+mod ir_allowlist;
+mod ir_limits;
+mod keepalive;
+mod lineage;
+mod logging;
+mod mcu;
+mod merge;
+mod metrics_push;
+mod middleware;
 mod node;
 mod offline;
+mod peer;
+mod pii;
+mod placement;
+mod plan_state;
+mod predicate;
+mod prepared;
 mod proto;
+mod provision;
+mod recording;
+mod redis_store;
+mod register_memory;
+mod replay;
+mod response_cache;
+mod sched;
+mod sdnotify;
+mod secret_audit;
+mod session;
+mod shaping;
+mod results;
+mod standby;
+mod supervisor;
 mod svm;
+mod tabular;
+mod taint;
+mod tenant;
+mod tls;
+mod transform;
+mod units;
+mod update;
 mod vault;
+mod verifier;
+mod wal;
+mod workflow_stats;
+mod ws_limits;
 
 use anyhow::Result;
 use offline::{ensure_parent, OfflineBuffer};
-use tracing::info;
+use tracing::{info, warn};
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // ── 0. Subcommands ────────────────────────────────────────────────────────
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("replay") {
+        return replay::run(&args[2..]);
+    }
+    if args.get(1).map(String::as_str) == Some("reproduce") {
+        return recording::reproduce(&args[2..]).await;
+    }
+    if args.get(1).map(String::as_str) == Some("verify") {
+        return integrity::run(&args[2..]);
+    }
+    if args.get(1).map(String::as_str) == Some("audit") {
+        return audit_export::run(&args[2..]).await;
+    }
+    if args.get(1).map(String::as_str) == Some("provision") {
+        return provision::run(&args[2..]).await;
+    }
+    if args.get(1).map(String::as_str) == Some("bench") {
+        return bench::run(&args[2..]).await;
+    }
+    if args.get(1).map(String::as_str) == Some("bench-json") {
+        return fastjson::bench(&args[2..]);
+    }
+    if args.get(1).map(String::as_str) == Some("version") {
+        println!("eyeflow-svm-node {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+
     // ── 1. Config ─────────────────────────────────────────────────────────────
     // Load .env file if present (development convenience)
     let _ = dotenvy::dotenv();
     let config = config::Config::from_env();
 
     // ── 2. Logging ────────────────────────────────────────────────────────────
-    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| {
-            tracing_subscriber::EnvFilter::new(&config.log_level)
-        });
-
-    tracing_subscriber::fmt()
-        .with_env_filter(env_filter)
-        .with_target(true)
-        .compact()
-        .init();
+    logging::init(&config)?;
 
     info!(
         "eyeflow-svm-node v{} starting (node_id={}, tier={})",
@@ -48,20 +152,60 @@ async fn main() -> Result<()> {
         config.node_tier,
     );
 
+    // ── 2a. Self-update: a crash-looping release is rolled back first ─────────
+    let updater = update::Updater::from_config(&config)?;
+    if let Some(u) = &updater {
+        if u.boot()? {
+            return u.restart();
+        }
+    }
+
     // ── 3. Offline buffer ─────────────────────────────────────────────────────
     let buf_path = std::path::PathBuf::from(&config.offline_buffer_path);
     ensure_parent(&buf_path).await?;
-    let offline = OfflineBuffer::new(&buf_path, config.offline_buffer_max);
+    let disk_watch = disk_watch::DiskWatch::from_config(&config);
+    let offline = OfflineBuffer::new(&buf_path, config.offline_buffer_max)
+        .with_persist_every(config.offline_persist_every)
+        .with_max_alerts(config.alert_queue_max)
+        .with_disk_watch(disk_watch.clone());
 
     // ── 4. Audit chain ────────────────────────────────────────────────────────
-    let audit = audit::AuditChain::new(
+    let keystore_pem = provision::keystore_pem(&config)?;
+    let mut audit = audit::AuditChain::new(
         config.node_id.clone(),
-        config.signing_private_key_pem.as_deref(),
+        config.signing_private_key_pem.as_deref().or(keystore_pem.as_deref()),
     )?;
+    audit.set_policy(audit_policy::AuditPolicy::from_config(&config)?.map(std::sync::Arc::new));
+
+    // ── 4a. CONFIG_UPDATE verification ────────────────────────────────────────
+    let config_verifier = config_sig::ConfigVerifier::new(config.config_ops_public_key.as_deref())?
+        .with_state(config.config_update_state_path.clone().into());
+
+    if !config_verifier.requires_signature() {
+        warn!("[Main] SVM_CONFIG_OPS_PUBLIC_KEY not set — CONFIG_UPDATE is applied unsigned");
+    }
+    let ir_key = config.ir_public_key.as_deref()
+        .or(config.config_ops_public_key.as_deref())
+        .map(config_sig::verifying_key_from_str)
+        .transpose()?;
+    if config.allow_unsigned_ir {
+        warn!("[Main] SVM_ALLOW_UNSIGNED_IR — unsigned IR artifacts are executed (development only)");
+    } else if ir_key.is_none() {
+        warn!("[Main] no IR signing key pinned (SVM_IR_PUBLIC_KEY) — every IR artifact will be refused");
+    }
 
     // ── 4b. HealthMonitor ─────────────────────────────────────────────────────
     let health_state = health::HealthState::new(&config.node_id, &config.node_tier);
     let health_port  = config.health_port;
+    health_state.set_thresholds(health_score::HealthThresholds::from_config(&config)?);
+    health_state.set_workflow_metrics_max(config.workflow_metrics_max);
+    health_state.set_disk_path(match &config.health_disk_path {
+        Some(p) => std::path::PathBuf::from(p),
+        None => buf_path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(std::path::Path::new(".")).to_path_buf(),
+    });
+    if let Some(w) = &disk_watch {
+        health_state.register_collector(w.clone());
+    }
     {
         let hs = health_state.clone();
         tokio::spawn(async move {
@@ -71,11 +215,268 @@ async fn main() -> Result<()> {
         });
     }
     info!("[Health] HealthMonitor started on port {health_port}");
+    if let Some(pusher) = metrics_push::MetricsPusher::from_config(&config, health_state.clone())? {
+        tokio::spawn(pusher.run());
+    }
+
+    // ── 4c. Enrollment (keystore-provisioned nodes) ───────────────────────────
+    let enrollment = provision::Enrollment::from_config(&config, audit.signing_key());
+    if let Some(e) = &enrollment {
+        health_state.register_collector(e.clone());
+        if e.quarantined() {
+            tokio::spawn(e.clone().run());
+        }
+    }
+
+    // ── 4d. mTLS client certificate (renewed ahead of expiry) ─────────────────
+    let tls = tls::TlsIdentity::from_config(&config)?;
+    if let Some(t) = &tls {
+        health_state.register_collector(t.clone());
+        tokio::spawn(t.clone().run());
+    }
+
+    // ── 4e. Uplink shaping (constrained links) ────────────────────────────────
+    let shaper = shaping::Shaper::from_config(&config);
+    if let Some(s) = &shaper {
+        health_state.register_collector(s.clone());
+    }
+
+    // ── 4f. Self-update probation ─────────────────────────────────────────────
+    if let Some(u) = &updater {
+        health_state.register_collector(u.clone());
+        tokio::spawn(update::run_probation(u.clone(), health_state.clone()));
+    }
+
+    // ── 5. Service catalogue ──────────────────────────────────────────────────
+    let catalog_path = std::path::PathBuf::from(&config.catalog_path);
+    ensure_parent(&catalog_path).await?;
+    let catalog = catalog::ServiceCatalog::new(catalog_path);
+    if let Err(e) = catalog.load().await {
+        warn!("[Catalog] failed to load snapshot: {e}");
+    }
+    // ── 5b. Plan-scoped shared state ──────────────────────────────────────────
+    let plan_state_path = config.plan_state_path.as_ref().map(std::path::PathBuf::from);
+    if let Some(p) = &plan_state_path {
+        ensure_parent(p).await?;
+    }
+    let plan_state = plan_state::PlanStateStore::new(config.plan_state_ttl_secs, plan_state_path);
+    if let Err(e) = plan_state.load().await {
+        warn!("[PlanState] failed to load snapshot: {e}");
+    }
+
+    // ── 5c. Action write-ahead log ────────────────────────────────────────────
+    let wal_path = std::path::PathBuf::from(&config.action_wal_path);
+    ensure_parent(&wal_path).await?;
+    let action_wal = wal::ActionWal::open(wal_path).await?;
+
+    // ── 5d. Kafka sink ────────────────────────────────────────────────────────
+    let kafka = connectors::kafka::KafkaSink::from_config(&config, disk_watch.clone()).await?;
+    if let Some(sink) = &kafka {
+        health_state.register_collector(sink.clone());
+        tokio::spawn(sink.clone().run());
+    }
+
+    // ── 5e. Redis shared state ────────────────────────────────────────────────
+    let redis = redis_store::RedisStore::from_config(&config)?;
+
+    // ── 5f. CALL_ACTION callbacks ─────────────────────────────────────────────
+    let callbacks = callbacks::CallbackRegistry::from_config(&config);
+    if let Some(registry) = &callbacks {
+        health_state.set_callbacks(registry.clone());
+        health_state.register_collector(registry.clone());
+    }
 
-    // ── 5. SVM executor ────────────────────────────────────────────────────────
-    let svm = svm::Svm::new(config.clone());
+    // ── 5g. Chaos mode (fault injection) ──────────────────────────────────────
+    let chaos = chaos::Chaos::from_config(&config)?;
+    if let Some(c) = &chaos {
+        health_state.register_collector(c.clone());
+    }
+
+    // ── 5h. MCU bridge (downstream MCU SVMs) ──────────────────────────────────
+    let mcu = mcu::McuBridge::from_config(&config)?;
+    if let Some(bridge) = &mcu {
+        health_state.register_collector(bridge.clone());
+    }
+
+    // ── 5i. Device hotplug (USB serial adapters, GPIO chips) ──────────────────
+    let hotplug = hotplug::Hotplug::from_config(&config, mcu.as_deref());
+    if let Some(h) = &hotplug {
+        health_state.register_collector(h.clone());
+    }
+
+    // ── 5j. Zigbee / Z-Wave bridges (MQTT) ────────────────────────────────────
+    let mqtt = connectors::mqtt::MqttBridge::from_config(&config)?;
+    if let Some(bridge) = &mqtt {
+        health_state.register_collector(bridge.clone());
+        tokio::spawn(bridge.clone().run());
+    }
+
+    // ── 6. SVM executor ────────────────────────────────────────────────────────
+    let peer_link = peer::PeerLink::from_config(&config)?;
+    let middleware = std::sync::Arc::new(middleware::Middlewares::from_config(&config)?);
+    let cpu_budgets = std::sync::Arc::new(budget::CpuBudgets::from_config(&config)?);
+    health_state.register_collector(cpu_budgets.clone());
+    let allowlist = std::sync::Arc::new(ir_allowlist::IrAllowList::from_config(&config)?);
+    health_state.register_collector(allowlist.clone());
+    health_state.register_collector(middleware.clone());
+    let svm = std::sync::Arc::new(
+        svm::Svm::new(config.clone(), catalog, plan_state, action_wal)
+            .with_recorder(recording::Recorder::from_config(&config)?)
+            .with_peers(peer_link.clone())
+            .with_kafka(kafka.clone())
+            .with_redis(redis)
+            .with_callbacks(callbacks)
+            .with_chaos(chaos.clone())
+            .with_mcu(mcu)
+            .with_hotplug(hotplug.clone())
+            .with_mqtt(mqtt)
+            .with_allowlist(allowlist.clone())
+            .with_plugins(std::sync::Arc::new(connectors::plugin::PluginHost::from_config(&config)?))
+            .with_shaper(shaper.clone())
+            .with_middleware(middleware.clone())
+            .with_cpu_budgets(cpu_budgets)
+            .with_health(Some(health_state.clone())),
+    );
+    health_state.register_collector(svm.resource_arbiter().clone());
+    health_state.register_collector(svm.response_cache().clone());
+    health_state.register_collector(svm.notifier().clone());
+    health_state.register_collector(svm.supervisor().clone());
+    health_state.register_collector(svm.http_cache().clone());
+    health_state.register_collector(svm.blobs().clone());
+    health_state.register_collector(svm.resolver().clone());
+    // Same client (timeout, SVM_DNS_HOSTS resolution) as the SVM's calls to central
+    tokio::spawn(catalog::run_sync(
+        svm.catalog().clone(),
+        svm.http().clone(),
+        config.central_http_url.clone(),
+        config.node_id.clone(),
+        config.catalog_sync_interval_secs,
+    ));
+    svm.capabilities().refresh().await;
+    tokio::spawn(capabilities::run(svm.capabilities().clone()));
+    if let Some(h) = hotplug {
+        tokio::spawn(hotplug::run(h, svm.clone(), health_state.clone()));
+    }
+
+    // ── 6a. Peer delegation (served after 6e) ─────────────────────────────────
+    if let Some(link) = &peer_link {
+        health_state.register_collector(link.clone());
+    }
+
+    // ── 6b. Tenant quotas ─────────────────────────────────────────────────────
+    let tenants = tenant::TenantGovernor::new(tenant::TenantQuota::from(&config));
+    health_state.register_collector(tenants.clone());
+
+    // ── 6c. Result retention ──────────────────────────────────────────────────
+    let results_path = config.results_path.as_ref().map(std::path::PathBuf::from);
+    if let Some(p) = &results_path {
+        ensure_parent(p).await?;
+    }
+    let results = results::ResultStore::new(config.results_max, config.results_retention_secs, results_path);
+    if let Err(e) = results.load().await {
+        warn!("[Results] failed to load snapshot: {e}");
+    }
+    health_state.set_result_store(results.clone(), config.health_api_token.clone());
+    let session_path = config.session_path.as_ref().map(std::path::PathBuf::from);
+    if let Some(p) = &session_path {
+        ensure_parent(p).await?;
+    }
+    let session = session::SessionLedger::new(config.session_max_unacked, session_path);
+    if let Err(e) = session.load().await {
+        warn!("[Session] failed to load ledger: {e}");
+    }
+    health_state.register_collector(session.clone());
 
-    // ── 6. Node client — runs forever ─────────────────────────────────────────────────
-    let mut client = node::NodeClient::new(config, svm, audit, offline, health_state);
-    client.run().await
+    // ── 6d. Audit log (export) ────────────────────────────────────────────────
+    let audit_log = audit_export::AuditLog::from_config(&config, audit.signing_key());
+    if let Some(log) = &audit_log {
+        ensure_parent(log.path()).await?;
+        health_state.set_audit_log(log.clone(), config.health_api_token.clone());
+    }
+
+    // ── 6e. Warm standby ──────────────────────────────────────────────────────
+    let standby = if config.standby_enabled {
+        let s = standby::Standby::new(&config)?;
+        health_state.register_collector(s.clone());
+        tokio::spawn(standby::run_election(s.clone(), health_state.clone()));
+        Some(s)
+    } else {
+        None
+    };
+
+    // Delegations from peers pass the same admission gates as central's slices
+    if let Some(link) = peer_link.filter(|_| config.peer_port > 0) {
+        let admission = admission::Admission::new(allowlist, tenants.clone())
+            .with_enrollment(enrollment.clone())
+            .with_standby(standby.clone());
+        let (svm, port) = (svm.clone(), config.peer_port);
+        tokio::spawn(async move {
+            if let Err(e) = peer::serve(link, svm, admission, port).await {
+                tracing::error!("[Peer] server exited: {e}");
+            }
+        });
+    }
+
+
+    // ── 6f. Graceful shutdown ─────────────────────────────────────────────────
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let mut restart = updater.as_ref().map(|u| u.restart_requested());
+    tokio::spawn(async move {
+        let restart_requested = async {
+            let requested = match restart.as_mut() {
+                Some(rx) => rx.wait_for(|r| *r).await.is_ok(),
+                None => false,
+            };
+            if !requested {
+                std::future::pending::<()>().await;
+            }
+        };
+        tokio::select! {
+            _ = shutdown_signal() => info!("[Main] shutdown requested — draining"),
+            _ = restart_requested => info!("[Main] restart for a self-update — draining"),
+        }
+        sdnotify::stopping();
+        let _ = shutdown_tx.send(true);
+    });
+
+    // ── 7. Node client — runs until shutdown ──────────────────────────────────────────
+    let mut client = node::NodeClient::new(config, svm, audit, offline, health_state, tenants, results)
+        .with_standby(standby)
+        .with_kafka(kafka)
+        .with_audit_log(audit_log)
+        .with_enrollment(enrollment)
+        .with_tls(tls)
+        .with_shaper(shaper)
+        .with_disk_watch(disk_watch)
+        .with_chaos(chaos)
+        .with_shutdown(shutdown_rx)
+        .with_config_verifier(config_verifier)
+        .with_ir_key(ir_key)
+
+        .with_session(session)
+        .with_updater(updater.clone());
+    client.run().await?;
+    match updater {
+        Some(u) if u.restart_pending() => u.restart(),
+        _ => Ok(()),
+    }
+}
+
+/// Resolves on SIGTERM (systemd stop) or SIGINT.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = term.recv() => {}
+                    _ = tokio::signal::ctrl_c() => {}
+                }
+                return;
+            }
+            Err(e) => warn!("[Main] cannot listen for SIGTERM: {e}"),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
 }
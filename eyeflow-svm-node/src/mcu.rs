@@ -0,0 +1,652 @@
+//! MCU bridge — downstream MCU SVMs on serial links
+//!
+//! Microcontrollers behind the node (eyeflow-svm-mcu) execute the compact
+//! MCU binary IR, received over a serial link.  The IR layout, opcodes,
+//! framing (0xAA 0x55 <len> <payload> <CRC-16>), link messages and error
+//! codes come from eyeflow-svm-core, the crate the firmware is built on, so
+//! what the node generates is exactly what the firmware parses.
+//!
+//!   SVM_MCU_PORTS="/dev/ttyACM0,/dev/ttyUSB*@57600"
+//!       ttys to bridge, baud 115200 unless given; a trailing `*`
+//!       enumerates every matching device at start-up
+//!   SVM_MCU_TIMEOUT_MS=2000     wait for the MCU's RESULT
+//!
+//! A port is named after its device and addressed as `mcu://ttyACM0`:
+//!
+//!   CALL_SERVICE  operands { "mcu": { "service": 2 } }
+//!       → LOAD_IMM R0 ← input; CALL_SERVICE 2 R0 → R1; RETURN R1
+//!   CALL_ACTION   operands { "mcu": { "action": 0, "args": [5] } }
+//!       → LOAD_IMM R0 ← input; CALL_ACTION 0 R0 [5]; RETURN R0
+//!   either        operands { "mcu": { "artifact": "<base64>" } }
+//!       → a whole MCU-profile artifact, validated and sent as is
+//!   LOAD_RESOURCE mcu://ttyACM0/flush
+//!       → drains the offline entries the MCU flushed:
+//!         [{ "kind", "flags", "value", "receivedAt" }]
+//!
+//! The input is a number 0..=65535, a bool or { "value": n } (null = 0);
+//! the answer is { "port", "value", "output" } (value = the RETURN register,
+//! output = the returned bytes in hex).  With `mcu.convert` (a units.rs
+//! spec, e.g. an ADC calibration) the answer also carries the reading in
+//! engineering units as `converted` (and `unit`).  The MCU answers every artifact with
+//! a RESULT, then FLUSH / STATUS; one artifact is in flight per port.
+//! Rejected artifacts and MCU runtime faults fail with VALIDATION, calls the
+//! MCU queued offline with UPSTREAM (transient), silence with TIMEOUT.
+//!
+//! A reader thread per port deframes what the MCU sends and reopens the
+//! tty every 5 s after an error, or as soon as the device watcher sees it
+//! plugged back (hotplug.rs).  /metrics: eyeflow_mcu_up,
+//! eyeflow_mcu_frames_total{direction}, eyeflow_mcu_frame_errors_total,
+//! eyeflow_mcu_results_total{status}, eyeflow_mcu_timeouts_total,
+//! eyeflow_mcu_flushed_entries_total, eyeflow_mcu_offline_pending,
+//! eyeflow_mcu_offline_dropped and eyeflow_mcu_last_seen_seconds.
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{debug, info, warn};
+
+use eyeflow_svm_core::frame::FrameDecoder;
+use eyeflow_svm_core::ir::{Instruction, Program, MAX_SERVICE_ID};
+use eyeflow_svm_core::link::{Message, Status};
+use eyeflow_svm_core::FrameError;
+
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+use crate::health::MetricsCollector;
+use crate::units::Conversion;
+
+const DEFAULT_BAUD: u32 = 115_200;
+const REOPEN_EVERY: Duration = Duration::from_secs(5);
+/// Flushed entries kept until a LOAD_RESOURCE drains them
+const MAX_FLUSHED: usize = 1024;
+/// Receive buffer: the largest frame an MCU sends
+const RX_FRAME_LEN: usize = 4096;
+
+// ── Artifacts and frames ──────────────────────────────────────────────────────
+
+/// Encode `instrs` as an MCU-profile artifact.
+pub fn artifact(instrs: &[Instruction]) -> Result<Vec<u8>> {
+    eyeflow_svm_core::ir::encode(instrs)
+        .map_err(|e| ExecError::validation(format!("MCU artifact: {e}")))
+}
+
+/// Wrap `payload` in an edge-link frame.
+pub fn frame(payload: &[u8]) -> Result<Vec<u8>> {
+    eyeflow_svm_core::frame::encode(payload)
+        .map_err(|e| ExecError::validation(format!("MCU frame: {e}")))
+}
+
+/// Which IR instruction is being translated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Call {
+    Service,
+    Action,
+}
+
+/// The MCU program for one CALL_SERVICE / CALL_ACTION on an `mcu://` endpoint.
+pub fn translate(call: Call, operands: &Value, input: Option<&Value>) -> Result<Vec<u8>> {
+    let spec = operands.get("mcu")
+        .ok_or_else(|| ExecError::validation("mcu:// endpoints need operands.mcu"))?;
+    if let Some(encoded) = spec.get("artifact").and_then(|v| v.as_str()) {
+        let bytes = B64.decode(encoded.trim())
+            .map_err(|e| ExecError::validation(format!("mcu.artifact is not base64: {e}")))?;
+        Program::parse(&bytes).map_err(|e| ExecError::validation(format!("mcu.artifact: {e}")))?;
+        return Ok(bytes);
+    }
+    let byte = |name: &str| -> Result<u8> {
+        spec.get(name)
+            .and_then(|v| v.as_u64())
+            .and_then(|v| u8::try_from(v).ok())
+            .ok_or_else(|| ExecError::validation(format!("mcu.{name} must be an integer 0..=255")))
+    };
+    let value = match input.map(|v| v.get("value").unwrap_or(v)) {
+        None | Some(Value::Null) => 0,
+        Some(Value::Bool(b)) => u16::from(*b),
+        Some(v) => v.as_u64()
+            .and_then(|n| u16::try_from(n).ok())
+            .ok_or_else(|| ExecError::validation(format!("MCU input must be an integer 0..=65535, got {v}")))?,
+    };
+    let instrs = match call {
+        Call::Service => {
+            let service = byte("service")?;
+            if service >= MAX_SERVICE_ID {
+                return Err(ExecError::validation(format!("mcu.service must be below {MAX_SERVICE_ID}")));
+            }
+            [
+                Instruction::LoadImm { output: 0, value },
+                Instruction::CallService { service, input: 0, output: 1 },
+                Instruction::Return { output: 1 },
+            ]
+        }
+        Call::Action => {
+            let mut args = [0u8; 5];
+            let given = spec.get("args").and_then(|v| v.as_array()).map_or(&[][..], Vec::as_slice);
+            if given.len() > args.len() {
+                return Err(ExecError::validation("mcu.args takes at most 5 bytes"));
+            }
+            for (slot, v) in args.iter_mut().zip(given) {
+                *slot = v.as_u64().and_then(|n| u8::try_from(n).ok())
+                    .ok_or_else(|| ExecError::validation("mcu.args must be integers 0..=255"))?;
+            }
+            [
+                Instruction::LoadImm { output: 0, value },
+                Instruction::CallAction { action: byte("action")?, value: 0, args },
+                Instruction::Return { output: 0 },
+            ]
+        }
+    };
+    artifact(&instrs)
+}
+
+// ── Ports ─────────────────────────────────────────────────────────────────────
+
+/// RESULT of the artifact in flight.
+#[derive(Debug)]
+struct Reply {
+    status: Status,
+    code: u8,
+    output: Vec<u8>,
+}
+
+#[derive(Default)]
+struct PortStats {
+    frames_tx: AtomicU64,
+    frames_rx: AtomicU64,
+    frame_errors: AtomicU64,
+    ok: AtomicU64,
+    rejected: AtomicU64,
+    faulted: AtomicU64,
+    queued: AtomicU64,
+    timeouts: AtomicU64,
+    flushed: AtomicU64,
+    /// Last STATUS
+    offline_pending: AtomicU64,
+    offline_dropped: AtomicU64,
+}
+
+pub struct McuPort {
+    name: String,
+    device: String,
+    baud: u32,
+    /// Open tty (None while disconnected); the reader thread owns (re)opening
+    file: Mutex<Option<Arc<File>>>,
+    /// Reader thread, unparked to reopen the tty at once
+    reader: Mutex<Option<std::thread::Thread>>,
+    /// One artifact in flight at a time
+    turn: tokio::sync::Mutex<()>,
+    waiting: Mutex<Option<oneshot::Sender<Reply>>>,
+    flushed: Mutex<VecDeque<Value>>,
+    last_seen: Mutex<Option<Instant>>,
+    stats: PortStats,
+}
+
+impl McuPort {
+    fn connected(&self) -> bool {
+        self.file.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+    }
+
+    /// Reader thread: (re)open the tty and deframe what the MCU sends.
+    fn run(self: Arc<Self>) {
+        loop {
+            let file = match open(&self.device, self.baud) {
+                Ok(f) => Arc::new(f),
+                Err(e) => {
+                    debug!("[MCU] {}: {e}", self.name);
+                    std::thread::park_timeout(REOPEN_EVERY);
+                    continue;
+                }
+            };
+            *self.file.lock().unwrap_or_else(|e| e.into_inner()) = Some(file.clone());
+            info!("[MCU] {} connected ({} @ {} baud)", self.name, self.device, self.baud);
+
+            let mut decoder: FrameDecoder<RX_FRAME_LEN> = FrameDecoder::new();
+            let mut buf = [0u8; 512];
+            let error = loop {
+                // The tty is blocking: read only once poll reports data or a hang-up
+                match wait(&file, true, 1_000) {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(e) => break e,
+                }
+                match (&*file).read(&mut buf) {
+                    Ok(0) => break anyhow!("hung up"),
+                    Ok(n) => {
+                        for &b in &buf[..n] {
+                            if let Some(frame) = decoder.push(b) {
+                                self.on_frame(frame);
+                            }
+                        }
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                    Err(e) => break e.into(),
+                }
+            };
+            *self.file.lock().unwrap_or_else(|e| e.into_inner()) = None;
+            warn!("[MCU] {} disconnected: {error}", self.name);
+            std::thread::park_timeout(REOPEN_EVERY);
+        }
+    }
+
+    fn on_frame(&self, frame: Result<&[u8], FrameError>) {
+        let message = frame.and_then(Message::parse);
+        let message = match message {
+            Ok(m) => m,
+            Err(e) => {
+                self.stats.frame_errors.fetch_add(1, Ordering::Relaxed);
+                debug!("[MCU] {}: {e}", self.name);
+                return;
+            }
+        };
+        self.stats.frames_rx.fetch_add(1, Ordering::Relaxed);
+        *self.last_seen.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+        match message {
+            Message::Result { status, code, output } => {
+                let counter = match status {
+                    Status::Ok => &self.stats.ok,
+                    Status::ValidationError => &self.stats.rejected,
+                    Status::RuntimeError => &self.stats.faulted,
+                    Status::OfflineQueued => &self.stats.queued,
+                };
+                counter.fetch_add(1, Ordering::Relaxed);
+                match self.waiting.lock().unwrap_or_else(|e| e.into_inner()).take() {
+                    Some(tx) => {
+                        let _ = tx.send(Reply { status, code, output: output.to_vec() });
+                    }
+                    None => debug!("[MCU] {}: RESULT with no artifact in flight", self.name),
+                }
+            }
+            Message::Flush(_) => {
+                let received_at = chrono::Utc::now().to_rfc3339();
+                let mut flushed = self.flushed.lock().unwrap_or_else(|e| e.into_inner());
+                for entry in message.entries() {
+                    let kind = match entry.kind {
+                        0x01 => json!("report"),
+                        0x02 => json!("action_result"),
+                        other => json!(other),
+                    };
+                    flushed.push_back(json!({
+                        "kind": kind, "flags": entry.flags, "value": entry.value, "receivedAt": received_at,
+                    }));
+                    if flushed.len() > MAX_FLUSHED {
+                        flushed.pop_front();
+                    }
+                    self.stats.flushed.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            Message::Status { pending, dropped } => {
+                self.stats.offline_pending.store(pending.into(), Ordering::Relaxed);
+                self.stats.offline_dropped.store(dropped.into(), Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Send `artifact` and wait for its RESULT.
+    async fn request(&self, artifact: &[u8], timeout: Duration) -> Result<Reply> {
+        let wire = frame(artifact)?;
+        let _turn = self.turn.lock().await;
+        let file = self.file.lock().unwrap_or_else(|e| e.into_inner()).clone().ok_or_else(|| ExecError::new(
+            ErrorCode::Network,
+            format!("MCU {} ({}) is not connected", self.name, self.device),
+        ))?;
+        let (tx, rx) = oneshot::channel();
+        *self.waiting.lock().unwrap_or_else(|e| e.into_inner()) = Some(tx);
+        let written = tokio::task::spawn_blocking(move || write_all(&file, &wire)).await?;
+        if let Err(e) = written {
+            self.waiting.lock().unwrap_or_else(|e| e.into_inner()).take();
+            return Err(ExecError::new(ErrorCode::Network, format!("MCU {}: {e}", self.name)).into());
+        }
+        self.stats.frames_tx.fetch_add(1, Ordering::Relaxed);
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(reply)) => Ok(reply),
+            _ => {
+                self.waiting.lock().unwrap_or_else(|e| e.into_inner()).take();
+                self.stats.timeouts.fetch_add(1, Ordering::Relaxed);
+                Err(ExecError::new(ErrorCode::Timeout, format!("MCU {} did not answer in {timeout:?}", self.name)).into())
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open(device: &str, baud: u32) -> Result<File> {
+    crate::connectors::device::open_serial(device, baud)
+}
+
+/// Wait up to `timeout_ms` for the tty to become readable / writable.
+#[cfg(target_os = "linux")]
+fn wait(file: &File, read: bool, timeout_ms: i32) -> Result<bool> {
+    use std::os::fd::AsRawFd;
+    let mut fds = libc::pollfd { fd: file.as_raw_fd(), events: if read { libc::POLLIN } else { libc::POLLOUT }, revents: 0 };
+    // SAFETY: one valid pollfd.
+    match unsafe { libc::poll(&mut fds, 1, timeout_ms) } {
+        n if n < 0 => Err(std::io::Error::last_os_error().into()),
+        n => Ok(n > 0),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open(device: &str, _baud: u32) -> Result<File> {
+    Err(ExecError::new(ErrorCode::Unsupported, format!("{device}: MCU bridging is only supported on Linux nodes")).into())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn wait(_file: &File, _read: bool, _timeout_ms: i32) -> Result<bool> {
+    Ok(true)
+}
+
+fn write_all(mut file: &File, bytes: &[u8]) -> Result<()> {
+    let mut written = 0;
+    while written < bytes.len() {
+        match file.write(&bytes[written..]) {
+            Ok(n) => written += n,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                wait(file, false, 100)?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+// ── Bridge ────────────────────────────────────────────────────────────────────
+
+pub struct McuBridge {
+    ports: Vec<Arc<McuPort>>,
+    timeout: Duration,
+}
+
+impl McuBridge {
+    /// Bridge `ports` (device, baud), each served by its own reader thread.
+    pub fn new(ports: Vec<(String, u32)>, timeout: Duration) -> Result<Arc<Self>> {
+        let mut bridged = Vec::new();
+        for (device, baud) in ports {
+            let name = device.rsplit('/').next().unwrap_or(&device).to_owned();
+            let port = Arc::new(McuPort {
+                name: name.clone(),
+                device,
+                baud,
+                file: Mutex::new(None),
+                reader: Mutex::new(None),
+                turn: tokio::sync::Mutex::new(()),
+                waiting: Mutex::new(None),
+                flushed: Mutex::new(VecDeque::new()),
+                last_seen: Mutex::new(None),
+                stats: PortStats::default(),
+            });
+            let reader = port.clone();
+            let thread = std::thread::Builder::new()
+                .name(format!("mcu-{name}"))
+                .spawn(move || reader.run())?;
+            *port.reader.lock().unwrap_or_else(|e| e.into_inner()) = Some(thread.thread().clone());
+            bridged.push(port);
+        }
+        Ok(Arc::new(Self { ports: bridged, timeout }))
+    }
+
+    /// Bridge the ttys of SVM_MCU_PORTS (None when unset).
+    pub fn from_config(config: &Config) -> Result<Option<Arc<Self>>> {
+        let mut ports = Vec::new();
+        for spec in &config.mcu_ports {
+            let (path, baud) = match spec.rsplit_once('@') {
+                Some((p, b)) => (p, b.parse().map_err(|_| anyhow!("SVM_MCU_PORTS: bad baud rate in '{spec}'"))?),
+                None => (spec.as_str(), DEFAULT_BAUD),
+            };
+            match path.strip_suffix('*') {
+                Some(prefix) => {
+                    let dir = std::path::Path::new(prefix).parent().unwrap_or(std::path::Path::new("/dev"));
+                    let mut found: Vec<String> = std::fs::read_dir(dir)?
+                        .filter_map(|e| e.ok().map(|e| e.path().to_string_lossy().into_owned()))
+                        .filter(|p| p.starts_with(prefix))
+                        .collect();
+                    if found.is_empty() {
+                        warn!("[MCU] no device matches {path}");
+                    }
+                    found.sort();
+                    ports.extend(found.into_iter().map(|p| (p, baud)));
+                }
+                None => ports.push((path.to_owned(), baud)),
+            }
+        }
+        if ports.is_empty() {
+            return Ok(None);
+        }
+        info!("[MCU] bridging {}", ports.iter().map(|(p, _)| p.as_str()).collect::<Vec<_>>().join(", "));
+        Self::new(ports, Duration::from_millis(config.mcu_timeout_ms)).map(Some)
+    }
+
+    /// Port of `mcu://<name>[/<rest>]` and the rest of the URL.
+    fn port<'u>(&self, url: &'u str) -> Result<(&Arc<McuPort>, &'u str)> {
+        let rest = url.strip_prefix("mcu://")
+            .ok_or_else(|| ExecError::validation(format!("not an MCU URL: {url}")))?;
+        let (name, rest) = rest.split_once('/').unwrap_or((rest, ""));
+        let port = self.ports.iter().find(|p| p.name == name)
+            .ok_or_else(|| ExecError::new(ErrorCode::NotFound, format!("{url}: no MCU port '{name}' in SVM_MCU_PORTS")))?;
+        Ok((port, rest))
+    }
+
+    /// Bridged ttys (watched by hotplug.rs).
+    pub fn devices(&self) -> Vec<String> {
+        self.ports.iter().map(|p| p.device.clone()).collect()
+    }
+
+    /// tty of the port `url` addresses.
+    pub fn device(&self, url: &str) -> Option<&str> {
+        self.port(url).ok().map(|(p, _)| p.device.as_str())
+    }
+
+    /// `device` was plugged (back): its port reopens it now instead of at
+    /// the next retry.
+    pub fn rebind(&self, device: &str) {
+        for port in self.ports.iter().filter(|p| p.device == device && !p.connected()) {
+            if let Some(reader) = &*port.reader.lock().unwrap_or_else(|e| e.into_inner()) {
+                reader.unpark();
+            }
+        }
+    }
+
+    /// Run a CALL_SERVICE / CALL_ACTION on the MCU at `url`.
+    pub async fn call(&self, url: &str, call: Call, operands: &Value, input: Option<&Value>) -> Result<Value> {
+        let (port, _) = self.port(url)?;
+        let program = translate(call, operands, input)?;
+        let conversion = operands.pointer("/mcu/convert").map(Conversion::from_json).transpose()?;
+        let reply = port.request(&program, self.timeout).await?;
+        match reply.status {
+            Status::Ok => {
+                let value = (reply.output.len() >= 2).then(|| u16::from_be_bytes([reply.output[0], reply.output[1]]));
+                let mut out = json!({ "port": port.name, "value": value, "output": hex::encode(&reply.output) });
+                if let (Some(conversion), Some(raw)) = (&conversion, value) {
+                    out["converted"] = conversion.apply(&json!(raw))?;
+                    if let Some(unit) = operands.pointer("/mcu/convert/to") {
+                        out["unit"] = unit.clone();
+                    }
+                }
+                Ok(out)
+            }
+            Status::ValidationError => Err(ExecError::validation(format!(
+                "MCU {} rejected the artifact (code 0x{:02x})", port.name, reply.code,
+            ))),
+            Status::RuntimeError => Err(ExecError::validation(format!(
+                "MCU {} runtime fault (code 0x{:02x})", port.name, reply.code,
+            ))),
+            Status::OfflineQueued => Err(ExecError::new(ErrorCode::Upstream, format!(
+                "MCU {}: {} service/action call(s) failed and were queued offline", port.name, reply.code,
+            )).into()),
+        }
+    }
+
+    /// LOAD_RESOURCE `mcu://<name>/flush`: the entries flushed since the last read.
+    pub fn load(&self, url: &str) -> Result<Value> {
+        let (port, rest) = self.port(url)?;
+        if rest != "flush" {
+            return Err(ExecError::validation(format!("{url}: LOAD_RESOURCE supports mcu://<port>/flush")));
+        }
+        Ok(Value::Array(port.flushed.lock().unwrap_or_else(|e| e.into_inner()).drain(..).collect()))
+    }
+}
+
+impl MetricsCollector for McuBridge {
+    fn prometheus(&self, node_id: &str) -> String {
+        let mut out = String::new();
+        let mut family = |name: &str, kind: &str, help: &str, samples: &mut dyn Iterator<Item = (String, f64)>| {
+            out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+            for (labels, value) in samples {
+                out.push_str(&format!("{name}{{node_id=\"{node_id}\",{labels}}} {value}\n"));
+            }
+        };
+        let port = |p: &McuPort| format!("port=\"{}\"", p.name);
+        let counter = |c: &AtomicU64| c.load(Ordering::Relaxed) as f64;
+        family("eyeflow_mcu_up", "gauge", "Whether the MCU tty is open",
+            &mut self.ports.iter().map(|p| (port(p), f64::from(u8::from(p.connected())))));
+        family("eyeflow_mcu_frames_total", "counter", "Edge-link frames exchanged with the MCU",
+            &mut self.ports.iter().flat_map(|p| [
+                (format!("{},direction=\"tx\"", port(p)), counter(&p.stats.frames_tx)),
+                (format!("{},direction=\"rx\"", port(p)), counter(&p.stats.frames_rx)),
+            ]));
+        family("eyeflow_mcu_frame_errors_total", "counter", "Frames dropped (CRC, overflow, malformed)",
+            &mut self.ports.iter().map(|p| (port(p), counter(&p.stats.frame_errors))));
+        family("eyeflow_mcu_results_total", "counter", "MCU RESULTs by status",
+            &mut self.ports.iter().flat_map(|p| [
+                (format!("{},status=\"ok\"", port(p)), counter(&p.stats.ok)),
+                (format!("{},status=\"validation_error\"", port(p)), counter(&p.stats.rejected)),
+                (format!("{},status=\"runtime_error\"", port(p)), counter(&p.stats.faulted)),
+                (format!("{},status=\"offline_queued\"", port(p)), counter(&p.stats.queued)),
+            ]));
+        family("eyeflow_mcu_timeouts_total", "counter", "Artifacts the MCU did not answer in time",
+            &mut self.ports.iter().map(|p| (port(p), counter(&p.stats.timeouts))));
+        family("eyeflow_mcu_flushed_entries_total", "counter", "Offline entries flushed by the MCU",
+            &mut self.ports.iter().map(|p| (port(p), counter(&p.stats.flushed))));
+        family("eyeflow_mcu_offline_pending", "gauge", "Entries in the MCU offline buffer (last STATUS)",
+            &mut self.ports.iter().map(|p| (port(p), counter(&p.stats.offline_pending))));
+        family("eyeflow_mcu_offline_dropped", "gauge", "Entries the MCU dropped on overflow (last STATUS)",
+            &mut self.ports.iter().map(|p| (port(p), counter(&p.stats.offline_dropped))));
+        family("eyeflow_mcu_last_seen_seconds", "gauge", "Seconds since the last valid MCU frame (-1 = never)",
+            &mut self.ports.iter().map(|p| {
+                let seen = p.last_seen.lock().unwrap_or_else(|e| e.into_inner()).map_or(-1.0, |t| t.elapsed().as_secs_f64());
+                (port(p), seen)
+            }));
+        out
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyeflow_svm_core::ir::Condition;
+
+    #[test]
+    fn test_framed_artifact_parses_as_on_the_mcu() {
+        let instrs = [
+            Instruction::CallService { service: 0x02, input: 0, output: 1 },
+            Instruction::Branch { condition: Condition::Error as u8, target: 3 },
+            Instruction::CallAction { action: 0x02, value: 1, args: [0; 5] },
+            Instruction::Return { output: 1 },
+        ];
+        let wire = frame(&artifact(&instrs).unwrap()).unwrap();
+
+        let mut decoder: FrameDecoder<4096> = FrameDecoder::new();
+        let mut payload = Vec::new();
+        for &b in &wire {
+            if let Some(frame) = decoder.push(b) {
+                payload = frame.unwrap().to_vec();
+            }
+        }
+        let program = Program::parse(&payload).unwrap();
+        assert_eq!(program.len(), instrs.len());
+        assert_eq!(program.instruction(2).unwrap(), instrs[2]);
+
+        let too_many = vec![Instruction::Return { output: 0 }; 257];
+        assert!(artifact(&too_many).unwrap_err().to_string().contains("too many instructions"));
+
+        let action = translate(Call::Action, &json!({ "mcu": { "action": 0, "args": [17] } }), Some(&json!(true))).unwrap();
+        let program = Program::parse(&action).unwrap();
+        assert_eq!(program.instruction(0).unwrap(), Instruction::LoadImm { output: 0, value: 1 });
+        assert_eq!(program.instruction(1).unwrap(), Instruction::CallAction { action: 0, value: 0, args: [17, 0, 0, 0, 0] });
+        // Byte for byte what eyeflow-svm-core's execution test runs
+        let service = translate(Call::Service, &json!({ "mcu": { "service": 2 } }), Some(&json!({ "value": 7 }))).unwrap();
+        assert_eq!(
+            hex::encode(service),
+            "eff1010100030000050000070000000001020001000000000401000000000000",
+        );
+        assert!(translate(Call::Service, &json!({ "mcu": { "service": 2 } }), Some(&json!(70_000))).is_err());
+
+        assert!(translate(Call::Service, &json!({ "mcu": { "artifact": "AAAA" } }), None).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_bridge_exchanges_frames_with_an_mcu() {
+        use std::ffi::CStr;
+        use std::os::fd::FromRawFd;
+        use eyeflow_svm_core::link::FlushEntry;
+
+        // A pseudo-terminal stands in for the MCU's USART
+        // SAFETY: standard pty setup; the name is copied before use.
+        let (master, slave) = unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+            assert!(fd >= 0 && libc::grantpt(fd) == 0 && libc::unlockpt(fd) == 0);
+            let mut name = [0 as libc::c_char; 64];
+            assert_eq!(libc::ptsname_r(fd, name.as_mut_ptr(), name.len()), 0);
+            (File::from_raw_fd(fd), CStr::from_ptr(name.as_ptr()).to_string_lossy().into_owned())
+        };
+        // Fake firmware: answer a CALL_SERVICE with 1500, then flush one entry
+        let mcu = std::thread::spawn(move || {
+            let mut master = master;
+            let mut decoder: FrameDecoder<4096> = FrameDecoder::new();
+            let mut buf = [0u8; 256];
+            let program = 'frame: loop {
+                let n = master.read(&mut buf).unwrap();
+                for &b in &buf[..n] {
+                    if let Some(frame) = decoder.push(b) {
+                        break 'frame frame.unwrap().to_vec();
+                    }
+                }
+            };
+            let send = |master: &mut File, msg: Message| {
+                let mut payload = [0u8; 64];
+                let n = msg.encode_into(&mut payload).unwrap();
+                master.write_all(&frame(&payload[..n]).unwrap()).unwrap();
+            };
+            send(&mut master, Message::Result { status: Status::Ok, code: 0, output: &1500u16.to_be_bytes() });
+            send(&mut master, Message::Flush(&FlushEntry { kind: 0x01, flags: 0, value: 42 }.to_bytes()));
+            send(&mut master, Message::Status { pending: 0, dropped: 3 });
+            // Keep the master open: closing it hangs up the slave side
+            (master, program)
+        });
+
+        let bridge = McuBridge::new(vec![(slave.clone(), 115_200)], Duration::from_secs(2)).unwrap();
+        let name = slave.rsplit('/').next().unwrap().to_owned();
+        while !bridge.ports[0].connected() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let url = format!("mcu://{name}");
+        let operands = json!({ "mcu": { "service": 2, "convert": { "calibration": { "linear": { "gain": 0.1 } }, "from": "degC", "to": "degF" } } });
+        let out = bridge.call(&url, Call::Service, &operands, Some(&json!({ "value": 7 }))).await.unwrap();
+        assert_eq!((out["value"].clone(), out["converted"].clone(), out["unit"].clone()), (json!(1500), json!(302.0), json!("degF")));
+        let (_master, program) = mcu.join().unwrap();
+        assert_eq!(Program::parse(&program).unwrap().instruction(0).unwrap(), Instruction::LoadImm { output: 0, value: 7 });
+
+        // FLUSH / STATUS follow the RESULT
+        let mut flushed = Value::Array(Vec::new());
+        for _ in 0..200 {
+            flushed = bridge.load(&format!("{url}/flush")).unwrap();
+            if flushed.as_array().is_some_and(|a| !a.is_empty()) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!((flushed[0]["kind"].clone(), flushed[0]["value"].clone()), (json!("report"), json!(42)));
+        let metrics = bridge.prometheus("n1");
+        assert!(metrics.contains(&format!("eyeflow_mcu_up{{node_id=\"n1\",port=\"{name}\"}} 1")));
+        assert!(metrics.contains(&format!("eyeflow_mcu_results_total{{node_id=\"n1\",port=\"{name}\",status=\"ok\"}} 1")));
+        assert!(bridge.call("mcu://nope", Call::Service, &json!({ "mcu": { "service": 2 } }), None).await.is_err());
+    }
+}
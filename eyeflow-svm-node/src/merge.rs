@@ -0,0 +1,146 @@
+//! PARALLEL_MERGE policies — how the branches of a fan-out are combined
+//!
+//! Chosen by the MERGE instruction's operands:
+//!
+//!   { "mergePolicy": "ALL" }             every branch must succeed; the merge
+//!                                        register gets the array of outputs
+//!   { "mergePolicy": "FIRST_SUCCESS" }   output of the first branch (IR order)
+//!                                        that succeeded
+//!   { "mergePolicy": "MAJORITY" }        value returned by more than half of
+//!                                        the branches (redundant LLM calls)
+//!   { "mergePolicy": "CONCAT" }          successful outputs concatenated
+//!                                        (arrays are flattened one level)
+//!   { "mergePolicy": "BEST_EFFORT" }     default — failed branches become
+//!                                        null, the merge gets every output
+//!
+//! Each branch still writes its own dest register (null when it failed).
+//! The policy and per-branch outcomes are appended to the audit chain as a
+//! PARALLEL_MERGE event.  A policy that cannot be met fails the slice with
+//! the code of the first failing branch (UPSTREAM when no branch failed,
+//! e.g. no majority).
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::errors::{ErrorCode, ExecError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MergePolicy {
+    All,
+    FirstSuccess,
+    Majority,
+    Concat,
+    #[default]
+    BestEffort,
+}
+
+impl MergePolicy {
+    /// Policy from the MERGE instruction's operands (unknown = BEST_EFFORT).
+    pub fn from_operands(operands: &Value) -> Self {
+        let name = operands.get("mergePolicy").and_then(|v| v.as_str()).unwrap_or("");
+        match name.to_uppercase().replace('-', "_").as_str() {
+            "ALL" | "ALL_MUST_SUCCEED" => Self::All,
+            "FIRST_SUCCESS" | "FIRST_SUCCESS_WINS" => Self::FirstSuccess,
+            "MAJORITY" | "MAJORITY_VOTE" => Self::Majority,
+            "CONCAT" => Self::Concat,
+            _ => Self::BestEffort,
+        }
+    }
+}
+
+/// Outcome of one fan-out branch.
+#[derive(Debug, Clone)]
+pub struct Branch {
+    pub instruction: i32,
+    pub dest: i32,
+    pub result: Result<Value, ExecError>,
+}
+
+/// Combine `branches` under `policy`.
+///
+/// Returns the merge value (or the error failing the slice) together with
+/// the audit details.
+pub fn merge(policy: MergePolicy, branches: &[Branch]) -> (Result<Value, ExecError>, Value) {
+    let details = json!({
+        "policy": policy,
+        "branches": branches.iter().map(|b| match &b.result {
+            Ok(_) => json!({ "instruction": b.instruction, "dest": b.dest, "status": "OK" }),
+            Err(e) => json!({
+                "instruction": b.instruction, "dest": b.dest, "status": "FAILED",
+                "errorCode": e.code, "error": e.message,
+            }),
+        }).collect::<Vec<_>>(),
+    });
+    let ok: Vec<&Value> = branches.iter().filter_map(|b| b.result.as_ref().ok()).collect();
+    let first_err = || branches.iter().find_map(|b| b.result.as_ref().err()).cloned();
+    let unmet = |why: String| {
+        let mut e = first_err().unwrap_or_else(|| ExecError::new(ErrorCode::Upstream, ""));
+        e.message = if e.message.is_empty() { why } else { format!("{why}: {}", e.message) };
+        e
+    };
+
+    let result = match policy {
+        MergePolicy::BestEffort => Ok(Value::Array(
+            branches.iter().map(|b| b.result.clone().unwrap_or(Value::Null)).collect(),
+        )),
+        MergePolicy::All => match first_err() {
+            None => Ok(Value::Array(ok.into_iter().cloned().collect())),
+            Some(_) => Err(unmet(format!(
+                "PARALLEL_MERGE ALL: {} of {} branch(es) failed",
+                branches.len() - ok.len(), branches.len()
+            ))),
+        },
+        MergePolicy::FirstSuccess => ok.first().map(|v| (*v).clone())
+            .ok_or_else(|| unmet("PARALLEL_MERGE FIRST_SUCCESS: every branch failed".into())),
+        MergePolicy::Majority => {
+            let mut votes: Vec<(&Value, usize)> = Vec::new();
+            for v in &ok {
+                match votes.iter_mut().find(|(w, _)| w == v) {
+                    Some((_, n)) => *n += 1,
+                    None => votes.push((v, 1)),
+                }
+            }
+            match votes.iter().max_by_key(|(_, n)| *n) {
+                Some((v, n)) if *n * 2 > branches.len() => Ok((*v).clone()),
+                best => Err(unmet(format!(
+                    "PARALLEL_MERGE MAJORITY: no value reached a majority ({} of {} vote(s) at best)",
+                    best.map_or(0, |(_, n)| *n), branches.len()
+                ))),
+            }
+        }
+        MergePolicy::Concat => Ok(Value::Array(ok.into_iter().flat_map(|v| match v {
+            Value::Array(items) => items.clone(),
+            other => vec![other.clone()],
+        }).collect())),
+    };
+    (result, details)
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_policies() {
+        let b = |i, r: Result<Value, ExecError>| Branch { instruction: i, dest: i * 10, result: r };
+        let timeout = || Err(ExecError::new(ErrorCode::Timeout, "slow"));
+        let branches = [b(1, Ok(json!("yes"))), b(2, timeout()), b(3, Ok(json!("yes"))), b(4, Ok(json!(["no"])))];
+
+        assert_eq!(MergePolicy::from_operands(&json!({ "mergePolicy": "majority-vote" })), MergePolicy::Majority);
+        assert_eq!(MergePolicy::from_operands(&Value::Null), MergePolicy::BestEffort);
+
+        let (r, details) = merge(MergePolicy::All, &branches);
+        assert_eq!(r.unwrap_err().code, ErrorCode::Timeout);
+        assert_eq!(details["branches"][1]["status"], "FAILED");
+        assert_eq!(merge(MergePolicy::FirstSuccess, &branches[1..]).0.unwrap(), json!("yes"));
+        assert_eq!(merge(MergePolicy::Concat, &branches).0.unwrap(), json!(["yes", "yes", "no"]));
+        assert_eq!(merge(MergePolicy::BestEffort, &branches[..2]).0.unwrap(), json!(["yes", null]));
+
+        // 2 of 4 is not a majority; 2 of 3 is
+        assert_eq!(merge(MergePolicy::Majority, &branches).0.unwrap_err().code, ErrorCode::Timeout);
+        assert_eq!(merge(MergePolicy::Majority, &branches[..3]).0.unwrap(), json!("yes"));
+    }
+}
@@ -0,0 +1,276 @@
+//! Metrics push — for nodes Prometheus cannot scrape (NAT, cellular links)
+//!
+//! The /metrics pull endpoint stays as it is; in addition, every
+//! SVM_METRICS_PUSH_INTERVAL_SECS (default 30) the same snapshot is pushed to
+//! SVM_METRICS_PUSH_URL using SVM_METRICS_PUSH_MODE:
+//!
+//!   pushgateway    PUT <url>/metrics/job/eyeflow-svm-node/instance/<node_id>
+//!                  with the Prometheus text (replaces the node's group)
+//!   remote_write   POST <url> a snappy-compressed protobuf WriteRequest
+//!                  (Prometheus remote-write 1.0), labels job + instance added
+//!
+//! Credentials come from Vault: SVM_METRICS_PUSH_VAULT_PATH names a secret
+//! holding either "user:password" (Basic auth) or a bearer token.  Push
+//! failures are logged and retried on the next tick; they never affect
+//! execution.
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use prost::Message;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::config::Config;
+use crate::health::HealthState;
+use crate::vault::VaultClient;
+
+const JOB: &str = "eyeflow-svm-node";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PushMode {
+    Pushgateway,
+    RemoteWrite,
+}
+
+impl PushMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "pushgateway" => Some(Self::Pushgateway),
+            "remote_write" => Some(Self::RemoteWrite),
+            _ => None,
+        }
+    }
+}
+
+// ── Remote-write wire format (prometheus/prompb) ──────────────────────────────
+
+#[derive(Clone, PartialEq, Message)]
+struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    timeseries: Vec<TimeSeries>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct TimeSeries {
+    #[prost(message, repeated, tag = "1")]
+    labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    samples: Vec<Sample>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Label {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(string, tag = "2")]
+    value: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Sample {
+    #[prost(double, tag = "1")]
+    value: f64,
+    #[prost(int64, tag = "2")]
+    timestamp: i64,
+}
+
+/// Parse Prometheus text exposition into remote-write series, adding
+/// `extra` labels and stamping every sample with `timestamp_ms`.
+fn to_write_request(text: &str, extra: &[(&str, &str)], timestamp_ms: i64) -> Result<WriteRequest> {
+    let mut timeseries = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (series, value) = line.rsplit_once(' ').ok_or_else(|| anyhow!("malformed sample '{line}'"))?;
+        let value: f64 = value.parse().map_err(|_| anyhow!("malformed value in '{line}'"))?;
+        let (name, mut labels) = match series.split_once('{') {
+            Some((name, rest)) => (name, parse_labels(rest.strip_suffix('}').unwrap_or(rest))?),
+            None => (series, Vec::new()),
+        };
+        labels.push(Label { name: "__name__".into(), value: name.to_owned() });
+        for (k, v) in extra {
+            if !labels.iter().any(|l| l.name == *k) {
+                labels.push(Label { name: (*k).to_owned(), value: (*v).to_owned() });
+            }
+        }
+        labels.sort_by(|a, b| a.name.cmp(&b.name));
+        timeseries.push(TimeSeries { labels, samples: vec![Sample { value, timestamp: timestamp_ms }] });
+    }
+    Ok(WriteRequest { timeseries })
+}
+
+/// `a="x",b="y\"z"` → labels, unescaping \\ \" \n.
+fn parse_labels(s: &str) -> Result<Vec<Label>> {
+    let mut labels = Vec::new();
+    let mut chars = s.chars().peekable();
+    loop {
+        let name: String = chars.by_ref().take_while(|c| *c != '=').collect::<String>().trim_matches([',', ' ']).to_owned();
+        if name.is_empty() {
+            return Ok(labels);
+        }
+        if chars.next() != Some('"') {
+            return Err(anyhow!("label {name} has no quoted value"));
+        }
+        let mut value = String::new();
+        loop {
+            match chars.next() {
+                Some('\\') => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some(c) => value.push(c),
+                    None => return Err(anyhow!("unterminated label {name}")),
+                },
+                Some('"') => break,
+                Some(c) => value.push(c),
+                None => return Err(anyhow!("unterminated label {name}")),
+            }
+        }
+        labels.push(Label { name, value });
+    }
+}
+
+/// Snappy block format using literals only — valid for any decoder, and
+/// metric snapshots are small enough that compression does not matter.
+fn snappy_literal(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 60_000 * 3 + 8);
+    let mut n = data.len() as u64;
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+    for chunk in data.chunks(65_536) {
+        let len = chunk.len() - 1;
+        if len < 60 {
+            out.push((len as u8) << 2);
+        } else if len < 256 {
+            out.extend([60 << 2, len as u8]);
+        } else {
+            out.extend([61 << 2, len as u8, (len >> 8) as u8]);
+        }
+        out.extend_from_slice(chunk);
+    }
+    out
+}
+
+// ── Pusher ────────────────────────────────────────────────────────────────────
+
+pub struct MetricsPusher {
+    url: String,
+    mode: PushMode,
+    interval: Duration,
+    vault_path: Option<String>,
+    vault: VaultClient,
+    http: reqwest::Client,
+    health: Arc<HealthState>,
+}
+
+impl MetricsPusher {
+    /// None when SVM_METRICS_PUSH_URL is not set.
+    pub fn from_config(config: &Config, health: Arc<HealthState>) -> Result<Option<Self>> {
+        let Some(url) = config.metrics_push_url.clone() else { return Ok(None) };
+        let mode = PushMode::parse(&config.metrics_push_mode)
+            .ok_or_else(|| anyhow!("SVM_METRICS_PUSH_MODE must be pushgateway or remote_write, got '{}'", config.metrics_push_mode))?;
+        let http = reqwest::Client::builder().timeout(Duration::from_secs(10)).build()?;
+        let mut vault = VaultClient::new(
+            http.clone(),
+            config.vault_addr.clone(),
+            config.vault_token.clone(),
+            config.vault_namespace.clone(),
+        );
+        vault.set_kv_layout(config.vault_kv_version, &config.vault_kv_mounts);
+        Ok(Some(Self {
+            url: url.trim_end_matches('/').to_owned(),
+            mode,
+            interval: Duration::from_secs(config.metrics_push_interval_secs.max(1)),
+            vault_path: config.metrics_push_vault_path.clone(),
+            vault,
+            http,
+            health,
+        }))
+    }
+
+    /// Push every interval, forever.
+    pub async fn run(mut self) {
+        info!("[MetricsPush] pushing to {} every {:?} ({:?})", self.url, self.interval, self.mode);
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            ticker.tick().await;
+            match self.push_once().await {
+                Ok(()) => debug!("[MetricsPush] pushed"),
+                Err(e) => warn!("[MetricsPush] push failed: {e}"),
+            }
+        }
+    }
+
+    async fn push_once(&mut self) -> Result<()> {
+        let text = self.health.to_prometheus();
+        let node_id = self.health.node_id.clone();
+        let request = match self.mode {
+            PushMode::Pushgateway => self.http
+                .put(format!("{}/metrics/job/{JOB}/instance/{node_id}", self.url))
+                .header("Content-Type", "text/plain; version=0.0.4")
+                .body(text),
+            PushMode::RemoteWrite => {
+                let now = chrono::Utc::now().timestamp_millis();
+                let write = to_write_request(&text, &[("job", JOB), ("instance", &node_id)], now)?;
+                self.http.post(&self.url)
+                    .header("Content-Type", "application/x-protobuf")
+                    .header("Content-Encoding", "snappy")
+                    .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+                    .body(snappy_literal(&write.encode_to_vec()))
+            }
+        };
+        let request = match self.authorization().await? {
+            Some(auth) => request.header("Authorization", auth),
+            None => request,
+        };
+        let resp = request.send().await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("HTTP {}", resp.status()));
+        }
+        Ok(())
+    }
+
+    /// Authorization header from the Vault secret, if one is configured.
+    async fn authorization(&mut self) -> Result<Option<String>> {
+        let Some(path) = &self.vault_path else { return Ok(None) };
+        let secret = self.vault.fetch_secret(path).await?;
+        Ok(Some(match secret.value.split_once(':') {
+            Some(_) => format!("Basic {}", B64.encode(&secret.value)),
+            None => format!("Bearer {}", secret.value),
+        }))
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_write_encoding() {
+        let text = "# HELP up Up\n# TYPE up gauge\n\
+                    eyeflow_ws_connected{node_id=\"n1\"} 1\n\
+                    eyeflow_peer_delegations_total{node_id=\"n1\",status=\"a \\\"b\\\"\"} 3\n\
+                    plain 2.5\n";
+        let req = to_write_request(text, &[("job", JOB), ("instance", "n1")], 1_000).unwrap();
+        assert_eq!(req.timeseries.len(), 3);
+        let names = |ts: &TimeSeries| ts.labels.iter().map(|l| format!("{}={}", l.name, l.value)).collect::<Vec<_>>();
+        assert_eq!(
+            names(&req.timeseries[1]),
+            ["__name__=eyeflow_peer_delegations_total", "instance=n1", "job=eyeflow-svm-node", "node_id=n1", "status=a \"b\""],
+        );
+        assert_eq!(req.timeseries[2].samples, [Sample { value: 2.5, timestamp: 1_000 }]);
+        assert!(to_write_request("broken", &[], 0).is_err());
+
+        // Literal-only snappy: varint length, then tagged literal runs
+        assert_eq!(snappy_literal(b"abc"), [3, 2 << 2, b'a', b'b', b'c']);
+        let big = vec![7u8; 300];
+        let framed = snappy_literal(&big);
+        assert_eq!(&framed[..5], [0xac, 0x02, 61 << 2, 0x2b, 0x01]);
+        assert_eq!(framed.len(), 5 + 300);
+    }
+}
@@ -0,0 +1,376 @@
+//! Instruction middleware — policy, metrics and payload hooks around opcodes
+//!
+//! Every instruction of every slice (sub-slices and delegated work included)
+//! passes through the node's middleware pipeline:
+//!
+//!   pre_instruction    in order, before the opcode runs; an error fails the
+//!                      instruction (a TRY region can catch it)
+//!   post_instruction   in reverse order, with the value written to the
+//!                      instruction's dest register (rewritable) or its error
+//!
+//! Deployments implement `Middleware` and `push` it onto the pipeline the
+//! Svm is built with.  Built-ins come from SVM_MIDDLEWARE, comma-separated,
+//! run in the order given:
+//!
+//!   opcode_metrics              eyeflow_instructions_total{opcode,result},
+//!                               eyeflow_instruction_errors_total{opcode,code},
+//!                               eyeflow_instruction_duration_ms_sum{opcode}
+//!   trace_instructions          log every instruction with its workflow,
+//!                               tenant, service, empty source registers and
+//!                               how it ended
+//!   deny_opcodes=A|B            refuse these opcodes (UNSUPPORTED)
+//!   redact_fields=password|pin  replace these object keys in instruction
+//!                               outputs with "[REDACTED]", at any depth
+//!
+//! e.g. SVM_MIDDLEWARE="opcode_metrics,deny_opcodes=LLM_CALL,redact_fields=ssn".
+//! Hooks are synchronous and run on the slice's task — keep them cheap.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+use crate::health::MetricsCollector;
+use crate::proto::llmir::{IrInstruction, IrOpcode};
+use crate::svm::Registers;
+
+// ── Hooks ─────────────────────────────────────────────────────────────────────
+
+/// What a hook sees of the running instruction (not every hook reads all of it).
+pub struct InstrContext<'a> {
+    pub workflow_id: &'a str,
+    pub tenant: &'a str,
+    pub instr: &'a IrInstruction,
+    pub opcode: IrOpcode,
+    /// Registers before (pre) / after (post) the instruction
+    pub registers: &'a Registers,
+}
+
+/// How an instruction ended.
+pub enum Outcome<'a> {
+    /// `output` is the instruction's dest register, if it holds a value
+    Done { output: Option<&'a mut Value>, elapsed_ms: u64 },
+    Failed {
+        error: &'a ExecError,
+        elapsed_ms: u64,
+    },
+}
+
+pub trait Middleware: Send + Sync {
+    /// Name used in errors and logs.
+    fn name(&self) -> &str;
+
+    /// Before the instruction runs; an error fails it.
+    fn pre_instruction(&self, _ctx: &InstrContext<'_>) -> Result<()> {
+        Ok(())
+    }
+
+    /// After it ran (or failed).
+    fn post_instruction(&self, _ctx: &InstrContext<'_>, _outcome: &mut Outcome<'_>) {}
+
+    /// Prometheus series of this middleware, if any.
+    fn prometheus(&self, _node_id: &str) -> String {
+        String::new()
+    }
+}
+
+// ── Pipeline ──────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct Middlewares {
+    chain: Vec<Arc<dyn Middleware>>,
+}
+
+impl std::fmt::Debug for Middlewares {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.chain.iter().map(|m| m.name())).finish()
+    }
+}
+
+impl Middlewares {
+    /// Built-ins listed in SVM_MIDDLEWARE (empty pipeline when unset).
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let mut pipeline = Self::default();
+        for spec in &config.middleware {
+            let (name, arg) = spec.split_once('=').unwrap_or((spec, ""));
+            let list = || arg.split('|').map(str::trim).filter(|s| !s.is_empty()).map(str::to_owned).collect();
+            let middleware: Arc<dyn Middleware> = match name.trim() {
+                "opcode_metrics" => Arc::new(OpcodeMetrics::default()),
+                "trace_instructions" => Arc::new(TraceInstructions),
+                "deny_opcodes" => Arc::new(DenyOpcodes::new(list())?),
+                "redact_fields" => Arc::new(RedactFields { fields: list() }),
+                other => return Err(anyhow!("SVM_MIDDLEWARE: unknown middleware '{other}'")),
+            };
+            pipeline.push(middleware);
+        }
+        Ok(pipeline)
+    }
+
+    /// Append `middleware` (its pre hook runs after those already pushed).
+    pub fn push(&mut self, middleware: Arc<dyn Middleware>) {
+        self.chain.push(middleware);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chain.is_empty()
+    }
+
+    pub fn pre(&self, ctx: &InstrContext<'_>) -> Result<()> {
+        for m in &self.chain {
+            m.pre_instruction(ctx).with_context(|| format!("middleware {}", m.name()))?;
+        }
+        Ok(())
+    }
+
+    pub fn post(&self, ctx: &InstrContext<'_>, outcome: &mut Outcome<'_>) {
+        for m in self.chain.iter().rev() {
+            m.post_instruction(ctx, outcome);
+        }
+    }
+}
+
+impl MetricsCollector for Middlewares {
+    fn prometheus(&self, node_id: &str) -> String {
+        self.chain.iter().map(|m| m.prometheus(node_id)).collect()
+    }
+}
+
+// ── Built-ins ─────────────────────────────────────────────────────────────────
+
+/// Instructions per opcode and result, failures per error code, and their
+/// summed duration.
+#[derive(Default)]
+struct OpcodeMetrics {
+    /// opcode → (ok, failed, duration ms)
+    counts: Mutex<BTreeMap<&'static str, (u64, u64, u64)>>,
+    /// (opcode, error code) → failed
+    errors: Mutex<BTreeMap<(&'static str, &'static str), u64>>,
+}
+
+impl Middleware for OpcodeMetrics {
+    fn name(&self) -> &str {
+        "opcode_metrics"
+    }
+
+    fn post_instruction(&self, ctx: &InstrContext<'_>, outcome: &mut Outcome<'_>) {
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        let c = counts.entry(ctx.opcode.as_str_name()).or_default();
+        match outcome {
+            Outcome::Done { elapsed_ms, .. } => {
+                c.0 += 1;
+                c.2 += *elapsed_ms;
+            }
+            Outcome::Failed { error, elapsed_ms } => {
+                c.1 += 1;
+                c.2 += *elapsed_ms;
+                let mut errors = self.errors.lock().unwrap_or_else(|e| e.into_inner());
+                *errors.entry((ctx.opcode.as_str_name(), error.code.as_str())).or_default() += 1;
+            }
+        }
+    }
+
+    fn prometheus(&self, node_id: &str) -> String {
+        let counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::from(
+            "# HELP eyeflow_instructions_total Instructions executed per opcode by result\n\
+             # TYPE eyeflow_instructions_total counter\n",
+        );
+        for (opcode, (ok, failed, _)) in counts.iter() {
+            out.push_str(&format!(
+                "eyeflow_instructions_total{{node_id=\"{node_id}\",opcode=\"{opcode}\",result=\"ok\"}} {ok}\n\
+                 eyeflow_instructions_total{{node_id=\"{node_id}\",opcode=\"{opcode}\",result=\"failed\"}} {failed}\n",
+            ));
+        }
+        out.push_str(
+            "# HELP eyeflow_instruction_errors_total Failed instructions per opcode by error code\n\
+             # TYPE eyeflow_instruction_errors_total counter\n",
+        );
+        for ((opcode, code), n) in self.errors.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            out.push_str(&format!("eyeflow_instruction_errors_total{{node_id=\"{node_id}\",opcode=\"{opcode}\",code=\"{code}\"}} {n}\n"));
+        }
+        out.push_str(
+            "# HELP eyeflow_instruction_duration_ms_sum Time spent in instructions per opcode\n\
+             # TYPE eyeflow_instruction_duration_ms_sum counter\n",
+        );
+        for (opcode, (_, _, ms)) in counts.iter() {
+            out.push_str(&format!("eyeflow_instruction_duration_ms_sum{{node_id=\"{node_id}\",opcode=\"{opcode}\"}} {ms}\n"));
+        }
+        out
+    }
+}
+
+/// Logs each instruction as it starts and ends.
+struct TraceInstructions;
+
+impl Middleware for TraceInstructions {
+    fn name(&self) -> &str {
+        "trace_instructions"
+    }
+
+    fn pre_instruction(&self, ctx: &InstrContext<'_>) -> Result<()> {
+        let empty: Vec<String> = ctx.instr.src.iter()
+            .filter(|r| !ctx.registers.contains_key(r))
+            .map(|r| format!("r{r}"))
+            .collect();
+        info!(
+            "[Trace] {} (tenant {}) #{} {} {}{}",
+            ctx.workflow_id,
+            if ctx.tenant.is_empty() { "-" } else { ctx.tenant },
+            ctx.instr.index,
+            ctx.opcode.as_str_name(),
+            ctx.instr.service_id,
+            if empty.is_empty() { String::new() } else { format!(" — {} empty", empty.join(", ")) },
+        );
+        Ok(())
+    }
+
+    fn post_instruction(&self, ctx: &InstrContext<'_>, outcome: &mut Outcome<'_>) {
+        match outcome {
+            Outcome::Done { elapsed_ms, .. } => {
+                info!("[Trace] {} #{} done in {elapsed_ms} ms", ctx.workflow_id, ctx.instr.index);
+            }
+            Outcome::Failed { error, elapsed_ms } => {
+                info!("[Trace] {} #{} failed after {elapsed_ms} ms: {} {}", ctx.workflow_id, ctx.instr.index, error.code, error.message);
+            }
+        }
+    }
+}
+
+/// Refuses the listed opcodes.
+struct DenyOpcodes {
+    denied: HashSet<IrOpcode>,
+}
+
+impl DenyOpcodes {
+    fn new(names: Vec<String>) -> Result<Self> {
+        let denied = names.iter()
+            .map(|n| IrOpcode::from_str_name(&n.to_uppercase()).ok_or_else(|| anyhow!("deny_opcodes: unknown opcode '{n}'")))
+            .collect::<Result<_>>()?;
+        Ok(Self { denied })
+    }
+}
+
+impl Middleware for DenyOpcodes {
+    fn name(&self) -> &str {
+        "deny_opcodes"
+    }
+
+    fn pre_instruction(&self, ctx: &InstrContext<'_>) -> Result<()> {
+        if self.denied.contains(&ctx.opcode) {
+            return Err(ExecError::new(
+                ErrorCode::Unsupported,
+                format!("{} is denied on this node", ctx.opcode.as_str_name()),
+            ).fatal().into());
+
+        }
+        Ok(())
+    }
+}
+
+/// Masks the listed object keys in instruction outputs.
+struct RedactFields {
+    fields: Vec<String>,
+}
+
+impl RedactFields {
+    fn redact(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (k, v) in map.iter_mut() {
+                    if self.fields.iter().any(|f| f == k) {
+                        *v = Value::String("[REDACTED]".into());
+                    } else {
+                        self.redact(v);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|v| self.redact(v)),
+            _ => {}
+        }
+    }
+}
+
+impl Middleware for RedactFields {
+    fn name(&self) -> &str {
+        "redact_fields"
+    }
+
+    fn post_instruction(&self, _ctx: &InstrContext<'_>, outcome: &mut Outcome<'_>) {
+        if let Outcome::Done { output: Some(value), .. } = outcome {
+            self.redact(value);
+        }
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditChain;
+    use crate::prepared::PreparedIr;
+    use crate::proto::llmir::LlmIntermediateRepresentation;
+    use crate::svm::Svm;
+    use serde_json::json;
+
+    /// Refuses instructions whose first source register is missing.
+    struct RequireInput;
+
+    impl Middleware for RequireInput {
+        fn name(&self) -> &str {
+            "require_input"
+        }
+
+        fn pre_instruction(&self, ctx: &InstrContext<'_>) -> Result<()> {
+            match ctx.instr.src.first() {
+                Some(r) if !ctx.registers.contains_key(r) => Err(anyhow!("r{r} is empty")),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_builtins_and_custom_hooks_wrap_instructions() {
+        let dir = std::env::temp_dir().join(format!("eyeflow_middleware_{}", uuid::Uuid::new_v4().simple()));
+        let mut config = Config::from_env();
+        config.middleware = vec![
+            "opcode_metrics".into(), "trace_instructions".into(), "redact_fields=pin".into(), "deny_opcodes=llm_call".into(),
+        ];
+        let mut pipeline = Middlewares::from_config(&config).unwrap();
+        pipeline.push(Arc::new(RequireInput));
+        let pipeline = Arc::new(pipeline);
+        let svm = Svm::scratch(config, &dir).await.unwrap().with_middleware(pipeline.clone());
+
+        let slice = |opcode: IrOpcode| {
+            let instr = IrInstruction { index: 1, opcode: opcode as i32, dest: 6, src: vec![5], ..Default::default() };
+            PreparedIr::new(LlmIntermediateRepresentation {
+                instructions: [(1, instr)].into(),
+                instruction_order: vec![1],
+                ..Default::default()
+            }, String::new())
+        };
+        let mut audit = AuditChain::new("n".into(), None).unwrap();
+        let input = Registers::from([(5, json!({ "user": "op-7", "badge": { "pin": "4421" } }))]);
+
+        let (regs, _) = svm.execute_delegated(&slice(IrOpcode::StoreMemory), &mut audit, 1, input.clone()).await.unwrap();
+        assert_eq!(regs[&6], json!({ "user": "op-7", "badge": { "pin": "[REDACTED]" } }));
+
+        let err = svm.execute_delegated(&slice(IrOpcode::LlmCall), &mut audit, 1, input).await.unwrap_err();
+        assert_eq!((err.code, err.instruction), (ErrorCode::Unsupported, Some(1)));
+        assert!(err.message.contains("middleware deny_opcodes"), "{}", err.message);
+
+        let err = svm.execute_delegated(&slice(IrOpcode::StoreMemory), &mut audit, 1, Registers::new()).await.unwrap_err();
+        assert!(err.message.starts_with("middleware require_input: r5 is empty"), "{}", err.message);
+
+        let metrics = pipeline.prometheus("n");
+        assert!(metrics.contains("eyeflow_instructions_total{node_id=\"n\",opcode=\"STORE_MEMORY\",result=\"ok\"} 1"));
+        assert!(metrics.contains("eyeflow_instructions_total{node_id=\"n\",opcode=\"STORE_MEMORY\",result=\"failed\"} 1"));
+        assert!(metrics.contains("opcode=\"LLM_CALL\",result=\"failed\"} 1"));
+        assert!(metrics.contains("eyeflow_instruction_errors_total{node_id=\"n\",opcode=\"LLM_CALL\",code=\"UNSUPPORTED\"} 1"));
+        assert!(Middlewares::from_config(&Config { middleware: vec!["rate_limit".into()], ..Config::from_env() }).is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
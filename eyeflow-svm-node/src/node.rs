@@ -1,40 +1,132 @@
-/// WebSocket Node Client — spec §8.2 + §8.3
-///
-/// This module manages the persistent WebSocket connection between the Rust SVM
-/// node and the NestJS central orchestrator (eyeflow-server).
-///
-/// Protocol (JSON-framed over WebSocket):
-///
-///   Central → Node:
-///     { "type": "IR_DISTRIBUTION",  "payload": <base64 proto> }   — run IR slice
-///     { "type": "PING" }                                            — keepalive
-///     { "type": "CONFIG_UPDATE",    "payload": {...} }              — config push
-///
-///   Node → Central:
-///     { "type": "REGISTER",   "payload": { nodeId, tier, capabilities } }
-///     { "type": "RESULT",     "payload": <SliceExecutionResult JSON> }
-///     { "type": "PONG" }                                            — keepalive reply
-///     { "type": "AUDIT_FLUSH","payload": [AuditEvent, ...] }        — offline flush
-///
-/// On disconnect, audit events and execution results are persisted to the
-/// OfflineBuffer and replayed as an AUDIT_FLUSH on reconnect.
+//! WebSocket Node Client — spec §8.2 + §8.3
+//!
+//! This module manages the persistent WebSocket connection between the Rust SVM
+//! node and the NestJS central orchestrator (eyeflow-server).
+//!
+//! Protocol (JSON-framed over WebSocket):
+//!
+//!   Central → Node:
+//!     { "type": "IR_DISTRIBUTION",  "payload": <IRDistributionMessage JSON> } — run IR slice
+//!                                     (same version, checksum and Ed25519 signature
+//!                                      checks as a binary frame; the signature is
+//!                                      verified with SVM_IR_PUBLIC_KEY, else
+//!                                      SVM_CONFIG_OPS_PUBLIC_KEY.  Unsigned artifacts
+//!                                      and bare base64 LLM-IR payloads run only with
+//!                                      SVM_ALLOW_UNSIGNED_IR=true — development)
+//!     { "type": "PING",             "payload": { serverTime, rttMs } } — keepalive + clock
+//!     { "type": "CONFIG_UPDATE",    "payload": {...} }              — config push
+//!                                     (signedPayload + signature when an ops key is pinned)
+//!                                     (payload.serviceCatalog → ServiceCatalog,
+//!                                      payload.resourceCapacities → ResourceArbiter,
+//!                                      payload.auditPolicy → AuditChain sampling,
+//!                                      payload.healthThresholds → health score)
+//!
+//!     { "type": "CHALLENGE",        "payload": { "nonce": "..." } }   — sent on connect
+//!                                     (flushEncodings: ["dict-v1"] → compact AUDIT_FLUSH,
+//!                                      flush_codec.rs)
+//!     { "type": "LEADER",           "payload": { group, leader, ttlMs } } — standby grant
+//!     { "type": "ENROLLMENT",       "payload": { status, enrollmentId } } — key approval
+//!                             (keystore nodes run quarantined until approved, provision.rs)
+//!     { "type": "RESULT_ACK",       "payload": { "sliceIds": [...] } }  — results processed
+//!     { "type": "RESUME",           "payload": { "resend": [sliceId, ...] } } — answer to
+//!                                     REGISTER `resume` (session.rs)
+//!     { "type": "ASSET_DISTRIBUTION", "payload": { name, kind, sha256, signature,
+//!                                     base64 | url } } — signed asset (assets.rs)
+//!     { "type": "BLOB_REQUEST",     "payload": { "id": "sha256:…" } } — binary register (blob.rs)
+//!     { "type": "RELEASE",          "signedPayload", "signature" } — signed node release,
+//!                                     staged and applied when idle (update.rs)
+//!
+//!   Node → Central:
+//!     { "type": "REGISTER",   "payload": { nodeId, tier, capabilities, labels, auth, enrollment,
+//!                                         flushEncodings, resume } }
+//!                             auth = { alg, nonce, signature, publicKey, fingerprint }
+//!     { "type": "RESULT",     "payload": <SliceExecutionResult JSON> }
+//!     { "type": "PONG",       "payload": { serverTime, nodeTime } } — keepalive reply
+//!     WebSocket Ping frames every SVM_KEEPALIVE_INTERVAL_SECS; a missing Pong
+//!                             drops the connection (keepalive.rs)
+//!     { "type": "AUDIT_FLUSH","header": { count, offlineSince, onlineAt,
+//!                                         oldestEnqueuedAt, newestEnqueuedAt },
+//!                             "payload": [BufferedEvent, ...] }  — offline flush;
+//!                             events keep enqueued_at and carry replayed: true
+//!     { "type": "ACTIONS_IN_DOUBT", "payload": [Intent, ...] }     — after REGISTER,
+//!                             side effects interrupted by a restart (wal.rs)
+//!     { "type": "ASSET_ACK",  "payload": { sha256, name, status, error } } — CACHED / REJECTED
+//!     { "type": "CAPABILITIES_UPDATE", "payload": { nodeId, capabilities, added, removed } }
+//!                             — a device or broker appeared / disappeared (capabilities.rs)
+//!     { "type": "UPDATE_STATUS", "payload": { version, status, target, error } } — self-update
+//!                             progress (STAGED, REJECTED, APPLYING, CONFIRMED, ROLLED_BACK)
+//!     { "type": "ROLE",       "payload": { group, role, artifacts } } — standby pairs,
+//!                             after REGISTER and on every role change
+//!     { "type": "ALERT",      "payload": [{ type, nodeId, ..., raisedAt, replayed }, ...] }
+//!                             — security alerts (and DISK_PRESSURE, disk_watch.rs),
+//!                             batched for SVM_ALERT_BATCH_MS
+//!
+//! IR_DISTRIBUTIONs that arrive while a slice runs wait in a queue ordered
+//! by plan priority, with aging (sched.rs).
+//!
+//! On disconnect, audit events and execution results are persisted to the
+//! OfflineBuffer and replayed as an AUDIT_FLUSH on reconnect.  Security
+//! alerts go through the buffer's alert queue: sent as ALERT frames while
+//! connected, kept (and persisted) while not, and flushed right after
+//! REGISTER, ahead of the AUDIT_FLUSH.
+//!
+//! Frames from central are bounded by SVM_WS_MAX_MESSAGE_BYTES,
+//! SVM_WS_MAX_FRAME_BYTES and SVM_WS_MAX_JSON_DEPTH (ws_limits.rs).
+//!
+//! With SVM_TLS_CERT_PATH / SVM_TLS_KEY_PATH the handshake presents a client
+//! certificate (mTLS); each reconnect picks up the renewed one (tls.rs).
+//! The central host resolves through SVM_DNS_HOSTS first (dns.rs).
+//!
+//! With SVM_UPLINK_BYTES_PER_SEC set, sends are paced by a token bucket in
+//! which RESULT frames overtake the offline flush, itself split into
+//! AUDIT_FLUSH parts sent between incoming frames (shaping.rs).
 
 use anyhow::{anyhow, Result};
 use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
 use futures_util::{SinkExt, StreamExt};
 use prost::Message as ProstMessage;
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex, Notify};
 use tokio::time::{sleep, Duration};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::tungstenite::http::Uri;
+use tokio_tungstenite::{client_async_tls_with_config, Connector};
 use tracing::{debug, error, info, warn};
+use crate::admission::Admission;
 use crate::audit::{AuditChain, AuditEvent};
+use crate::audit_export::AuditLog;
+use crate::audit_policy::AuditPolicy;
+use crate::audit_verify::AuditVerifier;
+use crate::backoff::Backoff;
+use crate::chaos::Chaos;
+use crate::clock::ClockSync;
 use crate::config::Config;
+use crate::config_sig::ConfigVerifier;
+use crate::connectors::kafka::KafkaSink;
+use crate::disk_watch::DiskWatch;
 use crate::health::HealthState;
-use crate::offline::{ensure_parent, OfflineBuffer};
-use crate::proto::llmir::{IrDistributionMessage, SliceExecutionResult};
+use crate::health_score::HealthThresholds;
+use crate::ir_limits::{self, DecodeLimits, LimitViolation};
+use crate::keepalive::{Keepalive, Tick};
+use crate::offline::{FlushHeader, OfflineBuffer};
+use crate::prepared::{IrCache, PreparedIr};
+use crate::proto::llmir::{IrDistributionMessage, SignedIrArtifact, SliceExecutionResult};
+use crate::provision::Enrollment;
+use crate::errors::ErrorCode;
+use crate::flush_codec;
+use crate::results::ResultStore;
+use crate::sched::{self, SliceQueue};
+use crate::session::SessionLedger;
+use crate::shaping::{Priority, Shaper};
+use crate::standby::Standby;
 use crate::svm::Svm;
+use crate::tenant::TenantGovernor;
+use crate::tls::TlsIdentity;
+use crate::update::Updater;
+use crate::ws_limits::FrameLimits;
 
 // ── IR format compatibility (spec §5.3) ───────────────────────────────────────
 
@@ -43,48 +135,295 @@ use crate::svm::Svm;
 /// Different major → refuse execution entirely (returns INCOMPATIBLE error).
 const SVM_IR_FORMAT_VERSION_MAJOR: u32 = 1;
 
+/// Security alerts carried by one ALERT frame.
+const ALERTS_PER_FRAME: usize = 100;
+
+// ── Slice queue ───────────────────────────────────────────────────────────────
+
+/// How a slice's result goes back: the frame kind it arrived in.
+enum Reply {
+    Text,
+    Binary,
+}
+
+/// A prepared IR_DISTRIBUTION waiting for its turn.
+struct QueuedSlice {
+    prepared: Arc<PreparedIr>,
+    reply: Reply,
+}
+
+// ── Paced offline flush ───────────────────────────────────────────────────────
+
+/// An offline flush sent as several AUDIT_FLUSH frames; the events stay in
+/// the OfflineBuffer until their part is delivered.
+struct PendingFlush {
+    header: FlushHeader,
+    /// Events per part
+    chunk: usize,
+    /// dict-v1 encoded parts
+    compact: bool,
+    parts: usize,
+    sent: usize,
+    /// Next frame and how many events it carries
+    next: Option<(String, usize)>,
+}
+
+impl PendingFlush {
+    fn new(header: FlushHeader, chunk: usize, compact: bool, buf: &OfflineBuffer) -> Self {
+        let parts = header.count.div_ceil(chunk);
+        let mut pending = Self { header, chunk, compact, parts, sent: 0, next: None };
+        pending.prepare(buf);
+        pending
+    }
+
+    fn prepare(&mut self, buf: &OfflineBuffer) {
+        let events = buf.flush_part(self.chunk);
+        if events.is_empty() {
+            self.next = None;
+            return;
+        }
+        let mut header = serde_json::to_value(&self.header).unwrap_or_default();
+        header["part"] = json!(self.sent + 1);
+        header["parts"] = json!(self.parts.max(self.sent + 1));
+        let frame = if self.compact {
+            json!({ "type": "AUDIT_FLUSH", "encoding": flush_codec::DICT_V1, "header": header, "payload": flush_codec::encode(&events) })
+        } else {
+            json!({ "type": "AUDIT_FLUSH", "header": header, "payload": events })
+        };
+        self.next = Some((frame.to_string(), events.len()));
+    }
+}
+
 // ── Node client ───────────────────────────────────────────────────────────────
 
 pub struct NodeClient {
     config:  Config,
-    svm:     Svm,
+    svm:     Arc<Svm>,
+    /// Decoded, execution-ready artifacts keyed by payload SHA-256
+    ir_cache: Arc<IrCache>,
     audit:   Arc<Mutex<AuditChain>>,
+    /// Audit chain / log self-test (SVM_AUDIT_VERIFY_SECS)
+    audit_verify: Arc<AuditVerifier>,
     offline: Arc<Mutex<OfflineBuffer>>,
+    /// Signalled when a security alert is queued
+    alerts_ready: Arc<Notify>,
     health:  Arc<HealthState>,
+    tenants: Arc<TenantGovernor>,
+    results: Arc<ResultStore>,
+    /// Sent results central has not acknowledged (REGISTER `resume`)
+    session: Arc<SessionLedger>,
+    /// Slices waiting while another runs, by plan priority
+    queue: SliceQueue<QueuedSlice>,
+    /// Leader/standby pairing (None = always executes)
+    standby: Option<Arc<Standby>>,
+    /// Audit mirror (SVM_KAFKA_AUDIT_TOPIC)
+    kafka: Option<Arc<KafkaSink>>,
+    /// Local copy of shipped audit events for export (SVM_AUDIT_LOG_PATH)
+    audit_log: Option<Arc<AuditLog>>,
+    /// Key enrollment; no IR runs until central approves (SVM_KEYSTORE_PATH)
+    enrollment: Option<Arc<Enrollment>>,
+    /// mTLS client certificate of the central link (SVM_TLS_CERT_PATH)
+    tls: Option<Arc<TlsIdentity>>,
+    /// Uplink pacing (SVM_UPLINK_BYTES_PER_SEC)
+    shaper: Option<Arc<Shaper>>,
+    /// Free-space watchdog of the buffer / audit disks (SVM_DISK_PRESSURE_BYTES)
+    disk_watch: Option<Arc<DiskWatch>>,
+    /// Offline flush parts not sent yet (shaped sessions only)
+    pending_flush: Option<PendingFlush>,
+    /// Central accepted the dict-v1 AUDIT_FLUSH encoding this session
+    compact_flush: bool,
+    /// Injected central connection drops (SVM_CHAOS)
+    chaos: Option<Arc<Chaos>>,
+    /// Flips to true on SIGTERM / SIGINT
+    shutdown: watch::Receiver<bool>,
+    /// CONFIG_UPDATE signature check against the pinned ops key
+    config_verifier: ConfigVerifier,
+    /// Key IR artifacts must be signed with (SVM_IR_PUBLIC_KEY)
+    ir_key: Option<VerifyingKey>,
+    /// Offset estimate to central's clock, fed by PING
+    clock: ClockSync,
+    /// Signed self-update (SVM_UPDATE)
+    updater: Option<Arc<Updater>>,
+    /// When the last slice finished (self-update idle window)
+    last_slice_at: tokio::time::Instant,
 }
 
 impl NodeClient {
     pub fn new(
         config: Config,
-        svm: Svm,
+        svm: Arc<Svm>,
         audit: AuditChain,
         offline: OfflineBuffer,
         health: Arc<HealthState>,
+        tenants: Arc<TenantGovernor>,
+        results: Arc<ResultStore>,
     ) -> Self {
+        let ir_cache = svm.ir_cache().clone();
+        health.register_collector(ir_cache.clone());
+        let queue = SliceQueue::from_config(&config);
+        health.register_collector(queue.stats());
+        let audit_verify = AuditVerifier::from_config(&config);
+        health.register_collector(audit_verify.clone());
         Self {
             config: config.clone(),
             svm,
+            ir_cache,
             audit:   Arc::new(Mutex::new(audit)),
+            audit_verify,
             offline: Arc::new(Mutex::new(offline)),
+            alerts_ready: Arc::new(Notify::new()),
             health,
+            tenants,
+            results,
+            session: SessionLedger::new(config.session_max_unacked, None),
+            queue,
+            standby: None,
+            kafka: None,
+            audit_log: None,
+            enrollment: None,
+            tls: None,
+            shaper: None,
+            disk_watch: None,
+            pending_flush: None,
+            compact_flush: false,
+            chaos: None,
+            shutdown: watch::channel(false).1,
+            config_verifier: ConfigVerifier::new(None).expect("no key to parse"),
+            ir_key: None,
+            clock: ClockSync::default(),
+            updater: None,
+            last_slice_at: tokio::time::Instant::now(),
         }
     }
 
+    /// Run as one node of a warm-standby pair (see standby.rs).
+    pub fn with_standby(mut self, standby: Option<Arc<Standby>>) -> Self {
+        self.standby = standby;
+        self
+    }
+
+    /// Drop the central connection at random (SVM_CHAOS `central.drop`).
+    pub fn with_chaos(mut self, chaos: Option<Arc<Chaos>>) -> Self {
+        self.chaos = chaos;
+        self
+    }
+
+    /// Mirror audit events to Kafka through `sink`.
+    pub fn with_kafka(mut self, sink: Option<Arc<KafkaSink>>) -> Self {
+        self.kafka = sink;
+        self
+    }
+
+    /// Keep a copy of every shipped audit event in `log`.
+    pub fn with_audit_log(mut self, log: Option<Arc<AuditLog>>) -> Self {
+        self.audit_log = log;
+        self
+    }
+
+    /// Refuse IR while `enrollment` is not approved.
+    pub fn with_enrollment(mut self, enrollment: Option<Arc<Enrollment>>) -> Self {
+        self.enrollment = enrollment;
+        self
+    }
+
+    /// Present this client certificate to central.
+    pub fn with_tls(mut self, tls: Option<Arc<TlsIdentity>>) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// Pace sends through `shaper`, RESULTs ahead of the offline flush.
+    pub fn with_shaper(mut self, shaper: Option<Arc<Shaper>>) -> Self {
+        self.shaper = shaper;
+        self
+    }
+
+    /// Watch the buffer / audit disks (see disk_watch.rs).
+    pub fn with_disk_watch(mut self, watch: Option<Arc<DiskWatch>>) -> Self {
+        self.disk_watch = watch;
+        self
+    }
+
+    /// Drain and return from `run` once `shutdown` flips to true.
+    pub fn with_shutdown(mut self, shutdown: watch::Receiver<bool>) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Track unacked results in `session` (persisted ledger, see session.rs).
+    pub fn with_session(mut self, session: Arc<SessionLedger>) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Only apply CONFIG_UPDATEs that pass `verifier` (see config_sig.rs).
+    pub fn with_config_verifier(mut self, verifier: ConfigVerifier) -> Self {
+        self.config_verifier = verifier;
+        self
+    }
+
+    /// Only run IR artifacts signed with `key` (spec §13.1).
+    pub fn with_ir_key(mut self, key: Option<VerifyingKey>) -> Self {
+        self.ir_key = key;
+        self
+    }
+
+    /// Accept RELEASE frames and apply them through `updater` (see update.rs).
+    pub fn with_updater(mut self, updater: Option<Arc<Updater>>) -> Self {
+        self.updater = updater;
+        self
+    }
+
+    fn stopping(&self) -> bool {
+        *self.shutdown.borrow()
+    }
+
     /// Main loop: connect → register → read messages → on disconnect: persist buffers →
-    ///            wait reconnect_interval → retry forever.
+    ///            wait a jittered backoff (backoff.rs) → retry until shutdown.
+    ///
+    /// On shutdown the slice in progress finishes, the connection is closed and
+    /// the offline buffer is persisted before returning.
     pub async fn run(&mut self) -> Result<()> {
         // Restore any persisted offline events from a previous crash
         {
             let mut buf = self.offline.lock().await;
+            let (default_max, per_tenant) = self.tenants.offline_limits();
+            buf.set_tenant_limits(default_max, per_tenant);
             if let Err(e) = buf.load().await {
                 warn!("[Node] failed to load offline buffer: {e}");
             }
+            self.sync_offline_metrics(&buf);
         }
-
+        tokio::spawn(crate::offline::run_autosave(
+            self.offline.clone(),
+            self.config.offline_autosave_secs,
+        ));
+        if let Some(watch) = &self.disk_watch {
+            tokio::spawn(crate::disk_watch::run(
+                watch.clone(),
+                self.offline.clone(),
+                self.health.clone(),
+                self.alerts_ready.clone(),
+            ));
+        }
+        tokio::spawn(crate::audit_verify::run(
+            self.audit_verify.clone(),
+            self.audit.clone(),
+            self.audit_log.clone(),
+            self.offline.clone(),
+            self.health.clone(),
+            self.alerts_ready.clone(),
+        ));
+
+        let mut backoff = Backoff::new(
+            Duration::from_secs(self.config.reconnect_interval_secs),
+            Duration::from_secs(self.config.reconnect_max_secs),
+        );
         loop {
             info!("[Node] connecting to {}", self.config.central_ws_url);
 
-            match self.connect_and_run().await {
+            let mut registered = false;
+            match self.connect_and_run(&mut registered).await {
                 Ok(()) => {
                     info!("[Node] connection closed gracefully");
                 }
@@ -98,22 +437,74 @@ impl NodeClient {
             {
                 let mut buf = self.offline.lock().await;
                 buf.notify_connected(false);
-                self.health.set_offline_depth(buf.len());
+                if let Some(pending) = self.pending_flush.take() {
+                    // Its unsent events never left the buffer
+                    warn!("[Node] offline flush interrupted after {} of {} part(s)", pending.sent, pending.parts);
+                    buf.requeue(pending.header, Vec::new());
+                }
+                self.sync_offline_metrics(&buf);
                 if let Err(e) = buf.persist().await {
                     warn!("[Node] failed to persist offline buffer: {e}");
                 }
             }
+            self.persist_session().await;
+            let dropped = self.queue.clear();
+            if dropped > 0 {
+                warn!("[Node] {dropped} queued slice(s) dropped with the session — never started");
+            }
+            if self.stopping() {
+                info!("[Node] drained — exiting");
+                return Ok(());
+            }
 
-            let wait = Duration::from_secs(self.config.reconnect_interval_secs);
+            // Central answered: retry soon.  Unreachable: back off.
+            let wait = if registered {
+                backoff.after_session(&mut rand::thread_rng())
+            } else {
+                backoff.after_network_error(&mut rand::thread_rng())
+            };
             info!("[Node] reconnecting in {wait:?}…");
-            sleep(wait).await;
+            crate::sdnotify::status("disconnected from central — reconnecting");
+            let mut shutdown = self.shutdown.clone();
+            let mut pet = crate::sdnotify::pet_ticker();
+            let reconnect = sleep(wait);
+            tokio::pin!(reconnect);
+            loop {
+                tokio::select! {
+                    _ = &mut reconnect => break,
+                    _ = pet.tick() => crate::sdnotify::watchdog(),
+                    _ = shutdown_requested(&mut shutdown) => break,
+                }
+            }
+            if self.stopping() {
+                info!("[Node] drained — exiting");
+                return Ok(());
+            }
         }
     }
 
     // ── Single connection session ─────────────────────────────────────────────
 
-    async fn connect_and_run(&mut self) -> Result<()> {
-        let (ws_stream, _resp) = connect_async(&self.config.central_ws_url).await
+    /// TCP connection to central through the hosts table / DNS cache (dns.rs).
+    async fn connect_central(&self) -> Result<tokio::net::TcpStream> {
+        let uri: Uri = self.config.central_ws_url.parse()
+            .map_err(|e| anyhow!("CENTRAL_WS_URL: {e}"))?;
+        let host = uri.host().ok_or_else(|| anyhow!("CENTRAL_WS_URL has no host"))?;
+        let port = uri.port_u16().unwrap_or(if uri.scheme_str() == Some("wss") { 443 } else { 80 });
+        let addrs = self.svm.resolver().lookup(host, port).await
+            .map_err(|e| anyhow!("resolving {host}: {e}"))?;
+        tokio::net::TcpStream::connect(&addrs[..]).await
+            .map_err(|e| anyhow!("connecting to {host}:{port}: {e}"))
+    }
+
+    /// One session; sets `registered` once REGISTER has been sent.
+    async fn connect_and_run(&mut self, registered: &mut bool) -> Result<()> {
+        let frames = FrameLimits::from(&self.config);
+        // Fetched per session: a renewed certificate applies from the next handshake
+        let connector = self.tls.as_ref().map(|tls| Connector::NativeTls(tls.connector()));
+        let stream = self.connect_central().await?;
+        let (ws_stream, _resp) = client_async_tls_with_config(&self.config.central_ws_url, stream, Some(frames.ws_config()), connector)
+            .await
             .map_err(|e| anyhow!("WebSocket handshake failed: {e}"))?;
 
         let (mut write, mut read) = ws_stream.split();
@@ -125,25 +516,176 @@ impl NodeClient {
         }
         self.health.set_ws_connected(true);
 
+        // Challenge-response: central opens with a nonce that we sign with the
+        // node's Ed25519 key.  A central that predates the handshake sends
+        // nothing — after the timeout we register without `auth`, and any
+        // frame that arrived meanwhile is handled once registered.
+        let (nonce, early_frame) = self.await_challenge(&mut read).await?;
+
         // Send registration frame
-        let reg = json!({
-            "type": "REGISTER",
-            "payload": {
-                "nodeId": self.config.node_id,
-                "tier": self.config.node_tier,
-                "capabilities": self.build_capabilities(),
-                "version": env!("CARGO_PKG_VERSION"),
-            }
+        let mut payload = json!({
+            "nodeId": self.config.node_id,
+            "tier": self.config.node_tier,
+            "capabilities": self.build_capabilities(),
+            "labels": self.config.node_labels,
+            "version": env!("CARGO_PKG_VERSION"),
         });
-        write.send(Message::Text(reg.to_string())).await?;
-        info!("[Node] registered as {} (tier={})", self.config.node_id, self.config.node_tier);
-
-        // Flush offline events accumulated during prior disconnection
+        if let Some(updater) = &self.updater {
+            payload["update"] = updater.status();
+        }
+        if self.config.flush_compact {
+            payload["flushEncodings"] = json!([flush_codec::DICT_V1]);
+        }
+        let buffered_results = self.offline.lock().await.execution_results();
+        payload["resume"] = self.session.resume_payload(buffered_results);
+        match &nonce {
+            Some(nonce) => {
+                let audit = self.audit.lock().await;
+                payload["auth"] = json!({
+                    "alg": "Ed25519",
+                    "nonce": nonce,
+                    "signature": audit.sign_register_challenge(nonce),
+                    "publicKey": audit.public_key_hex(),
+                    "fingerprint": audit.key_fingerprint(),
+                });
+            }
+            None => warn!("[Node] no CHALLENGE from central — registering without node authentication"),
+        }
+        if let Some(enrollment) = &self.enrollment {
+            payload["enrollment"] = json!({
+                "status": enrollment.status().as_str(),
+                "fingerprint": enrollment.fingerprint(),
+            });
+        }
+        let reg = json!({ "type": "REGISTER", "payload": payload }).to_string();
+        self.shape(reg.len(), Priority::Interactive).await;
+        write.send(Message::Text(reg)).await?;
+        *registered = true;
+        info!(
+            "[Node] registered as {} (tier={}, authenticated={})",
+            self.config.node_id, self.config.node_tier, nonce.is_some()
+        );
+
+        // Alerts raised while disconnected go first, then the offline events
+        self.send_alerts(&mut write).await?;
         self.flush_offline_events(&mut write).await;
+        self.report_in_doubt_actions(&mut write).await;
+        self.send_update_reports(&mut write).await?;
+        if self.standby.is_some() {
+            self.report_role(&mut write).await?;
+        }
+        crate::sdnotify::ready("connected to central");
 
-        // Message loop
-        while let Some(msg) = read.next().await {
-            let msg = msg?;
+        if let Some(text) = early_frame {
+            if let Err(e) = self.handle_text_message(&text, &mut write).await {
+                warn!("[Node] message handler error: {e}");
+            }
+        }
+
+        // Message loop (standby pairs also announce role changes)
+        let mut role_rx = self.standby.as_ref().map(|s| s.subscribe());
+        let mut shutdown = self.shutdown.clone();
+        let mut pet = crate::sdnotify::pet_ticker();
+        let mut keepalive = Keepalive::new(
+            Duration::from_secs(self.config.keepalive_interval_secs),
+            Duration::from_secs(self.config.keepalive_timeout_secs),
+            tokio::time::Instant::now(),
+        );
+        let alert_batch = Duration::from_millis(self.config.alert_batch_ms);
+        let mut alerts_at: Option<tokio::time::Instant> = None;
+        let mut update_tick = tokio::time::interval(Duration::from_secs(5));
+        let capabilities = self.svm.capabilities().clone();
+        loop {
+            let role_changed = async {
+                match role_rx.as_mut() {
+                    Some(rx) => rx.changed().await,
+                    None => std::future::pending().await,
+                }
+            };
+            let keepalive_due = async {
+                match keepalive.next_wake() {
+                    Some(at) => tokio::time::sleep_until(at).await,
+                    None => std::future::pending().await,
+                }
+            };
+            let alerts_due = async {
+                match alerts_at {
+                    Some(at) => tokio::time::sleep_until(at).await,
+                    None => std::future::pending().await,
+                }
+            };
+            let flush_part_due = async {
+                let next = self.pending_flush.as_ref().and_then(|p| p.next.as_ref());
+                match (&self.shaper, next) {
+                    (Some(shaper), Some((frame, _))) => shaper.acquire(frame.len(), Priority::Bulk).await,
+                    _ => std::future::pending().await,
+                }
+            };
+            // Biased towards reading: after a long slice the Pong may already
+            // be buffered when the keepalive deadline fires.
+            let msg = tokio::select! {
+                biased;
+                msg = read.next() => match msg {
+                    Some(Ok(msg)) => msg,
+                    Some(Err(e)) => return Err(self.read_error(e).await),
+                    None => break,
+                },
+                _ = keepalive_due => {
+                    match keepalive.poll(tokio::time::Instant::now()) {
+                        Tick::Ping(_) if self.chaos.as_ref().is_some_and(|c| c.drop_central()) => {
+                            warn!("[Chaos] dropping the central connection");
+                            return Err(anyhow!("chaos: injected central connection drop"));
+                        }
+                        Tick::Ping(data) => write.send(Message::Ping(data)).await?,
+                        Tick::Dead(silent) => {
+                            self.health.record_keepalive_timeout();
+                            return Err(anyhow!("no keepalive Pong from central in {silent:?} — dropping the connection"));
+                        }
+                        Tick::Idle => {}
+                    }
+                    continue;
+                }
+                Ok(()) = role_changed => {
+                    self.report_role(&mut write).await?;
+                    continue;
+                }
+                _ = self.alerts_ready.notified(), if alerts_at.is_none() => {
+                    alerts_at = Some(tokio::time::Instant::now() + alert_batch);
+                    continue;
+                }
+                _ = alerts_due => {
+                    alerts_at = None;
+                    self.send_alerts(&mut write).await?;
+                    continue;
+                }
+                _ = flush_part_due => {
+                    self.send_flush_part(&mut write).await?;
+                    continue;
+                }
+                _ = pet.tick() => {
+                    crate::sdnotify::watchdog();
+                    continue;
+                }
+                _ = capabilities.changed() => {
+                    self.announce_capabilities(&mut write).await?;
+                    continue;
+                }
+                _ = update_tick.tick(), if self.updater.is_some() => {
+                    self.tick_update(&mut write).await?;
+                    continue;
+                }
+                _ = shutdown_requested(&mut shutdown) => {
+                    let _ = write.send(Message::Close(None)).await;
+                    break;
+                }
+                // Only once every frame already received is queued
+                _ = std::future::ready(()), if !self.queue.is_empty() => {
+                    if let Err(e) = self.run_next_slice(&mut write).await {
+                        warn!("[Node] slice execution error: {e}");
+                    }
+                    continue;
+                }
+            };
             match msg {
                 Message::Text(text) => {
                     match self.handle_text_message(&text, &mut write).await {
@@ -160,6 +702,12 @@ impl NodeClient {
                 Message::Ping(data) => {
                     write.send(Message::Pong(data)).await?;
                 }
+                Message::Pong(data) => {
+                    if let Some(rtt) = keepalive.on_pong(&data, tokio::time::Instant::now()) {
+                        debug!("[Node] keepalive RTT {rtt:?}");
+                        self.health.set_ws_rtt(rtt);
+                    }
+                }
                 Message::Close(_) => {
                     info!("[Node] server closed connection");
                     break;
@@ -171,6 +719,56 @@ impl NodeClient {
         Ok(())
     }
 
+    /// Wait up to SVM_REGISTER_CHALLENGE_TIMEOUT_MS for central's CHALLENGE.
+    ///
+    /// Returns the nonce (if any) and the first non-challenge text frame, which
+    /// the caller replays after registering; sets `compact_flush` when the
+    /// challenge offers dict-v1.
+    async fn await_challenge<S>(&mut self, read: &mut S) -> Result<(Option<String>, Option<String>)>
+    where
+        S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        self.compact_flush = false;
+        let timeout = Duration::from_millis(self.config.register_challenge_timeout_ms);
+        if timeout.is_zero() {
+            return Ok((None, None));
+        }
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        loop {
+            let msg = match tokio::time::timeout_at(deadline, read.next()).await {
+                Err(_) => return Ok((None, None)),
+                Ok(None) => return Err(anyhow!("connection closed before REGISTER")),
+                Ok(Some(Ok(msg))) => msg,
+                Ok(Some(Err(e))) => return Err(self.read_error(e).await),
+            };
+            let Message::Text(text) = msg else { continue };
+            let frame = FrameLimits::from(&self.config).parse_text(&text).unwrap_or(Value::Null);
+            if frame.get("type").and_then(|v| v.as_str()) != Some("CHALLENGE") {
+                return Ok((None, Some(text)));
+            }
+            let nonce = frame.pointer("/payload/nonce").and_then(|v| v.as_str())
+                .filter(|n| !n.is_empty())
+                .ok_or_else(|| anyhow!("CHALLENGE frame without nonce"))?;
+            let offered = frame.pointer("/payload/flushEncodings").and_then(|v| v.as_array());
+            self.compact_flush = self.config.flush_compact
+                && offered.is_some_and(|e| e.iter().any(|e| e.as_str() == Some(flush_codec::DICT_V1)));
+            debug!("[Node] ← CHALLENGE");
+            return Ok((Some(nonce.to_owned()), None));
+        }
+    }
+
+    async fn report_role(
+        &self,
+        write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
+    ) -> Result<()> {
+        let Some(standby) = &self.standby else { return Ok(()) };
+        let frame = json!({ "type": "ROLE", "payload": standby.role_payload().await }).to_string();
+        self.shape(frame.len(), Priority::Interactive).await;
+        write.send(Message::Text(frame)).await?;
+        Ok(())
+    }
+
     // ── Message dispatch ──────────────────────────────────────────────────────
 
     async fn handle_text_message(
@@ -178,7 +776,16 @@ impl NodeClient {
         text: &str,
         write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
     ) -> Result<()> {
-        let frame: Value = serde_json::from_str(text)?;
+        let frame = match FrameLimits::from(&self.config).parse_text(text) {
+            Ok(frame) => frame,
+            Err(e) => match e.downcast_ref::<LimitViolation>() {
+                Some(v) => {
+                    self.report_frame_violation(v).await;
+                    return Ok(());
+                }
+                None => return Err(e),
+            },
+        };
         let msg_type = frame.get("type").and_then(|v| v.as_str()).unwrap_or("UNKNOWN");
         debug!("[Node] ← {msg_type}");
 
@@ -186,21 +793,179 @@ impl NodeClient {
             "IR_DISTRIBUTION" => {
                 let payload = frame.get("payload")
                     .ok_or_else(|| anyhow!("IR_DISTRIBUTION missing payload"))?;
-                let result = self.execute_ir_from_payload(payload).await?;
-                let result_frame = json!({
-                    "type": "RESULT",
-                    "payload": result,
-                });
-                write.send(Message::Text(result_frame.to_string())).await?;
+                let (workflow_id, artifact) = self.artifact_from_payload(payload).await?;
+                let prepared = self.ingest_artifact(&workflow_id, &artifact).await?;
+                self.enqueue_slice(prepared, Reply::Text, write).await?;
             }
 
             "PING" => {
-                write.send(Message::Text(json!({"type":"PONG"}).to_string())).await?;
+                let now = chrono::Utc::now().timestamp_millis();
+                let server_time = frame.pointer("/payload/serverTime").and_then(|v| v.as_i64());
+                let pong = json!({
+                    "type": "PONG",
+                    "payload": { "serverTime": server_time, "nodeTime": now },
+                }).to_string();
+                self.shape(pong.len(), Priority::Interactive).await;
+                write.send(Message::Text(pong)).await?;
+                if let Some(server_time) = server_time {
+                    let rtt = frame.pointer("/payload/rttMs").and_then(|v| v.as_u64());
+                    self.update_clock_offset(server_time, rtt, now).await;
+                }
+            }
+
+            "RESULT_ACK" => {
+                let ids = frame.pointer("/payload/sliceIds").and_then(|v| v.as_array());
+                let acked = self.session.ack(ids.into_iter().flatten().filter_map(|id| id.as_str()));
+                debug!("[Node] RESULT_ACK: {acked} result(s) acknowledged");
+                self.persist_session().await;
+            }
+
+            "RESUME" => {
+                let ids = frame.pointer("/payload/resend").and_then(|v| v.as_array());
+                let resend = self.session.resume(ids.into_iter().flatten().filter_map(|id| id.as_str()));
+                for result in resend {
+                    let result_frame = json!({ "type": "RESULT", "payload": result }).to_string();
+                    self.shape(result_frame.len(), Priority::Interactive).await;
+                    write.send(Message::Text(result_frame)).await?;
+                }
+                self.persist_session().await;
             }
 
+            "BLOB_REQUEST" => {
+                let id = frame.pointer("/payload/id").and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow!("BLOB_REQUEST missing payload.id"))?;
+                let blob_frame = self.svm.blobs().frame(id).to_string();
+                self.shape(blob_frame.len(), Priority::Bulk).await;
+                write.send(Message::Text(blob_frame)).await?;
+            }
+
+            "ASSET_DISTRIBUTION" => {
+                let payload = frame.get("payload").unwrap_or(&Value::Null);
+                let result = match self.svm.assets() {
+                    Ok(assets) => assets.receive(payload).await,
+                    Err(e) => Err(e),
+                };
+                let ack = match result {
+                    Ok(spec) => json!({ "sha256": spec.sha256, "name": spec.name, "status": "CACHED" }),
+                    Err(e) => {
+                        self.reject_asset(payload, &e).await;
+                        json!({
+                            "sha256": payload.get("sha256"),
+                            "name": payload.get("name"),
+                            "status": "REJECTED",
+                            "error": e.to_string(),
+                        })
+                    }
+                };
+                write.send(Message::Text(json!({ "type": "ASSET_ACK", "payload": ack }).to_string())).await?;
+            }
+
+            "RELEASE" => match self.updater.clone() {
+                Some(updater) => match updater.verify(&frame) {
+                    Ok(release) => {
+                        info!("[Node] RELEASE v{} — staging", release.version);
+                        tokio::spawn(updater.stage(release));
+                    }
+                    Err(e) => {
+                        let version = frame.get("signedPayload").and_then(|v| v.as_str())
+                            .and_then(|t| serde_json::from_str::<Value>(t).ok())
+                            .and_then(|m| m.get("version").and_then(|v| v.as_str()).map(str::to_owned))
+                            .unwrap_or_default();
+                        self.reject_release(&version, &e).await;
+                        updater.report(&version, "REJECTED", Some(e.to_string()));
+                        self.send_update_reports(write).await?;
+                    }
+                },
+                None => debug!("[Node] RELEASE frame ignored — self-update is off (SVM_UPDATE)"),
+            },
+
+            "ENROLLMENT" => match &self.enrollment {
+                Some(enrollment) => {
+                    if let Err(e) = enrollment.apply_value(frame.get("payload").unwrap_or(&Value::Null)) {
+                        warn!("[Node] ENROLLMENT: {e}");
+                    }
+                }
+                None => debug!("[Node] ENROLLMENT frame ignored — node key is not keystore-provisioned"),
+            },
+
+            "LEADER" => match &self.standby {
+                Some(standby) => standby.grant(frame.get("payload").unwrap_or(&Value::Null)),
+                None => debug!("[Node] LEADER frame ignored — standby mode is off"),
+            },
+
             "CONFIG_UPDATE" => {
-                // Live config updates not yet applied; log only
-                info!("[Node] CONFIG_UPDATE received (not applied)");
+                // Live-applied keys: serviceCatalog, resourceCapacities, tenantQuotas,
+                // auditPolicy, healthThresholds, irAllowList (signed only); others are logged
+                let payload = match self.config_verifier.verify(&frame) {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        self.reject_config_update(&e).await;
+                        return Ok(());
+                    }
+                };
+                if !self.config_verifier.requires_signature() {
+                    warn!("[Node] applying unsigned CONFIG_UPDATE (SVM_CONFIG_OPS_PUBLIC_KEY not set)");
+                }
+                let mut applied = false;
+                if let Some(services) = payload.get("serviceCatalog") {
+                    applied = true;
+                    match self.svm.catalog().apply_update(services).await {
+                        Ok(n) => info!("[Node] CONFIG_UPDATE: service catalogue updated ({n} services)"),
+                        Err(e) => warn!("[Node] CONFIG_UPDATE: {e}"),
+                    }
+                }
+                if let Some(caps) = payload.get("resourceCapacities") {
+                    applied = true;
+                    match self.svm.resource_arbiter().set_capacities(caps) {
+                        Ok(n) => info!("[Node] CONFIG_UPDATE: {n} resource capacities applied"),
+                        Err(e) => warn!("[Node] CONFIG_UPDATE: {e}"),
+                    }
+                }
+                if let Some(quotas) = payload.get("tenantQuotas") {
+                    applied = true;
+                    match self.tenants.set_quotas(quotas) {
+                        Ok(n) => {
+                            let (default_max, per_tenant) = self.tenants.offline_limits();
+                            self.offline.lock().await.set_tenant_limits(default_max, per_tenant);
+                            info!("[Node] CONFIG_UPDATE: {n} tenant quota(s) applied");
+                        }
+                        Err(e) => warn!("[Node] CONFIG_UPDATE: {e}"),
+                    }
+                }
+                if let Some(policy) = payload.get("auditPolicy") {
+                    applied = true;
+                    match AuditPolicy::parse(policy) {
+                        Ok(policy) => {
+                            info!("[Node] CONFIG_UPDATE: audit policy {}", if policy.is_some() { "applied" } else { "cleared" });
+                            self.audit.lock().await.set_policy(policy.map(Arc::new));
+                        }
+                        Err(e) => warn!("[Node] CONFIG_UPDATE: {e}"),
+                    }
+                }
+                if let Some(thresholds) = payload.get("healthThresholds") {
+                    applied = true;
+                    match HealthThresholds::parse(thresholds) {
+                        Ok(thresholds) => {
+                            info!("[Node] CONFIG_UPDATE: health thresholds applied");
+                            self.health.set_thresholds(thresholds);
+                        }
+                        Err(e) => warn!("[Node] CONFIG_UPDATE: {e}"),
+                    }
+                }
+                if let Some(list) = payload.get("irAllowList") {
+                    applied = true;
+                    if !self.config_verifier.requires_signature() {
+                        warn!("[Node] CONFIG_UPDATE: irAllowList ignored — it is only accepted signed (SVM_CONFIG_OPS_PUBLIC_KEY)");
+                    } else {
+                        match self.svm.allowlist().apply_update(list) {
+                            Ok(n) => info!("[Node] CONFIG_UPDATE: IR allow-list of {n} checksum(s) applied"),
+                            Err(e) => warn!("[Node] CONFIG_UPDATE: {e}"),
+                        }
+                    }
+                }
+                if !applied {
+                    info!("[Node] CONFIG_UPDATE received (not applied)");
+                }
             }
 
             other => {
@@ -216,24 +981,137 @@ impl NodeClient {
         write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
     ) -> Result<()> {
         // Binary frames are proto-encoded IRDistributionMessage
+        let limits = DecodeLimits::from(&self.config);
+        if let Err(e) = ir_limits::check_size(data.len(), &limits) {
+            self.report_limit_violation(&e, "").await;
+            return Err(e);
+        }
         let dist_msg = IrDistributionMessage::decode(data)
             .map_err(|e| anyhow!("proto decode error: {e}"))?;
-
         let artifact = dist_msg.artifact
             .ok_or_else(|| anyhow!("IRDistributionMessage.artifact is null"))?;
 
+        let prepared = self.ingest_artifact(&dist_msg.workflow_id, &artifact).await?;
+        self.enqueue_slice(prepared, Reply::Binary, write).await
+    }
+
+    // ── IR execution ──────────────────────────────────────────────────────────
+
+    /// Queue a prepared slice; one turned away by a full queue is answered
+    /// QUEUE_FULL straight away.
+    async fn enqueue_slice(
+        &mut self,
+        prepared: Arc<PreparedIr>,
+        reply: Reply,
+        write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
+    ) -> Result<()> {
+        let priority = sched::plan_priority(&prepared.ir);
+        let Some(refused) = self.queue.push(QueuedSlice { prepared, reply }, priority) else { return Ok(()) };
+        let workflow_id = refused.prepared.ir.metadata.as_ref()
+            .map(|m| m.id.clone())
+            .unwrap_or_else(|| "unknown".to_owned());
+        warn!("[Node] workflow={workflow_id} refused — {} slice(s) already queued", self.queue.len());
+        let result = self.rejected(
+            workflow_id, "QUEUE_FULL", ErrorCode::Quota,
+            format!("execution queue full ({} slices)", self.queue.len()),
+        );
+        self.reply(result, refused.reply, write).await
+    }
+
+    /// Run the most urgent queued slice and send its result.
+    async fn run_next_slice(
+        &mut self,
+        write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
+    ) -> Result<()> {
+        let Some(next) = self.queue.pop() else { return Ok(()) };
+        let result = self.execute_ir(&next.prepared).await;
+        self.last_slice_at = tokio::time::Instant::now();
+        self.reply(result?, next.reply, write).await
+    }
+
+    /// Record `result` and send it back in the frame kind the slice came in.
+    async fn reply(
+        &self,
+        result: SliceExecutionResult,
+        reply: Reply,
+        write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
+    ) -> Result<()> {
+        let json_result = serde_json::to_value(&result)?;
+        self.track_result(&json_result).await;
+        self.results.record(json_result.clone()).await;
+        match reply {
+            Reply::Text => {
+                let result_frame = json!({ "type": "RESULT", "payload": json_result }).to_string();
+                self.shape(result_frame.len(), Priority::Interactive).await;
+                write.send(Message::Text(result_frame)).await?;
+            }
+            Reply::Binary => {
+                let mut result_bytes = Vec::new();
+                result.encode(&mut result_bytes)?;
+                self.shape(result_bytes.len(), Priority::Interactive).await;
+                write.send(Message::Binary(result_bytes)).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a JSON-framed IR_DISTRIBUTION payload into what a binary frame
+    /// carries: either an IRDistributionMessage as JSON (`artifact` an
+    /// object), or — with SVM_ALLOW_UNSIGNED_IR only — a bare base64 LLM-IR
+    /// in `artifact` / `payload`, taken as an unsigned format-version-0
+    /// artifact.
+    async fn artifact_from_payload(&self, payload: &Value) -> Result<(String, SignedIrArtifact)> {
+        if payload.get("artifact").is_some_and(Value::is_object) {
+            let dist_msg: IrDistributionMessage = serde_json::from_value(payload.clone())
+                .map_err(|e| anyhow!("IR_DISTRIBUTION payload: {e}"))?;
+            let artifact = dist_msg.artifact
+                .ok_or_else(|| anyhow!("IRDistributionMessage.artifact is null"))?;
+            return Ok((dist_msg.workflow_id, artifact));
+        }
+
+        let b64 = payload.get("artifact")
+            .or_else(|| payload.get("payload"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+
+        if b64.is_empty() {
+            return Err(anyhow!("IR_DISTRIBUTION payload has no artifact field"));
+        }
+        if !self.config.allow_unsigned_ir {
+            return Err(anyhow!(
+                "IR_DISTRIBUTION carries a bare, unsigned IR payload — refused (SVM_ALLOW_UNSIGNED_IR is off)"
+            ));
+        }
+
+        // Size check on the base64 text, before anything is allocated
+        if let Err(e) = ir_limits::check_size(b64.len() / 4 * 3, &DecodeLimits::from(&self.config)) {
+            self.report_limit_violation(&e, "").await;
+            return Err(e);
+        }
+
+        let bytes = B64.decode(b64)
+            .map_err(|e| anyhow!("base64 decode error: {e}"))?;
+        Ok((String::new(), SignedIrArtifact { payload: bytes, ..Default::default() }))
+    }
+
+    /// The single way an artifact becomes runnable, whatever frame carried
+    /// it: format version policy (spec §5.3), checksum + signature (spec
+    /// §13.1), then decode limits through the prepared-IR cache.
+    async fn ingest_artifact(&self, workflow_id: &str, artifact: &SignedIrArtifact) -> Result<Arc<PreparedIr>> {
         // ── IR format version compatibility check (spec §5.3) ────────────────
         // Same major → execute (warn if minor differs)
         // Different major → refuse execution entirely
-        // Version 0 is reserved for dev/unsigned artifacts → accept with warning
+        // Version 0 is reserved for dev/unsigned artifacts → SVM_ALLOW_UNSIGNED_IR only
         let artifact_format_version = artifact.version;
         let node_major = self.config.ir_version_major;
 
         if artifact_format_version == 0 {
-            warn!(
-                "[Node] IR artifact format_version=0 (unsigned/dev artifact) — \
-                 accepting with warning. Set SVM_IR_VERSION_MAJOR in production."
-            );
+            if !self.config.allow_unsigned_ir {
+                return Err(anyhow!(
+                    "IR artifact format_version=0 (unsigned/dev artifact) — refused (SVM_ALLOW_UNSIGNED_IR is off)"
+                ));
+            }
+            warn!("[Node] IR artifact format_version=0 (unsigned/dev artifact) — accepted (SVM_ALLOW_UNSIGNED_IR)");
         } else if artifact_format_version != node_major {
             error!(
                 "[Node] ⛔ IR format version incompatible: \
@@ -241,23 +1119,13 @@ impl NodeClient {
                  refusing execution (spec §5.3)"
             );
             // Send security alert to central
-            let alert_url = format!(
-                "{}/api/nodes/security-alert",
-                self.config.central_http_url
-            );
-            let payload = serde_json::json!({
+            self.security_alert(json!({
                 "type": "IR_VERSION_INCOMPATIBLE",
                 "nodeId": self.config.node_id,
                 "nodeMajor": node_major,
                 "artifactMajor": artifact_format_version,
-                "workflowId": dist_msg.workflow_id,
-            });
-            // Best-effort alert; do not block on failure
-            let _ = reqwest::Client::new()
-                .post(&alert_url)
-                .json(&payload)
-                .send()
-                .await;
+                "workflowId": workflow_id,
+            })).await;
 
             return Err(anyhow!(
                 "IR major version mismatch: node={node_major} artifact={artifact_format_version}"
@@ -265,113 +1133,190 @@ impl NodeClient {
         }
 
         // Verify Ed25519 signature (spec §13.1)
-        Self::verify_artifact_signature(&artifact)?;
+        if let Err(e) = verify_artifact_signature(artifact, self.ir_key.as_ref(), self.config.allow_unsigned_ir) {
+            error!("[Node] ⛔ IR artifact for workflow={workflow_id} rejected: {e}");
+            self.security_alert(json!({
+                "type": "IR_SIGNATURE_INVALID",
+                "nodeId": self.config.node_id,
+                "workflowId": workflow_id,
+                "error": e.to_string(),
+            })).await;
+            return Err(e);
+        }
 
-        let ir = crate::proto::llmir::LlmIntermediateRepresentation::decode(
-            artifact.payload.as_ref()
-        ).map_err(|e| anyhow!("IR proto decode error: {e}"))?;
 
-        let result = self.execute_ir(&ir).await?;
-        let mut result_bytes = Vec::new();
-        result.encode(&mut result_bytes)?;
-        write.send(Message::Binary(result_bytes)).await?;
-        Ok(())
+        let limits = DecodeLimits::from(&self.config);
+        match self.ir_cache.get_or_prepare(&artifact.payload, |b| ir_limits::decode_ir(b, &limits)) {
+            Ok(p) => Ok(p),
+            Err(e) => {
+                self.report_limit_violation(&e, workflow_id).await;
+                Err(e)
+            }
+        }
     }
 
-    // ── IR execution ──────────────────────────────────────────────────────────
+    async fn execute_ir(&mut self, prepared: &PreparedIr) -> Result<SliceExecutionResult> {
+        let ir = &prepared.ir;
+        let workflow_id = ir.metadata.as_ref()
+            .map(|m| m.id.clone())
+            .unwrap_or_else(|| "unknown".to_owned());
 
-    async fn execute_ir_from_payload(&mut self, payload: &Value) -> Result<Value> {
-        // JSON-framed IR distribution (non-binary path)
-        let b64 = payload.get("artifact")
-            .or_else(|| payload.get("payload"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
+        // Static verification — reject malformed IR before any side effect
+        if let Err(issues) = crate::verifier::verify(ir) {
+            warn!("[Node] IR for workflow={workflow_id} failed verification ({} issue(s))", issues.len());
+            self.health.record_execution(&workflow_id, 0, false);
+            return Ok(self.rejected(
+                workflow_id, "VALIDATION_FAILED", ErrorCode::Validation,
+                json!({ "issues": issues }).to_string(),
+            ));
+        }
 
-        if b64.is_empty() {
-            return Err(anyhow!("IR_DISTRIBUTION payload has no artifact field"));
+        // Placement — validity window (central's clock) and node selector
+        if let Some(meta) = &ir.metadata {
+            let offset = self.health.clock_offset().unwrap_or(0);
+            let now = chrono::Utc::now() + chrono::Duration::milliseconds(offset);
+            if let Err(refusal) = crate::placement::check(meta, &self.config.node_labels, now) {
+                warn!("[Node] workflow={workflow_id} not runnable here: {}", refusal.reason);
+                return Ok(self.rejected(
+                    workflow_id, refusal.status, refusal.code(),
+                    serde_json::to_string(&refusal)?,
+                ));
+            }
         }
 
-        let proto_bytes = B64.decode(b64)
-            .map_err(|e| anyhow!("base64 decode error: {e}"))?;
+        // Warm standby — keep the artifact for handoff (only the leader executes)
+        if let Some(standby) = &self.standby {
+            standby.remember(&workflow_id, ir).await;
+        }
 
-        let ir = crate::proto::llmir::LlmIntermediateRepresentation::decode(
-            proto_bytes.as_slice()
-        ).map_err(|e| anyhow!("IR proto decode: {e}"))?;
+        // Admission — quarantine, allow-list, standby and tenant quotas
+        // (admission.rs, shared with peer delegations)
+        let permit = match self.admission().admit(ir, &prepared.checksum) {
+            Ok(admitted) => {
+                if admitted.unlisted {
+                    self.report_unlisted(&workflow_id, &prepared.checksum, false).await;
+                }
+                admitted.permit
+            }
+            Err(refusal) => {
+                if refusal.status == "NOT_ALLOWLISTED" {
+                    self.report_unlisted(&workflow_id, &prepared.checksum, true).await;
+                } else {
+                    warn!("[Node] workflow={workflow_id} not admitted ({}): {}", refusal.status, refusal.reason);
+                }
+                return Ok(self.rejected(workflow_id, refusal.status, refusal.code, refusal.reason));
+            }
+        };
 
-        let result_proto = self.execute_ir(&ir).await?;
+        // Feature negotiation — refuse artifacts needing what this build lacks
+        let negotiation = crate::features::negotiate(ir, &self.svm.features());
+        // Instruction-level gaps can be filled by a peer at run time
+        let implied = crate::features::instruction_features(ir);
+        let delegable = self.svm.can_delegate()
+            && negotiation.missing_required.iter().all(|f| implied.contains(f));
+        if !negotiation.missing_required.is_empty() && delegable {
+            info!(
+                "[Node] workflow={workflow_id}: instructions needing {:?} will be delegated to peers",
+                negotiation.missing_required
+            );
+        } else if !negotiation.missing_required.is_empty() {
+            warn!(
+                "[Node] IR for workflow={workflow_id} requires unsupported features: {:?}",
+                negotiation.missing_required
+            );
+            self.health.record_execution(&workflow_id, 0, false);
+            return Ok(self.rejected(
+                workflow_id, "INCOMPATIBLE", ErrorCode::Unsupported,
+                json!({ "missingFeatures": negotiation.missing_required }).to_string(),
+            ));
+        }
+        if !negotiation.missing_optional.is_empty() {
+            warn!(
+                "[Node] workflow={workflow_id} runs degraded — optional features unavailable: {:?}",
+                negotiation.missing_optional
+            );
+        }
 
-        // Convert proto result to JSON for text-framed response
-        let json_result = serde_json::to_value(&ResultJson::from(&result_proto))?;
-        Ok(json_result)
-    }
+        let tenant = crate::tenant::tenant_of(ir);
 
-    async fn execute_ir(
-        &mut self,
-        ir: &crate::proto::llmir::LlmIntermediateRepresentation,
-    ) -> Result<SliceExecutionResult> {
-        let workflow_id = ir.metadata.as_ref()
-            .map(|m| m.id.clone())
-            .unwrap_or_else(|| "unknown".to_owned());
 
         let mut audit = self.audit.lock().await;
         let start = std::time::Instant::now();
 
-        let (regs, elapsed_ms) = match self.svm.execute(ir, &mut audit).await {
+        let mut lineage = self.config.lineage_enabled
+            .then(|| crate::lineage::LineageTracker::new(ir.input_register));
+
+        let (regs, elapsed_ms) = match self.svm.execute(prepared, &mut audit, lineage.as_mut()).await {
             Ok(r) => {
-                self.health.record_execution(r.1, true);
+                self.health.record_execution(&workflow_id, r.1, true);
+                permit.finish(r.1, true);
                 r
             }
             Err(e) => {
-                self.health.record_execution(start.elapsed().as_millis() as u64, false);
-                error!("[Node] SVM execution failed: {e}");
+                let elapsed_ms = start.elapsed().as_millis() as u64;
+                self.health.record_execution(&workflow_id, elapsed_ms, false);
+                permit.finish(elapsed_ms, false);
+                error!("[Node] SVM execution failed ({}): {e}", e.code);
+                // Distinct status so central can reschedule on a larger node
+                let status = if e.code == ErrorCode::MemoryLimit { "MEMORY_LIMIT" } else { "FAILED" };
+
+                audit.append(
+                    &workflow_id, ir.metadata.as_ref().map(|m| m.version as u32),
+                    e.instruction.map(|i| i.to_string()),
+                    "SLICE_FAILED",
+                    None, None,
+                    elapsed_ms,
+                    Some(json!(e)),
+                );
 
                 // Try to get offline buffer and enqueue the error
+                let slice_id = uuid::Uuid::new_v4().to_string();
                 let mut buf = self.offline.lock().await;
-                self.health.set_offline_depth(buf.len());
                 if buf.is_buffering() {
                     buf.enqueue_execution_result(json!({
                         "workflowId": workflow_id,
-                        "status": "FAILED",
-                        "error": e.to_string(),
+                        "sliceId": slice_id,
+                        "tenantId": tenant,
+                        "status": status,
+                        "error": e.message,
+                        "errorCode": e.code,
+                        "errorInstruction": e.instruction,
                     }));
+                    if buf.persist_due() {
+                        if let Err(e) = buf.persist().await {
+                            warn!("[Node] failed to persist offline buffer: {e}");
+                        }
+                    }
                 }
+                self.sync_offline_metrics(&buf);
 
                 return Ok(SliceExecutionResult {
                     plan_id: workflow_id.clone(),
-                    slice_id: uuid::Uuid::new_v4().to_string(),
+                    slice_id,
                     node_id: self.config.node_id.clone(),
-                    status: "FAILED".to_owned(),
-                    error: e.to_string(),
-                    duration_ms: start.elapsed().as_millis() as i32,
+                    status: status.to_owned(),
+                    error: e.message,
+                    duration_ms: elapsed_ms as i32,
                     output_registers: Default::default(),
-                    audit_events: vec![],
+                    audit_events: self.drain_audit(&mut audit).await.into_iter().map(audit_event_proto).collect(),
+                    lineage: vec![],
+                    error_code: e.code.as_str().to_owned(),
+                    error_instruction: e.instruction.unwrap_or(-1),
+                    blobs: vec![],
                 });
             }
         };
 
-        let audit_events = audit.drain()
-            .into_iter()
-            .map(|ev| crate::proto::llmir::AuditEventProto {
-                event_id:            ev.event_id,
-                timestamp:           ev.timestamp,
-                node_id:             ev.node_id,
-                workflow_id:         ev.workflow_id,
-                workflow_version:    ev.workflow_version.unwrap_or(0) as i32,
-                instruction_id:      ev.instruction_id.unwrap_or_default(),
-                event_type:          ev.event_type,
-                input_hash:          ev.input_hash,
-                output_hash:         ev.output_hash,
-                duration_ms:         ev.duration_ms as i32,
-                previous_event_hash: ev.previous_event_hash,
-                self_hash:           ev.self_hash,
-                signature:           ev.signature,
-            })
-            .collect();
+        let audit_events = self.drain_audit(&mut audit).await.into_iter().map(audit_event_proto).collect();
 
         let output_registers: std::collections::HashMap<i32, String> = regs
             .iter()
             .map(|(k, v)| (*k, v.to_string()))
             .collect();
+        let lineage = lineage
+            .map(|l| l.to_proto(regs.keys().copied()))
+            .unwrap_or_default();
+        let blobs = crate::blob::references(&regs);
 
         Ok(SliceExecutionResult {
             plan_id: workflow_id,
@@ -382,11 +1327,104 @@ impl NodeClient {
             duration_ms: elapsed_ms as i32,
             output_registers,
             audit_events,
+            lineage,
+            error_code: String::new(),
+            error_instruction: -1,
+            blobs,
         })
     }
 
+    /// Hold `result` (JSON view) until central acks it.
+    async fn track_result(&self, result: &Value) {
+        self.session.sent(result);
+        self.persist_session().await;
+    }
+
+    async fn persist_session(&self) {
+        if let Err(e) = self.session.persist().await {
+            warn!("[Node] failed to persist session ledger: {e}");
+        }
+    }
+
+    /// The admission gates over this client's enrollment, standby and tenants.
+    fn admission(&self) -> Admission {
+        Admission::new(self.svm.allowlist().clone(), self.tenants.clone())
+            .with_enrollment(self.enrollment.clone())
+            .with_standby(self.standby.clone())
+    }
+
+    /// Result for a slice refused before execution.
+    fn rejected(&self, workflow_id: String, status: &str, code: ErrorCode, error: String) -> SliceExecutionResult {
+        SliceExecutionResult {
+            plan_id: workflow_id,
+            slice_id: uuid::Uuid::new_v4().to_string(),
+            node_id: self.config.node_id.clone(),
+            status: status.to_owned(),
+            error,
+            duration_ms: 0,
+            output_registers: Default::default(),
+            audit_events: vec![],
+            lineage: vec![],
+            error_code: code.as_str().to_owned(),
+            error_instruction: -1,
+            blobs: vec![],
+        }
+    }
+
     // ── Offline flush ─────────────────────────────────────────────────────────
 
+    /// Take the slice's audit events, queueing them for the Kafka audit topic
+    /// and appending them to the audit log.  A chain failing verification is
+    /// quarantined instead (audit_verify.rs) and nothing is returned.
+    async fn drain_audit(&self, audit: &mut AuditChain) -> Vec<AuditEvent> {
+        let alert = self.audit_verify.check_chain(audit).await;
+        self.health.set_audit_chain(self.audit_verify.status());
+        if let Some(alert) = alert {
+            self.security_alert(alert).await;
+            return Vec::new();
+        }
+        let events = audit.drain();
+        if let Some(log) = &self.audit_log {
+            if let Err(e) = log.append(&events).await {
+                warn!("[Node] audit log {}: {e}", log.path().display());
+            }
+        }
+        if let Some(kafka) = &self.kafka {
+            kafka.mirror_audit(&events).await;
+        }
+        events
+    }
+
+    /// Publish total and per-tenant offline depth to /metrics.
+    fn sync_offline_metrics(&self, buf: &OfflineBuffer) {
+        self.health.set_offline_depth(buf.len());
+        self.tenants.set_offline_depths(buf.tenant_depths());
+    }
+
+    /// Tell central about CALL_ACTION / CALL_SERVICE calls whose outcome was
+    /// lost in a restart; they are forgotten once the frame is sent.
+    async fn report_in_doubt_actions(
+        &self,
+        write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
+    ) {
+        let wal = self.svm.action_wal();
+        let intents = wal.in_doubt().await;
+        if intents.is_empty() {
+            return;
+        }
+        warn!("[Node] reporting {} in-doubt action(s) to central", intents.len());
+        let frame = json!({ "type": "ACTIONS_IN_DOUBT", "payload": intents }).to_string();
+        self.shape(frame.len(), Priority::Bulk).await;
+        match write.send(Message::Text(frame)).await {
+            Ok(()) => {
+                if let Err(e) = wal.acknowledge_in_doubt().await {
+                    warn!("[Node] failed to compact action WAL: {e}");
+                }
+            }
+            Err(e) => warn!("[Node] in-doubt report failed: {e} — will retry on reconnect"),
+        }
+    }
+
     async fn flush_offline_events(
         &mut self,
         write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
@@ -397,12 +1435,29 @@ impl NodeClient {
         }
 
         info!("[Node] flushing {} offline event(s)", buf.len());
-        let events = buf.drain_for_flush();
-
-        let frame = json!({
-            "type": "AUDIT_FLUSH",
-            "payload": events,
-        });
+        if let Some(shaper) = &self.shaper {
+            // Sent part by part from the message loop, so RESULTs can cut in
+            let pending = PendingFlush::new(buf.flush_header(), shaper.flush_chunk(), self.compact_flush, &buf);
+            info!("[Node] offline flush paced in {} part(s)", pending.parts);
+            self.pending_flush = Some(pending);
+            return;
+        }
+        let (header, events) = buf.drain_for_flush();
+
+        let frame = if self.compact_flush {
+            json!({
+                "type": "AUDIT_FLUSH",
+                "encoding": flush_codec::DICT_V1,
+                "header": header,
+                "payload": flush_codec::encode(&events),
+            })
+        } else {
+            json!({
+                "type": "AUDIT_FLUSH",
+                "header": header,
+                "payload": events,
+            })
+        };
 
         match write.send(Message::Text(frame.to_string())).await {
             Ok(()) => {
@@ -413,106 +1468,416 @@ impl NodeClient {
             }
             Err(e) => {
                 warn!("[Node] offline flush send failed: {e} — re-enqueuing");
-                for ev in events {
-                    // Re-enqueue (drop oldest if full)
-                    match &ev {
-                        crate::offline::BufferedEvent::AuditEvent { payload, .. } => {
-                            buf.enqueue_audit_event(payload.clone());
-                        }
-                        crate::offline::BufferedEvent::ExecutionResult { payload, .. } => {
-                            buf.enqueue_execution_result(payload.clone());
-                        }
-                        crate::offline::BufferedEvent::TriggerFire { payload, .. } => {
-                            buf.enqueue_trigger_fire(payload.clone());
-                        }
-                    }
+                buf.requeue(header, events);
+                if let Err(e) = buf.persist().await {
+                    warn!("[Node] failed to persist offline buffer: {e}");
                 }
             }
         }
     }
 
-    // ── Signature verification ────────────────────────────────────────────────
+    /// Send the queued security alerts as ALERT frames, each batch leaving
+    /// the alert queue once written; on error the rest wait for the next
+    /// session.
+    async fn send_alerts(
+        &self,
+        write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
+    ) -> Result<()> {
+        loop {
+            let batch = self.offline.lock().await.alert_batch(ALERTS_PER_FRAME);
+            if batch.is_empty() {
+                return Ok(());
+            }
+            let count = batch.len();
+            let frame = json!({ "type": "ALERT", "payload": batch }).to_string();
+            self.shape(frame.len(), Priority::Interactive).await;
+            write.send(Message::Text(frame)).await?;
+            info!("[Node] → ALERT ({count} security alert(s))");
+            self.offline.lock().await.pop_alerts(count);
+        }
+    }
 
-    fn verify_artifact_signature(
-        artifact: &crate::proto::llmir::SignedIrArtifact,
+    /// Send the next part of a paced flush (its uplink tokens are taken) and
+    /// drop its events from the buffer.
+    async fn send_flush_part(
+        &mut self,
+        write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
     ) -> Result<()> {
-        use sha2::{Digest, Sha256};
+        let Some(pending) = self.pending_flush.as_mut() else { return Ok(()) };
+        let Some((frame, count)) = pending.next.take() else { return Ok(()) };
+        write.send(Message::Text(frame)).await?;
 
-        // Verify SHA-256 payload checksum
-        let mut hasher = Sha256::new();
-        hasher.update(&artifact.payload);
-        let actual_checksum = hex::encode(hasher.finalize());
+        let mut buf = self.offline.lock().await;
+        for _ in 0..count {
+            buf.pop_front();
+        }
+        pending.sent += 1;
+        pending.prepare(&buf);
+        if pending.next.is_none() {
+            self.pending_flush = None;
+            info!("[Node] offline flush sent");
+            if let Err(e) = buf.clear_disk().await {
+                warn!("[Node] failed to clear offline disk: {e}");
+            }
+        }
+        self.sync_offline_metrics(&buf);
+        Ok(())
+    }
 
-        if !artifact.payload_checksum.is_empty()
-            && actual_checksum != artifact.payload_checksum
-        {
-            return Err(anyhow!(
-                "IR artifact checksum mismatch: expected {} got {}",
-                artifact.payload_checksum,
-                actual_checksum
-            ));
+    /// Wait for uplink tokens (immediate when unshaped).
+    async fn shape(&self, bytes: usize, priority: Priority) {
+        if let Some(shaper) = &self.shaper {
+            shaper.acquire(bytes, priority).await;
         }
+    }
 
-        // Ed25519 signature verification skipped when public_key_pem is empty
-        // (e.g. internal test messages).  In production the key is always present.
-        if artifact.public_key_pem.is_empty() || artifact.signature.is_empty() {
-            warn!("[Node] IR artifact has no signature — skipping verification");
-            return Ok(());
+    // ── Security alerts ───────────────────────────────────────────────────────
+
+    /// Queue a security alert for the next ALERT frame (never blocks
+    /// execution).  Raised while offline it is persisted straight away.
+    async fn security_alert(&self, payload: Value) {
+        let mut buf = self.offline.lock().await;
+        buf.enqueue_alert(payload);
+        if buf.is_buffering() {
+            if let Err(e) = buf.persist().await {
+                warn!("[Node] failed to persist offline buffer: {e}");
+            }
         }
+        drop(buf);
+        self.alerts_ready.notify_one();
+    }
+
+    /// Refuse a CONFIG_UPDATE that failed signature verification.
+    async fn reject_config_update(&self, err: &anyhow::Error) {
+        error!("[Node] ⛔ CONFIG_UPDATE rejected: {err}");
+        self.audit.lock().await.append(
+            "", None, None::<String>,
+            "CONFIG_UPDATE_REJECTED",
+            None, None, 0,
+            Some(json!({ "reason": err.to_string() })),
+        );
+        self.security_alert(json!({
+            "type": "CONFIG_UPDATE_SIGNATURE_INVALID",
+            "nodeId": self.config.node_id,
+            "reason": err.to_string(),
+        })).await;
+    }
 
-        // TODO: parse PEM public key + verify sig bytes
-        // For now: trust checksum verification above (production adds full verify)
-        debug!("[Node] signature present — full PEM verification TODO");
+    /// Log a refused ASSET_DISTRIBUTION; a bad signature is a security alert.
+    async fn reject_asset(&self, payload: &Value, err: &anyhow::Error) {
+        if crate::errors::classify(err) != ErrorCode::Auth {
+            warn!("[Node] ASSET_DISTRIBUTION rejected: {err}");
+            return;
+        }
+        error!("[Node] ⛔ ASSET_DISTRIBUTION rejected: {err}");
+        self.audit.lock().await.append(
+            "", None, None::<String>,
+            "ASSET_REJECTED",
+            None, None, 0,
+            Some(json!({ "sha256": payload.get("sha256"), "name": payload.get("name"), "reason": err.to_string() })),
+        );
+        self.security_alert(json!({
+            "type": "ASSET_SIGNATURE_INVALID",
+            "nodeId": self.config.node_id,
+            "sha256": payload.get("sha256"),
+            "name": payload.get("name"),
+            "reason": err.to_string(),
+        })).await;
+    }
 
+    /// Log a refused RELEASE; a bad signature or a replay is a security alert.
+    async fn reject_release(&self, version: &str, err: &anyhow::Error) {
+        if crate::errors::classify(err) != ErrorCode::Auth {
+            warn!("[Node] RELEASE v{version} rejected: {err}");
+            return;
+        }
+        error!("[Node] ⛔ RELEASE rejected: {err}");
+        self.security_alert(json!({
+            "type": "RELEASE_SIGNATURE_INVALID",
+            "nodeId": self.config.node_id,
+            "version": version,
+            "reason": err.to_string(),
+        })).await;
+    }
+
+    /// Re-announce the capabilities after a probe changed them.
+    async fn announce_capabilities(
+        &self,
+        write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
+    ) -> Result<()> {
+        let change = self.svm.capabilities().last_change();
+        let frame = json!({
+            "type": "CAPABILITIES_UPDATE",
+            "payload": {
+                "nodeId": self.config.node_id,
+                "capabilities": self.build_capabilities(),
+                "added": change["added"],
+                "removed": change["removed"],
+            },
+        }).to_string();
+        self.shape(frame.len(), Priority::Interactive).await;
+        write.send(Message::Text(frame)).await?;
         Ok(())
     }
 
+    // ── Self-update ───────────────────────────────────────────────────────────
+
+    /// Apply a staged release once the node has been idle long enough, then
+    /// report self-update progress.
+    async fn tick_update(
+        &mut self,
+        write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
+    ) -> Result<()> {
+        let Some(updater) = self.updater.clone() else { return Ok(()) };
+        if updater.staged_version().is_some()
+            && self.queue.is_empty()
+            && self.last_slice_at.elapsed() >= updater.idle_window()
+            && !self.stopping()
+        {
+            if let Err(e) = updater.apply() {
+                error!("[Node] self-update not applied: {e}");
+            }
+        }
+        self.send_update_reports(write).await
+    }
+
+    /// Send queued UPDATE_STATUS frames, each also audited as NODE_UPDATE.
+    async fn send_update_reports(
+        &self,
+        write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
+    ) -> Result<()> {
+        let Some(updater) = &self.updater else { return Ok(()) };
+        for report in updater.take_reports() {
+            self.audit.lock().await.append(
+                "", None, None::<String>,
+                "NODE_UPDATE",
+                None, None, 0,
+                Some(report.clone()),
+            );
+            let frame = json!({ "type": "UPDATE_STATUS", "payload": report }).to_string();
+            self.shape(frame.len(), Priority::Interactive).await;
+            write.send(Message::Text(frame)).await?;
+        }
+        Ok(())
+    }
+
+    /// Raise IR_NOT_ALLOWLISTED for an artifact missing from the allow-list.
+    async fn report_unlisted(&self, workflow_id: &str, checksum: &str, enforced: bool) {
+        if enforced {
+            error!("[Node] ⛔ workflow={workflow_id}: artifact {checksum} is not on the IR allow-list — refused");
+        } else {
+            warn!("[Node] workflow={workflow_id}: artifact {checksum} is not on the IR allow-list (audit mode — running)");
+        }
+        self.audit.lock().await.append(
+            workflow_id, None, None::<String>,
+            "IR_NOT_ALLOWLISTED",
+            None, None, 0,
+            Some(json!({ "checksum": checksum, "enforced": enforced })),
+        );
+        self.security_alert(json!({
+            "type": "IR_NOT_ALLOWLISTED",
+            "nodeId": self.config.node_id,
+            "workflowId": workflow_id,
+            "checksum": checksum,
+            "enforced": enforced,
+        })).await;
+    }
+
+    /// Raise IR_DECODE_LIMIT_EXCEEDED if `err` is a decode-limit violation.
+    async fn report_limit_violation(&self, err: &anyhow::Error, workflow_id: &str) {
+        let Some(v) = err.downcast_ref::<LimitViolation>() else { return };
+        error!("[Node] ⛔ {v} — artifact rejected");
+        self.security_alert(json!({
+            "type": "IR_DECODE_LIMIT_EXCEEDED",
+            "nodeId": self.config.node_id,
+            "workflowId": workflow_id,
+            "limit": v.limit,
+            "actual": v.actual,
+            "max": v.max,
+        })).await;
+    }
+
+    /// Raise WS_FRAME_LIMIT_EXCEEDED for a frame from central over `v`.
+    async fn report_frame_violation(&self, v: &LimitViolation) {
+        error!("[Node] ⛔ {v} — frame from central rejected");
+        self.security_alert(json!({
+            "type": "WS_FRAME_LIMIT_EXCEEDED",
+            "nodeId": self.config.node_id,
+            "limit": v.limit,
+            "actual": v.actual,
+            "max": v.max,
+        })).await;
+    }
+
+    /// Turn a read error into the session's error, alerting on size limits.
+    async fn read_error(&self, err: WsError) -> anyhow::Error {
+        if let Some(v) = FrameLimits::from(&self.config).violation(&err) {
+            self.report_frame_violation(&v).await;
+        }
+        err.into()
+    }
+
+    // ── Clock drift ───────────────────────────────────────────────────────────
+
+    async fn update_clock_offset(&mut self, server_ms: i64, rtt_ms: Option<u64>, local_ms: i64) {
+        let offset = self.clock.record(server_ms, rtt_ms, local_ms);
+        let exceeded = offset.unsigned_abs() > self.config.clock_drift_warn_ms;
+        let was_exceeded = self.health.clock_drift_exceeded.load(std::sync::atomic::Ordering::Relaxed);
+        if exceeded && !was_exceeded {
+            warn!(
+                "[Node] clock drift: {offset} ms from central (threshold {} ms) — audit timestamps are skewed",
+                self.config.clock_drift_warn_ms
+            );
+        } else if !exceeded && was_exceeded {
+            info!("[Node] clock drift back within threshold ({offset} ms)");
+        }
+        self.health.set_clock_offset(offset, exceeded);
+        if self.config.audit_clock_offset {
+            self.audit.lock().await.set_clock_offset(Some(offset));
+        }
+    }
+
     // ── Misc ──────────────────────────────────────────────────────────────────
 
     fn build_capabilities(&self) -> Value {
         json!({
-            "opcodes": [
-                "LOAD_RESOURCE", "STORE_MEMORY",
-                "CALL_SERVICE", "CALL_ACTION", "CALL_MCP",
-                "LLM_CALL", "TRANSFORM", "VALIDATE",
-                "BRANCH", "LOOP", "JUMP", "RETURN",
-                "PARALLEL_SPAWN", "PARALLEL_MERGE",
-                "AGGREGATE", "FILTER"
-            ],
-            "serviceFormats": ["HTTP", "CONNECTOR", "MCP"],
+            "irVersion": {
+                "major": SVM_IR_FORMAT_VERSION_MAJOR,
+                "minor": crate::features::IR_FORMAT_VERSION_MINOR,
+            },
+            "features": self.svm.features(),
+            "peers": self.config.peers.keys().collect::<Vec<_>>(),
+            "opcodes": crate::features::supported_opcodes(),
+            "serviceFormats": crate::features::supported_formats().into_iter()
+                .filter(|f| self.svm.capabilities().contains(&format!("format:{f}")))
+                .map(String::from)
+                .chain(self.svm.plugins().formats())
+                .collect::<Vec<_>>(),
+            "plugins": self.svm.plugins().manifests(),
+            "assets": self.svm.assets().map(|a| a.cached()).unwrap_or_default(),
+            "probes": self.svm.capabilities().probes(),
             "aarch64": cfg!(target_arch = "aarch64"),
             "x86_64": cfg!(target_arch = "x86_64"),
         })
     }
 }
 
-// ── JSON-serialisable view of SliceExecutionResult ────────────────────────────
-
-#[derive(serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-struct ResultJson {
-    plan_id: String,
-    slice_id: String,
-    node_id: String,
-    status: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
-    error: String,
-    duration_ms: i32,
-    output_registers: std::collections::HashMap<String, String>,
+fn audit_event_proto(ev: crate::audit::AuditEvent) -> crate::proto::llmir::AuditEventProto {
+    let error_code = ev.details.as_ref()
+        .and_then(|d| d.get("code"))
+        .and_then(|c| c.as_str())
+        .filter(|_| ev.event_type == "SLICE_FAILED")
+        .unwrap_or_default()
+        .to_owned();
+    crate::proto::llmir::AuditEventProto {
+        event_id:            ev.event_id,
+        timestamp:           ev.timestamp,
+        node_id:             ev.node_id,
+        workflow_id:         ev.workflow_id,
+        workflow_version:    ev.workflow_version.unwrap_or(0) as i32,
+        instruction_id:      ev.instruction_id.unwrap_or_default(),
+        event_type:          ev.event_type,
+        input_hash:          ev.input_hash,
+        output_hash:         ev.output_hash,
+        duration_ms:         ev.duration_ms as i32,
+        previous_event_hash: ev.previous_event_hash,
+        self_hash:           ev.self_hash,
+        signature:           ev.signature,
+        tenant_id:           ev.tenant_id.unwrap_or_default(),
+        error_code,
+        clock_offset_ms:     ev.clock_offset_ms.unwrap_or_default(),
+        parent_slice:        ev.parent_slice.unwrap_or_default(),
+    }
 }
 
-impl From<&SliceExecutionResult> for ResultJson {
-    fn from(r: &SliceExecutionResult) -> Self {
-        Self {
-            plan_id: r.plan_id.clone(),
-            slice_id: r.slice_id.clone(),
-            node_id: r.node_id.clone(),
-            status: r.status.clone(),
-            error: r.error.clone(),
-            duration_ms: r.duration_ms,
-            output_registers: r.output_registers.iter()
-                .map(|(k, v)| (k.to_string(), v.clone()))
-                .collect(),
+/// Resolves once `shutdown` is true (never, if its sender is gone).
+async fn shutdown_requested(shutdown: &mut watch::Receiver<bool>) {
+    if shutdown.wait_for(|s| *s).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+// ── Signature verification ────────────────────────────────────────────────────
+
+/// Check `artifact`'s SHA-256 checksum, then its Ed25519 signature over the
+/// payload against the pinned `key` (spec §13.1).  An unsigned artifact, or
+/// any artifact while no key is pinned, passes only with `allow_unsigned`
+/// (SVM_ALLOW_UNSIGNED_IR, development).
+fn verify_artifact_signature(
+    artifact: &SignedIrArtifact,
+    key: Option<&VerifyingKey>,
+    allow_unsigned: bool,
+) -> Result<()> {
+    let actual_checksum = hex::encode(Sha256::digest(&artifact.payload));
+    if !artifact.payload_checksum.is_empty() && actual_checksum != artifact.payload_checksum {
+        return Err(anyhow!(
+            "IR artifact checksum mismatch: expected {} got {}",
+            artifact.payload_checksum,
+            actual_checksum
+        ));
+    }
+
+    if artifact.signature.is_empty() {
+        if !allow_unsigned {
+            return Err(anyhow!("IR artifact is not signed"));
         }
+        warn!("[Node] IR artifact has no signature — accepted (SVM_ALLOW_UNSIGNED_IR)");
+        return Ok(());
+    }
+    let Some(key) = key else {
+        if !allow_unsigned {
+            return Err(anyhow!("no IR signing key pinned (SVM_IR_PUBLIC_KEY or SVM_CONFIG_OPS_PUBLIC_KEY)"));
+        }
+        warn!("[Node] no IR signing key pinned — signature not checked (SVM_ALLOW_UNSIGNED_IR)");
+        return Ok(());
+    };
+    // The embedded key is informational; it must name the pinned one
+    if !artifact.public_key_pem.is_empty()
+        && crate::config_sig::verifying_key_from_str(&artifact.public_key_pem).ok().as_ref() != Some(key)
+    {
+        return Err(anyhow!("IR artifact is signed by a key other than the pinned one"));
+    }
+    let signature = Signature::from_slice(&artifact.signature)
+        .map_err(|_| anyhow!("malformed IR artifact signature"))?;
+    key.verify(&artifact.payload, &signature)
+        .map_err(|_| anyhow!("IR artifact signature does not match the pinned key"))
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    #[test]
+    fn test_artifact_signature_is_verified() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let pinned = key.verifying_key();
+        let payload = b"llm-ir".to_vec();
+        let signed = SignedIrArtifact {
+            version: 1,
+            signature: key.sign(&payload).to_bytes().to_vec(),
+            payload_checksum: hex::encode(Sha256::digest(&payload)),
+            public_key_pem: hex::encode(pinned.as_bytes()),
+            payload,
+            ..Default::default()
+        };
+        assert!(verify_artifact_signature(&signed, Some(&pinned), false).is_ok());
+
+        // Tampered payload, another signer, a wrong embedded key
+        let tampered = SignedIrArtifact { payload: b"llm-iR".to_vec(), payload_checksum: String::new(), ..signed.clone() };
+        assert!(verify_artifact_signature(&tampered, Some(&pinned), false).is_err());
+        let other = SigningKey::from_bytes(&[8; 32]).verifying_key();
+        assert!(verify_artifact_signature(&signed, Some(&other), false).is_err());
+        let claimed = SignedIrArtifact { public_key_pem: hex::encode(other.as_bytes()), ..signed.clone() };
+        assert!(verify_artifact_signature(&claimed, Some(&pinned), false).is_err());
+
+        // Unsigned, or signed with no key pinned: development mode only
+        let unsigned = SignedIrArtifact { signature: vec![], ..signed.clone() };
+        assert!(verify_artifact_signature(&unsigned, Some(&pinned), false).is_err());
+        assert!(verify_artifact_signature(&signed, None, false).is_err());
+        assert!(verify_artifact_signature(&unsigned, Some(&pinned), true).is_ok());
+        assert!(verify_artifact_signature(&signed, None, true).is_ok());
     }
 }
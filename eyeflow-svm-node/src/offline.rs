@@ -1,37 +1,92 @@
-/// Offline Buffer — spec §8.3
-///
-/// NDJSON-backed persistent queue that accumulates events when the central
-/// WebSocket connection is down.  On reconnect, the caller drains the buffer
-/// and, on successful delivery, removes the flushed entries from disk.
-///
-/// This mirrors the NestJS `OfflineBufferService` (295 lines) in Rust.
+//! Offline Buffer — spec §8.3
+//!
+//! NDJSON-backed persistent queue that accumulates events when the central
+//! WebSocket connection is down.  On reconnect, the caller drains the buffer
+//! and, on successful delivery, removes the flushed entries from disk.
+//!
+//! This mirrors the NestJS `OfflineBufferService` (295 lines) in Rust.
+//!
+//! Durability: the queue is also saved while nominally online — every
+//! OFFLINE_BUFFER_PERSIST_EVERY enqueued events (`persist_due`) and by the
+//! `run_autosave` task every OFFLINE_BUFFER_AUTOSAVE_SECS when dirty — so a
+//! power cut loses at most one interval / N events.
+//!
+//! Accounting is per tenant: a tenant over its own cap loses its oldest
+//! event, and when the whole buffer is full the tenant holding the most
+//! events is trimmed — one noisy tenant cannot evict everyone else's backlog.
+//!
+//! Replay: flushed events keep their original `enqueued_at` and carry
+//! `replayed: true`, and the flush opens with a `FlushHeader` giving the
+//! offline window, so central does not mistake a backlog for live traffic.
+//!
+//! Security alerts wait in a queue of their own (SVM_ALERT_QUEUE_MAX, oldest
+//! dropped first, outside the tenant caps) that is persisted ahead of the
+//! events and delivered as ALERT frames before any AUDIT_FLUSH — a full
+//! backlog never delays or evicts them.
+//!
+//! With a disk watchdog attached (disk_watch.rs), Kafka records and trigger
+//! fires are refused while the disk is under pressure.
+//!
+//! Startup load streams the file (bounded memory even for a buffer grown
+//! over a long outage), logs its progress, and cuts off a corrupted tail.
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tracing::{debug, info, warn};
 
 use crate::audit::AuditEvent;
+use crate::disk_watch::DiskWatch;
+use crate::tenant::DEFAULT_TENANT;
+
+/// Longest line `load` keeps; longer ones are skipped without being buffered.
+const MAX_LINE_BYTES: usize = 16 * 1024 * 1024;
+
+/// `load` logs its progress every this many bytes.
+const PROGRESS_EVERY_BYTES: u64 = 64 * 1024 * 1024;
 
 // ── Event envelope ────────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "kind", rename_all = "SCREAMING_SNAKE_CASE")]
+#[allow(clippy::large_enum_variant)]
 pub enum BufferedEvent {
     AuditEvent {
         payload: AuditEvent,
         enqueued_at: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        replayed: bool,
     },
     ExecutionResult {
         payload: serde_json::Value,
         enqueued_at: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        replayed: bool,
     },
     TriggerFire {
         payload: serde_json::Value,
         enqueued_at: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        replayed: bool,
+    },
+    /// Kafka record awaiting a reachable broker (connectors/kafka.rs)
+    KafkaRecord {
+        payload: serde_json::Value,
+        enqueued_at: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        replayed: bool,
+    },
+    /// Security alert awaiting an ALERT frame (alert queue)
+    SecurityAlert {
+        payload: serde_json::Value,
+        enqueued_at: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        replayed: bool,
     },
 }
 
@@ -40,26 +95,108 @@ impl BufferedEvent {
         chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
     }
 
+    #[allow(dead_code)]
     pub fn from_audit(ev: AuditEvent) -> Self {
-        Self::AuditEvent { payload: ev, enqueued_at: Self::timestamp() }
+        Self::AuditEvent { payload: ev, enqueued_at: Self::timestamp(), replayed: false }
     }
 
     pub fn from_execution(result: serde_json::Value) -> Self {
-        Self::ExecutionResult { payload: result, enqueued_at: Self::timestamp() }
+        Self::ExecutionResult { payload: result, enqueued_at: Self::timestamp(), replayed: false }
     }
 
+    #[allow(dead_code)]
     pub fn from_trigger(fire: serde_json::Value) -> Self {
-        Self::TriggerFire { payload: fire, enqueued_at: Self::timestamp() }
+        Self::TriggerFire { payload: fire, enqueued_at: Self::timestamp(), replayed: false }
+    }
+
+    pub fn from_kafka(record: serde_json::Value) -> Self {
+        Self::KafkaRecord { payload: record, enqueued_at: Self::timestamp(), replayed: false }
+    }
+
+    pub fn from_alert(alert: serde_json::Value) -> Self {
+        Self::SecurityAlert { payload: alert, enqueued_at: Self::timestamp(), replayed: false }
+    }
+
+    /// When the event was first buffered (RFC 3339, UTC).
+    pub fn enqueued_at(&self) -> &str {
+        match self {
+            Self::AuditEvent { enqueued_at, .. }
+            | Self::ExecutionResult { enqueued_at, .. }
+            | Self::TriggerFire { enqueued_at, .. }
+            | Self::KafkaRecord { enqueued_at, .. }
+            | Self::SecurityAlert { enqueued_at, .. } => enqueued_at,
+        }
+    }
+
+    fn set_replayed(&mut self, value: bool) {
+        match self {
+            Self::AuditEvent { replayed, .. }
+            | Self::ExecutionResult { replayed, .. }
+            | Self::TriggerFire { replayed, .. }
+            | Self::KafkaRecord { replayed, .. }
+            | Self::SecurityAlert { replayed, .. } => *replayed = value,
+        }
+    }
+
+    /// Mirrors and re-derivable events, the first refused when the disk fills.
+    fn is_low_priority(&self) -> bool {
+        matches!(self, Self::KafkaRecord { .. } | Self::TriggerFire { .. })
+    }
+
+    /// Tenant the event is accounted to.
+    pub fn tenant(&self) -> &str {
+        let tenant = match self {
+            Self::AuditEvent { payload, .. } => payload.tenant_id.as_deref(),
+            Self::ExecutionResult { payload, .. }
+            | Self::TriggerFire { payload, .. }
+            | Self::KafkaRecord { payload, .. }
+            | Self::SecurityAlert { payload, .. } => {
+                payload.get("tenantId").and_then(|v| v.as_str())
+            }
+        };
+        tenant.unwrap_or(DEFAULT_TENANT)
     }
 }
 
+/// Sent ahead of flushed events: how many, and the offline window they
+/// cover, so central can place them on its timeline.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FlushHeader {
+    pub count: usize,
+    /// When the connection dropped (None if unknown, e.g. after a restart)
+    pub offline_since: Option<String>,
+    /// When the connection came back and the flush started
+    pub online_at: String,
+    pub oldest_enqueued_at: Option<String>,
+    pub newest_enqueued_at: Option<String>,
+}
+
 // ── Buffer ────────────────────────────────────────────────────────────────────
 
 pub struct OfflineBuffer {
     queue: VecDeque<BufferedEvent>,
+    /// Security alerts, delivered ahead of `queue`
+    alerts: VecDeque<BufferedEvent>,
+    max_alerts: usize,
     path: PathBuf,
     max_size: usize,
     is_online: bool,
+    /// When connectivity was last lost (start of the offline window)
+    offline_since: Option<String>,
+    /// Events queued per tenant
+    tenant_counts: HashMap<String, usize>,
+    /// Per-tenant cap for tenants without an override (0 = none)
+    tenant_default_max: usize,
+    tenant_max: HashMap<String, usize>,
+    /// Queue changed since the last persist
+    dirty: bool,
+    /// Events enqueued since the last persist
+    unsaved: usize,
+    /// Persist after this many enqueued events (0 = interval only)
+    persist_every: usize,
+    /// Free-space watchdog; low-priority events are refused under pressure
+    disk: Option<Arc<DiskWatch>>,
 }
 
 impl OfflineBuffer {
@@ -68,18 +205,55 @@ impl OfflineBuffer {
     pub fn new(path: impl Into<PathBuf>, max_size: usize) -> Self {
         Self {
             queue: VecDeque::new(),
+            alerts: VecDeque::new(),
+            max_alerts: 1_000,
             path: path.into(),
             max_size,
             is_online: false,
+            offline_since: None,
+            tenant_counts: HashMap::new(),
+            tenant_default_max: 0,
+            tenant_max: HashMap::new(),
+            dirty: false,
+            unsaved: 0,
+            persist_every: 0,
+            disk: None,
         }
     }
 
+    /// Persist after every `n` enqueued events (0 = only on autosave / disconnect).
+    pub fn with_persist_every(mut self, n: usize) -> Self {
+        self.persist_every = n;
+        self
+    }
+
+    /// Keep at most `n` undelivered security alerts.
+    pub fn with_max_alerts(mut self, n: usize) -> Self {
+        self.max_alerts = n.max(1);
+        self
+    }
+
+    /// Refuse low-priority events while `disk` reports pressure.
+    pub fn with_disk_watch(mut self, disk: Option<Arc<DiskWatch>>) -> Self {
+        self.disk = disk;
+        self
+    }
+
+    /// Per-tenant caps (see `TenantGovernor::offline_limits`).
+    pub fn set_tenant_limits(&mut self, default_max: usize, per_tenant: HashMap<String, usize>) {
+        self.tenant_default_max = default_max;
+        self.tenant_max = per_tenant;
+    }
+
     // ── Connectivity notifications ────────────────────────────────────────────
 
     /// Signal connectivity change to the buffer.
     pub fn notify_connected(&mut self, online: bool) {
         if self.is_online != online {
             info!("[OfflineBuffer] connectivity → {}", if online { "ONLINE" } else { "OFFLINE" });
+            if !online {
+                self.offline_since = Some(BufferedEvent::timestamp());
+            }
         }
         self.is_online = online;
     }
@@ -90,6 +264,7 @@ impl OfflineBuffer {
 
     // ── Enqueue ───────────────────────────────────────────────────────────────
 
+    #[allow(dead_code)]
     pub fn enqueue_audit_event(&mut self, event: AuditEvent) {
         self.push(BufferedEvent::from_audit(event));
     }
@@ -98,31 +273,159 @@ impl OfflineBuffer {
         self.push(BufferedEvent::from_execution(result));
     }
 
-    pub fn enqueue_trigger_fire(&mut self, fire: serde_json::Value) {
-        self.push(BufferedEvent::from_trigger(fire));
+    /// False when refused under disk pressure.
+    #[allow(dead_code)]
+    pub fn enqueue_trigger_fire(&mut self, fire: serde_json::Value) -> bool {
+        self.push(BufferedEvent::from_trigger(fire))
+    }
+
+    /// False when refused under disk pressure.
+    pub fn enqueue_kafka_record(&mut self, record: serde_json::Value) -> bool {
+        self.push(BufferedEvent::from_kafka(record))
+    }
+
+    /// Queue a security alert until an ALERT frame carries it; raised while
+    /// offline it is marked `replayed`.
+    pub fn enqueue_alert(&mut self, alert: serde_json::Value) {
+        if self.alerts.len() >= self.max_alerts {
+            warn!("[OfflineBuffer] {} undelivered alerts — dropping the oldest", self.max_alerts);
+            self.alerts.pop_front();
+        }
+        let mut event = BufferedEvent::from_alert(alert);
+        event.set_replayed(!self.is_online);
+        self.alerts.push_back(event);
+        self.dirty = true;
+        self.unsaved += 1;
     }
 
-    fn push(&mut self, event: BufferedEvent) {
-        if self.queue.len() >= self.max_size {
+    fn push(&mut self, event: BufferedEvent) -> bool {
+        if let Some(disk) = self.disk.as_ref().filter(|d| d.under_pressure() && event.is_low_priority()) {
+            debug!("[OfflineBuffer] disk pressure — low-priority event refused");
+            disk.record_refused();
+            return false;
+        }
+        let tenant = event.tenant().to_owned();
+        let cap = self.tenant_max.get(&tenant).copied().unwrap_or(self.tenant_default_max);
+        if cap > 0 && self.tenant_count(&tenant) >= cap {
+            warn!("[OfflineBuffer] tenant={tenant} cap={cap} reached — dropping its oldest event");
+            self.evict_oldest_of(&tenant);
+        } else if self.queue.len() >= self.max_size {
+            // Trim the tenant holding the most events
+            let heaviest = self.tenant_counts.iter()
+                .max_by_key(|(_, n)| **n)
+                .map(|(t, _)| t.clone())
+                .unwrap_or_else(|| tenant.clone());
             warn!(
-                "[OfflineBuffer] max_size={} reached — dropping oldest event",
+                "[OfflineBuffer] max_size={} reached — dropping oldest event of tenant={heaviest}",
                 self.max_size
             );
-            self.queue.pop_front();
+            self.evict_oldest_of(&heaviest);
         }
         debug!("[OfflineBuffer] enqueued (queue_len={})", self.queue.len() + 1);
+        *self.tenant_counts.entry(tenant).or_default() += 1;
         self.queue.push_back(event);
+        self.dirty = true;
+        self.unsaved += 1;
+        true
+    }
+
+    /// True once `persist_every` events have been enqueued since the last save.
+    pub fn persist_due(&self) -> bool {
+        self.persist_every > 0 && self.unsaved >= self.persist_every
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn tenant_count(&self, tenant: &str) -> usize {
+        self.tenant_counts.get(tenant).copied().unwrap_or(0)
+    }
+
+    fn evict_oldest_of(&mut self, tenant: &str) {
+        if let Some(i) = self.queue.iter().position(|e| e.tenant() == tenant) {
+            self.queue.remove(i);
+            self.uncount(tenant);
+        }
+    }
+
+    fn uncount(&mut self, tenant: &str) {
+        if let Some(n) = self.tenant_counts.get_mut(tenant) {
+            *n -= 1;
+            if *n == 0 {
+                self.tenant_counts.remove(tenant);
+            }
+        }
+    }
+
+    /// Events currently queued, per tenant.
+    pub fn tenant_depths(&self) -> &HashMap<String, usize> {
+        &self.tenant_counts
     }
 
     // ── Drain / flush ─────────────────────────────────────────────────────────
 
-    /// Drain all queued events for flushing.  The caller is responsible for
-    /// calling `confirm_flushed(count)` after successful delivery.
-    pub fn drain_for_flush(&mut self) -> Vec<BufferedEvent> {
-        self.queue.drain(..).collect()
+    /// Drain all queued events for flushing, marked `replayed` and keeping
+    /// their original `enqueued_at`.  The caller is responsible for calling
+    /// `clear_disk()` after successful delivery, or `requeue` on failure.
+    pub fn drain_for_flush(&mut self) -> (FlushHeader, Vec<BufferedEvent>) {
+        let header = self.flush_header();
+        self.tenant_counts.clear();
+        self.dirty = true;
+        let mut events: Vec<BufferedEvent> = self.queue.drain(..).collect();
+        for ev in &mut events {
+            ev.set_replayed(true);
+        }
+        (header, events)
+    }
+
+    /// Header of a flush of the whole queue, which stays queued: a paced
+    /// flush copies `flush_part`s and `pop_front`s each once delivered, so
+    /// the backlog is still on disk if the node stops half way.  Undo with
+    /// `requeue(header, vec![])`.
+    pub fn flush_header(&mut self) -> FlushHeader {
+        FlushHeader {
+            count: self.queue.len(),
+            offline_since: self.offline_since.take(),
+            online_at: BufferedEvent::timestamp(),
+            oldest_enqueued_at: self.queue.iter().map(|e| e.enqueued_at()).min().map(str::to_owned),
+            newest_enqueued_at: self.queue.iter().map(|e| e.enqueued_at()).max().map(str::to_owned),
+        }
+    }
+
+    /// The `n` oldest events, marked `replayed`, left in the queue.
+    pub fn flush_part(&self, n: usize) -> Vec<BufferedEvent> {
+        self.queue.iter().take(n).cloned().map(|mut ev| {
+            ev.set_replayed(true);
+            ev
+        }).collect()
+    }
+
+    /// Put back a flush that could not be delivered, timestamps intact.
+    pub fn requeue(&mut self, header: FlushHeader, events: Vec<BufferedEvent>) {
+        self.offline_since = header.offline_since;
+        for mut ev in events {
+            ev.set_replayed(false);
+            self.push(ev);
+        }
+    }
+
+    /// Oldest queued event.
+    pub fn front(&self) -> Option<&BufferedEvent> {
+        self.queue.front()
+    }
+
+    /// Remove the oldest event once it has been delivered (in-order
+    /// consumers such as the Kafka sink).
+    pub fn pop_front(&mut self) -> Option<BufferedEvent> {
+        let event = self.queue.pop_front()?;
+        self.uncount(event.tenant());
+        self.dirty = true;
+        Some(event)
     }
 
     /// Return a snapshot without consuming the queue.
+    #[allow(dead_code)]
     pub fn snapshot(&self) -> Vec<&BufferedEvent> {
         self.queue.iter().collect()
     }
@@ -135,16 +438,53 @@ impl OfflineBuffer {
         self.queue.is_empty()
     }
 
+    /// Execution results among the queued events.
+    pub fn execution_results(&self) -> usize {
+        self.queue.iter().filter(|e| matches!(e, BufferedEvent::ExecutionResult { .. })).count()
+    }
+
+    // ── Alert queue ───────────────────────────────────────────────────────────
+
+    /// The `n` oldest undelivered alerts, left queued until `pop_alerts`.
+    pub fn alert_batch(&self, n: usize) -> Vec<serde_json::Value> {
+        self.alerts.iter().take(n).filter_map(|ev| match ev {
+            BufferedEvent::SecurityAlert { payload, enqueued_at, replayed } => {
+                let mut alert = payload.clone();
+                if let Some(obj) = alert.as_object_mut() {
+                    obj.insert("raisedAt".into(), enqueued_at.clone().into());
+                    obj.insert("replayed".into(), (*replayed).into());
+                }
+                Some(alert)
+            }
+            _ => None,
+        }).collect()
+    }
+
+    /// Drop the `n` oldest alerts once their ALERT frame was sent.
+    pub fn pop_alerts(&mut self, n: usize) {
+        let n = n.min(self.alerts.len());
+        self.alerts.drain(..n);
+        if n > 0 {
+            self.dirty = true;
+        }
+    }
+
+    #[cfg(test)]
+    pub fn alert_len(&self) -> usize {
+        self.alerts.len()
+    }
+
     // ── Persistence ───────────────────────────────────────────────────────────
 
     /// Persist the entire queue to an NDJSON file (one JSON object per line).
     /// Atomically replaces the existing file to avoid corruption.
-    pub async fn persist(&self) -> Result<()> {
-        if self.queue.is_empty() {
+    pub async fn persist(&mut self) -> Result<()> {
+        if self.queue.is_empty() && self.alerts.is_empty() {
             // Truncate file if queue emptied
             if self.path.exists() {
                 fs::write(&self.path, b"").await?;
             }
+            self.mark_saved();
             return Ok(());
         }
 
@@ -157,7 +497,7 @@ impl OfflineBuffer {
             .open(&tmp)
             .await?;
 
-        for event in &self.queue {
+        for event in self.alerts.iter().chain(&self.queue) {
             let mut line = serde_json::to_string(event)?;
             line.push('\n');
             file.write_all(line.as_bytes()).await?;
@@ -165,25 +505,64 @@ impl OfflineBuffer {
         file.flush().await?;
         drop(file);
         fs::rename(&tmp, &self.path).await?;
+        self.mark_saved();
 
-        info!("[OfflineBuffer] persisted {} events to {:?}", self.queue.len(), self.path);
+        debug!(
+            "[OfflineBuffer] persisted {} events and {} alerts to {:?}",
+            self.queue.len(), self.alerts.len(), self.path
+        );
         Ok(())
     }
 
+    fn mark_saved(&mut self) {
+        self.dirty = false;
+        self.unsaved = 0;
+    }
+
     /// Load queue from NDJSON file (called on startup to restore state after crash).
+    ///
+    /// The file is streamed line by line, so memory stays at one line (at
+    /// most MAX_LINE_BYTES) on top of the events kept.  Unreadable lines are
+    /// skipped; when they run to the end of the file — a write cut short by
+    /// a power loss — the file is truncated after the last good line.
     pub async fn load(&mut self) -> Result<usize> {
         if !self.path.exists() {
             return Ok(0);
         }
-        let content = fs::read_to_string(&self.path).await?;
-        let mut count = 0usize;
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() {
+        let file = fs::File::open(&self.path).await?;
+        let total = file.metadata().await?.len();
+        let mut reader = BufReader::with_capacity(64 * 1024, file);
+        let mut line = Vec::new();
+        let (mut count, mut offset, mut good_end, mut next_progress) = (0usize, 0u64, 0u64, PROGRESS_EVERY_BYTES);
+        let mut truncated = false;
+        while let Some((read, complete)) = read_line_bounded(&mut reader, &mut line, MAX_LINE_BYTES).await? {
+            offset += read as u64;
+            if offset >= next_progress {
+                info!("[OfflineBuffer] loading {:?}: {} / {} MiB, {count} events", self.path, offset >> 20, total >> 20);
+                next_progress += PROGRESS_EVERY_BYTES;
+            }
+            let text = line.trim_ascii();
+            if text.is_empty() {
+                if complete {
+                    good_end = offset;
+                }
+                continue;
+            }
+            if !complete {
+                warn!("[OfflineBuffer] Skipping line over {MAX_LINE_BYTES} bytes at offset {}", offset - read as u64);
                 continue;
             }
-            match serde_json::from_str::<BufferedEvent>(line) {
+            match crate::fastjson::from_slice::<BufferedEvent>(text) {
+                Ok(event @ BufferedEvent::SecurityAlert { .. }) => {
+                    good_end = offset;
+                    if self.alerts.len() < self.max_alerts {
+                        self.alerts.push_back(event);
+                    }
+                    continue;
+                }
                 Ok(event) => {
+                    good_end = offset;
+                    *self.tenant_counts.entry(event.tenant().to_owned()).or_default() += 1;
                     self.queue.push_back(event);
                     count += 1;
                 }
@@ -193,24 +572,93 @@ impl OfflineBuffer {
             }
             if self.queue.len() >= self.max_size {
                 warn!("[OfflineBuffer] max_size reached during load — truncating");
+                truncated = true;
                 break;
             }
         }
-        info!("[OfflineBuffer] loaded {} events from {:?}", count, self.path);
+        if !truncated && good_end < offset {
+            warn!(
+                "[OfflineBuffer] corrupted tail of {} bytes in {:?} — cutting the file after the last good line",
+                offset - good_end, self.path
+            );
+            let file = fs::OpenOptions::new().write(true).open(&self.path).await?;
+            file.set_len(good_end).await?;
+        }
+        info!("[OfflineBuffer] loaded {} events and {} alerts from {:?}", count, self.alerts.len(), self.path);
         Ok(count)
     }
 
-    /// Delete the persistence file (after confirmed delivery).
-    pub async fn clear_disk(&self) -> Result<()> {
+    /// Delete the persistence file (after confirmed delivery).  Alerts still
+    /// queued leave the buffer dirty so the next save writes them back.
+    pub async fn clear_disk(&mut self) -> Result<()> {
         if self.path.exists() {
             fs::remove_file(&self.path).await?;
         }
+        if self.queue.is_empty() && self.alerts.is_empty() {
+            self.mark_saved();
+        } else if !self.alerts.is_empty() {
+            self.dirty = true;
+        }
         Ok(())
     }
 }
 
+// ── Autosave ──────────────────────────────────────────────────────────────────
+
+/// Persist `buffer` every `interval_secs` while it has unsaved changes.
+pub async fn run_autosave(buffer: Arc<tokio::sync::Mutex<OfflineBuffer>>, interval_secs: u64) {
+    if interval_secs == 0 {
+        info!("[OfflineBuffer] periodic autosave disabled");
+        return;
+    }
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+    loop {
+        ticker.tick().await;
+        let mut buf = buffer.lock().await;
+        if buf.is_dirty() {
+            if let Err(e) = buf.persist().await {
+                warn!("[OfflineBuffer] autosave failed: {e}");
+            }
+        }
+    }
+}
+
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
+/// Read the next line (newline included) into `line`, keeping at most
+/// `max` bytes of it.  Returns the bytes consumed and whether the line was
+/// kept whole (ends in a newline, or at end of file, within `max`), or None
+/// at end of file.
+async fn read_line_bounded<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    line: &mut Vec<u8>,
+    max: usize,
+) -> std::io::Result<Option<(usize, bool)>> {
+    line.clear();
+    let (mut read, mut overflow) = (0usize, false);
+    loop {
+        let chunk = reader.fill_buf().await?;
+        if chunk.is_empty() {
+            return Ok((read > 0).then_some((read, !overflow)));
+        }
+        let (take, done) = match chunk.iter().position(|&b| b == b'\n') {
+            Some(i) => (i + 1, true),
+            None => (chunk.len(), false),
+        };
+        if !overflow && line.len() + take <= max {
+            line.extend_from_slice(&chunk[..take]);
+        } else if !overflow {
+            overflow = true;
+            line.clear();
+        }
+        reader.consume(take);
+        read += take;
+        if done {
+            return Ok(Some((read, !overflow)));
+        }
+    }
+}
+
 /// Ensure the parent directory for `path` exists.
 pub async fn ensure_parent(path: &Path) -> Result<()> {
     if let Some(parent) = path.parent() {
@@ -220,3 +668,147 @@ pub async fn ensure_parent(path: &Path) -> Result<()> {
     }
     Ok(())
 }
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result(tenant: &str, n: u32) -> serde_json::Value {
+        json!({ "tenantId": tenant, "n": n })
+    }
+
+    fn tenants(buf: &OfflineBuffer) -> Vec<String> {
+        buf.snapshot().iter().map(|e| e.tenant().to_owned()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_dirty_tracking() {
+        let path = std::env::temp_dir().join(format!("eyeflow_offline_{}.ndjson", uuid::Uuid::new_v4()));
+        let mut buf = OfflineBuffer::new(&path, 10).with_persist_every(2);
+        assert!(!buf.is_dirty());
+
+        buf.enqueue_execution_result(result("t", 0));
+        assert!(buf.is_dirty() && !buf.persist_due());
+        buf.enqueue_execution_result(result("t", 1));
+        assert!(buf.persist_due());
+
+        buf.persist().await.unwrap();
+        assert!(!buf.is_dirty() && !buf.persist_due());
+        let mut restored = OfflineBuffer::new(&path, 10);
+        assert_eq!(restored.load().await.unwrap(), 2);
+
+        buf.drain_for_flush();
+        assert!(buf.is_dirty());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_flush_keeps_original_timestamps() {
+        let mut buf = OfflineBuffer::new("/nonexistent/offline.ndjson", 10);
+        buf.notify_connected(true);
+        buf.notify_connected(false);
+        buf.enqueue_trigger_fire(json!({ "triggerId": "t1" }));
+        std::thread::sleep(Duration::from_millis(5));
+        buf.enqueue_execution_result(result("t", 0));
+        let first = buf.snapshot()[0].enqueued_at().to_owned();
+        buf.notify_connected(true);
+
+        let (header, events) = buf.drain_for_flush();
+        assert_eq!(header.count, 2);
+        assert!(header.offline_since.as_deref().unwrap() <= first.as_str());
+        assert_eq!(header.oldest_enqueued_at.as_deref(), Some(first.as_str()));
+        assert!(header.newest_enqueued_at.as_deref().unwrap() > first.as_str());
+        let wire = serde_json::to_value(&events).unwrap();
+        assert_eq!(wire[0]["kind"], "TRIGGER_FIRE");
+        assert_eq!(wire[0]["enqueued_at"], first);
+        assert_eq!(wire[0]["replayed"], true);
+
+        // A failed flush goes back unchanged and is replayed again later
+        buf.requeue(header.clone(), events);
+        assert_eq!(buf.snapshot()[0].enqueued_at(), first);
+        let (again, _) = buf.drain_for_flush();
+        assert_eq!(again.offline_since, header.offline_since);
+        assert_eq!(again.oldest_enqueued_at, header.oldest_enqueued_at);
+    }
+
+    #[test]
+    fn test_per_tenant_eviction() {
+        let mut buf = OfflineBuffer::new("/nonexistent/offline.ndjson", 4);
+        buf.set_tenant_limits(0, HashMap::from([("noisy".to_owned(), 2)]));
+
+        for n in 0..5 {
+            buf.enqueue_execution_result(result("noisy", n));
+        }
+        // noisy is held to its own cap of 2
+        assert_eq!(tenants(&buf), ["noisy", "noisy"]);
+
+        buf.enqueue_execution_result(result("quiet", 0));
+        buf.enqueue_trigger_fire(json!({}));
+        // Buffer full: the heaviest tenant (noisy) pays, not the newcomer
+        buf.enqueue_execution_result(result("quiet", 1));
+        assert_eq!(tenants(&buf), ["noisy", "quiet", DEFAULT_TENANT, "quiet"]);
+        assert_eq!(buf.tenant_depths()["quiet"], 2);
+
+        buf.drain_for_flush();
+        assert!(buf.tenant_depths().is_empty());
+    }
+    #[tokio::test]
+    async fn test_alert_queue_survives_and_skips_flush() {
+        let path = std::env::temp_dir().join(format!("eyeflow_offline_{}.ndjson", uuid::Uuid::new_v4()));
+        let mut buf = OfflineBuffer::new(&path, 1).with_max_alerts(2);
+        buf.enqueue_execution_result(result("t", 0));
+        for n in 0..3 {
+            buf.enqueue_alert(json!({ "type": "IR_VERSION_INCOMPATIBLE", "n": n }));
+        }
+        // Capped on its own, oldest dropped; a full event queue does not touch it
+        buf.enqueue_execution_result(result("t", 1));
+        assert_eq!((buf.len(), buf.alert_len()), (1, 2));
+
+        buf.persist().await.unwrap();
+        let mut restored = OfflineBuffer::new(&path, 10);
+        assert_eq!(restored.load().await.unwrap(), 1);
+        let batch = restored.alert_batch(10);
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0]["n"], 1);
+        assert_eq!(batch[0]["replayed"], true);
+        assert!(batch[0]["raisedAt"].is_string());
+
+        // The AUDIT_FLUSH leaves alerts queued, and on disk after clear_disk
+        let (header, _) = restored.drain_for_flush();
+        assert_eq!(header.count, 1);
+        restored.clear_disk().await.unwrap();
+        assert!(restored.is_dirty());
+        restored.pop_alerts(1);
+        assert_eq!(restored.alert_batch(10)[0]["n"], 2);
+        restored.notify_connected(true);
+        restored.enqueue_alert(json!({ "n": 3 }));
+        assert_eq!(restored.alert_batch(10)[1]["replayed"], false);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_load_cuts_corrupted_tail() {
+        let path = std::env::temp_dir().join(format!("eyeflow_offline_{}.ndjson", uuid::Uuid::new_v4()));
+        let line = |n| serde_json::to_string(&BufferedEvent::from_execution(result("t", n))).unwrap() + "\n";
+        let good = format!("{}not json\n\n{}", line(0), line(1));
+        // A persist cut short mid-line, then a partial second write
+        std::fs::write(&path, format!("{good}{}\n{{\"kind\":", &line(2)[..20])).unwrap();
+
+        let mut buf = OfflineBuffer::new(&path, 10);
+        assert_eq!(buf.load().await.unwrap(), 2);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), good);
+
+        // Over-long lines are consumed without being kept
+        let mut reader = BufReader::new(&b"0123456789\nabc"[..]);
+        let mut out = Vec::new();
+        assert_eq!(read_line_bounded(&mut reader, &mut out, 4).await.unwrap(), Some((11, false)));
+        assert!(out.is_empty());
+        assert_eq!(read_line_bounded(&mut reader, &mut out, 4).await.unwrap(), Some((3, true)));
+        assert_eq!(out, b"abc");
+        assert_eq!(read_line_bounded(&mut reader, &mut out, 4).await.unwrap(), None);
+        let _ = std::fs::remove_file(path);
+    }
+}
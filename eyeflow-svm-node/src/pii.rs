@@ -0,0 +1,272 @@
+//! PII masking — before audit hashing and LLM dispatch
+//!
+//! Payloads flowing through a workflow often carry personal data that has
+//! no business leaving the gateway.  A masking policy rewrites it before an
+//! instruction's input/output is hashed into its audit event and before an
+//! LLM_CALL payload (userIntent and runtime dynamic slots) is forwarded to
+//! the LLM service:
+//!
+//!   email    e-mail addresses inside strings          → "[EMAIL]"
+//!   card     Luhn-valid 13–19 digit card numbers
+//!            (strings, "4111 1111 …" or bare numbers) → "[CARD]"
+//!   paths    fields by JSONPath — "$.customer.ssn", "items[*].iban",
+//!            "contact.*" — whatever their content    → "[REDACTED]"
+//!
+//! The node default comes from SVM_PII_MASK (comma list of "email", "card")
+//! and SVM_PII_PATHS (comma list of paths); both empty = no masking.  A
+//! workflow overrides it with `WorkflowMetadata.pii_policy`:
+//!
+//!   { "patterns": ["email", "card"], "paths": ["$.patient.id"],
+//!     "scope": ["audit", "llm"] }                  (scope default: both)
+//!   "off"                                          no masking at all
+//!
+//! Registers keep the real values; only what is hashed or sent is masked.
+//! Audit events record how many fields were masked as `details.piiMasked`,
+//! LLM_CALL events the count sent masked as `details.llmPiiMasked`.
+
+use regex_lite::Regex;
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::config::Config;
+
+const EMAIL: &str = "[EMAIL]";
+const CARD: &str = "[CARD]";
+const REDACTED: &str = "[REDACTED]";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    /// `*` / `[*]` — every field or item
+    Any,
+}
+
+#[derive(Debug, Clone)]
+pub struct PiiPolicy {
+    email: Option<Regex>,
+    card: Option<Regex>,
+    paths: Vec<Vec<Segment>>,
+    /// Mask what is hashed into audit events
+    pub audit: bool,
+    /// Mask LLM_CALL payloads
+    pub llm: bool,
+}
+
+impl PiiPolicy {
+    fn build(patterns: &[&str], paths: &[&str], audit: bool, llm: bool) -> Result<Self, String> {
+        let mut policy = Self { email: None, card: None, paths: Vec::new(), audit, llm };
+        for p in patterns {
+            match p.trim() {
+                "email" => policy.email = Some(Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}").unwrap()),
+                "card" => policy.card = Some(Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").unwrap()),
+                "" => {}
+                other => return Err(format!("unknown PII pattern '{other}' (expected email, card)")),
+            }
+        }
+        policy.paths = paths.iter()
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .map(parse_path)
+            .collect::<Result<_, _>>()?;
+        Ok(policy)
+    }
+
+    /// Node default from SVM_PII_MASK / SVM_PII_PATHS (None = no masking).
+    pub fn from_config(config: &Config) -> Result<Option<Arc<Self>>, String> {
+        let patterns: Vec<&str> = config.pii_mask.iter().map(String::as_str).collect();
+        let paths: Vec<&str> = config.pii_paths.iter().map(String::as_str).collect();
+        let policy = Self::build(&patterns, &paths, true, true)?;
+        Ok((!policy.is_empty()).then(|| Arc::new(policy)))
+    }
+
+    /// Parse a workflow's `pii_policy` (None = inherit the node default).
+    /// `"off"` yields an empty policy, which disables masking.
+    pub fn parse(json: &str) -> Result<Option<Self>, String> {
+        let json = json.trim();
+        if json.is_empty() {
+            return Ok(None);
+        }
+        if json == "off" || json == "\"off\"" {
+            return Self::build(&[], &[], false, false).map(Some);
+        }
+        let spec: Value = serde_json::from_str(json).map_err(|e| format!("pii_policy is not JSON: {e}"))?;
+        let list = |name: &str| -> Result<Vec<&str>, String> {
+            match spec.get(name) {
+                None => Ok(Vec::new()),
+                Some(Value::Array(items)) => items.iter()
+                    .map(|v| v.as_str().ok_or_else(|| format!("pii_policy.{name} must list strings")))
+                    .collect(),
+                Some(_) => Err(format!("pii_policy.{name} must be an array")),
+            }
+        };
+        let scope = list("scope")?;
+        for s in &scope {
+            if !matches!(*s, "audit" | "llm") {
+                return Err(format!("unknown pii_policy scope '{s}' (expected audit, llm)"));
+            }
+        }
+        let (audit, llm) = match scope.is_empty() && spec.get("scope").is_none() {
+            true => (true, true),
+            false => (scope.contains(&"audit"), scope.contains(&"llm")),
+        };
+        Self::build(&list("patterns")?, &list("paths")?, audit, llm).map(Some)
+    }
+
+    /// True when the policy masks nothing.
+    pub fn is_empty(&self) -> bool {
+        (self.email.is_none() && self.card.is_none() && self.paths.is_empty()) || !(self.audit || self.llm)
+    }
+
+    /// Masked copy of `value` and the number of fields masked.
+    pub fn mask(&self, value: &Value) -> (Value, u32) {
+        let mut out = value.clone();
+        let mut count = 0;
+        for path in &self.paths {
+            redact(&mut out, path, &mut count);
+        }
+        if self.email.is_some() || self.card.is_some() {
+            self.scan(&mut out, &mut count);
+        }
+        (out, count)
+    }
+
+    /// Replace e-mail addresses and card numbers in every leaf of `value`.
+    fn scan(&self, value: &mut Value, count: &mut u32) {
+        match value {
+            Value::String(s) if s != REDACTED => {
+                let mut text = s.clone();
+                if let Some(re) = &self.email {
+                    text = re.replace_all(&text, EMAIL).into_owned();
+                }
+                if let Some(re) = &self.card {
+                    text = re.replace_all(&text, |c: &regex_lite::Captures| {
+                        if luhn(&c[0]) { CARD.to_owned() } else { c[0].to_owned() }
+                    }).into_owned();
+                }
+                if text != *s {
+                    *s = text;
+                    *count += 1;
+                }
+            }
+            Value::Number(n) if self.card.is_some() => {
+                let digits = n.to_string();
+                if (13..=19).contains(&digits.len()) && digits.bytes().all(|b| b.is_ascii_digit()) && luhn(&digits) {
+                    *value = Value::String(CARD.into());
+                    *count += 1;
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|v| self.scan(v, count)),
+            Value::Object(map) => map.values_mut().for_each(|v| self.scan(v, count)),
+            _ => {}
+        }
+    }
+}
+
+/// `$.a.b[0]`, `a.b[*].c`, `a.*` → segments.
+fn parse_path(path: &str) -> Result<Vec<Segment>, String> {
+    let bad = || format!("invalid PII path '{path}'");
+    let body = path.strip_prefix('$').unwrap_or(path);
+    let body = body.strip_prefix('.').unwrap_or(body);
+    let mut segments = Vec::new();
+    for part in body.split('.') {
+        let (key, mut rest) = part.split_at(part.find('[').unwrap_or(part.len()));
+        match key {
+            "" if rest.is_empty() => return Err(bad()),
+            "" => {}
+            "*" => segments.push(Segment::Any),
+            k => segments.push(Segment::Key(k.to_owned())),
+        }
+        while !rest.is_empty() {
+            let end = rest.find(']').ok_or_else(bad)?;
+            segments.push(match &rest[1..end] {
+                "*" => Segment::Any,
+                i => Segment::Index(i.parse().map_err(|_| bad())?),
+            });
+            rest = &rest[end + 1..];
+            if !rest.is_empty() && !rest.starts_with('[') {
+                return Err(bad());
+            }
+        }
+    }
+    if segments.is_empty() { Err(bad()) } else { Ok(segments) }
+}
+
+/// Replace every non-null field matched by `path` with "[REDACTED]".
+fn redact(value: &mut Value, path: &[Segment], count: &mut u32) {
+    let Some((head, rest)) = path.split_first() else {
+        if !value.is_null() && value.as_str() != Some(REDACTED) {
+            *value = Value::String(REDACTED.into());
+            *count += 1;
+        }
+        return;
+    };
+    match (head, value) {
+        (Segment::Key(k), Value::Object(map)) => {
+            if let Some(v) = map.get_mut(k) {
+                redact(v, rest, count);
+            }
+        }
+        (Segment::Index(i), Value::Array(items)) => {
+            if let Some(v) = items.get_mut(*i) {
+                redact(v, rest, count);
+            }
+        }
+        (Segment::Any, Value::Object(map)) => map.values_mut().for_each(|v| redact(v, rest, count)),
+        (Segment::Any, Value::Array(items)) => items.iter_mut().for_each(|v| redact(v, rest, count)),
+        _ => {}
+    }
+}
+
+/// Luhn checksum of the digits in `s`.
+fn luhn(s: &str) -> bool {
+    let digits: Vec<u32> = s.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits.iter().rev().enumerate()
+        .map(|(i, &d)| if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_masks_patterns_and_paths() {
+        let policy = PiiPolicy::parse(r#"{
+            "patterns": ["email", "card"],
+            "paths": ["$.customer.ssn", "items[*].iban"]
+        }"#).unwrap().unwrap();
+        let (masked, n) = policy.mask(&json!({
+            "note": "contact jane.doe@example.com about order 42",
+            "customer": { "ssn": "123-45-6789", "phone": null },
+            "payment": { "card": "4111 1111 1111 1111", "raw": 4111111111111111u64, "ref": "1234567890123" },
+            "items": [{ "iban": "DE89370400440532013000" }, { "iban": "FR7630006000011234567890189" }],
+        }));
+        assert_eq!(masked, json!({
+            "note": "contact [EMAIL] about order 42",
+            "customer": { "ssn": "[REDACTED]", "phone": null },
+            "payment": { "card": "[CARD]", "raw": "[CARD]", "ref": "1234567890123" },
+            "items": [{ "iban": "[REDACTED]" }, { "iban": "[REDACTED]" }],
+        }));
+        assert_eq!(n, 6);
+        assert!(policy.audit && policy.llm);
+    }
+
+    #[test]
+    fn test_policy_parsing() {
+        assert!(PiiPolicy::parse("").unwrap().is_none());
+        assert!(PiiPolicy::parse("off").unwrap().unwrap().is_empty());
+        let audit_only = PiiPolicy::parse(r#"{"patterns":["email"],"scope":["audit"]}"#).unwrap().unwrap();
+        assert!(audit_only.audit && !audit_only.llm);
+        assert!(PiiPolicy::parse(r#"{"patterns":["ssn"]}"#).is_err());
+        assert!(PiiPolicy::parse(r#"{"paths":["a[x]"]}"#).is_err());
+        assert_eq!(
+            parse_path("$.a[*].b[2]").unwrap(),
+            vec![Segment::Key("a".into()), Segment::Any, Segment::Key("b".into()), Segment::Index(2)],
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Placement constraints — validity window and node selector (WorkflowMetadata)
+//!
+//! An artifact may be valid only for a time window and only on some nodes:
+//!
+//!   valid_from / valid_until   ISO 8601; empty = unbounded
+//!   node_selector              label → required value ("*" = label present)
+//!
+//! Node labels come from SVM_NODE_LABELS ("site=plant-a,zone=3,gpu=true")
+//! and are advertised in REGISTER.  The window is checked against central's
+//! clock (local time corrected by the measured offset, see clock.rs).  A
+//! refused artifact is not executed and gets a distinct status so central
+//! can reschedule it:
+//!
+//!   NOT_YET_VALID   before valid_from
+//!   EXPIRED         after valid_until
+//!   NODE_MISMATCH   a selector label is missing or has another value
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::errors::ErrorCode;
+use crate::proto::llmir::WorkflowMetadata;
+
+/// Parse "k=v,k2=v2" (whitespace tolerated, empty entries ignored).
+pub fn parse_labels(s: &str) -> BTreeMap<String, String> {
+    s.split(',')
+        .filter_map(|kv| {
+            let (k, v) = kv.split_once('=')?;
+            Some((k.trim().to_owned(), v.trim().to_owned()))
+        })
+        .filter(|(k, _)| !k.is_empty())
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Refusal {
+    #[serde(skip)]
+    pub status: &'static str,
+    pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl Refusal {
+    fn new(status: &'static str, reason: String) -> Self {
+        Self { status, reason, valid_from: None, valid_until: None, label: None }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self.status {
+            "NODE_MISMATCH" => ErrorCode::Unsupported,
+            _ => ErrorCode::Validation,
+        }
+    }
+}
+
+fn parse_time(field: &str, value: &str) -> Result<Option<DateTime<Utc>>, Refusal> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| Some(t.with_timezone(&Utc)))
+        .map_err(|e| Refusal::new("VALIDATION_FAILED", format!("{field} '{value}' is not ISO 8601: {e}")))
+}
+
+/// Whether `meta` may run on a node with `labels` at central time `now`.
+pub fn check(
+    meta: &WorkflowMetadata,
+    labels: &BTreeMap<String, String>,
+    now: DateTime<Utc>,
+) -> Result<(), Refusal> {
+    let from = parse_time("valid_from", &meta.valid_from)?;
+    let until = parse_time("valid_until", &meta.valid_until)?;
+    if from.is_some_and(|f| now < f) {
+        return Err(Refusal {
+            valid_from: Some(meta.valid_from.clone()),
+            ..Refusal::new("NOT_YET_VALID", format!("artifact is valid from {}", meta.valid_from))
+        });
+    }
+    if until.is_some_and(|u| now >= u) {
+        return Err(Refusal {
+            valid_until: Some(meta.valid_until.clone()),
+            ..Refusal::new("EXPIRED", format!("artifact expired at {}", meta.valid_until))
+        });
+    }
+
+    // BTreeMap order keeps the reported label deterministic
+    let selector: BTreeMap<_, _> = meta.node_selector.iter().collect();
+    for (label, want) in selector {
+        let ok = match labels.get(label) {
+            Some(have) => want == "*" || have == want,
+            None => false,
+        };
+        if !ok {
+            let have = labels.get(label).map_or("<unset>", String::as_str);
+            return Err(Refusal {
+                label: Some(label.clone()),
+                ..Refusal::new("NODE_MISMATCH", format!("node label {label}={have}, artifact requires {want}"))
+            });
+        }
+    }
+    Ok(())
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_and_selector() {
+        let labels = parse_labels(" site=plant-a, zone=3,,gpu=true");
+        assert_eq!(labels.len(), 3);
+        let now = DateTime::parse_from_rfc3339("2026-03-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        let meta = |from: &str, until: &str, sel: &[(&str, &str)]| WorkflowMetadata {
+            valid_from: from.into(),
+            valid_until: until.into(),
+            node_selector: sel.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            ..Default::default()
+        };
+
+        assert!(check(&meta("", "", &[]), &labels, now).is_ok());
+        assert!(check(&meta("2026-03-01T00:00:00Z", "2026-03-02T00:00:00+01:00", &[("site", "plant-a"), ("gpu", "*")]), &labels, now).is_ok());
+        assert_eq!(check(&meta("2026-03-01T13:00:00Z", "", &[]), &labels, now).unwrap_err().status, "NOT_YET_VALID");
+        assert_eq!(check(&meta("", "2026-03-01T12:00:00Z", &[]), &labels, now).unwrap_err().status, "EXPIRED");
+        assert_eq!(check(&meta("", "yesterday", &[]), &labels, now).unwrap_err().status, "VALIDATION_FAILED");
+
+        let r = check(&meta("", "", &[("site", "plant-b")]), &labels, now).unwrap_err();
+        assert_eq!((r.status, r.label.as_deref()), ("NODE_MISMATCH", Some("site")));
+        assert_eq!(check(&meta("", "", &[("arch", "*")]), &labels, now).unwrap_err().code(), ErrorCode::Unsupported);
+    }
+}
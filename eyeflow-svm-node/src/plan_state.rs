@@ -0,0 +1,162 @@
+//! Plan-scoped shared state — KV context shared by the slices of one plan
+//!
+//! Slices of the same plan executed on this node can hand intermediate values
+//! to each other without a round trip to central:
+//!
+//!   STORE_MEMORY   operands_json = { "planKey": "batch_totals" }
+//!                  → src[0] is written to the register AND to the plan context
+//!   LOAD_RESOURCE  operands_json = { "planKey": "batch_totals" }
+//!                  → the stored value (or null) is loaded into dest
+//!
+//! Contexts are keyed by plan id (the workflow id carried in IR metadata) and
+//! expire SVM_PLAN_STATE_TTL_SECS after their last write.  When
+//! SVM_PLAN_STATE_PATH is set the store is snapshotted to disk after every
+//! write and restored on startup, so a node restart between slices is safe.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::fs;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlanContext {
+    values: HashMap<String, Value>,
+    /// Unix epoch milliseconds after which the context is discarded
+    expires_at_ms: i64,
+}
+
+pub struct PlanStateStore {
+    plans: RwLock<HashMap<String, PlanContext>>,
+    ttl_ms: i64,
+    path: Option<PathBuf>,
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+impl PlanStateStore {
+    pub fn new(ttl_secs: u64, path: Option<PathBuf>) -> Arc<Self> {
+        Arc::new(Self {
+            plans: RwLock::new(HashMap::new()),
+            ttl_ms: (ttl_secs as i64).saturating_mul(1000),
+            path,
+        })
+    }
+
+    /// Write `key` in the plan's context and refresh its TTL.
+    pub async fn put(&self, plan_id: &str, key: &str, value: Value) {
+        {
+            let mut plans = self.plans.write().await;
+            let now = now_ms();
+            plans.retain(|_, ctx| ctx.expires_at_ms > now);
+            let ctx = plans.entry(plan_id.to_owned()).or_default();
+            ctx.values.insert(key.to_owned(), value);
+            ctx.expires_at_ms = now + self.ttl_ms;
+        }
+        debug!("[PlanState] {plan_id}.{key} written");
+
+        if let Err(e) = self.persist().await {
+            warn!("[PlanState] failed to persist: {e}");
+        }
+    }
+
+    /// Read `key` from the plan's context (None if absent or expired).
+    pub async fn get(&self, plan_id: &str, key: &str) -> Option<Value> {
+        let plans = self.plans.read().await;
+        plans.get(plan_id)
+            .filter(|ctx| ctx.expires_at_ms > now_ms())
+            .and_then(|ctx| ctx.values.get(key).cloned())
+    }
+
+    /// Drop a plan's context (e.g. when central reports the plan finished).
+    #[cfg(test)]
+    pub async fn clear_plan(&self, plan_id: &str) {
+        self.plans.write().await.remove(plan_id);
+        if let Err(e) = self.persist().await {
+            warn!("[PlanState] failed to persist: {e}");
+        }
+    }
+
+    #[cfg(test)]
+    pub async fn plan_count(&self) -> usize {
+        let now = now_ms();
+        self.plans.read().await.values().filter(|c| c.expires_at_ms > now).count()
+    }
+
+    // ── Persistence ───────────────────────────────────────────────────────────
+
+    /// Snapshot live contexts to disk (no-op when persistence is disabled).
+    pub async fn persist(&self) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let now = now_ms();
+        let live: HashMap<String, PlanContext> = self.plans.read().await
+            .iter()
+            .filter(|(_, ctx)| ctx.expires_at_ms > now)
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(&live)?).await?;
+        fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+
+    /// Restore unexpired contexts written by a previous run.
+    pub async fn load(&self) -> Result<usize> {
+        let Some(path) = &self.path else { return Ok(0) };
+        if !path.exists() {
+            return Ok(0);
+        }
+        let snapshot: HashMap<String, PlanContext> = serde_json::from_slice(&fs::read(path).await?)?;
+        let now = now_ms();
+        let mut plans = self.plans.write().await;
+        plans.extend(snapshot.into_iter().filter(|(_, ctx)| ctx.expires_at_ms > now));
+        info!("[PlanState] restored {} plan context(s) from {:?}", plans.len(), path);
+        Ok(plans.len())
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_put_get_and_isolation() {
+        let store = PlanStateStore::new(60, None);
+        store.put("plan-a", "total", json!(42)).await;
+        assert_eq!(store.get("plan-a", "total").await, Some(json!(42)));
+        assert_eq!(store.get("plan-b", "total").await, None);
+
+        store.clear_plan("plan-a").await;
+        assert_eq!(store.get("plan-a", "total").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_expiry() {
+        let store = PlanStateStore::new(0, None);
+        store.put("plan-a", "k", json!("v")).await;
+        assert_eq!(store.get("plan-a", "k").await, None);
+        assert_eq!(store.plan_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_persist_and_load() {
+        let path = std::env::temp_dir().join(format!("eyeflow_plan_{}.json", uuid::Uuid::new_v4()));
+        let store = PlanStateStore::new(60, Some(path.clone()));
+        store.put("plan-a", "k", json!({"x": 1})).await;
+
+        let restored = PlanStateStore::new(60, Some(path.clone()));
+        assert_eq!(restored.load().await.unwrap(), 1);
+        assert_eq!(restored.get("plan-a", "k").await, Some(json!({"x": 1})));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,291 @@
+//! Typed predicates — BRANCH operators and LOOP convergence checks
+//!
+//! By default (`Svm::compare`) numbers only order against numbers, strings
+//! against strings, and truthiness is JSON's: "0" is truthy and ISO
+//! timestamps compare as plain text.  A predicate can declare how its two
+//! sides are compared:
+//!
+//!   compareAs   auto      the default rules above
+//!               numeric   numbers, numeric strings (" 42 ", "1e3") and
+//!                         booleans (1 / 0)
+//!               string    the text of any scalar ("1" == 1), ordered by
+//!                         code point
+//!               datetime  RFC 3339 timestamps, dates ("2024-05-01", UTC
+//!                         midnight) or epoch milliseconds; offsets are
+//!                         normalised before comparing
+//!               semver    MAJOR.MINOR.PATCH[-pre][+build], optional "v";
+//!                         semver 2.0 precedence (build metadata ignored)
+//!
+//!   nulls       how a null side — or one the mode cannot convert — behaves:
+//!               false     (default) every operator is false, except `==`
+//!                         (true when both are null), `!=` and `exists`
+//!               low       null sorts before any value
+//!               high      null sorts after any value
+//!               error     the instruction fails (VALIDATION)
+//!
+//! BRANCH reads them from operands_json (`compareAs`, `nulls`), LOOP from
+//! its convergence predicate (`compare_as`, `nulls`).  In a typed mode
+//! `truthy` looks at the converted value: non-zero, non-empty and not
+//! "0" / "false", or any valid timestamp / version.
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+use crate::errors::ExecError;
+use crate::svm::Svm;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompareAs {
+    #[default]
+    Auto,
+    Numeric,
+    String,
+    Datetime,
+    Semver,
+}
+
+impl CompareAs {
+    /// "" = auto.
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "" | "auto" => Ok(Self::Auto),
+            "numeric" | "number" => Ok(Self::Numeric),
+            "string" | "text" => Ok(Self::String),
+            "datetime" | "timestamp" => Ok(Self::Datetime),
+            "semver" | "version" => Ok(Self::Semver),
+            other => Err(ExecError::validation(format!(
+                "unknown compareAs '{other}' (auto, numeric, string, datetime, semver)"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Nulls {
+    #[default]
+    False,
+    Low,
+    High,
+    Error,
+}
+
+impl Nulls {
+    /// "" = false.
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "" | "false" => Ok(Self::False),
+            "low" | "first" => Ok(Self::Low),
+            "high" | "last" => Ok(Self::High),
+            "error" => Ok(Self::Error),
+            other => Err(ExecError::validation(format!("unknown nulls '{other}' (false, low, high, error)"))),
+        }
+    }
+}
+
+// ── Typed values ──────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+struct Version {
+    core: (u64, u64, u64),
+    /// Pre-release identifiers (empty = a release, which ranks higher)
+    pre: Vec<String>,
+}
+
+impl Version {
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().trim_start_matches(['v', 'V']);
+        let s = s.split('+').next()?;
+        let (core, pre) = match s.split_once('-') {
+            Some((core, pre)) => (core, pre.split('.').map(str::to_owned).collect()),
+            None => (s, Vec::new()),
+        };
+        let mut parts = core.split('.').map(|p| p.parse::<u64>().ok());
+        let core = (parts.next()??, parts.next()??, parts.next()??);
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self { core, pre })
+    }
+
+    fn precedence(&self, other: &Self) -> Ordering {
+        self.core.cmp(&other.core).then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => {
+                for (a, b) in self.pre.iter().zip(&other.pre) {
+                    // Numeric identifiers rank below alphanumeric ones
+                    let ord = match (a.parse::<u64>(), b.parse::<u64>()) {
+                        (Ok(a), Ok(b)) => a.cmp(&b),
+                        (Ok(_), Err(_)) => Ordering::Less,
+                        (Err(_), Ok(_)) => Ordering::Greater,
+                        (Err(_), Err(_)) => a.cmp(b),
+                    };
+                    if ord != Ordering::Equal {
+                        return ord;
+                    }
+                }
+                self.pre.len().cmp(&other.pre.len())
+            }
+        })
+    }
+}
+
+/// A side converted for a typed mode.
+#[derive(Debug, Clone)]
+enum Typed {
+    Num(f64),
+    Text(String),
+    Time(DateTime<Utc>),
+    Ver(Version),
+}
+
+impl Typed {
+    fn cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Num(a), Self::Num(b)) => a.partial_cmp(b),
+            (Self::Text(a), Self::Text(b)) => Some(a.cmp(b)),
+            (Self::Time(a), Self::Time(b)) => Some(a.cmp(b)),
+            (Self::Ver(a), Self::Ver(b)) => Some(a.precedence(b)),
+            _ => None,
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        match self {
+            Self::Num(n) => *n != 0.0,
+            Self::Text(s) => !s.is_empty() && s != "0" && !s.eq_ignore_ascii_case("false"),
+            Self::Time(_) | Self::Ver(_) => true,
+        }
+    }
+}
+
+fn convert(value: &Value, mode: CompareAs) -> Option<Typed> {
+    match (mode, value) {
+        (_, Value::Null) => None,
+        (CompareAs::Numeric, Value::Number(n)) => n.as_f64().map(Typed::Num),
+        (CompareAs::Numeric, Value::String(s)) => s.trim().parse().ok().filter(|f: &f64| f.is_finite()).map(Typed::Num),
+        (CompareAs::Numeric, Value::Bool(b)) => Some(Typed::Num(f64::from(u8::from(*b)))),
+        (CompareAs::String, Value::String(s)) => Some(Typed::Text(s.clone())),
+        (CompareAs::String, Value::Number(_) | Value::Bool(_)) => Some(Typed::Text(value.to_string())),
+        (CompareAs::Datetime, Value::String(s)) => {
+            let s = s.trim();
+            DateTime::parse_from_rfc3339(s).map(|t| t.with_timezone(&Utc)).ok()
+                .or_else(|| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0).map(|t| t.and_utc()))
+                .map(Typed::Time)
+        }
+        (CompareAs::Datetime, Value::Number(n)) => DateTime::from_timestamp_millis(n.as_i64()?).map(Typed::Time),
+        (CompareAs::Semver, Value::String(s)) => Version::parse(s).map(Typed::Ver),
+        _ => None,
+    }
+}
+
+// ── Evaluation ────────────────────────────────────────────────────────────────
+
+/// `left OP right` under `mode` and `nulls`.
+pub fn eval(left: &Value, operator: &str, right: &Value, mode: CompareAs, nulls: Nulls) -> Result<bool> {
+    let unary = matches!(operator, "truthy" | "exists");
+    if mode == CompareAs::Auto {
+        let has_null = left.is_null() || (!unary && right.is_null());
+        if !has_null || nulls == Nulls::False {
+            return Ok(Svm::compare(left, operator, right));
+        }
+    }
+    let typed = |v: &Value| match mode {
+        // Auto only gets here with one side null, which alone decides the order
+        CompareAs::Auto => (!v.is_null()).then(|| Typed::Text(String::new())),
+        _ => convert(v, mode),
+    };
+    let (l, r) = (typed(left), if unary { None } else { typed(right) });
+
+    if nulls == Nulls::Error && (l.is_none() || (!unary && r.is_none())) {
+        let side = if l.is_none() { left } else { right };
+        return Err(ExecError::validation(format!("predicate '{operator}': {side} is null or not a {mode:?} value")));
+    }
+    match operator {
+        "truthy" => return Ok(l.is_some_and(|l| l.truthy())),
+        "exists" => return Ok(l.is_some()),
+        "contains" => return Ok(match (&l, &r) {
+            (Some(Typed::Text(a)), Some(Typed::Text(b))) if mode == CompareAs::String => a.contains(b.as_str()),
+            _ => false,
+        }),
+        _ => {}
+    }
+
+    let ord = match (&l, &r, nulls) {
+        (Some(a), Some(b), _) => a.cmp(b),
+        (None, None, Nulls::Low | Nulls::High) => Some(Ordering::Equal),
+        (None, Some(_), Nulls::Low) | (Some(_), None, Nulls::High) => Some(Ordering::Less),
+        (None, Some(_), Nulls::High) | (Some(_), None, Nulls::Low) => Some(Ordering::Greater),
+        (None, None, _) => return Ok(matches!(operator, "==" | "eq" | "deep_eq")),
+        _ => return Ok(matches!(operator, "!=" | "ne")),
+    };
+    Ok(match operator {
+        "==" | "eq" | "deep_eq" => ord == Some(Ordering::Equal),
+        "!=" | "ne" => ord != Some(Ordering::Equal),
+        "<" | "lt" => ord == Some(Ordering::Less),
+        "<=" | "le" => matches!(ord, Some(Ordering::Less | Ordering::Equal)),
+        ">" | "gt" => ord == Some(Ordering::Greater),
+        ">=" | "ge" => matches!(ord, Some(Ordering::Greater | Ordering::Equal)),
+        _ => false,
+    })
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn is(left: Value, op: &str, right: Value, mode: &str, nulls: &str) -> bool {
+        eval(&left, op, &right, CompareAs::parse(mode).unwrap(), Nulls::parse(nulls).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_typed_modes() {
+        // Defaults keep Svm::compare
+        assert!(is(json!("0"), "truthy", Value::Null, "", ""));
+        assert!(!is(json!("3"), "<", json!(4), "", ""));
+
+        assert!(!is(json!("0"), "truthy", Value::Null, "numeric", ""));
+        assert!(is(json!(" 3 "), "<", json!(4), "numeric", ""));
+        assert!(is(json!(true), "==", json!(1), "numeric", ""));
+        assert!(is(json!(10), "==", json!("10"), "string", ""));
+        assert!(is(json!("10"), "<", json!("9"), "string", ""));
+        assert!(!is(json!("FALSE"), "truthy", Value::Null, "string", ""));
+        assert!(is(json!("apple pie"), "contains", json!("pie"), "string", ""));
+
+        // Offsets normalised; dates at UTC midnight; epoch ms
+        assert!(is(json!("2024-05-01T10:00:00+02:00"), "==", json!("2024-05-01T08:00:00Z"), "datetime", ""));
+        assert!(is(json!("2024-05-01"), "<", json!("2024-05-01T00:00:01Z"), "datetime", ""));
+        assert!(is(json!(1_714_521_600_000i64), "==", json!("2024-05-01"), "datetime", ""));
+
+        assert!(is(json!("1.10.0"), ">", json!("v1.9.3"), "semver", ""));
+        assert!(is(json!("1.0.0-alpha.2"), "<", json!("1.0.0-alpha.10"), "semver", ""));
+        assert!(is(json!("1.0.0-rc.1"), "<", json!("1.0.0"), "semver", ""));
+        assert!(is(json!("1.0.0+build.5"), "==", json!("1.0.0"), "semver", ""));
+        assert!(CompareAs::parse("fuzzy").is_err());
+    }
+
+    #[test]
+    fn test_null_handling() {
+        let null = Value::Null;
+        assert!(!is(null.clone(), "<", json!(1), "numeric", ""));
+        assert!(is(null.clone(), "!=", json!(1), "numeric", ""));
+        assert!(is(null.clone(), "==", null.clone(), "numeric", ""));
+        // Unconvertible counts as null
+        assert!(!is(json!("n/a"), ">=", json!(0), "numeric", ""));
+
+        assert!(is(null.clone(), "<", json!(1), "numeric", "low"));
+        assert!(is(null.clone(), ">", json!("2024-01-01"), "datetime", "high"));
+        assert!(is(null.clone(), "<", json!(1), "", "low"));
+        assert!(is(json!(1), "!=", json!(2), "", "low"));
+        assert!(!is(null.clone(), "exists", null.clone(), "", "low"));
+
+        let err = eval(&json!("n/a"), ">", &json!(0), CompareAs::Numeric, Nulls::Error).unwrap_err();
+        assert_eq!(crate::errors::classify(&err), crate::errors::ErrorCode::Validation);
+    }
+}
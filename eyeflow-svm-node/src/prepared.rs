@@ -0,0 +1,320 @@
+//! Prepared IR — decode once, execute many times
+//!
+//! Hot workflows arrive with the same artifact over and over; decoding the
+//! proto and re-parsing every `operands_json` on each run is wasted work.  A
+//! `PreparedIr` is the immutable, execution-ready form of an artifact:
+//!
+//!   - parsed operands per instruction
+//!   - parsed fallback strategy + config per instruction
+//!   - jump / branch targets resolved to positions in `instruction_order`
+//!   - SWITCH case tables
+//!   - CALL_SERVICE input schemas, LLM_CALL output guardrails
+//!   - the workflow's PII masking policy
+//!   - the Vault paths its instructions read (prefetched before a run)
+//!
+//! `IrCache` keeps the most recently used ones keyed by the SHA-256 of the
+//! encoded IR, so a repeated IR_DISTRIBUTION skips decode limits, prost and
+//! all JSON parsing.  SVM_IR_CACHE_SIZE bounds the entries (0 = disabled);
+//! hits and misses are exported on /metrics.  CALL_SLICE looks its
+//! sub-workflows up in the same cache, by checksum or workflow id.
+
+use anyhow::Result;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::fallback::{FallbackEngine, FallbackStrategy, InstructionFallbackConfig};
+use crate::health::MetricsCollector;
+use crate::guardrails::Guardrails;
+use crate::input_schema::InputSchema;
+use crate::pii::PiiPolicy;
+use crate::proto::llmir::{IrInstruction, IrOpcode, LlmIntermediateRepresentation};
+
+// ── SWITCH case table ─────────────────────────────────────────────────────────
+
+/// SWITCH operands: the first case equal to the value register (numbers
+/// compare numerically) names the instruction to jump to; otherwise
+/// `default`, otherwise the next instruction.
+///
+///   { "cases": [ { "value": "OPEN", "target": 12 }, { "value": 3, "target": 14 } ],
+///     "default": 20 }
+///   { "cases": { "OPEN": 12, "CLOSED": 14 } }          string keys only
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SwitchTable {
+    pub cases: Vec<(Value, i32)>,
+    pub default: Option<i32>,
+}
+
+impl SwitchTable {
+    pub fn parse(operands: &Value) -> Result<Self, String> {
+        let target = |v: &Value, what: &str| {
+            v.as_i64().and_then(|t| i32::try_from(t).ok())
+                .ok_or_else(|| format!("SWITCH {what} target must be an instruction index"))
+        };
+        let cases = match operands.get("cases") {
+            Some(Value::Array(items)) => items.iter()
+                .map(|c| Ok((
+                    c.get("value").cloned().ok_or("SWITCH case has no value")?,
+                    target(c.get("target").unwrap_or(&Value::Null), "case")?,
+                )))
+                .collect::<Result<_, String>>()?,
+            Some(Value::Object(map)) => map.iter()
+                .map(|(k, t)| Ok((Value::String(k.clone()), target(t, "case")?)))
+                .collect::<Result<_, String>>()?,
+            _ => return Err("SWITCH operands need a `cases` array or object".into()),
+        };
+        let default = operands.get("default").map(|d| target(d, "default")).transpose()?;
+        Ok(Self { cases, default })
+    }
+
+    /// Every instruction the table can jump to.
+    pub fn targets(&self) -> impl Iterator<Item = i32> + '_ {
+        self.cases.iter().map(|(_, t)| *t).chain(self.default)
+    }
+
+    /// Instruction to jump to for `value` (None = fall through).
+    pub fn target_for(&self, value: &Value) -> Option<i32> {
+        self.cases.iter()
+            .find(|(v, _)| crate::svm::Svm::compare(value, "==", v))
+            .map(|(_, t)| *t)
+            .or(self.default)
+    }
+}
+
+// ── PreparedIr ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default)]
+pub struct PreparedInstr {
+    /// `operands_json`, parsed (Null when empty or malformed)
+    pub operands: Value,
+    pub strategy: FallbackStrategy,
+    pub fallback: InstructionFallbackConfig,
+    /// Position of `target_instruction` in `instruction_order` (len = end)
+    pub target_ip: usize,
+    /// Case table of a SWITCH (None for other opcodes or a malformed table)
+    pub switch: Option<SwitchTable>,
+    /// `dispatch_metadata.input_schema` (None when absent or malformed)
+    pub input_schema: Option<InputSchema>,
+    /// LLM_CALL `guardrails` operand (None when absent or malformed)
+    pub guardrails: Option<Guardrails>,
+}
+
+impl PreparedInstr {
+    pub fn new(instr: &IrInstruction, positions: &HashMap<i32, usize>, len: usize) -> Self {
+        let (strategy, fallback) = FallbackEngine::strategy_for(&instr.operands_json);
+        let operands = serde_json::from_str(&instr.operands_json).unwrap_or(Value::Null);
+        let switch = (instr.opcode == IrOpcode::Switch as i32)
+            .then(|| SwitchTable::parse(&operands).ok())
+            .flatten();
+        let input_schema = instr.dispatch_metadata.as_ref()
+            .and_then(|dm| InputSchema::parse(&dm.input_schema).ok().flatten());
+        let guardrails = (instr.opcode == IrOpcode::LlmCall as i32)
+            .then(|| {
+                let output_schema = instr.dispatch_metadata.as_ref().map_or("", |dm| dm.output_schema.as_str());
+                Guardrails::parse(&operands, output_schema).ok().flatten()
+            })
+            .flatten();
+        Self {
+            operands,
+            strategy,
+            fallback,
+            target_ip: positions.get(&instr.target_instruction).copied().unwrap_or(len),
+            switch,
+            input_schema,
+            guardrails,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PreparedIr {
+    pub ir: LlmIntermediateRepresentation,
+    /// SHA-256 (hex) of the encoded IR; empty when built from a decoded IR
+    pub checksum: String,
+    /// `metadata.pii_policy` (None = node default, or malformed)
+    pub pii: Option<Arc<PiiPolicy>>,
+    /// credentials_vault_path and vault dynamic slot paths, deduplicated
+    pub vault_paths: Vec<String>,
+    instrs: HashMap<i32, PreparedInstr>,
+    positions: HashMap<i32, usize>,
+}
+
+impl PreparedIr {
+    pub fn new(ir: LlmIntermediateRepresentation, checksum: String) -> Self {
+        // First occurrence wins, like a linear search of instruction_order
+        let mut positions = HashMap::new();
+        for (pos, idx) in ir.instruction_order.iter().enumerate() {
+            positions.entry(*idx).or_insert(pos);
+        }
+        let len = ir.instruction_order.len();
+        let instrs = ir.instructions.iter()
+            .map(|(idx, instr)| (*idx, PreparedInstr::new(instr, &positions, len)))
+            .collect();
+        let pii = ir.metadata.as_ref()
+            .and_then(|m| PiiPolicy::parse(&m.pii_policy).ok().flatten())
+            .map(Arc::new);
+        let mut vault_paths: Vec<String> = ir.instructions.values()
+            .filter_map(|instr| instr.dispatch_metadata.as_ref())
+            .flat_map(|dm| {
+                std::iter::once(dm.credentials_vault_path.clone())
+                    .chain(dm.dynamic_slots.iter().filter(|s| s.source_type == "vault").map(|s| s.source_key.clone()))
+            })
+            .filter(|p| !p.is_empty())
+            .collect();
+        vault_paths.sort();
+        vault_paths.dedup();
+        Self { ir, checksum, pii, vault_paths, instrs, positions }
+    }
+
+    /// Prepared form of instruction `idx` (keyed like `ir.instructions`).
+    pub fn instr(&self, idx: i32) -> Option<&PreparedInstr> {
+        self.instrs.get(&idx)
+    }
+
+    /// Position of instruction `idx` in `instruction_order` (len = not found).
+    pub fn ip_of(&self, idx: i32) -> usize {
+        self.positions.get(&idx).copied().unwrap_or(self.ir.instruction_order.len())
+    }
+}
+
+// ── IrCache ───────────────────────────────────────────────────────────────────
+
+struct Entry {
+    prepared: Arc<PreparedIr>,
+    last_used: u64,
+}
+
+pub struct IrCache {
+    capacity: usize,
+    entries: Mutex<HashMap<String, Entry>>,
+    tick: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl IrCache {
+    pub fn new(capacity: usize) -> Arc<Self> {
+        Arc::new(Self {
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+            tick: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    /// Prepared IR for the encoded `bytes`, decoding with `decode` on a miss.
+    pub fn get_or_prepare(
+        &self,
+        bytes: &[u8],
+        decode: impl FnOnce(&[u8]) -> Result<LlmIntermediateRepresentation>,
+    ) -> Result<Arc<PreparedIr>> {
+        let checksum = hex::encode(Sha256::digest(bytes));
+        let now = self.tick.fetch_add(1, Ordering::Relaxed);
+        if let Some(e) = self.entries.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&checksum) {
+            e.last_used = now;
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(e.prepared.clone());
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let prepared = Arc::new(PreparedIr::new(decode(bytes)?, checksum.clone()));
+        if self.capacity > 0 {
+            let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            if entries.len() >= self.capacity && !entries.contains_key(&checksum) {
+                let lru = entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k.clone());
+                if let Some(k) = lru {
+                    entries.remove(&k);
+                }
+            }
+            entries.insert(checksum, Entry { prepared: prepared.clone(), last_used: now });
+        }
+        Ok(prepared)
+    }
+
+    /// Cached IR whose checksum or workflow id is `key` (the most recently
+    /// used one when several versions of a workflow are cached).
+    pub fn find(&self, key: &str) -> Option<Arc<PreparedIr>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(e) = entries.get(key) {
+            return Some(e.prepared.clone());
+        }
+        entries.values()
+            .filter(|e| e.prepared.ir.metadata.as_ref().is_some_and(|m| m.id == key))
+            .max_by_key(|e| e.last_used)
+            .map(|e| e.prepared.clone())
+    }
+}
+
+impl MetricsCollector for IrCache {
+    fn prometheus(&self, node_id: &str) -> String {
+        let size = self.entries.lock().map(|e| e.len()).unwrap_or(0);
+        format!(
+            "# HELP eyeflow_ir_cache_hits_total IR distributions served from the prepared-IR cache\n\
+             # TYPE eyeflow_ir_cache_hits_total counter\n\
+             eyeflow_ir_cache_hits_total{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_ir_cache_misses_total IR distributions decoded and prepared\n\
+             # TYPE eyeflow_ir_cache_misses_total counter\n\
+             eyeflow_ir_cache_misses_total{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_ir_cache_entries Prepared IRs currently cached\n\
+             # TYPE eyeflow_ir_cache_entries gauge\n\
+             eyeflow_ir_cache_entries{{node_id=\"{node_id}\"}} {size}\n",
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn test_prepare_and_cache() {
+        let ir = |n: i32| LlmIntermediateRepresentation {
+            instructions: [
+                (10, IrInstruction { index: 10, target_instruction: 30, operands_json: r#"{"strategy":"DEGRADED_MODE","k":1}"#.into(), ..Default::default() }),
+                (30, IrInstruction { index: 30, target_instruction: 99, ..Default::default() }),
+            ].into(),
+            instruction_order: vec![10, 20, 30],
+            input_register: n,
+            ..Default::default()
+        };
+        let p = PreparedIr::new(ir(0), String::new());
+        let first = p.instr(10).unwrap();
+        assert_eq!((first.target_ip, first.operands["k"].clone()), (2, 1.into()));
+        assert_eq!(first.strategy, FallbackStrategy::DegradedMode);
+        assert_eq!((p.instr(30).unwrap().target_ip, p.ip_of(20), p.ip_of(7)), (3, 1, 3));
+
+        let cache = IrCache::new(1);
+        let decode = |b: &[u8]| Ok(LlmIntermediateRepresentation::decode(b)?);
+        let (a, b) = (ir(1).encode_to_vec(), ir(2).encode_to_vec());
+        let p1 = cache.get_or_prepare(&a, decode).unwrap();
+        assert!(Arc::ptr_eq(&p1, &cache.get_or_prepare(&a, |_| panic!("cached")).unwrap()));
+        // Capacity 1: b evicts a
+        cache.get_or_prepare(&b, decode).unwrap();
+        assert!(!Arc::ptr_eq(&p1, &cache.get_or_prepare(&a, decode).unwrap()));
+        assert!(cache.prometheus("n").contains("eyeflow_ir_cache_hits_total{node_id=\"n\"} 1"));
+    }
+
+    #[test]
+    fn test_switch_table() {
+        let t = SwitchTable::parse(&serde_json::json!({
+            "cases": [{ "value": "OPEN", "target": 12 }, { "value": 3, "target": 14 }],
+            "default": 20,
+        })).unwrap();
+        assert_eq!(t.target_for(&"OPEN".into()), Some(12));
+        assert_eq!(t.target_for(&serde_json::json!(3.0)), Some(14));
+        assert_eq!(t.target_for(&Value::Null), Some(20));
+        assert_eq!(t.targets().collect::<Vec<_>>(), vec![12, 14, 20]);
+
+        let keyed = SwitchTable::parse(&serde_json::json!({ "cases": { "A": 1 } })).unwrap();
+        assert_eq!((keyed.target_for(&"B".into()), keyed.default), (None, None));
+        assert!(SwitchTable::parse(&serde_json::json!({ "cases": [{ "value": 1 }] })).is_err());
+    }
+}
@@ -1,7 +1,76 @@
-/// Generated Protobuf types — included from prost build output (OUT_DIR).
-///
-/// Access these types as `crate::proto::llmir::LlmIntermediateRepresentation`, etc.
+//! Generated Protobuf types — included from prost build output (OUT_DIR).
+//!
+//! Access these types as `crate::proto::llmir::LlmIntermediateRepresentation`, etc.
+//!
+//! Every message is also serde (de)serialisable as camelCase JSON (see
+//! build.rs): `serde_json::to_value(&result)` is the RESULT payload of a
+//! text frame.  Enum fields stay their proto numbers, `bytes` are base64,
+//! and missing fields take their proto default.
 
 pub mod llmir {
     include!(concat!(env!("OUT_DIR"), "/llmir.rs"));
 }
+
+/// SliceExecutionResult.error_instruction of a slice-level failure.
+pub fn slice_level() -> i32 {
+    -1
+}
+
+/// Whether `instruction` is `slice_level` (serde skip predicate).
+pub fn is_slice_level(instruction: &i32) -> bool {
+    *instruction < 0
+}
+
+/// `bytes` fields as standard base64 strings.
+pub mod base64_bytes {
+    use base64::{engine::general_purpose::STANDARD as B64, Engine};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&B64.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(d)?;
+        B64.decode(text).map_err(serde::de::Error::custom)
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::llmir::{RegisterLineage, SignedIrArtifact, SliceExecutionResult};
+    use serde_json::json;
+
+    #[test]
+    fn test_json_view_matches_the_result_wire() {
+        let mut result = SliceExecutionResult {
+            plan_id: "wf".into(),
+            slice_id: "s1".into(),
+            status: "SUCCESS".into(),
+            duration_ms: 12,
+            error_instruction: -1,
+            output_registers: [(3, "42".to_owned())].into(),
+            ..Default::default()
+        };
+        assert_eq!(serde_json::to_value(&result).unwrap(), json!({
+            "planId": "wf", "sliceId": "s1", "nodeId": "", "status": "SUCCESS",
+            "durationMs": 12, "outputRegisters": { "3": "42" },
+        }));
+
+        result.error_instruction = 2;
+        result.lineage = vec![RegisterLineage { register_index: 3, instructions: vec![1, 2], ..Default::default() }];
+        let wire = serde_json::to_value(&result).unwrap();
+        assert_eq!((wire["errorInstruction"].as_i64(), wire["lineage"][0]["register"].as_i64()), (Some(2), Some(3)));
+        assert_eq!(serde_json::from_value::<SliceExecutionResult>(wire).unwrap(), result);
+        // Omitted = slice-level
+        let parsed: SliceExecutionResult = serde_json::from_value(json!({ "planId": "wf" })).unwrap();
+        assert_eq!(parsed.error_instruction, -1);
+
+        let artifact = SignedIrArtifact { payload: vec![1, 2, 3], ..Default::default() };
+        let wire = serde_json::to_value(&artifact).unwrap();
+        assert_eq!(wire["payload"], "AQID");
+        assert_eq!(serde_json::from_value::<SignedIrArtifact>(wire).unwrap(), artifact);
+    }
+}
@@ -0,0 +1,402 @@
+//! Node provisioning — first-boot identity and enrollment
+//!
+//!   SVM_KEYSTORE_PATH         PKCS#8 PEM of the node key, mode 0600; created
+//!                             on first boot when missing (unused when
+//!                             SVM_SIGNING_PRIVATE_KEY_PEM is set — the key is
+//!                             then provisioned out of band)
+//!   SVM_ENROLLMENT_TOKEN      one-time bootstrap token forwarded to central
+//!   SVM_ENROLLMENT_POLL_SECS  seconds between enrollment requests (default 60)
+//!
+//! A node with a keystore enrolls its key with central:
+//!
+//!   POST {CENTRAL_HTTP_URL}/api/nodes/enroll
+//!   { nodeId, tier, labels, version, publicKey, fingerprint, requestedAt,
+//!     token, signature }
+//!   signature = Ed25519 over `eyeflow-enroll:v1:{nodeId}:{publicKey}:{requestedAt}`
+//!   → { "status": "pending" | "approved" | "rejected", "enrollmentId" }
+//!
+//! and repeats the (re-signed) request until an operator decides; central
+//! can also push `{ "type": "ENROLLMENT", "payload": { status, enrollmentId } }`
+//! over the WebSocket.  Until approved the node is quarantined: it connects,
+//! registers (REGISTER carries `enrollment`) and reports health, but answers
+//! every IR_DISTRIBUTION with status QUARANTINED (AUTH) — and refuses peer
+//! delegations alike (admission.rs).  A rejected node stays quarantined and
+//! stops asking.
+//!
+//! The decision is kept next to the keystore (`<keystore>.enrollment.json`,
+//! bound to the key fingerprint), so a restart does not re-quarantine and a
+//! new key has to enroll again.
+//!
+//!   eyeflow-svm-node provision [--keystore <path>] [--rotate] [--no-enroll] [--json]
+//!
+//! creates the keystore (a new key with --rotate, which resets enrollment),
+//! submits the enrollment request and prints the fingerprint and status.
+//! /metrics: eyeflow_node_quarantined.
+
+use anyhow::{anyhow, Context, Result};
+use ed25519_dalek::{Signer, SigningKey};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::audit;
+use crate::config::Config;
+use crate::health::MetricsCollector;
+
+const USAGE: &str = "usage: eyeflow-svm-node provision [--keystore <path>] [--rotate] [--no-enroll] [--json]";
+
+// ── Keystore ──────────────────────────────────────────────────────────────────
+
+/// The node key in `path`, generated (mode 0600) when missing or `rotate` is set.
+/// Returns the key and whether it was created.
+pub fn load_or_create(path: &Path, rotate: bool) -> Result<(SigningKey, bool)> {
+    if !rotate {
+        match std::fs::read_to_string(path) {
+            Ok(pem) => {
+                check_permissions(path)?;
+                let key = audit::signing_key_from_pkcs8_pem(&pem)
+                    .with_context(|| format!("keystore {}", path.display()))?;
+                return Ok((key, false));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(anyhow!("reading keystore {}: {e}", path.display())),
+        }
+    }
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let key = SigningKey::generate(&mut OsRng);
+    // Written aside and renamed: a crash never leaves a truncated keystore
+    let tmp = path.with_extension("tmp");
+    let _ = std::fs::remove_file(&tmp);
+    {
+        use std::io::Write;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&tmp).with_context(|| format!("creating keystore {}", tmp.display()))?;
+        file.write_all(audit::signing_key_to_pkcs8_pem(&key).as_bytes())?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)?;
+    Ok((key, true))
+}
+
+#[cfg(unix)]
+fn check_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = std::fs::metadata(path)?.permissions().mode();
+    if mode & 0o077 != 0 {
+        return Err(anyhow!("keystore {} is accessible to other users (mode {:o}) — chmod 600 it", path.display(), mode & 0o777));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// PEM of the keystore key, when the node key comes from SVM_KEYSTORE_PATH.
+pub fn keystore_pem(config: &Config) -> Result<Option<String>> {
+    let Some(path) = config.keystore_path.as_ref().filter(|_| config.signing_private_key_pem.is_none()) else {
+        return Ok(None);
+    };
+    let (key, created) = load_or_create(Path::new(path), false)?;
+    if created {
+        info!("[Provision] generated node key in {path} (fingerprint {})", fingerprint(&key));
+    }
+    Ok(Some(audit::signing_key_to_pkcs8_pem(&key)))
+}
+
+fn fingerprint(key: &SigningKey) -> String {
+    hex::encode(Sha256::digest(key.verifying_key().as_bytes()))
+}
+
+/// Message signed in an enrollment request.
+pub fn enrollment_message(node_id: &str, public_key_hex: &str, requested_at: &str) -> String {
+    format!("eyeflow-enroll:v1:{node_id}:{public_key_hex}:{requested_at}")
+}
+
+// ── Enrollment ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl Status {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "pending" => Ok(Self::Pending),
+            "approved" => Ok(Self::Approved),
+            "rejected" => Ok(Self::Rejected),
+            other => Err(anyhow!("unknown enrollment status '{other}'")),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// Central's decision as kept next to the keystore.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Record {
+    status: Status,
+    fingerprint: String,
+    enrollment_id: Option<String>,
+    updated_at: String,
+}
+
+#[derive(Debug)]
+pub struct Enrollment {
+    node_id: String,
+    tier: String,
+    labels: Value,
+    token: Option<String>,
+    url: String,
+    key: SigningKey,
+    state_path: PathBuf,
+    poll: Duration,
+    status: RwLock<(Status, Option<String>)>,
+}
+
+impl Enrollment {
+    pub fn new(config: &Config, keystore: &Path, key: SigningKey) -> Self {
+        let state_path = PathBuf::from(format!("{}.enrollment.json", keystore.display()));
+        let fp = fingerprint(&key);
+        let stored = std::fs::read_to_string(&state_path).ok()
+            .and_then(|text| serde_json::from_str::<Record>(&text).ok())
+            .filter(|r| r.fingerprint == fp)
+            .map_or((Status::Pending, None), |r| (r.status, r.enrollment_id));
+        Self {
+            node_id: config.node_id.clone(),
+            tier: config.node_tier.clone(),
+            labels: json!(config.node_labels),
+            token: config.enrollment_token.clone(),
+            url: format!("{}/api/nodes/enroll", config.central_http_url.trim_end_matches('/')),
+            key,
+            state_path,
+            poll: Duration::from_secs(config.enrollment_poll_secs.max(1)),
+            status: RwLock::new(stored),
+        }
+    }
+
+    /// Enrollment of the keystore key (None when the node has no keystore).
+    pub fn from_config(config: &Config, key: &SigningKey) -> Option<Arc<Self>> {
+        let path = config.keystore_path.as_ref().filter(|_| config.signing_private_key_pem.is_none())?;
+        let enrollment = Self::new(config, Path::new(path), key.clone());
+        if enrollment.quarantined() {
+            warn!("[Provision] node key not approved yet ({}) — quarantined, no IR is accepted", enrollment.status().as_str());
+        }
+        Some(Arc::new(enrollment))
+    }
+
+    pub fn status(&self) -> Status {
+        self.status.read().map_or(Status::Pending, |s| s.0)
+    }
+
+    pub fn quarantined(&self) -> bool {
+        self.status() != Status::Approved
+    }
+
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.key)
+    }
+
+    /// A freshly signed enrollment request.
+    pub fn request(&self) -> Value {
+        let public_key = hex::encode(self.key.verifying_key().as_bytes());
+        let requested_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let signature = self.key.sign(enrollment_message(&self.node_id, &public_key, &requested_at).as_bytes());
+        json!({
+            "nodeId": self.node_id,
+            "tier": self.tier,
+            "labels": self.labels,
+            "version": env!("CARGO_PKG_VERSION"),
+            "publicKey": public_key,
+            "fingerprint": self.fingerprint(),
+            "requestedAt": requested_at,
+            "token": self.token,
+            "signature": hex::encode(signature.to_bytes()),
+        })
+    }
+
+    /// Record central's decision (persisted when it changes).
+    pub fn apply(&self, status: Status, enrollment_id: Option<String>) -> Result<()> {
+        {
+            let mut current = self.status.write().map_err(|_| anyhow!("enrollment state poisoned"))?;
+            if current.0 == status && (enrollment_id.is_none() || current.1 == enrollment_id) {
+                return Ok(());
+            }
+            *current = (status, enrollment_id.clone().or(current.1.take()));
+        }
+        match status {
+            Status::Approved => info!("[Provision] enrollment approved — quarantine lifted"),
+            Status::Rejected => warn!("[Provision] enrollment rejected — node stays quarantined"),
+            Status::Pending => info!("[Provision] enrollment pending approval"),
+        }
+        let record = Record {
+            status,
+            fingerprint: self.fingerprint(),
+            enrollment_id: self.status.read().ok().and_then(|s| s.1.clone()),
+            updated_at: chrono::Utc::now().to_rfc3339(),
+        };
+        std::fs::write(&self.state_path, serde_json::to_vec_pretty(&record)?)
+            .with_context(|| format!("writing {}", self.state_path.display()))
+    }
+
+    /// Apply a `{ status, enrollmentId }` object (central's answer or an ENROLLMENT frame).
+    pub fn apply_value(&self, value: &Value) -> Result<Status> {
+        let status = Status::parse(value.get("status").and_then(|v| v.as_str()).unwrap_or(""))?;
+        let id = value.get("enrollmentId").and_then(|v| v.as_str()).map(str::to_owned);
+        self.apply(status, id)?;
+        Ok(status)
+    }
+
+    /// Submit the enrollment request and apply the answer.
+    pub async fn submit(&self, http: &reqwest::Client) -> Result<Status> {
+        let resp = http.post(&self.url).json(&self.request()).send().await
+            .with_context(|| format!("POST {}", self.url))?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("POST {}: HTTP {}", self.url, resp.status()));
+        }
+        self.apply_value(&resp.json::<Value>().await?)
+    }
+
+    /// Ask central until it approves or rejects the key.
+    pub async fn run(self: Arc<Self>) {
+        let http = reqwest::Client::new();
+        while self.status() == Status::Pending {
+            if let Err(e) = self.submit(&http).await {
+                warn!("[Provision] enrollment request failed: {e:#}");
+            }
+            if self.status() != Status::Pending {
+                break;
+            }
+            tokio::time::sleep(self.poll).await;
+        }
+    }
+}
+
+impl MetricsCollector for Enrollment {
+    fn prometheus(&self, node_id: &str) -> String {
+        format!(
+            "# HELP eyeflow_node_quarantined Whether the node key awaits enrollment approval (no IR accepted)\n\
+             # TYPE eyeflow_node_quarantined gauge\n\
+             eyeflow_node_quarantined{{node_id=\"{node_id}\",status=\"{}\"}} {}\n",
+            self.status().as_str(),
+            u8::from(self.quarantined()),
+        )
+    }
+}
+
+// ── CLI ───────────────────────────────────────────────────────────────────────
+
+pub async fn run(args: &[String]) -> Result<()> {
+    let _ = dotenvy::dotenv();
+    let config = Config::from_env();
+
+    let mut keystore = config.keystore_path.clone();
+    let (mut rotate, mut enroll, mut as_json) = (false, true, false);
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--keystore" => keystore = Some(it.next().cloned().ok_or_else(|| anyhow!(USAGE))?),
+            "--rotate" => rotate = true,
+            "--no-enroll" => enroll = false,
+            "--json" => as_json = true,
+            other => return Err(anyhow!("unknown argument {other}\n{USAGE}")),
+        }
+    }
+    let keystore = PathBuf::from(keystore.ok_or_else(|| anyhow!("set SVM_KEYSTORE_PATH or pass --keystore\n{USAGE}"))?);
+
+    let (key, created) = load_or_create(&keystore, rotate)?;
+    let enrollment = Enrollment::new(&config, &keystore, key);
+    if created {
+        let _ = std::fs::remove_file(&enrollment.state_path);
+    }
+    let submitted = if enroll && enrollment.status() != Status::Approved {
+        Some(enrollment.submit(&reqwest::Client::new()).await)
+    } else {
+        None
+    };
+
+    let report = json!({
+        "keystore": keystore.display().to_string(),
+        "created": created,
+        "nodeId": config.node_id,
+        "publicKey": hex::encode(enrollment.key.verifying_key().as_bytes()),
+        "fingerprint": enrollment.fingerprint(),
+        "status": enrollment.status().as_str(),
+        "error": submitted.as_ref().and_then(|r| r.as_ref().err()).map(|e| format!("{e:#}")),
+    });
+    if as_json {
+        println!("{report:#}");
+    } else {
+        println!("keystore     {}{}", report["keystore"].as_str().unwrap_or(""), if created { " (new key)" } else { "" });
+        println!("node         {}", config.node_id);
+        println!("public key   {}", report["publicKey"].as_str().unwrap_or(""));
+        println!("fingerprint  {}", report["fingerprint"].as_str().unwrap_or(""));
+        println!("enrollment   {}", enrollment.status().as_str());
+    }
+    match submitted {
+        Some(Err(e)) => Err(e.context("enrollment request failed (the node retries at start)")),
+        _ => Ok(()),
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, Verifier};
+
+    #[test]
+    fn test_keystore_and_enrollment_state() {
+        let dir = std::env::temp_dir().join(format!("eyeflow_provision_{}", uuid::Uuid::new_v4().simple()));
+        let path = dir.join("node.key");
+        let (key, created) = load_or_create(&path, false).unwrap();
+        assert!(created);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        let (again, created) = load_or_create(&path, false).unwrap();
+        assert!(!created);
+        assert_eq!(again.to_bytes(), key.to_bytes());
+
+        let config = Config::from_env();
+        let enrollment = Enrollment::new(&config, &path, key.clone());
+        assert!(enrollment.quarantined());
+        let req = enrollment.request();
+        let message = enrollment_message(&config.node_id, req["publicKey"].as_str().unwrap(), req["requestedAt"].as_str().unwrap());
+        let signature = Signature::from_slice(&hex::decode(req["signature"].as_str().unwrap()).unwrap()).unwrap();
+        assert!(key.verifying_key().verify(message.as_bytes(), &signature).is_ok());
+
+        enrollment.apply_value(&json!({ "status": "approved", "enrollmentId": "e-1" })).unwrap();
+        assert!(enrollment.prometheus("n").contains("eyeflow_node_quarantined{node_id=\"n\",status=\"approved\"} 0"));
+        // The decision survives a restart, but only for the same key
+        assert!(!Enrollment::new(&config, &path, key).quarantined());
+        let (rotated, _) = load_or_create(&path, true).unwrap();
+        assert!(Enrollment::new(&config, &path, rotated).quarantined());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
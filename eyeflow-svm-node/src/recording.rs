@@ -0,0 +1,348 @@
+//! Record / replay — deterministic re-execution of production slices
+//!
+//!   SVM_RECORD_DIR=<dir>     every slice writes <dir>/<workflow>_<time>_<id>.json
+//!                            with each external response it received
+//!   SVM_REPLAY_FILE=<file>   external dispatches are answered from the
+//!                            recording instead of calling out
+//!
+//! Recorded: the final value (or typed error) of LOAD_RESOURCE, CALL_SERVICE,
+//! CALL_ACTION, CALL_MCP and LLM_CALL — after fallback, so retries and
+//! degraded answers replay as they happened — keyed by instruction index and
+//! occurrence (loops run an instruction more than once); dispatches of a
+//! CALL_SLICE sub-workflow are keyed by the calling instruction too.  Vault lookups made
+//! while dispatching are listed per entry (path + success); secret values are
+//! never written.  In replay no dispatch runs, so Vault, HTTP and actuators
+//! are never contacted and nothing is journaled in the action WAL.
+//!
+//! To reproduce on a developer machine:
+//!
+//!   eyeflow-svm-node reproduce --ir <artifact> --recording <file>
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+use crate::errors::{ErrorCode, ExecError};
+use crate::proto::llmir::IrInstruction;
+
+tokio::task_local! {
+    /// Vault lookups of the dispatch being recorded.
+    static VAULT_LOOKUPS: Arc<Mutex<Vec<VaultLookup>>>;
+}
+
+/// Note a Vault lookup (called by VaultClient; no-op outside a recording).
+pub fn note_vault_lookup(path: &str, ok: bool) {
+    let _ = VAULT_LOOKUPS.try_with(|v| {
+        if let Ok(mut v) = v.lock() {
+            v.push(VaultLookup { path: path.to_owned(), ok });
+        }
+    });
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VaultLookup {
+    pub path: String,
+    pub ok: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    /// CALL_SLICE path of a sub-workflow dispatch ("12", "12/4"; None = top level)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slice: Option<String>,
+    pub instruction: i32,
+    /// Occurrence of this instruction within the slice (0-based)
+    pub seq: u32,
+    pub opcode: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ExecError>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vault: Vec<VaultLookup>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recording {
+    pub workflow_id: String,
+    pub node_id: String,
+    pub recorded_at: String,
+    pub entries: Vec<Entry>,
+    /// How the slice ended (None = success)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<ExecError>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading recording {path:?}"))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+// ── Recorder (per node) ───────────────────────────────────────────────────────
+
+#[derive(Debug, Default)]
+pub enum Recorder {
+    #[default]
+    Off,
+    Record { dir: PathBuf, node_id: String },
+    Replay(Arc<Recording>),
+}
+
+impl Recorder {
+    pub fn from_config(config: &crate::config::Config) -> Result<Self> {
+        match (&config.replay_file, &config.record_dir) {
+            (Some(_), Some(_)) => Err(anyhow!("SVM_RECORD_DIR and SVM_REPLAY_FILE are mutually exclusive")),
+            (Some(file), None) => {
+                let rec = Recording::load(Path::new(file))?;
+                warn!(
+                    "[Recorder] REPLAY mode — {} recorded response(s) from {file}; no external calls will be made",
+                    rec.entries.len()
+                );
+                Ok(Self::Replay(Arc::new(rec)))
+            }
+            (None, Some(dir)) => {
+                std::fs::create_dir_all(dir)?;
+                info!("[Recorder] recording external responses to {dir}");
+                Ok(Self::Record { dir: PathBuf::from(dir), node_id: config.node_id.clone() })
+            }
+            (None, None) => Ok(Self::Off),
+        }
+    }
+
+    /// Tape for one slice execution.
+    pub fn tape(&self, workflow_id: &str) -> Tape<'_> {
+        if let Self::Replay(rec) = self {
+            if rec.workflow_id != workflow_id {
+                warn!("[Recorder] replaying a recording of {} for workflow {workflow_id}", rec.workflow_id);
+            }
+        }
+        Tape {
+            recorder: self,
+            workflow_id: workflow_id.to_owned(),
+            slice: None,
+            state: Arc::new(Mutex::new(TapeState::default())),
+        }
+    }
+}
+
+// ── Tape (per slice) ──────────────────────────────────────────────────────────
+
+#[derive(Default)]
+struct TapeState {
+    seq: HashMap<(Option<String>, i32), u32>,
+    entries: Vec<Entry>,
+}
+
+pub struct Tape<'a> {
+    recorder: &'a Recorder,
+    workflow_id: String,
+    slice: Option<String>,
+    state: Arc<Mutex<TapeState>>,
+}
+
+impl<'a> Tape<'a> {
+    /// Tape for the sub-workflow run by CALL_SLICE instruction `instruction`;
+    /// its entries land in this tape's recording.
+    pub fn nested(&self, instruction: i32) -> Tape<'a> {
+        Tape {
+            recorder: self.recorder,
+            workflow_id: self.workflow_id.clone(),
+            slice: Some(match &self.slice {
+                Some(s) => format!("{s}/{instruction}"),
+                None => instruction.to_string(),
+            }),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Whether dispatches are answered from a recording.
+    pub fn replaying(&self) -> bool {
+        matches!(self.recorder, Recorder::Replay(_))
+    }
+
+    /// Run (or replay) the external dispatch of `instr`.
+    pub async fn dispatch<F>(&self, instr: &IrInstruction, opcode: &str, call: F) -> Result<Value>
+    where
+        F: std::future::Future<Output = Result<Value>>,
+    {
+        let seq = {
+            let mut st = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let n = st.seq.entry((self.slice.clone(), instr.index)).or_insert(0);
+            *n += 1;
+            *n - 1
+        };
+        match self.recorder {
+            Recorder::Off => call.await,
+            Recorder::Replay(rec) => {
+                let entry = rec.entries.iter()
+                    .find(|e| e.slice == self.slice && e.instruction == instr.index && e.seq == seq)
+                    .ok_or_else(|| ExecError::new(
+                        ErrorCode::NotFound,
+                        format!("no recorded response for {opcode} #{} (occurrence {seq})", instr.index),
+                    ))?;
+                match &entry.error {
+                    Some(e) => Err(e.clone().into()),
+                    None => Ok(entry.response.clone().unwrap_or(Value::Null)),
+                }
+            }
+            Recorder::Record { .. } => {
+                let lookups = Arc::new(Mutex::new(Vec::new()));
+                let result = VAULT_LOOKUPS.scope(lookups.clone(), call).await;
+                let entry = Entry {
+                    slice: self.slice.clone(),
+                    instruction: instr.index,
+                    seq,
+                    opcode: opcode.to_owned(),
+                    response: result.as_ref().ok().cloned(),
+                    error: result.as_ref().err().map(|e| ExecError::from_anyhow(e, Some(instr.index))),
+                    vault: std::mem::take(&mut *lookups.lock().unwrap_or_else(|e| e.into_inner())),
+                };
+                self.state.lock().unwrap_or_else(|e| e.into_inner()).entries.push(entry);
+                result
+            }
+        }
+    }
+
+    /// Write the recording (record mode only).
+    pub async fn finish(self, outcome: Option<&ExecError>) {
+        let Recorder::Record { dir, node_id } = self.recorder else { return };
+        let mut entries = std::mem::take(&mut self.state.lock().unwrap_or_else(|e| e.into_inner()).entries);
+        entries.sort_by(|a, b| (&a.slice, a.instruction, a.seq).cmp(&(&b.slice, b.instruction, b.seq)));
+        let now = chrono::Utc::now();
+        let rec = Recording {
+            workflow_id: self.workflow_id.clone(),
+            node_id: node_id.clone(),
+            recorded_at: now.to_rfc3339(),
+            entries,
+            outcome: outcome.cloned(),
+        };
+        let safe: String = self.workflow_id.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let path = dir.join(format!("{safe}_{}_{}.json", now.format("%Y%m%dT%H%M%S"), &id[..8]));
+        let res = async {
+            tokio::fs::write(&path, serde_json::to_vec_pretty(&rec)?).await?;
+            anyhow::Ok(())
+        }.await;
+        match res {
+            Ok(()) => info!("[Recorder] {} response(s) recorded to {path:?}", rec.entries.len()),
+            Err(e) => warn!("[Recorder] failed to write {path:?}: {e}"),
+        }
+    }
+}
+
+// ── `reproduce` subcommand ───────────────────────────────────────────────────
+
+const USAGE: &str = "usage: eyeflow-svm-node reproduce --ir <artifact> --recording <file> [--json]";
+
+/// `reproduce` subcommand (args after the subcommand name): run the IR
+/// locally against a recording and compare the outcome with the recorded one.
+pub async fn reproduce(args: &[String]) -> Result<()> {
+    let (mut ir_path, mut rec_path, mut json) = (None, None, false);
+    let mut it = args.iter();
+    while let Some(a) = it.next() {
+        match a.as_str() {
+            "--ir" => ir_path = it.next().map(PathBuf::from),
+            "--recording" => rec_path = it.next().map(PathBuf::from),
+            "--json" => json = true,
+            other => return Err(anyhow!("unknown argument '{other}'\n{USAGE}")),
+        }
+    }
+    let ir_path = ir_path.ok_or_else(|| anyhow!("--ir is required\n{USAGE}"))?;
+    let rec_path = rec_path.ok_or_else(|| anyhow!("--recording is required\n{USAGE}"))?;
+    let ir = crate::replay::decode_ir(&std::fs::read(&ir_path).with_context(|| format!("reading {ir_path:?}"))?)?;
+    let recording = Recording::load(&rec_path)?;
+    let recorded_outcome = recording.outcome.clone();
+
+    // Scratch state: nothing from the local node is read or modified
+    let _ = dotenvy::dotenv();
+    let config = crate::config::Config::from_env();
+    let scratch = std::env::temp_dir().join(format!("eyeflow_reproduce_{}", uuid::Uuid::new_v4().simple()));
+    let svm = crate::svm::Svm::scratch(config.clone(), &scratch).await?
+        .with_recorder(Recorder::Replay(Arc::new(recording)));
+    let mut audit = crate::audit::AuditChain::new(config.node_id.clone(), None)?;
+    let prepared = crate::prepared::PreparedIr::new(ir, String::new());
+    let outcome = svm.execute(&prepared, &mut audit, None).await;
+    let _ = std::fs::remove_dir_all(&scratch);
+
+    let (outputs, error) = match &outcome {
+        Ok((regs, _)) => (Some(regs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect::<serde_json::Map<_, _>>()), None),
+        Err(e) => (None, Some(e.clone())),
+    };
+    // Messages embed timings and hosts; the code and instruction must match
+    let same = |a: &Option<ExecError>, b: &Option<ExecError>| match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => a.code == b.code && a.instruction == b.instruction,
+        _ => false,
+    };
+    let matches = same(&error, &recorded_outcome);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "outputs": outputs,
+            "error": error,
+            "recordedError": recorded_outcome,
+            "matchesRecording": matches,
+        }))?);
+    } else {
+        match (&outputs, &error) {
+            (Some(o), _) => println!("outputs: {}", serde_json::to_string_pretty(o)?),
+            (_, Some(e)) => println!("failed: {} at #{:?}: {}", e.code, e.instruction, e.message),
+            _ => {}
+        }
+        println!("outcome {} the recording", if matches { "matches" } else { "DIFFERS from" });
+    }
+    if !matches {
+        return Err(anyhow!("reproduced outcome differs from the recorded one"));
+    }
+    Ok(())
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let dir = std::env::temp_dir().join(format!("eyeflow_rec_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let instr = IrInstruction { index: 3, ..Default::default() };
+
+        let recorder = Recorder::Record { dir: dir.clone(), node_id: "n".into() };
+        let tape = recorder.tape("wf");
+        let first = tape.dispatch(&instr, "CALL_SERVICE", async {
+            note_vault_lookup("secret/api", true);
+            Ok(json!({ "temp": 21 }))
+        }).await.unwrap();
+        assert_eq!(first["temp"], 21);
+        let _ = tape.dispatch(&instr, "CALL_SERVICE", async {
+            Err(ExecError::new(ErrorCode::Timeout, "slow upstream").into())
+        }).await;
+        tape.finish(None).await;
+
+        let file = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        let rec = Recording::load(&file).unwrap();
+        assert_eq!(rec.entries[0].vault, vec![VaultLookup { path: "secret/api".into(), ok: true }]);
+
+        // Replay: same answers, in the same order, without calling out
+        let replayer = Recorder::Replay(Arc::new(rec));
+        let tape = replayer.tape("wf");
+        let never = || async { panic!("replay must not dispatch") };
+        assert_eq!(tape.dispatch(&instr, "CALL_SERVICE", never()).await.unwrap(), json!({ "temp": 21 }));
+        let err = tape.dispatch(&instr, "CALL_SERVICE", never()).await.unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::Timeout);
+        assert!(tape.dispatch(&instr, "CALL_SERVICE", never()).await.is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,444 @@
+//! Redis-backed shared state — STORE_MEMORY namespaces and response caches
+//!
+//! Several node processes on one gateway (one per tenant, or a standby pair)
+//! each keep their own plan state and caches.  When SVM_REDIS_URL is set,
+//! instructions can address a Redis namespace instead, so those processes
+//! share counters, latches and cached responses:
+//!
+//!   STORE_MEMORY   operands_json = { "redis": { "namespace": "line3", "key": "count",
+//!                                               "op": "incrby", "ttlSecs": 3600 } }
+//!   LOAD_RESOURCE  operands_json = { "redis": { "namespace": "line3", "key": "count" } }
+//!
+//! STORE_MEMORY ops (src[0] is the operand; dest receives the result):
+//!
+//!   set          (default) store src                        → src
+//!   setnx        store src unless the key exists            → the value now held
+//!   getset       store src                                  → the previous value
+//!   incrby       add src (integer, default 1)               → the new counter
+//!   incrbyfloat  add src (number)                           → the new counter
+//!   push         append src to a list ("maxLen" trims it)   → the list length
+//!   del          remove the key                             → 1 if it existed
+//!
+//! LOAD_RESOURCE ops: get (default) → the stored value or null; range → the
+//! whole list.  Values are stored as JSON text.  `ttlSecs` (re)arms the key's
+//! expiry in the same command or MULTI/EXEC transaction as the write, so
+//! every op is atomic across processes.
+//!
+//! Keys are `{SVM_REDIS_PREFIX}mem:{namespace}:{key}` (prefix default
+//! "eyeflow:"); the response cache (response_cache.rs) uses
+//! `{prefix}cache:{digest}`.  SVM_REDIS_TIMEOUT_MS bounds each command
+//! (default 1000).
+//!
+//! Redis is spoken through the redis crate's ConnectionManager (RedisConn
+//! below, shared with dlock.rs): connected on first use, AUTH / SELECT from
+//! redis://[:password@]host[:port][/db], reconnected after errors.
+
+use anyhow::{anyhow, Result};
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use redis::{Cmd, FromRedisValue, Pipeline, RedisResult, ScriptInvocation};
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use tracing::info;
+
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+
+// ── Connection ────────────────────────────────────────────────────────────────
+
+/// One Redis server, connected on first use.
+pub(crate) struct RedisConn {
+    client: redis::Client,
+    manager: OnceCell<ConnectionManager>,
+    timeout: Duration,
+}
+
+impl RedisConn {
+    /// `timeout` bounds each command, connecting included.
+    pub(crate) fn open(url: &str, timeout: Duration) -> Result<Self> {
+        let client = redis::Client::open(url).map_err(|e| anyhow!("invalid Redis URL {url}: {e}"))?;
+        Ok(Self { client, manager: OnceCell::new(), timeout })
+    }
+
+    async fn manager(&self) -> RedisResult<ConnectionManager> {
+        let config = ConnectionManagerConfig::new()
+            .set_connection_timeout(self.timeout)
+            .set_response_timeout(self.timeout)
+            .set_number_of_retries(1);
+        self.manager
+            .get_or_try_init(|| ConnectionManager::new_with_config(self.client.clone(), config))
+            .await
+            .cloned()
+    }
+
+    async fn within<T>(&self, call: impl Future<Output = RedisResult<T>>) -> Result<T> {
+        match tokio::time::timeout(self.timeout, call).await {
+            Ok(out) => out.map_err(redis_error),
+            Err(_) => Err(ExecError::new(ErrorCode::Timeout, "Redis command timed out").into()),
+        }
+    }
+
+    pub(crate) async fn query<T: FromRedisValue>(&self, cmd: &Cmd) -> Result<T> {
+        self.within(async { cmd.query_async(&mut self.manager().await?).await }).await
+    }
+
+    /// Run `pipe` (MULTI/EXEC when atomic) and return its replies.
+    pub(crate) async fn query_pipe<T: FromRedisValue>(&self, pipe: &Pipeline) -> Result<T> {
+        self.within(async { pipe.query_async(&mut self.manager().await?).await }).await
+    }
+
+    /// EVALSHA `script`, loading it first if the server does not have it.
+    pub(crate) async fn invoke<T: FromRedisValue>(&self, script: &ScriptInvocation<'_>) -> Result<T> {
+        self.within(async { script.invoke_async(&mut self.manager().await?).await }).await
+    }
+}
+
+/// Typed error: a Redis error reply is a bad request (WRONGTYPE, …), the rest
+/// are connectivity problems worth retrying.
+fn redis_error(e: redis::RedisError) -> anyhow::Error {
+    let code = if e.is_timeout() {
+        ErrorCode::Timeout
+    } else if e.is_io_error() || e.is_connection_dropped() || e.is_connection_refusal() {
+        ErrorCode::Network
+    } else {
+        ErrorCode::Validation
+    };
+    ExecError::new(code, format!("Redis: {e}")).into()
+}
+
+// ── Operands ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Set,
+    SetNx,
+    GetSet,
+    IncrBy,
+    IncrByFloat,
+    Push,
+    Del,
+    Get,
+    Range,
+}
+
+impl Op {
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "set" => Self::Set,
+            "setnx" => Self::SetNx,
+            "getset" => Self::GetSet,
+            "incrby" => Self::IncrBy,
+            "incrbyfloat" => Self::IncrByFloat,
+            "push" => Self::Push,
+            "del" => Self::Del,
+            "get" => Self::Get,
+            "range" => Self::Range,
+            other => return Err(ExecError::validation(format!("unknown redis op '{other}'"))),
+        })
+    }
+
+    fn is_read(self) -> bool {
+        matches!(self, Self::Get | Self::Range)
+    }
+}
+
+/// The `redis` operand of a STORE_MEMORY / LOAD_RESOURCE instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryOp {
+    pub namespace: String,
+    pub key: String,
+    pub op: Op,
+    pub ttl_secs: Option<u64>,
+    pub max_len: Option<u64>,
+}
+
+impl MemoryOp {
+    /// Parse `operands.redis`; None when the instruction does not use Redis.
+    /// `store` selects the STORE_MEMORY ops (else the LOAD_RESOURCE ones).
+    pub fn from_operands(operands: &Value, store: bool) -> Result<Option<Self>> {
+        let Some(spec) = operands.get("redis") else {
+            return Ok(None);
+        };
+        let field = |name: &str| spec.get(name).and_then(|v| v.as_str()).filter(|s| !s.is_empty());
+        let key = field("key").ok_or_else(|| ExecError::validation("redis operand needs a \"key\""))?;
+        let op = Op::parse(field("op").unwrap_or(if store { "set" } else { "get" }))?;
+        if op.is_read() == store {
+            let which = if store { "STORE_MEMORY" } else { "LOAD_RESOURCE" };
+            return Err(ExecError::validation(format!("redis op '{}' is not valid for {which}", field("op").unwrap_or_default())));
+        }
+        Ok(Some(Self {
+            namespace: field("namespace").unwrap_or("default").to_owned(),
+            key: key.to_owned(),
+            op,
+            ttl_secs: spec.get("ttlSecs").and_then(|v| v.as_u64()).filter(|&t| t > 0),
+            max_len: spec.get("maxLen").and_then(|v| v.as_u64()).filter(|&n| n > 0),
+        }))
+    }
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+pub struct RedisStore {
+    conn: RedisConn,
+    prefix: String,
+}
+
+impl RedisStore {
+    pub fn new(url: &str, prefix: &str, timeout: Duration) -> Result<Arc<Self>> {
+        Ok(Arc::new(Self {
+            conn: RedisConn::open(url, timeout)?,
+            prefix: prefix.to_owned(),
+        }))
+    }
+
+    /// Store configured by SVM_REDIS_URL, if any.
+    pub fn from_config(config: &Config) -> Result<Option<Arc<Self>>> {
+        let Some(url) = &config.redis_url else {
+            return Ok(None);
+        };
+        let store = Self::new(url, &config.redis_prefix, Duration::from_millis(config.redis_timeout_ms))?;
+        info!("[Redis] shared state enabled (prefix \"{}\")", config.redis_prefix);
+        Ok(Some(store))
+    }
+
+    fn memory_key(&self, op: &MemoryOp) -> String {
+        format!("{}mem:{}:{}", self.prefix, op.namespace, op.key)
+    }
+
+    /// Full key of a response-cache entry.
+    pub fn cache_key(&self, digest: &str) -> String {
+        format!("{}cache:{digest}", self.prefix)
+    }
+
+    /// Run `cmds`; several commands are wrapped in MULTI/EXEC and the EXEC
+    /// replies returned.
+    async fn run(&self, cmds: Vec<Cmd>) -> Result<Vec<redis::Value>> {
+        if let [only] = &cmds[..] {
+            return self.conn.query(only).await.map(|r| vec![r]);
+        }
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for cmd in &cmds {
+            pipe.add_command(cmd.clone());
+        }
+        let replies: Vec<redis::Value> = self.conn.query_pipe(&pipe).await?;
+        if replies.len() != cmds.len() {
+            return Err(ExecError::new(ErrorCode::Upstream, format!("Redis EXEC returned {} replies for {} commands", replies.len(), cmds.len())).into());
+        }
+        Ok(replies)
+    }
+
+    /// Apply the STORE_MEMORY `op` with operand `value`.
+    pub async fn store(&self, op: &MemoryOp, value: &Value) -> Result<Value> {
+        let key = self.memory_key(op);
+        let json = value.to_string();
+        let set = |flag: Option<&str>| {
+            let mut cmd = redis::cmd("SET");
+            cmd.arg(&key).arg(&json);
+            if let Some(flag) = flag {
+                cmd.arg(flag);
+            }
+            if let Some(ttl) = op.ttl_secs {
+                cmd.arg("EX").arg(ttl);
+            }
+            cmd
+        };
+        let mut cmds = vec![match op.op {
+            Op::Set => set(None),
+            Op::SetNx => set(Some("NX")),
+            Op::GetSet => set(Some("GET")),
+            Op::IncrBy => {
+                let by = if value.is_null() { Some(1) } else { value.as_i64() };
+                let by = by.ok_or_else(|| ExecError::validation(format!("incrby needs an integer, got {value}")))?;
+                redis::cmd("INCRBY").arg(&key).arg(by).clone()
+            }
+            Op::IncrByFloat => {
+                let by = value.as_f64()
+                    .ok_or_else(|| ExecError::validation(format!("incrbyfloat needs a number, got {value}")))?;
+                redis::cmd("INCRBYFLOAT").arg(&key).arg(by).clone()
+            }
+            Op::Push => redis::cmd("RPUSH").arg(&key).arg(&json).clone(),
+            Op::Del => redis::cmd("DEL").arg(&key).clone(),
+            Op::Get | Op::Range => return Err(ExecError::validation("read op used for STORE_MEMORY")),
+        }];
+        match op.op {
+            Op::SetNx => cmds.push(redis::cmd("GET").arg(&key).clone()),
+            Op::Push => {
+                if let Some(n) = op.max_len {
+                    cmds.push(redis::cmd("LTRIM").arg(&key).arg(format!("-{n}")).arg(-1).clone());
+                }
+            }
+            _ => {}
+        }
+        if let Some(ttl) = op.ttl_secs.filter(|_| matches!(op.op, Op::IncrBy | Op::IncrByFloat | Op::Push)) {
+            cmds.push(redis::cmd("EXPIRE").arg(&key).arg(ttl).clone());
+        }
+
+        let mut replies = self.run(cmds).await?.into_iter();
+        let first = replies.next().unwrap_or(redis::Value::Nil);
+        Ok(match op.op {
+            Op::Set => value.clone(),
+            Op::SetNx => decode(replies.next().unwrap_or(redis::Value::Nil)),
+            Op::IncrByFloat => match decode(first) {
+                Value::String(s) => s.parse::<f64>().map(Value::from).unwrap_or(Value::Null),
+                other => other,
+            },
+            _ => decode(first),
+        })
+    }
+
+    /// Read the value named by the LOAD_RESOURCE `op` (null when unset).
+    pub async fn load(&self, op: &MemoryOp) -> Result<Value> {
+        let key = self.memory_key(op);
+        let cmd = match op.op {
+            Op::Range => redis::cmd("LRANGE").arg(&key).arg(0).arg(-1).clone(),
+            _ => redis::cmd("GET").arg(&key).clone(),
+        };
+        let reply = self.run(vec![cmd]).await?.pop().unwrap_or(redis::Value::Nil);
+        Ok(decode(reply))
+    }
+
+    /// Cached response under `digest`, if any.
+    pub async fn cache_get(&self, digest: &str) -> Result<Option<Value>> {
+        let bytes: Option<Vec<u8>> = self.conn.query(redis::cmd("GET").arg(self.cache_key(digest))).await?;
+        Ok(bytes.and_then(|b| serde_json::from_slice(&b).ok()))
+    }
+
+    /// Cache `value` under `digest` for `ttl`.
+    pub async fn cache_put(&self, digest: &str, value: &Value, ttl: Duration) -> Result<()> {
+        let ttl_ms = ttl.as_millis().max(1) as u64;
+        self.conn.query::<()>(redis::cmd("SET").arg(self.cache_key(digest)).arg(value.to_string()).arg("PX").arg(ttl_ms)).await
+    }
+}
+
+/// Stored JSON text back to a value (non-JSON strings come back as strings).
+fn decode(reply: redis::Value) -> Value {
+    match reply {
+        redis::Value::BulkString(bytes) => serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned())),
+        redis::Value::Int(n) => n.into(),
+        redis::Value::SimpleString(s) => Value::String(s),
+        redis::Value::Okay => Value::String("OK".into()),
+        redis::Value::Array(items) | redis::Value::Set(items) => Value::Array(items.into_iter().map(decode).collect()),
+        redis::Value::Double(n) => n.into(),
+        redis::Value::Boolean(b) => b.into(),
+        _ => Value::Null,
+    }
+}
+
+// ── Test support ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+
+    /// Read one RESP command (array of bulk strings).
+    async fn read_command(s: &mut tokio::io::BufReader<tokio::net::TcpStream>) -> Vec<String> {
+        let mut line = String::new();
+        if s.read_line(&mut line).await.unwrap() == 0 {
+            return Vec::new();
+        }
+        let n: usize = line.trim_end()[1..].parse().unwrap();
+        let mut out = Vec::new();
+        for _ in 0..n {
+            line.clear();
+            s.read_line(&mut line).await.unwrap();
+            let len: usize = line.trim_end()[1..].parse().unwrap();
+            let mut buf = vec![0u8; len + 2];
+            s.read_exact(&mut buf).await.unwrap();
+            out.push(String::from_utf8_lossy(&buf[..len]).into_owned());
+        }
+        out
+    }
+
+    /// RESP server on a local port answering each command by name; returns what it was
+    /// sent (minus the client's CLIENT SETINFO handshake).
+    pub(crate) async fn fake_redis(answer: fn(&[String]) -> &'static [u8]) -> (String, tokio::task::JoinHandle<Vec<Vec<String>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (sock, _) = listener.accept().await.unwrap();
+            let mut s = tokio::io::BufReader::new(sock);
+            let mut seen = Vec::new();
+            loop {
+                let cmd = read_command(&mut s).await;
+                if cmd.is_empty() {
+                    return seen;
+                }
+                s.get_mut().write_all(answer(&cmd)).await.unwrap();
+                if cmd[0] != "CLIENT" {
+                    seen.push(cmd);
+                }
+            }
+        });
+        (format!("redis://{addr}"), server)
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_support::fake_redis;
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_memory_operands() {
+        let op = MemoryOp::from_operands(&json!({ "redis": { "namespace": "line3", "key": "count", "op": "incrby", "ttlSecs": 60 } }), true)
+            .unwrap().unwrap();
+        assert_eq!((op.namespace.as_str(), op.op, op.ttl_secs), ("line3", Op::IncrBy, Some(60)));
+        let load = MemoryOp::from_operands(&json!({ "redis": { "key": "count" } }), false).unwrap().unwrap();
+        assert_eq!((load.namespace.as_str(), load.op), ("default", Op::Get));
+        assert!(MemoryOp::from_operands(&json!({ "planKey": "x" }), true).unwrap().is_none());
+        assert!(MemoryOp::from_operands(&json!({ "redis": { "key": "k", "op": "get" } }), true).is_err());
+        assert!(MemoryOp::from_operands(&json!({ "redis": { "op": "set" } }), true).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_atomic_counter_and_load() {
+        let (url, server) = fake_redis(|cmd| match cmd[0].as_str() {
+            "INCRBY" | "EXPIRE" => b"+QUEUED\r\n",
+            "EXEC" => b"*2\r\n:5\r\n:1\r\n",
+            "GET" => b"$14\r\n{\"batch\":\"b7\"}\r\n",
+            "LRANGE" => b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n",
+            _ => b"+OK\r\n",
+        }).await;
+
+        let store = RedisStore::new(&url, "eyeflow:", Duration::from_secs(2)).unwrap();
+        let incr = MemoryOp::from_operands(&json!({ "redis": { "namespace": "line3", "key": "count", "op": "incrby", "ttlSecs": 60 } }), true)
+            .unwrap().unwrap();
+        assert_eq!(store.store(&incr, &json!(2)).await.unwrap(), json!(5));
+        let get = MemoryOp::from_operands(&json!({ "redis": { "namespace": "line3", "key": "last" } }), false)
+            .unwrap().unwrap();
+        assert_eq!(store.load(&get).await.unwrap(), json!({ "batch": "b7" }));
+        // An error reply is a bad request, not a connectivity problem
+        let range = MemoryOp::from_operands(&json!({ "redis": { "namespace": "line3", "key": "last", "op": "range" } }), false)
+            .unwrap().unwrap();
+        let err = store.load(&range).await.unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::Validation, "{err}");
+        drop(store);
+
+        let seen = server.await.unwrap();
+        assert_eq!(seen[0], ["MULTI"]);
+        assert_eq!(seen[1], ["INCRBY", "eyeflow:mem:line3:count", "2"]);
+        assert_eq!(seen[2], ["EXPIRE", "eyeflow:mem:line3:count", "60"]);
+        assert_eq!(seen[3], ["EXEC"]);
+        assert_eq!(seen[4], ["GET", "eyeflow:mem:line3:last"]);
+        assert_eq!(seen[5], ["LRANGE", "eyeflow:mem:line3:last", "0", "-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let store = RedisStore::new(&format!("redis://{addr}"), "eyeflow:", Duration::from_millis(500)).unwrap();
+        let err = store.cache_get("d1").await.unwrap_err();
+        assert!(crate::errors::classify(&err).is_transient(), "{err}");
+        assert!(RedisStore::new("http://redis-a", "eyeflow:", Duration::from_secs(1)).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Register-file memory accounting — one workflow must not OOM the node
+//!
+//! Every register write is charged with the serialized (JSON) size of the
+//! value; overwriting a register releases its previous charge.  When the
+//! register file would exceed SVM_REGISTER_MEMORY_MAX_BYTES (0 = unlimited)
+//! the write is refused and the slice aborts with `MEMORY_LIMIT` — the node
+//! reports the slice with that status instead of being killed by the kernel.
+//!
+//! The writing instruction may opt into a fallback in `operands_json`:
+//!
+//!   { "onMemoryLimit": "FAIL_SAFE", "safeDefault": {...} }   store safeDefault
+//!   { "onMemoryLimit": "DEGRADED_MODE" }                     store null
+//!
+//! (the default, "ABORT", fails the slice).  The fallback value is charged
+//! too; if it does not fit either, the slice aborts.
+
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::errors::{ErrorCode, ExecError};
+use crate::proto::llmir::IrInstruction;
+use crate::svm::Registers;
+
+/// Serialized JSON size of `v`, computed without allocating the text.
+pub fn serialized_size(v: &Value) -> usize {
+    struct Counter(usize);
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut c = Counter(0);
+    let _ = serde_json::to_writer(&mut c, v);
+    c.0
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OnLimit {
+    on_memory_limit: Option<String>,
+    safe_default: Option<Value>,
+}
+
+#[derive(Debug)]
+pub struct RegisterMeter {
+    /// Ceiling in bytes (0 = unlimited)
+    limit: usize,
+    sizes: HashMap<i32, usize>,
+    total: usize,
+    peak: usize,
+}
+
+impl RegisterMeter {
+    pub fn new(limit: usize) -> Self {
+        Self { limit, sizes: HashMap::new(), total: 0, peak: 0 }
+    }
+
+    /// Largest size the register file reached.
+    pub fn peak(&self) -> usize {
+        self.peak
+    }
+
+    fn fits(&self, dest: i32, size: usize) -> bool {
+        let released = self.sizes.get(&dest).copied().unwrap_or(0);
+        self.limit == 0 || self.total - released + size <= self.limit
+    }
+
+    fn charge(&mut self, dest: i32, size: usize) {
+        let released = self.sizes.insert(dest, size).unwrap_or(0);
+        self.total = self.total - released + size;
+        self.peak = self.peak.max(self.total);
+    }
+
+    /// Write `value` to register `dest` on behalf of `instr`, applying the
+    /// instruction's memory-limit fallback when it does not fit.
+    pub fn store(&mut self, regs: &mut Registers, instr: &IrInstruction, dest: i32, value: Value) -> Result<()> {
+        let size = serialized_size(&value);
+        if self.fits(dest, size) {
+            self.charge(dest, size);
+            regs.insert(dest, value);
+            return Ok(());
+        }
+
+        let exceeded = format!(
+            "register file would hold {} bytes (limit {}) after writing {size} bytes to r{dest}",
+            self.total - self.sizes.get(&dest).copied().unwrap_or(0) + size,
+            self.limit,
+        );
+        let on: OnLimit = serde_json::from_str(&instr.operands_json).unwrap_or_default();
+        let fallback = match on.on_memory_limit.as_deref().map(str::to_uppercase).as_deref() {
+            Some("FAIL_SAFE") => Some(on.safe_default.unwrap_or(Value::Null)),
+            Some("DEGRADED_MODE") => Some(Value::Null),
+            _ => None,
+        };
+        if let Some(v) = fallback {
+            let size = serialized_size(&v);
+            if self.fits(dest, size) {
+                warn!("[Svm] MEMORY_LIMIT at #{}: {exceeded} — storing fallback value", instr.index);
+                self.charge(dest, size);
+                regs.insert(dest, v);
+                return Ok(());
+            }
+        }
+        Err(ExecError::new(ErrorCode::MemoryLimit, exceeded).into())
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_meter_limit_and_fallback() {
+        assert_eq!(serialized_size(&json!({ "a": [1, "xy"] })), r#"{"a":[1,"xy"]}"#.len());
+
+        let mut regs = Registers::new();
+        let mut m = RegisterMeter::new(20);
+        let plain = IrInstruction::default();
+        m.store(&mut regs, &plain, 1, json!("0123456789")).unwrap();     // 12 bytes
+        // Overwriting releases the old charge
+        m.store(&mut regs, &plain, 1, json!("01234567890123")).unwrap(); // 16 bytes
+        assert_eq!(m.peak(), 16);
+
+        let err = m.store(&mut regs, &plain, 2, json!("too big")).unwrap_err();
+        assert_eq!(crate::errors::classify(&err), ErrorCode::MemoryLimit);
+        assert!(!regs.contains_key(&2));
+
+        let degraded = IrInstruction { operands_json: r#"{"onMemoryLimit":"DEGRADED_MODE"}"#.into(), ..Default::default() };
+        m.store(&mut regs, &degraded, 2, json!("too big")).unwrap();
+        assert_eq!((regs[&2].clone(), m.peak()), (Value::Null, 20));
+    }
+}
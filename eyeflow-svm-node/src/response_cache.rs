@@ -0,0 +1,180 @@
+//! Response cache — LLM_CALL / CALL_SERVICE results reused across executions
+//!
+//! An instruction opts in with a `cacheTtlSecs` operand:
+//!
+//!   LLM_CALL      operands_json = { "cacheTtlSecs": 600 }
+//!   CALL_SERVICE  operands_json = { "cacheTtlSecs": 30 }
+//!
+//! The key is a SHA-256 over the opcode, service id, endpoint, credentials
+//! path, prompt fields and input, so only identical requests share an entry.
+//! Only successful results are cached; a hit skips the resource permit and
+//! the call altogether.
+//!
+//! Entries are kept in memory (SVM_RESPONSE_CACHE_SIZE entries, LRU, default
+//! 1024, 0 = disabled) or, when SVM_REDIS_URL is set, in Redis so that every
+//! node process on the gateway shares them.  A Redis failure counts as a
+//! miss — the call goes through.
+
+use anyhow::Result;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::health::MetricsCollector;
+use crate::redis_store::RedisStore;
+
+struct Entry {
+    value: Value,
+    expires: Instant,
+    last_used: u64,
+}
+
+pub struct ResponseCache {
+    capacity: usize,
+    redis: Option<Arc<RedisStore>>,
+    entries: Mutex<HashMap<String, Entry>>,
+    tick: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ResponseCache {
+    pub fn new(capacity: usize, redis: Option<Arc<RedisStore>>) -> Arc<Self> {
+        Arc::new(Self {
+            capacity,
+            redis,
+            entries: Mutex::new(HashMap::new()),
+            tick: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    /// Cache key of a request described by `request`.
+    pub fn digest(request: &Value) -> String {
+        hex::encode(Sha256::digest(request.to_string().as_bytes()))
+    }
+
+    /// The cached response for `digest`, else the result of `call` (cached
+    /// for `ttl` when it succeeds).
+    pub async fn get_or_call<F>(&self, digest: &str, ttl: Duration, call: F) -> Result<Value>
+    where
+        F: Future<Output = Result<Value>>,
+    {
+        if let Some(value) = self.get(digest).await {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            debug!("[ResponseCache] hit {digest}");
+            return Ok(value);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let value = call.await?;
+        self.put(digest, &value, ttl).await;
+        Ok(value)
+    }
+
+    async fn get(&self, digest: &str) -> Option<Value> {
+        if let Some(redis) = &self.redis {
+            return redis.cache_get(digest).await
+                .unwrap_or_else(|e| {
+                    warn!("[ResponseCache] Redis lookup failed: {e}");
+                    None
+                });
+        }
+        let now = self.tick.fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get_mut(digest) {
+            Some(e) if e.expires > Instant::now() => {
+                e.last_used = now;
+                Some(e.value.clone())
+            }
+            Some(_) => {
+                entries.remove(digest);
+                None
+            }
+            None => None,
+        }
+    }
+
+    async fn put(&self, digest: &str, value: &Value, ttl: Duration) {
+        if let Some(redis) = &self.redis {
+            if let Err(e) = redis.cache_put(digest, value, ttl).await {
+                warn!("[ResponseCache] Redis store failed: {e}");
+            }
+            return;
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        let now = self.tick.fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= self.capacity && !entries.contains_key(digest) {
+            let t = Instant::now();
+            entries.retain(|_, e| e.expires > t);
+            if entries.len() >= self.capacity {
+                let lru = entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k.clone());
+                if let Some(k) = lru {
+                    entries.remove(&k);
+                }
+            }
+        }
+        entries.insert(digest.to_owned(), Entry { value: value.clone(), expires: Instant::now() + ttl, last_used: now });
+    }
+}
+
+impl MetricsCollector for ResponseCache {
+    fn prometheus(&self, node_id: &str) -> String {
+        let backend = if self.redis.is_some() { "redis" } else { "memory" };
+        let size = self.entries.lock().map(|e| e.len()).unwrap_or(0);
+        format!(
+            "# HELP eyeflow_response_cache_hits_total LLM_CALL / CALL_SERVICE results served from the response cache\n\
+             # TYPE eyeflow_response_cache_hits_total counter\n\
+             eyeflow_response_cache_hits_total{{node_id=\"{node_id}\",backend=\"{backend}\"}} {}\n\
+             # HELP eyeflow_response_cache_misses_total Cacheable calls that went to the service\n\
+             # TYPE eyeflow_response_cache_misses_total counter\n\
+             eyeflow_response_cache_misses_total{{node_id=\"{node_id}\",backend=\"{backend}\"}} {}\n\
+             # HELP eyeflow_response_cache_entries Responses held in the in-memory cache\n\
+             # TYPE eyeflow_response_cache_entries gauge\n\
+             eyeflow_response_cache_entries{{node_id=\"{node_id}\"}} {size}\n",
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_hits_expiry_and_eviction() {
+        let cache = ResponseCache::new(2, None);
+        let a = ResponseCache::digest(&json!({ "kind": "LLM_CALL", "input": "a" }));
+        let b = ResponseCache::digest(&json!({ "kind": "LLM_CALL", "input": "b" }));
+        assert_ne!(a, b);
+
+        let ttl = Duration::from_secs(60);
+        assert_eq!(cache.get_or_call(&a, ttl, async { Ok(json!(1)) }).await.unwrap(), json!(1));
+        // Served from the cache: the call is never polled
+        assert_eq!(cache.get_or_call(&a, ttl, async { unreachable!() }).await.unwrap(), json!(1));
+        // Failures are not cached
+        assert!(cache.get_or_call(&b, ttl, async { Err(anyhow::anyhow!("down")) }).await.is_err());
+        assert_eq!(cache.get_or_call(&b, ttl, async { Ok(json!(2)) }).await.unwrap(), json!(2));
+        assert_eq!((cache.hits.load(Ordering::Relaxed), cache.misses.load(Ordering::Relaxed)), (1, 3));
+
+        // A third entry evicts the least recently used one (a)
+        let c = ResponseCache::digest(&json!("c"));
+        cache.get_or_call(&c, Duration::ZERO, async { Ok(json!(3)) }).await.unwrap();
+        assert_eq!(cache.entries.lock().unwrap().len(), 2);
+        assert!(!cache.entries.lock().unwrap().contains_key(&a));
+        // c expired immediately
+        assert_eq!(cache.get_or_call(&c, ttl, async { Ok(json!(4)) }).await.unwrap(), json!(4));
+    }
+}
@@ -0,0 +1,157 @@
+//! Result retention — the node's own record of recent executions
+//!
+//! When central is unreachable, field engineers still need to know what ran
+//! and how it ended.  Every SliceExecutionResult (its JSON view, without
+//! audit events) is kept here:
+//!
+//!   SVM_RESULTS_MAX              results kept (oldest dropped first)
+//!   SVM_RESULTS_RETENTION_SECS   results older than this are discarded
+//!   SVM_RESULTS_PATH             snapshot file (unset = memory only)
+//!
+//! and served by the health server (see health.rs):
+//!
+//!   GET /results?workflow_id=<id>&limit=<n>
+//!   Authorization: Bearer <SVM_HEALTH_API_TOKEN>
+//!
+//! Newest results come first.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::fs;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredResult {
+    /// Unix epoch milliseconds when the result was recorded
+    recorded_at_ms: i64,
+    result: Value,
+}
+
+#[derive(Debug)]
+pub struct ResultStore {
+    results: RwLock<VecDeque<StoredResult>>,
+    max: usize,
+    retention_ms: i64,
+    path: Option<PathBuf>,
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+impl ResultStore {
+    pub fn new(max: usize, retention_secs: u64, path: Option<PathBuf>) -> Arc<Self> {
+        Arc::new(Self {
+            results: RwLock::new(VecDeque::new()),
+            max,
+            retention_ms: (retention_secs as i64).saturating_mul(1000),
+            path,
+        })
+    }
+
+    /// Keep `result` (a SliceExecutionResult as JSON), evicting by count and age.
+    pub async fn record(&self, result: Value) {
+        if self.max == 0 {
+            return;
+        }
+        {
+            let mut results = self.results.write().await;
+            let now = now_ms();
+            results.push_back(StoredResult { recorded_at_ms: now, result });
+            self.trim(&mut results, now);
+        }
+        if let Err(e) = self.persist().await {
+            warn!("[Results] failed to persist: {e}");
+        }
+    }
+
+    fn trim(&self, results: &mut VecDeque<StoredResult>, now: i64) {
+        while results.len() > self.max {
+            results.pop_front();
+        }
+        while results.front().is_some_and(|r| now - r.recorded_at_ms > self.retention_ms) {
+            results.pop_front();
+        }
+    }
+
+    /// Up to `limit` retained results, newest first, optionally for one workflow.
+    pub async fn query(&self, workflow_id: Option<&str>, limit: usize) -> Vec<Value> {
+        let now = now_ms();
+        self.results.read().await
+            .iter()
+            .rev()
+            .filter(|r| now - r.recorded_at_ms <= self.retention_ms)
+            .filter(|r| workflow_id.is_none_or(|w| r.result.get("planId").and_then(|p| p.as_str()) == Some(w)))
+            .take(limit)
+            .map(|r| {
+                let mut v = r.result.clone();
+                if let Some(obj) = v.as_object_mut() {
+                    let at = chrono::DateTime::from_timestamp_millis(r.recorded_at_ms)
+                        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+                    obj.insert("recordedAt".into(), at.into());
+                }
+                v
+            })
+            .collect()
+    }
+
+    // ── Persistence ───────────────────────────────────────────────────────────
+
+    /// Snapshot retained results to disk (no-op when persistence is disabled).
+    pub async fn persist(&self) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let bytes = serde_json::to_vec(&*self.results.read().await)?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, bytes).await?;
+        fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+
+    /// Restore results written by a previous run (expired ones are dropped).
+    pub async fn load(&self) -> Result<usize> {
+        let Some(path) = &self.path else { return Ok(0) };
+        if !path.exists() {
+            return Ok(0);
+        }
+        let snapshot: VecDeque<StoredResult> = serde_json::from_slice(&fs::read(path).await?)?;
+        let mut results = self.results.write().await;
+        *results = snapshot;
+        self.trim(&mut results, now_ms());
+        info!("[Results] restored {} result(s) from {:?}", results.len(), path);
+        Ok(results.len())
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_retention_and_query() {
+        let path = std::env::temp_dir().join(format!("eyeflow_results_{}.json", uuid::Uuid::new_v4()));
+        let store = ResultStore::new(3, 3600, Some(path.clone()));
+        for (i, wf) in ["a", "b", "a", "a"].iter().enumerate() {
+            store.record(json!({ "planId": wf, "durationMs": i })).await;
+        }
+
+        // Capacity 3: the first "a" was evicted; newest first
+        let all = store.query(None, 10).await;
+        assert_eq!(all.iter().map(|r| r["durationMs"].as_u64().unwrap()).collect::<Vec<_>>(), [3, 2, 1]);
+        assert!(all[0]["recordedAt"].is_string());
+        assert_eq!(store.query(Some("a"), 1).await[0]["durationMs"], 3);
+
+        let restored = ResultStore::new(3, 3600, Some(path.clone()));
+        assert_eq!(restored.load().await.unwrap(), 3);
+        assert_eq!(restored.query(Some("b"), 10).await.len(), 1);
+        let _ = std::fs::remove_file(path);
+    }
+}
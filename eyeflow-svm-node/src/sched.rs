@@ -0,0 +1,257 @@
+//! Slice scheduler — queued IR_DISTRIBUTIONs run by priority, not arrival
+//!
+//! The node runs one slice at a time.  Frames that arrive while it is busy
+//! are prepared and queued here; when the slice finishes, every frame
+//! already received is taken in before the next one is picked, so a
+//! critical plan overtakes a backlog of routine ones.
+//!
+//!   plan priority   the most urgent PriorityPolicy.priority_level among its
+//!                   instructions (0 = critical … 255 = lowest, also the
+//!                   level of a plan without any policy)
+//!   aging           every SVM_SCHED_AGING_MS spent waiting raises a slice
+//!                   one level (0 = no aging), so low-priority work is
+//!                   delayed, never starved; FIFO within a level
+//!   bound           SVM_SCHED_QUEUE_MAX queued slices; when full the least
+//!                   urgent one (possibly the newcomer) is refused with
+//!                   status QUEUE_FULL (QUOTA)
+//!
+//! Metrics per priority class (critical 0–63, high 64–127, normal 128–191,
+//! low 192–255):
+//!
+//!   eyeflow_sched_queued{class}                 slices waiting now
+//!   eyeflow_sched_enqueued_total{class}
+//!   eyeflow_sched_queue_position_sum{class}     slices ahead on arrival
+//!   eyeflow_sched_wait_ms_sum{class} / _max{class}
+//!   eyeflow_sched_aged_total{class}             run ahead of a more urgent
+//!                                               slice thanks to aging
+//!   eyeflow_sched_rejected_total{class}
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::arbiter::LOWEST_PRIORITY;
+use crate::config::Config;
+use crate::health::MetricsCollector;
+use crate::proto::llmir::LlmIntermediateRepresentation;
+
+const CLASSES: [&str; 4] = ["critical", "high", "normal", "low"];
+
+fn class_index(priority: u32) -> usize {
+    (priority.min(LOWEST_PRIORITY) / 64) as usize
+}
+
+/// Priority of a whole plan: its most urgent instruction's policy.
+pub fn plan_priority(ir: &LlmIntermediateRepresentation) -> u32 {
+    ir.instructions.values()
+        .filter_map(|i| i.priority_policy.as_ref())
+        .map(|p| p.priority_level.min(LOWEST_PRIORITY))
+        .min()
+        .unwrap_or(LOWEST_PRIORITY)
+}
+
+// ── Metrics ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default, Clone, Copy)]
+struct ClassStats {
+    queued: u64,
+    enqueued_total: u64,
+    position_sum: u64,
+    wait_ms_sum: u64,
+    wait_ms_max: u64,
+    aged_total: u64,
+    rejected_total: u64,
+}
+
+/// Metric name, type, help, and its value for one class.
+type Series = (&'static str, &'static str, &'static str, fn(&ClassStats) -> u64);
+
+#[derive(Debug, Default)]
+pub struct SchedStats {
+    classes: Mutex<[ClassStats; 4]>,
+}
+
+impl SchedStats {
+    fn update(&self, priority: u32, f: impl FnOnce(&mut ClassStats)) {
+        f(&mut self.classes.lock().unwrap_or_else(|e| e.into_inner())[class_index(priority)]);
+    }
+}
+
+impl MetricsCollector for SchedStats {
+    fn prometheus(&self, node_id: &str) -> String {
+        let classes = *self.classes.lock().unwrap_or_else(|e| e.into_inner());
+        let series: [Series; 7] = [
+            ("eyeflow_sched_queued", "gauge", "Slices waiting for their turn", |c| c.queued),
+            ("eyeflow_sched_enqueued_total", "counter", "Slices queued for execution", |c| c.enqueued_total),
+            ("eyeflow_sched_queue_position_sum", "counter", "Slices ahead of each queued slice on arrival", |c| c.position_sum),
+            ("eyeflow_sched_wait_ms_sum", "counter", "Time queued slices waited (ms)", |c| c.wait_ms_sum),
+            ("eyeflow_sched_wait_ms_max", "gauge", "Longest wait of a queued slice (ms)", |c| c.wait_ms_max),
+            ("eyeflow_sched_aged_total", "counter", "Slices run ahead of a more urgent one by aging", |c| c.aged_total),
+            ("eyeflow_sched_rejected_total", "counter", "Slices refused because the queue was full", |c| c.rejected_total),
+        ];
+        let mut out = String::new();
+        for (name, kind, help, value) in series {
+            out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+            for (class, stats) in CLASSES.iter().zip(&classes) {
+                out.push_str(&format!("{name}{{node_id=\"{node_id}\",class=\"{class}\"}} {}\n", value(stats)));
+            }
+        }
+        out
+    }
+}
+
+// ── Queue ─────────────────────────────────────────────────────────────────────
+
+struct Entry<T> {
+    item: T,
+    priority: u32,
+    seq: u64,
+    since: Instant,
+}
+
+pub struct SliceQueue<T> {
+    entries: Vec<Entry<T>>,
+    next_seq: u64,
+    max: usize,
+    /// Wait that buys one priority level (None = no aging)
+    aging: Option<Duration>,
+    stats: Arc<SchedStats>,
+}
+
+impl<T> SliceQueue<T> {
+    pub fn new(max: usize, aging: Option<Duration>) -> Self {
+        Self { entries: Vec::new(), next_seq: 0, max: max.max(1), aging, stats: Arc::default() }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        let aging = (config.sched_aging_ms > 0).then(|| Duration::from_millis(config.sched_aging_ms));
+        Self::new(config.sched_queue_max, aging)
+    }
+
+    pub fn stats(&self) -> Arc<SchedStats> {
+        self.stats.clone()
+    }
+
+    /// Priority of `e` at `now`, aging included (lower runs first).
+    fn effective(&self, e: &Entry<T>, now: Instant) -> u32 {
+        let levels = self.aging.map_or(0, |step| {
+            (now.saturating_duration_since(e.since).as_millis() / step.as_millis().max(1)) as u32
+        });
+        e.priority.saturating_sub(levels)
+    }
+
+    /// Scheduling order: effective priority, then arrival.
+    fn rank(&self, e: &Entry<T>, now: Instant) -> (u32, u64) {
+        (self.effective(e, now), e.seq)
+    }
+
+    pub fn push(&mut self, item: T, priority: u32) -> Option<T> {
+        self.push_at(item, priority, Instant::now())
+    }
+
+    /// Queue `item`; returns the slice turned away when the queue is full
+    /// (the least urgent, which may be `item` itself).
+    pub fn push_at(&mut self, item: T, priority: u32, now: Instant) -> Option<T> {
+        let priority = priority.min(LOWEST_PRIORITY);
+        let mut refused = None;
+        if self.entries.len() >= self.max {
+            let worst = (0..self.entries.len()).max_by_key(|&i| self.rank(&self.entries[i], now))?;
+            if self.effective(&self.entries[worst], now) <= priority {
+                self.stats.update(priority, |c| c.rejected_total += 1);
+                return Some(item);
+            }
+            let evicted = self.entries.remove(worst);
+            self.stats.update(evicted.priority, |c| {
+                c.rejected_total += 1;
+                c.queued -= 1;
+            });
+            refused = Some(evicted.item);
+        }
+        let ahead = self.entries.iter().filter(|e| self.effective(e, now) <= priority).count() as u64;
+        self.stats.update(priority, |c| {
+            c.queued += 1;
+            c.enqueued_total += 1;
+            c.position_sum += ahead;
+        });
+        self.entries.push(Entry { item, priority, seq: self.next_seq, since: now });
+        self.next_seq += 1;
+        refused
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.pop_at(Instant::now())
+    }
+
+    /// Take the most urgent slice (aging included), oldest first within a level.
+    pub fn pop_at(&mut self, now: Instant) -> Option<T> {
+        let next = (0..self.entries.len()).min_by_key(|&i| self.rank(&self.entries[i], now))?;
+        let entry = self.entries.remove(next);
+        let aged = self.entries.iter().any(|e| e.priority < entry.priority);
+        let waited = now.saturating_duration_since(entry.since).as_millis() as u64;
+        self.stats.update(entry.priority, |c| {
+            c.queued -= 1;
+            c.wait_ms_sum += waited;
+            c.wait_ms_max = c.wait_ms_max.max(waited);
+            if aged {
+                c.aged_total += 1;
+            }
+        });
+        Some(entry.item)
+    }
+
+    /// Drop everything queued (the session they came on is gone).
+    pub fn clear(&mut self) -> usize {
+        let n = self.entries.len();
+        for e in self.entries.drain(..) {
+            self.stats.update(e.priority, |c| c.queued -= 1);
+        }
+        n
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority_aging_and_bound() {
+        let t0 = Instant::now();
+        let ms = |n| t0 + Duration::from_millis(n);
+        let mut queue = SliceQueue::new(3, Some(Duration::from_millis(10)));
+
+        assert!(queue.push_at("routine-1", 200, t0).is_none());
+        assert!(queue.push_at("routine-2", 200, t0).is_none());
+        assert!(queue.push_at("critical", 10, ms(5)).is_none());
+        // Full: a low newcomer is refused, an urgent one evicts the newest routine slice
+        assert_eq!(queue.push_at("bulk", 255, ms(5)), Some("bulk"));
+        assert_eq!(queue.push_at("high", 100, ms(5)), Some("routine-2"));
+        assert_eq!(queue.pop_at(ms(6)), Some("critical"));
+
+        // At 1.5 s "high" is still ahead (level 0 vs 50); by 2.1 s routine-1
+        // has aged to level 0 as well and, having arrived first, goes next
+        assert_eq!(queue.push_at("high-2", 100, ms(5)), None);
+        assert_eq!(queue.pop_at(ms(1_500)), Some("high"));
+        assert_eq!(queue.pop_at(ms(2_100)), Some("routine-1"));
+        assert_eq!(queue.pop_at(ms(2_100)), Some("high-2"));
+        assert!(queue.is_empty() && queue.pop().is_none());
+
+        let metrics = queue.stats().prometheus("n");
+        assert!(metrics.contains("eyeflow_sched_rejected_total{node_id=\"n\",class=\"low\"} 2"), "{metrics}");
+        assert!(metrics.contains("eyeflow_sched_aged_total{node_id=\"n\",class=\"low\"} 1"));
+        assert!(metrics.contains("eyeflow_sched_queue_position_sum{node_id=\"n\",class=\"critical\"} 0"));
+        assert!(metrics.contains("eyeflow_sched_queued{node_id=\"n\",class=\"high\"} 0"));
+        assert!(metrics.contains("eyeflow_sched_wait_ms_max{node_id=\"n\",class=\"low\"} 2100"));
+
+        queue.push_at("x", 0, t0);
+        assert_eq!((queue.len(), queue.clear()), (1, 1));
+    }
+}
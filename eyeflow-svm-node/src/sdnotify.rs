@@ -0,0 +1,131 @@
+//! systemd integration — Type=notify + WatchdogSec
+//!
+//!   READY=1      after the first successful REGISTER
+//!   STATUS=…     connection state, shown by `systemctl status`
+//!   WATCHDOG=1   pets from the node's main loops (message loop, reconnect wait)
+//!   STOPPING=1   on SIGTERM / SIGINT, before the graceful drain
+//!
+//! Pets come from the loops themselves rather than a timer task, so a wedged
+//! executor stops petting and systemd restarts the node.  A slice runs inside
+//! the message loop: WatchdogSec must exceed the longest expected slice.
+//!
+//! Everything is a no-op when NOTIFY_SOCKET is unset (not run by systemd).
+//!
+//! Example unit:
+//!
+//!   [Service]
+//!   Type=notify
+//!   NotifyAccess=main
+//!   WatchdogSec=60
+//!   Restart=on-failure
+
+use std::time::Duration;
+use tracing::debug;
+
+/// Send `state` to the service manager; false when not supervised.
+pub fn notify(state: &str) -> bool {
+    let Some(socket) = std::env::var_os("NOTIFY_SOCKET") else { return false };
+    match send(&socket, state) {
+        Ok(()) => true,
+        Err(e) => {
+            debug!("[sd_notify] {state:?} not delivered: {e}");
+            false
+        }
+    }
+}
+
+#[cfg(unix)]
+fn send(socket: &std::ffi::OsStr, state: &str) -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::net::UnixDatagram;
+
+    let sock = UnixDatagram::unbound()?;
+    let path = socket.as_bytes();
+    match path.strip_prefix(b"@") {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            sock.send_to_addr(state.as_bytes(), &addr)?;
+        }
+        _ => {
+            sock.send_to(state.as_bytes(), socket)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send(_socket: &std::ffi::OsStr, _state: &str) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+pub fn ready(status: &str) {
+    notify(&format!("READY=1\nSTATUS={status}"));
+}
+
+pub fn status(status: &str) {
+    notify(&format!("STATUS={status}"));
+}
+
+pub fn stopping() {
+    notify("STOPPING=1\nSTATUS=draining");
+}
+
+pub fn watchdog() {
+    notify("WATCHDOG=1");
+}
+
+/// How often to pet: half of WatchdogSec, or None when the watchdog is off
+/// (or WATCHDOG_PID names another process).
+pub fn watchdog_interval() -> Option<Duration> {
+    parse_watchdog(
+        std::env::var("WATCHDOG_USEC").ok().as_deref(),
+        std::env::var("WATCHDOG_PID").ok().as_deref(),
+        std::process::id(),
+    )
+}
+
+fn parse_watchdog(usec: Option<&str>, pid: Option<&str>, own_pid: u32) -> Option<Duration> {
+    let usec: u64 = usec?.parse().ok().filter(|u| *u > 0)?;
+    if pid.and_then(|p| p.parse::<u32>().ok()).is_some_and(|p| p != own_pid) {
+        return None;
+    }
+    Some(Duration::from_micros(usec / 2))
+}
+
+/// Ticker for the pet arm of a loop's `select!` (hourly no-op when off).
+pub fn pet_ticker() -> tokio::time::Interval {
+    let period = watchdog_interval().unwrap_or(Duration::from_secs(3600));
+    let mut t = tokio::time::interval(period);
+    t.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    t
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog_interval() {
+        assert_eq!(parse_watchdog(Some("30000000"), None, 7), Some(Duration::from_secs(15)));
+        assert_eq!(parse_watchdog(Some("30000000"), Some("7"), 7), Some(Duration::from_secs(15)));
+        assert_eq!(parse_watchdog(Some("30000000"), Some("8"), 7), None);
+        assert_eq!(parse_watchdog(Some("0"), None, 7), None);
+        assert_eq!(parse_watchdog(None, None, 7), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_send_datagram() {
+        let path = std::env::temp_dir().join(format!("eyeflow_notify_{}.sock", uuid::Uuid::new_v4()));
+        let server = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+        send(path.as_os_str(), "READY=1").unwrap();
+        let mut buf = [0u8; 64];
+        let n = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"READY=1");
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,102 @@
+//! Secret usage audit — which instruction read which credential
+//!
+//! With SVM_AUDIT_SECRET_ACCESS=true every secret lookup made while an
+//! instruction runs (credentials_vault_path, vault dynamic slots,
+//! `{{secret:…}}` templates) is appended to the audit chain after it:
+//!
+//!   eventType   SECRET_ACCESS
+//!   instructionId  the requesting instruction
+//!   details     { "path": "sap/api_key", "source": "vault" | "env" | "rawEnv",
+//!                 "cacheHit": false, "ok": true, "opcode": "CALL_SERVICE" }
+//!
+//! `source` is the tier that answered (absent when none did).  The value
+//! itself is never recorded, hashed or logged.  Lookups outside an
+//! instruction (metrics push credentials) are not audited.
+
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use crate::vault::SecretSource;
+
+tokio::task_local! {
+    /// Secret lookups of the instruction being run.
+    static ACCESSES: Arc<Mutex<Vec<SecretAccess>>>;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretAccess {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<&'static str>,
+    pub cache_hit: bool,
+    pub ok: bool,
+}
+
+impl SecretSource {
+    fn tier(&self) -> &'static str {
+        match self {
+            Self::HashiCorpVault => "vault",
+            Self::EnvVar => "env",
+            Self::RawEnvKey => "rawEnv",
+        }
+    }
+}
+
+/// Note a lookup (called by VaultClient; no-op outside `tracked`).
+pub fn note(path: &str, source: Option<&SecretSource>, cache_hit: bool) {
+    let _ = ACCESSES.try_with(|a| {
+        if let Ok(mut a) = a.lock() {
+            a.push(SecretAccess {
+                path: path.to_owned(),
+                source: source.map(SecretSource::tier),
+                cache_hit,
+                ok: source.is_some(),
+            });
+        }
+    });
+}
+
+/// Run `call`, returning its output and the secret lookups it made.
+pub async fn tracked<F: Future>(call: F) -> (F::Output, Vec<SecretAccess>) {
+    let found = Arc::new(Mutex::new(Vec::new()));
+    let out = ACCESSES.scope(found.clone(), call).await;
+    let found = std::mem::take(&mut *found.lock().unwrap_or_else(|e| e.into_inner()));
+    (out, found)
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditChain;
+    use crate::vault::VaultClient;
+
+    #[tokio::test]
+    async fn test_lookups_are_audited_without_values() {
+        std::env::set_var("VAULT_SECRET_PLANT_AUDITED_KEY", "hunter2");
+        let vault = VaultClient::new(reqwest::Client::new(), None, None, None);
+
+        let (resolved, accesses) = tracked(async {
+            let slots = vault.resolve_slots(&[("key", "plant/audited_key"), ("missing", "plant/absent_key")]).await;
+            vault.inject_into_template("Bearer {{secret:plant/audited_key}}").await.unwrap();
+            slots
+        }).await;
+        assert_eq!(resolved["key"], "hunter2");
+        assert_eq!(accesses.len(), 3);
+        assert_eq!(accesses[0], SecretAccess { path: "plant/audited_key".into(), source: Some("env"), cache_hit: false, ok: true });
+        assert_eq!((accesses[1].ok, accesses[1].source), (false, None));
+
+        // Not scoped: nothing collected, nothing panics
+        vault.fetch_secret("plant/audited_key").await.unwrap();
+
+        let mut chain = AuditChain::new("n".into(), None).unwrap();
+        for a in &accesses {
+            chain.append("wf", None, Some("3"), "SECRET_ACCESS", None, None, 0, Some(serde_json::to_value(a).unwrap()));
+        }
+        let events = serde_json::to_string(&chain.drain()).unwrap();
+        assert!(events.contains("plant/audited_key") && !events.contains("hunter2"));
+    }
+}
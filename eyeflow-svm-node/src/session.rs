@@ -0,0 +1,245 @@
+//! Session resume — central learns what it missed without a full replay
+//!
+//! Every RESULT the node sends (text or binary) is kept, in its JSON view,
+//! until central acknowledges it:
+//!
+//!   Central → Node   { "type": "RESULT_ACK", "payload": { "sliceIds": [...] } }
+//!
+//! After a reconnect, REGISTER carries
+//!
+//!   "resume": { "sessionId", "lastAcked": [sliceId, ...],      (oldest first)
+//!               "unacked": [sliceId, ...], "bufferedResults": n }
+//!
+//! (`bufferedResults` = execution results waiting in the offline buffer)
+//! and central answers
+//!
+//!   Central → Node   { "type": "RESUME", "payload": { "resend": [sliceId, ...] } }
+//!
+//! The listed results are sent again as RESULT frames (`resent: true`) and
+//! stay unacked; every other unacked result is taken as processed and
+//! dropped, so central never handles a slice twice.  A central that never
+//! acks just lets the ledger roll over.  `sessionId` survives restarts when
+//! the ledger is persisted.
+//!
+//!   SVM_SESSION_PATH            ledger snapshot (unset = memory only)
+//!   SVM_SESSION_MAX_UNACKED     unacked results kept, oldest dropped (default 1000)
+//!
+//!   eyeflow_session_unacked_results
+//!   eyeflow_session_resent_results_total
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::fs;
+use tracing::{debug, info};
+
+use crate::health::MetricsCollector;
+
+/// Acknowledged slice ids reported in REGISTER.
+const LAST_ACKED: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Unacked {
+    slice_id: String,
+    result: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Ledger {
+    session_id: String,
+    /// Sent and not yet acknowledged, oldest first
+    unacked: VecDeque<Unacked>,
+    last_acked: VecDeque<String>,
+}
+
+impl Default for Ledger {
+    fn default() -> Self {
+        Self {
+            session_id: uuid::Uuid::new_v4().to_string(),
+            unacked: VecDeque::new(),
+            last_acked: VecDeque::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SessionLedger {
+    ledger: Mutex<Ledger>,
+    max_unacked: usize,
+    path: Option<PathBuf>,
+    resent: AtomicU64,
+}
+
+impl SessionLedger {
+    pub fn new(max_unacked: usize, path: Option<PathBuf>) -> Arc<Self> {
+        Arc::new(Self {
+            ledger: Mutex::new(Ledger::default()),
+            max_unacked,
+            path,
+            resent: AtomicU64::new(0),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Ledger> {
+        self.ledger.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Remember `result` (a SliceExecutionResult as JSON) until central acks its sliceId.
+    pub fn sent(&self, result: &Value) {
+        let Some(slice_id) = result.get("sliceId").and_then(|s| s.as_str()) else { return };
+        if self.max_unacked == 0 {
+            return;
+        }
+        let mut ledger = self.lock();
+        if ledger.unacked.iter().any(|u| u.slice_id == slice_id) {
+            return;
+        }
+        if ledger.unacked.len() >= self.max_unacked {
+            if let Some(dropped) = ledger.unacked.pop_front() {
+                debug!("[Session] {} unacked results — forgetting slice {}", self.max_unacked, dropped.slice_id);
+            }
+        }
+        ledger.unacked.push_back(Unacked { slice_id: slice_id.to_owned(), result: result.clone() });
+    }
+
+    /// RESULT_ACK: drop the acknowledged results; returns how many were known.
+    pub fn ack<'a>(&self, slice_ids: impl IntoIterator<Item = &'a str>) -> usize {
+        let mut ledger = self.lock();
+        let mut known = 0;
+        for id in slice_ids {
+            if let Some(i) = ledger.unacked.iter().position(|u| u.slice_id == id) {
+                ledger.unacked.remove(i);
+                known += 1;
+            }
+            if ledger.last_acked.len() >= LAST_ACKED {
+                ledger.last_acked.pop_front();
+            }
+            ledger.last_acked.push_back(id.to_owned());
+        }
+        known
+    }
+
+    /// REGISTER `resume` block.
+    pub fn resume_payload(&self, buffered_results: usize) -> Value {
+        let ledger = self.lock();
+        json!({
+            "sessionId": ledger.session_id,
+            "lastAcked": ledger.last_acked,
+            "unacked": ledger.unacked.iter().map(|u| &u.slice_id).collect::<Vec<_>>(),
+            "bufferedResults": buffered_results,
+        })
+    }
+
+    /// RESUME: the results central asked for again (marked `resent`), in
+    /// send order; all other unacked results are dropped.
+    pub fn resume<'a>(&self, resend: impl IntoIterator<Item = &'a str>) -> Vec<Value> {
+        let wanted: std::collections::HashSet<&str> = resend.into_iter().collect();
+        let mut ledger = self.lock();
+        let before = ledger.unacked.len();
+        ledger.unacked.retain(|u| wanted.contains(u.slice_id.as_str()));
+        info!(
+            "[Session] resume: resending {} result(s), {} already processed by central",
+            ledger.unacked.len(), before - ledger.unacked.len()
+        );
+        self.resent.fetch_add(ledger.unacked.len() as u64, Ordering::Relaxed);
+        ledger.unacked.iter().map(|u| {
+            let mut result = u.result.clone();
+            result["resent"] = json!(true);
+            result
+        }).collect()
+    }
+
+    pub fn unacked(&self) -> usize {
+        self.lock().unacked.len()
+    }
+
+    // ── Persistence ───────────────────────────────────────────────────────────
+
+    /// Snapshot the ledger to disk (no-op when persistence is disabled).
+    pub async fn persist(&self) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let bytes = serde_json::to_vec(&*self.lock())?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, bytes).await?;
+        fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+
+    /// Restore the ledger of a previous run; returns its unacked results.
+    pub async fn load(&self) -> Result<usize> {
+        let Some(path) = &self.path else { return Ok(0) };
+        if !path.exists() {
+            return Ok(0);
+        }
+        let mut snapshot: Ledger = serde_json::from_slice(&fs::read(path).await?)?;
+        while snapshot.unacked.len() > self.max_unacked {
+            snapshot.unacked.pop_front();
+        }
+        let n = snapshot.unacked.len();
+        info!("[Session] resumed session {} ({n} unacked result(s)) from {:?}", snapshot.session_id, path);
+        *self.lock() = snapshot;
+        Ok(n)
+    }
+}
+
+impl MetricsCollector for SessionLedger {
+    fn prometheus(&self, node_id: &str) -> String {
+        format!(
+            "# HELP eyeflow_session_unacked_results Results sent to central and not acknowledged yet\n\
+             # TYPE eyeflow_session_unacked_results gauge\n\
+             eyeflow_session_unacked_results{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_session_resent_results_total Results sent again on central's RESUME\n\
+             # TYPE eyeflow_session_resent_results_total counter\n\
+             eyeflow_session_resent_results_total{{node_id=\"{node_id}\"}} {}\n",
+            self.unacked(),
+            self.resent.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(slice: &str) -> Value {
+        json!({ "planId": "wf", "sliceId": slice, "status": "SUCCESS" })
+    }
+
+    #[tokio::test]
+    async fn test_ack_resume_and_restore() {
+        let path = std::env::temp_dir().join(format!("eyeflow_session_{}.json", uuid::Uuid::new_v4()));
+        let session = SessionLedger::new(3, Some(path.clone()));
+        for slice in ["s1", "s2", "s3", "s4"] {
+            session.sent(&result(slice));
+        }
+        session.sent(&result("s4"));
+        session.sent(&json!({ "status": "SUCCESS" }));
+        // Capacity 3: s1 rolled over
+        assert_eq!(session.ack(["s2", "s1"]), 1);
+        let resume = session.resume_payload(5);
+        assert_eq!(resume["unacked"], json!(["s3", "s4"]));
+        assert_eq!(resume["lastAcked"], json!(["s2", "s1"]));
+        assert_eq!(resume["bufferedResults"], 5);
+
+        session.persist().await.unwrap();
+        let restored = SessionLedger::new(3, Some(path.clone()));
+        assert_eq!(restored.load().await.unwrap(), 2);
+        assert_eq!(restored.resume_payload(0)["sessionId"], resume["sessionId"]);
+
+        // Central has s3 already: only s4 goes out again, and stays unacked
+        let resend = restored.resume(["s4", "s9"]);
+        assert_eq!(resend.len(), 1);
+        assert_eq!((resend[0]["sliceId"].as_str(), resend[0]["resent"].as_bool()), (Some("s4"), Some(true)));
+        assert_eq!(restored.unacked(), 1);
+        assert!(restored.prometheus("n").contains("eyeflow_session_resent_results_total{node_id=\"n\"} 1"));
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,214 @@
+//! Outbound bandwidth shaping for constrained (cellular / satellite) links
+//!
+//!   SVM_UPLINK_BYTES_PER_SEC   token-bucket rate (0 = unlimited, the default)
+//!   SVM_UPLINK_BURST_BYTES     bucket size (default one second of rate)
+//!   SVM_UPLINK_FLUSH_CHUNK     events per AUDIT_FLUSH frame while shaped (default 200)
+//!   SVM_UPLINK_BULK_MIN_BYTES  HTTP bodies this large are shaped (default 16384)
+//!
+//! One bucket covers everything the node sends upstream:
+//!
+//!   interactive   REGISTER, RESULT (text and binary), PONG, ROLE
+//!   bulk          AUDIT_FLUSH, ACTIONS_IN_DOUBT, CALL_SERVICE / CALL_ACTION
+//!                 request bodies of at least SVM_UPLINK_BULK_MIN_BYTES
+//!
+//! Bulk senders yield while an interactive one is waiting for tokens, so a
+//! small RESULT overtakes a backlog flush.  Preemption is per WebSocket
+//! message: while shaped, the offline flush goes out as several AUDIT_FLUSH
+//! frames (header gains `part` / `parts`, `count` stays the total) sent
+//! between incoming frames.  A message larger than the bucket leaves when it
+//! is full and puts the bucket in debt.  WebSocket Ping / Pong control
+//! frames are not shaped.
+//!
+//! /metrics: eyeflow_uplink_bytes_total{class} and
+//! eyeflow_uplink_wait_seconds_total{class}.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::config::Config;
+use crate::health::MetricsCollector;
+
+/// How often a bulk sender checks again while yielding.
+const YIELD_POLL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    Interactive,
+    Bulk,
+}
+
+impl Priority {
+    fn index(self) -> usize {
+        match self {
+            Self::Interactive => 0,
+            Self::Bulk => 1,
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    at: Instant,
+}
+
+/// Counts an interactive sender as waiting until dropped.
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl<'a> Waiting<'a> {
+    fn new(count: &'a AtomicUsize) -> Self {
+        count.fetch_add(1, Ordering::SeqCst);
+        Self(count)
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub struct Shaper {
+    /// Bytes per second
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+    interactive_waiting: AtomicUsize,
+    flush_chunk: usize,
+    bulk_min_bytes: usize,
+    bytes: [AtomicU64; 2],
+    waited_ms: [AtomicU64; 2],
+}
+
+impl Shaper {
+    pub fn new(bytes_per_sec: u64, burst_bytes: u64) -> Self {
+        let burst = if burst_bytes == 0 { bytes_per_sec } else { burst_bytes } as f64;
+        Self {
+            rate: bytes_per_sec as f64,
+            burst,
+            bucket: Mutex::new(Bucket { tokens: burst, at: Instant::now() }),
+            interactive_waiting: AtomicUsize::new(0),
+            flush_chunk: 200,
+            bulk_min_bytes: 16 * 1024,
+            bytes: Default::default(),
+            waited_ms: Default::default(),
+        }
+    }
+
+    /// SVM_UPLINK_* (None when SVM_UPLINK_BYTES_PER_SEC is 0).
+    pub fn from_config(config: &Config) -> Option<Arc<Self>> {
+        if config.uplink_bytes_per_sec == 0 {
+            return None;
+        }
+        let mut shaper = Self::new(config.uplink_bytes_per_sec, config.uplink_burst_bytes);
+        shaper.flush_chunk = config.uplink_flush_chunk.max(1);
+        shaper.bulk_min_bytes = config.uplink_bulk_min_bytes;
+        Some(Arc::new(shaper))
+    }
+
+    /// Events per AUDIT_FLUSH frame.
+    pub fn flush_chunk(&self) -> usize {
+        self.flush_chunk
+    }
+
+    /// Whether an HTTP body of `bytes` is shaped.
+    pub fn shapes_upload(&self, bytes: usize) -> bool {
+        bytes >= self.bulk_min_bytes
+    }
+
+    /// Wait until `bytes` may be sent.
+    pub async fn acquire(&self, bytes: usize, priority: Priority) {
+        let started = Instant::now();
+        let _waiting = (priority == Priority::Interactive).then(|| Waiting::new(&self.interactive_waiting));
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+                let now = Instant::now();
+                bucket.tokens = (bucket.tokens + now.duration_since(bucket.at).as_secs_f64() * self.rate).min(self.burst);
+                bucket.at = now;
+                let need = (bytes as f64).min(self.burst);
+                if priority == Priority::Bulk && self.interactive_waiting.load(Ordering::SeqCst) > 0 {
+                    YIELD_POLL
+                } else if bucket.tokens >= need {
+                    bucket.tokens -= bytes as f64;
+                    break;
+                } else {
+                    Duration::from_secs_f64((need - bucket.tokens) / self.rate)
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+        let i = priority.index();
+        self.bytes[i].fetch_add(bytes as u64, Ordering::Relaxed);
+        self.waited_ms[i].fetch_add(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+impl MetricsCollector for Shaper {
+    fn prometheus(&self, node_id: &str) -> String {
+        let mut out = String::from(
+            "# HELP eyeflow_uplink_bytes_total Bytes sent through the uplink shaper by class\n\
+             # TYPE eyeflow_uplink_bytes_total counter\n",
+        );
+        let classes = [("interactive", Priority::Interactive), ("bulk", Priority::Bulk)];
+        for (class, p) in classes {
+            out.push_str(&format!(
+                "eyeflow_uplink_bytes_total{{node_id=\"{node_id}\",class=\"{class}\"}} {}\n",
+                self.bytes[p.index()].load(Ordering::Relaxed),
+            ));
+        }
+        out.push_str(
+            "# HELP eyeflow_uplink_wait_seconds_total Time spent waiting for uplink tokens by class\n\
+             # TYPE eyeflow_uplink_wait_seconds_total counter\n",
+        );
+        for (class, p) in classes {
+            out.push_str(&format!(
+                "eyeflow_uplink_wait_seconds_total{{node_id=\"{node_id}\",class=\"{class}\"}} {:.3}\n",
+                self.waited_ms[p.index()].load(Ordering::Relaxed) as f64 / 1000.0,
+            ));
+        }
+        out
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_interactive_overtakes_bulk() {
+        let shaper = Arc::new(Shaper::new(1_000, 1_000));
+        // Empty the bucket: the next 1000 bytes take a second
+        shaper.acquire(1_000, Priority::Bulk).await;
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let bulk = {
+            let (shaper, order) = (shaper.clone(), order.clone());
+            tokio::spawn(async move {
+                shaper.acquire(500, Priority::Bulk).await;
+                order.lock().unwrap().push("bulk");
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let result = {
+            let (shaper, order) = (shaper.clone(), order.clone());
+            tokio::spawn(async move {
+                shaper.acquire(200, Priority::Interactive).await;
+                order.lock().unwrap().push("result");
+            })
+        };
+        let start = tokio::time::Instant::now();
+        result.await.unwrap();
+        bulk.await.unwrap();
+        assert_eq!(*order.lock().unwrap(), ["result", "bulk"]);
+        // 200 + 500 bytes at 1000 B/s, from an empty bucket
+        assert!(start.elapsed() >= Duration::from_millis(550));
+
+        let metrics = shaper.prometheus("n");
+        assert!(metrics.contains("eyeflow_uplink_bytes_total{node_id=\"n\",class=\"bulk\"} 1500"));
+        assert!(metrics.contains("eyeflow_uplink_bytes_total{node_id=\"n\",class=\"interactive\"} 200"));
+    }
+}
@@ -0,0 +1,359 @@
+//! Warm standby — two nodes, one executor (SVM_STANDBY=true)
+//!
+//! Critical sites run a pair of node processes on separate hosts sharing a
+//! SVM_STANDBY_GROUP.  Exactly one of them, the leader, executes slices; the
+//! other answers IR_DISTRIBUTION with status "STANDBY" so central re-routes.
+//! Leadership is a lease (SVM_STANDBY_LEASE):
+//!
+//!   redis    Redlock lease "eyeflow:lock:leader:{group}" (SVM_DLOCK_REDIS_URLS)
+//!   file     lease file on a shared volume (SVM_STANDBY_LEASE_PATH) holding
+//!            { holder, expiresAtMs } — relies on the hosts' clocks agreeing
+//!   central  central grants it: { "type": "LEADER", "payload": { "leader": true, "ttlMs": n } }
+//!            and must re-send the grant before it expires
+//!
+//! A node only competes while its central connection is up, and a leader
+//! whose connection has been down for longer than the lease TTL lets the
+//! lease go — so failover happens both when the leader dies and when its
+//! health lapses.
+//!
+//! Handoff: every verified artifact either node receives is kept (encoded
+//! LLM-IR, last one per workflow) and mirrored to SVM_STANDBY_HANDOFF_DIR.
+//! On promotion the new leader reloads that directory, and every role change
+//! is announced to central:
+//!
+//!   { "type": "ROLE", "payload": { group, role: "LEADER"|"STANDBY", artifacts: [workflowId…] } }
+
+use anyhow::{anyhow, Result};
+use prost::Message as ProstMessage;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::fs;
+use tokio::sync::{watch, RwLock};
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::dlock::DistributedLock;
+use crate::health::{HealthState, MetricsCollector};
+use crate::proto::llmir::LlmIntermediateRepresentation;
+
+pub enum LeaseBackend {
+    Redis(Arc<DistributedLock>),
+    File(PathBuf),
+    Central,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileLease {
+    holder: String,
+    expires_at_ms: i64,
+}
+
+pub struct Standby {
+    node_id: String,
+    group: String,
+    ttl: Duration,
+    backend: LeaseBackend,
+    leader: watch::Sender<bool>,
+    /// Expiry of central's LEADER grant (central backend)
+    grant_until: std::sync::Mutex<Option<Instant>>,
+    /// Encoded IR per workflow id
+    artifacts: RwLock<BTreeMap<String, Vec<u8>>>,
+    handoff_dir: Option<PathBuf>,
+}
+
+impl Standby {
+    pub fn new(config: &Config) -> Result<Arc<Self>> {
+        let backend = match config.standby_lease.as_str() {
+            "redis" => LeaseBackend::Redis(
+                DistributedLock::new(&config.dlock_redis_urls, config.standby_lease_ttl_ms)?,
+            ),
+            "file" => LeaseBackend::File(PathBuf::from(
+                config.standby_lease_path.as_deref()
+                    .ok_or_else(|| anyhow!("SVM_STANDBY_LEASE=file needs SVM_STANDBY_LEASE_PATH"))?,
+            )),
+            "central" => LeaseBackend::Central,
+            other => return Err(anyhow!("unknown SVM_STANDBY_LEASE '{other}' (redis | file | central)")),
+        };
+        info!(
+            "[Standby] group={} lease={} ttl={}ms — starting as STANDBY",
+            config.standby_group, config.standby_lease, config.standby_lease_ttl_ms
+        );
+        Ok(Arc::new(Self {
+            node_id: config.node_id.clone(),
+            group: config.standby_group.clone(),
+            ttl: Duration::from_millis(config.standby_lease_ttl_ms.max(1_000)),
+            backend,
+            leader: watch::channel(false).0,
+            grant_until: std::sync::Mutex::new(None),
+            artifacts: RwLock::new(BTreeMap::new()),
+            handoff_dir: config.standby_handoff_dir.as_ref().map(PathBuf::from),
+        }))
+    }
+
+    pub fn is_leader(&self) -> bool {
+        *self.leader.borrow()
+    }
+
+    /// Notified on every role change.
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.leader.subscribe()
+    }
+
+    /// Apply a LEADER frame from central (central backend only).
+    pub fn grant(&self, payload: &Value) {
+        if !matches!(self.backend, LeaseBackend::Central) {
+            warn!("[Standby] LEADER frame ignored — lease backend is not 'central'");
+            return;
+        }
+        if payload.get("group").and_then(|g| g.as_str()).is_some_and(|g| g != self.group) {
+            return;
+        }
+        let leader = payload.get("leader").and_then(|v| v.as_bool()).unwrap_or(false);
+        let ttl = payload.get("ttlMs").and_then(|v| v.as_u64())
+            .map(Duration::from_millis)
+            .unwrap_or(self.ttl);
+        *self.grant_until.lock().unwrap_or_else(|e| e.into_inner()) = leader.then(|| Instant::now() + ttl);
+    }
+
+    /// The ROLE frame payload.
+    pub async fn role_payload(&self) -> Value {
+        json!({
+            "group": self.group,
+            "role": if self.is_leader() { "LEADER" } else { "STANDBY" },
+            "artifacts": self.artifacts.read().await.keys().collect::<Vec<_>>(),
+        })
+    }
+
+    // ── Artifact handoff ──────────────────────────────────────────────────────
+
+    /// Keep `ir` for `workflow_id` and mirror it to the handoff directory.
+    pub async fn remember(&self, workflow_id: &str, ir: &LlmIntermediateRepresentation) {
+        let bytes = ir.encode_to_vec();
+        if let Some(dir) = &self.handoff_dir {
+            let path = dir.join(format!("{}.ir", hex::encode(workflow_id)));
+            let tmp = path.with_extension("ir.tmp");
+            let res = async {
+                fs::create_dir_all(dir).await?;
+                fs::write(&tmp, &bytes).await?;
+                fs::rename(&tmp, &path).await
+            }.await;
+            if let Err(e) = res {
+                warn!("[Standby] failed to mirror artifact for {workflow_id}: {e}");
+            }
+        }
+        self.artifacts.write().await.insert(workflow_id.to_owned(), bytes);
+    }
+
+    /// Reload artifacts mirrored by the previous leader.
+    async fn load_handoff(&self) -> Result<usize> {
+        let Some(dir) = &self.handoff_dir else { return Ok(0) };
+        if !dir.exists() {
+            return Ok(0);
+        }
+        let mut loaded = 0;
+        let mut entries = fs::read_dir(dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("ir") {
+                continue;
+            }
+            let id = path.file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| hex::decode(s).ok())
+                .and_then(|b| String::from_utf8(b).ok());
+            let Some(id) = id else { continue };
+            let bytes = fs::read(&path).await?;
+            if LlmIntermediateRepresentation::decode(bytes.as_slice()).is_ok() {
+                self.artifacts.write().await.insert(id, bytes);
+                loaded += 1;
+            }
+        }
+        Ok(loaded)
+    }
+
+    // ── Role transitions ──────────────────────────────────────────────────────
+
+    async fn promote(&self) {
+        if self.is_leader() {
+            return;
+        }
+        match self.load_handoff().await {
+            Ok(n) => info!("[Standby] promoted to LEADER of '{}' ({n} handed-off artifact(s))", self.group),
+            Err(e) => warn!("[Standby] promoted to LEADER of '{}' — handoff load failed: {e}", self.group),
+        }
+        self.leader.send_replace(true);
+    }
+
+    fn demote(&self, why: &str) {
+        if self.is_leader() {
+            warn!("[Standby] stepping down in '{}': {why}", self.group);
+            self.leader.send_replace(false);
+        }
+    }
+}
+
+// ── Election loop ─────────────────────────────────────────────────────────────
+
+/// Tracks how long the central connection has been down.
+struct HealthWatch {
+    health: Arc<HealthState>,
+    down_since: Option<Instant>,
+}
+
+impl HealthWatch {
+    fn connected(&mut self) -> bool {
+        let up = self.health.ws_connected.load(std::sync::atomic::Ordering::Relaxed);
+        if up {
+            self.down_since = None;
+        } else {
+            self.down_since.get_or_insert_with(Instant::now);
+        }
+        up
+    }
+
+    /// Down for longer than `ttl` — the leader should let the lease go.
+    fn lapsed(&mut self, ttl: Duration) -> bool {
+        !self.connected() && self.down_since.is_some_and(|t| t.elapsed() > ttl)
+    }
+}
+
+/// Compete for and hold leadership of the standby group, forever.
+pub async fn run_election(standby: Arc<Standby>, health: Arc<HealthState>) {
+    let mut hw = HealthWatch { health, down_since: None };
+    let tick = standby.ttl / 3;
+    loop {
+        match &standby.backend {
+            LeaseBackend::Redis(lock) => {
+                if !hw.connected() {
+                    tokio::time::sleep(tick).await;
+                    continue;
+                }
+                let resource = format!("leader:{}", standby.group);
+                let mut lease = match lock.acquire(&resource, std::time::Instant::now()).await {
+                    Ok(Some(lease)) => lease,
+                    Ok(None) => {
+                        tokio::time::sleep(tick).await;
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!("[Standby] lease acquisition failed: {e}");
+                        tokio::time::sleep(tick).await;
+                        continue;
+                    }
+                };
+                standby.promote().await;
+                loop {
+                    tokio::select! {
+                        _ = lease.lost.changed() => {
+                            standby.demote("Redis lease lost");
+                            break;
+                        }
+                        _ = tokio::time::sleep(tick) => {
+                            if hw.lapsed(standby.ttl) {
+                                standby.demote("central connection lapsed");
+                                break;
+                            }
+                        }
+                    }
+                }
+                drop(lease);
+                // Give the peer a full TTL to take over before competing again
+                tokio::time::sleep(standby.ttl).await;
+            }
+
+            LeaseBackend::File(path) => {
+                let holding = standby.is_leader();
+                let healthy = if holding { !hw.lapsed(standby.ttl) } else { hw.connected() };
+                match file_lease_step(path, &standby.node_id, standby.ttl, healthy).await {
+                    Ok(true) => standby.promote().await,
+                    Ok(false) if holding && !healthy => standby.demote("central connection lapsed"),
+                    Ok(false) => standby.demote("file lease taken over"),
+                    Err(e) => {
+                        warn!("[Standby] lease file {path:?}: {e}");
+                        standby.demote("lease file unavailable");
+                    }
+                }
+                tokio::time::sleep(tick).await;
+            }
+
+            LeaseBackend::Central => {
+                let granted = standby.grant_until.lock().unwrap_or_else(|e| e.into_inner()).is_some_and(|t| Instant::now() < t);
+                if !granted {
+                    standby.demote("LEADER grant expired");
+                } else if standby.is_leader() && hw.lapsed(standby.ttl) {
+                    standby.demote("central connection lapsed");
+                } else if hw.connected() {
+                    standby.promote().await;
+                }
+                tokio::time::sleep(tick).await;
+            }
+        }
+    }
+}
+
+/// One round of the file lease: renew/take it when `healthy` and free (or
+/// ours); returns whether this node holds it afterwards.
+async fn file_lease_step(path: &PathBuf, node_id: &str, ttl: Duration, healthy: bool) -> Result<bool> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let current: Option<FileLease> = match fs::read(path).await {
+        Ok(b) => serde_json::from_slice(&b).ok(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    let free = current.as_ref().is_none_or(|l| l.holder == node_id || l.expires_at_ms <= now);
+    if !healthy || !free {
+        return Ok(false);
+    }
+    let lease = FileLease { holder: node_id.to_owned(), expires_at_ms: now + ttl.as_millis() as i64 };
+    let tmp = path.with_extension(format!("{node_id}.tmp"));
+    fs::write(&tmp, serde_json::to_vec(&lease)?).await?;
+    fs::rename(&tmp, path).await?;
+    // Both peers may have written — whoever's rename landed last wins
+    let check: FileLease = serde_json::from_slice(&fs::read(path).await?)?;
+    Ok(check.holder == node_id)
+}
+
+// ── Metrics ───────────────────────────────────────────────────────────────────
+
+impl MetricsCollector for Standby {
+    fn prometheus(&self, node_id: &str) -> String {
+        let name = "eyeflow_standby_leader";
+        format!(
+            "# HELP {name} 1 if this node leads its standby group\n# TYPE {name} gauge\n\
+             {name}{{node_id=\"{node_id}\",group=\"{}\"}} {}\n",
+            self.group,
+            u8::from(self.is_leader()),
+        )
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_lease() {
+        let path = std::env::temp_dir().join(format!("eyeflow_leader_{}.json", uuid::Uuid::new_v4()));
+        let ttl = Duration::from_secs(30);
+
+        assert!(file_lease_step(&path, "node-a", ttl, true).await.unwrap());
+        // Held and fresh: the peer can't take it, the holder renews it
+        assert!(!file_lease_step(&path, "node-b", ttl, true).await.unwrap());
+        assert!(file_lease_step(&path, "node-a", ttl, true).await.unwrap());
+        // An unhealthy node never takes or renews
+        assert!(!file_lease_step(&path, "node-a", ttl, false).await.unwrap());
+
+        // Expired lease → failover
+        let stale = FileLease { holder: "node-a".into(), expires_at_ms: 0 };
+        std::fs::write(&path, serde_json::to_vec(&stale).unwrap()).unwrap();
+        assert!(file_lease_step(&path, "node-b", ttl, true).await.unwrap());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,89 @@
+//! Slice supervisor — a panicking opcode handler fails its slice, not the node
+//!
+//! Every slice (local or delegated by a peer) is polled under a
+//! catch_unwind guard.  A panic anywhere below it — an opcode handler, a
+//! connector, a parallel branch — unwinds to the guard and becomes
+//!
+//!   result      status FAILED, errorCode INTERNAL, error "slice panicked: <message>",
+//!               errorInstruction = the instruction that was running
+//!   audit       SLICE_PANIC { "panic": "<message>" } (then SLICE_FAILED as usual)
+//!   metrics     eyeflow_slice_panics_total
+//!
+//! The slice stays on the caller's task (it borrows the node's audit chain),
+//! so nothing it started outlives it: parallel branches are joined inside it
+//! and blocking work is awaited.  Isolation needs the unwinding panic
+//! strategy (the default; do not set `panic = "abort"` in the profile).
+
+use futures_util::FutureExt;
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::health::MetricsCollector;
+
+#[derive(Debug, Default)]
+pub struct SliceSupervisor {
+    panics: AtomicU64,
+}
+
+/// Text of a panic payload (`panic!` with a literal or a formatted message).
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload.downcast_ref::<&str>().map(|s| (*s).to_owned())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_owned())
+}
+
+impl SliceSupervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Poll `slice` to completion; a panic is caught, counted and returned
+    /// as its message.
+    pub async fn guard<F: Future>(&self, slice: F) -> Result<F::Output, String> {
+        AssertUnwindSafe(slice).catch_unwind().await.map_err(|payload| {
+            self.panics.fetch_add(1, Ordering::Relaxed);
+            panic_message(payload.as_ref())
+        })
+    }
+
+    pub fn panics(&self) -> u64 {
+        self.panics.load(Ordering::Relaxed)
+    }
+}
+
+impl MetricsCollector for SliceSupervisor {
+    fn prometheus(&self, node_id: &str) -> String {
+        format!(
+            "# HELP eyeflow_slice_panics_total Slices failed by a panic in the SVM\n\
+             # TYPE eyeflow_slice_panics_total counter\n\
+             eyeflow_slice_panics_total{{node_id=\"{node_id}\"}} {}\n",
+            self.panics(),
+        )
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_panics_are_caught_and_counted() {
+        let supervisor = SliceSupervisor::new();
+        assert_eq!(supervisor.guard(async { 7 }).await, Ok(7));
+
+        let register = 12;
+        let caught = supervisor.guard(async move {
+            tokio::task::yield_now().await;
+            if register > 0 {
+                panic!("register {register} out of range");
+            }
+        }).await;
+        assert_eq!(caught, Err("register 12 out of range".to_owned()));
+        assert_eq!(supervisor.guard(async { panic!("static") }).await, Err::<(), _>("static".to_owned()));
+        assert!(supervisor.prometheus("n").contains("eyeflow_slice_panics_total{node_id=\"n\"} 2"));
+    }
+}
@@ -1,53 +1,126 @@
-/// SVM Executor — spec §6 + §8
-///
-/// Register-based virtual machine that executes LLM-IR slices assigned to
-/// this node by the NestJS orchestrator.
-///
-/// Supported opcodes (spec §3.4):
-///   LOAD_RESOURCE   — fetch resource (HTTP GET or registry lookup)
-///   STORE_MEMORY    — write register value to in-memory KV store
-///   CALL_SERVICE    — HTTP / connector dispatch
-///   CALL_ACTION     — physical actuator / MQTT publish
-///   CALL_MCP        — Model Context Protocol tool call
-///   LLM_CALL        — forward to LLM provider
-///   TRANSFORM       — apply JSONPath / template transform
-///   VALIDATE        — JSON Schema validation
-///   BRANCH          — conditional jump
-///   LOOP            — bounded loop with convergence predicate
-///   PARALLEL_SPAWN  — fan-out (local channels)
-///   PARALLEL_MERGE  — fan-in (local channels)
-///   RETURN          — end of slice, sets output register
-///   JUMP, AGGREGATE, FILTER — implemented as NOOP stubs (delegated to central)
+//! SVM Executor — spec §6 + §8
+//!
+//! Register-based virtual machine that executes LLM-IR slices assigned to
+//! this node by the NestJS orchestrator.
+//!
+//! Supported opcodes (spec §3.4):
+//!   LOAD_RESOURCE   — fetch resource (HTTP GET, file://, s3://, SQL, ble://, camera snapshot, snmp://, zigbee:// / zwave://, catalogue or plan context)
+//!   STORE_MEMORY    — write register value to in-memory KV store (+ plan context)
+//!   CALL_SERVICE    — HTTP / connector dispatch
+//!   CALL_ACTION     — physical actuator / MQTT publish / SMS or voice alert
+//!   CALL_MCP        — Model Context Protocol tool call
+//!   LLM_CALL        — forward to LLM provider
+//!   TRANSFORM       — apply JSONPath / template transform
+//!   VALIDATE        — JSON Schema validation
+//!   BRANCH          — conditional jump (truthiness or src[0] OP src[1])
+//!   LOOP            — bounded loop with convergence predicate
+//!   PARALLEL_SPAWN  — fan-out, at most `maxConcurrency` branches in flight
+//!   PARALLEL_MERGE  — fan-in under a merge policy (merge.rs)
+//!   RETURN          — end of slice, sets output register
+//!   SWITCH          — multi-way jump on a value register (case table in operands)
+//!   TRY_BEGIN/END   — scoped error handler: a failure inside jumps to the handler
+//!                     (MEMORY_LIMIT, CANCELLED and fatal refusals are not caught)
+//!   CALL_SLICE      — run a cached IR artifact as a sub-workflow (own register file)
+//!   JUMP, AGGREGATE, FILTER — implemented as NOOP stubs (delegated to central)
+//!
+//! An instruction needing a feature this node lacks is handed to a peer
+//! node when SVM_PEERS is set (peer.rs).
 
 use anyhow::{anyhow, Result};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
-use tokio::sync::{Mutex, RwLock, Semaphore};
-use tracing::{debug, info, warn};
+use std::time::Instant;
+use tracing::{debug, error, info, warn};
 
+use crate::arbiter::{Claim, ResourceArbiter};
+use crate::assets::AssetStore;
+use crate::async_poll;
 use crate::audit::AuditChain;
+use crate::blob::{BlobStore, BodyKind};
+use crate::budget::CpuBudgets;
+use crate::callbacks::CallbackRegistry;
+use crate::capabilities::Capabilities;
+use crate::catalog::ServiceCatalog;
+use crate::chaos::{Chaos, Target};
+use crate::mcu::{Call, McuBridge};
 use crate::config::Config;
-use crate::fallback::{FallbackEngine, FallbackResult};
+use crate::dlock::DistributedLock;
+use crate::dns::Resolver;
+use crate::hotplug::Hotplug;
+#[cfg(target_os = "linux")]
+use crate::connectors::ble::BleConnector;
+use crate::connectors::camera::{self, CameraConnector};
+#[cfg(target_os = "linux")]
+use crate::connectors::device::{DeviceConnector, Endpoint};
+use crate::connectors::fs::FsConnector;
+use crate::connectors::kafka::{self, KafkaSink};
+use crate::connectors::mqtt::MqttBridge;
+use crate::connectors::notify::Notifier;
+use crate::connectors::plugin::PluginHost;
+use crate::connectors::s3::{S3Connector, S3Credentials};
+use crate::connectors::snmp::SnmpConnector;
+#[cfg(feature = "sql")]
+use crate::connectors::sql::{self, SqlConnector};
+use crate::errors::{ErrorCode, ExecError};
+use crate::fallback::{self, FallbackEngine};
+use crate::inference::Inference;
+use crate::fastjson;
+use crate::guardrails;
+use crate::health::HealthState;
+use crate::http_cache::{CacheRule, HttpCache};
+use crate::ir_allowlist::{IrAllowList, Verdict};
+use crate::lineage::LineageTracker;
+use crate::merge::{self, Branch, MergePolicy};
+use crate::middleware::{InstrContext, Middlewares, Outcome};
+use crate::peer::{self, PeerLink};
+use crate::pii::PiiPolicy;
+use crate::plan_state::PlanStateStore;
+use crate::predicate;
+use crate::prepared::{IrCache, PreparedInstr, PreparedIr};
+use crate::recording::{Recorder, Tape};
+use crate::redis_store::{MemoryOp, RedisStore};
+use crate::register_memory::RegisterMeter;
+use crate::response_cache::ResponseCache;
+use crate::secret_audit;
+use crate::shaping::{Priority, Shaper};
+use crate::supervisor::SliceSupervisor;
+use crate::tabular::Limits;
+use crate::transform;
 use crate::vault::VaultClient;
+use crate::wal::{ActionWal, Intent};
 use crate::proto::llmir::{
-    IrOpcode, LlmIntermediateRepresentation, ServiceFormat,
+    IrInstruction, IrOpcode, LoopConvergencePredicate, ServiceFormat,
 };
 
 // ── Register file ─────────────────────────────────────────────────────────────
 
 pub type Registers = HashMap<i32, Value>;
 
-// ── Resource Arbiter (spec §6.5) ──────────────────────────────────────────────
-//
-// When multiple concurrent workflows attempt to acquire the same physical
-// resource (e.g. Modbus gateway, DB connection), priority_policy governs access.
-// Each resource gets a Semaphore(1) — effectively a mutex.
-// Lower priority_level number = higher priority (0 = critical).
-// preemptible = true means a higher-priority workflow can skip the wait.
-// max_wait_ms constrains how long the instruction waits before triggering fallback.
-type ResourceArbiter = Arc<RwLock<HashMap<String, Arc<Semaphore>>>>;
+/// A boxed `run_slice` future: final registers and the instruction count.
+type SliceFuture<'a> =
+    std::pin::Pin<Box<dyn std::future::Future<Output = Result<(Registers, u64)>> + Send + 'a>>;
+
+/// Where and how a slice run starts.
+#[derive(Default)]
+struct Frame {
+    /// Enclosing CALL_SLICEs
+    depth: usize,
+    /// Initial register file
+    inputs: Registers,
+    /// Position in instruction_order to start at
+    start_ip: usize,
+    /// Run on behalf of a peer — never delegated again
+    delegated: bool,
+}
+
+/// An open TRY_BEGIN region.
+struct TryScope<'a> {
+    instr: &'a IrInstruction,
+    /// Position of the handler (TRY_BEGIN's target_instruction)
+    handler_ip: usize,
+}
 
 // ── SVM ───────────────────────────────────────────────────────────────────────
 
@@ -55,18 +128,98 @@ pub struct Svm {
     config: Config,
     /// Shared HTTP client (reused across service calls)
     http: reqwest::Client,
+    /// Uplink bandwidth shared with the central link (SVM_UPLINK_BYTES_PER_SEC)
+    shaper: Option<Arc<Shaper>>,
+    /// Static hosts table + DNS cache behind `http` (SVM_DNS_HOSTS)
+    resolver: Arc<Resolver>,
     /// FallbackEngine — spec §6.4: 5 resilience strategies
     fallback: FallbackEngine,
     /// VaultClient — spec §6.1 + §13.2: edge-side secret injection
-    vault: Mutex<VaultClient>,
+    vault: Arc<VaultClient>,
     /// ResourceArbiter — spec §6.5: priority-based resource access control
-    resource_arbiter: ResourceArbiter,
+    resource_arbiter: Arc<ResourceArbiter>,
+    /// ServiceCatalog — spec §6.1: local registry for service_id resolution
+    catalog: Arc<ServiceCatalog>,
+    /// Plan-scoped KV shared between slices of the same plan
+    plan_state: Arc<PlanStateStore>,
+    /// Intent/completion journal for CALL_ACTION / CALL_SERVICE
+    wal: Arc<ActionWal>,
+    /// Record / replay of external responses (SVM_RECORD_DIR / SVM_REPLAY_FILE)
+    recorder: Recorder,
+    /// Prepared artifacts (filled by the node; CALL_SLICE resolves sub-workflows here)
+    ir_cache: Arc<IrCache>,
+    /// Approved artifact checksums (SVM_IR_ALLOWLIST_FILE / CONFIG_UPDATE)
+    allowlist: Arc<IrAllowList>,
+    /// Build features + SVM_NODE_CAPABILITIES + probed devices / runtimes
+    capabilities: Arc<Capabilities>,
+    /// Peer nodes taking instructions this node cannot run (SVM_PEERS)
+    peers: Option<Arc<PeerLink>>,
+    /// Sandboxed `file://` resources
+    fs: FsConnector,
+    /// Local GPIO lines and serial ports (`gpio://`, `serial://`)
+    #[cfg(target_os = "linux")]
+    device: DeviceConnector,
+    /// BLE sensors (`ble://`)
+    #[cfg(target_os = "linux")]
+    ble: BleConnector,
+    /// IP camera snapshots (`rtsp://`, `camera+http://`)
+    camera: CameraConnector,
+    /// S3-compatible object storage (`s3://bucket/key`)
+    s3: S3Connector,
+    /// SNMP GET / WALK on network equipment (`snmp://host/oid`)
+    snmp: SnmpConnector,
+    /// Kafka producer (`kafka://topic`, SVM_KAFKA_BROKERS)
+    kafka: Option<Arc<KafkaSink>>,
+    /// One-shot webhook URLs for CALL_ACTION completion (SVM_CALLBACK_BASE_URL)
+    callbacks: Option<Arc<CallbackRegistry>>,
+    /// Shared Redis namespaces for STORE_MEMORY / LOAD_RESOURCE (SVM_REDIS_URL)
+    redis: Option<Arc<RedisStore>>,
+    /// LLM_CALL / CALL_SERVICE results for instructions with `cacheTtlSecs`
+    response_cache: Arc<ResponseCache>,
+    /// Panic guard around every slice (eyeflow_slice_panics_total)
+    supervisor: Arc<SliceSupervisor>,
+    /// Hooks around every instruction (SVM_MIDDLEWARE)
+    middleware: Arc<Middlewares>,
+    /// Blocking-pool budgets and yield points (SVM_CPU_BUDGETS)
+    cpu: Arc<CpuBudgets>,
+    /// Cache-Control-aware cache of idempotent HTTP requests
+    http_cache: Arc<HttpCache>,
+    /// Bytes behind binary register values (SVM_BLOB_MAX_BYTES / _STORE_MAX_BYTES)
+    blobs: Arc<BlobStore>,
+    /// Signed assets from ASSET_DISTRIBUTION (None = invalid asset key)
+    assets: Option<Arc<AssetStore>>,
+    /// Local ONNX models (CALL_SERVICE format ONNX)
+    inference: Option<Inference>,
+    /// Fault injection (SVM_CHAOS)
+    chaos: Option<Arc<Chaos>>,
+    /// Downstream MCU SVMs on serial links (`mcu://`, SVM_MCU_PORTS)
+    mcu: Option<Arc<McuBridge>>,
+    /// Device watcher: instructions wait for unplugged devices (SVM_DEVICE_WAIT_MS)
+    hotplug: Option<Arc<Hotplug>>,
+    /// SMS / voice pages (`sms://`, `voice://`, SUPERVISED_RECOMPILE alerts)
+    notifier: Arc<Notifier>,
+    /// Zigbee / Z-Wave devices behind MQTT bridges (`zigbee://`, `zwave://`, SVM_MQTT_URL)
+    mqtt: Option<Arc<MqttBridge>>,
+    /// Connector plugins (`plugin://`, SVM_PLUGIN_DIR)
+    plugins: Arc<PluginHost>,
+    /// Default PII masking (SVM_PII_MASK / SVM_PII_PATHS); workflows override it
+    pii: Option<Arc<PiiPolicy>>,
+    /// SQL databases (feature `sql`)
+    #[cfg(feature = "sql")]
+    sql: SqlConnector,
 }
 
 impl Svm {
-    pub fn new(config: Config) -> Self {
+    pub fn new(
+        config: Config,
+        catalog: Arc<ServiceCatalog>,
+        plan_state: Arc<PlanStateStore>,
+        wal: Arc<ActionWal>,
+    ) -> Self {
+        let resolver = Arc::new(Resolver::from_config(&config));
         let http = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(30))
+            .dns_resolver(resolver.clone())
             .build()
             .expect("failed to build HTTP client");
 
@@ -76,36 +229,421 @@ impl Svm {
             config.node_id.clone(),
         );
 
-        let vault = VaultClient::new(
+        let mut vault = VaultClient::new(
             http.clone(),
             config.vault_addr.clone(),
             config.vault_token.clone(),
             config.vault_namespace.clone(),
         );
+        vault.set_kv_layout(config.vault_kv_version, &config.vault_kv_mounts);
+        vault.set_prefail(config.vault_prefail_after, std::time::Duration::from_secs(config.vault_probe_secs));
+
+        #[cfg(feature = "sql")]
+        let config_sql_pool_size = config.sql_pool_size;
+
+        let fs = FsConnector::new(
+            config.fs_sandbox_root.as_ref().map(std::path::PathBuf::from),
+            config.fs_max_bytes,
+        );
+        #[cfg(target_os = "linux")]
+        let device = DeviceConnector::new(config.device_allow.clone());
+        #[cfg(target_os = "linux")]
+        let ble = BleConnector::new(
+            config.ble_adapter,
+            std::time::Duration::from_millis(config.ble_scan_timeout_ms),
+        );
+
+        let distributed = if config.dlock_redis_urls.is_empty() {
+            None
+        } else {
+            match DistributedLock::new(&config.dlock_redis_urls, config.dlock_ttl_ms) {
+                Ok(d) => Some(d),
+                Err(e) => {
+                    warn!("[Svm] distributed locks disabled: {e}");
+                    None
+                }
+            }
+        };
+        let resource_arbiter = Arc::new(ResourceArbiter::with_distributed(distributed));
+
+        let camera = CameraConnector::new(
+            http.clone(),
+            config.ffmpeg_path.clone(),
+            std::time::Duration::from_millis(config.camera_timeout_ms),
+        );
+        let snmp = SnmpConnector::from_config(&config);
+        let notifier = Arc::new(Notifier::from_config(&config, http.clone()));
+        let s3 = S3Connector::new(
+            http.clone(),
+            config.s3_endpoint.clone(),
+            config.s3_region.clone(),
+            config.s3_max_bytes,
+            std::path::PathBuf::from(&config.s3_temp_dir),
+        );
+
+        let ir_cache = IrCache::new(config.ir_cache_size);
+        let response_cache = ResponseCache::new(config.response_cache_size, None);
+        let http_cache = HttpCache::from_config(&config);
+        let blobs = BlobStore::from_config(&config);
+        let assets = AssetStore::from_config(&config, http.clone()).map_err(|e| {
+            warn!("[Svm] signed assets (and ONNX inference) disabled: {e}");
+        }).ok();
+        let inference = assets.clone().map(|a| Inference::new(&config, a, blobs.clone()));
+        let pii = PiiPolicy::from_config(&config).unwrap_or_else(|e| {
+            warn!("[Svm] PII masking disabled: {e}");
+            None
+        });
+        let capabilities = Capabilities::new(&config, inference.is_some());
 
         Self {
             config,
             http,
+            shaper: None,
+            resolver,
             fallback,
-            vault: Mutex::new(vault),
-            resource_arbiter: Arc::new(RwLock::new(HashMap::new())),
+            vault: Arc::new(vault),
+            resource_arbiter,
+            catalog,
+            plan_state,
+            wal,
+            recorder: Recorder::Off,
+            ir_cache,
+            allowlist: Arc::new(IrAllowList::default()),
+            capabilities,
+            peers: None,
+            #[cfg(target_os = "linux")]
+            device,
+            #[cfg(target_os = "linux")]
+            ble,
+            fs,
+            camera,
+            snmp,
+            s3,
+            kafka: None,
+            callbacks: None,
+            redis: None,
+            response_cache,
+            supervisor: Arc::new(SliceSupervisor::new()),
+            middleware: Arc::new(Middlewares::default()),
+            cpu: Arc::new(CpuBudgets::default()),
+            http_cache,
+            blobs,
+            assets,
+            inference,
+            chaos: None,
+            mcu: None,
+            mqtt: None,
+            notifier,
+            hotplug: None,
+            plugins: Arc::new(PluginHost::default()),
+            pii,
+            #[cfg(feature = "sql")]
+            sql: SqlConnector::new(config_sql_pool_size),
+        }
+    }
+
+    /// An SVM whose catalogue and action WAL live in `dir`, away from the
+    /// node's own (bench runs, recording replays).
+    pub async fn scratch(config: Config, dir: &std::path::Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let plan_state = PlanStateStore::new(config.plan_state_ttl_secs, None);
+        let wal = ActionWal::open(dir.join("actions.wal")).await?;
+        Ok(Self::new(config, ServiceCatalog::new(dir.join("catalog.json")), plan_state, wal))
+    }
+
+    /// The VaultClient, while the SVM is being built (not yet shared).
+    fn vault_mut(&mut self) -> &mut VaultClient {
+        Arc::get_mut(&mut self.vault).expect("VaultClient is configured before it is shared")
+    }
+
+    /// Inject SVM_CHAOS faults into CALL_SERVICE, LLM_CALL and Vault lookups.
+    pub fn with_chaos(mut self, chaos: Option<Arc<Chaos>>) -> Self {
+        self.vault_mut().set_chaos(chaos.clone());
+        self.chaos = chaos;
+        self
+    }
+
+    /// Report Vault reachability to the health score.
+    pub fn with_health(mut self, health: Option<Arc<HealthState>>) -> Self {
+        self.vault_mut().set_health(health);
+        self
+    }
+
+    /// Run every instruction through `middleware`.
+    pub fn with_middleware(mut self, middleware: Arc<Middlewares>) -> Self {
+        self.middleware = middleware;
+        self
+    }
+
+    /// Offload budgeted opcodes and yield between instructions per `cpu`.
+    pub fn with_cpu_budgets(mut self, cpu: Arc<CpuBudgets>) -> Self {
+        self.cpu = cpu;
+        self
+    }
+
+    /// Pace large CALL_SERVICE / CALL_ACTION bodies through `shaper`.
+    pub fn with_shaper(mut self, shaper: Option<Arc<Shaper>>) -> Self {
+        self.shaper = shaper;
+        self
+    }
+
+    /// Run `mcu://` CALL_SERVICE / CALL_ACTION / LOAD_RESOURCE on `bridge`.
+    pub fn with_mcu(mut self, bridge: Option<Arc<McuBridge>>) -> Self {
+        self.mcu = bridge;
+        self
+    }
+
+    /// Run `zigbee://` / `zwave://` LOAD_RESOURCE and CALL_ACTION on `bridge`.
+    pub fn with_mqtt(mut self, bridge: Option<Arc<MqttBridge>>) -> Self {
+        self.mqtt = bridge;
+        self
+    }
+
+    /// Pause instructions on devices `hotplug` sees unplugged.
+    pub fn with_hotplug(mut self, hotplug: Option<Arc<Hotplug>>) -> Self {
+        self.hotplug = hotplug;
+        self
+    }
+
+    /// Refuse CALL_SLICE into artifacts `allowlist` does not approve.
+    pub fn with_allowlist(mut self, allowlist: Arc<IrAllowList>) -> Self {
+        self.allowlist = allowlist;
+        self
+    }
+
+    /// Dispatch `plugin://` endpoints to `plugins` and advertise them.
+    pub fn with_plugins(mut self, plugins: Arc<PluginHost>) -> Self {
+        self.capabilities.extend(plugins.features());
+        self.plugins = plugins;
+        self
+    }
+
+    /// Record external responses to, or replay them from, `recorder`.
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = recorder;
+        self
+    }
+
+    /// Delegate instructions this node cannot run to `peers`.
+    pub fn with_peers(mut self, peers: Option<Arc<PeerLink>>) -> Self {
+        self.peers = peers;
+        self
+    }
+
+    /// Publish `kafka://` CALL_ACTIONs through `sink`.
+    pub fn with_kafka(mut self, sink: Option<Arc<KafkaSink>>) -> Self {
+        self.kafka = sink;
+        self
+    }
+
+    /// Complete `async_callback` CALL_ACTIONs through `registry`.
+    pub fn with_callbacks(mut self, registry: Option<Arc<CallbackRegistry>>) -> Self {
+        self.callbacks = registry;
+        self
+    }
+
+    /// Keep STORE_MEMORY namespaces and the response cache in `redis`.
+    pub fn with_redis(mut self, redis: Option<Arc<RedisStore>>) -> Self {
+        if redis.is_some() {
+            self.response_cache = ResponseCache::new(self.config.response_cache_size, redis.clone());
         }
+        self.redis = redis;
+        self
+    }
+
+    /// Features this node supports (build + local capabilities), as of the
+    /// last capability probe.
+    pub fn features(&self) -> BTreeSet<String> {
+        self.capabilities.features()
+    }
+
+    /// Probed capabilities (start-up probe, CAPABILITIES_UPDATE).
+    pub fn capabilities(&self) -> &Arc<Capabilities> {
+        &self.capabilities
+    }
+
+    /// Whether a missing instruction feature can be delegated to a peer.
+    pub fn can_delegate(&self) -> bool {
+        self.peers.as_ref().is_some_and(|p| p.has_peers())
+    }
+
+    /// Shared service catalogue (updated by CONFIG_UPDATE and the sync task).
+    pub fn catalog(&self) -> &Arc<ServiceCatalog> {
+        &self.catalog
+    }
+
+    /// HTTP client for central and the services (catalogue sync).
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    /// Host resolution shared with the central link (/metrics collector).
+    pub fn resolver(&self) -> &Arc<Resolver> {
+        &self.resolver
+    }
+
+    /// Resource arbiter (capacities pushed by CONFIG_UPDATE, /metrics collector).
+    pub fn resource_arbiter(&self) -> &Arc<ResourceArbiter> {
+        &self.resource_arbiter
+    }
+
+    /// Prepared-IR cache (IR distributions, CALL_SLICE sub-workflows, /metrics).
+    pub fn ir_cache(&self) -> &Arc<IrCache> {
+        &self.ir_cache
+    }
+
+    /// LLM_CALL / CALL_SERVICE response cache (/metrics collector).
+    pub fn response_cache(&self) -> &Arc<ResponseCache> {
+        &self.response_cache
+    }
+
+    /// SMS / voice alert sender (metrics).
+    pub fn notifier(&self) -> &Arc<Notifier> {
+        &self.notifier
+    }
+
+    /// IR allow-list (checked by the node before executing an artifact).
+    pub fn allowlist(&self) -> &Arc<IrAllowList> {
+        &self.allowlist
+    }
+
+    /// Loaded connector plugins (REGISTER capabilities).
+    pub fn plugins(&self) -> &Arc<PluginHost> {
+        &self.plugins
+    }
+
+    /// Slice panic guard (/metrics collector).
+    pub fn supervisor(&self) -> &Arc<SliceSupervisor> {
+        &self.supervisor
+    }
+
+    pub fn http_cache(&self) -> &Arc<HttpCache> {
+        &self.http_cache
+    }
+
+    /// Binary register values (BLOB_REQUEST, /metrics collector).
+    pub fn blobs(&self) -> &Arc<BlobStore> {
+        &self.blobs
+    }
+
+    /// Signed asset cache (ASSET_DISTRIBUTION, REGISTER capabilities).
+    pub fn assets(&self) -> Result<&Arc<AssetStore>> {
+        self.assets.as_ref().ok_or_else(|| ExecError::new(
+            ErrorCode::Unsupported,
+            "signed assets are disabled on this node (invalid SVM_ASSET_PUBLIC_KEY)",
+        ).into())
+    }
+
+    /// Action write-ahead log (in-doubt actions are reported on connect).
+    pub fn action_wal(&self) -> &Arc<ActionWal> {
+        &self.wal
     }
 
     /// Execute an IR slice.
     ///
-    /// Returns `(output_registers, elapsed_ms)`.
+    /// Returns `(output_registers, elapsed_ms)`.  With a `lineage` tracker,
+    /// every register write is recorded in it.  Failures are `ExecError`s
+    /// carrying the failing instruction index.
     pub async fn execute(
         &self,
-        ir: &LlmIntermediateRepresentation,
+        prepared: &PreparedIr,
+        audit: &mut AuditChain,
+        lineage: Option<&mut LineageTracker>,
+    ) -> std::result::Result<(Registers, u64), ExecError> {
+        self.run_supervised(prepared, audit, lineage, Frame::default()).await
+    }
+
+    /// Execute work delegated by a peer: from instruction `start` with the
+    /// peer's `registers`, never delegating further.
+    pub async fn execute_delegated(
+        &self,
+        prepared: &PreparedIr,
+        audit: &mut AuditChain,
+        start: i32,
+        registers: Registers,
+    ) -> std::result::Result<(Registers, u64), ExecError> {
+        let frame = Frame { inputs: registers, start_ip: prepared.ip_of(start), delegated: true, ..Default::default() };
+        self.run_supervised(prepared, audit, None, frame).await
+    }
+
+    /// Run a top-level slice under the panic guard (supervisor.rs), with
+    /// secret taint tracking (taint.rs) when enabled.
+    async fn run_supervised(
+        &self,
+        prepared: &PreparedIr,
         audit: &mut AuditChain,
+        lineage: Option<&mut LineageTracker>,
+        frame: Frame,
+    ) -> std::result::Result<(Registers, u64), ExecError> {
+        let mut current = None;
+        let workflow_id = prepared.ir.metadata.as_ref().map_or("unknown", |m| m.id.as_str());
+        let tape = self.recorder.tape(workflow_id);
+        let run = async {
+            let guarded = self.supervisor.guard(Box::pin(
+                self.run_slice(prepared, &mut *audit, lineage, &mut current, &tape, frame),
+            )).await;
+            let mut result = match guarded {
+                Ok(result) => result.map_err(|e| ExecError::from_anyhow(&e, current)),
+                Err(panic) => {
+                    let panic = crate::taint::scrub(&panic);
+                    error!("[SVM] {workflow_id}: slice panicked at instruction {current:?}: {panic}");
+                    audit.append(
+                        workflow_id, prepared.ir.metadata.as_ref().map(|m| m.version as u32),
+                        current.map(|i| i.to_string()),
+                        "SLICE_PANIC",
+                        None, None, 0,
+                        Some(serde_json::json!({ "panic": panic })),
+                    );
+                    Err(ExecError {
+                        instruction: current,
+                        ..ExecError::new(ErrorCode::Internal, format!("slice panicked: {panic}"))
+                    })
+                }
+            };
+            Self::scrub_tainted(workflow_id, &mut result);
+            result
+        };
+        let result = if self.config.secret_taint { crate::taint::tracked(run).await } else { run.await };
+        tape.finish(result.as_ref().err()).await;
+        result
+    }
+
+    /// Scrub secrets out of the registers a slice hands back (taint.rs).
+    fn scrub_tainted(workflow_id: &str, result: &mut std::result::Result<(Registers, u64), ExecError>) {
+        if let Ok((registers, _)) = result {
+            let tainted = crate::taint::scrub_registers(registers.iter_mut());
+            if !tainted.is_empty() {
+                warn!("[SVM] {workflow_id}: scrubbed secret values from registers {tainted:?}");
+            }
+        }
+    }
+
+    async fn run_slice(
+        &self,
+        prepared: &PreparedIr,
+        audit: &mut AuditChain,
+        mut lineage: Option<&mut LineageTracker>,
+        current: &mut Option<i32>,
+        tape: &Tape<'_>,
+        frame: Frame,
     ) -> Result<(Registers, u64)> {
+        let Frame { depth, inputs, start_ip, delegated } = frame;
+        let ir = &prepared.ir;
         let workflow_id = ir
             .metadata
             .as_ref()
             .map(|m| m.id.clone())
             .unwrap_or_else(|| "unknown".to_owned());
         let workflow_version = ir.metadata.as_ref().map(|m| m.version as u32);
+        let tenant = crate::tenant::tenant_of(ir);
+        let plan_scope = crate::tenant::plan_scope(tenant, &workflow_id);
+        audit.set_tenant(
+            Some(tenant.to_owned()).filter(|t| t != crate::tenant::DEFAULT_TENANT),
+        );
+        // The workflow's own policy wins over the node default ("off" = none)
+        let pii = prepared.pii.clone().or_else(|| self.pii.clone()).filter(|p| !p.is_empty());
+        audit.set_pii(pii.clone());
+        let llm_pii = pii.filter(|p| p.llm);
 
         info!(
             "[Svm] executing IR workflow={} ({} instructions)",
@@ -113,322 +651,818 @@ impl Svm {
             ir.instruction_order.len()
         );
 
-        let mut regs: Registers = HashMap::new();
+        // One concurrent pass over the slice's secrets instead of a round
+        // trip per instruction
+        if self.config.vault_prefetch && !prepared.vault_paths.is_empty() {
+            let warmed = self.vault.prefetch(&prepared.vault_paths).await;
+            debug!("[Svm] vault: prefetched {warmed}/{} path(s)", prepared.vault_paths.len());
+        }
+
+        // A device unplugged under the slice pauses it before its first side effect
+        for instr in ir.instructions.values() {
+            self.device_precheck(instr).await?;
+        }
+
+        let mut regs: Registers = inputs;
+        let mut meter = RegisterMeter::new(self.config.register_memory_max_bytes);
         let start = Instant::now();
+        // Idempotency keys: "<execution>:<instruction>:<n-th side effect>"
+        let execution_id = uuid::Uuid::new_v4();
+        let mut side_effects = 0u32;
+        let mut intent = |instr: &crate::proto::llmir::IrInstruction, opcode: &str| {
+            side_effects += 1;
+            Intent::new(
+                format!("{execution_id}:{}:{side_effects}", instr.index),
+                &workflow_id,
+                Some(tenant.to_owned()).filter(|t| t != crate::tenant::DEFAULT_TENANT),
+                instr,
+                opcode,
+            )
+        };
 
-        let order: Vec<i32> = ir.instruction_order.clone();
-        let mut ip = 0usize;
+        let order = &ir.instruction_order;
+        let mut ip = start_ip;
+        // Open TRY_BEGIN scopes, innermost last
+        let mut try_scopes: Vec<TryScope> = Vec::new();
+        let mut last_yield = Instant::now();
 
         while ip < order.len() {
+            self.cpu.checkpoint(&mut last_yield).await;
             let idx = order[ip];
+            *current = Some(idx);
             let instr = ir
                 .instructions
                 .get(&idx)
-                .ok_or_else(|| anyhow!("missing instruction #{idx}"))?;
+                .ok_or_else(|| ExecError::validation(format!("missing instruction #{idx}")))?;
+            let prep = prepared.instr(idx).expect("prepared alongside ir.instructions");
 
             let opcode = IrOpcode::try_from(instr.opcode)
                 .unwrap_or(IrOpcode::Return);
 
             debug!("[Svm] ip={ip} opcode={opcode:?} dest={}", instr.dest);
 
-            let instr_start = Instant::now();
-            let next_ip = match opcode {
-                // ── Memory ─────────────────────────────────────────────────────
-                IrOpcode::LoadResource => {
-                    let result = self.load_resource_with_fallback(instr, &regs, &workflow_id).await?;
-                    regs.insert(instr.dest, result.clone());
-                    audit.append(
-                        &workflow_id, workflow_version,
-                        Some(&instr.service_id),
-                        "LOAD_RESOURCE",
-                        None, Some(&result),
-                        instr_start.elapsed().as_millis() as u64,
-                        None,
-                    );
-                    ip + 1
-                }
-
-                IrOpcode::StoreMemory => {
-                    let src = self.read_src(instr, &regs, 0)?;
-                    regs.insert(instr.dest, src);
-                    ip + 1
+            // Features this node lacks → a peer runs the instruction (or the rest)
+            let missing: Vec<String> = match &self.peers {
+                Some(p) if !delegated && p.has_peers() => {
+                    crate::features::required_by_instruction(instr, &prep.operands)
+                        .into_iter()
+                        .filter(|f| !self.capabilities.contains(f))
+                        .collect()
                 }
+                _ => Vec::new(),
+            };
 
-                // ── Service calls ───────────────────────────────────────────────
-                IrOpcode::CallService => {
-                    // PriorityPolicy: acquire resource permit before call (spec §6.5)
-                    let _permit = if let Some(pp) = &instr.priority_policy {
-                        let key = if !instr.service_id.is_empty() { instr.service_id.as_str() } else { "service_default" };
-                        match self.acquire_resource_permit(key, pp.max_wait_ms).await {
-                            Ok(p) => Some(p),
-                            Err(e) => {
-                                warn!("[Svm] CALL_SERVICE priority_policy: {e} — triggering fallback");
-                                return Err(e); // caller's FallbackEngine handles it
-                            }
+            let instr_start = Instant::now();
+            // CALL_SLICE recurses into run_slice: kept out of the opcode match
+            // so each nesting level doesn't stack that (large) frame again
+            let (mut step, secrets): (Result<usize>, _) = secret_audit::tracked(async {
+                self.middleware.pre(&InstrContext { workflow_id: &workflow_id, tenant, instr, opcode, registers: &regs })?;
+                if opcode == IrOpcode::CallSlice && missing.is_empty() { async {
+                let (output, details) = self.call_slice(
+                    instr, prep, &regs, audit, tape, (depth, delegated), tenant, &workflow_id,
+                ).await?;
+                meter.store(&mut regs, instr, instr.dest, output.clone())?;
+                audit.append(
+                    &workflow_id, workflow_version,
+                    Some(idx.to_string()),
+                    "CALL_SLICE",
+                    None, Some(&output),
+                    instr_start.elapsed().as_millis() as u64,
+                    Some(details),
+                );
+                Ok(ip + 1)
+            }.await } else { async {
+                if !missing.is_empty() {
+                    // Boxed: the WebSocket exchange would bloat every slice's future
+                    let out = Box::pin(self.delegate(prepared, instr, opcode, prep, &missing, &regs, audit, tape)).await?;
+                    return Ok(match out {
+                        (peer::Mode::Instruction, mut out) => {
+                            let value = out.remove(&instr.dest).unwrap_or(Value::Null);
+                            meter.store(&mut regs, instr, instr.dest, value)?;
+                            ip + 1
                         }
-                    } else { None };
-                    let input = self.read_src(instr, &regs, 0).ok();
-                    let result = self.call_service_with_fallback(instr, input.as_ref(), &regs, &workflow_id).await?;
-                    regs.insert(instr.dest, result.clone());
-                    audit.append(
-                        &workflow_id, workflow_version,
-                        Some(&instr.service_id),
-                        "CALL_SERVICE",
-                        input.as_ref(), Some(&result),
-                        instr_start.elapsed().as_millis() as u64,
-                        None,
-                    );
-                    ip + 1
-                }
-
-                IrOpcode::CallAction => {
-                    // PriorityPolicy: acquire resource permit before physical actuation (spec §6.5)
-                    let _permit = if let Some(pp) = &instr.priority_policy {
-                        let key = if !instr.service_id.is_empty() { instr.service_id.as_str() } else { "action_default" };
-                        match self.acquire_resource_permit(key, pp.max_wait_ms).await {
-                            Ok(p) => Some(p),
-                            Err(e) => {
-                                warn!("[Svm] CALL_ACTION priority_policy: {e} — triggering fallback");
-                                return Err(e);
+                        (peer::Mode::Slice, out) => {
+                            for (r, v) in out {
+                                meter.store(&mut regs, instr, r, v)?;
                             }
+                            order.len()
                         }
-                    } else { None };
-                    let input = self.read_src(instr, &regs, 0).ok();
-                    let result = self.call_action_with_fallback(instr, input.as_ref(), &workflow_id).await?;
-                    regs.insert(instr.dest, result.clone());
-                    audit.append(
-                        &workflow_id, workflow_version,
-                        Some(&instr.service_id),
-                        "CALL_ACTION",
-                        input.as_ref(), Some(&result),
-                        instr_start.elapsed().as_millis() as u64,
-                        None,
-                    );
-                    ip + 1
-                }
-
-                IrOpcode::CallMcp => {
-                    let input = self.read_src(instr, &regs, 0).ok();
-                    let result = self.call_mcp_with_fallback(instr, input.as_ref(), &workflow_id).await?;
-                    regs.insert(instr.dest, result.clone());
-                    ip + 1
+                    });
                 }
-
-                // ── LLM call ───────────────────────────────────────────────────
-                IrOpcode::LlmCall => {
-                    let input = self.read_src(instr, &regs, 0).ok();
-                    let result = self.llm_call_with_fallback(instr, input.as_ref(), &workflow_id).await?;
-                    regs.insert(instr.dest, result.clone());
-                    audit.append(
-                        &workflow_id, workflow_version,
-                        Some(&instr.service_id),
-                        "LLM_CALL",
-                        input.as_ref(), Some(&result),
-                        instr_start.elapsed().as_millis() as u64,
-                        None,
-                    );
-                    ip + 1
+                self.vault_precheck(instr).await?;
+                self.device_precheck(instr).await?;
+                if let Some(dm) = instr.dispatch_metadata.as_ref().filter(|dm| !dm.assets.is_empty()) {
+                    self.assets()?.precheck(&dm.assets)?;
                 }
-
-                // ── Control flow ───────────────────────────────────────────────
-                IrOpcode::Branch => {
-                    let cond = self.read_src(instr, &regs, 0).ok();
-                    let truthy = Self::is_truthy(cond.as_ref());
-                    if truthy {
-                        // jump to target_instruction index in order slice
-                        let target_ip = self.resolve_ip(&order, instr.target_instruction);
-                        target_ip
-                    } else {
+                Ok(match opcode {
+                    // ── Memory ─────────────────────────────────────────────────────
+                    IrOpcode::LoadResource => {
+                        let (result, attempts) = fallback::tracked_attempts(Box::pin(tape.dispatch(instr, "LOAD_RESOURCE",
+                            self.with_resource(instr, prep, "resource_default", &workflow_id, || {
+                                self.load_resource_with_fallback(instr, prep, &workflow_id, &plan_scope)
+                            }),
+                        ))).await;
+                        let elapsed_ms = instr_start.elapsed().as_millis() as u64;
+                        Self::audit_retry_exhausted(audit, &workflow_id, workflow_version, instr, &result, attempts.as_ref(), elapsed_ms);
+                        let result = result?;
+                        meter.store(&mut regs, instr, instr.dest, result.clone())?;
+                        audit.append(
+                            &workflow_id, workflow_version,
+                            Some(&instr.service_id),
+                            "LOAD_RESOURCE",
+                            None, Some(&result),
+                            elapsed_ms,
+                            Self::with_attempts(None, attempts),
+                        );
                         ip + 1
                     }
-                }
 
-                IrOpcode::Jump => {
-                    self.resolve_ip(&order, instr.target_instruction)
-                }
+                    IrOpcode::StoreMemory => {
+                        let src = self.read_src(instr, &regs, 0)?;
+                        if let Some(key) = Self::plan_key(&prep.operands) {
+                            self.plan_state.put(&plan_scope, &key, src.clone()).await;
+                        }
+                        let result = match MemoryOp::from_operands(&prep.operands, true)? {
+                            Some(op) => tape.dispatch(instr, "STORE_MEMORY", async {
+                                self.redis()?.store(&op, &src).await
+                            }).await?,
+                            None => src,
+                        };
+                        meter.store(&mut regs, instr, instr.dest, result)?;
+                        ip + 1
+                    }
 
-                IrOpcode::Loop => {
-                    let lo = instr.loop_operands.as_ref()
-                        .ok_or_else(|| anyhow!("LOOP instruction #{idx} missing loop_operands"))?;
+                    // ── Service calls ───────────────────────────────────────────────
+                    IrOpcode::CallService => {
+                        // PriorityPolicy: acquire resource permit before call (spec §6.5)
+                        let input = self.read_src(instr, &regs, 0).ok();
+                        let input = Self::coerce_input(instr, prep, input)?;
+                        let intent = intent(instr, "CALL_SERVICE");
+                        let ((result, polling), attempts) = fallback::tracked_attempts(async_poll::tracked(Box::pin(tape.dispatch(instr, "CALL_SERVICE",
+                            self.cached(instr, prep, "CALL_SERVICE", input.as_ref(),
+                                self.with_resource(instr, prep, "service_default", &workflow_id, || {
+                                    self.wal.journaled(
+                                        intent.clone(),
+                                        self.call_service_with_fallback(instr, prep, input.as_ref(), &regs, &workflow_id),
+                                    )
+                                }),
+                            ),
+                        )))).await;
+                        let elapsed_ms = instr_start.elapsed().as_millis() as u64;
+                        Self::audit_retry_exhausted(audit, &workflow_id, workflow_version, instr, &result, attempts.as_ref(), elapsed_ms);
+                        let result = result?;
+                        meter.store(&mut regs, instr, instr.dest, result.clone())?;
+                        audit.append(
+                            &workflow_id, workflow_version,
+                            Some(&instr.service_id),
+                            "CALL_SERVICE",
+                            input.as_ref(), Some(&result),
+                            elapsed_ms,
+                            Self::with_attempts(polling.map(|p| serde_json::json!({ "polling": p })), attempts),
+                        );
+                        ip + 1
+                    }
 
-                    let max_iter = lo.max_iterations.max(1) as usize;
-                    let body_start = self.resolve_ip(&order, lo.body_start_index);
-                    let exit_ip    = self.resolve_ip(&order, lo.exit_index);
+                    IrOpcode::CallAction => {
+                        // PriorityPolicy: acquire resource permit before physical actuation (spec §6.5)
+                        let input = self.read_src(instr, &regs, 0).ok();
+                        let intent = intent(instr, "CALL_ACTION");
+                        let ((result, polling), attempts) = fallback::tracked_attempts(async_poll::tracked(Box::pin(tape.dispatch(instr, "CALL_ACTION",
+                            self.with_resource(instr, prep, "action_default", &workflow_id, || {
+                                self.wal.journaled(
+                                    intent.clone(),
+                                    self.call_action_with_fallback(instr, prep, input.as_ref(), &workflow_id),
+                                )
+                            }),
+                        )))).await;
+                        let elapsed_ms = instr_start.elapsed().as_millis() as u64;
+                        Self::audit_retry_exhausted(audit, &workflow_id, workflow_version, instr, &result, attempts.as_ref(), elapsed_ms);
+                        let result = result?;
+                        meter.store(&mut regs, instr, instr.dest, result.clone())?;
+                        audit.append(
+                            &workflow_id, workflow_version,
+                            Some(&instr.service_id),
+                            "CALL_ACTION",
+                            input.as_ref(), Some(&result),
+                            elapsed_ms,
+                            Self::with_attempts(polling.map(|p| serde_json::json!({ "polling": p })), attempts),
+                        );
+                        ip + 1
+                    }
 
-                    // We run the loop body as a sub-sequence (inline bounded execution)
-                    let mut iter = 0usize;
-                    let mut body_ip = body_start;
+                    IrOpcode::CallMcp => {
+                        let input = self.read_src(instr, &regs, 0).ok();
+                        let (result, attempts) = fallback::tracked_attempts(Box::pin(tape.dispatch(instr, "CALL_MCP",
+                            self.with_resource(instr, prep, "mcp_default", &workflow_id, || {
+                                self.call_mcp_with_fallback(instr, prep, input.as_ref(), &workflow_id)
+                            }),
+                        ))).await;
+                        Self::audit_retry_exhausted(audit, &workflow_id, workflow_version, instr, &result, attempts.as_ref(), instr_start.elapsed().as_millis() as u64);
+                        let result = result?;
+                        meter.store(&mut regs, instr, instr.dest, result.clone())?;
+                        ip + 1
+                    }
 
-                    loop {
-                        if iter >= max_iter {
-                            warn!("[Svm] LOOP hit max_iterations={max_iter} — breaking");
-                            break;
-                        }
+                    // ── LLM call ───────────────────────────────────────────────────
+                    IrOpcode::LlmCall => {
+                        let input = self.read_src(instr, &regs, 0).ok();
+                        let (sent, masked) = Self::mask_for_llm(llm_pii.as_deref(), input.as_ref());
+                        let ((result, rejected), attempts) = fallback::tracked_attempts(guardrails::tracked(Box::pin(tape.dispatch(instr, "LLM_CALL",
+                            self.cached(instr, prep, "LLM_CALL", sent.as_ref(),
+                                self.with_resource(instr, prep, "llm_default", &workflow_id, || {
+                                    self.llm_call_with_fallback(instr, prep, sent.as_ref(), &workflow_id)
+                                }),
+                            ),
+                        )))).await;
+                        Self::audit_violations(audit, prepared, rejected);
+                        let elapsed_ms = instr_start.elapsed().as_millis() as u64;
+                        Self::audit_retry_exhausted(audit, &workflow_id, workflow_version, instr, &result, attempts.as_ref(), elapsed_ms);
+                        let result = result?;
+                        meter.store(&mut regs, instr, instr.dest, result.clone())?;
+                        audit.append(
+                            &workflow_id, workflow_version,
+                            Some(&instr.service_id),
+                            "LLM_CALL",
+                            input.as_ref(), Some(&result),
+                            elapsed_ms,
+                            Self::with_attempts((masked > 0).then(|| serde_json::json!({ "llmPiiMasked": masked })), attempts),
+                        );
+                        ip + 1
+                    }
 
-                        // Check convergence predicate
-                        if let Some(pred) = &lo.convergence_predicate {
-                            let reg_val = regs.get(&pred.register_index).cloned()
-                                .unwrap_or(Value::Null);
-                            if Self::eval_predicate(&reg_val, &pred.operator, &pred.value_json) {
-                                debug!("[Svm] LOOP converged at iter={iter}");
-                                break;
+                    // ── Control flow ───────────────────────────────────────────────
+                    IrOpcode::Branch => {
+                        // `operator` operand: src[0] OP src[1] (or OP the literal
+                        // `value`); without one, src[0] is tested for truthiness.
+                        // `compareAs` / `nulls` select typed comparison (predicate.rs)
+                        let str_operand = |key: &str| prep.operands.get(key).and_then(|v| v.as_str()).unwrap_or("");
+                        let mode = predicate::CompareAs::parse(str_operand("compareAs"))?;
+                        let nulls = predicate::Nulls::parse(str_operand("nulls"))?;
+                        let taken = match prep.operands.get("operator").and_then(|v| v.as_str()) {
+                            Some(op) => {
+                                let left = self.read_src(instr, &regs, 0)?;
+                                let right = match instr.src.len() {
+                                    1 => prep.operands.get("value").cloned().unwrap_or(Value::Null),
+                                    _ => self.read_src(instr, &regs, 1)?,
+                                };
+                                predicate::eval(&left, op, &right, mode, nulls)?
                             }
+                            None if mode == predicate::CompareAs::Auto => {
+                                Self::is_truthy(self.read_src(instr, &regs, 0).ok().as_ref())
+                            }
+                            None => {
+                                let left = self.read_src(instr, &regs, 0).unwrap_or(Value::Null);
+                                predicate::eval(&left, "truthy", &Value::Null, mode, nulls)?
+                            }
+                        };
+                        if taken {
+                            // jump to target_instruction index in order slice
+                            prep.target_ip
+                        } else {
+                            ip + 1
                         }
+                    }
 
-                        // Execute one body instruction
-                        let body_idx = *order.get(body_ip)
-                            .ok_or_else(|| anyhow!("LOOP body_ip out of bounds"))?;
-                        let body_instr = ir.instructions.get(&body_idx)
-                            .ok_or_else(|| anyhow!("LOOP body instruction #{body_idx} missing"))?;
-                        let body_opcode = IrOpcode::try_from(body_instr.opcode)
-                            .unwrap_or(IrOpcode::Return);
+                    IrOpcode::Jump => prep.target_ip,
 
-                        if matches!(body_opcode, IrOpcode::Return) {
-                            break;
-                        }
+                    IrOpcode::TryBegin => {
+                        try_scopes.push(TryScope { instr, handler_ip: prep.target_ip });
+                        ip + 1
+                    }
+
+                    IrOpcode::TryEnd => {
+                        try_scopes.pop();
+                        ip + 1
+                    }
 
-                        body_ip += 1;
-                        if body_ip >= exit_ip {
-                            // Wrap back to body_start for next iteration
-                            body_ip = body_start;
-                            iter += 1;
+                    IrOpcode::Switch => {
+                        let table = prep.switch.as_ref()
+                            .ok_or_else(|| ExecError::validation(format!("SWITCH #{idx} has no valid case table")))?;
+                        let value = self.read_src(instr, &regs, 0)?;
+                        match table.target_for(&value) {
+                            Some(target) => {
+                                debug!("[Svm] SWITCH #{idx}: {value} → #{target}");
+                                prepared.ip_of(target)
+                            }
+                            None => ip + 1,
                         }
                     }
 
-                    exit_ip
-                }
+                    // Handled before the opcode match
+                    IrOpcode::CallSlice => unreachable!("CALL_SLICE is executed outside the opcode match"),
 
-                IrOpcode::Return => {
-                    break;
-                }
+                    IrOpcode::Loop => {
+                        let lo = instr.loop_operands.as_ref()
+                            .ok_or_else(|| ExecError::validation(format!("LOOP instruction #{idx} missing loop_operands")))?;
 
-                // ── Transform / Validate / Aggregate / Filter ─────────────────
-                IrOpcode::Transform => {
-                    // Apply a simple JSONPath/template transform (spec §3.4)
-                    let src = self.read_src(instr, &regs, 0).unwrap_or(Value::Null);
-                    let operands: Value = serde_json::from_str(&instr.operands_json)
-                        .unwrap_or(Value::Null);
-                    let result = Self::apply_transform(&src, &operands);
-                    regs.insert(instr.dest, result);
-                    ip + 1
-                }
+                        let max_iter = lo.max_iterations.max(1) as usize;
+                        let body_start = prepared.ip_of(lo.body_start_index);
+                        let exit_ip    = prepared.ip_of(lo.exit_index);
 
-                IrOpcode::Validate => {
-                    // JSON Schema validation; just a passthrough for now
-                    let src = self.read_src(instr, &regs, 0).unwrap_or(Value::Null);
-                    regs.insert(instr.dest, src);
-                    ip + 1
-                }
+                        // We run the loop body as a sub-sequence (inline bounded execution)
+                        let mut iter = 0usize;
+                        let mut body_ip = body_start;
 
-                IrOpcode::Aggregate | IrOpcode::Filter => {
-                    // Complex aggregation/filter is handled centrally; pass value through
-                    let src = self.read_src(instr, &regs, 0).unwrap_or(Value::Null);
-                    regs.insert(instr.dest, src);
-                    ip + 1
-                }
+                        loop {
+                            if iter >= max_iter {
+                                warn!("[Svm] LOOP hit max_iterations={max_iter} — breaking");
+                                break;
+                            }
 
-                IrOpcode::ParallelSpawn => {
-                    // Collect all LLM_CALL instructions between this PARALLEL_SPAWN
-                    // and the matching PARALLEL_MERGE, then run them concurrently
-                    // using futures_util::future::join_all (spec §10.2 / §17).
-                    //
-                    // Nesting is supported: inner SPAWN/MERGE pairs are skipped.
-                    let mut parallel_instrs: Vec<crate::proto::llmir::IrInstruction> = Vec::new();
-                    let mut parallel_dests:  Vec<i32> = Vec::new();
-                    let mut merge_ip = ip + 1;
-                    let mut nesting  = 1usize;
-                    let mut scan_ip  = ip + 1;
-
-                    while scan_ip < order.len() {
-                        let scan_idx = order[scan_ip];
-                        if let Some(scan_instr) = ir.instructions.get(&scan_idx) {
-                            let scan_op = IrOpcode::try_from(scan_instr.opcode)
-                                .unwrap_or(IrOpcode::Return);
-                            match scan_op {
-                                IrOpcode::ParallelSpawn => nesting += 1,
-                                IrOpcode::ParallelMerge => {
-                                    nesting -= 1;
-                                    if nesting == 0 {
-                                        merge_ip = scan_ip;
-                                        break;
-                                    }
-                                }
-                                IrOpcode::LlmCall => {
-                                    parallel_dests.push(scan_instr.dest);
-                                    parallel_instrs.push(scan_instr.clone());
+                            // Check convergence predicate
+                            if let Some(pred) = &lo.convergence_predicate {
+                                let reg_val = regs.get(&pred.register_index).cloned()
+                                    .unwrap_or(Value::Null);
+                                if Self::eval_predicate(&reg_val, pred)? {
+                                    debug!("[Svm] LOOP converged at iter={iter}");
+                                    break;
                                 }
-                                _ => {}
+                            }
+
+                            // Execute one body instruction
+                            let body_idx = *order.get(body_ip)
+                                .ok_or_else(|| ExecError::validation("LOOP body_ip out of bounds"))?;
+                            let body_instr = ir.instructions.get(&body_idx)
+                                .ok_or_else(|| ExecError::validation(format!("LOOP body instruction #{body_idx} missing")))?;
+                            let body_opcode = IrOpcode::try_from(body_instr.opcode)
+                                .unwrap_or(IrOpcode::Return);
+
+                            if matches!(body_opcode, IrOpcode::Return) {
+                                break;
+                            }
+
+                            body_ip += 1;
+                            if body_ip >= exit_ip {
+                                // Wrap back to body_start for next iteration
+                                body_ip = body_start;
+                                iter += 1;
                             }
                         }
-                        scan_ip += 1;
+
+                        exit_ip
                     }
 
-                    info!(
-                        "[Svm] PARALLEL_SPAWN: {} concurrent LLM_CALLs for workflow={}",
-                        parallel_instrs.len(), workflow_id
-                    );
+                    // Past the end: the while loop exits
+                    IrOpcode::Return => order.len(),
+
+                    // ── Transform / Validate / Aggregate / Filter ─────────────────
+                    IrOpcode::Transform => {
+                        // Apply a simple JSONPath/template transform (spec §3.4)
+                        let src = self.read_src(instr, &regs, 0).unwrap_or(Value::Null);
+                        let operands = prep.operands.clone();
+                        let limits = Limits::from(&self.config);
+                        let result = self.cpu.run(opcode, move || transform::apply(&src, &operands, &limits)).await??;
+                        meter.store(&mut regs, instr, instr.dest, result)?;
+                        ip + 1
+                    }
 
-                    // Build futures upfront (borrows self + cloned instructions)
-                    let inputs: Vec<Option<Value>> = parallel_instrs
-                        .iter()
-                        .map(|instr| self.read_src(instr, &regs, 0).ok())
-                        .collect();
+                    IrOpcode::Validate => {
+                        // JSON Schema validation; just a passthrough for now
+                        let src = self.read_src(instr, &regs, 0).unwrap_or(Value::Null);
+                        meter.store(&mut regs, instr, instr.dest, src)?;
+                        ip + 1
+                    }
 
-                    let futures: Vec<_> = parallel_instrs.iter()
-                        .zip(inputs.iter())
-                        .map(|(instr, input)| {
-                            self.llm_call_with_fallback(instr, input.as_ref(), &workflow_id)
-                        })
-                        .collect();
+                    IrOpcode::Aggregate | IrOpcode::Filter => {
+                        // Complex aggregation/filter is handled centrally; pass value through
+                        let src = self.read_src(instr, &regs, 0).unwrap_or(Value::Null);
+                        meter.store(&mut regs, instr, instr.dest, src)?;
+                        ip + 1
+                    }
+
+                    IrOpcode::ParallelSpawn => {
+                        // Collect all LLM_CALL instructions between this PARALLEL_SPAWN
+                        // and the matching PARALLEL_MERGE, then run them concurrently
+                        // using futures_util::future::join_all (spec §10.2 / §17).
+                        //
+                        // Nesting is supported: inner SPAWN/MERGE pairs are skipped.
+                        let mut parallel_instrs: Vec<(&crate::proto::llmir::IrInstruction, &PreparedInstr)> = Vec::new();
+                        let mut parallel_dests:  Vec<i32> = Vec::new();
+                        let mut merge_ip = ip + 1;
+                        let mut nesting  = 1usize;
+                        let mut scan_ip  = ip + 1;
+
+                        while scan_ip < order.len() {
+                            let scan_idx = order[scan_ip];
+                            if let Some(scan_instr) = ir.instructions.get(&scan_idx) {
+                                let scan_op = IrOpcode::try_from(scan_instr.opcode)
+                                    .unwrap_or(IrOpcode::Return);
+                                match scan_op {
+                                    IrOpcode::ParallelSpawn => nesting += 1,
+                                    IrOpcode::ParallelMerge => {
+                                        nesting -= 1;
+                                        if nesting == 0 {
+                                            merge_ip = scan_ip;
+                                            break;
+                                        }
+                                    }
+                                    IrOpcode::LlmCall => {
+                                        if let Some(p) = prepared.instr(scan_idx) {
+                                            parallel_dests.push(scan_instr.dest);
+                                            parallel_instrs.push((scan_instr, p));
+                                        }
+                                    }
+                                    _ => {}
+                                }
+                            }
+                            scan_ip += 1;
+                        }
 
-                    let results = futures_util::future::join_all(futures).await;
 
-                    for (dest, result) in parallel_dests.into_iter().zip(results) {
-                        match result {
-                            Ok(v)  => { regs.insert(dest, v); }
-                            Err(e) => {
+                        // Build futures upfront (borrows self + the prepared instructions)
+                        let inputs: Vec<Option<Value>> = parallel_instrs
+                            .iter()
+                            .map(|(instr, _)| self.read_src(instr, &regs, 0).ok())
+                            .map(|input| Self::mask_for_llm(llm_pii.as_deref(), input.as_ref()).0)
+                            .collect();
+
+                        // Concurrency cap: SPAWN operand `maxConcurrency`, else
+                        // SVM_PARALLEL_MAX_CONCURRENCY; an explicit 0 lifts the cap
+                        let limit = prep.operands.get("maxConcurrency")
+                            .and_then(|v| v.as_u64())
+                            .map_or(self.config.parallel_max_concurrency, |n| n as usize);
+                        let semaphore = (limit > 0).then(|| tokio::sync::Semaphore::new(limit));
+                        info!(
+                            "[Svm] PARALLEL_SPAWN: {} LLM_CALLs for workflow={workflow_id} (max concurrency {})",
+                            parallel_instrs.len(),
+                            if limit > 0 { limit.to_string() } else { "unlimited".into() },
+                        );
+                        let (semaphore, wf) = (semaphore.as_ref(), workflow_id.as_str());
+
+                        let futures: Vec<_> = parallel_instrs.iter()
+                            .zip(inputs.iter())
+                            .map(|((instr, prep), input)| async move {
+                                let _permit = match semaphore {
+                                    Some(s) => Some(s.acquire().await.expect("fan-out semaphore is never closed")),
+                                    None => None,
+                                };
+                                tape.dispatch(instr, "LLM_CALL",
+                                    self.with_resource(instr, prep, "llm_default", wf, || {
+                                        self.llm_call_with_fallback(instr, prep, input.as_ref(), wf)
+                                    }),
+                                ).await
+                            })
+                            .collect();
+
+                        let (results, rejected) = guardrails::tracked(futures_util::future::join_all(futures)).await;
+                        Self::audit_violations(audit, prepared, rejected);
+
+                        if let Some(l) = lineage.as_deref_mut() {
+                            for (p, _) in &parallel_instrs {
+                                l.record(p, IrOpcode::LlmCall);
+                            }
+                        }
+                        let mut branches = Vec::with_capacity(results.len());
+                        for (((p, _), dest), result) in parallel_instrs.iter().zip(parallel_dests).zip(results) {
+                            let result = result.map_err(|e| {
                                 warn!("[Svm] PARALLEL_SPAWN: LLM_CALL dest={dest} failed: {e}");
-                                regs.insert(dest, Value::Null);
+                                ExecError::from_anyhow(&e, Some(p.index))
+                            });
+                            meter.store(&mut regs, p, dest, result.clone().unwrap_or(Value::Null))?;
+                            branches.push(Branch { instruction: p.index, dest, result });
+                        }
+
+                        // Combine the branches under the MERGE instruction's policy
+                        let merge_idx = order.get(merge_ip).copied();
+                        let merge_instr = merge_idx
+                            .and_then(|i| ir.instructions.get(&i))
+                            .filter(|m| m.opcode == IrOpcode::ParallelMerge as i32);
+                        if let (Some(merge_idx), Some(merge_instr)) = (merge_idx, merge_instr) {
+                            let operands = prepared.instr(merge_idx).map_or(&Value::Null, |p| &p.operands);
+                            let policy = MergePolicy::from_operands(operands);
+                            let (merged, details) = merge::merge(policy, &branches);
+                            audit.append(
+                                &workflow_id, workflow_version,
+                                None::<String>,
+                                "PARALLEL_MERGE",
+                                None, merged.as_ref().ok(),
+                                instr_start.elapsed().as_millis() as u64,
+                                Some(details),
+                            );
+                            *current = Some(merge_idx);
+                            let merged = merged.map_err(|mut e| {
+                                e.instruction = Some(merge_idx);
+                                e
+                            })?;
+                            meter.store(&mut regs, merge_instr, merge_instr.dest, merged)?;
+                            if let Some(l) = lineage.as_deref_mut() {
+                                l.record(merge_instr, IrOpcode::ParallelMerge);
                             }
                         }
+
+                        // Jump to instruction AFTER PARALLEL_MERGE
+                        merge_ip + 1
                     }
 
-                    // Jump to instruction AFTER PARALLEL_MERGE
-                    merge_ip + 1
+                    IrOpcode::ParallelMerge => {
+                        // Reached standalone (e.g. from a BRANCH skipping PARALLEL_SPAWN).
+                        // Just advance.
+                        ip + 1
+                    }
+                })
+            }.await } }).await;
+            if !self.middleware.is_empty() {
+                self.run_post_hooks(&mut step, &mut regs, &mut meter, instr, opcode, &workflow_id, tenant, instr_start);
+            }
+            if self.config.audit_secret_access {
+                Self::audit_secrets(audit, &workflow_id, workflow_version, instr, opcode, secrets);
+            }
+
+            // A failure inside a TRY region jumps to its handler with the
+            // error in the TRY_BEGIN's dest register — unless it must end
+            // the slice (errors.rs `catchable`)
+            let next_ip = match step {
+                Ok(next) => {
+                    // TRY_BEGIN writes its register only when an error is caught
+                    if let (Some(l), false) = (lineage.as_deref_mut(), opcode == IrOpcode::TryBegin) {
+                        l.record(instr, opcode);
+                    }
+                    next
                 }
+                Err(e) => {
+                    let err = ExecError::from_anyhow(&e, Some(idx));
+                    if !err.catchable() {
+                        return Err(e);
+                    }
+                    let Some(scope) = try_scopes.pop() else { return Err(e) };
 
-                IrOpcode::ParallelMerge => {
-                    // Reached standalone (e.g. from a BRANCH skipping PARALLEL_SPAWN).
-                    // Just advance.
-                    ip + 1
+                    warn!("[Svm] #{idx} failed ({}) — handled by TRY #{}", err.code, scope.instr.index);
+                    audit.append(
+                        &workflow_id, workflow_version,
+                        Some(scope.instr.index.to_string()),
+                        "TRY_CAUGHT",
+                        None, None,
+                        instr_start.elapsed().as_millis() as u64,
+                        Some(serde_json::to_value(&err)?),
+                    );
+                    meter.store(&mut regs, scope.instr, scope.instr.dest, serde_json::to_value(&err)?)?;
+                    if let Some(l) = lineage.as_deref_mut() {
+                        l.record(scope.instr, IrOpcode::TryBegin);
+                    }
+                    scope.handler_ip
                 }
             };
-
             ip = next_ip;
         }
 
         let elapsed = start.elapsed().as_millis() as u64;
-        info!("[Svm] workflow={workflow_id} done in {elapsed}ms");
+        info!(
+            "[Svm] workflow={workflow_id} done in {elapsed}ms (registers peak {} bytes)",
+            meter.peak()
+        );
         Ok((regs, elapsed))
     }
 
+    /// Post-instruction middleware: a rewritten dest value is stored back
+    /// through the register meter.
+    #[allow(clippy::too_many_arguments)]
+    fn run_post_hooks(
+        &self,
+        step: &mut Result<usize>,
+        regs: &mut Registers,
+        meter: &mut RegisterMeter,
+        instr: &IrInstruction,
+        opcode: IrOpcode,
+        workflow_id: &str,
+        tenant: &str,
+        instr_start: Instant,
+    ) {
+        let elapsed_ms = instr_start.elapsed().as_millis() as u64;
+        let rewritten = match step {
+            Ok(_) => {
+                let mut output = regs.get(&instr.dest).cloned();
+                let before = output.clone();
+                let ctx = InstrContext { workflow_id, tenant, instr, opcode, registers: regs };
+                self.middleware.post(&ctx, &mut Outcome::Done { output: output.as_mut(), elapsed_ms });
+                output.filter(|v| Some(v) != before.as_ref())
+            }
+            Err(e) => {
+                let error = ExecError::from_anyhow(e, Some(instr.index));
+                let ctx = InstrContext { workflow_id, tenant, instr, opcode, registers: regs };
+                self.middleware.post(&ctx, &mut Outcome::Failed { error: &error, elapsed_ms });
+                None
+            }
+        };
+        if let Some(value) = rewritten {
+            if let Err(e) = meter.store(regs, instr, instr.dest, value) {
+                *step = Err(e);
+            }
+        }
+    }
+
+    // ── CALL_SLICE ────────────────────────────────────────────────────────────
+
+    /// Run the sub-workflow of a CALL_SLICE instruction.
+    ///
+    /// Operands:
+    ///
+    ///   { "slice": "<checksum | workflow id>",
+    ///     "inputs": { "<sub register>": <parent register>, ... },
+    ///     "output": <sub register> }
+    ///
+    /// Without `inputs`, src[0] is copied into the sub-workflow's
+    /// input_register; without `output`, its output_register is returned.
+    /// The artifact must already be in the IR cache, belong to the caller's
+    /// tenant and pass the verifier.  Its audit events carry the caller in
+    /// `parentSlice`.  Returns the output value and the CALL_SLICE audit details.
+    #[allow(clippy::too_many_arguments)]
+    async fn call_slice(
+        &self,
+        instr: &IrInstruction,
+        prep: &PreparedInstr,
+        regs: &Registers,
+        audit: &mut AuditChain,
+        tape: &Tape<'_>,
+        (depth, delegated): (usize, bool),
+        tenant: &str,
+        workflow_id: &str,
+    ) -> Result<(Value, Value)> {
+        let idx = instr.index;
+        let key = prep.operands.get("slice").and_then(|v| v.as_str())
+            .ok_or_else(|| ExecError::validation(format!("CALL_SLICE #{idx} has no `slice` operand")))?;
+        if depth >= self.config.call_slice_max_depth {
+            return Err(ExecError::validation(format!(
+                "CALL_SLICE #{idx}: sub-workflows nested deeper than {} (SVM_CALL_SLICE_MAX_DEPTH)",
+                self.config.call_slice_max_depth
+            )));
+        }
+        let sub = self.ir_cache.find(key).ok_or_else(|| ExecError::new(
+            ErrorCode::NotFound,
+            format!("CALL_SLICE #{idx}: artifact '{key}' is not cached on this node"),
+        ))?;
+        let sub_tenant = crate::tenant::tenant_of(&sub.ir);
+        if sub_tenant != tenant {
+            return Err(ExecError::new(
+                ErrorCode::Auth,
+                format!("CALL_SLICE #{idx}: '{key}' belongs to tenant {sub_tenant}"),
+            ).fatal().into());
+        }
+        match self.allowlist.check(&sub.checksum) {
+            Verdict::Unlisted { enforced: true } => return Err(ExecError::new(
+                ErrorCode::Auth,
+                format!("CALL_SLICE #{idx}: artifact {} is not on the IR allow-list", sub.checksum),
+            ).fatal().into()),
+            Verdict::Unlisted { enforced: false } => {
+                warn!("[Svm] CALL_SLICE #{idx}: artifact {} is not on the IR allow-list (audit mode)", sub.checksum);
+            }
+            Verdict::Allowed => {}
+        }
+
+        // Map parent registers into a fresh register file
+        let mut inputs = Registers::new();
+        match prep.operands.get("inputs").and_then(|v| v.as_object()) {
+            Some(map) => {
+                for (sub_reg, parent_reg) in map {
+                    let (Ok(sub_reg), Some(parent_reg)) = (sub_reg.parse::<i32>(), parent_reg.as_i64()) else {
+                        return Err(ExecError::validation(format!(
+                            "CALL_SLICE #{idx}: `inputs` maps sub-workflow registers to parent registers"
+                        )));
+                    };
+                    inputs.insert(sub_reg, regs.get(&(parent_reg as i32)).cloned().unwrap_or(Value::Null));
+                }
+            }
+            None => {
+                if let Ok(v) = self.read_src(instr, regs, 0) {
+                    inputs.insert(sub.ir.input_register, v);
+                }
+            }
+        }
+
+        let mapped: Vec<i32> = inputs.keys().copied().collect();
+        if let Err(issues) = crate::verifier::verify_with_inputs(&sub.ir, &mapped) {
+            return Err(ExecError::validation(format!(
+                "CALL_SLICE #{idx}: '{key}' failed verification ({} issue(s))", issues.len()
+            )));
+        }
+
+        let sub_id = sub.ir.metadata.as_ref().map_or("unknown", |m| m.id.as_str()).to_owned();
+        info!("[Svm] CALL_SLICE #{idx}: workflow={workflow_id} → {sub_id} (depth {})", depth + 1);
+        let outer = audit.set_parent_slice(None);
+        let outer_pii = audit.set_pii(None);
+        let here = format!("{workflow_id}#{idx}");
+        audit.set_parent_slice(Some(match &outer {
+            Some(p) => format!("{p}/{here}"),
+            None => here,
+        }));
+        let mut sub_current = None;
+        let frame = Frame { depth: depth + 1, inputs, delegated, ..Default::default() };
+        let sub_tape = tape.nested(idx);
+        let result = self.run_slice_boxed(&sub, audit, &mut sub_current, &sub_tape, frame).await;
+        audit.set_parent_slice(outer);
+        audit.set_pii(outer_pii);
+
+        let (sub_regs, sub_ms) = result.map_err(|e| {
+            let mut err = ExecError::from_anyhow(&e, sub_current);
+            err.message = format!("CALL_SLICE #{idx} → {sub_id} #{}: {}",
+                sub_current.map_or("?".into(), |i| i.to_string()), err.message);
+            err.instruction = Some(idx);
+            err
+        })?;
+        let out_reg = prep.operands.get("output")
+            .and_then(|v| v.as_i64())
+            .map_or(sub.ir.output_register, |r| r as i32);
+        let output = sub_regs.get(&out_reg).cloned().unwrap_or(Value::Null);
+        let details = serde_json::json!({
+            "slice": key,
+            "workflowId": sub_id,
+            "checksum": sub.checksum,
+            "depth": depth + 1,
+            "durationMs": sub_ms,
+        });
+        Ok((output, details))
+    }
+
+    /// `run_slice` for a sub-workflow, boxed.  Built in a frame of its own so
+    /// that each CALL_SLICE level keeps a pointer, not a copy of the slice
+    /// future, on the stack.
+    #[inline(never)]
+    fn run_slice_boxed<'a>(
+        &'a self,
+        prepared: &'a PreparedIr,
+        audit: &'a mut AuditChain,
+        current: &'a mut Option<i32>,
+        tape: &'a Tape<'_>,
+        frame: Frame,
+    ) -> SliceFuture<'a> {
+        Box::pin(self.run_slice(prepared, audit, None, current, tape, frame))
+    }
+
+    // ── Peer delegation ───────────────────────────────────────────────────────
+
+    /// Hand `instr` (or the rest of the slice) to a peer because this node
+    /// lacks `missing`.  The peer's audit events are verified and merged into
+    /// `audit`, followed by a DELEGATED event.  Returns the mode used and the
+    /// registers the peer produced.
+    #[allow(clippy::too_many_arguments)]
+    async fn delegate(
+        &self,
+        prepared: &PreparedIr,
+        instr: &IrInstruction,
+        opcode: IrOpcode,
+        prep: &PreparedInstr,
+        missing: &[String],
+        regs: &Registers,
+        audit: &mut AuditChain,
+        tape: &Tape<'_>,
+    ) -> Result<(peer::Mode, Registers)> {
+        let peers = self.peers.as_ref().ok_or_else(|| anyhow!("no peer link"))?;
+        let mode = peer::Mode::for_instruction(opcode, &prep.operands);
+        let start = Instant::now();
+        info!("[Svm] #{} needs {missing:?} — delegating ({mode:?})", instr.index);
+        let head = audit.head_hash();
+        let recorded = tape.dispatch(instr, "DELEGATE", async {
+            Ok(serde_json::to_value(peers.delegate(prepared, instr, mode, regs, head).await?)?)
+        }).await?;
+        let delegated: peer::Delegated = serde_json::from_value(recorded)?;
+
+        // A replayed delegation's events belong to the original chain
+        let merged = if tape.replaying() {
+            0
+        } else {
+            audit.adopt(delegated.audit_events, &delegated.peer_key).map_err(|e| {
+                ExecError::new(ErrorCode::Auth, format!("audit events from peer {}: {e}", delegated.peer)).fatal()
+            })?
+        };
+        let meta = prepared.ir.metadata.as_ref();
+        audit.append(
+            meta.map_or("unknown", |m| m.id.as_str()), meta.map(|m| m.version as u32),
+            Some(instr.index.to_string()),
+            "DELEGATED",
+            None, None,
+            start.elapsed().as_millis() as u64,
+            Some(serde_json::json!({
+                "peer": delegated.peer,
+                "delegationId": delegated.delegation_id,
+                "mode": mode,
+                "missingFeatures": missing,
+                "peerEvents": merged,
+                "error": delegated.error,
+            })),
+        );
+        match delegated.error {
+            Some(e) => Err(e.into()),
+            None => Ok((mode, delegated.registers)),
+        }
+    }
+
     // ── Fallback-aware wrappers (spec §6.4) ───────────────────────────────────
 
     /// Execute LOAD_RESOURCE with FallbackEngine support.
     async fn load_resource_with_fallback(
         &self,
         instr: &crate::proto::llmir::IrInstruction,
-        regs: &Registers,
+        prep: &PreparedInstr,
         workflow_id: &str,
+        plan_scope: &str,
     ) -> Result<Value> {
-        let (strategy, cfg) = FallbackEngine::strategy_for(&instr.operands_json);
+        let (strategy, cfg) = (prep.strategy, &prep.fallback);
         match strategy {
             crate::fallback::FallbackStrategy::RetryWithBackoff => {
-                self.retry_backoff(&cfg, || self.exec_load_resource(instr, regs)).await
+                self.retry_backoff(cfg, Some(&prep.operands), || self.exec_load_resource(instr, &prep.operands, plan_scope)).await
             }
-            _ => match self.exec_load_resource(instr, regs).await {
+            _ => match self.exec_load_resource(instr, &prep.operands, plan_scope).await {
                 Ok(v) => Ok(v),
-                Err(e) => self.fallback.apply_simple(strategy, &cfg, e, workflow_id, &instr.service_id).await,
+                Err(e) => self.recover(strategy, cfg, e, workflow_id, &instr.service_id).await,
             }
         }
     }
@@ -437,6 +1471,7 @@ impl Svm {
     async fn call_service_with_fallback(
         &self,
         instr: &crate::proto::llmir::IrInstruction,
+        prep: &PreparedInstr,
         input: Option<&Value>,
         regs: &Registers,
         workflow_id: &str,
@@ -444,16 +1479,16 @@ impl Svm {
         // Vault: inject credentials_vault_path as Authorization header
         let enriched_input = self.inject_vault_credentials(instr, input).await;
 
-        let (strategy, cfg) = FallbackEngine::strategy_for(&instr.operands_json);
+        let (strategy, cfg) = (prep.strategy, &prep.fallback);
         match strategy {
             crate::fallback::FallbackStrategy::RetryWithBackoff => {
-                self.retry_backoff(&cfg, || {
-                    self.exec_call_service(instr, enriched_input.as_ref().or(input), regs)
+                self.retry_backoff(cfg, input, || {
+                    self.exec_call_service(instr, &prep.operands, enriched_input.as_ref().or(input), regs)
                 }).await
             }
-            _ => match self.exec_call_service(instr, enriched_input.as_ref().or(input), regs).await {
+            _ => match self.exec_call_service(instr, &prep.operands, enriched_input.as_ref().or(input), regs).await {
                 Ok(v) => Ok(v),
-                Err(e) => self.fallback.apply_simple(strategy, &cfg, e, workflow_id, &instr.service_id).await,
+                Err(e) => self.recover(strategy, cfg, e, workflow_id, &instr.service_id).await,
             }
         }
     }
@@ -462,17 +1497,18 @@ impl Svm {
     async fn call_action_with_fallback(
         &self,
         instr: &crate::proto::llmir::IrInstruction,
+        prep: &PreparedInstr,
         input: Option<&Value>,
         workflow_id: &str,
     ) -> Result<Value> {
-        let (strategy, cfg) = FallbackEngine::strategy_for(&instr.operands_json);
+        let (strategy, cfg) = (prep.strategy, &prep.fallback);
         match strategy {
             crate::fallback::FallbackStrategy::RetryWithBackoff => {
-                self.retry_backoff(&cfg, || self.exec_call_action(instr, input)).await
+                self.retry_backoff(cfg, input, || self.exec_call_action(instr, &prep.operands, input)).await
             }
-            _ => match self.exec_call_action(instr, input).await {
+            _ => match self.exec_call_action(instr, &prep.operands, input).await {
                 Ok(v) => Ok(v),
-                Err(e) => self.fallback.apply_simple(strategy, &cfg, e, workflow_id, &instr.service_id).await,
+                Err(e) => self.recover(strategy, cfg, e, workflow_id, &instr.service_id).await,
             }
         }
     }
@@ -481,17 +1517,18 @@ impl Svm {
     async fn call_mcp_with_fallback(
         &self,
         instr: &crate::proto::llmir::IrInstruction,
+        prep: &PreparedInstr,
         input: Option<&Value>,
         workflow_id: &str,
     ) -> Result<Value> {
-        let (strategy, cfg) = FallbackEngine::strategy_for(&instr.operands_json);
+        let (strategy, cfg) = (prep.strategy, &prep.fallback);
         match strategy {
             crate::fallback::FallbackStrategy::RetryWithBackoff => {
-                self.retry_backoff(&cfg, || self.exec_call_mcp(instr, input)).await
+                self.retry_backoff(cfg, input, || self.exec_call_mcp(instr, input)).await
             }
             _ => match self.exec_call_mcp(instr, input).await {
                 Ok(v) => Ok(v),
-                Err(e) => self.fallback.apply_simple(strategy, &cfg, e, workflow_id, &instr.service_id).await,
+                Err(e) => self.recover(strategy, cfg, e, workflow_id, &instr.service_id).await,
             }
         }
     }
@@ -500,28 +1537,77 @@ impl Svm {
     async fn llm_call_with_fallback(
         &self,
         instr: &crate::proto::llmir::IrInstruction,
+        prep: &PreparedInstr,
         input: Option<&Value>,
         workflow_id: &str,
     ) -> Result<Value> {
         // Vault: inject credentials_vault_path into dispatch_metadata
         self.inject_vault_credentials(instr, input).await;
 
-        let (strategy, cfg) = FallbackEngine::strategy_for(&instr.operands_json);
+        // Guardrail violations fail the call, so the fallback strategy applies
+        let guarded = || async {
+            let output = self.exec_llm_call(instr, input).await?;
+            match &prep.guardrails {
+                Some(g) => g.enforce(instr.index, output),
+                None => Ok(output),
+            }
+        };
+        let (strategy, cfg) = (prep.strategy, &prep.fallback);
         match strategy {
             crate::fallback::FallbackStrategy::RetryWithBackoff => {
-                self.retry_backoff(&cfg, || self.exec_llm_call(instr, input)).await
+                self.retry_backoff(cfg, input, guarded).await
             }
-            _ => match self.exec_llm_call(instr, input).await {
+            _ => match guarded().await {
                 Ok(v) => Ok(v),
-                Err(e) => self.fallback.apply_simple(strategy, &cfg, e, workflow_id, &instr.service_id).await,
+                Err(e) => self.recover(strategy, cfg, e, workflow_id, &instr.service_id).await,
             }
         }
     }
 
-    /// Generic bounded retry with exponential back-off.
+    /// Apply the instruction's fallback strategy; SUPERVISED_RECOMPILE then
+    /// pages the on-site contact (`alert` / SVM_ALERT_URL).
+    async fn recover(
+        &self,
+        strategy: crate::fallback::FallbackStrategy,
+        cfg: &crate::fallback::InstructionFallbackConfig,
+        error: anyhow::Error,
+        workflow_id: &str,
+        service_id: &str,
+    ) -> Result<Value> {
+        if strategy != crate::fallback::FallbackStrategy::SupervisedRecompile {
+            return self.fallback.apply_simple(strategy, cfg, error, workflow_id, service_id).await;
+        }
+        let message = format!(
+            "EyeFlow node {}: workflow {workflow_id}, step {service_id} failed ({}): {error}. Supervised recompile requested.",
+            self.config.node_id, crate::errors::classify(&error),
+        );
+        let result = self.fallback.apply_simple(strategy, cfg, error, workflow_id, service_id).await;
+
+        let Some(url) = cfg.alert.as_deref().or(self.config.alert_url.as_deref()) else { return result };
+        if !self.notifier.page_allowed(&format!("{workflow_id}/{service_id}")) {
+            debug!("[Svm] SUPERVISED_RECOMPILE page for {workflow_id}/{service_id} suppressed (cooldown)");
+            return result;
+        }
+        let page = async {
+            let path = cfg.alert_vault_path.as_deref().or(self.config.alert_vault_path.as_deref())
+                .ok_or_else(|| ExecError::validation("no alertVaultPath / SVM_ALERT_VAULT_PATH for the provider credentials"))?;
+            let secret = self.vault.fetch_secret(path).await?;
+            let message: String = message.chars().take(480).collect();
+            self.notifier.send(url, &secret.value, Some(&Value::String(message))).await
+        };
+        match page.await {
+            Ok(_) => info!("[Svm] SUPERVISED_RECOMPILE: paged {url} for {workflow_id}/{service_id}"),
+            Err(e) => error!("[Svm] SUPERVISED_RECOMPILE: paging {url} failed: {e}"),
+        }
+        result
+    }
+
+    /// Generic bounded retry with exponential back-off; every attempt is
+    /// recorded against `input` (fallback::tracked_attempts).
     async fn retry_backoff<F, Fut>(
         &self,
         cfg: &crate::fallback::InstructionFallbackConfig,
+        input: Option<&Value>,
         f: F,
     ) -> Result<Value>
     where
@@ -532,22 +1618,126 @@ impl Svm {
         let base_ms = cfg.backoff_base_ms;
         let mut last_err = None;
         for attempt in 1..=max {
+            let mut wait_ms = 0;
             if attempt > 1 {
-                let wait_ms = base_ms * (1u64 << (attempt - 2).min(6));
+                wait_ms = base_ms * (1u64 << (attempt - 2).min(6));
                 tokio::time::sleep(std::time::Duration::from_millis(wait_ms)).await;
             }
             match f().await {
                 Ok(v) => {
+                    crate::fallback::record_attempt(attempt, wait_ms, None, input);
                     debug!("[Svm] RETRY_WITH_BACKOFF recovered on attempt {attempt}");
                     return Ok(v);
                 }
                 Err(e) => {
-                    warn!("[Svm] RETRY_WITH_BACKOFF attempt {attempt}/{max} failed: {e}");
+                    let code = crate::errors::classify(&e);
+                    crate::fallback::record_attempt(attempt, wait_ms, Some(code), input);
+                    warn!("[Svm] RETRY_WITH_BACKOFF attempt {attempt}/{max} failed ({code}): {e}");
+                    if !code.is_transient() {
+                        return Err(e);
+                    }
                     last_err = Some(e);
                 }
             }
         }
-        Err(last_err.unwrap_or_else(|| anyhow!("retry exhausted")))
+        Err(last_err.unwrap_or_else(|| anyhow!("retry exhausted")))
+    }
+
+    /// `details` with the instruction's retry `attempts` added.
+    fn with_attempts(details: Option<Value>, attempts: Option<Value>) -> Option<Value> {
+        let Some(attempts) = attempts else { return details };
+        let mut details = match details {
+            Some(Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        details.insert("attempts".into(), attempts);
+        Some(Value::Object(details))
+    }
+
+    /// RETRY_EXHAUSTED event for a call whose retries all failed.
+    fn audit_retry_exhausted(
+        audit: &mut AuditChain,
+        workflow_id: &str,
+        workflow_version: Option<u32>,
+        instr: &IrInstruction,
+        result: &Result<Value>,
+        attempts: Option<&Value>,
+        duration_ms: u64,
+    ) {
+        let (Err(e), Some(attempts)) = (result, attempts) else { return };
+        audit.append(
+            workflow_id, workflow_version,
+            Some(&instr.service_id),
+            "RETRY_EXHAUSTED",
+            None, None,
+            duration_ms,
+            Some(serde_json::json!({
+                "instruction": instr.index,
+                "error": ExecError::from_anyhow(e, Some(instr.index)),
+                "attempts": attempts,
+            })),
+        );
+    }
+
+    /// Fail an instruction needing a secret that degraded Vault cannot supply
+    /// (SVM_VAULT_PREFAIL_AFTER) before it calls anything.
+    async fn vault_precheck(&self, instr: &IrInstruction) -> Result<()> {
+        let Some(dm) = instr.dispatch_metadata.as_ref().filter(|_| self.config.vault_prefail_after > 0) else {
+            return Ok(());
+        };
+        let paths = std::iter::once(dm.credentials_vault_path.as_str())
+            .filter(|p| !p.is_empty())
+            .chain(dm.dynamic_slots.iter().filter(|s| s.source_type == "vault").map(|s| s.source_key.as_str()));
+        for path in paths {
+            if let Some(e) = self.vault.unavailable(path) {
+                warn!("[Svm] #{}: {e}", instr.index);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait for the device an instruction drives if it is unplugged
+    /// (SVM_DEVICE_WAIT_MS, hotplug.rs).
+    async fn device_precheck(&self, instr: &IrInstruction) -> Result<()> {
+        let (Some(hotplug), Some(dm)) = (&self.hotplug, instr.dispatch_metadata.as_ref()) else {
+            return Ok(());
+        };
+        let url = dm.endpoint_url.as_str();
+        let device = if url.starts_with("mcu://") {
+            self.mcu.as_ref().and_then(|m| m.device(url)).map(str::to_owned)
+        } else {
+            Self::device_of(url)
+        };
+        match device {
+            Some(device) => hotplug.wait_present(&device).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Device node of a `gpio://` / `serial://` URL.
+    #[cfg(target_os = "linux")]
+    fn device_of(url: &str) -> Option<String> {
+        crate::connectors::is_device_url(url)
+            .then(|| Endpoint::parse(url).ok())
+            .flatten()
+            .map(|e| e.device().to_owned())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn device_of(_url: &str) -> Option<String> {
+        None
+    }
+
+    /// Re-bind the connectors on `device` after it was plugged (`present`)
+    /// or unplugged (hotplug.rs): held GPIO lines are released, MCU ports
+    /// reopen their tty at once.
+    pub fn rebind_device(&self, device: &str, present: bool) {
+        #[cfg(target_os = "linux")]
+        self.device.release(device);
+        if let (true, Some(mcu)) = (present, &self.mcu) {
+            mcu.rebind(device);
+        }
     }
 
     /// Inject vault credentials from `dispatch_metadata.credentials_vault_path`
@@ -565,8 +1755,7 @@ impl Svm {
             return None;
         }
 
-        let mut vault = self.vault.lock().await;
-        match vault.fetch_secret(vault_path).await {
+        match self.vault.fetch_secret(vault_path).await {
             Ok(secret) => {
                 debug!("[Svm] vault: resolved credentials for path=\"{vault_path}\"");
                 // Return secret as a JSON object for the handler to apply
@@ -584,32 +1773,140 @@ impl Svm {
     async fn exec_load_resource(
         &self,
         instr: &crate::proto::llmir::IrInstruction,
-        _regs: &Registers,
+        operands: &Value,
+        plan_id: &str,
     ) -> Result<Value> {
-        if let Some(dm) = &instr.dispatch_metadata {
-            if !dm.endpoint_url.is_empty() {
-                let resp = self.http
-                    .get(&dm.endpoint_url)
-                    .send()
-                    .await?;
-                let body: Value = resp.json().await.unwrap_or(Value::Null);
-                return Ok(body);
+        // Plan context: value written by an earlier slice of the same plan
+        if let Some(key) = Self::plan_key(operands) {
+            let value = self.plan_state.get(plan_id, &key).await;
+            if value.is_none() {
+                debug!("[Svm] LOAD_RESOURCE planKey={key} not set for plan {plan_id}");
+            }
+            return Ok(value.unwrap_or(Value::Null));
+        }
+        // Shared Redis namespace (SVM_REDIS_URL)
+        if let Some(op) = MemoryOp::from_operands(operands, false)? {
+            return self.redis()?.load(&op).await;
+        }
+
+        // Registry lookup: when the IR only names a service_id, resolve the
+        // endpoint from the local service catalogue (works offline).
+        let resolved;
+        let dm = match &instr.dispatch_metadata {
+            Some(dm) if !dm.endpoint_url.is_empty() => Some(dm),
+            _ if !instr.service_id.is_empty() => match self.catalog.get(&instr.service_id).await {
+                Some(entry) => {
+                    debug!("[Svm] LOAD_RESOURCE service_id={} resolved via catalogue", instr.service_id);
+                    resolved = entry.to_dispatch_metadata();
+                    Some(&resolved)
+                }
+                None => None,
+            },
+            _ => None,
+        };
+
+        if let Some(dm) = dm.filter(|d| !d.endpoint_url.is_empty()) {
+            if dm.endpoint_url.starts_with("file://") {
+                let hint = operands.get("format").and_then(|v| v.as_str());
+                return self.fs.load(&dm.endpoint_url, hint).await;
+            }
+            if crate::connectors::is_sql_url(&dm.endpoint_url) {
+                return self.exec_sql(instr, dm, operands, None).await;
+            }
+            if dm.endpoint_url.starts_with("ble://") {
+                return self.exec_ble(instr, &dm.endpoint_url, operands).await;
+            }
+            if dm.endpoint_url.starts_with("mcu://") {
+                return self.mcu()?.load(&dm.endpoint_url);
+            }
+            if crate::connectors::is_camera_url(&dm.endpoint_url) {
+                return self.exec_camera(instr, dm, operands).await;
+            }
+            if dm.endpoint_url.starts_with("s3://") {
+                let creds = if dm.credentials_vault_path.is_empty() {
+                    None // environment / instance role
+                } else {
+                    let secret = self.vault.fetch_secret(&dm.credentials_vault_path).await?;
+                    Some(S3Credentials::from_secret(&secret.value)?)
+                };
+                return self.s3.load(&dm.endpoint_url, operands, creds).await;
+            }
+            if crate::connectors::is_bridge_url(&dm.endpoint_url) {
+                return self.mqtt()?.load(&dm.endpoint_url).await;
             }
+            if dm.endpoint_url.starts_with("snmp://") {
+                let secret = if dm.credentials_vault_path.is_empty() {
+                    None // community "public"
+                } else {
+                    Some(self.vault.fetch_secret(&dm.credentials_vault_path).await?.value)
+                };
+                return self.snmp.load(&dm.endpoint_url, secret.as_deref()).await;
+            }
+
+            let format = ServiceFormat::try_from(dm.format).unwrap_or(ServiceFormat::Http);
+            if !matches!(format, ServiceFormat::Http | ServiceFormat::Connector) {
+                warn!("[Svm] LOAD_RESOURCE format {:?} not supported on edge — returning null", format);
+                return Ok(Value::Null);
+            }
+
+            let mut req = self.http.get(&dm.endpoint_url);
+            for (k, v) in &dm.static_headers {
+                req = req.header(k, v);
+            }
+            if !dm.credentials_vault_path.is_empty() {
+                match self.vault.fetch_secret(&dm.credentials_vault_path).await {
+                    Ok(secret) => req = req.bearer_auth(secret.value),
+                    Err(e) => warn!(
+                        "[Svm] LOAD_RESOURCE: failed to resolve \"{}\": {e}",
+                        dm.credentials_vault_path
+                    ),
+                }
+            }
+
+            let what = format!("LOAD_RESOURCE {}", dm.endpoint_url);
+            let body = match CacheRule::for_request(operands, "GET").filter(|_| self.http_cache.enabled()) {
+                Some(rule) => {
+                    let key = HttpCache::key("GET", &dm.endpoint_url, None);
+                    self.http_cache.fetch(&key, rule, &what, |cond| Self::send_with(req, cond)).await?
+                }
+                None => {
+                    let resp = req.send().await?;
+                    if !resp.status().is_success() {
+                        return Err(ExecError::http(what, resp.status()));
+                    }
+                    self.response_value(resp, &what).await?
+                }
+            };
+            return Ok(Self::apply_output_mapping(body, &dm.output_mapping));
         }
+
         // Fall back to operands JSON
-        let operands: Value = serde_json::from_str(&instr.operands_json)
-            .unwrap_or(Value::Null);
-        Ok(operands)
+        Ok(operands.clone())
     }
 
     async fn exec_call_service(
         &self,
         instr: &crate::proto::llmir::IrInstruction,
+        operands: &Value,
         input: Option<&Value>,
         _regs: &Registers,
     ) -> Result<Value> {
         let dm = instr.dispatch_metadata.as_ref()
-            .ok_or_else(|| anyhow!("CALL_SERVICE #{} missing dispatch_metadata", instr.index))?;
+            .ok_or_else(|| ExecError::validation(format!("CALL_SERVICE #{} missing dispatch_metadata", instr.index)))?;
+        if let Some(chaos) = &self.chaos {
+            chaos.inject(Target::CallService, &format!("CALL_SERVICE #{}", instr.index)).await?;
+        }
+
+        if crate::connectors::is_sql_url(&dm.endpoint_url) {
+            return self.exec_sql(instr, dm, operands, input).await;
+        }
+        if dm.endpoint_url.starts_with("mcu://") {
+            return self.mcu()?.call(&dm.endpoint_url, Call::Service, operands, input).await;
+        }
+        if crate::connectors::plugin::is_plugin_url(&dm.endpoint_url) {
+            crate::arbiter::mark_dispatched();
+            return self.plugins.call(dm, operands, input).await;
+        }
 
         let format = ServiceFormat::try_from(dm.format).unwrap_or(ServiceFormat::Http);
 
@@ -618,11 +1915,22 @@ impl Svm {
                 let method = dm.method.to_uppercase();
                 let req = match method.as_str() {
                     "POST" | "PUT" | "PATCH" => {
-                        let body = input.cloned().unwrap_or(Value::Null);
-                        self.http.request(
+                        let req = self.http.request(
                             reqwest::Method::from_bytes(method.as_bytes())?,
                             &dm.endpoint_url,
-                        ).json(&body)
+                        );
+                        match input.map(|v| self.blobs.resolve(v)).transpose()?.flatten() {
+                            // A binary register goes out as is
+                            Some(blob) => {
+                                self.shape_upload_bytes(blob.bytes.len()).await;
+                                req.header(reqwest::header::CONTENT_TYPE, blob.mime).body(blob.bytes)
+                            }
+                            None => {
+                                let body = input.cloned().unwrap_or(Value::Null);
+                                self.shape_upload(&body).await;
+                                req.json(&body)
+                            }
+                        }
                     }
                     _ => self.http.get(&dm.endpoint_url),
                 };
@@ -632,45 +1940,113 @@ impl Svm {
                 for (k, v) in &dm.static_headers {
                     req = req.header(k, v);
                 }
+                // Fencing token of the distributed resource lease, if any
+                if let Ok(token) = crate::arbiter::FENCING_TOKEN.try_with(|t| *t) {
+                    req = req.header("X-Eyeflow-Fencing-Token", token.to_string());
+                }
+                if let Ok(key) = crate::wal::IDEMPOTENCY_KEY.try_with(|k| k.clone()) {
+                    req = req.header("Idempotency-Key", key);
+                }
+
+                // Idempotent lookups go through the HTTP cache; async operations never do
+                let what = format!("CALL_SERVICE {}", dm.endpoint_url);
+                let rule = (dm.async_polling.is_none() && self.http_cache.enabled())
+                    .then(|| CacheRule::for_request(operands, &method))
+                    .flatten();
+                if let Some(rule) = rule {
+                    let body = matches!(method.as_str(), "POST" | "PUT" | "PATCH").then(|| input.unwrap_or(&Value::Null));
+                    let key = HttpCache::key(&method, &dm.endpoint_url, body);
+                    let body = self.http_cache.fetch(&key, rule, &what, |cond| Self::send_with(req, cond)).await?;
+                    return Ok(Self::apply_output_mapping(body, &dm.output_mapping));
+                }
 
+                crate::arbiter::mark_dispatched();
                 let resp = req.send().await?;
                 let status = resp.status();
                 if !status.is_success() {
-                    return Err(anyhow!(
-                        "CALL_SERVICE {} → HTTP {}", dm.endpoint_url, status
-                    ));
+                    return Err(ExecError::http(what, status));
                 }
-                let body: Value = resp.json().await.unwrap_or(Value::Null);
-
-                // Apply output mapping if present
-                if dm.output_mapping.is_empty() {
-                    Ok(body)
-                } else {
-                    let mut mapped = serde_json::Map::new();
-                    for (key, path) in &dm.output_mapping {
-                        let val = Self::json_path_get(&body, path);
-                        mapped.insert(key.clone(), val);
+                let body: Value = match &dm.async_polling {
+                    Some(cfg) if status == reqwest::StatusCode::ACCEPTED => {
+                        async_poll::follow(&self.http, cfg, &dm.endpoint_url, &dm.static_headers, resp).await?
                     }
-                    Ok(Value::Object(mapped))
-                }
+                    _ => self.response_value(resp, &what).await?,
+                };
+                Ok(Self::apply_output_mapping(body, &dm.output_mapping))
             }
             ServiceFormat::Grpc | ServiceFormat::Wasm | ServiceFormat::Native | ServiceFormat::Docker => {
-                // Not implemented in edge node — return placeholder
-                warn!("[Svm] CALL_SERVICE format {:?} not supported on edge — returning null", format);
-                Ok(Value::Null)
+                // Central-only formats, never advertised (features.rs)
+                Err(ExecError::new(
+                    ErrorCode::Unsupported,
+                    format!("CALL_SERVICE format {} runs on central only", format.as_str_name()),
+                ).into())
             }
             ServiceFormat::Mcp => {
+                crate::arbiter::mark_dispatched();
                 self.exec_call_mcp(instr, input).await
             }
             ServiceFormat::LlmCallFormat | ServiceFormat::EmbeddedJs => {
+                crate::arbiter::mark_dispatched();
                 self.exec_llm_call(instr, input).await
             }
+            ServiceFormat::Onnx => {
+                let inference = self.inference.as_ref().ok_or_else(|| ExecError::new(
+                    ErrorCode::Unsupported,
+                    "ONNX inference is disabled on this node (invalid asset key)",
+                ))?;
+                inference.run(dm, operands, input).await
+            }
         }
     }
 
+    /// Run a parameterised SQL statement (LOAD_RESOURCE / CALL_SERVICE).
+    ///
+    /// The ResourceArbiter permit (capacity = pool size) is taken before a
+    /// pooled connection is checked out, keyed by service_id.
+    #[cfg(feature = "sql")]
+    async fn exec_sql(
+        &self,
+        instr: &crate::proto::llmir::IrInstruction,
+        dm: &crate::proto::llmir::DispatchMetadata,
+        operands: &Value,
+        input: Option<&Value>,
+    ) -> Result<Value> {
+        let mut dsn = dm.endpoint_url.clone();
+        if !dm.credentials_vault_path.is_empty() {
+            let secret = self.vault.fetch_secret(&dm.credentials_vault_path).await?;
+            dsn = sql::with_credentials(&dsn, &secret.value)?;
+        }
+
+        let key = format!("sql:{}", instr.service_id);
+        let claim = instr.priority_policy.as_ref()
+            .map(Claim::from)
+            .unwrap_or(Claim::background(30_000));
+        let max_wait_ms = claim.max_wait_ms;
+        let _permit = self.resource_arbiter
+            .acquire(&key, self.sql.pool_size() as usize, claim)
+            .await?;
+
+        self.sql.run(&dsn, operands, input, std::time::Duration::from_millis(max_wait_ms.max(50) as u64)).await
+    }
+
+    #[cfg(not(feature = "sql"))]
+    async fn exec_sql(
+        &self,
+        _instr: &crate::proto::llmir::IrInstruction,
+        dm: &crate::proto::llmir::DispatchMetadata,
+        _operands: &Value,
+        _input: Option<&Value>,
+    ) -> Result<Value> {
+        Err(ExecError::new(
+            crate::errors::ErrorCode::Unsupported,
+            format!("{}: SQL connector not compiled in — rebuild with `--features sql`", dm.service_id),
+        ).into())
+    }
+
     async fn exec_call_action(
         &self,
         instr: &crate::proto::llmir::IrInstruction,
+        operands: &Value,
         input: Option<&Value>,
     ) -> Result<Value> {
         // Physical actuator calls are dispatched via the central MQTT broker
@@ -682,21 +2058,177 @@ impl Svm {
             warn!("[Svm] CALL_ACTION has no endpoint — skipping");
             return Ok(Value::Null);
         }
+        if crate::connectors::is_device_url(endpoint) {
+            return self.exec_device_action(instr, endpoint, input).await;
+        }
+        if endpoint.starts_with("kafka://") {
+            crate::arbiter::mark_dispatched();
+            return self.exec_kafka(endpoint, input).await;
+        }
+        if crate::connectors::is_bridge_url(endpoint) {
+            crate::arbiter::mark_dispatched();
+            return self.mqtt()?.call(endpoint, input).await;
+        }
+        if crate::connectors::is_alert_url(endpoint) {
+            let path = dm.map(|d| d.credentials_vault_path.as_str()).unwrap_or_default();
+            if path.is_empty() {
+                return Err(ExecError::validation(format!("CALL_ACTION {endpoint} needs credentials_vault_path (provider credentials)")));
+            }
+            let secret = self.vault.fetch_secret(path).await?;
+            crate::arbiter::mark_dispatched();
+            return self.notifier.send(endpoint, &secret.value, input).await;
+        }
+        if endpoint.starts_with("mcu://") {
+            return self.mcu()?.call(endpoint, Call::Action, operands, input).await;
+        }
 
-        let body = input.cloned().unwrap_or(Value::Null);
-        let resp = self.http
-            .post(endpoint)
-            .json(&body)
-            .send()
-            .await?;
+        let mut body = input.cloned().unwrap_or(Value::Null);
+        // Webhook completion: register the one-shot URL before the service can call it
+        let callback = match dm.and_then(|d| d.async_callback.as_ref()) {
+            Some(cfg) => {
+                let registry = self.callbacks.as_ref().ok_or_else(|| ExecError::new(
+                    ErrorCode::Unsupported,
+                    format!("CALL_ACTION {endpoint} expects a callback but SVM_CALLBACK_BASE_URL is not set"),
+                ))?;
+                let pending = registry.register();
+                let field = if cfg.url_field.is_empty() { "callbackUrl" } else { cfg.url_field.as_str() };
+                if let Value::Object(map) = &mut body {
+                    map.insert(field.to_owned(), Value::String(pending.url.clone()));
+                }
+                Some((cfg, pending))
+            }
+            None => None,
+        };
+        self.shape_upload(&body).await;
+        let mut req = self.http.post(endpoint).json(&body);
+        if let Ok(key) = crate::wal::IDEMPOTENCY_KEY.try_with(|k| k.clone()) {
+            req = req.header("Idempotency-Key", key);
+        }
+        if let Some((cfg, pending)) = callback.as_ref().filter(|(cfg, _)| !cfg.url_header.is_empty()) {
+            req = req.header(cfg.url_header.as_str(), pending.url.as_str());
+        }
+        crate::arbiter::mark_dispatched();
+        let resp = req.send().await?;
 
         if !resp.status().is_success() {
-            return Err(anyhow!("CALL_ACTION {} → HTTP {}", endpoint, resp.status()));
+            return Err(ExecError::http(format!("CALL_ACTION {endpoint}"), resp.status()));
+        }
+        if let Some((cfg, pending)) = callback {
+            let timeout = if cfg.timeout_ms > 0 { cfg.timeout_ms as u64 } else { 300_000 };
+            debug!("[Svm] CALL_ACTION {endpoint} accepted — waiting for callback {}", pending.id);
+            let result = pending.wait(std::time::Duration::from_millis(timeout)).await?;
+            return Ok(if cfg.result_path.is_empty() { result } else { extract_dot_path(&result, &cfg.result_path) });
+        }
+        if let Some(cfg) = dm.and_then(|d| d.async_polling.as_ref()) {
+            if resp.status() == reqwest::StatusCode::ACCEPTED {
+                return async_poll::follow(&self.http, cfg, endpoint, &HashMap::new(), resp).await;
+            }
         }
-        let result: Value = resp.json().await.unwrap_or(Value::Null);
+        let result = fastjson::response_body(resp).await;
         Ok(result)
     }
 
+    /// Drive a local GPIO line or serial port.
+    ///
+    /// One ResourceArbiter permit per device node (capacity 1), held for the
+    /// whole exchange: a serial request/response or a GPIO pulse must not
+    /// interleave with another workflow's, and is never preempted half-way.
+    #[cfg(target_os = "linux")]
+    async fn exec_device_action(
+        &self,
+        instr: &crate::proto::llmir::IrInstruction,
+        endpoint: &str,
+        input: Option<&Value>,
+    ) -> Result<Value> {
+        let key = format!("device:{}", Endpoint::parse(endpoint)?.device());
+        let claim = instr.priority_policy.as_ref()
+            .map(|pp| Claim { preemptible: false, ..Claim::from(pp) })
+            .unwrap_or(Claim::background(30_000));
+        let _permit = self.resource_arbiter.acquire(&key, 1, claim).await?;
+        self.device.call(endpoint, input).await
+    }
+
+    /// Publish the action input to a Kafka topic (buffered while brokers
+    /// are unreachable).
+    async fn exec_kafka(&self, endpoint: &str, input: Option<&Value>) -> Result<Value> {
+        let sink = self.kafka.as_ref().ok_or_else(|| ExecError::new(
+            ErrorCode::Unsupported,
+            format!("{endpoint}: SVM_KAFKA_BROKERS is not set on this node"),
+        ))?;
+        let (topic, key_path) = kafka::parse_url(endpoint)?;
+        let value = input.cloned().unwrap_or(Value::Null);
+        let key = key_path.map(|p| match extract_dot_path(&value, &p) {
+            Value::String(s) => s,
+            other => other.to_string(),
+        });
+        sink.publish(kafka::Record::new(topic, key, value)).await
+    }
+
+    /// Grab a camera frame into the blob store, one capture per camera at a
+    /// time (arbiter capacity 1, not preempted mid-capture).
+    async fn exec_camera(
+        &self,
+        instr: &crate::proto::llmir::IrInstruction,
+        dm: &crate::proto::llmir::DispatchMetadata,
+        operands: &Value,
+    ) -> Result<Value> {
+        let credentials = if dm.credentials_vault_path.is_empty() {
+            None
+        } else {
+            Some(self.vault.fetch_secret(&dm.credentials_vault_path).await?.value)
+        };
+        let claim = instr.priority_policy.as_ref()
+            .map(|pp| Claim { preemptible: false, ..Claim::from(pp) })
+            .unwrap_or(Claim::background(30_000));
+        let _permit = self.resource_arbiter.acquire(&CameraConnector::arbiter_key(&dm.endpoint_url), 1, claim).await?;
+        let frame = self.camera.snapshot(&dm.endpoint_url, operands, credentials.as_deref()).await?;
+        let what = format!("camera {}", camera::redact(&dm.endpoint_url));
+        let reference = self.blobs.put(&frame.mime, frame.bytes, &what)?;
+        Ok(camera::register(reference, &dm.endpoint_url))
+    }
+
+    /// Read a BLE characteristic, holding the radio's arbiter permit for
+    /// the scan + connection.
+    #[cfg(target_os = "linux")]
+    async fn exec_ble(
+        &self,
+        instr: &crate::proto::llmir::IrInstruction,
+        url: &str,
+        operands: &Value,
+    ) -> Result<Value> {
+        let claim = instr.priority_policy.as_ref()
+            .map(|pp| Claim { preemptible: false, ..Claim::from(pp) })
+            .unwrap_or(Claim::background(30_000));
+        let _permit = self.resource_arbiter.acquire(&self.ble.arbiter_key(), 1, claim).await?;
+        self.ble.load(url, operands).await
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn exec_ble(
+        &self,
+        _instr: &crate::proto::llmir::IrInstruction,
+        url: &str,
+        _operands: &Value,
+    ) -> Result<Value> {
+        Err(ExecError::new(
+            ErrorCode::Unsupported,
+            format!("{url}: BLE sensors are only supported on Linux nodes"),
+        ).into())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn exec_device_action(
+        &self,
+        _instr: &crate::proto::llmir::IrInstruction,
+        endpoint: &str,
+        _input: Option<&Value>,
+    ) -> Result<Value> {
+        Err(ExecError::new(
+            ErrorCode::Unsupported,
+            format!("{endpoint}: local devices are only supported on Linux nodes"),
+        ).into())
+    }
+
     async fn exec_call_mcp(
         &self,
         instr: &crate::proto::llmir::IrInstruction,
@@ -704,7 +2236,7 @@ impl Svm {
     ) -> Result<Value> {
         // MCP tool call — POST JSON-RPC to endpoint
         let dm = instr.dispatch_metadata.as_ref()
-            .ok_or_else(|| anyhow!("CALL_MCP #{} missing dispatch_metadata", instr.index))?;
+            .ok_or_else(|| ExecError::validation(format!("CALL_MCP #{} missing dispatch_metadata", instr.index)))?;
 
         let tool_call = serde_json::json!({
             "jsonrpc": "2.0",
@@ -732,7 +2264,10 @@ impl Svm {
         input: Option<&Value>,
     ) -> Result<Value> {
         let dm = instr.dispatch_metadata.as_ref()
-            .ok_or_else(|| anyhow!("LLM_CALL #{} missing dispatch_metadata", instr.index))?;
+            .ok_or_else(|| ExecError::validation(format!("LLM_CALL #{} missing dispatch_metadata", instr.index)))?;
+        if let Some(chaos) = &self.chaos {
+            chaos.inject(Target::LlmCall, &format!("LLM_CALL #{}", instr.index)).await?;
+        }
 
         // ── 1. Build frozen few-shot context (spec §3.4) ───────────────────
         // few_shot_examples were frozen at compile time — injected verbatim.
@@ -752,9 +2287,8 @@ impl Svm {
             let value = match slot.source_type.as_str() {
                 "vault" => {
                     // Fetch secret from Vault at runtime, destroy immediately after use
-                    let mut vault = self.vault.lock().await;
-                    vault.fetch(&slot.source_key).await
-                        .map(|s| Value::String(s))
+                    self.vault.fetch_secret(&slot.source_key).await
+                        .map(|s| Value::String(s.value))
                         .unwrap_or_else(|e| {
                             warn!("[Svm] dynamic_slot '{}': vault fetch failed: {e}", slot.slot_id);
                             Value::Null
@@ -773,9 +2307,12 @@ impl Svm {
         }
 
         // ── 3. Forward enriched payload to eyeflow-llm-service (spec §10.1) ─
+        // Only the dynamic slots may carry secrets to the LLM service
         let llm_service_url = format!("{}/api/rules/generate", self.config.central_http_url);
+        let mut user_intent = input.cloned().unwrap_or(Value::Null);
+        crate::taint::scrub_value(&mut user_intent);
         let payload = serde_json::json!({
-            "userIntent":    input.cloned().unwrap_or(Value::Null),
+            "userIntent":    user_intent,
             "systemPrompt":  dm.system_prompt,
             "promptTemplate": dm.prompt_template,
             "model":         dm.model,
@@ -794,7 +2331,7 @@ impl Svm {
             .await?;
 
         if !resp.status().is_success() {
-            return Err(anyhow!("LLM_CALL → HTTP {}", resp.status()));
+            return Err(ExecError::http("LLM_CALL", resp.status()));
         }
         let body: Value = resp.json().await.unwrap_or(Value::Null);
         Ok(body)
@@ -809,14 +2346,184 @@ impl Svm {
         n: usize,
     ) -> Result<Value> {
         let idx = instr.src.get(n).copied()
-            .ok_or_else(|| anyhow!("instruction #{} has no src[{n}]", instr.index))?;
+            .ok_or_else(|| ExecError::validation(format!("instruction #{} has no src[{n}]", instr.index)))?;
         regs.get(&idx)
             .cloned()
-            .ok_or_else(|| anyhow!("register R{idx} is undefined"))
+            .ok_or_else(|| ExecError::validation(format!("register R{idx} is undefined")))
+    }
+
+    /// CALL_SERVICE input coerced to and checked against the instruction's
+    /// `input_schema`, so bad payloads fail here rather than as a remote 400.
+    fn coerce_input(
+        instr: &crate::proto::llmir::IrInstruction,
+        prep: &PreparedInstr,
+        input: Option<Value>,
+    ) -> Result<Option<Value>> {
+        let Some(schema) = &prep.input_schema else {
+            return Ok(input);
+        };
+        schema.coerce(input.as_ref().unwrap_or(&Value::Null))
+            .map(Some)
+            .map_err(|errors| ExecError::validation(format!(
+                "CALL_SERVICE #{} input does not match input_schema: {}", instr.index, errors.join("; "),
+            )))
+    }
+
+    /// Send `req` with the extra `headers` (HTTP cache revalidation).
+    async fn send_with(mut req: reqwest::RequestBuilder, headers: Vec<(&'static str, String)>) -> Result<reqwest::Response> {
+        for (k, v) in headers {
+            req = req.header(k, v);
+        }
+        crate::arbiter::mark_dispatched();
+        Ok(req.send().await?)
+    }
+
+    /// One GUARDRAIL_VIOLATION audit event per output the guardrails rejected.
+    fn audit_violations(
+        audit: &mut AuditChain,
+        prepared: &PreparedIr,
+        rejected: Vec<guardrails::Violation>,
+    ) {
+        let meta = prepared.ir.metadata.as_ref();
+        for v in rejected {
+            let strategy = prepared.instr(v.instruction).map(|p| p.strategy).unwrap_or_default();
+            warn!("[Svm] LLM_CALL #{} guardrails: {}", v.instruction, v.violations.join("; "));
+            audit.append(
+                meta.map_or("unknown", |m| m.id.as_str()), meta.map(|m| m.version as u32),
+                Some(v.instruction.to_string()),
+                "GUARDRAIL_VIOLATION",
+                None, Some(&v.output),
+                0,
+                Some(serde_json::json!({ "violations": v.violations, "fallbackStrategy": strategy.to_string() })),
+            );
+        }
+    }
+
+    /// One SECRET_ACCESS audit event per secret lookup of `instr`.
+    fn audit_secrets(
+        audit: &mut AuditChain,
+        workflow_id: &str,
+        workflow_version: Option<u32>,
+        instr: &IrInstruction,
+        opcode: IrOpcode,
+        secrets: Vec<secret_audit::SecretAccess>,
+    ) {
+        for access in secrets {
+            let mut details = serde_json::to_value(&access).unwrap_or_default();
+            details["opcode"] = opcode.as_str_name().into();
+            audit.append(
+                workflow_id, workflow_version,
+                Some(instr.index.to_string()),
+                "SECRET_ACCESS",
+                None, None,
+                0,
+                Some(details),
+            );
+        }
+    }
+
+    /// LLM_CALL input as forwarded under `pii`, and the number of fields masked.
+    fn mask_for_llm(pii: Option<&PiiPolicy>, input: Option<&Value>) -> (Option<Value>, u32) {
+        match (pii, input) {
+            (Some(policy), Some(v)) => {
+                let (masked, n) = policy.mask(v);
+                (Some(masked), n)
+            }
+            _ => (input.cloned(), 0),
+        }
+    }
+
+    /// Redis store for `redis` operands (Unsupported when not configured).
+    fn redis(&self) -> Result<&Arc<RedisStore>> {
+        self.redis.as_ref().ok_or_else(|| {
+            ExecError::new(ErrorCode::Unsupported, "redis operand used but SVM_REDIS_URL is not set").into()
+        })
+    }
+
+    /// Wait for uplink tokens before sending a large request body.
+    async fn shape_upload(&self, body: &Value) {
+        self.shape_upload_bytes(serde_json::to_vec(body).map_or(0, |b| b.len())).await;
+    }
+
+    async fn shape_upload_bytes(&self, bytes: usize) {
+        let Some(shaper) = &self.shaper else { return };
+        if shaper.shapes_upload(bytes) {
+            shaper.acquire(bytes, Priority::Bulk).await;
+        }
+    }
+
+    /// Register value of an HTTP answer, by Content-Type: JSON as JSON, text
+    /// as a string, anything else as a blob reference (blob.rs).
+    async fn response_value(&self, resp: reqwest::Response, what: &str) -> Result<Value> {
+        let mime = resp.headers().get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_owned();
+        match BodyKind::of(&mime) {
+            BodyKind::Json => Ok(fastjson::response_body(resp).await),
+            BodyKind::Text => Ok(Value::String(resp.text().await?)),
+            BodyKind::Binary => {
+                let mut resp = resp;
+                if let Some(len) = resp.content_length() {
+                    self.blobs.check_size(len as usize, what)?;
+                }
+                // Chunked answers are capped as they arrive
+                let mut bytes = Vec::new();
+                while let Some(chunk) = resp.chunk().await? {
+                    bytes.extend_from_slice(&chunk);
+                    self.blobs.check_size(bytes.len(), what)?;
+                }
+                self.blobs.put(mime.split(';').next().unwrap_or_default().trim(), bytes.into(), what)
+            }
+        }
+    }
+
+    fn mqtt(&self) -> Result<&Arc<MqttBridge>> {
+        self.mqtt.as_ref().ok_or_else(|| {
+            ExecError::new(ErrorCode::Unsupported, "zigbee:// / zwave:// endpoint used but SVM_MQTT_URL is not set").into()
+        })
+    }
+
+    fn mcu(&self) -> Result<&Arc<McuBridge>> {
+        self.mcu.as_ref().ok_or_else(|| {
+            ExecError::new(ErrorCode::Unsupported, "mcu:// endpoint used but SVM_MCU_PORTS is not set").into()
+        })
+    }
+
+    /// Serve `call` from the response cache when the instruction sets
+    /// `cacheTtlSecs`.
+    async fn cached<Fut>(
+        &self,
+        instr: &crate::proto::llmir::IrInstruction,
+        prep: &PreparedInstr,
+        opcode: &str,
+        input: Option<&Value>,
+        call: Fut,
+    ) -> Result<Value>
+    where
+        Fut: std::future::Future<Output = Result<Value>>,
+    {
+        let Some(ttl) = prep.operands.get("cacheTtlSecs").and_then(|v| v.as_u64()).filter(|&t| t > 0) else {
+            return call.await;
+        };
+        let dm = instr.dispatch_metadata.as_ref();
+        let digest = ResponseCache::digest(&serde_json::json!({
+            "opcode": opcode,
+            "service": instr.service_id,
+            "endpoint": dm.map(|d| d.endpoint_url.as_str()),
+            "method": dm.map(|d| d.method.as_str()),
+            "credentials": dm.map(|d| d.credentials_vault_path.as_str()),
+            "model": dm.map(|d| d.model.as_str()),
+            "systemPrompt": dm.map(|d| d.system_prompt.as_str()),
+            "promptTemplate": dm.map(|d| d.prompt_template.as_str()),
+            "input": input,
+        }));
+        self.response_cache.get_or_call(&digest, std::time::Duration::from_secs(ttl), call).await
     }
 
-    fn resolve_ip(&self, order: &[i32], target_instr_idx: i32) -> usize {
-        order.iter().position(|&i| i == target_instr_idx).unwrap_or(order.len())
+    /// `planKey` operand addressing the plan-scoped context, if any.
+    fn plan_key(operands: &Value) -> Option<String> {
+        operands.get("planKey").and_then(|v| v.as_str()).map(str::to_owned)
     }
 
     fn is_truthy(val: Option<&Value>) -> bool {
@@ -831,25 +2538,39 @@ impl Svm {
         }
     }
 
-    fn eval_predicate(val: &Value, operator: &str, expected_json: &str) -> bool {
-        let expected: Value = serde_json::from_str(expected_json).unwrap_or(Value::Null);
-        match operator {
-            "==" | "eq"  => val == &expected,
-            "!=" | "ne"  => val != &expected,
-            "truthy"     => Self::is_truthy(Some(val)),
-            "exists"     => !matches!(val, Value::Null),
-            "<"  => Self::cmp_f64(val, &expected, |a, b| a < b),
-            "<=" => Self::cmp_f64(val, &expected, |a, b| a <= b),
-            ">"  => Self::cmp_f64(val, &expected, |a, b| a > b),
-            ">=" => Self::cmp_f64(val, &expected, |a, b| a >= b),
-            _    => false,
-        }
+    fn eval_predicate(val: &Value, pred: &LoopConvergencePredicate) -> Result<bool> {
+        let expected: Value = serde_json::from_str(&pred.value_json).unwrap_or(Value::Null);
+        let mode = predicate::CompareAs::parse(&pred.compare_as)?;
+        predicate::eval(val, &pred.operator, &expected, mode, predicate::Nulls::parse(&pred.nulls)?)
     }
 
-    fn cmp_f64(a: &Value, b: &Value, f: impl Fn(f64, f64) -> bool) -> bool {
-        match (a.as_f64(), b.as_f64()) {
-            (Some(av), Some(bv)) => f(av, bv),
-            _ => false,
+    /// `left OP right` — numbers compare numerically (1 == 1.0), strings
+    /// lexicographically, anything else only by (deep) equality.
+    pub(crate) fn compare(left: &Value, operator: &str, right: &Value) -> bool {
+        use std::cmp::Ordering;
+        let ord = match (left, right) {
+            (Value::Number(a), Value::Number(b)) => a.as_f64().zip(b.as_f64()).and_then(|(a, b)| a.partial_cmp(&b)),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            _ => None,
+        };
+        match operator {
+            "==" | "eq"  => ord.map_or(left == right, |o| o == Ordering::Equal),
+            "!=" | "ne"  => ord.map_or(left != right, |o| o != Ordering::Equal),
+            // Structural equality, no numeric coercion
+            "deep_eq"    => left == right,
+            "truthy"     => Self::is_truthy(Some(left)),
+            "exists"     => !matches!(left, Value::Null),
+            "<"  | "lt"  => ord == Some(Ordering::Less),
+            "<=" | "le"  => matches!(ord, Some(Ordering::Less | Ordering::Equal)),
+            ">"  | "gt"  => ord == Some(Ordering::Greater),
+            ">=" | "ge"  => matches!(ord, Some(Ordering::Greater | Ordering::Equal)),
+            "contains"   => match (left, right) {
+                (Value::String(a), Value::String(b)) => a.contains(b.as_str()),
+                (Value::Array(items), v) => items.contains(v),
+                (Value::Object(map), Value::String(k)) => map.contains_key(k),
+                _ => false,
+            },
+            _    => false,
         }
     }
 
@@ -865,66 +2586,88 @@ impl Svm {
         cur.clone()
     }
 
-    fn apply_transform(src: &Value, operands: &Value) -> Value {
-        // Very lightweight template: if operands has a "path" key, extract it
-        if let Some(path_str) = operands.get("path").and_then(|v| v.as_str()) {
-            return Self::json_path_get(src, path_str);
+    /// Apply `dispatch_metadata.output_mapping` (key → JSONPath) if present.
+    fn apply_output_mapping(body: Value, mapping: &HashMap<String, String>) -> Value {
+        if mapping.is_empty() {
+            return body;
         }
-        // If operands has a "template" key, do basic {{register}} substitution
-        if let Some(tmpl) = operands.get("template").and_then(|v| v.as_str()) {
-            let obj = src.as_object().cloned().unwrap_or_default();
-            let mut out = tmpl.to_owned();
-            for (k, v) in &obj {
-                let placeholder = format!("{{{{{k}}}}}");
-                let val_str = v.as_str().map(|s| s.to_owned())
-                    .unwrap_or_else(|| v.to_string());
-                out = out.replace(&placeholder, &val_str);
-            }
-            return Value::String(out);
+        let mut mapped = serde_json::Map::new();
+        for (key, path) in mapping {
+            mapped.insert(key.clone(), Self::json_path_get(&body, path));
         }
-        src.clone()
+        Value::Object(mapped)
     }
 
-    /// Acquire a resource permit according to the PriorityPolicy (spec §6.5).
+    /// Run a dispatch under the instruction's PriorityPolicy (spec §6.5).
     ///
-    /// Each service_id/resource gets a binary semaphore (capacity = 1).
-    /// `max_wait_ms = 0` means non-blocking: returns immediately if unavailable.
-    ///
-    /// Returns `Ok(permit)` on success, `Err(...)` if `max_wait_ms` elapsed
-    /// without acquiring the lock (caller should trigger fallback).
-    async fn acquire_resource_permit(
+    /// Applies to every externally-dispatching opcode (LOAD_RESOURCE,
+    /// CALL_SERVICE, CALL_ACTION, CALL_MCP, LLM_CALL).  Without a policy the
+    /// dispatch runs unguarded.  With one, a slot on the resource (keyed by
+    /// service_id, else `default_key`) is held for the duration of the call.
+    /// A preemptible holder asked to yield has its in-flight call cancelled
+    /// and releases the slot.  A read (LOAD_RESOURCE, CALL_MCP, LLM_CALL), or
+    /// a side effect (CALL_SERVICE, CALL_ACTION) preempted before its request
+    /// left the node (`arbiter::mark_dispatched`, called where the request is
+    /// sent), re-queues and is issued once the resource is granted again; a
+    /// side effect already dispatched may have reached its target, so it is
+    /// not re-sent — it fails CANCELLED into the instruction's fallback
+    /// strategy.  A wait that exceeds `max_wait_ms` goes through the fallback
+    /// strategy like a dispatch failure.
+    async fn with_resource<F, Fut>(
         &self,
-        resource_key: &str,
-        max_wait_ms: u32,
-    ) -> Result<tokio::sync::OwnedSemaphorePermit> {
-        // Get or create a Semaphore(1) for this resource
-        let sem = {
-            let read = self.resource_arbiter.read().await;
-            if let Some(s) = read.get(resource_key) {
-                Arc::clone(s)
-            } else {
-                drop(read);
-                let mut write = self.resource_arbiter.write().await;
-                // Double-checked locking
-                write.entry(resource_key.to_string())
-                    .or_insert_with(|| Arc::new(Semaphore::new(1)))
-                    .clone()
-            }
+        instr: &crate::proto::llmir::IrInstruction,
+        prep: &PreparedInstr,
+        default_key: &str,
+        workflow_id: &str,
+        dispatch: F,
+    ) -> Result<Value>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = Result<Value>>,
+    {
+        let Some(pp) = &instr.priority_policy else {
+            return dispatch().await;
         };
+        // SQL endpoints, devices, cameras and MCUs take their own permits
+        // (exec_sql, exec_device_action, exec_ble, exec_camera, one artifact
+        // in flight per MCU port)
+        let own_permit = instr.dispatch_metadata.as_ref().is_some_and(|dm| {
+            crate::connectors::is_sql_url(&dm.endpoint_url)
+                || crate::connectors::is_device_url(&dm.endpoint_url)
+                || crate::connectors::is_camera_url(&dm.endpoint_url)
+                || dm.endpoint_url.starts_with("ble://")
+                || dm.endpoint_url.starts_with("mcu://")
+        });
+        if own_permit {
+            return dispatch().await;
+        }
+        let key = if !instr.service_id.is_empty() { instr.service_id.as_str() } else { default_key };
+        let side_effect = instr.opcode == IrOpcode::CallService as i32 || instr.opcode == IrOpcode::CallAction as i32;
 
-        let deadline = if max_wait_ms == 0 {
-            Duration::from_millis(50) // non-blocking: short grace period
-        } else {
-            Duration::from_millis(max_wait_ms as u64)
-        };
+        loop {
+            let permit = match self.resource_arbiter.acquire(key, 1, Claim::from(pp)).await {
+                Ok(p) => p,
+                Err(e) => {
+                    warn!("[Svm] priority_policy: {e} — triggering fallback");
+                    return self.recover(prep.strategy, &prep.fallback, e, workflow_id, &instr.service_id).await;
+                }
+            };
+            let dispatched = Arc::new(AtomicBool::new(false));
+            let call = crate::arbiter::DISPATCHED.scope(dispatched.clone(), dispatch());
+            match permit.run(call).await {
+                Some(result) => return result,
+                None if side_effect && dispatched.load(Ordering::Relaxed) => {
+                    warn!("[Svm] '{key}' preempted after #{} was dispatched — not re-sending, triggering fallback", instr.index);
+                    let e = ExecError::new(
+                        ErrorCode::Cancelled,
+                        format!("preempted on '{key}' after the call was dispatched — it may have reached its target"),
+                    );
+                    return self.recover(prep.strategy, &prep.fallback, e.into(), workflow_id, &instr.service_id).await;
+                }
+                None => info!("[Svm] '{key}' preempted by a higher-priority workflow — re-queueing"),
+            }
+        }
 
-        tokio::time::timeout(deadline, sem.clone().acquire_owned())
-            .await
-            .map_err(|_| anyhow!(
-                "resource '{}' busy — max_wait_ms={} exceeded (spec §6.5 PriorityPolicy)",
-                resource_key, max_wait_ms
-            ))?
-            .map_err(|e| anyhow!("semaphore closed: {e}"))
     }
 }
 
@@ -932,7 +2675,7 @@ impl Svm {
 
 /// Extract a value from a JSON object using dot-notation path (e.g. "user.id").
 /// Used by dynamic_slots with source_type = "runtime" (spec §3.4 + §13.2).
-fn extract_dot_path(root: &Value, path: &str) -> Value {
+pub(crate) fn extract_dot_path(root: &Value, path: &str) -> Value {
     let mut cur = root;
     for part in path.trim_start_matches("$.").split('.') {
         match cur.get(part) {
@@ -942,3 +2685,331 @@ fn extract_dot_path(root: &Value, path: &str) -> Value {
     }
     cur.clone()
 }
+
+// ── Test support ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+
+    /// A scratch SVM with the environment's config, its files in `dir`.
+    pub(crate) async fn svm_in(dir: &std::path::Path) -> Svm {
+        Svm::scratch(Config::from_env(), dir).await.unwrap()
+    }
+
+    /// Requests seen by `mock_http`.
+    #[derive(Default)]
+    pub(crate) struct Calls {
+        pub(crate) total: std::sync::atomic::AtomicUsize,
+        pub(crate) in_flight: std::sync::atomic::AtomicUsize,
+        pub(crate) peak: std::sync::atomic::AtomicUsize,
+    }
+
+    /// HTTP server answering every request with `{"result":"ok"}` after
+    /// `delay` — stands in for the LLM service, MCP tools and resources.
+    pub(crate) async fn mock_http(delay: std::time::Duration) -> (String, Arc<Calls>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let calls = Arc::new(Calls::default());
+        let seen = calls.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                let calls = seen.clone();
+                tokio::spawn(async move {
+                    // Headers, then Content-Length bytes of body
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 4096];
+                    loop {
+                        let n = sock.read(&mut chunk).await.unwrap();
+                        buf.extend_from_slice(&chunk[..n]);
+                        let text = String::from_utf8_lossy(&buf);
+                        let Some(end) = text.find("\r\n\r\n") else { continue };
+                        let length = text[..end].lines()
+                            .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                            .unwrap_or(0usize);
+                        if n == 0 || buf.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                    calls.total.fetch_add(1, Ordering::SeqCst);
+                    let now = calls.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    calls.peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(delay).await;
+                    calls.in_flight.fetch_sub(1, Ordering::SeqCst);
+                    let body = r#"{"result":"ok"}"#;
+                    let answer = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len());
+                    let _ = sock.write_all(answer.as_bytes()).await;
+                });
+            }
+        });
+        (url, calls)
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_support::{mock_http, svm_in};
+    use crate::proto::llmir::LlmIntermediateRepresentation;
+    use serde_json::json;
+
+    #[test]
+    fn test_compare_operators() {
+        assert!(Svm::compare(&json!(1), "==", &json!(1.0)));
+        assert!(!Svm::compare(&json!(1), "deep_eq", &json!(1.0)));
+        assert!(Svm::compare(&json!({ "a": [1, 2] }), "eq", &json!({ "a": [1, 2] })));
+        assert!(Svm::compare(&json!(2.5), ">", &json!(2)));
+        assert!(Svm::compare(&json!("apple"), "lt", &json!("banana")));
+        // Mixed types never order
+        assert!(!Svm::compare(&json!("3"), "<", &json!(4)));
+        assert!(Svm::compare(&json!("3"), "!=", &json!(3)));
+        assert!(Svm::compare(&json!(["x", "y"]), "contains", &json!("y")));
+        let pred = |op: &str, value: &str, compare_as: &str| LoopConvergencePredicate {
+            operator: op.into(), value_json: value.into(), compare_as: compare_as.into(), ..Default::default()
+        };
+        assert!(Svm::eval_predicate(&json!(10), &pred(">=", "10", "")).unwrap());
+        // Typed: a sensor reporting "0" has converged; timestamps order as instants
+        assert!(Svm::eval_predicate(&json!("0"), &pred("==", "0", "numeric")).unwrap());
+        assert!(!Svm::eval_predicate(&json!("0"), &pred("truthy", "", "numeric")).unwrap());
+        assert!(Svm::eval_predicate(&json!("2024-05-01T12:00:00+02:00"), &pred(">=", "\"2024-05-01T09:30:00Z\"", "datetime")).unwrap());
+        assert!(Svm::eval_predicate(&json!(1), &pred("==", "1", "roman")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_try_handler_catches_failure() {
+        let dir = std::env::temp_dir().join(format!("eyeflow_svm_try_{}", uuid::Uuid::new_v4().simple()));
+        let svm = svm_in(&dir).await;
+        let op = |index, opcode: IrOpcode, dest, src: Vec<i32>, target| IrInstruction {
+            index, opcode: opcode as i32, dest, src, target_instruction: target,
+            operands_json: r#"{"cases":{"a":4}}"#.into(),
+            ..Default::default()
+        };
+        // #2 reads an undefined register; the handler at #4 sees the error in R9
+        let slice = |with_try: bool| {
+            let mut instrs = vec![op(2, IrOpcode::Switch, 0, vec![5], 0), op(4, IrOpcode::Return, 0, vec![], 0)];
+            if with_try {
+                instrs.push(op(1, IrOpcode::TryBegin, 9, vec![], 4));
+                instrs.push(op(3, IrOpcode::TryEnd, 0, vec![], 0));
+            }
+            let mut order: Vec<i32> = instrs.iter().map(|i| i.index).collect();
+            order.sort();
+            PreparedIr::new(LlmIntermediateRepresentation {
+                instructions: instrs.into_iter().map(|i| (i.index, i)).collect(),
+                instruction_order: order,
+                ..Default::default()
+            }, String::new())
+        };
+        let mut audit = AuditChain::new("n".into(), None).unwrap();
+
+        let err = svm.execute(&slice(false), &mut audit, None).await.unwrap_err();
+        assert_eq!((err.code, err.instruction), (crate::errors::ErrorCode::Validation, Some(2)));
+
+        let (regs, _) = svm.execute(&slice(true), &mut audit, None).await.unwrap();
+        assert_eq!((regs[&9]["code"].clone(), regs[&9]["instruction"].clone()), (json!("VALIDATION"), json!(2)));
+        assert_eq!(audit.drain().last().unwrap().event_type, "TRY_CAUGHT");
+
+        // Exhausted register memory ends the slice through the TRY scope
+        let config = Config { register_memory_max_bytes: 64, ..Config::from_env() };
+        let svm = Svm::scratch(config, &dir.join("small")).await.unwrap();
+        let mut big = op(2, IrOpcode::Transform, 5, vec![], 0);
+        big.operands_json = json!({ "template": "x".repeat(256) }).to_string();
+        let ir = LlmIntermediateRepresentation {
+            instructions: [
+                (1, op(1, IrOpcode::TryBegin, 9, vec![], 4)),
+                (2, big),
+                (3, op(3, IrOpcode::TryEnd, 0, vec![], 0)),
+                (4, op(4, IrOpcode::Return, 0, vec![], 0)),
+            ].into(),
+            instruction_order: vec![1, 2, 3, 4],
+            ..Default::default()
+        };
+        let err = svm.execute(&PreparedIr::new(ir, String::new()), &mut audit, None).await.unwrap_err();
+        assert_eq!((err.code, err.instruction), (crate::errors::ErrorCode::MemoryLimit, Some(2)));
+        assert!(audit.drain().iter().all(|e| e.event_type != "TRY_CAUGHT"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+
+    #[tokio::test]
+    async fn test_preempted_side_effect_is_resent_only_before_dispatch() {
+        use crate::arbiter::Claim;
+        use crate::proto::llmir::PriorityPolicy;
+        use std::sync::atomic::AtomicU32;
+        let dir = std::env::temp_dir().join(format!("eyeflow_svm_preempt_{}", uuid::Uuid::new_v4().simple()));
+        let svm = svm_in(&dir).await;
+        // A read is re-issued after preemption, and so is an action still
+        // waiting to send its request; an action already sent is not
+        for (opcode, sent, dispatched, result) in [
+            (IrOpcode::LoadResource, true, 2, json!("done")),
+            (IrOpcode::CallAction, false, 2, json!("done")),
+            (IrOpcode::CallAction, true, 1, json!("safe")),
+        ] {
+            let instr = IrInstruction {
+                index: 1, opcode: opcode as i32, service_id: "valve".into(),
+                operands_json: r#"{"strategy":"FAIL_SAFE","safeDefault":"safe"}"#.into(),
+                priority_policy: Some(PriorityPolicy { priority_level: 200, preemptible: true, max_wait_ms: 5_000 }),
+                ..Default::default()
+            };
+            let prep = PreparedInstr::new(&instr, &HashMap::new(), 1);
+            let dispatches = AtomicU32::new(0);
+            let call = svm.with_resource(&instr, &prep, "default", "wf", || async {
+                // The first dispatch is waiting (before or after sending its
+                // request) when the critical workflow arrives
+                if dispatches.fetch_add(1, Ordering::SeqCst) == 0 {
+                    if sent {
+                        crate::arbiter::mark_dispatched();
+                    }
+                    std::future::pending::<()>().await;
+                }
+
+                Ok(json!("done"))
+            });
+            let preempt = async {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                let claim = Claim { priority: 0, preemptible: false, max_wait_ms: 5_000 };
+                drop(svm.resource_arbiter().acquire("valve", 1, claim).await.unwrap());
+            };
+            let (out, _) = tokio::join!(call, preempt);
+            assert_eq!((dispatches.load(Ordering::SeqCst), out.unwrap()), (dispatched, result), "{opcode:?}");
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_priority_policy_queues_reads_tools_and_llm_calls() {
+        use crate::arbiter::Claim;
+        use crate::proto::llmir::{DispatchMetadata, PriorityPolicy};
+        use std::time::Duration;
+        let dir = std::env::temp_dir().join(format!("eyeflow_svm_arbiter_{}", uuid::Uuid::new_v4().simple()));
+        let (url, calls) = mock_http(Duration::ZERO).await;
+        let config = Config { central_http_url: url.clone(), ..Config::from_env() };
+        let svm = Svm::scratch(config, &dir).await.unwrap();
+        let slice = |opcode: IrOpcode, max_wait_ms| {
+            let instr = IrInstruction {
+                index: 1, opcode: opcode as i32, dest: 2, service_id: "gpu".into(),
+                operands_json: r#"{"strategy":"FAIL_SAFE","safeDefault":"busy"}"#.into(),
+                priority_policy: Some(PriorityPolicy { priority_level: 100, preemptible: false, max_wait_ms }),
+                dispatch_metadata: Some(DispatchMetadata { endpoint_url: format!("{url}/gpu"), ..Default::default() }),
+                ..Default::default()
+            };
+            PreparedIr::new(LlmIntermediateRepresentation {
+                instructions: [(1, instr)].into(),
+                instruction_order: vec![1],
+                ..Default::default()
+            }, String::new())
+        };
+        let mut audit = AuditChain::new("n".into(), None).unwrap();
+        let holder = Claim { priority: 0, preemptible: false, max_wait_ms: 1_000 };
+
+        for (n, opcode) in [IrOpcode::LoadResource, IrOpcode::CallMcp, IrOpcode::LlmCall].into_iter().enumerate() {
+            // Waits while another workflow holds "gpu", runs once it is released
+            let permit = svm.resource_arbiter().acquire("gpu", 1, holder).await.unwrap();
+            let release = async {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                assert_eq!(calls.total.load(Ordering::SeqCst), n, "{opcode:?} ran while the permit was held");
+                drop(permit);
+            };
+            let queued = slice(opcode, 5_000);
+            let (out, ()) = tokio::join!(svm.execute(&queued, &mut audit, None), release);
+            assert_ne!(out.unwrap().0[&2], json!("busy"), "{opcode:?}");
+            assert_eq!(calls.total.load(Ordering::SeqCst), n + 1);
+
+            // Still held after max_wait_ms: the instruction's fallback answers
+            let _permit = svm.resource_arbiter().acquire("gpu", 1, holder).await.unwrap();
+            let (regs, _) = svm.execute(&slice(opcode, 50), &mut audit, None).await.unwrap();
+            assert_eq!(regs[&2], json!("busy"), "{opcode:?}");
+            assert_eq!(calls.total.load(Ordering::SeqCst), n + 1);
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_spawn_caps_branches_in_flight() {
+        use std::time::Duration;
+        let dir = std::env::temp_dir().join(format!("eyeflow_svm_fanout_{}", uuid::Uuid::new_v4().simple()));
+        let mut audit = AuditChain::new("n".into(), None).unwrap();
+
+        // SPAWN, six LLM_CALLs, MERGE; `None` leaves the operand out
+        let fan_out = |max: Option<u64>| {
+            let op = |index, opcode: IrOpcode, operands: Value| IrInstruction {
+                index, opcode: opcode as i32, dest: 10 + index, operands_json: operands.to_string(),
+                ..Default::default()
+            };
+            let spawn = max.map_or(json!({}), |n| json!({ "maxConcurrency": n }));
+            let mut instrs = vec![op(1, IrOpcode::ParallelSpawn, spawn)];
+            instrs.extend((2..8).map(|i| IrInstruction {
+                dispatch_metadata: Some(Default::default()),
+                ..op(i, IrOpcode::LlmCall, json!({}))
+            }));
+            instrs.push(op(8, IrOpcode::ParallelMerge, json!({ "mergePolicy": "ALL" })));
+            PreparedIr::new(LlmIntermediateRepresentation {
+                instruction_order: instrs.iter().map(|i| i.index).collect(),
+                instructions: instrs.into_iter().map(|i| (i.index, i)).collect(),
+                ..Default::default()
+            }, String::new())
+        };
+
+        // The operand wins over the node default; without it the default
+        // applies; 0 runs every branch at once
+        for (n, (max, peak)) in [(Some(2), 2), (None, 3), (Some(0), 6)].into_iter().enumerate() {
+            let (url, calls) = mock_http(Duration::from_millis(100)).await;
+            let config = Config { central_http_url: url, parallel_max_concurrency: 3, ..Config::from_env() };
+            let svm = Svm::scratch(config, &dir.join(n.to_string())).await.unwrap();
+            svm.execute(&fan_out(max), &mut audit, None).await.unwrap();
+            assert_eq!(calls.total.load(Ordering::SeqCst), 6, "{max:?}");
+            assert_eq!(calls.peak.load(Ordering::SeqCst), peak, "{max:?}");
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_call_slice_nests_sub_workflows() {
+
+        use prost::Message;
+        let dir = std::env::temp_dir().join(format!("eyeflow_svm_call_{}", uuid::Uuid::new_v4().simple()));
+        let svm = svm_in(&dir).await;
+        let op = |index, opcode: IrOpcode, dest, src: Vec<i32>, operands: Value| IrInstruction {
+            index, opcode: opcode as i32, dest, src, operands_json: operands.to_string(),
+            ..Default::default()
+        };
+        let ir = |id: &str, instrs: Vec<IrInstruction>| LlmIntermediateRepresentation {
+            instruction_order: instrs.iter().map(|i| i.index).collect(),
+            instructions: instrs.into_iter().map(|i| (i.index, i)).collect(),
+            input_register: 1,
+            output_register: 2,
+            metadata: Some(crate::proto::llmir::WorkflowMetadata { id: id.into(), ..Default::default() }),
+            ..Default::default()
+        };
+        // top → mid → leaf (copies its input register to its output register);
+        // "loop" calls itself until the depth limit
+        let call = |slice: &str| op(1, IrOpcode::CallSlice, 2, vec![1], json!({ "slice": slice }));
+        for artifact in [
+            ir("leaf", vec![op(1, IrOpcode::StoreMemory, 2, vec![1], Value::Null)]),
+            ir("mid", vec![call("leaf")]),
+            ir("loop", vec![call("loop")]),
+        ] {
+            svm.ir_cache().get_or_prepare(&artifact.encode_to_vec(), |b| Ok(LlmIntermediateRepresentation::decode(b)?)).unwrap();
+        }
+        let top = |slice: &str| PreparedIr::new(ir("top", vec![
+            op(1, IrOpcode::Transform, 5, vec![], json!({ "template": "hi" })),
+            op(2, IrOpcode::CallSlice, 6, vec![5], json!({ "slice": slice, "inputs": { "1": 5 } })),
+        ]), String::new());
+        let mut audit = AuditChain::new("n".into(), None).unwrap();
+
+        let (regs, _) = svm.execute(&top("mid"), &mut audit, None).await.unwrap();
+        assert_eq!(regs[&6], json!("hi"));
+        let events = audit.drain();
+        let labels: Vec<_> = events.iter().map(|e| (e.workflow_id.as_str(), e.parent_slice.as_deref())).collect();
+        assert_eq!(labels, vec![("mid", Some("top#2")), ("top", None)]);
+        assert_eq!(events[1].details.as_ref().unwrap()["workflowId"], "mid");
+
+        let err = svm.execute(&top("loop"), &mut audit, None).await.unwrap_err();
+        assert_eq!((err.code, err.instruction), (crate::errors::ErrorCode::Validation, Some(2)));
+        assert!(err.message.contains("SVM_CALL_SLICE_MAX_DEPTH"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
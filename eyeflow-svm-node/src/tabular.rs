@@ -0,0 +1,209 @@
+//! CSV and NDJSON codecs for TRANSFORM
+//!
+//! Legacy endpoints answer with CSV, log shippers with NDJSON; both become
+//! registers through the transform function library (transform.rs):
+//!
+//!   parse_csv(text[, delimiter[, header]])   rows; header (default true) =
+//!                                            objects keyed by the first line,
+//!                                            else arrays of cells
+//!   to_csv(rows[, delimiter])                objects (columns in first-seen
+//!                                            order, header line first) or
+//!                                            arrays of cells
+//!   split_ndjson(text)                       one value per non-blank line
+//!   join_ndjson(items)                       one compact JSON line per item
+//!
+//! CSV is split by the fs.rs reader (RFC 4180): quoted cells may hold
+//! delimiters, "" and line breaks; LF or CRLF line ends are read, CRLF is
+//! written.  Cells parse as strings (compare them with compareAs numeric, convert them with
+//! units.rs); null writes an empty cell, objects / arrays their JSON text.
+//! A row with a different cell count than the first fails with VALIDATION.
+//!
+//!   SVM_TABULAR_MAX_BYTES   text parsed or produced (default 8 MiB)
+//!   SVM_TABULAR_MAX_ROWS    rows / lines (default 100 000)
+//!
+//! Exceeding either fails the instruction with QUOTA.
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+use crate::config::Config;
+use crate::connectors::fs::{split_csv, CsvOptions};
+use crate::errors::{ErrorCode, ExecError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_bytes: usize,
+    pub max_rows: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self { max_bytes: 8 * 1024 * 1024, max_rows: 100_000 }
+    }
+}
+
+impl From<&Config> for Limits {
+    fn from(c: &Config) -> Self {
+        Self { max_bytes: c.tabular_max_bytes, max_rows: c.tabular_max_rows }
+    }
+}
+
+impl Limits {
+    fn bytes(&self, what: &str, len: usize) -> Result<()> {
+        if len > self.max_bytes {
+            return Err(ExecError::new(ErrorCode::Quota, format!(
+                "{what}: {len} bytes exceed SVM_TABULAR_MAX_BYTES={}", self.max_bytes,
+            )).into());
+        }
+        Ok(())
+    }
+
+    fn rows(&self, what: &str, rows: usize) -> Result<()> {
+        if rows > self.max_rows {
+            return Err(ExecError::new(ErrorCode::Quota, format!(
+                "{what}: more than SVM_TABULAR_MAX_ROWS={} rows", self.max_rows,
+            )).into());
+        }
+        Ok(())
+    }
+}
+
+// ── CSV ───────────────────────────────────────────────────────────────────────
+
+pub fn parse_csv(text: &str, delimiter: char, header: bool, limits: &Limits) -> Result<Value> {
+    limits.bytes("parse_csv", text.len())?;
+    let table = split_csv(text, CsvOptions { delimiter, header })?;
+    limits.rows("parse_csv", table.rows.len() + usize::from(table.columns.is_some()))?;
+
+    let width = table.columns.as_ref().or(table.rows.first()).map_or(0, Vec::len);
+    if let Some(i) = table.rows.iter().position(|r| r.len() != width) {
+        return Err(ExecError::validation(format!(
+            "parse_csv: row {} has {} cell(s), expected {width}",
+            i + 1 + usize::from(table.columns.is_some()), table.rows[i].len(),
+        )));
+    }
+    let rows = match table.columns {
+        Some(columns) => table.rows.into_iter()
+            .map(|r| Value::Object(columns.iter().cloned().zip(r.into_iter().map(Value::String)).collect()))
+            .collect(),
+        None => table.rows.into_iter()
+            .map(|r| Value::Array(r.into_iter().map(Value::String).collect()))
+            .collect(),
+    };
+    Ok(Value::Array(rows))
+}
+
+pub fn to_csv(rows: &Value, delimiter: char, limits: &Limits) -> Result<String> {
+    let rows = rows.as_array().ok_or_else(|| ExecError::validation("to_csv: expected an array of rows"))?;
+    limits.rows("to_csv", rows.len())?;
+    let mut out = String::new();
+    let write_line = |out: &mut String, cells: &mut dyn Iterator<Item = Option<&Value>>| -> Result<()> {
+        for (i, v) in cells.enumerate() {
+            if i > 0 {
+                out.push(delimiter);
+            }
+            let text = match v {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(s)) => s.clone(),
+                Some(v) => v.to_string(),
+            };
+            if text.contains([delimiter, '"', '\r', '\n']) {
+                out.push('"');
+                out.push_str(&text.replace('"', "\"\""));
+                out.push('"');
+            } else {
+                out.push_str(&text);
+            }
+        }
+        out.push_str("\r\n");
+        limits.bytes("to_csv", out.len())
+    };
+
+    if rows.iter().all(Value::is_object) && !rows.is_empty() {
+        let mut columns: Vec<&String> = Vec::new();
+        for row in rows {
+            for key in row.as_object().into_iter().flat_map(Map::keys) {
+                if !columns.contains(&key) {
+                    columns.push(key);
+                }
+            }
+        }
+        let header: Vec<Value> = columns.iter().map(|c| Value::String((*c).clone())).collect();
+        write_line(&mut out, &mut header.iter().map(Some))?;
+        for row in rows {
+            write_line(&mut out, &mut columns.iter().map(|c| row.get(c.as_str())))?;
+        }
+    } else {
+        for (i, row) in rows.iter().enumerate() {
+            let cells = row.as_array()
+                .ok_or_else(|| ExecError::validation(format!("to_csv: row {} is neither an object nor an array", i + 1)))?;
+            write_line(&mut out, &mut cells.iter().map(Some))?;
+        }
+    }
+    Ok(out)
+}
+
+// ── NDJSON ────────────────────────────────────────────────────────────────────
+
+pub fn split_ndjson(text: &str, limits: &Limits) -> Result<Value> {
+    limits.bytes("split_ndjson", text.len())?;
+    let mut items = Vec::new();
+    for (i, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        items.push(serde_json::from_str(line)
+            .map_err(|e| ExecError::validation(format!("split_ndjson: line {}: {e}", i + 1)))?);
+        limits.rows("split_ndjson", items.len())?;
+    }
+    Ok(Value::Array(items))
+}
+
+pub fn join_ndjson(items: &Value, limits: &Limits) -> Result<String> {
+    let items = items.as_array().ok_or_else(|| ExecError::validation("join_ndjson: expected an array"))?;
+    limits.rows("join_ndjson", items.len())?;
+    let mut out = String::new();
+    for item in items {
+        out.push_str(&serde_json::to_string(item)?);
+        out.push('\n');
+        limits.bytes("join_ndjson", out.len())?;
+    }
+    Ok(out)
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::classify;
+    use serde_json::json;
+
+    #[test]
+    fn test_csv_round_trip() {
+        let limits = Limits::default();
+        let text = "site;reading;note\r\nLyon;21.5;\"ok; \"\"stable\"\"\"\nNantes;19;\"two\nlines\"\n";
+        let rows = parse_csv(text, ';', true, &limits).unwrap();
+        assert_eq!(rows, json!([
+            { "site": "Lyon", "reading": "21.5", "note": "ok; \"stable\"" },
+            { "site": "Nantes", "reading": "19", "note": "two\nlines" },
+        ]));
+        let back = to_csv(&rows, ';', &limits).unwrap();
+        assert_eq!(parse_csv(&back, ';', true, &limits).unwrap(), rows);
+
+        assert_eq!(parse_csv("1,2\n3,4", ',', false, &limits).unwrap(), json!([["1", "2"], ["3", "4"]]));
+        assert_eq!(to_csv(&json!([{ "a": 1 }, { "b": null, "a": [2] }]), ',', &limits).unwrap(), "a,b\r\n1,\r\n[2],\r\n");
+        assert_eq!(classify(&parse_csv("a,b\n1\n", ',', true, &limits).unwrap_err()), ErrorCode::Validation);
+        assert_eq!(classify(&parse_csv("a\n\"open\n", ',', true, &limits).unwrap_err()), ErrorCode::Validation);
+    }
+
+    #[test]
+    fn test_ndjson_and_limits() {
+        let limits = Limits { max_bytes: 64, max_rows: 2 };
+        let items = split_ndjson("{\"a\":1}\n\n[2]\r\n", &limits).unwrap();
+        assert_eq!(items, json!([{ "a": 1 }, [2]]));
+        assert_eq!(join_ndjson(&items, &limits).unwrap(), "{\"a\":1}\n[2]\n");
+        assert_eq!(classify(&split_ndjson("1\n{bad\n", &limits).unwrap_err()), ErrorCode::Validation);
+
+        assert_eq!(classify(&split_ndjson("1\n2\n3\n", &limits).unwrap_err()), ErrorCode::Quota);
+        assert_eq!(classify(&parse_csv(&"x".repeat(65), ',', false, &limits).unwrap_err()), ErrorCode::Quota);
+        assert_eq!(classify(&join_ndjson(&json!(["x".repeat(70)]), &limits).unwrap_err()), ErrorCode::Quota);
+    }
+}
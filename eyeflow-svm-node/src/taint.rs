@@ -0,0 +1,188 @@
+//! Secret taint tracking — keep resolved secrets out of what leaves a slice
+//!
+//! Secrets are meant to go where an instruction sends them (an Authorization
+//! header, an LLM_CALL dynamic slot), but a service may echo a token back, an
+//! error may quote a request, a `{{secret:…}}` template may end up in a
+//! register.  While a slice runs (SVM_SECRET_TAINT, default true), every
+//! value VaultClient resolves is remembered and, wherever it appears in a
+//! string, replaced with "[SECRET]":
+//!
+//!   audit events   input / output before they are hashed, and details;
+//!                  `details.secretsRedacted` counts the strings scrubbed
+//!   errors         ExecError messages (results, TRY_CAUGHT, SLICE_FAILED)
+//!   LLM_CALL       the userIntent forwarded to the LLM service (dynamic
+//!                  slots still carry the secrets they asked for)
+//!   slice end      every register holding one is scrubbed before the
+//!                  registers are returned, retained or sent to central
+//!
+//! Values shorter than MIN_SECRET_LEN are not tracked — they would match
+//! ordinary text.  Outside a slice (metrics push credentials) nothing is
+//! tracked.
+
+use serde_json::Value;
+use std::borrow::Cow;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+tokio::task_local! {
+    /// Secret values resolved by the slice being run, longest first.
+    static SECRETS: Arc<Mutex<Vec<String>>>;
+}
+
+/// Shorter secrets are not tracked.
+pub const MIN_SECRET_LEN: usize = 6;
+
+const PLACEHOLDER: &str = "[SECRET]";
+
+/// Run `call` with taint tracking.
+pub async fn tracked<F: Future>(call: F) -> F::Output {
+    SECRETS.scope(Arc::new(Mutex::new(Vec::new())), call).await
+}
+
+/// Remember a resolved secret (called by VaultClient; no-op outside `tracked`).
+pub fn note_secret(value: &str) {
+    if value.len() < MIN_SECRET_LEN {
+        return;
+    }
+    let _ = SECRETS.try_with(|s| {
+        let mut s = s.lock().unwrap_or_else(|e| e.into_inner());
+        if !s.iter().any(|known| known == value) {
+            s.push(value.to_owned());
+            s.sort_by_key(|known| std::cmp::Reverse(known.len()));
+        }
+    });
+}
+
+/// Secrets known in this task (empty outside `tracked`).
+fn secrets() -> Vec<String> {
+    SECRETS.try_with(|s| s.lock().unwrap_or_else(|e| e.into_inner()).clone()).unwrap_or_default()
+}
+
+fn scrub_with<'a>(text: &'a str, secrets: &[String]) -> Cow<'a, str> {
+    let mut out = Cow::Borrowed(text);
+    for secret in secrets {
+        if out.contains(secret.as_str()) {
+            out = Cow::Owned(out.replace(secret.as_str(), PLACEHOLDER));
+        }
+    }
+    out
+}
+
+fn scrub_value_with(value: &mut Value, secrets: &[String]) -> usize {
+    match value {
+        Value::String(s) => match scrub_with(s, secrets) {
+            Cow::Owned(clean) => {
+                *s = clean;
+                1
+            }
+            Cow::Borrowed(_) => 0,
+        },
+        Value::Array(items) => items.iter_mut().map(|v| scrub_value_with(v, secrets)).sum(),
+        Value::Object(map) => map.values_mut().map(|v| scrub_value_with(v, secrets)).sum(),
+        _ => 0,
+    }
+}
+
+/// `text` with every known secret replaced.
+pub fn scrub(text: &str) -> String {
+    let secrets = secrets();
+    scrub_with(text, &secrets).into_owned()
+}
+
+/// Scrub the strings of `value`; returns how many changed.
+pub fn scrub_value(value: &mut Value) -> usize {
+    let secrets = secrets();
+    if secrets.is_empty() {
+        return 0;
+    }
+    scrub_value_with(value, &secrets)
+}
+
+/// Scrub every value of `registers`; returns the registers that held a secret.
+pub fn scrub_registers<'a>(registers: impl IntoIterator<Item = (&'a i32, &'a mut Value)>) -> Vec<i32> {
+    let secrets = secrets();
+    if secrets.is_empty() {
+        return Vec::new();
+    }
+    let mut tainted: Vec<i32> = registers.into_iter()
+        .filter_map(|(r, v)| (scrub_value_with(v, &secrets) > 0).then_some(*r))
+        .collect();
+    tainted.sort_unstable();
+    tainted
+}
+
+/// An audit event's input, output and details with secrets scrubbed.
+pub struct ScrubbedEvent<'a> {
+    pub input: Option<Cow<'a, Value>>,
+    pub output: Option<Cow<'a, Value>>,
+    pub details: Option<Value>,
+    /// Strings scrubbed
+    pub redacted: usize,
+}
+
+/// Scrub an audit event (borrowed unchanged when no secret is known).
+pub fn scrub_event<'a>(input: Option<&'a Value>, output: Option<&'a Value>, details: Option<Value>) -> ScrubbedEvent<'a> {
+    let secrets = secrets();
+    if secrets.is_empty() {
+        return ScrubbedEvent { input: input.map(Cow::Borrowed), output: output.map(Cow::Borrowed), details, redacted: 0 };
+    }
+    let mut redacted = 0;
+    let mut clean = |v: Option<&'a Value>| {
+        v.map(|v| {
+            let mut copy = v.clone();
+            match scrub_value_with(&mut copy, &secrets) {
+                0 => Cow::Borrowed(v),
+                n => {
+                    redacted += n;
+                    Cow::Owned(copy)
+                }
+            }
+        })
+    };
+    let (input, output) = (clean(input), clean(output));
+    let details = details.map(|mut d| {
+        redacted += scrub_value_with(&mut d, &secrets);
+        d
+    });
+    ScrubbedEvent { input, output, details, redacted }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditChain;
+    use crate::errors::{ErrorCode, ExecError};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_secrets_are_scrubbed_from_audit_errors_and_registers() {
+        tracked(async {
+            note_secret("tok-3f9a77");
+            note_secret("abc"); // too short to track
+            assert_eq!(scrub("Bearer tok-3f9a77 rejected, abc"), "Bearer [SECRET] rejected, abc");
+
+            let err = anyhow::Error::new(ExecError::new(ErrorCode::Auth, "401 for Bearer tok-3f9a77"));
+            assert_eq!(ExecError::from_anyhow(&err, Some(2)).message, "401 for Bearer [SECRET]");
+
+            let echoed = json!({ "headers": { "authorization": "Bearer tok-3f9a77" }, "n": 1 });
+            let mut chain = AuditChain::new("n".into(), None).unwrap();
+            let ev = chain.append("wf", None, Some("4"), "CALL_SERVICE", None, Some(&echoed), 0, None).unwrap();
+            let mut clean = echoed.clone();
+            assert_eq!(scrub_value(&mut clean), 1);
+            assert_eq!(ev.output_hash, AuditChain::new("n".into(), None).unwrap()
+                .append("wf", None, Some("4"), "CALL_SERVICE", None, Some(&clean), 0, None).unwrap().output_hash);
+            assert_eq!(ev.details.unwrap()["secretsRedacted"], 1);
+
+            let mut regs: HashMap<i32, Value> = HashMap::from([(1, echoed), (2, json!("plain"))]);
+            assert_eq!(scrub_registers(regs.iter_mut()), [1]);
+            assert_eq!(regs[&1]["headers"]["authorization"], "Bearer [SECRET]");
+        }).await;
+
+        // Nothing is tracked outside a slice
+        note_secret("tok-3f9a77");
+        assert_eq!(scrub("tok-3f9a77"), "tok-3f9a77");
+    }
+}
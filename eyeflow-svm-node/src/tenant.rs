@@ -0,0 +1,361 @@
+//! Multi-tenant isolation — per-tenant quotas on shared edge hardware
+//!
+//! Every slice belongs to the tenant named in `WorkflowMetadata.tenant_id`
+//! (empty → "default").  The `TenantGovernor` admits a slice only if its
+//! tenant is within quota:
+//!
+//!   maxConcurrent      slices in flight at once            (0 = unlimited)
+//!   ratePerMinute      slices admitted per minute (bucket) (0 = unlimited)
+//!   offlineMaxEvents   offline buffer events kept per tenant (0 = unlimited)
+//!
+//! Defaults come from SVM_TENANT_MAX_CONCURRENT / SVM_TENANT_RATE_PER_MIN /
+//! SVM_OFFLINE_TENANT_MAX_EVENTS; central overrides them per tenant through
+//! CONFIG_UPDATE `tenantQuotas` (key "*" replaces the defaults):
+//!
+//!   { "tenantQuotas": { "acme": { "maxConcurrent": 2, "ratePerMinute": 60 } } }
+//!
+//! A rejected slice is answered THROTTLED instead of queueing behind the
+//! noisy tenant.  Plan-scoped state is keyed by `plan_scope(tenant, plan)`,
+//! so two tenants reusing a plan id never see each other's values.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::config::Config;
+use crate::health::MetricsCollector;
+use crate::proto::llmir::LlmIntermediateRepresentation;
+
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Tenant owning `ir`.
+pub fn tenant_of(ir: &LlmIntermediateRepresentation) -> &str {
+    ir.metadata.as_ref()
+        .map(|m| m.tenant_id.as_str())
+        .filter(|t| !t.is_empty())
+        .unwrap_or(DEFAULT_TENANT)
+}
+
+/// Key for plan-scoped state.  The default tenant keeps the bare plan id so
+/// state persisted before tenants existed is still found.
+pub fn plan_scope(tenant: &str, plan_id: &str) -> String {
+    if tenant == DEFAULT_TENANT {
+        plan_id.to_owned()
+    } else {
+        format!("{tenant}/{plan_id}")
+    }
+}
+
+// ── Quotas ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TenantQuota {
+    pub max_concurrent: usize,
+    pub rate_per_min: u32,
+    pub offline_max_events: usize,
+}
+
+impl From<&Config> for TenantQuota {
+    fn from(c: &Config) -> Self {
+        Self {
+            max_concurrent: c.tenant_max_concurrent,
+            rate_per_min: c.tenant_rate_per_min,
+            offline_max_events: c.offline_tenant_max_events,
+        }
+    }
+}
+
+impl TenantQuota {
+    /// Overlay the camelCase fields present in `v` onto `self`.
+    fn merged(mut self, v: &Value) -> Result<Self> {
+        let obj = v.as_object().ok_or_else(|| anyhow!("tenant quota must be an object"))?;
+        let field = |name: &str| -> Result<Option<u64>> {
+            match obj.get(name) {
+                None => Ok(None),
+                Some(n) => n.as_u64().map(Some)
+                    .ok_or_else(|| anyhow!("{name} must be a non-negative integer")),
+            }
+        };
+        if let Some(n) = field("maxConcurrent")? {
+            self.max_concurrent = n as usize;
+        }
+        if let Some(n) = field("ratePerMinute")? {
+            self.rate_per_min = n as u32;
+        }
+        if let Some(n) = field("offlineMaxEvents")? {
+            self.offline_max_events = n as usize;
+        }
+        Ok(self)
+    }
+}
+
+/// Why a slice was not admitted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Throttled {
+    pub tenant_id: String,
+    /// "CONCURRENCY" | "RATE_LIMIT"
+    pub reason: &'static str,
+    pub limit: u64,
+}
+
+impl fmt::Display for Throttled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tenant {} throttled ({}, limit {})", self.tenant_id, self.reason, self.limit)
+    }
+}
+
+// ── Governor ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug)]
+struct TenantState {
+    in_flight: usize,
+    tokens: f64,
+    refilled: Instant,
+    admitted_total: u64,
+    throttled_concurrency: u64,
+    throttled_rate: u64,
+    failed_total: u64,
+    exec_ms_sum: u64,
+    offline_events: usize,
+}
+
+impl TenantState {
+    fn new(quota: &TenantQuota) -> Self {
+        Self {
+            in_flight: 0,
+            tokens: quota.rate_per_min as f64,
+            refilled: Instant::now(),
+            admitted_total: 0,
+            throttled_concurrency: 0,
+            throttled_rate: 0,
+            failed_total: 0,
+            exec_ms_sum: 0,
+            offline_events: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    defaults: TenantQuota,
+    overrides: HashMap<String, TenantQuota>,
+    tenants: HashMap<String, TenantState>,
+}
+
+impl Inner {
+    fn quota(&self, tenant: &str) -> TenantQuota {
+        self.overrides.get(tenant).copied().unwrap_or(self.defaults)
+    }
+}
+
+#[derive(Debug)]
+pub struct TenantGovernor {
+    inner: Mutex<Inner>,
+}
+
+impl TenantGovernor {
+    pub fn new(defaults: TenantQuota) -> Arc<Self> {
+        Arc::new(Self {
+            inner: Mutex::new(Inner { defaults, overrides: HashMap::new(), tenants: HashMap::new() }),
+        })
+    }
+
+    /// Admit one slice for `tenant`, or say why not.
+    pub fn admit(self: &Arc<Self>, tenant: &str) -> Result<TenantPermit, Throttled> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let quota = inner.quota(tenant);
+        let st = inner.tenants.entry(tenant.to_owned()).or_insert_with(|| TenantState::new(&quota));
+
+        if quota.max_concurrent > 0 && st.in_flight >= quota.max_concurrent {
+            st.throttled_concurrency += 1;
+            return Err(Throttled {
+                tenant_id: tenant.to_owned(),
+                reason: "CONCURRENCY",
+                limit: quota.max_concurrent as u64,
+            });
+        }
+        if quota.rate_per_min > 0 {
+            // Token bucket: burst = one minute's worth, refilled continuously
+            let rate = quota.rate_per_min as f64;
+            let now = Instant::now();
+            let refill = now.duration_since(st.refilled).as_secs_f64() * rate / 60.0;
+            st.tokens = (st.tokens + refill).min(rate);
+            st.refilled = now;
+            if st.tokens < 1.0 {
+                st.throttled_rate += 1;
+                return Err(Throttled {
+                    tenant_id: tenant.to_owned(),
+                    reason: "RATE_LIMIT",
+                    limit: quota.rate_per_min as u64,
+                });
+            }
+            st.tokens -= 1.0;
+        }
+
+        st.in_flight += 1;
+        st.admitted_total += 1;
+        Ok(TenantPermit { governor: self.clone(), tenant: tenant.to_owned() })
+    }
+
+    /// Apply CONFIG_UPDATE `tenantQuotas`; returns how many entries were applied.
+    pub fn set_quotas(&self, v: &Value) -> Result<usize> {
+        let obj = v.as_object().ok_or_else(|| anyhow!("tenantQuotas must be an object"))?;
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        // Validate everything before touching live state
+        let mut defaults = inner.defaults;
+        let mut overrides = HashMap::new();
+        for (tenant, q) in obj {
+            if tenant == "*" {
+                defaults = defaults.merged(q).map_err(|e| anyhow!("tenantQuotas.*: {e}"))?;
+            }
+        }
+        for (tenant, q) in obj.iter().filter(|(t, _)| *t != "*") {
+            let base = inner.overrides.get(tenant).copied().unwrap_or(defaults);
+            overrides.insert(tenant.clone(), base.merged(q).map_err(|e| anyhow!("tenantQuotas.{tenant}: {e}"))?);
+        }
+        inner.defaults = defaults;
+        for (tenant, q) in &overrides {
+            if let Some(st) = inner.tenants.get_mut(tenant) {
+                st.tokens = q.rate_per_min as f64;
+            }
+        }
+        inner.overrides.extend(overrides);
+        Ok(obj.len())
+    }
+
+    /// Per-tenant offline buffer caps (tenants without an override use `default`).
+    pub fn offline_limits(&self) -> (usize, HashMap<String, usize>) {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let per_tenant = inner.overrides.iter()
+            .map(|(t, q)| (t.clone(), q.offline_max_events))
+            .collect();
+        (inner.defaults.offline_max_events, per_tenant)
+    }
+
+    /// Record the offline buffer's per-tenant depth for /metrics.
+    pub fn set_offline_depths(&self, depths: &HashMap<String, usize>) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let Inner { tenants, defaults, overrides } = &mut *inner;
+        for st in tenants.values_mut() {
+            st.offline_events = 0;
+        }
+        for (tenant, n) in depths {
+            let quota = overrides.get(tenant).copied().unwrap_or(*defaults);
+            tenants.entry(tenant.clone()).or_insert_with(|| TenantState::new(&quota)).offline_events = *n;
+        }
+    }
+
+    fn release(&self, tenant: &str, outcome: Option<(u64, bool)>) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(st) = inner.tenants.get_mut(tenant) {
+            st.in_flight = st.in_flight.saturating_sub(1);
+            if let Some((elapsed_ms, ok)) = outcome {
+                st.exec_ms_sum += elapsed_ms;
+                if !ok {
+                    st.failed_total += 1;
+                }
+            }
+        }
+    }
+}
+
+/// One admitted slice; frees the tenant's concurrency slot on drop.
+#[derive(Debug)]
+pub struct TenantPermit {
+    governor: Arc<TenantGovernor>,
+    tenant: String,
+}
+
+impl TenantPermit {
+    /// Release the slot and record the execution outcome.
+    pub fn finish(self, elapsed_ms: u64, ok: bool) {
+        self.governor.release(&self.tenant, Some((elapsed_ms, ok)));
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for TenantPermit {
+    fn drop(&mut self) {
+        self.governor.release(&self.tenant, None);
+    }
+}
+
+// ── Metrics ───────────────────────────────────────────────────────────────────
+
+type Series = (&'static str, &'static str, &'static str, fn(&TenantState) -> u64);
+
+impl MetricsCollector for TenantGovernor {
+    fn prometheus(&self, node_id: &str) -> String {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.tenants.is_empty() {
+            return String::new();
+        }
+        let mut tenants: Vec<_> = inner.tenants.iter().collect();
+        tenants.sort_by(|a, b| a.0.cmp(b.0));
+
+        let series: [Series; 7] = [
+            ("eyeflow_tenant_in_flight", "gauge", "Slices currently executing", |s| s.in_flight as u64),
+            ("eyeflow_tenant_executions_total", "counter", "Slices admitted", |s| s.admitted_total),
+            ("eyeflow_tenant_executions_failed", "counter", "Admitted slices that failed", |s| s.failed_total),
+            ("eyeflow_tenant_exec_ms_sum", "counter", "Total execution time (ms)", |s| s.exec_ms_sum),
+            ("eyeflow_tenant_offline_events", "gauge", "Events queued in the offline buffer", |s| s.offline_events as u64),
+            ("eyeflow_tenant_throttled_concurrency_total", "counter", "Slices refused at maxConcurrent", |s| s.throttled_concurrency),
+            ("eyeflow_tenant_throttled_rate_total", "counter", "Slices refused at ratePerMinute", |s| s.throttled_rate),
+        ];
+
+        let mut out = String::new();
+        for (name, kind, help, get) in series {
+            out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+            for (tenant, st) in &tenants {
+                out.push_str(&format!("{name}{{node_id=\"{node_id}\",tenant=\"{tenant}\"}} {}\n", get(st)));
+            }
+        }
+        out
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_concurrency_and_rate_are_per_tenant() {
+        let gov = TenantGovernor::new(TenantQuota { max_concurrent: 1, ..Default::default() });
+        let a = gov.admit("acme").unwrap();
+        assert_eq!(gov.admit("acme").unwrap_err().reason, "CONCURRENCY");
+        // Another tenant is unaffected
+        let _b = gov.admit("globex").unwrap();
+        a.finish(12, true);
+        let _a2 = gov.admit("acme").unwrap();
+
+        gov.set_quotas(&json!({ "slow": { "ratePerMinute": 2, "maxConcurrent": 0 } })).unwrap();
+        drop(gov.admit("slow").unwrap());
+        drop(gov.admit("slow").unwrap());
+        assert_eq!(gov.admit("slow").unwrap_err().reason, "RATE_LIMIT");
+
+        let metrics = gov.prometheus("n1");
+        assert!(metrics.contains("eyeflow_tenant_exec_ms_sum{node_id=\"n1\",tenant=\"acme\"} 12"));
+        assert!(metrics.contains("eyeflow_tenant_throttled_rate_total{node_id=\"n1\",tenant=\"slow\"} 1"));
+    }
+
+    #[test]
+    fn test_set_quotas_validates_and_scopes() {
+        let gov = TenantGovernor::new(TenantQuota::default());
+        assert!(gov.set_quotas(&json!({ "acme": { "maxConcurrent": -1 } })).is_err());
+        gov.set_quotas(&json!({ "*": { "offlineMaxEvents": 10 }, "acme": { "offlineMaxEvents": 3 } })).unwrap();
+        let (default, per_tenant) = gov.offline_limits();
+        assert_eq!(default, 10);
+        assert_eq!(per_tenant["acme"], 3);
+
+        assert_eq!(plan_scope(DEFAULT_TENANT, "p1"), "p1");
+        assert_eq!(plan_scope("acme", "p1"), "acme/p1");
+    }
+}
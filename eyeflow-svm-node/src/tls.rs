@@ -0,0 +1,420 @@
+//! mTLS client identity for the central link, with automatic renewal
+//!
+//!   SVM_TLS_CERT_PATH          client certificate chain (PEM)  } both set: the
+//!   SVM_TLS_KEY_PATH           its key (PKCS#8 PEM, 0600)      } central WebSocket
+//!                                                                presents them
+//!   SVM_TLS_CA_PATH            extra CA bundle trusted for central (PEM)
+//!   SVM_TLS_RENEW_BEFORE_SECS  renew once the certificate expires sooner
+//!                              (default 7 days)
+//!   SVM_TLS_CHECK_SECS         expiry / on-disk change check (default 3600)
+//!
+//! Renewal is central-issued: the node generates a fresh P-256 key and a CSR
+//! (CN = node id) and asks
+//!
+//!   POST {CENTRAL_HTTP_URL}/api/nodes/{nodeId}/certificate
+//!   Authorization: Bearer <SVM_AUTH_TOKEN>        (over mTLS, current cert)
+//!   { "csr": "<PEM>", "currentFingerprint": "<SHA-256 of the current cert>" }
+//!   → { "certificate": "<PEM chain>" }
+//!
+//! The answer must be a currently valid certificate for the new key; both
+//! files are then replaced (written aside, renamed) and the connector is
+//! swapped.  Certificates replaced on disk by another tool (an ACME client,
+//! configuration management) are picked up at the next check the same way.
+//!
+//! The connector only serves new handshakes: the open session, and the
+//! executions running on it, keep their connection; the next reconnect
+//! presents the new certificate.
+//!
+//! /metrics: eyeflow_tls_cert_expiry_seconds,
+//! eyeflow_tls_cert_not_after_timestamp_seconds and
+//! eyeflow_tls_renewals_total{result}.
+
+use anyhow::{anyhow, Context, Result};
+use openssl::asn1::Asn1Time;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::x509::{X509, X509NameBuilder, X509ReqBuilder};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::health::MetricsCollector;
+
+// ── Loaded identity ───────────────────────────────────────────────────────────
+
+struct Loaded {
+    connector: native_tls::TlsConnector,
+    /// Leaf certificate expiry (Unix seconds)
+    not_after: i64,
+    /// SHA-256 of the leaf certificate (hex)
+    fingerprint: String,
+    /// Modification times of the certificate and key files when loaded
+    modified: (Option<SystemTime>, Option<SystemTime>),
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Unix seconds of an ASN.1 time.
+fn unix_seconds(time: &openssl::asn1::Asn1TimeRef) -> Result<i64> {
+    let diff = Asn1Time::from_unix(0)?.diff(time)?;
+    Ok(i64::from(diff.days) * 86_400 + i64::from(diff.secs))
+}
+
+/// Check `cert_pem` / `key_pem` belong together and are currently valid;
+/// returns the leaf's expiry and fingerprint.
+fn inspect(cert_pem: &[u8], key_pem: &[u8]) -> Result<(i64, String)> {
+    let chain = X509::stack_from_pem(cert_pem).context("client certificate")?;
+    let leaf = chain.first().ok_or_else(|| anyhow!("client certificate file holds no certificate"))?;
+    let key = PKey::private_key_from_pem(key_pem).context("client key")?;
+    if !leaf.public_key()?.public_eq(&key) {
+        return Err(anyhow!("client certificate does not match its key"));
+    }
+    let now = chrono::Utc::now().timestamp();
+    if unix_seconds(leaf.not_before())? > now {
+        return Err(anyhow!("client certificate is not valid yet"));
+    }
+    let not_after = unix_seconds(leaf.not_after())?;
+    if not_after <= now {
+        return Err(anyhow!("client certificate expired"));
+    }
+    Ok((not_after, hex::encode(leaf.digest(MessageDigest::sha256())?)))
+}
+
+fn write_atomic(path: &Path, bytes: &[u8], private: bool) -> Result<()> {
+    use std::io::Write;
+    let tmp = path.with_extension("tmp");
+    let _ = std::fs::remove_file(&tmp);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if private {
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    let mut file = options.open(&tmp).with_context(|| format!("writing {}", tmp.display()))?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+// ── Client identity ───────────────────────────────────────────────────────────
+
+pub struct TlsIdentity {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    ca_pem: Option<Vec<u8>>,
+    node_id: String,
+    renew_url: String,
+    auth_token: String,
+    renew_before: Duration,
+    check_every: Duration,
+    current: RwLock<Loaded>,
+    renewed: AtomicU64,
+    renew_failed: AtomicU64,
+}
+
+impl TlsIdentity {
+    /// The client identity of SVM_TLS_CERT_PATH / SVM_TLS_KEY_PATH (None when unset).
+    pub fn from_config(config: &Config) -> Result<Option<Arc<Self>>> {
+        let (cert_path, key_path) = match (&config.tls_cert_path, &config.tls_key_path) {
+            (Some(c), Some(k)) => (PathBuf::from(c), PathBuf::from(k)),
+            (None, None) => return Ok(None),
+            _ => return Err(anyhow!("SVM_TLS_CERT_PATH and SVM_TLS_KEY_PATH must be set together")),
+        };
+        let ca_pem = match &config.tls_ca_path {
+            Some(path) => Some(std::fs::read(path).with_context(|| format!("SVM_TLS_CA_PATH {path}"))?),
+            None => None,
+        };
+        let current = Self::load(&cert_path, &key_path, ca_pem.as_deref())?;
+        let identity = Self {
+            cert_path,
+            key_path,
+            ca_pem,
+            node_id: config.node_id.clone(),
+            renew_url: format!(
+                "{}/api/nodes/{}/certificate",
+                config.central_http_url.trim_end_matches('/'), config.node_id,
+            ),
+            auth_token: config.auth_token.clone(),
+            renew_before: Duration::from_secs(config.tls_renew_before_secs),
+            check_every: Duration::from_secs(config.tls_check_secs.max(1)),
+            current: RwLock::new(current),
+            renewed: AtomicU64::new(0),
+            renew_failed: AtomicU64::new(0),
+        };
+        info!(
+            "[TLS] client certificate {} expires in {}",
+            identity.cert_path.display(), format_secs(identity.expires_in_secs()),
+        );
+        Ok(Some(Arc::new(identity)))
+    }
+
+    fn load(cert_path: &Path, key_path: &Path, ca_pem: Option<&[u8]>) -> Result<Loaded> {
+        let cert_pem = std::fs::read(cert_path).with_context(|| format!("reading {}", cert_path.display()))?;
+        let key_pem = std::fs::read(key_path).with_context(|| format!("reading {}", key_path.display()))?;
+        let (not_after, fingerprint) = inspect(&cert_pem, &key_pem)?;
+        Ok(Loaded {
+            connector: Self::connector_for(&cert_pem, &key_pem, ca_pem)?,
+            not_after,
+            fingerprint,
+            modified: (modified(cert_path), modified(key_path)),
+        })
+    }
+
+    fn connector_for(cert_pem: &[u8], key_pem: &[u8], ca_pem: Option<&[u8]>) -> Result<native_tls::TlsConnector> {
+        let mut builder = native_tls::TlsConnector::builder();
+        builder.identity(native_tls::Identity::from_pkcs8(cert_pem, key_pem).context("client identity")?);
+        if let Some(ca) = ca_pem {
+            for cert in X509::stack_from_pem(ca).context("SVM_TLS_CA_PATH")? {
+                builder.add_root_certificate(native_tls::Certificate::from_der(&cert.to_der()?)?);
+            }
+        }
+        Ok(builder.build()?)
+    }
+
+    /// Connector for the next handshake.
+    pub fn connector(&self) -> native_tls::TlsConnector {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).connector.clone()
+    }
+
+    pub fn expires_in_secs(&self) -> i64 {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).not_after - chrono::Utc::now().timestamp()
+    }
+
+    fn swap(&self, loaded: Loaded) {
+        info!("[TLS] client certificate swapped (expires in {})", format_secs(loaded.not_after - chrono::Utc::now().timestamp()));
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = loaded;
+    }
+
+    /// Reload the files when another tool replaced them.  Returns whether
+    /// the connector was swapped.
+    pub fn reload_if_changed(&self) -> Result<bool> {
+        let on_disk = (modified(&self.cert_path), modified(&self.key_path));
+        if self.current.read().unwrap_or_else(|e| e.into_inner()).modified == on_disk {
+            return Ok(false);
+        }
+        self.swap(Self::load(&self.cert_path, &self.key_path, self.ca_pem.as_deref())?);
+        Ok(true)
+    }
+
+    /// A fresh key and its CSR (PEM).
+    fn csr(&self) -> Result<(PKey<Private>, Vec<u8>)> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+        let key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+        let mut name = X509NameBuilder::new()?;
+        name.append_entry_by_nid(Nid::COMMONNAME, &self.node_id)?;
+        let mut req = X509ReqBuilder::new()?;
+        req.set_subject_name(&name.build())?;
+        req.set_pubkey(&key)?;
+        req.sign(&key, MessageDigest::sha256())?;
+        Ok((key, req.build().to_pem()?))
+    }
+
+    /// Have central issue a certificate for a fresh key, then swap to it.
+    pub async fn renew(&self) -> Result<()> {
+        let result = self.try_renew().await;
+        let counter = if result.is_ok() { &self.renewed } else { &self.renew_failed };
+        counter.fetch_add(1, Ordering::Relaxed);
+        result
+    }
+
+    async fn try_renew(&self) -> Result<()> {
+        let (key, csr) = self.csr()?;
+        let key_pem = key.private_key_to_pem_pkcs8()?;
+
+        let mut identity_pem = std::fs::read(&self.cert_path)?;
+        identity_pem.extend_from_slice(&std::fs::read(&self.key_path)?);
+        let mut http = reqwest::Client::builder()
+            .identity(reqwest::Identity::from_pem(&identity_pem).context("current client identity")?)
+            .timeout(Duration::from_secs(30));
+        if let Some(ca) = &self.ca_pem {
+            for cert in reqwest::Certificate::from_pem_bundle(ca)? {
+                http = http.add_root_certificate(cert);
+            }
+        }
+        let mut req = http.build()?.post(&self.renew_url).json(&json!({
+            "csr": String::from_utf8_lossy(&csr),
+            "currentFingerprint": self.current.read().unwrap_or_else(|e| e.into_inner()).fingerprint,
+        }));
+        if !self.auth_token.is_empty() {
+            req = req.bearer_auth(&self.auth_token);
+        }
+        let resp = req.send().await.with_context(|| format!("POST {}", self.renew_url))?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("POST {}: HTTP {}", self.renew_url, resp.status()));
+        }
+        let body: Value = resp.json().await?;
+        let cert_pem = body.get("certificate").and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("certificate answer has no `certificate`"))?
+            .as_bytes()
+            .to_vec();
+        inspect(&cert_pem, &key_pem).context("issued certificate")?;
+
+        // Key first: a crash in between leaves a mismatched pair, which the
+        // next start reports, rather than a certificate for a lost key
+        write_atomic(&self.key_path, &key_pem, true)?;
+        write_atomic(&self.cert_path, &cert_pem, false)?;
+        self.swap(Self::load(&self.cert_path, &self.key_path, self.ca_pem.as_deref())?);
+        Ok(())
+    }
+
+    /// Watch the files and renew ahead of expiry.
+    pub async fn run(self: Arc<Self>) {
+        let mut retry = self.check_every;
+        loop {
+            tokio::time::sleep(retry).await;
+            retry = self.check_every;
+            if let Err(e) = self.reload_if_changed() {
+                warn!("[TLS] reloading the client certificate: {e:#}");
+            }
+            let left = self.expires_in_secs();
+            if left > self.renew_before.as_secs() as i64 {
+                continue;
+            }
+            info!("[TLS] client certificate expires in {} — renewing", format_secs(left));
+            if let Err(e) = self.renew().await {
+                warn!("[TLS] renewal failed: {e:#}");
+                // Retry sooner as expiry approaches
+                retry = self.check_every.min(Duration::from_secs((left.max(0) as u64 / 4).max(60)));
+            }
+        }
+    }
+}
+
+fn format_secs(secs: i64) -> String {
+    if secs.abs() >= 86_400 {
+        format!("{}d {}h", secs / 86_400, (secs % 86_400) / 3_600)
+    } else {
+        format!("{}h {}m", secs / 3_600, (secs % 3_600) / 60)
+    }
+}
+
+impl MetricsCollector for TlsIdentity {
+    fn prometheus(&self, node_id: &str) -> String {
+        let not_after = self.current.read().unwrap_or_else(|e| e.into_inner()).not_after;
+        format!(
+            "# HELP eyeflow_tls_cert_expiry_seconds Seconds until the mTLS client certificate expires\n\
+             # TYPE eyeflow_tls_cert_expiry_seconds gauge\n\
+             eyeflow_tls_cert_expiry_seconds{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_tls_cert_not_after_timestamp_seconds Expiry of the mTLS client certificate\n\
+             # TYPE eyeflow_tls_cert_not_after_timestamp_seconds gauge\n\
+             eyeflow_tls_cert_not_after_timestamp_seconds{{node_id=\"{node_id}\"}} {not_after}\n\
+             # HELP eyeflow_tls_renewals_total Client certificate renewals by outcome\n\
+             # TYPE eyeflow_tls_renewals_total counter\n\
+             eyeflow_tls_renewals_total{{node_id=\"{node_id}\",result=\"ok\"}} {}\n\
+             eyeflow_tls_renewals_total{{node_id=\"{node_id}\",result=\"failed\"}} {}\n",
+            not_after - chrono::Utc::now().timestamp(),
+            self.renewed.load(Ordering::Relaxed),
+            self.renew_failed.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::bn::BigNum;
+    use openssl::x509::X509Req;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn p256() -> PKey<Private> {
+        PKey::from_ec_key(EcKey::generate(&EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap()).unwrap()).unwrap()
+    }
+
+    /// Certificate for `public` issued by `ca_key` (self-signed when `ca` is None).
+    fn issue(public: &PKey<impl openssl::pkey::HasPublic>, cn: &str, days: u32, ca: Option<&X509>, ca_key: &PKey<Private>) -> X509 {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, cn).unwrap();
+        let name = name.build();
+        let mut cert = X509::builder().unwrap();
+        cert.set_version(2).unwrap();
+        cert.set_serial_number(&BigNum::from_u32(rand::random::<u32>()).unwrap().to_asn1_integer().unwrap()).unwrap();
+        cert.set_subject_name(&name).unwrap();
+        cert.set_issuer_name(ca.map_or(&name, |c| c.subject_name())).unwrap();
+        cert.set_pubkey(public).unwrap();
+        cert.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        cert.set_not_after(&Asn1Time::days_from_now(days).unwrap()).unwrap();
+        cert.sign(ca_key, MessageDigest::sha256()).unwrap();
+        cert.build()
+    }
+
+    #[tokio::test]
+    async fn test_renewal_swaps_the_identity() {
+        let ca_key = p256();
+        let ca = issue(&ca_key, "eyeflow-ca", 365, None, &ca_key);
+        let key = p256();
+        let cert = issue(&key, "node-1", 2, Some(&ca), &ca_key);
+
+        // Central's certificate endpoint: signs the CSR for 90 days
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (ca_pem, ca_key_pem) = (ca.to_pem().unwrap(), ca_key.private_key_to_pem_pkcs8().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 8192];
+            let body = loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).into_owned();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head.lines()
+                        .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break body.to_owned();
+                    }
+                }
+            };
+            let body: Value = serde_json::from_str(&body).unwrap();
+            let csr = X509Req::from_pem(body["csr"].as_str().unwrap().as_bytes()).unwrap();
+            let (ca, ca_key) = (X509::from_pem(&ca_pem).unwrap(), PKey::private_key_from_pem(&ca_key_pem).unwrap());
+            let issued = issue(&csr.public_key().unwrap(), "node-1", 90, Some(&ca), &ca_key);
+            let answer = json!({ "certificate": String::from_utf8(issued.to_pem().unwrap()).unwrap() }).to_string();
+            let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{answer}", answer.len());
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let dir = std::env::temp_dir().join(format!("eyeflow_tls_{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (cert_path, key_path, ca_path) = (dir.join("node.crt"), dir.join("node.key"), dir.join("ca.pem"));
+        std::fs::write(&cert_path, cert.to_pem().unwrap()).unwrap();
+        std::fs::write(&key_path, key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+        std::fs::write(&ca_path, ca.to_pem().unwrap()).unwrap();
+
+        let mut config = Config::from_env();
+        config.node_id = "node-1".into();
+        config.central_http_url = format!("http://127.0.0.1:{port}");
+        config.tls_cert_path = Some(cert_path.display().to_string());
+        config.tls_key_path = Some(key_path.display().to_string());
+        config.tls_ca_path = Some(ca_path.display().to_string());
+        let identity = TlsIdentity::from_config(&config).unwrap().unwrap();
+        assert!((86_400..=2 * 86_400).contains(&identity.expires_in_secs()));
+        assert!(!identity.reload_if_changed().unwrap());
+
+        identity.renew().await.unwrap();
+        assert!(identity.expires_in_secs() > 80 * 86_400);
+        let new_key = PKey::private_key_from_pem(&std::fs::read(&key_path).unwrap()).unwrap();
+        assert!(!new_key.public_eq(&key));
+        let metrics = identity.prometheus("node-1");
+        assert!(metrics.contains("eyeflow_tls_renewals_total{node_id=\"node-1\",result=\"ok\"} 1"));
+
+        // A mismatched pair on disk is refused and the current identity kept
+        std::fs::write(&key_path, key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+        assert!(identity.reload_if_changed().is_err());
+        assert!(identity.expires_in_secs() > 80 * 86_400);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
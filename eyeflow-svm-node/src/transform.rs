@@ -0,0 +1,432 @@
+//! TRANSFORM — paths, templates and the function library (spec §3.4)
+//!
+//! operands_json selects what TRANSFORM does with its source register:
+//!
+//!   { "path": "reading.ts" }                    value at a dot path ($. optional)
+//!   { "template": "Due {{due}} ({{site}})" }    {{expr}} substituted into text
+//!   { "convert": { "from": "bar", … } }         unit conversion / calibration of
+//!                                               the result (units.rs)
+//!
+//! Both accept expressions, not just paths: a path, a literal ('text',
+//! "text", 12.5, true, null) or a function call whose arguments are
+//! expressions themselves:
+//!
+//!   { "path": "add_duration(now(), '4h')" }
+//!   { "template": "Shift ends {{format_ts(end, '%H:%M', 'Europe/Paris')}}" }
+//!
+//! Date/time functions — timestamps are RFC 3339 strings or epoch ms, time
+//! zones IANA names ("Europe/Paris"), "UTC" or fixed offsets ("+05:30"),
+//! durations "4h", "1d2h30m", "-15m", "500ms", ISO 8601 ("PT4H", "P1DT2H")
+//! or milliseconds:
+//!
+//!   now()                            current UTC time
+//!   add_duration(ts, duration)       ts + duration, keeping ts's offset
+//!   format_ts(ts, fmt[, tz])         strftime text, in tz (default: ts's own)
+//!   parse_ts(text[, fmt[, tz]])      RFC 3339 from RFC 3339 / RFC 2822 text,
+//!                                    or from `fmt`; a fmt without %z is read
+//!                                    as local time in tz (default UTC)
+//!   to_tz(ts, tz)                    the same instant at tz's offset
+//!
+//! convert(x, from, to) converts a reading between units (units.rs), and
+//! parse_csv / to_csv / split_ndjson / join_ndjson handle tabular text
+//! within SVM_TABULAR_MAX_BYTES / _ROWS (tabular.rs).
+//!
+//! Functions return RFC 3339 text (UTC as "Z").  A bad argument fails the
+//! instruction with VALIDATION; a path that does not resolve is null (or,
+//! in a template, leaves its {{placeholder}} as it was).
+
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, SecondsFormat, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+use crate::errors::ExecError;
+use crate::tabular::{self, Limits};
+use crate::units::Conversion;
+
+/// Run the TRANSFORM described by `operands` over `src`.
+pub fn apply(src: &Value, operands: &Value, limits: &Limits) -> Result<Value> {
+    let out = if let Some(path) = operands.get("path").and_then(|v| v.as_str()) {
+        eval(&parse(path)?, src, limits)?.unwrap_or(Value::Null)
+    } else if let Some(tmpl) = operands.get("template").and_then(|v| v.as_str()) {
+        Value::String(render(tmpl, src, limits)?)
+    } else {
+        src.clone()
+    };
+    match operands.get("convert") {
+        Some(spec) => Conversion::from_json(spec)?.apply(&out),
+        None => Ok(out),
+    }
+}
+
+/// Substitute every {{expr}} in `tmpl`.
+pub fn render(tmpl: &str, src: &Value, limits: &Limits) -> Result<String> {
+    static PLACEHOLDER: OnceLock<regex_lite::Regex> = OnceLock::new();
+    let re = PLACEHOLDER.get_or_init(|| regex_lite::Regex::new(r"\{\{\s*(.+?)\s*\}\}").expect("valid regex"));
+    let mut out = String::with_capacity(tmpl.len());
+    let mut last = 0;
+    for caps in re.captures_iter(tmpl) {
+        let whole = caps.get(0).expect("group 0");
+        out.push_str(&tmpl[last..whole.start()]);
+        match eval(&parse(&caps[1])?, src, limits)? {
+            Some(Value::String(s)) => out.push_str(&s),
+            Some(v) => out.push_str(&v.to_string()),
+            None => out.push_str(whole.as_str()),
+        }
+        last = whole.end();
+    }
+    out.push_str(&tmpl[last..]);
+    Ok(out)
+}
+
+// ── Expressions ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Lit(Value),
+    Path(String),
+    Call(String, Vec<Expr>),
+}
+
+fn parse(text: &str) -> Result<Expr> {
+    let mut p = Parser { s: text, pos: 0 };
+    let expr = p.expr()?;
+    p.ws();
+    if p.pos < text.len() {
+        return Err(p.error("unexpected trailing input"));
+    }
+    Ok(expr)
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn ws(&mut self) {
+        self.pos = self.s.len() - self.rest().trim_start().len();
+    }
+
+    fn error(&self, msg: &str) -> anyhow::Error {
+        ExecError::validation(format!("transform expression '{}': {msg} at {}", self.s, self.pos))
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        self.ws();
+        let rest = self.rest();
+        if let Some(quote) = rest.chars().next().filter(|c| *c == '\'' || *c == '"') {
+            let body = &rest[1..];
+            let end = body.find(quote).ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += end + 2;
+            return Ok(Expr::Lit(Value::String(body[..end].to_owned())));
+        }
+        let len = rest.find(|c: char| c.is_whitespace() || "(),".contains(c)).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected a value"));
+        }
+        let token = &rest[..len];
+        self.pos += len;
+        self.ws();
+        if self.rest().starts_with('(') {
+            self.pos += 1;
+            let mut args = Vec::new();
+            self.ws();
+            if self.rest().starts_with(')') {
+                self.pos += 1;
+                return Ok(Expr::Call(token.to_owned(), args));
+            }
+            loop {
+                args.push(self.expr()?);
+                self.ws();
+                match self.rest().chars().next() {
+                    Some(',') => self.pos += 1,
+                    Some(')') => {
+                        self.pos += 1;
+                        return Ok(Expr::Call(token.to_owned(), args));
+                    }
+                    _ => return Err(self.error("expected ',' or ')'")),
+                }
+            }
+        }
+        Ok(match token {
+            "true" => Expr::Lit(Value::Bool(true)),
+            "false" => Expr::Lit(Value::Bool(false)),
+            "null" => Expr::Lit(Value::Null),
+            _ if token.starts_with(|c: char| c.is_ascii_digit() || c == '-') => {
+                Expr::Lit(serde_json::from_str(token).map_err(|_| self.error("invalid number"))?)
+            }
+            _ => Expr::Path(token.to_owned()),
+        })
+    }
+}
+
+/// `None` = a path that does not resolve.
+fn eval(expr: &Expr, src: &Value, limits: &Limits) -> Result<Option<Value>> {
+    match expr {
+        Expr::Lit(v) => Ok(Some(v.clone())),
+        Expr::Path(path) => Ok(path_get(src, path).cloned()),
+        Expr::Call(name, args) => {
+            let args = args.iter()
+                .map(|a| eval(a, src, limits).map(|v| v.unwrap_or(Value::Null)))
+                .collect::<Result<Vec<_>>>()?;
+            call(name, &args, limits).map(Some)
+        }
+    }
+}
+
+fn path_get<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim_start_matches('$').trim_start_matches('.');
+    if path.is_empty() {
+        return Some(root);
+    }
+    path.split('.').try_fold(root, |cur, part| match cur {
+        Value::Array(items) => items.get(part.parse::<usize>().ok()?),
+        _ => cur.get(part),
+    })
+}
+
+// ── Functions ─────────────────────────────────────────────────────────────────
+
+fn call(name: &str, args: &[Value], limits: &Limits) -> Result<Value> {
+    let arity = |min: usize, max: usize| {
+        if (min..=max).contains(&args.len()) {
+            Ok(())
+        } else {
+            Err(ExecError::validation(format!("{name}() takes {min}..={max} argument(s), got {}", args.len())))
+        }
+    };
+    let text = |i: usize| -> Result<&str> {
+        args[i].as_str().ok_or_else(|| ExecError::validation(format!("{name}(): argument {} must be a string", i + 1)))
+    };
+    let ts = match name {
+        "now" => {
+            arity(0, 0)?;
+            return Ok(Value::String(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)));
+        }
+        "add_duration" => {
+            arity(2, 2)?;
+            let ts = timestamp(&args[0])?;
+            ts.checked_add_signed(duration(&args[1])?)
+                .ok_or_else(|| ExecError::validation("add_duration(): result out of range"))?
+        }
+        "format_ts" => {
+            arity(2, 3)?;
+            let mut ts = timestamp(&args[0])?;
+            if args.len() == 3 {
+                ts = zone(text(2)?)?.convert(&ts);
+            }
+            let mut out = String::new();
+            std::fmt::Write::write_fmt(&mut out, format_args!("{}", ts.format(text(1)?)))
+                .map_err(|_| ExecError::validation(format!("format_ts(): invalid format '{}'", text(1).unwrap_or(""))))?;
+            return Ok(Value::String(out));
+        }
+        "parse_ts" => {
+            arity(1, 3)?;
+            match args.get(1) {
+                None | Some(Value::Null) => timestamp(&args[0])?,
+                Some(_) => {
+                    let (input, fmt) = (text(0)?, text(1)?);
+                    match DateTime::parse_from_str(input, fmt) {
+                        Ok(ts) => ts,
+                        Err(_) => {
+                            let naive = NaiveDateTime::parse_from_str(input, fmt).map_err(|e| {
+                                ExecError::validation(format!("parse_ts(): '{input}' does not match '{fmt}': {e}"))
+                            })?;
+                            let tz = if args.len() == 3 { zone(text(2)?)? } else { Zone::Fixed(FixedOffset::east_opt(0).expect("UTC")) };
+                            tz.local(&naive)?
+                        }
+                    }
+                }
+            }
+        }
+        "to_tz" => {
+            arity(2, 2)?;
+            zone(text(1)?)?.convert(&timestamp(&args[0])?)
+        }
+        "convert" => {
+            arity(3, 3)?;
+            let spec = serde_json::json!({ "from": text(1)?, "to": text(2)? });
+            return Conversion::from_json(&spec)?.apply(&args[0]);
+        }
+        "parse_csv" => {
+            arity(1, 3)?;
+            let header = args.get(2).map_or(Some(true), Value::as_bool)
+                .ok_or_else(|| ExecError::validation("parse_csv(): header must be true or false"))?;
+            return tabular::parse_csv(text(0)?, delimiter(args.get(1))?, header, limits);
+        }
+        "to_csv" => {
+            arity(1, 2)?;
+            return tabular::to_csv(&args[0], delimiter(args.get(1))?, limits).map(Value::String);
+        }
+        "split_ndjson" => {
+            arity(1, 1)?;
+            return tabular::split_ndjson(text(0)?, limits);
+        }
+        "join_ndjson" => {
+            arity(1, 1)?;
+            return tabular::join_ndjson(&args[0], limits).map(Value::String);
+        }
+        _ => return Err(ExecError::validation(format!("unknown transform function '{name}()'"))),
+    };
+    Ok(Value::String(ts.to_rfc3339_opts(SecondsFormat::AutoSi, true)))
+}
+
+/// A single-character CSV delimiter (default ',').
+fn delimiter(arg: Option<&Value>) -> Result<char> {
+    let Some(arg) = arg.filter(|a| !a.is_null()) else { return Ok(',') };
+    let mut chars = arg.as_str().unwrap_or_default().chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c != '"' && c != '\r' && c != '\n' => Ok(c),
+        _ => Err(ExecError::validation(format!("CSV delimiter must be one character, got {arg}"))),
+    }
+}
+
+/// RFC 3339 / RFC 2822 text or epoch milliseconds.
+fn timestamp(v: &Value) -> Result<DateTime<FixedOffset>> {
+    let parsed = match v {
+        Value::String(s) => DateTime::parse_from_rfc3339(s.trim())
+            .or_else(|_| DateTime::parse_from_rfc2822(s.trim()))
+            .ok(),
+        Value::Number(n) => n.as_i64().and_then(DateTime::from_timestamp_millis).map(|t| t.fixed_offset()),
+        _ => None,
+    };
+    parsed.ok_or_else(|| ExecError::validation(format!("not a timestamp: {v}")))
+}
+
+enum Zone {
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+fn zone(name: &str) -> Result<Zone> {
+    let name = name.trim();
+    if name.eq_ignore_ascii_case("utc") || name == "Z" {
+        return Ok(Zone::Fixed(FixedOffset::east_opt(0).expect("UTC")));
+    }
+    if let Ok(offset) = name.parse::<FixedOffset>() {
+        return Ok(Zone::Fixed(offset));
+    }
+    name.parse::<Tz>()
+        .map(Zone::Named)
+        .map_err(|_| ExecError::validation(format!("unknown time zone '{name}'")))
+}
+
+impl Zone {
+    fn convert(&self, ts: &DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+        match self {
+            Self::Named(tz) => ts.with_timezone(tz).fixed_offset(),
+            Self::Fixed(offset) => ts.with_timezone(offset),
+        }
+    }
+
+    /// `naive` read as wall-clock time here (the earlier instant when a DST
+    /// change makes it ambiguous).
+    fn local(&self, naive: &NaiveDateTime) -> Result<DateTime<FixedOffset>> {
+        let ts = match self {
+            Self::Named(tz) => tz.from_local_datetime(naive).earliest().map(|t| t.fixed_offset()),
+            Self::Fixed(offset) => offset.from_local_datetime(naive).earliest(),
+        };
+        ts.ok_or_else(|| ExecError::validation(format!("parse_ts(): {naive} does not exist in that time zone")))
+    }
+}
+
+/// "4h", "1d2h30m", "-15m", "500ms", ISO 8601 "PT4H" / "P1DT2H", or a number
+/// of milliseconds.
+fn duration(v: &Value) -> Result<TimeDelta> {
+    let invalid = || ExecError::validation(format!("not a duration: {v}"));
+    let ms = match v {
+        Value::Number(n) => n.as_f64().ok_or_else(invalid)?,
+        Value::String(s) => {
+            let s = s.trim();
+            let (sign, s) = match s.strip_prefix('-') {
+                Some(rest) => (-1.0, rest),
+                None => (1.0, s.strip_prefix('+').unwrap_or(s)),
+            };
+            let iso = s.strip_prefix('P');
+            let mut rest = iso.unwrap_or(s);
+            let mut in_time = false;
+            let mut total = 0.0;
+            while !rest.is_empty() {
+                if iso.is_some() && rest.starts_with('T') {
+                    in_time = true;
+                    rest = &rest[1..];
+                    continue;
+                }
+                let n_len = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+                let unit_len = rest[n_len..].find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len() - n_len);
+                let unit_len = if iso.is_some() { unit_len.min(1) } else { unit_len };
+                let n: f64 = rest[..n_len].parse().map_err(|_| invalid())?;
+                let unit_ms = match (iso.is_some(), in_time, &rest[n_len..n_len + unit_len]) {
+                    (false, _, "ms") => 1.0,
+                    (false, _, "s") | (true, true, "S") => 1_000.0,
+                    (false, _, "m") | (true, true, "M") => 60_000.0,
+                    (false, _, "h") | (true, true, "H") => 3_600_000.0,
+                    (false, _, "d") | (true, false, "D") => 86_400_000.0,
+                    (false, _, "w") | (true, false, "W") => 604_800_000.0,
+                    _ => return Err(invalid()),
+                };
+                total += n * unit_ms;
+                rest = &rest[n_len + unit_len..];
+            }
+            if total == 0.0 && s.trim_start_matches(['P', 'T']).is_empty() {
+                return Err(invalid());
+            }
+            sign * total
+        }
+        _ => return Err(invalid()),
+    };
+    TimeDelta::try_milliseconds(ms.round() as i64).ok_or_else(invalid)
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn path(src: &Value, expr: &str) -> Value {
+        apply(src, &json!({ "path": expr }), &Limits::default()).unwrap()
+    }
+
+    #[test]
+    fn test_datetime_functions() {
+        let src = json!({ "ts": "2024-03-30T23:30:00Z", "ms": 1_711_841_400_000i64 });
+        assert_eq!(path(&src, "add_duration(ts, '4h')"), json!("2024-03-31T03:30:00Z"));
+        assert_eq!(path(&src, "add_duration(ts, 'P1DT30M')"), json!("2024-04-01T00:00:00Z"));
+        assert_eq!(path(&src, "add_duration(ms, -90000)"), json!("2024-03-30T23:28:30Z"));
+        // Paris switches to CEST overnight
+        assert_eq!(path(&src, "to_tz(ts, 'Europe/Paris')"), json!("2024-03-31T00:30:00+01:00"));
+        assert_eq!(path(&src, "to_tz(add_duration(ts, '2h'), 'Europe/Paris')"), json!("2024-03-31T03:30:00+02:00"));
+        assert_eq!(path(&src, "format_ts(ts, '%d/%m %H:%M', '+05:30')"), json!("31/03 05:00"));
+        assert_eq!(path(&src, "parse_ts('31.03.2024 09:15', '%d.%m.%Y %H:%M', 'Europe/Paris')"), json!("2024-03-31T09:15:00+02:00"));
+        assert_eq!(path(&src, "parse_ts('Sat, 30 Mar 2024 23:30:00 +0000')"), json!("2024-03-30T23:30:00Z"));
+        assert!(path(&json!({}), "now()").as_str().unwrap().ends_with('Z'));
+
+        for bad in ["add_duration(ts, '4 parsecs')", "to_tz(ts, 'Mars/Olympus')", "frobnicate(ts)", "now(", "to_tz(ts)"] {
+            let err = apply(&src, &json!({ "path": bad }), &Limits::default()).unwrap_err();
+            assert_eq!(crate::errors::classify(&err), crate::errors::ErrorCode::Validation, "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_paths_and_templates() {
+        let src = json!({ "site": "Lyon", "due": "2024-05-01T08:00:00Z", "v": { "n": [1, 2] } });
+        assert_eq!(path(&src, "$.v.n.1"), json!(2));
+        assert_eq!(path(&json!({ "t": "21.5" }), "convert(t, 'degC', 'K')"), json!(294.65));
+        let out = apply(&json!({ "p": { "value": 2 } }), &json!({ "path": "p", "convert": { "from": "bar", "to": "kPa" } }), &Limits::default());
+        assert_eq!(out.unwrap(), json!({ "value": 200.0, "unit": "kPa" }));
+        assert_eq!(path(&src, "missing.key"), Value::Null);
+        let out = apply(&src, &json!({
+            "template": "{{site}}: {{ format_ts(due, '%H:%M', 'Europe/Paris') }} {{v}} {{unknown}}"
+        }), &Limits::default()).unwrap();
+        assert_eq!(out, json!("Lyon: 10:00 {\"n\":[1,2]} {{unknown}}"));
+        assert_eq!(path(&json!({ "body": "a\tb\n1\t2\n" }), "parse_csv(body, '\t')"), json!([{ "a": "1", "b": "2" }]));
+    }
+}
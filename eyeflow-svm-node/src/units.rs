@@ -0,0 +1,236 @@
+//! Unit conversion and sensor calibration
+//!
+//! Raw readings (ADC counts, vendor units) are turned into engineering
+//! units on the node instead of in LLM prompts.  A conversion spec:
+//!
+//!   {
+//!     "calibration": { "linear": { "gain": 0.0625, "offset": -40 } },
+//!     "from": "degC", "to": "degF",
+//!     "round": 1
+//!   }
+//!
+//! applies, in order (every key optional):
+//!
+//!   calibration   raw → engineering units
+//!                 linear  { "gain", "offset" }         gain·x + offset
+//!                         { "raw": [r0, r1], "eng": [e0, e1] }
+//!                                                      two-point, e.g. 4–20 mA
+//!                 poly    [c0, c1, c2, …]              c0 + c1·x + c2·x² + …
+//!   from / to     unit conversion within one dimension:
+//!                 temperature  degC (°C, C), degF (°F, F), K
+//!                 pressure     Pa, kPa, MPa, bar, mbar, psi, atm
+//!                 length       mm, cm, m, km, in, ft
+//!                 mass         g, kg, t, lb, oz
+//!                 volume       ml, l, m3, gal (US)
+//!                 flow         l/s, l/min, m3/h, gpm (US)
+//!   round         decimal places
+//!
+//! It applies to a number, a numeric string, an array of them or the
+//! `value` field of an object.  Used by TRANSFORM (`convert` operand and
+//! the convert(x, from, to) function, transform.rs) and by MCU bridge calls
+//! (`mcu.convert`, mcu.rs).  An unknown unit, a dimension mismatch or a
+//! non-numeric reading fails with VALIDATION.
+
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::errors::ExecError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Temperature,
+    Pressure,
+    Length,
+    Mass,
+    Volume,
+    Flow,
+}
+
+/// `unit` as (dimension, offset, mul, div): value in the base unit =
+/// (x + offset)·mul / div.  Temperature's base is °C, so that °C ↔ °F is
+/// exact in both directions.
+fn unit(name: &str) -> Result<(Dimension, f64, f64, f64)> {
+    use Dimension::*;
+    Ok(match name.trim() {
+        "degC" | "°C" | "C" | "celsius" => (Temperature, 0.0, 1.0, 1.0),
+        "degF" | "°F" | "F" | "fahrenheit" => (Temperature, -32.0, 5.0, 9.0),
+        "K" | "kelvin" => (Temperature, -273.15, 1.0, 1.0),
+        "Pa" => (Pressure, 0.0, 1.0, 1.0),
+        "kPa" => (Pressure, 0.0, 1e3, 1.0),
+        "MPa" => (Pressure, 0.0, 1e6, 1.0),
+        "bar" => (Pressure, 0.0, 1e5, 1.0),
+        "mbar" | "hPa" => (Pressure, 0.0, 100.0, 1.0),
+        "psi" => (Pressure, 0.0, 6_894.757_293_168, 1.0),
+        "atm" => (Pressure, 0.0, 101_325.0, 1.0),
+        "mm" => (Length, 0.0, 1e-3, 1.0),
+        "cm" => (Length, 0.0, 1e-2, 1.0),
+        "m" => (Length, 0.0, 1.0, 1.0),
+        "km" => (Length, 0.0, 1e3, 1.0),
+        "in" => (Length, 0.0, 0.0254, 1.0),
+        "ft" => (Length, 0.0, 0.3048, 1.0),
+        "g" => (Mass, 0.0, 1e-3, 1.0),
+        "kg" => (Mass, 0.0, 1.0, 1.0),
+        "t" => (Mass, 0.0, 1e3, 1.0),
+        "lb" => (Mass, 0.0, 0.453_592_37, 1.0),
+        "oz" => (Mass, 0.0, 0.028_349_523_125, 1.0),
+        "ml" => (Volume, 0.0, 1e-3, 1.0),
+        "l" | "L" => (Volume, 0.0, 1.0, 1.0),
+        "m3" | "m³" => (Volume, 0.0, 1e3, 1.0),
+        "gal" => (Volume, 0.0, 3.785_411_784, 1.0),
+        "l/s" => (Flow, 0.0, 60.0, 1.0),
+        "l/min" => (Flow, 0.0, 1.0, 1.0),
+        "m3/h" | "m³/h" => (Flow, 0.0, 1e3, 60.0),
+        "gpm" => (Flow, 0.0, 3.785_411_784, 1.0),
+        other => return Err(ExecError::validation(format!("unknown unit '{other}'"))),
+    })
+}
+
+/// Convert `x` between two units of the same dimension.
+pub fn convert_unit(x: f64, from: &str, to: &str) -> Result<f64> {
+    let (from_dim, from_offset, from_mul, from_div) = unit(from)?;
+    let (to_dim, to_offset, to_mul, to_div) = unit(to)?;
+    if from_dim != to_dim {
+        return Err(ExecError::validation(format!("cannot convert {from} ({from_dim:?}) to {to} ({to_dim:?})")));
+    }
+    let base = (x + from_offset) * from_mul / from_div;
+    Ok(base * to_div / to_mul - to_offset)
+}
+
+// ── Conversion specs ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+enum Calibration {
+    Linear { gain: f64, offset: f64 },
+    Poly(Vec<f64>),
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Conversion {
+    calibration: Option<Calibration>,
+    units: Option<(String, String)>,
+    round: Option<i32>,
+}
+
+impl Conversion {
+    pub fn from_json(spec: &Value) -> Result<Self> {
+        let invalid = |msg: &str| ExecError::validation(format!("conversion {spec}: {msg}"));
+        let numbers = |v: &Value| -> Option<Vec<f64>> { v.as_array()?.iter().map(Value::as_f64).collect() };
+        let calibration = match spec.get("calibration") {
+            None | Some(Value::Null) => None,
+            Some(cal) => Some(if let Some(linear) = cal.get("linear") {
+                match (linear.get("raw").and_then(numbers), linear.get("eng").and_then(numbers)) {
+                    (Some(raw), Some(eng)) if raw.len() == 2 && eng.len() == 2 && raw[0] != raw[1] => {
+                        let gain = (eng[1] - eng[0]) / (raw[1] - raw[0]);
+                        Calibration::Linear { gain, offset: eng[0] - gain * raw[0] }
+                    }
+                    (None, None) => Calibration::Linear {
+                        gain: linear.get("gain").map_or(Some(1.0), Value::as_f64).ok_or_else(|| invalid("gain must be a number"))?,
+                        offset: linear.get("offset").map_or(Some(0.0), Value::as_f64).ok_or_else(|| invalid("offset must be a number"))?,
+                    },
+                    _ => return Err(invalid("linear raw / eng must be two distinct points")),
+                }
+            } else if let Some(poly) = cal.get("poly") {
+                Calibration::Poly(numbers(poly).filter(|c| !c.is_empty()).ok_or_else(|| invalid("poly must be a non-empty array of numbers"))?)
+            } else {
+                return Err(invalid("calibration must be linear or poly"));
+            }),
+        };
+        let units = match (spec.get("from").and_then(Value::as_str), spec.get("to").and_then(Value::as_str)) {
+            (Some(from), Some(to)) => {
+                // Fail on bad units when the spec is read, not per reading
+                convert_unit(0.0, from, to)?;
+                Some((from.to_owned(), to.to_owned()))
+            }
+            (None, None) => None,
+            _ => return Err(invalid("from and to go together")),
+        };
+        let round = match spec.get("round") {
+            None | Some(Value::Null) => None,
+            Some(r) => Some(r.as_u64().filter(|r| *r <= 15).ok_or_else(|| invalid("round must be 0..=15"))? as i32),
+        };
+        Ok(Self { calibration, units, round })
+    }
+
+    pub fn apply_f64(&self, x: f64) -> Result<f64> {
+        let mut y = match &self.calibration {
+            None => x,
+            Some(Calibration::Linear { gain, offset }) => gain * x + offset,
+            // Horner
+            Some(Calibration::Poly(c)) => c.iter().rev().fold(0.0, |acc, c| acc * x + c),
+        };
+        if let Some((from, to)) = &self.units {
+            y = convert_unit(y, from, to)?;
+        }
+        if let Some(places) = self.round {
+            let p = 10f64.powi(places);
+            y = (y * p).round() / p;
+        }
+        Ok(y)
+    }
+
+    /// Convert a number, numeric string, array or `{ "value": … }` object.
+    pub fn apply(&self, value: &Value) -> Result<Value> {
+        match value {
+            Value::Array(items) => items.iter().map(|v| self.apply(v)).collect::<Result<_>>().map(Value::Array),
+            Value::Object(obj) if obj.contains_key("value") => {
+                let mut obj = obj.clone();
+                let converted = self.apply(&obj["value"])?;
+                obj.insert("value".into(), converted);
+                if let Some((_, to)) = &self.units {
+                    obj.insert("unit".into(), Value::String(to.clone()));
+                }
+                Ok(Value::Object(obj))
+            }
+            _ => {
+                let x = match value {
+                    Value::Number(n) => n.as_f64(),
+                    Value::String(s) => s.trim().parse().ok(),
+                    _ => None,
+                }
+                .ok_or_else(|| ExecError::validation(format!("not a numeric reading: {value}")))?;
+                let y = self.apply_f64(x)?;
+                serde_json::Number::from_f64(y)
+                    .map(Value::Number)
+                    .ok_or_else(|| ExecError::validation(format!("conversion of {x} is not finite")))
+            }
+        }
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn convert(spec: Value, value: Value) -> Value {
+        Conversion::from_json(&spec).unwrap().apply(&value).unwrap()
+    }
+
+    #[test]
+    fn test_units_and_calibration() {
+        assert_eq!(convert(json!({ "from": "°C", "to": "degF" }), json!(100)), json!(212.0));
+        assert_eq!(convert(json!({ "from": "degF", "to": "K", "round": 2 }), json!("32")), json!(273.15));
+        assert_eq!(convert(json!({ "from": "bar", "to": "psi", "round": 3 }), json!([1, 2.5])), json!([14.504, 36.259]));
+        assert_eq!(convert(json!({ "from": "m3/h", "to": "l/min" }), json!(6)), json!(100.0));
+
+        // 12-bit ADC on a 4–20 mA loop: 819 → 0 bar, 4095 → 10 bar, shown in psi
+        let loop_4_20 = json!({
+            "calibration": { "linear": { "raw": [819, 4095], "eng": [0, 10] } },
+            "from": "bar", "to": "psi", "round": 1,
+        });
+        assert_eq!(convert(loop_4_20, json!({ "value": 2457, "port": "ttyACM0" })),
+            json!({ "value": 72.5, "port": "ttyACM0", "unit": "psi" }));
+        // Thermistor fit: 0.5 + 0.1·x + 0.001·x²
+        assert_eq!(convert(json!({ "calibration": { "poly": [0.5, 0.1, 0.001] } }), json!(100)), json!(20.5));
+        assert_eq!(convert(json!({ "calibration": { "linear": { "gain": 0.0625, "offset": -40 } } }), json!(1024)), json!(24.0));
+
+        for bad in [json!({ "from": "bar", "to": "degC" }), json!({ "from": "furlong", "to": "m" }),
+                    json!({ "from": "bar" }), json!({ "calibration": { "poly": [] } })] {
+            let err = Conversion::from_json(&bad).unwrap_err();
+            assert_eq!(crate::errors::classify(&err), crate::errors::ErrorCode::Validation, "{bad}");
+        }
+        assert!(Conversion::default().apply(&json!("n/a")).is_err());
+    }
+}
@@ -0,0 +1,627 @@
+//! Self-update — signed releases, idle-window swap, automatic rollback
+//!
+//! With SVM_UPDATE=true central may push a new node binary:
+//!
+//!   { "type": "RELEASE",
+//!     "signedPayload": "<manifest JSON text>",
+//!     "signature":     "<base64 Ed25519 over the UTF-8 bytes of signedPayload>" }
+//!
+//!   manifest = { "version": "0.9.0", "issuedAt": <epoch ms>,
+//!                "artifacts": { "x86_64-linux":  { "url", "sha256", "size" },
+//!                               "aarch64-linux": { … } } }
+//!
+//! The key is SVM_UPDATE_PUBLIC_KEY (SPKI PEM or 64 hex chars), else
+//! SVM_CONFIG_OPS_PUBLIC_KEY; without either the node refuses to start with
+//! SVM_UPDATE set — an unsigned binary is never run.  As for CONFIG_UPDATE,
+//! `issuedAt` must grow, so a captured manifest cannot be replayed.
+//!
+//!   stage      the artifact for this target is downloaded to `<binary>.new`
+//!              (at most SVM_UPDATE_MAX_BYTES, default 256 MiB), its sha256
+//!              checked and `<binary>.new version` must print the version
+//!   apply      once no slice has run for SVM_UPDATE_IDLE_SECS (default 30),
+//!              the running binary moves to `<binary>.prev`, the new one
+//!              takes its place and the node drains and restarts:
+//!              SVM_UPDATE_RESTART=exec (default) re-executes it in place,
+//!              "systemd" exits with 75 for the unit's Restart= to pick up
+//!   probation  the new version must keep /ready for SVM_UPDATE_HEALTHY_SECS
+//!              (default 30) in a row within SVM_UPDATE_PROBATION_SECS
+//!              (default 300), and must not crash more than
+//!              SVM_UPDATE_MAX_BOOTS (default 3) starts in a row
+//!   rollback   otherwise: `<binary>.prev` is restored and restarted, and
+//!              the failed version is refused from then on
+//!
+//! The phase lives in `<binary>.update.json`, so a crash loop is caught by
+//! the next start.  SVM_UPDATE_BINARY_PATH overrides the running executable
+//! (wrapper layouts).  Progress goes to central as UPDATE_STATUS frames —
+//! { version, status: STAGED | REJECTED | APPLYING | CONFIRMED | ROLLED_BACK,
+//! error } — each one also appended to the audit chain as NODE_UPDATE.
+//!
+//! /metrics: eyeflow_update_staged, eyeflow_update_trial and
+//! eyeflow_update_rollbacks_total.
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+use crate::config::Config;
+use crate::errors::{ErrorCode, ExecError};
+use crate::health::{HealthState, MetricsCollector};
+
+/// Exit status asking systemd to restart the unit (EX_TEMPFAIL).
+pub const EXIT_RESTART: i32 = 75;
+
+/// Artifact key of this build, e.g. "x86_64-linux".
+pub fn target() -> String {
+    format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
+/// A verified release manifest, narrowed to this target's artifact.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub version: String,
+    pub issued_at: i64,
+    pub url: String,
+    pub sha256: String,
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Phase {
+    /// Running a confirmed (or the original) binary
+    #[default]
+    Stable,
+    /// Running a new version that has not proven healthy yet
+    Trial,
+}
+
+/// Persisted in `<binary>.update.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct State {
+    phase: Phase,
+    /// Version on trial
+    version: Option<String>,
+    /// Version in `<binary>.prev`
+    previous: Option<String>,
+    /// Starts of the version on trial
+    boots: u32,
+    /// `issuedAt` of the last accepted manifest
+    issued_at: i64,
+    /// Versions rolled back — never staged again
+    rejected: Vec<String>,
+}
+
+/// Outcome of one health sample during probation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Wait,
+    Confirm,
+    RollBack,
+}
+
+/// Health samples of a version on trial.
+pub struct Probation {
+    deadline: Instant,
+    healthy_for: Duration,
+    healthy_since: Option<Instant>,
+}
+
+impl Probation {
+    pub fn new(start: Instant, window: Duration, healthy_for: Duration) -> Self {
+        Self { deadline: start + window, healthy_for, healthy_since: None }
+    }
+
+    pub fn sample(&mut self, now: Instant, healthy: bool) -> Verdict {
+        self.healthy_since = if healthy { self.healthy_since.or(Some(now)) } else { None };
+        match self.healthy_since {
+            Some(since) if now.duration_since(since) >= self.healthy_for => Verdict::Confirm,
+            _ if now >= self.deadline => Verdict::RollBack,
+            _ => Verdict::Wait,
+        }
+    }
+}
+
+pub struct Updater {
+    key: VerifyingKey,
+    /// Version of the running binary
+    current: String,
+    binary: PathBuf,
+    http: reqwest::Client,
+    max_bytes: u64,
+    idle: Duration,
+    probation: Duration,
+    healthy_for: Duration,
+    max_boots: u32,
+    systemd: bool,
+    state: Mutex<State>,
+    /// Verified release waiting in `<binary>.new` for an idle window
+    staged: Mutex<Option<Release>>,
+    staging: AtomicBool,
+    /// UPDATE_STATUS payloads not sent to central yet
+    reports: Mutex<Vec<Value>>,
+    restart: watch::Sender<bool>,
+    rollbacks: AtomicU64,
+}
+
+impl Updater {
+    pub fn new(key: VerifyingKey, current: &str, binary: PathBuf, config: &Config) -> Arc<Self> {
+        let state = std::fs::read(sibling(&binary, "update.json")).ok()
+            .and_then(|b| serde_json::from_slice(&b).ok())
+            .unwrap_or_default();
+        Arc::new(Self {
+            key,
+            current: current.to_owned(),
+            binary,
+            http: reqwest::Client::new(),
+            max_bytes: config.update_max_bytes,
+            idle: Duration::from_secs(config.update_idle_secs),
+            probation: Duration::from_secs(config.update_probation_secs),
+            healthy_for: Duration::from_secs(config.update_healthy_secs),
+            max_boots: config.update_max_boots.max(1),
+            systemd: config.update_restart == "systemd",
+            state: Mutex::new(state),
+            staged: Mutex::new(None),
+            staging: AtomicBool::new(false),
+            reports: Mutex::new(Vec::new()),
+            restart: watch::channel(false).0,
+            rollbacks: AtomicU64::new(0),
+        })
+    }
+
+    /// None unless SVM_UPDATE is set; an error without a release key.
+    pub fn from_config(config: &Config) -> Result<Option<Arc<Self>>> {
+        if !config.update_enabled {
+            return Ok(None);
+        }
+        let key = config.update_public_key.as_deref()
+            .or(config.config_ops_public_key.as_deref())
+            .ok_or_else(|| anyhow!("SVM_UPDATE needs SVM_UPDATE_PUBLIC_KEY or SVM_CONFIG_OPS_PUBLIC_KEY"))?;
+        let key = crate::config_sig::verifying_key_from_str(key)?;
+        if !matches!(config.update_restart.as_str(), "exec" | "systemd") {
+            return Err(anyhow!("SVM_UPDATE_RESTART must be \"exec\" or \"systemd\", not {:?}", config.update_restart));
+        }
+        let binary = match &config.update_binary_path {
+            Some(path) => PathBuf::from(path),
+            None => std::env::current_exe()?,
+        };
+        Ok(Some(Self::new(key, env!("CARGO_PKG_VERSION"), binary, config)))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, state: &State) -> Result<()> {
+        let path = sibling(&self.binary, "update.json");
+        let tmp = sibling(&self.binary, "update.json.part");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(state)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Queue an UPDATE_STATUS payload for central.
+    pub fn report(&self, version: &str, status: &str, error: Option<String>) {
+        let mut payload = json!({ "version": version, "status": status, "target": target() });
+        if let Some(error) = error {
+            payload["error"] = json!(error);
+        }
+        self.reports.lock().unwrap_or_else(|e| e.into_inner()).push(payload);
+    }
+
+    pub fn take_reports(&self) -> Vec<Value> {
+        std::mem::take(&mut *self.reports.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// REGISTER `update` — the phase and versions involved.
+    pub fn status(&self) -> Value {
+        let state = self.lock();
+        json!({
+            "phase": if state.phase == Phase::Trial { "trial" } else { "stable" },
+            "version": self.current,
+            "previous": state.previous,
+            "staged": self.staged_version(),
+        })
+    }
+
+    // ── Start-up ──────────────────────────────────────────────────────────────
+
+    /// Count a start of the version on trial; rolls it back once it has
+    /// started more than SVM_UPDATE_MAX_BOOTS times.  Returns true when the
+    /// caller must restart into the restored binary.
+    pub fn boot(&self) -> Result<bool> {
+        let mut state = self.lock().clone();
+        if state.phase != Phase::Trial {
+            return Ok(false);
+        }
+        if state.version.as_deref() != Some(self.current.as_str()) {
+            // `<binary>.prev` was put back by hand: the trial is over
+            warn!("[Update] expected v{} on trial, running v{} — trial abandoned", state.version.as_deref().unwrap_or("?"), self.current);
+            state.phase = Phase::Stable;
+            self.save(&state)?;
+            *self.lock() = state;
+            return Ok(false);
+        }
+        state.boots += 1;
+        self.save(&state)?;
+        let boots = state.boots;
+        *self.lock() = state;
+        if boots > self.max_boots {
+            self.roll_back(&format!("v{} started {boots} times without proving healthy", self.current))?;
+            return Ok(true);
+        }
+        info!("[Update] v{} on trial (start {boots} of {})", self.current, self.max_boots);
+        Ok(false)
+    }
+
+    pub fn in_trial(&self) -> bool {
+        self.lock().phase == Phase::Trial
+    }
+
+    // ── Release manifest ──────────────────────────────────────────────────────
+
+    /// The release of a RELEASE `frame` for this target.
+    pub fn verify(&self, frame: &Value) -> Result<Release> {
+        let invalid = |msg: String| -> anyhow::Error { ExecError::new(ErrorCode::Auth, format!("RELEASE: {msg}")).into() };
+        let text = frame.get("signedPayload").and_then(Value::as_str)
+            .ok_or_else(|| invalid("unsigned (no signedPayload)".into()))?;
+        let signature = frame.get("signature").and_then(Value::as_str)
+            .and_then(|s| B64.decode(s).ok())
+            .and_then(|b| Signature::from_slice(&b).ok())
+            .ok_or_else(|| invalid("missing or malformed signature".into()))?;
+        self.key.verify(text.as_bytes(), &signature)
+            .map_err(|_| invalid("signature does not match the release key".into()))?;
+
+        let manifest: Value = serde_json::from_str(text)?;
+        let version = manifest.get("version").and_then(Value::as_str)
+            .ok_or_else(|| ExecError::validation("RELEASE manifest has no version"))?;
+        let issued_at = manifest.get("issuedAt").and_then(Value::as_i64)
+            .ok_or_else(|| ExecError::validation("RELEASE manifest has no issuedAt"))?;
+        let artifact = manifest.pointer(&format!("/artifacts/{}", target()))
+            .ok_or_else(|| ExecError::new(ErrorCode::Unsupported, format!("release v{version} has no {} artifact", target())))?;
+        let url = artifact.get("url").and_then(Value::as_str)
+            .ok_or_else(|| ExecError::validation(format!("release v{version}: artifact has no url")))?;
+        let sha256 = artifact.get("sha256").and_then(Value::as_str)
+            .map(|s| s.trim_start_matches("sha256:").to_ascii_lowercase())
+            .filter(|s| s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| ExecError::validation(format!("release v{version}: artifact sha256 must be 64 hex chars")))?;
+
+        let mut state = self.lock();
+        if issued_at <= state.issued_at {
+            return Err(invalid(format!("replayed manifest (issuedAt {issued_at} <= {})", state.issued_at)));
+        }
+        if version == self.current {
+            return Err(ExecError::validation(format!("v{version} is already running")));
+        }
+        if state.rejected.iter().any(|v| v == version) {
+            return Err(ExecError::validation(format!("v{version} was rolled back on this node")));
+        }
+        state.issued_at = issued_at;
+        let snapshot = state.clone();
+        drop(state);
+        self.save(&snapshot)?;
+        Ok(Release {
+            version: version.to_owned(),
+            issued_at,
+            url: url.to_owned(),
+            sha256,
+            size: artifact.get("size").and_then(Value::as_u64),
+        })
+    }
+
+    // ── Staging ───────────────────────────────────────────────────────────────
+
+    /// Download `release` to `<binary>.new` and keep it for the next idle
+    /// window; the outcome is reported as STAGED or REJECTED.
+    pub async fn stage(self: Arc<Self>, release: Release) {
+        if self.staging.swap(true, Ordering::SeqCst) {
+            self.report(&release.version, "REJECTED", Some("another release is being staged".into()));
+            return;
+        }
+        let result = match self.download(&release).await {
+            Ok(()) => self.check_staged(&release).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => {
+                info!("[Update] v{} staged — applied after {:?} without a slice", release.version, self.idle);
+                self.report(&release.version, "STAGED", None);
+                *self.staged.lock().unwrap_or_else(|e| e.into_inner()) = Some(release);
+            }
+            Err(e) => {
+                warn!("[Update] v{} rejected: {e}", release.version);
+                let _ = std::fs::remove_file(sibling(&self.binary, "new"));
+                self.report(&release.version, "REJECTED", Some(e.to_string()));
+            }
+        }
+        self.staging.store(false, Ordering::SeqCst);
+    }
+
+    async fn download(&self, release: &Release) -> Result<()> {
+        let what = format!("release v{}", release.version);
+        let too_large = || ExecError::new(ErrorCode::Quota, format!("{what}: larger than SVM_UPDATE_MAX_BYTES={}", self.max_bytes));
+        if release.size.is_some_and(|size| size > self.max_bytes) {
+            return Err(too_large().into());
+        }
+        let mut resp = self.http.get(&release.url).send().await?;
+        if !resp.status().is_success() {
+            return Err(ExecError::http(what, resp.status()));
+        }
+        if resp.content_length().is_some_and(|len| len > self.max_bytes) {
+            return Err(too_large().into());
+        }
+        let mut file = tokio::fs::File::create(sibling(&self.binary, "new")).await?;
+        let mut written = 0u64;
+        while let Some(chunk) = resp.chunk().await? {
+            written += chunk.len() as u64;
+            if written > self.max_bytes {
+                return Err(too_large().into());
+            }
+            file.write_all(&chunk).await?;
+        }
+        file.sync_all().await?;
+        Ok(())
+    }
+
+    /// `<binary>.new` matches `release` and runs on this machine.
+    async fn check_staged(&self, release: &Release) -> Result<()> {
+        let path = sibling(&self.binary, "new");
+        let bytes = tokio::fs::read(&path).await?;
+        let got = hex::encode(Sha256::digest(&bytes));
+        if got != release.sha256 {
+            return Err(ExecError::validation(format!("release v{} has sha256 {got}", release.version)));
+        }
+        if release.size.is_some_and(|size| size != bytes.len() as u64) {
+            return Err(ExecError::validation(format!("release v{}: {} bytes, manifest says {:?}", release.version, bytes.len(), release.size)));
+        }
+        drop(bytes);
+        #[cfg(unix)]
+        std::fs::set_permissions(&path, std::os::unix::fs::PermissionsExt::from_mode(0o755))?;
+
+        let probe = tokio::process::Command::new(&path)
+            .arg("version")
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(Duration::from_secs(10), probe).await
+            .map_err(|_| ExecError::new(ErrorCode::Timeout, format!("release v{}: `version` did not answer", release.version)))?
+            .map_err(|e| ExecError::new(ErrorCode::Unsupported, format!("release v{} does not run here: {e}", release.version)))?;
+        let printed = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() || !printed.split_whitespace().any(|w| w.trim_start_matches('v') == release.version) {
+            return Err(ExecError::validation(format!("release v{}: `version` printed {:?}", release.version, printed.trim())));
+        }
+        Ok(())
+    }
+
+    pub fn staged_version(&self) -> Option<String> {
+        self.staged.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map(|r| r.version.clone())
+    }
+
+    /// How long the node must go without a slice before applying.
+    pub fn idle_window(&self) -> Duration {
+        self.idle
+    }
+
+    // ── Swap / rollback ───────────────────────────────────────────────────────
+
+    /// Put the staged binary in place and ask for a restart into it.
+    pub fn apply(&self) -> Result<()> {
+        let Some(release) = self.staged.lock().unwrap_or_else(|e| e.into_inner()).take() else { return Ok(()) };
+        let (new, prev) = (sibling(&self.binary, "new"), sibling(&self.binary, "prev"));
+        std::fs::rename(&self.binary, &prev)
+            .map_err(|e| anyhow!("moving {} aside: {e}", self.binary.display()))?;
+        if let Err(e) = std::fs::rename(&new, &self.binary) {
+            std::fs::rename(&prev, &self.binary)?;
+            return Err(anyhow!("installing v{}: {e}", release.version));
+        }
+        let mut state = self.lock().clone();
+        state.phase = Phase::Trial;
+        state.version = Some(release.version.clone());
+        state.previous = Some(self.current.clone());
+        state.boots = 0;
+        self.save(&state)?;
+        *self.lock() = state;
+        info!("[Update] v{} installed over v{} — restarting", release.version, self.current);
+        self.report(&release.version, "APPLYING", None);
+        self.restart.send_replace(true);
+        Ok(())
+    }
+
+    /// The version on trial proved healthy: keep it.
+    pub fn confirm(&self) -> Result<()> {
+        let mut state = self.lock().clone();
+        if state.phase != Phase::Trial {
+            return Ok(());
+        }
+        state.phase = Phase::Stable;
+        state.boots = 0;
+        self.save(&state)?;
+        *self.lock() = state;
+        info!("[Update] v{} confirmed", self.current);
+        self.report(&self.current, "CONFIRMED", None);
+        Ok(())
+    }
+
+    /// Restore `<binary>.prev` and ask for a restart into it.
+    pub fn roll_back(&self, reason: &str) -> Result<()> {
+        let mut state = self.lock().clone();
+        let failed = state.version.clone().unwrap_or_else(|| self.current.clone());
+        error!("[Update] rolling back v{failed}: {reason}");
+        std::fs::rename(sibling(&self.binary, "prev"), &self.binary)
+            .map_err(|e| anyhow!("restoring {}.prev: {e}", self.binary.display()))?;
+        state.phase = Phase::Stable;
+        state.boots = 0;
+        if !state.rejected.contains(&failed) {
+            state.rejected.push(failed.clone());
+        }
+        state.version = state.previous.take();
+        self.save(&state)?;
+        *self.lock() = state;
+        self.rollbacks.fetch_add(1, Ordering::Relaxed);
+        self.report(&failed, "ROLLED_BACK", Some(reason.to_owned()));
+        self.restart.send_replace(true);
+        Ok(())
+    }
+
+    // ── Restart ───────────────────────────────────────────────────────────────
+
+    pub fn restart_requested(&self) -> watch::Receiver<bool> {
+        self.restart.subscribe()
+    }
+
+    pub fn restart_pending(&self) -> bool {
+        *self.restart.borrow()
+    }
+
+    /// Start the binary now in place; returns only on failure.
+    pub fn restart(&self) -> Result<()> {
+        if self.systemd {
+            info!("[Update] exiting with {EXIT_RESTART} for systemd to restart the node");
+            std::process::exit(EXIT_RESTART);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            info!("[Update] re-executing {}", self.binary.display());
+            let err = std::process::Command::new(&self.binary).args(std::env::args_os().skip(1)).exec();
+            Err(anyhow!("exec {}: {err}", self.binary.display()))
+        }
+        #[cfg(not(unix))]
+        Err(anyhow!("SVM_UPDATE_RESTART=exec needs a Unix host — use \"systemd\""))
+    }
+}
+
+/// `<binary>.<suffix>`
+fn sibling(binary: &Path, suffix: &str) -> PathBuf {
+    let mut name = binary.as_os_str().to_owned();
+    name.push(format!(".{suffix}"));
+    PathBuf::from(name)
+}
+
+impl MetricsCollector for Updater {
+    fn prometheus(&self, node_id: &str) -> String {
+        format!(
+            "# HELP eyeflow_update_staged 1 while a verified release waits for an idle window\n\
+             # TYPE eyeflow_update_staged gauge\n\
+             eyeflow_update_staged{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_update_trial 1 while the running version has not proven healthy\n\
+             # TYPE eyeflow_update_trial gauge\n\
+             eyeflow_update_trial{{node_id=\"{node_id}\"}} {}\n\
+             # HELP eyeflow_update_rollbacks_total Releases rolled back since start\n\
+             # TYPE eyeflow_update_rollbacks_total counter\n\
+             eyeflow_update_rollbacks_total{{node_id=\"{node_id}\"}} {}\n",
+            u8::from(self.staged_version().is_some()),
+            u8::from(self.in_trial()),
+            self.rollbacks.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// ── Probation task ────────────────────────────────────────────────────────────
+
+/// While a new version is on trial, sample /ready every 5 s: confirm it or
+/// roll it back (see `Probation`).
+pub async fn run_probation(updater: Arc<Updater>, health: Arc<HealthState>) {
+    if !updater.in_trial() {
+        return;
+    }
+    let mut probation = Probation::new(Instant::now(), updater.probation, updater.healthy_for);
+    let mut ticker = tokio::time::interval(Duration::from_secs(5));
+    loop {
+        ticker.tick().await;
+        let result = match probation.sample(Instant::now(), health.is_healthy()) {
+            Verdict::Wait => continue,
+            Verdict::Confirm => updater.confirm(),
+            Verdict::RollBack => updater.roll_back(&format!(
+                "not ready for {:?} in a row within {:?}", updater.healthy_for, updater.probation,
+            )),
+        };
+        if let Err(e) = result {
+            error!("[Update] {e}");
+        }
+        return;
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::classify;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    #[tokio::test]
+    async fn test_release_swap_and_rollback() {
+        let dir = std::env::temp_dir().join(format!("eyeflow-update-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let binary = dir.join("eyeflow-svm-node");
+        std::fs::write(&binary, "#!/bin/sh\necho eyeflow-svm-node 1.0.0\n").unwrap();
+        let key = SigningKey::from_bytes(&[9u8; 32]);
+        let config = Config { update_max_boots: 2, ..Config::from_env() };
+        let updater = Updater::new(key.verifying_key(), "1.0.0", binary.clone(), &config);
+
+        let new_binary = "#!/bin/sh\necho eyeflow-svm-node 1.1.0\n";
+        let manifest = |version: &str, issued_at: i64| json!({
+            "version": version,
+            "issuedAt": issued_at,
+            "artifacts": { target(): { "url": "http://central/node", "sha256": hex::encode(Sha256::digest(new_binary)) } },
+        }).to_string();
+        let frame = |text: &str, key: &SigningKey| json!({
+            "type": "RELEASE",
+            "signedPayload": text,
+            "signature": B64.encode(key.sign(text.as_bytes()).to_bytes()),
+        });
+
+        let forged = updater.verify(&frame(&manifest("1.1.0", 10), &SigningKey::from_bytes(&[1u8; 32]))).unwrap_err();
+        assert_eq!(classify(&forged), ErrorCode::Auth);
+        let release = updater.verify(&frame(&manifest("1.1.0", 10), &key)).unwrap();
+        assert_eq!(classify(&updater.verify(&frame(&manifest("1.1.0", 10), &key)).unwrap_err()), ErrorCode::Auth);
+
+        // Staged, installed, restarted on trial
+        std::fs::write(sibling(&binary, "new"), new_binary).unwrap();
+        updater.check_staged(&release).await.unwrap();
+        *updater.staged.lock().unwrap() = Some(release);
+        updater.apply().unwrap();
+        assert!(updater.restart_pending() && updater.in_trial());
+        assert_eq!(std::fs::read_to_string(&binary).unwrap(), new_binary);
+        let statuses: Vec<_> = updater.take_reports().into_iter().map(|r| r["status"].clone()).collect();
+        assert_eq!(statuses, vec![json!("APPLYING")]);
+
+        // v1.1.0 crashes on every start: rolled back on the third
+        let trial = Updater::new(key.verifying_key(), "1.1.0", binary.clone(), &config);
+        assert!(!trial.boot().unwrap() && !trial.boot().unwrap());
+        assert!(trial.boot().unwrap());
+        assert!(std::fs::read_to_string(&binary).unwrap().contains("1.0.0"));
+        assert_eq!(trial.take_reports()[0]["status"], "ROLLED_BACK");
+
+        // Back on 1.0.0, the failed version is refused
+        let restored = Updater::new(key.verifying_key(), "1.0.0", binary.clone(), &config);
+        assert!(!restored.boot().unwrap() && !restored.in_trial());
+        let err = restored.verify(&frame(&manifest("1.1.0", 20), &key)).unwrap_err();
+        assert!(err.to_string().contains("rolled back"), "{err}");
+        std::fs::remove_dir_all(&dir).ok();
+
+        // Probation: /ready must hold for healthy_for in a row
+        let t0 = Instant::now();
+        let mut p = Probation::new(t0, Duration::from_secs(60), Duration::from_secs(20));
+        assert_eq!(p.sample(t0 + Duration::from_secs(5), true), Verdict::Wait);
+        assert_eq!(p.sample(t0 + Duration::from_secs(10), false), Verdict::Wait);
+        assert_eq!(p.sample(t0 + Duration::from_secs(15), true), Verdict::Wait);
+        assert_eq!(p.sample(t0 + Duration::from_secs(35), true), Verdict::Confirm);
+        let mut p = Probation::new(t0, Duration::from_secs(60), Duration::from_secs(20));
+        assert_eq!(p.sample(t0 + Duration::from_secs(50), true), Verdict::Wait);
+        assert_eq!(p.sample(t0 + Duration::from_secs(60), false), Verdict::RollBack);
+    }
+}
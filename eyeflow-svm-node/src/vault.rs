@@ -1,24 +1,62 @@
-/// VaultClient — spec §6.1 + §13.2
-///
-/// HashiCorp Vault-compatible secret injection for Rust SVM edge nodes.
-///
-/// Secrets are NEVER stored in the LLM-IR. They are referenced by path
-/// (e.g. "sap/api_key") in `dynamic_slots` and resolved here at runtime,
-/// then immediately cleared from memory after the instruction completes.
-///
-/// Resolution strategy (in order):
-///   1. HashiCorp Vault HTTP API (KV v2 at VAULT_ADDR / VAULT_TOKEN)
-///   2. Environment variables (VAULT_SECRET_<UPPER_SNAKE> pattern)
-///   3. Raw env key (e.g. "OPENAI_API_KEY" directly)
-///
-/// TTL cache: 30 seconds (avoids hammering Vault on every instruction).
+//! VaultClient — spec §6.1 + §13.2
+//!
+//! HashiCorp Vault-compatible secret injection for Rust SVM edge nodes.
+//!
+//! Secrets are NEVER stored in the LLM-IR. They are referenced by path
+//! (e.g. "sap/api_key") in `dynamic_slots` and resolved here at runtime,
+//! then immediately cleared from memory after the instruction completes.
+//!
+//! Resolution strategy (in order):
+//!   1. HashiCorp Vault HTTP API (KV at VAULT_ADDR / VAULT_TOKEN)
+//!   2. Environment variables (VAULT_SECRET_<UPPER_SNAKE> pattern)
+//!   3. Raw env key (e.g. "OPENAI_API_KEY" directly)
+//!
+//! Paths are `mount/path[#field]`.  The mount is the longest VAULT_KV_MOUNTS
+//! entry the path starts with, else its first segment ("secret" for a bare
+//! key).  VAULT_KV_MOUNTS lists `mount[=1|2]` (e.g. "secret,legacy=1,
+//! teams/ot/kv=2"); unlisted mounts use VAULT_KV_VERSION (default 2):
+//!
+//!   KV v2   GET /v1/<mount>/data/<path>  → data.data
+//!   KV v1   GET /v1/<mount>/<path>       → data
+//!
+//! `#field` picks the field; without it the field named like the last path
+//! segment is used, else the first one.
+//!
+//! TTL cache: 30 seconds (avoids hammering Vault on every instruction).
+//! Lookups take `&self` and the cache sits behind an RwLock, so concurrent
+//! instructions share one client (`Arc<VaultClient>`) and cache hits never
+//! wait on each other or on a Vault round trip in flight.  Misses are not
+//! coalesced: instructions missing the same path at once each fetch it.
+//! That is accepted — the prefetch below resolves a slice's paths once
+//! before it runs, and a duplicate KV read has no side effect.
+//! Before a slice runs, the SVM prefetches every Vault path it references
+//! (credentials_vault_path, vault dynamic slots) concurrently, so its
+//! instructions find them cached (SVM_VAULT_PREFETCH=false opts out).
+//!
+//! Each HashiCorp lookup reports reachability to HealthState: network,
+//! timeout and 5xx failures count as down, other answers (403, 404) as up.
+//!
+//! Degraded mode (SVM_VAULT_PREFAIL_AFTER=n, default 0 = off): after n
+//! unreachable lookups in a row, lookups stop waiting on Vault.  A path with
+//! no cached value and no env fallback then fails with VAULT_UNAVAILABLE, and
+//! the SVM fails instructions needing one before they run instead of calling
+//! the service without credentials.  One lookup goes through to Vault every
+//! SVM_VAULT_PROBE_SECS (default 30) to notice when it is back.
 
 use anyhow::{anyhow, Result};
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 use tracing::{debug, warn};
 
+use crate::chaos::{Chaos, Target};
+use crate::errors::{ErrorCode, ExecError};
+use crate::health::HealthState;
+
+/// Vault requests in flight during a prefetch.
+const PREFETCH_CONCURRENCY: usize = 8;
+
 // ── Cache entry ───────────────────────────────────────────────────────────────
 
 struct CacheEntry {
@@ -33,8 +71,19 @@ pub struct VaultClient {
     vault_addr: Option<String>,
     vault_token: Option<String>,
     vault_namespace: Option<String>,
-    cache: HashMap<String, CacheEntry>,
+    cache: RwLock<HashMap<String, CacheEntry>>,
     cache_ttl: Duration,
+    /// Injected lookup faults (SVM_CHAOS)
+    chaos: Option<Arc<Chaos>>,
+    /// Reachability sink for the health score
+    health: Option<Arc<HealthState>>,
+    /// Unreachable lookups in a row before degraded mode (0 = off)
+    prefail_after: u32,
+    probe_interval: Duration,
+    /// KV version of unlisted mounts (VAULT_KV_VERSION)
+    kv_version: u8,
+    /// Known mounts and their KV version, longest first (VAULT_KV_MOUNTS)
+    kv_mounts: Vec<(String, u8)>,
 }
 
 #[derive(Debug)]
@@ -50,14 +99,20 @@ pub enum SecretSource {
     RawEnvKey,
 }
 
-/// HashiCorp Vault KV v2 API response (subset)
+/// HashiCorp Vault KV API response (subset): `data` is the secret (v1) or
+/// wraps it in `data.data` (v2)
 #[derive(Deserialize)]
 struct VaultResponse {
-    data: VaultData,
+    data: serde_json::Map<String, serde_json::Value>,
 }
-#[derive(Deserialize)]
-struct VaultData {
-    data: HashMap<String, serde_json::Value>,
+
+/// Where a secret path lives.
+#[derive(Debug, PartialEq)]
+struct Location<'a> {
+    mount: &'a str,
+    path: &'a str,
+    version: u8,
+    field: Option<&'a str>,
 }
 
 impl VaultClient {
@@ -72,12 +127,19 @@ impl VaultClient {
             vault_addr,
             vault_token,
             vault_namespace,
-            cache: HashMap::new(),
+            cache: RwLock::new(HashMap::new()),
             cache_ttl: Duration::from_secs(30),
+            chaos: None,
+            health: None,
+            prefail_after: 0,
+            probe_interval: Duration::from_secs(30),
+            kv_version: 2,
+            kv_mounts: Vec::new(),
         }
     }
 
     /// Create a VaultClient from environment variables.
+    #[allow(dead_code)]
     pub fn from_env(http: reqwest::Client) -> Self {
         Self::new(
             http,
@@ -87,32 +149,137 @@ impl VaultClient {
         )
     }
 
+    /// Inject SVM_CHAOS `vault` faults into every lookup.
+    pub fn set_chaos(&mut self, chaos: Option<Arc<Chaos>>) {
+        self.chaos = chaos;
+    }
+
+    /// Report Vault reachability to `health`.
+    pub fn set_health(&mut self, health: Option<Arc<HealthState>>) {
+        self.health = health;
+    }
+
+    /// KV engine layout: `version` for unlisted mounts and `mounts` entries
+    /// `mount[=1|2]`; malformed entries are logged and skipped.
+    pub fn set_kv_layout(&mut self, version: u8, mounts: &[String]) {
+        self.kv_version = if version == 1 { 1 } else { 2 };
+        if version != 1 && version != 2 {
+            warn!("[Vault] VAULT_KV_VERSION must be 1 or 2, got {version} — using 2");
+        }
+        self.kv_mounts = mounts.iter().filter_map(|entry| {
+            let (mount, v) = match entry.split_once('=') {
+                Some((mount, v)) => (mount, v.trim().parse::<u8>().ok().filter(|v| *v == 1 || *v == 2)),
+                None => (entry.as_str(), Some(self.kv_version)),
+            };
+            let mount = mount.trim().trim_matches('/');
+            match v {
+                Some(v) if !mount.is_empty() => Some((mount.to_owned(), v)),
+                _ => {
+                    warn!("[Vault] ignoring VAULT_KV_MOUNTS entry '{entry}' (expected mount[=1|2])");
+                    None
+                }
+            }
+        }).collect();
+        self.kv_mounts.sort_by_key(|(mount, _)| std::cmp::Reverse(mount.len()));
+    }
+
+    /// Mount, path within it, KV version and field of `secret_path`.
+    fn locate<'a>(&'a self, secret_path: &'a str) -> Location<'a> {
+        let (path, field) = match secret_path.split_once('#') {
+            Some((path, field)) => (path, Some(field).filter(|f| !f.is_empty())),
+            None => (secret_path, None),
+        };
+        let path = path.trim_start_matches('/');
+        let known = self.kv_mounts.iter().find_map(|(mount, version)| {
+            path.strip_prefix(mount.as_str())
+                .and_then(|rest| rest.strip_prefix('/'))
+                .map(|rest| (mount.as_str(), rest, *version))
+        });
+        let (mount, path, version) = known.unwrap_or_else(|| match path.split_once('/') {
+            Some((mount, rest)) => (mount, rest, self.kv_version),
+            None => ("secret", path, self.kv_version),
+        });
+        Location { mount, path, version, field }
+    }
+
+    /// Degraded mode after `after` unreachable lookups (0 = off), probing
+    /// Vault every `probe_interval`.
+    pub fn set_prefail(&mut self, after: u32, probe_interval: Duration) {
+        self.prefail_after = after;
+        self.probe_interval = probe_interval;
+    }
+
+    /// Whether lookups currently skip Vault (degraded mode, between probes).
+    fn degraded(&self) -> bool {
+        if self.prefail_after == 0 || self.vault_addr.is_none() || self.vault_token.is_none() {
+            return false;
+        }
+        let Some(status) = self.health.as_ref().and_then(|h| h.vault_status()) else {
+            return false;
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        status.consecutive_failures >= self.prefail_after
+            && status.last_failure.is_some_and(|at| now.saturating_sub(at) < self.probe_interval.as_secs())
+    }
+
+    /// VAULT_UNAVAILABLE error if `path` cannot be resolved in degraded mode
+    /// (not cached, no env fallback).
+    pub fn unavailable(&self, path: &str) -> Option<anyhow::Error> {
+        if !self.degraded()
+            || self.cached(path).is_some()
+            || env_fallback(path).is_some()
+        {
+            return None;
+        }
+        Some(ExecError::new(
+            ErrorCode::VaultUnavailable,
+            format!("Vault unreachable — secret \"{path}\" has no cached value or env fallback"),
+        ).into())
+    }
+
     /// Fetch a secret by its vault path (e.g. "sap/api_key").
     ///
     /// The returned value is only valid for the duration of the instruction.
     /// The caller must not store it beyond the instruction's lifetime.
-    pub async fn fetch_secret(&mut self, path: &str) -> Result<SecretValue> {
+    pub async fn fetch_secret(&self, path: &str) -> Result<SecretValue> {
+        let injected = match self.chaos.clone() {
+            Some(chaos) => chaos.inject(Target::Vault, path).await,
+            None => Ok(()),
+        };
+        let cache_hit = self.cached(path).is_some();
+        let result = match injected {
+            Ok(()) => self.lookup(path).await,
+            Err(e) => Err(e),
+        };
+        crate::recording::note_vault_lookup(path, result.is_ok());
+        crate::secret_audit::note(path, result.as_ref().ok().map(|s| &s.source), cache_hit);
+        if let Ok(secret) = &result {
+            crate::taint::note_secret(&secret.value);
+        }
+        result
+    }
+
+    async fn lookup(&self, path: &str) -> Result<SecretValue> {
         // 1. Check TTL cache
-        if let Some(entry) = self.cache.get(path) {
-            if entry.expires_at > Instant::now() {
-                debug!("[Vault] cache hit for \"{path}\"");
-                return Ok(SecretValue {
-                    value: entry.value.clone(),
-                    source: SecretSource::HashiCorpVault,
-                });
-            } else {
-                self.cache.remove(path);
-            }
+        if let Some(value) = self.cached(path) {
+            debug!("[Vault] cache hit for \"{path}\"");
+            return Ok(SecretValue { value, source: SecretSource::HashiCorpVault });
         }
 
-        // 2. Try HashiCorp Vault HTTP API (KV v2)
-        if let (Some(addr), Some(token)) = (&self.vault_addr, &self.vault_token) {
-            match self.fetch_from_hashicorp(addr, token, path).await {
+        // 2. Try HashiCorp Vault HTTP API (KV v2), unless degraded
+        if let Some(e) = self.unavailable(path) {
+            return Err(e);
+        }
+        let degraded = self.degraded();
+        if let (Some(addr), Some(token), false) = (&self.vault_addr, &self.vault_token, degraded) {
+            let fetched = self.fetch_from_hashicorp(addr, token, path).await;
+            self.report_reachability(&fetched);
+            match fetched {
                 Ok(value) => {
-                    self.cache.insert(path.to_owned(), CacheEntry {
-                        value: value.clone(),
-                        expires_at: Instant::now() + self.cache_ttl,
-                    });
+                    self.remember(path, &value);
                     return Ok(SecretValue { value, source: SecretSource::HashiCorpVault });
                 }
                 Err(e) => {
@@ -124,30 +291,24 @@ impl VaultClient {
             }
         }
 
-        // 3. Try VAULT_SECRET_<UPPER_SNAKE> env var pattern
-        let env_key = path_to_env_key(path);
-        if let Ok(value) = std::env::var(&env_key) {
-            debug!("[Vault] using env var {env_key} for \"{path}\"");
-            return Ok(SecretValue { value, source: SecretSource::EnvVar });
-        }
-
-        // 4. Try raw env key (e.g. path = "OPENAI_API_KEY")
-        let raw_key = path.to_uppercase().replace('/', "_").replace('-', "_");
-        if let Ok(value) = std::env::var(&raw_key) {
-            debug!("[Vault] using raw env key {raw_key} for \"{path}\"");
-            return Ok(SecretValue { value, source: SecretSource::RawEnvKey });
+        // 3. VAULT_SECRET_<UPPER_SNAKE>, then the raw env key (e.g. "OPENAI_API_KEY")
+        if let Some((key, secret)) = env_fallback(path) {
+            debug!("[Vault] using env key {key} for \"{path}\"");
+            return Ok(secret);
         }
 
         Err(anyhow!(
-            "secret \"{path}\" not found in HashiCorp Vault, env var {env_key}, \
-             or raw env key {raw_key}"
+            "secret \"{path}\" not found in HashiCorp Vault, env var {}, \
+             or raw env key {}",
+            path_to_env_key(path), raw_env_key(path),
         ))
     }
 
     /// Inject vault secrets into a prompt template, replacing `{{secret:path}}` placeholders.
     ///
     /// Example: `"Bearer {{secret:sap/api_key}}"` → `"Bearer sk-abc123"`
-    pub async fn inject_into_template(&mut self, template: &str) -> Result<String> {
+    #[allow(dead_code)]
+    pub async fn inject_into_template(&self, template: &str) -> Result<String> {
         let mut result = template.to_owned();
         let re = regex_lite::Regex::new(r"\{\{secret:([^}]+)\}\}").unwrap();
 
@@ -178,8 +339,9 @@ impl VaultClient {
     ///
     /// Only resolves slots whose vault_path is non-empty.
     /// Returns a map of slot_id → resolved secret value.
+    #[allow(dead_code)]
     pub async fn resolve_slots(
-        &mut self,
+        &self,
         slots: &[(&str, &str)],
     ) -> HashMap<String, String> {
         let mut resolved = HashMap::new();
@@ -204,25 +366,79 @@ impl VaultClient {
         resolved
     }
 
+    /// Resolve the uncached `paths` from Vault concurrently to warm the
+    /// cache; failures are left to the lookup that needs the path.  Returns
+    /// how many were fetched.
+    pub async fn prefetch(&self, paths: &[String]) -> usize {
+        let (Some(addr), Some(token)) = (self.vault_addr.clone(), self.vault_token.clone()) else {
+            return 0;
+        };
+        if self.degraded() {
+            return 0;
+        }
+        let missing: Vec<&String> = paths.iter().filter(|p| self.cached(p).is_none()).collect();
+        if missing.is_empty() {
+            return 0;
+        }
+        let mut fetched = Vec::with_capacity(missing.len());
+        for batch in missing.chunks(PREFETCH_CONCURRENCY) {
+            let lookups: Vec<_> = batch.iter().map(|path| self.fetch_from_hashicorp(&addr, &token, path)).collect();
+            fetched.extend(futures_util::future::join_all(lookups).await);
+        }
+        let mut warmed = 0;
+        for (path, result) in missing.into_iter().zip(fetched) {
+            self.report_reachability(&result);
+            match result {
+                Ok(value) => {
+                    self.remember(path, &value);
+                    warmed += 1;
+                }
+                Err(e) => debug!("[Vault] prefetch of \"{path}\" failed: {e}"),
+            }
+        }
+        warmed
+    }
+
     // ── Private helpers ───────────────────────────────────────────────────────
 
+    /// Unexpired cached value of `path`.
+    fn cached(&self, path: &str) -> Option<String> {
+        let cache = self.cache.read().unwrap_or_else(|e| e.into_inner());
+        cache.get(path).filter(|e| e.expires_at > Instant::now()).map(|e| e.value.clone())
+    }
+
+    fn remember(&self, path: &str, value: &str) {
+        let now = Instant::now();
+        let mut cache = self.cache.write().unwrap_or_else(|e| e.into_inner());
+        cache.retain(|_, e| e.expires_at > now);
+        cache.insert(path.to_owned(), CacheEntry {
+            value: value.to_owned(),
+            expires_at: now + self.cache_ttl,
+        });
+    }
+
+    /// Network, timeout and 5xx failures mean Vault is down.
+    fn report_reachability(&self, fetched: &Result<String>) {
+        if let Some(health) = &self.health {
+            let down = fetched.as_ref().err().filter(|e| {
+                matches!(ExecError::from_anyhow(e, None).code, ErrorCode::Network | ErrorCode::Timeout | ErrorCode::Upstream)
+            });
+            health.record_vault_lookup(down.map(|e| format!("{e:#}")));
+        }
+    }
+
     async fn fetch_from_hashicorp(
         &self,
         addr: &str,
         token: &str,
         secret_path: &str,
     ) -> Result<String> {
-        // KV v2 path format: /v1/secret/data/<path>
-        // Support both "mount/key" and "mount/subpath/key" formats
-        let parts: Vec<&str> = secret_path.splitn(2, '/').collect();
-        let (mount, key) = if parts.len() == 2 {
-            (parts[0], parts[1])
-        } else {
-            ("secret", secret_path)
+        let Location { mount, path, version, field } = self.locate(secret_path);
+        let url = match version {
+            1 => format!("{}/v1/{mount}/{path}", addr.trim_end_matches('/')),
+            _ => format!("{}/v1/{mount}/data/{path}", addr.trim_end_matches('/')),
         };
 
-        let url = format!("{}/v1/{}/data/{}", addr.trim_end_matches('/'), mount, key);
-
         let mut req = self.http
             .get(&url)
             .header("X-Vault-Token", token);
@@ -233,19 +449,31 @@ impl VaultClient {
 
         let resp = req.send().await?;
         if !resp.status().is_success() {
-            return Err(anyhow!("Vault HTTP {}: {}", resp.status(), url));
+            return Err(ExecError::http(format_args!("Vault {url}"), resp.status()));
         }
 
         let body: VaultResponse = resp.json().await
             .map_err(|e| anyhow!("Vault response parse error: {e}"))?;
 
-        // Take the first value from the KV map (or look for the key part)
-        let kv_key = secret_path.rsplit('/').next().unwrap_or(secret_path);
-        let value = body.data.data
-            .get(kv_key)
-            .or_else(|| body.data.data.values().next())
-            .and_then(|v| v.as_str().map(|s| s.to_owned()))
-            .ok_or_else(|| anyhow!("Vault KV key \"{kv_key}\" not found at {url}"))?;
+        let data = match version {
+            1 => &body.data,
+            _ => body.data.get("data").and_then(|d| d.as_object())
+                .ok_or_else(|| anyhow!("Vault response at {url} has no data.data (is {mount} KV v1?)"))?,
+        };
+        let value = match field {
+            Some(field) => data.get(field)
+                .ok_or_else(|| ExecError::new(ErrorCode::NotFound, format!("Vault field \"{field}\" not found at {url}")))?,
+            // The field named like the last segment, else the first one
+            None => {
+                let kv_key = path.rsplit('/').next().unwrap_or(path);
+                data.get(kv_key).or_else(|| data.values().next())
+                    .ok_or_else(|| anyhow!("Vault KV key \"{kv_key}\" not found at {url}"))?
+            }
+        };
+        let value = match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
 
         Ok(value)
     }
@@ -258,12 +486,25 @@ impl VaultClient {
 fn path_to_env_key(path: &str) -> String {
     let normalized = path
         .to_uppercase()
-        .replace('/', "_")
-        .replace('-', "_")
-        .replace('.', "_");
+        .replace(['/', '-', '.', '#'], "_");
     format!("VAULT_SECRET_{normalized}")
 }
 
+/// "OPENAI_API_KEY" / "openai-api-key" → "OPENAI_API_KEY"
+fn raw_env_key(path: &str) -> String {
+    path.to_uppercase().replace(['/', '-', '#'], "_")
+}
+
+/// Env fallback of `path`: the key it came from and the secret.
+fn env_fallback(path: &str) -> Option<(String, SecretValue)> {
+    let env_key = path_to_env_key(path);
+    if let Ok(value) = std::env::var(&env_key) {
+        return Some((env_key, SecretValue { value, source: SecretSource::EnvVar }));
+    }
+    let raw_key = raw_env_key(path);
+    std::env::var(&raw_key).ok().map(|value| (raw_key, SecretValue { value, source: SecretSource::RawEnvKey }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -274,4 +515,143 @@ mod tests {
         assert_eq!(path_to_env_key("db/password"),    "VAULT_SECRET_DB_PASSWORD");
         assert_eq!(path_to_env_key("OPENAI_API_KEY"), "VAULT_SECRET_OPENAI_API_KEY");
     }
+
+    #[tokio::test]
+    async fn test_kv_layouts_and_fields() {
+        let mut vault = VaultClient::new(reqwest::Client::new(), None, None, None);
+        vault.set_kv_layout(2, &["legacy=1".into(), "teams/ot/kv".into(), "bad=3".into()]);
+        let at = |path| vault.locate(path);
+        assert_eq!(at("teams/ot/kv/plc/creds#password"), Location { mount: "teams/ot/kv", path: "plc/creds", version: 2, field: Some("password") });
+        assert_eq!(at("legacy/sap/api_key"), Location { mount: "legacy", path: "sap/api_key", version: 1, field: None });
+        assert_eq!(at("sap/api_key"), Location { mount: "sap", path: "api_key", version: 2, field: None });
+        assert_eq!(at("api_key#token"), Location { mount: "secret", path: "api_key", version: 2, field: Some("token") });
+        assert_eq!(path_to_env_key("db/creds#user"), "VAULT_SECRET_DB_CREDS_USER");
+
+        // KV v1 answer: the secret is `data` itself
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let n = sock.read(&mut buf).await.unwrap();
+            let target = String::from_utf8_lossy(&buf[..n]).split_whitespace().nth(1).unwrap_or_default().to_owned();
+            let body = if target == "/v1/legacy/plc/line3" { r#"{"data":{"user":"ot","port":502}}"# } else { "{}" };
+            let answer = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len());
+            sock.write_all(answer.as_bytes()).await.unwrap();
+        });
+        vault.vault_addr = Some(addr);
+        vault.vault_token = Some("t".into());
+        let secret = vault.fetch_secret("legacy/plc/line3#port").await.unwrap();
+        assert_eq!((secret.value.as_str(), secret.source), ("502", SecretSource::HashiCorpVault));
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_warms_the_cache_concurrently() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let served = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                served.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+                    let mut buf = [0u8; 1024];
+                    let n = sock.read(&mut buf).await.unwrap();
+                    let target = String::from_utf8_lossy(&buf[..n]).split_whitespace().nth(1).unwrap_or_default().to_owned();
+                    let body = format!(r#"{{"data":{{"data":{{"value":"{target}"}}}}}}"#);
+                    let answer = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len());
+                    sock.write_all(answer.as_bytes()).await.unwrap();
+                });
+            }
+        });
+
+        let vault = VaultClient::new(reqwest::Client::new(), Some(addr), Some("t".into()), None);
+        let paths: Vec<String> = ["plc/a", "plc/b", "erp/c"].map(String::from).into();
+        assert_eq!(vault.prefetch(&paths).await, 3);
+        assert_eq!(vault.prefetch(&paths).await, 0);
+        for path in &paths {
+            let secret = vault.fetch_secret(path).await.unwrap();
+            assert_eq!(secret.value, format!("/v1/{}", path.replacen('/', "/data/", 1)));
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_cache_hits_skip_lookups_in_flight() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let served = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                served.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+                    let mut buf = [0u8; 1024];
+                    let n = sock.read(&mut buf).await.unwrap();
+                    // "plc/stuck" never answers
+                    if String::from_utf8_lossy(&buf[..n]).contains("/stuck ") {
+                        std::future::pending::<()>().await;
+                    }
+                    let body = r#"{"data":{"data":{"value":"v"}}}"#;
+                    let answer = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len());
+                    sock.write_all(answer.as_bytes()).await.unwrap();
+                });
+            }
+        });
+
+        let vault = Arc::new(VaultClient::new(reqwest::Client::new(), Some(addr), Some("t".into()), None));
+        vault.fetch_secret("plc/a").await.unwrap();
+        let stuck = tokio::spawn({
+            let vault = vault.clone();
+            async move { vault.fetch_secret("plc/stuck").await }
+        });
+        while requests.load(Ordering::SeqCst) < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        // Hits answer from the cache while the miss waits on Vault
+        let hits = futures_util::future::join_all((0..50).map(|_| vault.fetch_secret("plc/a")));
+        let hits = tokio::time::timeout(Duration::from_secs(1), hits).await.expect("cache hits waited on the miss");
+        assert!(hits.iter().all(|s| s.as_ref().is_ok_and(|s| s.value == "v")));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(!stuck.is_finished());
+        stuck.abort();
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_vault_enters_degraded_mode() {
+        // Nothing listens on the discard port: every lookup is a network failure
+        let health = HealthState::new("n", "LINUX");
+        let mut vault = VaultClient::new(reqwest::Client::new(), Some("http://127.0.0.1:9".into()), Some("t".into()), None);
+        vault.set_health(Some(health.clone()));
+        vault.set_prefail(2, Duration::from_secs(60));
+
+        for _ in 0..2 {
+            let e = vault.fetch_secret("plant/degraded_key").await.unwrap_err();
+            assert_ne!(ExecError::from_anyhow(&e, None).code, ErrorCode::VaultUnavailable);
+        }
+        let status = health.vault_status().unwrap();
+        assert!(!status.reachable && status.consecutive_failures == 2 && status.last_success.is_none());
+        assert!(health.to_prometheus().contains("eyeflow_vault_consecutive_failures{node_id=\"n\"} 2"));
+
+        let e = vault.unavailable("plant/degraded_key").unwrap();
+        assert_eq!(ExecError::from_anyhow(&e, None).code, ErrorCode::VaultUnavailable);
+        let e = vault.fetch_secret("plant/degraded_key").await.unwrap_err();
+        assert_eq!(ExecError::from_anyhow(&e, None).code, ErrorCode::VaultUnavailable);
+        // Degraded lookups don't count as more failures
+        assert_eq!(health.vault_status().unwrap().consecutive_failures, 2);
+
+        // An env fallback still resolves without waiting on Vault
+        std::env::set_var("VAULT_SECRET_PLANT_DEGRADED_FALLBACK", "s3cret");
+        assert!(vault.unavailable("plant/degraded_fallback").is_none());
+        let secret = vault.fetch_secret("plant/degraded_fallback").await.unwrap();
+        assert_eq!((secret.value.as_str(), secret.source), ("s3cret", SecretSource::EnvVar));
+    }
 }
@@ -0,0 +1,297 @@
+//! Static IR verifier — runs before any instruction executes
+//!
+//! Malformed artifacts used to fail mid-execution, after CALL_ACTION or
+//! CALL_SERVICE side effects had already happened.  `verify` walks the slice
+//! once and reports every structural problem it finds:
+//!
+//!   MISSING_INSTRUCTION     instruction_order names an index with no body
+//!   DUPLICATE_ORDER_ENTRY   an index appears twice in instruction_order
+//!   UNKNOWN_OPCODE          opcode value outside IROpcode
+//!   INVALID_JUMP_TARGET     BRANCH / JUMP / SWITCH target not in instruction_order
+//!   INVALID_SWITCH          SWITCH without a value register or a parsable case table
+//!   LOOP_MISSING_OPERANDS   LOOP without loop_operands
+//!   LOOP_BOUNDS             LOOP body/exit outside the slice or out of order
+//!   UNDEFINED_REGISTER      src register never written in the slice
+//!   UNBALANCED_PARALLEL     PARALLEL_SPAWN / PARALLEL_MERGE do not pair up
+//!   UNBALANCED_TRY          TRY_BEGIN / TRY_END do not pair up
+//!   INVALID_TRY_HANDLER     TRY_BEGIN handler not after the TRY_BEGIN
+//!   INVALID_CALL_SLICE      CALL_SLICE without a `slice` operand
+//!   INVALID_INPUT_SCHEMA    dispatch_metadata.input_schema unparsable
+//!   INVALID_PII_POLICY      metadata.pii_policy unparsable
+//!   INVALID_GUARDRAILS      LLM_CALL `guardrails` operand unparsable
+//!
+//! A non-empty issue list makes the node answer VALIDATION_FAILED without
+//! executing anything.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::guardrails::Guardrails;
+use crate::input_schema::InputSchema;
+use crate::pii::PiiPolicy;
+use crate::prepared::SwitchTable;
+use crate::proto::llmir::{IrOpcode, LlmIntermediateRepresentation};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyIssue {
+    /// Offending instruction index (None for slice-level problems)
+    pub instruction: Option<i32>,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl VerifyIssue {
+    fn new(instruction: Option<i32>, code: &'static str, message: impl Into<String>) -> Self {
+        Self { instruction, code, message: message.into() }
+    }
+}
+
+/// Opcodes that write their `dest` register (PARALLEL_MERGE writes the
+/// merged value, see merge.rs).
+pub(crate) fn writes_dest(op: IrOpcode) -> bool {
+    !matches!(
+        op,
+        IrOpcode::Branch
+            | IrOpcode::Jump
+            | IrOpcode::Loop
+            | IrOpcode::Return
+            | IrOpcode::ParallelSpawn
+            | IrOpcode::Switch
+            | IrOpcode::TryEnd
+    )
+}
+
+/// Check `ir` for structural errors; `Ok(())` when it is safe to execute.
+pub fn verify(ir: &LlmIntermediateRepresentation) -> Result<(), Vec<VerifyIssue>> {
+    verify_with_inputs(ir, &[])
+}
+
+/// `verify` for a CALL_SLICE sub-workflow, whose caller also fills `inputs`.
+pub fn verify_with_inputs(ir: &LlmIntermediateRepresentation, inputs: &[i32]) -> Result<(), Vec<VerifyIssue>> {
+    let mut issues = Vec::new();
+    let order = &ir.instruction_order;
+
+    // Position of each instruction in the execution order
+    let mut pos: HashMap<i32, usize> = HashMap::new();
+    for (ip, idx) in order.iter().enumerate() {
+        if pos.insert(*idx, ip).is_some() {
+            issues.push(VerifyIssue::new(Some(*idx), "DUPLICATE_ORDER_ENTRY",
+                format!("instruction #{idx} appears more than once in instruction_order")));
+        }
+        if !ir.instructions.contains_key(idx) {
+            issues.push(VerifyIssue::new(Some(*idx), "MISSING_INSTRUCTION",
+                format!("instruction_order references #{idx}, which is not defined")));
+        }
+    }
+
+    // Registers written anywhere in the slice (plus the input registers)
+    let mut written: HashSet<i32> = HashSet::from([ir.input_register]);
+    written.extend(inputs);
+    for idx in order {
+        if let Some(instr) = ir.instructions.get(idx) {
+            if IrOpcode::try_from(instr.opcode).map(writes_dest).unwrap_or(false) {
+                written.insert(instr.dest);
+            }
+        }
+    }
+
+    if let Some(Err(e)) = ir.metadata.as_ref().map(|m| PiiPolicy::parse(&m.pii_policy)) {
+        issues.push(VerifyIssue::new(None, "INVALID_PII_POLICY", e));
+    }
+
+    let mut parallel_depth = 0i32;
+    let mut try_depth = 0i32;
+    for (ip, idx) in order.iter().enumerate() {
+        let Some(instr) = ir.instructions.get(idx) else { continue };
+        let Ok(op) = IrOpcode::try_from(instr.opcode) else {
+            issues.push(VerifyIssue::new(Some(*idx), "UNKNOWN_OPCODE",
+                format!("opcode value {} is not a known IROpcode", instr.opcode)));
+            continue;
+        };
+
+        for r in &instr.src {
+            if !written.contains(r) {
+                issues.push(VerifyIssue::new(Some(*idx), "UNDEFINED_REGISTER",
+                    format!("{op:?} reads R{r}, which no instruction in the slice writes")));
+            }
+        }
+
+        if let Some(Err(e)) = instr.dispatch_metadata.as_ref().map(|dm| InputSchema::parse(&dm.input_schema)) {
+            issues.push(VerifyIssue::new(Some(*idx), "INVALID_INPUT_SCHEMA", e));
+        }
+        if op == IrOpcode::LlmCall {
+            let operands = serde_json::from_str(&instr.operands_json).unwrap_or(serde_json::Value::Null);
+            let output_schema = instr.dispatch_metadata.as_ref().map_or("", |dm| dm.output_schema.as_str());
+            if let Err(e) = Guardrails::parse(&operands, output_schema) {
+                issues.push(VerifyIssue::new(Some(*idx), "INVALID_GUARDRAILS", e));
+            }
+        }
+
+        match op {
+            IrOpcode::Branch | IrOpcode::Jump if !pos.contains_key(&instr.target_instruction) => {
+                issues.push(VerifyIssue::new(Some(*idx), "INVALID_JUMP_TARGET",
+                    format!("{op:?} targets #{}, which is not in instruction_order",
+                        instr.target_instruction)));
+            }
+            IrOpcode::TryBegin => {
+                try_depth += 1;
+                match pos.get(&instr.target_instruction) {
+                    None => issues.push(VerifyIssue::new(Some(*idx), "INVALID_JUMP_TARGET",
+                        format!("TRY_BEGIN handler #{} is not in instruction_order", instr.target_instruction))),
+                    Some(&handler) if handler <= ip => issues.push(VerifyIssue::new(Some(*idx), "INVALID_TRY_HANDLER",
+                        format!("TRY_BEGIN handler #{} must follow the TRY_BEGIN", instr.target_instruction))),
+                    Some(_) => {}
+                }
+            }
+            IrOpcode::TryEnd => {
+                try_depth -= 1;
+                if try_depth < 0 {
+                    issues.push(VerifyIssue::new(Some(*idx), "UNBALANCED_TRY",
+                        "TRY_END without a preceding TRY_BEGIN"));
+                    try_depth = 0;
+                }
+            }
+            IrOpcode::Switch => {
+                let operands = serde_json::from_str(&instr.operands_json).unwrap_or_default();
+                match SwitchTable::parse(&operands) {
+                    Err(e) => issues.push(VerifyIssue::new(Some(*idx), "INVALID_SWITCH", e)),
+                    Ok(_) if instr.src.is_empty() => issues.push(VerifyIssue::new(Some(*idx), "INVALID_SWITCH",
+                        "SWITCH has no value register (src[0])")),
+                    Ok(table) => {
+                        for target in table.targets().filter(|t| !pos.contains_key(t)) {
+                            issues.push(VerifyIssue::new(Some(*idx), "INVALID_JUMP_TARGET",
+                                format!("SWITCH targets #{target}, which is not in instruction_order")));
+                        }
+                    }
+                }
+            }
+            IrOpcode::Loop => match &instr.loop_operands {
+                None => issues.push(VerifyIssue::new(Some(*idx), "LOOP_MISSING_OPERANDS",
+                    "LOOP has no loop_operands")),
+                Some(lo) => {
+                    match (pos.get(&lo.body_start_index), pos.get(&lo.exit_index)) {
+                        (Some(&body), Some(&exit)) if ip < body && body < exit => {}
+                        (Some(_), Some(_)) => issues.push(VerifyIssue::new(Some(*idx), "LOOP_BOUNDS",
+                            format!("LOOP body #{} / exit #{} must follow the LOOP in that order",
+                                lo.body_start_index, lo.exit_index))),
+                        _ => issues.push(VerifyIssue::new(Some(*idx), "LOOP_BOUNDS",
+                            format!("LOOP body #{} or exit #{} is outside the slice",
+                                lo.body_start_index, lo.exit_index))),
+                    }
+                    if let Some(pred) = &lo.convergence_predicate {
+                        if !written.contains(&pred.register_index) {
+                            issues.push(VerifyIssue::new(Some(*idx), "UNDEFINED_REGISTER",
+                                format!("LOOP convergence predicate reads R{}, which is never written",
+                                    pred.register_index)));
+                        }
+                    }
+                }
+            },
+            IrOpcode::CallSlice => {
+                let operands: serde_json::Value = serde_json::from_str(&instr.operands_json).unwrap_or_default();
+                if !operands.get("slice").is_some_and(|s| s.is_string()) {
+                    issues.push(VerifyIssue::new(Some(*idx), "INVALID_CALL_SLICE",
+                        "CALL_SLICE has no `slice` operand (artifact checksum or workflow id)"));
+                }
+            }
+            IrOpcode::ParallelSpawn => parallel_depth += 1,
+            IrOpcode::ParallelMerge => {
+                parallel_depth -= 1;
+                if parallel_depth < 0 {
+                    issues.push(VerifyIssue::new(Some(*idx), "UNBALANCED_PARALLEL",
+                        "PARALLEL_MERGE without a preceding PARALLEL_SPAWN"));
+                    parallel_depth = 0;
+                }
+            }
+            _ => {}
+        }
+    }
+    if parallel_depth > 0 {
+        issues.push(VerifyIssue::new(None, "UNBALANCED_PARALLEL",
+            format!("{parallel_depth} PARALLEL_SPAWN without a matching PARALLEL_MERGE")));
+    }
+    if try_depth > 0 {
+        issues.push(VerifyIssue::new(None, "UNBALANCED_TRY",
+            format!("{try_depth} TRY_BEGIN without a matching TRY_END")));
+    }
+
+    if issues.is_empty() { Ok(()) } else { Err(issues) }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::llmir::{DispatchMetadata, IrInstruction, LoopOperands, WorkflowMetadata};
+
+    fn instr(index: i32, op: IrOpcode, dest: i32, src: Vec<i32>) -> IrInstruction {
+        IrInstruction { index, opcode: op as i32, dest, src, ..Default::default() }
+    }
+
+    fn ir(instrs: Vec<IrInstruction>) -> LlmIntermediateRepresentation {
+        LlmIntermediateRepresentation {
+            instruction_order: instrs.iter().map(|i| i.index).collect(),
+            instructions: instrs.into_iter().map(|i| (i.index, i)).collect(),
+            input_register: 100,
+            ..Default::default()
+        }
+    }
+
+    fn codes(r: Result<(), Vec<VerifyIssue>>) -> Vec<&'static str> {
+        r.err().unwrap_or_default().into_iter().map(|i| i.code).collect()
+    }
+
+    #[test]
+    fn test_valid_slice_passes() {
+        let mut branch = instr(2, IrOpcode::Branch, 0, vec![1]);
+        branch.target_instruction = 4;
+        let slice = ir(vec![
+            instr(1, IrOpcode::LoadResource, 1, vec![]),
+            branch,
+            instr(3, IrOpcode::Transform, 2, vec![1, 100]),
+            instr(4, IrOpcode::Return, 0, vec![]),
+        ]);
+        assert_eq!(verify(&slice), Ok(()));
+    }
+
+    #[test]
+    fn test_reports_structural_errors() {
+        let mut jump = instr(2, IrOpcode::Jump, 0, vec![]);
+        jump.target_instruction = 99;
+        let mut lp = instr(3, IrOpcode::Loop, 0, vec![]);
+        lp.loop_operands = Some(LoopOperands { body_start_index: 1, exit_index: 4, ..Default::default() });
+        let mut slice = ir(vec![
+            instr(1, IrOpcode::CallService, 1, vec![7]),
+            jump,
+            lp,
+            instr(4, IrOpcode::ParallelMerge, 0, vec![]),
+            instr(5, IrOpcode::ParallelSpawn, 0, vec![]),
+            IrInstruction { operands_json: r#"{"default":"x"}"#.into(), ..instr(6, IrOpcode::Switch, 0, vec![1]) },
+            IrInstruction { target_instruction: 1, ..instr(7, IrOpcode::TryBegin, 9, vec![]) },
+            instr(8, IrOpcode::CallSlice, 3, vec![]),
+            IrInstruction {
+                dispatch_metadata: Some(DispatchMetadata { input_schema: "{".into(), ..Default::default() }),
+                ..instr(9, IrOpcode::CallService, 4, vec![])
+            },
+            IrInstruction {
+                operands_json: r#"{"guardrails":{"maxLength":"long"}}"#.into(),
+                ..instr(10, IrOpcode::LlmCall, 5, vec![])
+            },
+        ]);
+        slice.instruction_order.push(42);
+        slice.metadata = Some(WorkflowMetadata { pii_policy: r#"{"patterns":["phone"]}"#.into(), ..Default::default() });
+
+        let found = codes(verify(&slice));
+        for expected in [
+            "UNDEFINED_REGISTER", "INVALID_JUMP_TARGET", "LOOP_BOUNDS",
+            "UNBALANCED_PARALLEL", "MISSING_INSTRUCTION", "INVALID_SWITCH",
+            "INVALID_TRY_HANDLER", "UNBALANCED_TRY", "INVALID_CALL_SLICE", "INVALID_INPUT_SCHEMA",
+            "INVALID_PII_POLICY", "INVALID_GUARDRAILS",
+        ] {
+            assert!(found.contains(&expected), "missing {expected} in {found:?}");
+        }
+        assert_eq!(found.iter().filter(|c| **c == "UNBALANCED_PARALLEL").count(), 2);
+    }
+}
@@ -0,0 +1,233 @@
+//! Action write-ahead log — side effects that may or may not have happened
+//!
+//! CALL_ACTION and CALL_SERVICE reach outside the node (valves, motors,
+//! third-party APIs).  If the node dies between sending the request and
+//! seeing the answer, nobody knows whether the actuation took place.
+//!
+//! Before each such call an INTENT record (instruction, idempotency key) is
+//! appended and fsync'd to SVM_ACTION_WAL_PATH; a DONE record follows once
+//! the call returns.  On start-up, intents without a DONE are "in doubt" and
+//! are reported to central after REGISTER:
+//!
+//!   { "type": "ACTIONS_IN_DOUBT", "payload": [Intent, ...] }
+//!
+//! The idempotency key is also sent as the `Idempotency-Key` header so a
+//! receiver that deduplicates can make a retry after recovery safe.
+//! A call cancelled mid-flight (preemption, resource timeout) stays open and
+//! is reported after the next restart; a re-issue reuses its key.
+//! The file is compacted whenever nothing is open or left to report.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+tokio::task_local! {
+    /// Idempotency key of the journaled call being dispatched.
+    pub static IDEMPOTENCY_KEY: String;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Intent {
+    pub key: String,
+    pub workflow_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    pub instruction: i32,
+    pub opcode: String,
+    pub service_id: String,
+    pub endpoint: String,
+    /// RFC 3339 time the call was about to be sent
+    pub started_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "SCREAMING_SNAKE_CASE")]
+enum Record {
+    Intent(Intent),
+    Done { key: String, ok: bool },
+}
+
+#[derive(Debug, Default)]
+struct WalState {
+    file: Option<fs::File>,
+    /// Calls currently in flight
+    open: HashMap<String, Intent>,
+    /// Calls left unfinished by a previous run, not yet reported
+    in_doubt: Vec<Intent>,
+}
+
+#[derive(Debug)]
+pub struct ActionWal {
+    path: PathBuf,
+    state: Mutex<WalState>,
+}
+
+impl ActionWal {
+    /// Open the WAL, collecting intents a previous run never completed.
+    pub async fn open(path: PathBuf) -> Result<Arc<Self>> {
+        let mut in_doubt: Vec<Intent> = Vec::new();
+        if path.exists() {
+            let text = fs::read_to_string(&path).await?;
+            for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str::<Record>(line) {
+                    Ok(Record::Intent(i)) => in_doubt.push(i),
+                    Ok(Record::Done { key, .. }) => in_doubt.retain(|i| i.key != key),
+                    // A torn final line is what a crash mid-append looks like
+                    Err(e) => warn!("[ActionWal] skipping unreadable record: {e}"),
+                }
+            }
+        }
+        if !in_doubt.is_empty() {
+            warn!("[ActionWal] {} action(s) in doubt from a previous run", in_doubt.len());
+        }
+        let wal = Arc::new(Self {
+            path,
+            state: Mutex::new(WalState { in_doubt, ..Default::default() }),
+        });
+        wal.compact(&mut *wal.state.lock().await).await?;
+        Ok(wal)
+    }
+
+    /// Durably record that `intent` is about to be executed.
+    pub async fn begin(&self, intent: Intent) -> Result<()> {
+        let mut state = self.state.lock().await;
+        Self::append(&mut state, &Record::Intent(intent.clone())).await
+            .context("action WAL unavailable")?;
+        state.open.insert(intent.key.clone(), intent);
+        Ok(())
+    }
+
+    /// Record the outcome of a call started with `begin`.
+    pub async fn complete(&self, key: &str, ok: bool) {
+        let mut state = self.state.lock().await;
+        state.open.remove(key);
+        let res = if state.open.is_empty() && state.in_doubt.is_empty() {
+            self.compact(&mut state).await
+        } else {
+            Self::append(&mut state, &Record::Done { key: key.to_owned(), ok }).await
+        };
+        if let Err(e) = res {
+            warn!("[ActionWal] failed to record completion of {key}: {e}");
+        }
+    }
+
+    /// Run `call` between an INTENT and a DONE record, with its key in
+    /// `IDEMPOTENCY_KEY`.  Fails without calling if the intent can't be written.
+    pub async fn journaled<T, F>(&self, intent: Intent, call: F) -> Result<T>
+    where
+        F: std::future::Future<Output = Result<T>>,
+    {
+        let key = intent.key.clone();
+        self.begin(intent).await?;
+        let result = IDEMPOTENCY_KEY.scope(key.clone(), call).await;
+        self.complete(&key, result.is_ok()).await;
+        result
+    }
+
+    /// Intents left unfinished by a previous run.
+    pub async fn in_doubt(&self) -> Vec<Intent> {
+        self.state.lock().await.in_doubt.clone()
+    }
+
+    /// Central has been told about the in-doubt actions — forget them.
+    pub async fn acknowledge_in_doubt(&self) -> Result<()> {
+        let mut state = self.state.lock().await;
+        state.in_doubt.clear();
+        self.compact(&mut state).await
+    }
+
+    async fn append(state: &mut WalState, record: &Record) -> Result<()> {
+        let file = state.file.as_mut().context("WAL file not open")?;
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        file.write_all(&line).await?;
+        file.sync_data().await?;
+        Ok(())
+    }
+
+    /// Rewrite the file with only the intents still open or in doubt.
+    async fn compact(&self, state: &mut WalState) -> Result<()> {
+        let mut out = Vec::new();
+        for i in state.in_doubt.iter().chain(state.open.values()) {
+            out.extend(serde_json::to_vec(&Record::Intent(i.clone()))?);
+            out.push(b'\n');
+        }
+        let tmp = self.path.with_extension("wal.tmp");
+        let mut f = fs::File::create(&tmp).await?;
+        f.write_all(&out).await?;
+        f.sync_all().await?;
+        fs::rename(&tmp, &self.path).await?;
+
+        state.file = Some(
+            fs::OpenOptions::new().append(true).create(true).open(&self.path).await?,
+        );
+        Ok(())
+    }
+}
+
+impl Intent {
+    pub fn new(
+        key: String,
+        workflow_id: &str,
+        tenant_id: Option<String>,
+        instr: &crate::proto::llmir::IrInstruction,
+        opcode: &str,
+    ) -> Self {
+        Self {
+            key,
+            workflow_id: workflow_id.to_owned(),
+            tenant_id,
+            instruction: instr.index,
+            opcode: opcode.to_owned(),
+            service_id: instr.service_id.clone(),
+            endpoint: instr.dispatch_metadata.as_ref()
+                .map(|d| d.endpoint_url.clone())
+                .unwrap_or_default(),
+            started_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::llmir::IrInstruction;
+
+    fn intent(key: &str) -> Intent {
+        let instr = IrInstruction { index: 4, service_id: "valve".into(), ..Default::default() };
+        Intent::new(key.into(), "wf", None, &instr, "CALL_ACTION")
+    }
+
+    #[tokio::test]
+    async fn test_unfinished_intents_are_in_doubt() {
+        let path = std::env::temp_dir().join(format!("eyeflow_wal_{}.wal", uuid::Uuid::new_v4()));
+        let wal = ActionWal::open(path.clone()).await.unwrap();
+        wal.begin(intent("a")).await.unwrap();
+        wal.begin(intent("b")).await.unwrap();
+        wal.complete("a", true).await;
+        drop(wal); // "crash" with b in flight
+
+        let wal = ActionWal::open(path.clone()).await.unwrap();
+        let doubt = wal.in_doubt().await;
+        assert_eq!(doubt.iter().map(|i| i.key.as_str()).collect::<Vec<_>>(), ["b"]);
+        assert_eq!(doubt[0].service_id, "valve");
+
+        // Still in doubt after another restart until acknowledged
+        drop(wal);
+        let wal = ActionWal::open(path.clone()).await.unwrap();
+        assert_eq!(wal.in_doubt().await.len(), 1);
+        wal.acknowledge_in_doubt().await.unwrap();
+        drop(wal);
+        assert!(ActionWal::open(path.clone()).await.unwrap().in_doubt().await.is_empty());
+        let _ = std::fs::remove_file(path);
+    }
+}
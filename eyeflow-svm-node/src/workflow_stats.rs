@@ -0,0 +1,207 @@
+//! Per-workflow execution counters behind /metrics and /health
+//!
+//! HealthState's totals say a node is failing, not which rule.  Each
+//! execution is also counted under its workflow id:
+//!
+//!   eyeflow_workflow_executions_total{workflow_id,result="ok"|"failed"}
+//!   eyeflow_workflow_last_execution_timestamp_seconds{workflow_id}
+//!   eyeflow_workflow_last_failure_timestamp_seconds{workflow_id}
+//!
+//! At most SVM_WORKFLOW_METRICS_MAX workflows (default 50, 0 = off) are
+//! tracked; a new one evicts the least recently executed, whose series then
+//! disappear (eyeflow_workflow_metrics_evicted_total counts them).  /health
+//! lists the tracked workflows with failures, most failures first.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Workflows listed under `failing` in /health.
+const FAILING_LISTED: usize = 10;
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowCounts {
+    pub ok: u64,
+    pub failed: u64,
+    /// Unix seconds of the last execution / failure (0 = none)
+    pub last_execution: u64,
+    pub last_failure: u64,
+    #[serde(skip)]
+    last_used: u64,
+}
+
+#[derive(Debug)]
+pub struct WorkflowStats {
+    capacity: AtomicUsize,
+    entries: Mutex<HashMap<String, WorkflowCounts>>,
+    tick: AtomicU64,
+    evicted: AtomicU64,
+}
+
+/// Prometheus label value escaping.
+fn label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+impl WorkflowStats {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: AtomicUsize::new(capacity),
+            entries: Mutex::new(HashMap::new()),
+            tick: AtomicU64::new(0),
+            evicted: AtomicU64::new(0),
+        }
+    }
+
+    /// Change how many workflows are tracked (drops the least recent beyond it).
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.store(capacity, Ordering::Relaxed);
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        self.evict_to(&mut entries, capacity);
+    }
+
+    fn evict_to(&self, entries: &mut HashMap<String, WorkflowCounts>, capacity: usize) {
+        while entries.len() > capacity {
+            let lru = entries.iter().min_by_key(|(_, c)| c.last_used).map(|(k, _)| k.clone());
+            if let Some(k) = lru {
+                entries.remove(&k);
+                self.evicted.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn record(&self, workflow_id: &str, ok: bool) {
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        if capacity == 0 {
+            return;
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let tick = self.tick.fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if !entries.contains_key(workflow_id) {
+            self.evict_to(&mut entries, capacity - 1);
+        }
+        let counts = entries.entry(workflow_id.to_owned()).or_default();
+        if ok {
+            counts.ok += 1;
+        } else {
+            counts.failed += 1;
+            counts.last_failure = now;
+        }
+        counts.last_execution = now;
+        counts.last_used = tick;
+    }
+
+    /// Counters of one tracked workflow.
+    #[cfg(test)]
+    pub fn get(&self, workflow_id: &str) -> Option<WorkflowCounts> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).get(workflow_id).cloned()
+    }
+
+    /// Tracked workflows with failures, most failures first.
+    pub fn failing(&self) -> Vec<(String, WorkflowCounts)> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let mut failing: Vec<_> = entries.iter()
+            .filter(|(_, c)| c.failed > 0)
+            .map(|(id, c)| (id.clone(), c.clone()))
+            .collect();
+        failing.sort_by(|a, b| b.1.failed.cmp(&a.1.failed).then_with(|| a.0.cmp(&b.0)));
+        failing.truncate(FAILING_LISTED);
+        failing
+    }
+
+    /// /health `workflows` object.
+    pub fn to_json(&self) -> serde_json::Value {
+        let tracked = self.entries.lock().map(|e| e.len()).unwrap_or(0);
+        let failing: Vec<_> = self.failing().into_iter()
+            .map(|(id, c)| {
+                let mut v = serde_json::to_value(c).unwrap_or_default();
+                v["workflowId"] = id.into();
+                v
+            })
+            .collect();
+        serde_json::json!({ "tracked": tracked, "failing": failing })
+    }
+
+    pub fn prometheus(&self, node_id: &str) -> String {
+        let mut entries: Vec<_> = {
+            let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            entries.iter().map(|(id, c)| (label(id), c.clone())).collect()
+        };
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut out = String::from(
+            "# HELP eyeflow_workflow_executions_total IR executions per workflow by result\n\
+             # TYPE eyeflow_workflow_executions_total counter\n",
+        );
+        for (id, c) in &entries {
+            out.push_str(&format!(
+                "eyeflow_workflow_executions_total{{node_id=\"{node_id}\",workflow_id=\"{id}\",result=\"ok\"}} {}\n\
+                 eyeflow_workflow_executions_total{{node_id=\"{node_id}\",workflow_id=\"{id}\",result=\"failed\"}} {}\n",
+                c.ok, c.failed,
+            ));
+        }
+        out.push_str(
+            "# HELP eyeflow_workflow_last_execution_timestamp_seconds Unix time of the workflow's last execution\n\
+             # TYPE eyeflow_workflow_last_execution_timestamp_seconds gauge\n",
+        );
+        for (id, c) in &entries {
+            out.push_str(&format!(
+                "eyeflow_workflow_last_execution_timestamp_seconds{{node_id=\"{node_id}\",workflow_id=\"{id}\"}} {}\n",
+                c.last_execution,
+            ));
+        }
+        out.push_str(
+            "# HELP eyeflow_workflow_last_failure_timestamp_seconds Unix time of the workflow's last failed execution\n\
+             # TYPE eyeflow_workflow_last_failure_timestamp_seconds gauge\n",
+        );
+        for (id, c) in entries.iter().filter(|(_, c)| c.failed > 0) {
+            out.push_str(&format!(
+                "eyeflow_workflow_last_failure_timestamp_seconds{{node_id=\"{node_id}\",workflow_id=\"{id}\"}} {}\n",
+                c.last_failure,
+            ));
+        }
+        out.push_str(&format!(
+            "# HELP eyeflow_workflow_metrics_evicted_total Workflows dropped from per-workflow metrics (SVM_WORKFLOW_METRICS_MAX)\n\
+             # TYPE eyeflow_workflow_metrics_evicted_total counter\n\
+             eyeflow_workflow_metrics_evicted_total{{node_id=\"{node_id}\"}} {}\n",
+            self.evicted.load(Ordering::Relaxed),
+        ));
+        out
+    }
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_per_workflow_and_evicts_least_recent() {
+        let stats = WorkflowStats::new(2);
+        stats.record("wf-a", true);
+        stats.record("wf-b", false);
+        stats.record("wf-a", false);
+        stats.record("wf-c", true); // evicts wf-b
+        assert!(stats.get("wf-b").is_none());
+        let a = stats.get("wf-a").unwrap();
+        assert_eq!((a.ok, a.failed), (1, 1));
+        assert!(a.last_failure > 0 && a.last_execution >= a.last_failure);
+
+        let metrics = stats.prometheus("n");
+        assert!(metrics.contains("eyeflow_workflow_executions_total{node_id=\"n\",workflow_id=\"wf-a\",result=\"failed\"} 1"));
+        assert!(metrics.contains("eyeflow_workflow_executions_total{node_id=\"n\",workflow_id=\"wf-c\",result=\"ok\"} 1"));
+        assert!(!metrics.contains("wf-b"));
+        assert!(metrics.contains("eyeflow_workflow_metrics_evicted_total{node_id=\"n\"} 1"));
+        assert_eq!(stats.to_json()["failing"][0]["workflowId"], "wf-a");
+
+        stats.record("line \"3\"", false);
+        assert!(stats.prometheus("n").contains("workflow_id=\"line \\\"3\\\"\""));
+        stats.set_capacity(0);
+        stats.record("wf-d", true);
+        assert!(stats.get("wf-d").is_none() && stats.to_json()["tracked"] == 0);
+    }
+}
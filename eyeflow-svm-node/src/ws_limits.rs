@@ -0,0 +1,149 @@
+//! WebSocket frame limits — bounds on what a remote end can make the node buffer
+//!
+//! tungstenite reassembles a whole message in memory before handing it over,
+//! so without limits a buggy or hostile central (or peer) can exhaust the
+//! node's memory with one frame.  Every WebSocket the node opens or accepts
+//! is configured with:
+//!
+//!   SVM_WS_MAX_MESSAGE_BYTES   reassembled message size (default 16 MiB)
+//!   SVM_WS_MAX_FRAME_BYTES     single frame size (default 4 MiB)
+//!
+//! and every text frame is checked before serde_json sees it:
+//!
+//!   SVM_WS_MAX_JSON_DEPTH      array/object nesting depth (default 32)
+//!
+//! Violations surface as a `LimitViolation`; the caller raises a
+//! WS_FRAME_LIMIT_EXCEEDED security alert.  An oversize message leaves the
+//! connection unusable and drops it; a too-deep frame is only discarded.
+
+use anyhow::Result;
+use serde_json::Value;
+use tokio_tungstenite::tungstenite::error::{CapacityError, Error as WsError};
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+
+use crate::config::Config;
+use crate::ir_limits::LimitViolation;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FrameLimits {
+    pub max_message_bytes: usize,
+    pub max_frame_bytes: usize,
+    pub max_json_depth: usize,
+}
+
+impl From<&Config> for FrameLimits {
+    fn from(c: &Config) -> Self {
+        Self {
+            max_message_bytes: c.ws_max_message_bytes,
+            max_frame_bytes: c.ws_max_frame_bytes,
+            max_json_depth: c.ws_max_json_depth,
+        }
+    }
+}
+
+impl FrameLimits {
+    /// tungstenite configuration enforcing the size limits.
+    pub fn ws_config(&self) -> WebSocketConfig {
+        WebSocketConfig {
+            max_message_size: Some(self.max_message_bytes),
+            max_frame_size: Some(self.max_frame_bytes),
+            ..Default::default()
+        }
+    }
+
+    /// Check a text frame's size and nesting depth, then parse it.
+    pub fn parse_text(&self, text: &str) -> Result<Value> {
+        self.check_text(text)?;
+        Ok(serde_json::from_str(text)?)
+    }
+
+    /// Check a text frame's size and nesting depth.
+    pub fn check_text(&self, text: &str) -> Result<()> {
+        if text.len() > self.max_message_bytes {
+            return Err(LimitViolation {
+                limit: "SVM_WS_MAX_MESSAGE_BYTES",
+                actual: text.len(),
+                max: self.max_message_bytes,
+            }.into());
+        }
+        let depth = json_depth(text);
+        if depth > self.max_json_depth {
+            return Err(LimitViolation {
+                limit: "SVM_WS_MAX_JSON_DEPTH",
+                actual: depth,
+                max: self.max_json_depth,
+            }.into());
+        }
+        Ok(())
+    }
+
+    /// The limit behind a tungstenite read error, if it was a size limit.
+    pub fn violation(&self, err: &WsError) -> Option<LimitViolation> {
+        match err {
+            WsError::Capacity(CapacityError::MessageTooLong { size, max_size }) => Some(LimitViolation {
+                limit: if *max_size == self.max_frame_bytes && *max_size != self.max_message_bytes {
+                    "SVM_WS_MAX_FRAME_BYTES"
+                } else {
+                    "SVM_WS_MAX_MESSAGE_BYTES"
+                },
+                actual: *size,
+                max: *max_size,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Deepest array/object nesting in `text`, without parsing it.
+fn json_depth(text: &str) -> usize {
+    let (mut depth, mut max) = (0usize, 0usize);
+    let (mut in_string, mut escaped) = (false, false);
+    for b in text.bytes() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                max = max.max(depth);
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    max
+}
+
+// ── Unit tests ────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_frame_limits() {
+        let limits = FrameLimits { max_message_bytes: 64, max_frame_bytes: 32, max_json_depth: 3 };
+        assert_eq!(json_depth(r#"{"a":[1,{"b":"[[[{{"}],"c":"\"]"}"#), 3);
+
+        let frame = limits.parse_text(r#"{"type":"PING","payload":{"x":[1]}}"#).unwrap();
+        assert_eq!(frame["type"], "PING");
+
+        let deep = limits.parse_text(r#"{"a":{"b":{"c":{}}}}"#).unwrap_err();
+        let v = deep.downcast_ref::<LimitViolation>().unwrap();
+        assert_eq!((v.limit, v.actual, v.max), ("SVM_WS_MAX_JSON_DEPTH", 4, 3));
+
+        let big = limits.parse_text(&format!(r#"{{"pad":"{}"}}"#, "x".repeat(64))).unwrap_err();
+        assert_eq!(big.downcast_ref::<LimitViolation>().unwrap().limit, "SVM_WS_MAX_MESSAGE_BYTES");
+
+        let err = WsError::Capacity(CapacityError::MessageTooLong { size: 40, max_size: 32 });
+        assert_eq!(limits.violation(&err).unwrap().limit, "SVM_WS_MAX_FRAME_BYTES");
+        assert!(limits.violation(&WsError::ConnectionClosed).is_none());
+    }
+}